@@ -10,6 +10,9 @@ pub struct Model {
     pub case_number: Option<String>,
     pub description: Option<String>,
     pub status: String,
+    pub language: String,     // Language of the case documents, e.g. "en", "de"
+    pub jurisdiction: String, // e.g. "us-ny", "de", "eu"; "unspecified" if unknown
+    pub legal_hold: bool,     // Blocks deletion of the case and pruning of its audit entries
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -7,6 +7,7 @@ pub struct Model {
     pub id: i32,
     pub case_id: i32,
     pub title: String,
+    pub summary: Option<String>,
     pub created_at: DateTime,
 }
 
@@ -21,6 +21,7 @@ pub struct Model {
     pub anonymization_applied: Option<String>, // "layer1-regex" | "layer2-ner" | "none"
     pub edit_count: i32,                   // Number of user edits
     pub metadata: Option<String>,          // JSON metadata
+    pub is_complete: bool,                 // false while generating or if cut short
 
     pub created_at: DateTime,
 }
@@ -17,6 +17,9 @@ pub struct Model {
     pub parameters: String,         // e.g., "7B", "13B"
     pub quantization: Option<String>, // e.g., "Q4_K_M", "Q8_0", null for full precision
     pub format: String,             // "gguf", "safetensors", etc.
+    pub architecture: Option<String>, // Read from the file, e.g. "llama"
+    pub context_length: Option<i64>,  // Read from the file, in tokens
+    pub quantization_bits: Option<i32>, // Read from the file, e.g. 4 for Q4_K_M
 
     // Download information
     pub status: String,             // "available", "downloading", "downloaded", "failed", "deleted"
@@ -46,6 +46,8 @@ pub struct Model {
     pub is_favorite: bool,
     pub license: Option<String>,
     pub tags: Option<String>,       // JSON array of tags
+    pub is_custom: bool,            // Added by the user, merged into the registry
+    pub label_entity_types: Option<String>, // JSON object: label name -> PII entity type
 
     // Usage tracking
     pub download_started_at: Option<DateTime>,
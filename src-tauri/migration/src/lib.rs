@@ -7,6 +7,13 @@ mod m20250106_000004_create_models;
 mod m20250106_000005_create_pii_operations;
 mod m20250106_000006_create_ner_models;
 mod m20250106_000007_add_ai_act_compliance_fields;
+mod m20250107_000008_add_model_metadata_fields;
+mod m20250108_000009_add_case_jurisdiction_fields;
+mod m20250109_000010_add_message_completion;
+mod m20250110_000011_add_case_legal_hold;
+mod m20250111_000012_add_conversation_summary;
+mod m20250112_000013_add_ner_model_custom_flag;
+mod m20250113_000014_add_ner_model_label_types;
 
 pub struct Migrator;
 
@@ -21,6 +28,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20250106_000005_create_pii_operations::Migration),
             Box::new(m20250106_000006_create_ner_models::Migration),
             Box::new(m20250106_000007_add_ai_act_compliance_fields::Migration),
+            Box::new(m20250107_000008_add_model_metadata_fields::Migration),
+            Box::new(m20250108_000009_add_case_jurisdiction_fields::Migration),
+            Box::new(m20250109_000010_add_message_completion::Migration),
+            Box::new(m20250110_000011_add_case_legal_hold::Migration),
+            Box::new(m20250111_000012_add_conversation_summary::Migration),
+            Box::new(m20250112_000013_add_ner_model_custom_flag::Migration),
+            Box::new(m20250113_000014_add_ner_model_label_types::Migration),
         ]
     }
 }
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Metadata read from the downloaded model file
+        // SQLite only supports one column per ALTER TABLE statement
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Models::Table)
+                    .add_column(ColumnDef::new(Models::Architecture).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Models::Table)
+                    .add_column(ColumnDef::new(Models::ContextLength).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Models::Table)
+                    .add_column(ColumnDef::new(Models::QuantizationBits).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Models::Table)
+                    .drop_column(Models::Architecture)
+                    .drop_column(Models::ContextLength)
+                    .drop_column(Models::QuantizationBits)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Models {
+    Table,
+    Architecture,
+    ContextLength,
+    QuantizationBits,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Structured fields for filtering cases across jurisdictions
+        // SQLite only supports one column per ALTER TABLE statement
+        // Existing cases get the defaults: English, jurisdiction not yet specified
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .add_column(
+                        ColumnDef::new(Cases::Language)
+                            .string()
+                            .not_null()
+                            .default("en"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .add_column(
+                        ColumnDef::new(Cases::Jurisdiction)
+                            .string()
+                            .not_null()
+                            .default("unspecified"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cases_jurisdiction_language")
+                    .table(Cases::Table)
+                    .col(Cases::Jurisdiction)
+                    .col(Cases::Language)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_cases_jurisdiction_language")
+                    .table(Cases::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // One column per statement here too
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .drop_column(Cases::Language)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .drop_column(Cases::Jurisdiction)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Cases {
+    Table,
+    Language,
+    Jurisdiction,
+}
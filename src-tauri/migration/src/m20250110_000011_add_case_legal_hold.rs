@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A case under legal hold can't be deleted and its audit entries
+        // aren't pruned. Existing cases start without a hold.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .add_column(
+                        ColumnDef::new(Cases::LegalHold)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cases::Table)
+                    .drop_column(Cases::LegalHold)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Cases {
+    Table,
+    LegalHold,
+}
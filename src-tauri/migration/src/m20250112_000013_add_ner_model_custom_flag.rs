@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Rows added by the user hold the whole model definition and are
+        // merged into the built-in registry; existing rows are not custom.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NerModels::Table)
+                    .add_column(
+                        ColumnDef::new(NerModels::IsCustom)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NerModels::Table)
+                    .drop_column(NerModels::IsCustom)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NerModels {
+    Table,
+    IsCustom,
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // JSON object from a custom model's label names to PII entity types;
+        // NULL keeps the default mapping
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NerModels::Table)
+                    .add_column(ColumnDef::new(NerModels::LabelEntityTypes).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NerModels::Table)
+                    .drop_column(NerModels::LabelEntityTypes)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NerModels {
+    Table,
+    LabelEntityTypes,
+}
@@ -0,0 +1,212 @@
+//! Chat templates of the supported model families
+//!
+//! Each family was fine-tuned on its own way of marking turns; a prompt in
+//! another family's format still runs but tends to produce odd output.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::ChatMessage;
+
+/// How chat messages are turned into a prompt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatTemplate {
+    /// `<|user|>` / `<|assistant|>` tags (Zephyr, TinyLlama chat)
+    #[default]
+    Zephyr,
+    /// `<|im_start|>role ... <|im_end|>` (Qwen, OpenHermes, ...)
+    ChatMl,
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`
+    Llama3,
+    /// `[INST] ... [/INST]` (Mistral, Llama 2 chat)
+    Mistral,
+    /// `<|user|> ... <|end|>`
+    Phi3,
+}
+
+impl ChatTemplate {
+    /// Template of a loaded model
+    ///
+    /// The GGUF `tokenizer.chat_template` is the most reliable hint, then
+    /// the architecture, then the model name; Zephyr-style tags otherwise.
+    pub fn detect(chat_template: Option<&str>, architecture: &str, model_id: &str) -> Self {
+        if let Some(template) = chat_template {
+            if template.contains("<|start_header_id|>") {
+                return Self::Llama3;
+            }
+            if template.contains("<|im_start|>") {
+                return Self::ChatMl;
+            }
+            if template.contains("[INST]") {
+                return Self::Mistral;
+            }
+            if template.contains("<|end|>") {
+                return Self::Phi3;
+            }
+            if template.contains("<|user|>") {
+                return Self::Zephyr;
+            }
+        }
+        if architecture == "phi3" {
+            return Self::Phi3;
+        }
+
+        let model_id = model_id.to_lowercase();
+        if model_id.contains("llama-3") || model_id.contains("llama3") {
+            Self::Llama3
+        } else if model_id.contains("qwen") || model_id.contains("hermes") {
+            Self::ChatMl
+        } else if model_id.contains("mistral") || model_id.contains("llama-2") {
+            Self::Mistral
+        } else {
+            Self::Zephyr
+        }
+    }
+
+    /// Messages followed by the prompt for the assistant's reply
+    pub fn render_prompt(&self, messages: &[ChatMessage], system_prompt: Option<&str>) -> String {
+        let mut prompt = self.render_history(messages, system_prompt);
+        prompt.push_str(self.generation_prompt());
+        prompt
+    }
+
+    /// Messages without the trailing generation prompt
+    ///
+    /// Every later prompt of the conversation starts with this text.
+    pub fn render_history(&self, messages: &[ChatMessage], system_prompt: Option<&str>) -> String {
+        let turns = system_prompt
+            .map(|system| ("system", system))
+            .into_iter()
+            .chain(messages.iter().map(|m| (m.role.as_str(), m.content.as_str())))
+            .filter(|(role, _)| matches!(*role, "system" | "user" | "assistant"));
+
+        let mut prompt = String::new();
+        match self {
+            Self::Zephyr => {
+                for (role, content) in turns {
+                    prompt.push_str(&format!("<|{}|>\n{}\n", role, content));
+                }
+            }
+            Self::ChatMl => {
+                for (role, content) in turns {
+                    prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", role, content));
+                }
+            }
+            Self::Llama3 => {
+                prompt.push_str("<|begin_of_text|>");
+                for (role, content) in turns {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        role, content
+                    ));
+                }
+            }
+            Self::Phi3 => {
+                for (role, content) in turns {
+                    prompt.push_str(&format!("<|{}|>\n{}<|end|>\n", role, content));
+                }
+            }
+            Self::Mistral => {
+                // No system role: system text leads the next user turn
+                prompt.push_str("<s>");
+                let mut pending_system: Vec<&str> = Vec::new();
+                for (role, content) in turns {
+                    match role {
+                        "system" => pending_system.push(content),
+                        "user" => {
+                            pending_system.push(content);
+                            let instruction = pending_system.join("\n\n");
+                            prompt.push_str(&format!("[INST] {} [/INST]", instruction));
+                            pending_system.clear();
+                        }
+                        _ => prompt.push_str(&format!("{}</s>", content)),
+                    }
+                }
+            }
+        }
+        prompt
+    }
+
+    /// Text that opens the assistant's turn
+    fn generation_prompt(&self) -> &'static str {
+        match self {
+            Self::Zephyr | Self::Phi3 => "<|assistant|>\n",
+            Self::ChatMl => "<|im_start|>assistant\n",
+            Self::Llama3 => "<|start_header_id|>assistant<|end_header_id|>\n\n",
+            Self::Mistral => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_templates_use_family_control_tokens() {
+        let messages = vec![
+            message("user", "Hello!"),
+            message("assistant", "Hi."),
+            message("user", "Sum up."),
+        ];
+        let render = |template: ChatTemplate| template.render_prompt(&messages, Some("Be brief."));
+
+        assert_eq!(
+            render(ChatTemplate::ChatMl),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHello!<|im_end|>\n\
+             <|im_start|>assistant\nHi.<|im_end|>\n<|im_start|>user\nSum up.<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+        assert_eq!(
+            render(ChatTemplate::Mistral),
+            "<s>[INST] Be brief.\n\nHello! [/INST]Hi.</s>[INST] Sum up. [/INST]"
+        );
+        let llama3 = render(ChatTemplate::Llama3);
+        assert!(llama3.starts_with(
+            "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>"
+        ));
+        assert!(llama3.ends_with("<|start_header_id|>assistant<|end_header_id|>\n\n"));
+        let phi3 = render(ChatTemplate::Phi3);
+        assert!(phi3.contains("<|user|>\nHello!<|end|>\n"));
+        assert!(phi3.ends_with("<|assistant|>\n"));
+        assert_eq!(
+            render(ChatTemplate::Zephyr),
+            "<|system|>\nBe brief.\n<|user|>\nHello!\n<|assistant|>\nHi.\n<|user|>\nSum up.\n<|assistant|>\n"
+        );
+
+        // The history of a conversation prefixes its next prompt in every family
+        for template in [
+            ChatTemplate::Zephyr,
+            ChatTemplate::ChatMl,
+            ChatTemplate::Llama3,
+            ChatTemplate::Mistral,
+            ChatTemplate::Phi3,
+        ] {
+            let history = template.render_history(&messages[..2], Some("Be brief."));
+            assert!(render(template).starts_with(&history), "{:?}", template);
+        }
+    }
+
+    #[test]
+    fn test_detect_template() {
+        let qwen = "{% for message in messages %}<|im_start|>{{ message.role }}";
+        assert_eq!(ChatTemplate::detect(Some(qwen), "qwen2", "any"), ChatTemplate::ChatMl);
+        assert_eq!(ChatTemplate::detect(None, "phi3", "microsoft/Phi-3-mini"), ChatTemplate::Phi3);
+        assert_eq!(
+            ChatTemplate::detect(None, "llama", "TheBloke/Mistral-7B-Instruct-v0.2-GGUF"),
+            ChatTemplate::Mistral
+        );
+        assert_eq!(
+            ChatTemplate::detect(None, "llama", "meta-llama/Meta-Llama-3-8B-Instruct"),
+            ChatTemplate::Llama3
+        );
+        assert_eq!(ChatTemplate::detect(None, "llama", "TinyLlama-1.1B-Chat"), ChatTemplate::Zephyr);
+    }
+}
@@ -0,0 +1,281 @@
+//! Tokenizer reconstruction from GGUF metadata
+//!
+//! Most GGUF files embed their vocabulary under the `tokenizer.ggml.*` keys,
+//! so a separate `tokenizer.json` is not required to run them. The tokenizer
+//! is rebuilt as a `tokenizer.json` document and loaded through the regular
+//! `tokenizers` deserializer, which keeps this independent of the builder API.
+
+use anyhow::{Context, Result};
+use candle_core::quantized::gguf_file::Value;
+use serde_json::json;
+use std::collections::HashMap;
+use tokenizers::Tokenizer;
+
+/// GGUF token type marking control tokens such as `<s>` and `</s>`
+const TOKEN_TYPE_CONTROL: i32 = 3;
+
+/// Build a tokenizer from the vocabulary embedded in GGUF metadata
+///
+/// Returns `Ok(None)` when the file carries no vocabulary, and an error when
+/// a vocabulary is present but uses an unsupported tokenizer model.
+pub fn tokenizer_from_gguf(metadata: &HashMap<String, Value>) -> Result<Option<Tokenizer>> {
+    let tokens = match metadata.get("tokenizer.ggml.tokens") {
+        Some(value) => string_array(value).context("Invalid tokenizer.ggml.tokens")?,
+        None => return Ok(None),
+    };
+
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let model = metadata
+        .get("tokenizer.ggml.model")
+        .and_then(|v| v.to_string().ok())
+        .map(|s| s.as_str())
+        .unwrap_or("llama");
+
+    let document = match model {
+        "llama" => sentencepiece_document(metadata, &tokens)?,
+        "gpt2" => byte_level_bpe_document(metadata, &tokens)?,
+        other => anyhow::bail!("Unsupported embedded tokenizer model: {}", other),
+    };
+
+    let tokenizer = Tokenizer::from_bytes(serde_json::to_vec(&document)?)
+        .map_err(|e| anyhow::anyhow!("Failed to build tokenizer from GGUF vocabulary: {}", e))?;
+
+    Ok(Some(tokenizer))
+}
+
+/// SentencePiece-style (Llama, Mistral) tokenizer as a Unigram model over the scored vocabulary
+fn sentencepiece_document(
+    metadata: &HashMap<String, Value>,
+    tokens: &[String],
+) -> Result<serde_json::Value> {
+    let scores = match metadata.get("tokenizer.ggml.scores") {
+        Some(value) => value
+            .to_vec()?
+            .iter()
+            .map(|v| v.to_f32().map(f64::from))
+            .collect::<candle_core::Result<Vec<f64>>>()?,
+        None => vec![0.0; tokens.len()],
+    };
+
+    let unk_id = metadata
+        .get("tokenizer.ggml.unknown_token_id")
+        .and_then(|v| v.to_u32().ok())
+        .map(|id| id as usize)
+        .or_else(|| tokens.iter().position(|t| t == "<unk>"))
+        .unwrap_or(0);
+
+    let vocab: Vec<serde_json::Value> = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| json!([token, scores.get(i).copied().unwrap_or(0.0)]))
+        .collect();
+
+    let byte_fallback = tokens.iter().any(|t| t == "<0x00>");
+
+    Ok(json!({
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": added_tokens(metadata, tokens)?,
+        "normalizer": {
+            "type": "Sequence",
+            "normalizers": [
+                { "type": "Prepend", "prepend": "\u{2581}" },
+                { "type": "Replace", "pattern": { "String": " " }, "content": "\u{2581}" }
+            ]
+        },
+        "pre_tokenizer": null,
+        "post_processor": null,
+        "decoder": {
+            "type": "Sequence",
+            "decoders": [
+                { "type": "Replace", "pattern": { "String": "\u{2581}" }, "content": " " },
+                { "type": "ByteFallback" },
+                { "type": "Fuse" },
+                { "type": "Strip", "content": " ", "start": 1, "stop": 0 }
+            ]
+        },
+        "model": {
+            "type": "Unigram",
+            "unk_id": unk_id,
+            "vocab": vocab,
+            "byte_fallback": byte_fallback
+        }
+    }))
+}
+
+/// GPT-2 style (Qwen, Phi, ...) byte-level BPE tokenizer
+fn byte_level_bpe_document(
+    metadata: &HashMap<String, Value>,
+    tokens: &[String],
+) -> Result<serde_json::Value> {
+    let merges = match metadata.get("tokenizer.ggml.merges") {
+        Some(value) => string_array(value).context("Invalid tokenizer.ggml.merges")?,
+        None => Vec::new(),
+    };
+
+    let vocab: serde_json::Map<String, serde_json::Value> = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| (token.clone(), json!(i)))
+        .collect();
+
+    let byte_level = json!({
+        "type": "ByteLevel",
+        "add_prefix_space": false,
+        "trim_offsets": true,
+        "use_regex": true
+    });
+
+    Ok(json!({
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": added_tokens(metadata, tokens)?,
+        "normalizer": null,
+        "pre_tokenizer": byte_level,
+        "post_processor": null,
+        "decoder": byte_level,
+        "model": {
+            "type": "BPE",
+            "dropout": null,
+            "unk_token": null,
+            "continuing_subword_prefix": null,
+            "end_of_word_suffix": null,
+            "fuse_unk": false,
+            "byte_fallback": false,
+            "vocab": vocab,
+            "merges": merges
+        }
+    }))
+}
+
+/// Control tokens are registered as special added tokens so they are never split
+fn added_tokens(
+    metadata: &HashMap<String, Value>,
+    tokens: &[String],
+) -> Result<Vec<serde_json::Value>> {
+    let token_types = match metadata.get("tokenizer.ggml.token_type") {
+        Some(value) => value
+            .to_vec()?
+            .iter()
+            .map(|v| v.to_i32())
+            .collect::<candle_core::Result<Vec<i32>>>()?,
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(token_types
+        .iter()
+        .enumerate()
+        .filter(|(_, &token_type)| token_type == TOKEN_TYPE_CONTROL)
+        .filter_map(|(id, _)| tokens.get(id).map(|content| (id, content)))
+        .map(|(id, content)| {
+            json!({
+                "id": id,
+                "content": content,
+                "single_word": false,
+                "lstrip": false,
+                "rstrip": false,
+                "normalized": false,
+                "special": true
+            })
+        })
+        .collect())
+}
+
+fn string_array(value: &Value) -> Result<Vec<String>> {
+    value
+        .to_vec()?
+        .iter()
+        .map(|v| v.to_string().cloned().map_err(anyhow::Error::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Value {
+        Value::Array(values.iter().map(|s| Value::String(s.to_string())).collect())
+    }
+
+    fn llama_metadata() -> HashMap<String, Value> {
+        let tokens = ["<unk>", "<s>", "</s>", "\u{2581}hello", "\u{2581}world", "\u{2581}"];
+        let mut metadata = HashMap::new();
+        metadata.insert("tokenizer.ggml.model".to_string(), Value::String("llama".to_string()));
+        metadata.insert("tokenizer.ggml.tokens".to_string(), strings(&tokens));
+        metadata.insert(
+            "tokenizer.ggml.scores".to_string(),
+            Value::Array(vec![
+                Value::F32(0.0),
+                Value::F32(0.0),
+                Value::F32(0.0),
+                Value::F32(-1.0),
+                Value::F32(-1.0),
+                Value::F32(-5.0),
+            ]),
+        );
+        metadata.insert(
+            "tokenizer.ggml.token_type".to_string(),
+            Value::Array(vec![
+                Value::I32(2),
+                Value::I32(3),
+                Value::I32(3),
+                Value::I32(1),
+                Value::I32(1),
+                Value::I32(1),
+            ]),
+        );
+        metadata
+    }
+
+    #[test]
+    fn test_no_vocabulary_returns_none() {
+        let metadata = HashMap::new();
+        assert!(tokenizer_from_gguf(&metadata).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sentencepiece_vocabulary() {
+        let tokenizer = tokenizer_from_gguf(&llama_metadata()).unwrap().unwrap();
+
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[3, 4]);
+
+        let decoded = tokenizer.decode(&[3, 4], true).unwrap();
+        assert_eq!(decoded, "hello world");
+    }
+
+    #[test]
+    fn test_control_tokens_are_special() {
+        let tokenizer = tokenizer_from_gguf(&llama_metadata()).unwrap().unwrap();
+
+        let encoding = tokenizer.encode("<s>hello", false).unwrap();
+        assert_eq!(encoding.get_ids()[0], 1);
+    }
+
+    #[test]
+    fn test_byte_level_bpe_vocabulary() {
+        let mut metadata = HashMap::new();
+        metadata.insert("tokenizer.ggml.model".to_string(), Value::String("gpt2".to_string()));
+        metadata.insert("tokenizer.ggml.tokens".to_string(), strings(&["h", "i", "hi"]));
+        metadata.insert("tokenizer.ggml.merges".to_string(), strings(&["h i"]));
+
+        let tokenizer = tokenizer_from_gguf(&metadata).unwrap().unwrap();
+        let encoding = tokenizer.encode("hi", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[2]);
+    }
+
+    #[test]
+    fn test_unsupported_tokenizer_model() {
+        let mut metadata = HashMap::new();
+        metadata.insert("tokenizer.ggml.model".to_string(), Value::String("rwkv".to_string()));
+        metadata.insert("tokenizer.ggml.tokens".to_string(), strings(&["a"]));
+
+        let err = tokenizer_from_gguf(&metadata).unwrap_err();
+        assert!(err.to_string().contains("rwkv"));
+    }
+}
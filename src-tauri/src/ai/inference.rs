@@ -1,24 +1,195 @@
 use anyhow::{Context, Result};
-use candle_core::{Device, Tensor};
+use candle_core::{DType, Device, Tensor};
 use candle_core::quantized::gguf_file;
+use candle_transformers::generation::LogitsProcessor;
 use candle_transformers::models::quantized_llama as gguf_llama;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Instant;
+use candle_transformers::models::quantized_phi as gguf_phi2;
+use candle_transformers::models::quantized_phi3 as gguf_phi3;
+use candle_transformers::models::quantized_qwen2 as gguf_qwen2;
+use candle_transformers::utils::apply_repeat_penalty;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as SyncMutex, RwLock as SyncRwLock};
+use std::time::{Duration, Instant};
 use tokenizers::Tokenizer;
 use tokio::sync::RwLock;
 
+use super::chat_template::ChatTemplate;
+use super::gguf_tokenizer::tokenizer_from_gguf;
+use super::kv_cache::{CacheHit, SessionCache};
 use super::types::{
-    ChatMessage, GenerateRequest, GenerationResult, ModelConfig, ModelFormat, ModelStatus,
-    TokenResponse,
+    BenchmarkResult, ChatMessage, FinishReason, GenerateRequest, GenerationConfig,
+    GenerationResult, ModelConfig, ModelFormat, ModelStatus, TokenResponse, TokenizeProgress,
 };
 
-/// Loaded model variants (safetensors or GGUF)
+/// Seed used for sampling when the request does not specify one
+const DEFAULT_SEED: u64 = 299792458;
+
+/// Tokens generated by `warm_up`
+const WARM_UP_TOKENS: usize = 2;
+
+/// Text repeated to build a benchmark prompt of the requested length
+const BENCHMARK_FILLER: &str = "The parties agree that this contract is governed by the laws of the state. ";
+
+/// Prompts longer than this, in bytes, are tokenized in chunks with progress
+const TOKENIZE_PROGRESS_THRESHOLD: usize = 32 * 1024;
+
+/// Size of each tokenized chunk of a large prompt, in bytes; a chunk is
+/// extended to the next line break, or failing that the next whitespace, so
+/// words and special tokens are not split
+const TOKENIZE_CHUNK_BYTES: usize = 8 * 1024;
+
+/// End-of-sequence tokens used by the supported chat model families
+const EOS_TOKENS: &[&str] = &["</s>", "<|endoftext|>", "<|im_end|>", "<|eot_id|>", "<|end|>"];
+
+/// Model architectures that can be loaded from a GGUF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GgufArchitecture {
+    /// Llama and derivatives stored as "llama" (Mistral, TinyLlama, ...)
+    Llama,
+    Phi2,
+    Phi3,
+    Qwen2,
+}
+
+impl GgufArchitecture {
+    /// Architectures accepted in `general.architecture`
+    const SUPPORTED: &'static [&'static str] = &["llama", "phi2", "phi3", "qwen2"];
+
+    /// Read the architecture from GGUF metadata
+    fn from_metadata(metadata: &HashMap<String, gguf_file::Value>) -> Result<Self> {
+        let architecture = metadata
+            .get("general.architecture")
+            .ok_or_else(|| anyhow::anyhow!("GGUF file has no general.architecture metadata"))?
+            .to_string()
+            .context("general.architecture is not a string")?;
+
+        match architecture.as_str() {
+            "llama" => Ok(Self::Llama),
+            "phi2" => Ok(Self::Phi2),
+            "phi3" => Ok(Self::Phi3),
+            "qwen2" => Ok(Self::Qwen2),
+            other => anyhow::bail!(
+                "Unsupported model architecture '{}'. Supported GGUF architectures: {}",
+                other,
+                Self::SUPPORTED.join(", ")
+            ),
+        }
+    }
+
+    /// Name as written in `general.architecture`
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Llama => "llama",
+            Self::Phi2 => "phi2",
+            Self::Phi3 => "phi3",
+            Self::Qwen2 => "qwen2",
+        }
+    }
+
+    /// Context window declared in GGUF metadata, e.g. `llama.context_length`
+    fn context_length(&self, metadata: &HashMap<String, gguf_file::Value>) -> Option<usize> {
+        let value = metadata.get(&format!("{}.context_length", self.name()))?;
+        value
+            .to_u32()
+            .map(u64::from)
+            .or_else(|_| value.to_u64())
+            .ok()
+            .and_then(|length| usize::try_from(length).ok())
+    }
+}
+
+/// Loaded model variants, one per supported GGUF architecture
+///
+/// Cloning is cheap: weights are shared, only the KV cache is per clone.
+#[derive(Clone)]
 enum LoadedModel {
-    GGUF(gguf_llama::ModelWeights),
+    Llama(gguf_llama::ModelWeights),
+    Phi2(gguf_phi2::ModelWeights),
+    Phi3(gguf_phi3::ModelWeights),
+    /// Not cloneable upstream, so clones share one KV cache and conversations
+    /// aren't cached; a prefill from position 0 resets the cache
+    Qwen2(Arc<SyncMutex<gguf_qwen2::ModelWeights>>),
     // SafeTensors variant would go here when implemented
 }
 
+impl LoadedModel {
+    /// Build the model struct matching the file's architecture
+    fn from_gguf(
+        architecture: GgufArchitecture,
+        content: gguf_file::Content,
+        file: &mut std::fs::File,
+        device: &Device,
+    ) -> Result<Self> {
+        let model = match architecture {
+            GgufArchitecture::Llama => {
+                LoadedModel::Llama(gguf_llama::ModelWeights::from_gguf(content, file, device)?)
+            }
+            GgufArchitecture::Phi2 => {
+                LoadedModel::Phi2(gguf_phi2::ModelWeights::from_gguf(content, file, device)?)
+            }
+            GgufArchitecture::Phi3 => LoadedModel::Phi3(gguf_phi3::ModelWeights::from_gguf(
+                false, content, file, device,
+            )?),
+            GgufArchitecture::Qwen2 => LoadedModel::Qwen2(Arc::new(SyncMutex::new(
+                gguf_qwen2::ModelWeights::from_gguf(content, file, device)?,
+            ))),
+        };
+        Ok(model)
+    }
+
+    fn forward(&mut self, input: &[u32], index_pos: usize, device: &Device) -> Result<Tensor> {
+        let input = Tensor::new(input, device)?.unsqueeze(0)?;
+        let logits = match self {
+            LoadedModel::Llama(weights) => weights.forward(&input, index_pos)?,
+            LoadedModel::Phi2(weights) => weights.forward(&input, index_pos)?,
+            LoadedModel::Phi3(weights) => weights.forward(&input, index_pos)?,
+            LoadedModel::Qwen2(weights) => weights
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .forward(&input, index_pos)?,
+        };
+        Ok(logits.squeeze(0)?.to_dtype(DType::F32)?)
+    }
+
+    /// Whether a clone keeps its own KV cache, so it can be stored as a
+    /// conversation's session
+    fn has_own_cache(&self) -> bool {
+        !matches!(self, LoadedModel::Qwen2(_))
+    }
+}
+
+/// Status and device of an engine, readable without the engine's lock
+///
+/// Commands hold the engine lock for a whole generation; status checks use
+/// this instead so they don't wait behind one.
+#[derive(Clone)]
+pub struct EngineStatusHandle {
+    status: Arc<RwLock<ModelStatus>>,
+    device: Arc<RwLock<Device>>,
+}
+
+impl EngineStatusHandle {
+    /// Get current model status
+    pub async fn get_status(&self) -> ModelStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Get current device info
+    pub async fn get_device_info(&self) -> String {
+        device_name(&*self.device.read().await).to_string()
+    }
+}
+
+/// Display name of an inference device
+fn device_name(device: &Device) -> &'static str {
+    match device {
+        Device::Cpu => "CPU",
+        Device::Cuda(_) => "CUDA (NVIDIA GPU)",
+        Device::Metal(_) => "Metal (Apple GPU)",
+    }
+}
+
 /// AI inference engine with GPU support
 pub struct InferenceEngine {
     model_path: Arc<RwLock<Option<PathBuf>>>,
@@ -27,6 +198,12 @@ pub struct InferenceEngine {
     device: Arc<RwLock<Device>>,
     model: Arc<RwLock<Option<LoadedModel>>>,
     tokenizer: Arc<RwLock<Option<Tokenizer>>>,
+    /// Per-model generation defaults, replaced when a model becomes active
+    generation_defaults: Arc<RwLock<GenerationConfig>>,
+    /// Model state after each conversation's last prompt, for incremental turns
+    sessions: Arc<RwLock<SessionCache<LoadedModel>>>,
+    /// Prompt format of the loaded model's family
+    chat_template: Arc<SyncRwLock<ChatTemplate>>,
 }
 
 impl InferenceEngine {
@@ -42,6 +219,9 @@ impl InferenceEngine {
             device: Arc::new(RwLock::new(device)),
             model: Arc::new(RwLock::new(None)),
             tokenizer: Arc::new(RwLock::new(None)),
+            generation_defaults: Arc::new(RwLock::new(GenerationConfig::default())),
+            sessions: Arc::new(RwLock::new(SessionCache::new())),
+            chat_template: Arc::new(SyncRwLock::new(ChatTemplate::default())),
         }
     }
 
@@ -88,7 +268,10 @@ impl InferenceEngine {
     }
 
     /// Load model from path (supports both SafeTensors and GGUF)
-    pub async fn load_model(&self, model_path: PathBuf, config: ModelConfig) -> Result<()> {
+    ///
+    /// A GGUF model's declared context length replaces
+    /// `config.max_position_embeddings`.
+    pub async fn load_model(&self, model_path: PathBuf, mut config: ModelConfig) -> Result<()> {
         let mut status = self.status.write().await;
         *status = ModelStatus::Loading;
         drop(status);
@@ -103,10 +286,24 @@ impl InferenceEngine {
         log::info!("Loading model from: {:?}", model_path);
         log::info!("Model format: {:?}", config.format);
 
+        // Cached sessions belong to the previous model
+        self.sessions.write().await.clear();
+
         // Load based on format
         match config.format {
             ModelFormat::GGUF => {
-                self.load_gguf_model(model_path.clone(), &config).await?;
+                match self.load_gguf_model(model_path.clone(), &config).await {
+                    Ok(context_length) => {
+                        if let Some(context_length) = context_length {
+                            config.max_position_embeddings = context_length;
+                        }
+                    }
+                    Err(e) => {
+                        let mut status = self.status.write().await;
+                        *status = ModelStatus::Error(e.to_string());
+                        return Err(e);
+                    }
+                }
             }
             ModelFormat::SafeTensors => {
                 // TODO: Implement SafeTensors loading
@@ -133,43 +330,18 @@ impl InferenceEngine {
         Ok(())
     }
 
-    /// Load GGUF quantized model
-    async fn load_gguf_model(&self, model_path: PathBuf, config: &ModelConfig) -> Result<()> {
+    /// Load GGUF quantized model, returning its declared context length
+    async fn load_gguf_model(
+        &self,
+        model_path: PathBuf,
+        config: &ModelConfig,
+    ) -> Result<Option<usize>> {
         log::info!("Loading GGUF model...");
 
-        // Find the .gguf file in the model directory
-        let gguf_file = if model_path.is_file() && model_path.extension().and_then(|s| s.to_str()) == Some("gguf") {
-            model_path.clone()
-        } else {
-            // Look for .gguf file in directory
-            std::fs::read_dir(&model_path)?
-                .filter_map(|e| e.ok())
-                .find(|e| {
-                    e.path().extension().and_then(|s| s.to_str()) == Some("gguf")
-                })
-                .map(|e| e.path())
-                .ok_or_else(|| anyhow::anyhow!("No GGUF file found in model directory"))?
-        };
+        let gguf_file = Self::find_gguf_file(&model_path)?;
 
         log::info!("Loading GGUF file: {:?}", gguf_file);
 
-        // Load tokenizer
-        let tokenizer_path = if model_path.is_file() {
-            model_path.parent().unwrap().join("tokenizer.json")
-        } else {
-            model_path.join("tokenizer.json")
-        };
-
-        if tokenizer_path.exists() {
-            let tokenizer = Tokenizer::from_file(&tokenizer_path)
-                .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?;
-            let mut tok_lock = self.tokenizer.write().await;
-            *tok_lock = Some(tokenizer);
-            log::info!("✓ Tokenizer loaded");
-        } else {
-            log::warn!("Tokenizer not found at: {:?}", tokenizer_path);
-        }
-
         // Load GGUF model with Candle
         let device = self.device.read().await;
         let mut file = std::fs::File::open(&gguf_file)
@@ -179,18 +351,103 @@ impl InferenceEngine {
         let content = gguf_file::Content::read(&mut file)
             .context("Failed to read GGUF file content")?;
 
+        let architecture = GgufArchitecture::from_metadata(&content.metadata)?;
+        log::info!("Model architecture: {:?}", architecture);
+        let context_length = architecture.context_length(&content.metadata);
+
+        // Resolve the tokenizer before loading weights so a model that cannot
+        // be tokenized fails fast instead of at the first generation request
+        let tokenizer = Self::resolve_tokenizer(&model_path, &content)?;
+
+        let chat_template = ChatTemplate::detect(
+            content
+                .metadata
+                .get("tokenizer.chat_template")
+                .and_then(|value| value.to_string().ok())
+                .map(String::as_str),
+            architecture.name(),
+            &config.model_id,
+        );
+        log::info!("Chat template: {:?}", chat_template);
+
         // Load model weights from GGUF
-        let model_weights = gguf_llama::ModelWeights::from_gguf(content, &mut file, &device)
+        let model = LoadedModel::from_gguf(architecture, content, &mut file, &device)
             .context("Failed to load GGUF model weights")?;
 
         // Store loaded model
         let mut model_lock = self.model.write().await;
-        *model_lock = Some(LoadedModel::GGUF(model_weights));
+        *model_lock = Some(model);
+
+        let mut tok_lock = self.tokenizer.write().await;
+        *tok_lock = Some(tokenizer);
+        self.set_chat_template(chat_template);
 
         log::info!("✓ GGUF model loaded into memory");
         log::info!("Quantization: {}", config.quantization.as_ref().unwrap_or(&"unknown".to_string()));
 
-        Ok(())
+        Ok(context_length)
+    }
+
+    /// The model file itself, or the first .gguf file in a model directory
+    fn find_gguf_file(model_path: &Path) -> Result<PathBuf> {
+        if model_path.is_file() && model_path.extension().and_then(|s| s.to_str()) == Some("gguf") {
+            return Ok(model_path.to_path_buf());
+        }
+
+        std::fs::read_dir(model_path)?
+            .filter_map(|e| e.ok())
+            .find(|e| {
+                e.path().extension().and_then(|s| s.to_str()) == Some("gguf")
+            })
+            .map(|e| e.path())
+            .ok_or_else(|| anyhow::anyhow!("No GGUF file found in model directory"))
+    }
+
+    /// Check that a GGUF model can be loaded, without reading its weights
+    ///
+    /// Confirms the header parses, the architecture is one the engine can
+    /// run and a tokenizer is available, i.e. everything `load_model` checks
+    /// before loading weights. Returns the architecture name.
+    pub fn check_gguf_compatibility(model_path: &Path) -> Result<&'static str> {
+        let gguf_file = Self::find_gguf_file(model_path)?;
+        let mut file = std::fs::File::open(&gguf_file)
+            .context(format!("Failed to open GGUF file: {:?}", gguf_file))?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| anyhow::anyhow!("Not a valid GGUF file: {}", e))?;
+
+        let architecture = GgufArchitecture::from_metadata(&content.metadata)?;
+        Self::resolve_tokenizer(model_path, &content)?;
+        Ok(architecture.name())
+    }
+
+    /// Find the tokenizer for a GGUF model
+    ///
+    /// Prefers a `tokenizer.json` next to the model and falls back to the
+    /// vocabulary embedded in the GGUF metadata.
+    fn resolve_tokenizer(model_path: &Path, content: &gguf_file::Content) -> Result<Tokenizer> {
+        let tokenizer_path = if model_path.is_file() {
+            model_path.parent().unwrap_or(model_path).join("tokenizer.json")
+        } else {
+            model_path.join("tokenizer.json")
+        };
+
+        if tokenizer_path.exists() {
+            let tokenizer = Tokenizer::from_file(&tokenizer_path)
+                .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?;
+            log::info!("✓ Tokenizer loaded from {:?}", tokenizer_path);
+            return Ok(tokenizer);
+        }
+
+        match tokenizer_from_gguf(&content.metadata)? {
+            Some(tokenizer) => {
+                log::info!("✓ Tokenizer loaded from embedded GGUF vocabulary");
+                Ok(tokenizer)
+            }
+            None => anyhow::bail!(
+                "No tokenizer found: {:?} does not exist and the GGUF file has no embedded vocabulary",
+                tokenizer_path
+            ),
+        }
     }
 
     /// Unload current model
@@ -212,6 +469,9 @@ impl InferenceEngine {
         let mut config_lock = self.model_config.write().await;
         *config_lock = None;
 
+        self.sessions.write().await.clear();
+        self.set_chat_template(ChatTemplate::default());
+
         log::info!("✓ Model unloaded");
     }
 
@@ -227,102 +487,250 @@ impl InferenceEngine {
         status.clone()
     }
 
-    /// Get current device info
-    pub async fn get_device_info(&self) -> String {
-        let device = self.device.read().await;
-        match &*device {
-            Device::Cpu => "CPU".to_string(),
-            Device::Cuda(_) => "CUDA (NVIDIA GPU)".to_string(),
-            Device::Metal(_) => "Metal (Apple GPU)".to_string(),
+    /// Context window of the loaded model, in tokens
+    pub async fn context_length(&self) -> Option<usize> {
+        self.model_config
+            .read()
+            .await
+            .as_ref()
+            .map(|config| config.max_position_embeddings)
+    }
+
+    /// Handle for reading status and device without locking the engine
+    pub fn status_handle(&self) -> EngineStatusHandle {
+        EngineStatusHandle {
+            status: self.status.clone(),
+            device: self.device.clone(),
         }
     }
 
-    /// Generate text completion
-    pub async fn generate(&self, request: GenerateRequest) -> Result<GenerationResult> {
-        // Check if model is loaded
+    /// Id of the loaded model, from the config it was loaded with
+    pub async fn loaded_model_id(&self) -> Option<String> {
+        self.model_config
+            .read()
+            .await
+            .as_ref()
+            .map(|config| config.model_id.clone())
+    }
+
+    /// Generation parameters used when a request doesn't override them
+    pub async fn get_generation_defaults(&self) -> GenerationConfig {
+        self.generation_defaults.read().await.clone()
+    }
+
+    /// Replace the generation defaults (e.g. with the active model's saved config)
+    pub async fn set_generation_defaults(&self, config: GenerationConfig) {
+        *self.generation_defaults.write().await = config;
+    }
+
+    /// Drop the cached KV state of a conversation (e.g. when it is deleted)
+    pub async fn drop_session(&self, conversation_id: i32) {
+        self.sessions.write().await.remove(conversation_id);
+    }
+
+    /// Rebuild a conversation's cached state from its stored history
+    ///
+    /// Called when a conversation is resumed after a restart, so its next turn
+    /// only processes the new message. Returns the number of primed tokens.
+    pub async fn prime_session(
+        &self,
+        conversation_id: i32,
+        messages: &[ChatMessage],
+        system_prompt: Option<&str>,
+    ) -> Result<usize> {
         if !self.is_loaded().await {
             anyhow::bail!("No model loaded");
         }
 
-        let start_time = Instant::now();
-
-        // Get tokenizer
+        let history = self.format_history(messages, system_prompt);
         let tokenizer_lock = self.tokenizer.read().await;
         let tokenizer = tokenizer_lock.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Tokenizer not loaded"))?;
+        let tokens = tokenizer.encode(history, false)
+            .map_err(|e| anyhow::anyhow!("Failed to tokenize history: {}", e))?
+            .get_ids()
+            .to_vec();
+
+        self.bind_sessions_to_loaded_model().await;
+        if tokens.is_empty() {
+            self.drop_session(conversation_id).await;
+            return Ok(0);
+        }
 
-        // Format messages into prompt
-        let prompt = self.format_prompt(&request.messages, request.system_prompt.as_deref());
+        let device = self.device.read().await.clone();
+        let mut model = {
+            let model_lock = self.model.read().await;
+            model_lock.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?
+                .clone()
+        };
+        if !model.has_own_cache() {
+            return Ok(0);
+        }
 
-        // Tokenize prompt
-        let encoding = tokenizer.encode(prompt.clone(), false)
-            .map_err(|e| anyhow::anyhow!("Failed to tokenize prompt: {}", e))?;
-        let prompt_tokens = encoding.get_ids();
-        let prompt_token_count = prompt_tokens.len();
+        prefill(&tokens, 0, |input, index_pos| model.forward(input, index_pos, &device))?;
 
-        log::info!("Generating response for {} token prompt", prompt_token_count);
+        log::info!("Primed conversation {} with {} history tokens", conversation_id, tokens.len());
+        let primed = tokens.len();
+        self.sessions.write().await.store(conversation_id, system_prompt, tokens, model);
+        Ok(primed)
+    }
 
-        // Check if model is GGUF
-        let model_lock = self.model.read().await;
-        let model = model_lock.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?;
-
-        match model {
-            LoadedModel::GGUF(_gguf_model) => {
-                // Actual GGUF inference
-                let device = self.device.read().await;
-
-                // Convert tokens to tensor
-                let _tokens = Tensor::new(prompt_tokens, &*device)?;
-
-                // Generate with the model
-                // Note: This is a simplified version - full implementation would include:
-                // - Proper sampling with temperature, top_p, top_k
-                // - Repetition penalty
-                // - Stop tokens
-                // - KV cache for efficiency
-
-                drop(device);
-                drop(model_lock);
-
-                // For now, return a placeholder that shows the infrastructure works
-                let generated_text = format!(
-                    "GGUF Model Response: Your prompt was successfully tokenized ({} tokens). \
-                    Full inference implementation requires additional sampling logic. \
-                    Model is loaded and ready on {}.",
-                    prompt_token_count,
-                    self.get_device_info().await
-                );
+    /// Get current device info
+    pub async fn get_device_info(&self) -> String {
+        device_name(&*self.device.read().await).to_string()
+    }
 
-                let generation_time = start_time.elapsed().as_millis() as u64;
-                let generated_tokens = generated_text.split_whitespace().count();
-                let total_tokens = prompt_token_count + generated_tokens;
+    /// Run a tiny generation so the first real request doesn't pay for lazy
+    /// kernel compilation and buffer allocation
+    ///
+    /// Call after `load_model`. Returns how long the warm-up took, in ms.
+    pub async fn warm_up(&self) -> Result<u64> {
+        let started = Instant::now();
+        let request = GenerateRequest {
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            config: GenerationConfig {
+                max_new_tokens: WARM_UP_TOKENS,
+                do_sample: false,
+                ..GenerationConfig::default()
+            },
+            system_prompt: None,
+            conversation_id: None,
+        };
 
-                let tokens_per_second = if generation_time > 0 {
-                    (generated_tokens as f64 / generation_time as f64) * 1000.0
-                } else {
-                    0.0
-                };
+        self.run_generation(&request, None, &mut |_| {}, |_| {})
+            .await
+            .context("Warm-up generation failed")?;
 
-                Ok(GenerationResult {
-                    text: generated_text,
-                    tokens: prompt_tokens.to_vec(),
-                    total_tokens,
-                    prompt_tokens: prompt_token_count,
-                    generated_tokens,
-                    generation_time_ms: generation_time,
-                    tokens_per_second,
-                })
-            }
+        let elapsed = started.elapsed().as_millis() as u64;
+        log::info!("Model warmed up in {} ms", elapsed);
+        Ok(elapsed)
+    }
+
+    /// Time prompt processing and generation of the given sizes
+    ///
+    /// The prompt is filler text cut to `prompt_tokens` tokens; generation is
+    /// greedy and runs the full `gen_tokens` whatever the model samples, so
+    /// runs are comparable. No conversation cache is used or written.
+    pub async fn benchmark(
+        &self,
+        prompt_tokens: usize,
+        gen_tokens: usize,
+    ) -> Result<BenchmarkResult> {
+        if !self.is_loaded().await {
+            anyhow::bail!("No model loaded");
+        }
+        check_benchmark_size(prompt_tokens, gen_tokens, self.context_length().await)?;
+
+        let prompt = {
+            let tokenizer_lock = self.tokenizer.read().await;
+            let tokenizer = tokenizer_lock.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Tokenizer not loaded"))?;
+            benchmark_prompt(tokenizer, prompt_tokens)?
+        };
+        let device = self.device.read().await.clone();
+        let mut model = {
+            let model_lock = self.model.read().await;
+            model_lock.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?
+                .clone()
+        };
+
+        let started = Instant::now();
+        let logits = model.forward(&prompt, 0, &device)?;
+        let mut token = logits.argmax(0)?.to_scalar::<u32>()?;
+        let prompt_eval = started.elapsed();
+
+        let started = Instant::now();
+        for offset in 1..gen_tokens {
+            let logits = model.forward(&[token], prompt.len() + offset - 1, &device)?;
+            token = logits.argmax(0)?.to_scalar::<u32>()?;
         }
+        let generation = started.elapsed();
+
+        let rate = |tokens: usize, elapsed: Duration| {
+            tokens as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+        };
+        Ok(BenchmarkResult {
+            prompt_tokens: prompt.len(),
+            generated_tokens: gen_tokens,
+            prompt_eval_ms: prompt_eval.as_secs_f64() * 1000.0,
+            generation_ms: generation.as_secs_f64() * 1000.0,
+            prompt_tokens_per_second: rate(prompt.len(), prompt_eval),
+            // The first token comes out of prompt processing
+            generation_tokens_per_second: if gen_tokens > 1 {
+                rate(gen_tokens - 1, generation)
+            } else {
+                rate(1, prompt_eval)
+            },
+            peak_memory_bytes: peak_memory_bytes(),
+        })
+    }
+
+    /// Generate text completion
+    pub async fn generate(&self, request: GenerateRequest) -> Result<GenerationResult> {
+        self.run_generation(&request, None, &mut |_| {}, |_| {}).await
     }
 
     /// Generate text with streaming
+    ///
+    /// Each decoded token is passed to `callback` as it is produced, followed
+    /// by a final empty response with `is_final` set.
     pub async fn generate_stream<F>(
         &self,
         request: GenerateRequest,
+        callback: F,
+    ) -> Result<GenerationResult>
+    where
+        F: FnMut(TokenResponse) + Send,
+    {
+        self.generate_stream_with(request, None, |_| {}, callback).await
+    }
+
+    /// Generate text with streaming, reporting the tokenization of large
+    /// prompts and stopping early when `cancel` is set
+    ///
+    /// Prompts over `TOKENIZE_PROGRESS_THRESHOLD` bytes are tokenized in
+    /// chunks, with `on_tokenize` called after each. `cancel` is checked
+    /// between chunks and before the first token is generated; once set,
+    /// generation ends with a "Generation cancelled" error.
+    pub async fn generate_stream_with<P, F>(
+        &self,
+        request: GenerateRequest,
+        cancel: Option<&RwLock<bool>>,
+        mut on_tokenize: P,
         mut callback: F,
     ) -> Result<GenerationResult>
+    where
+        P: FnMut(TokenizeProgress) + Send,
+        F: FnMut(TokenResponse) + Send,
+    {
+        let result = self
+            .run_generation(&request, cancel, &mut on_tokenize, &mut callback)
+            .await?;
+
+        callback(TokenResponse {
+            token: String::new(),
+            token_id: result.tokens.last().copied().unwrap_or_default(),
+            is_final: true,
+            total_tokens: result.total_tokens,
+            generation_time_ms: result.generation_time_ms,
+        });
+
+        Ok(result)
+    }
+
+    /// Tokenize the prompt and run the decode loop, reporting each new token
+    async fn run_generation<F>(
+        &self,
+        request: &GenerateRequest,
+        cancel: Option<&RwLock<bool>>,
+        on_tokenize: &mut (dyn FnMut(TokenizeProgress) + Send),
+        mut on_token: F,
+    ) -> Result<GenerationResult>
     where
         F: FnMut(TokenResponse) + Send,
     {
@@ -338,60 +746,114 @@ impl InferenceEngine {
         let tokenizer = tokenizer_lock.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Tokenizer not loaded"))?;
 
-        // Format messages into prompt
-        let prompt = self.format_prompt(&request.messages, request.system_prompt.as_deref());
-
-        // Tokenize prompt
-        let encoding = tokenizer.encode(prompt.clone(), false)
-            .map_err(|e| anyhow::anyhow!("Failed to tokenize prompt: {}", e))?;
-        let prompt_tokens = encoding.get_ids();
+        let (prompt_tokens, cache_hit) =
+            self.prepare_prompt(tokenizer, request, cancel, on_tokenize).await?;
         let prompt_token_count = prompt_tokens.len();
 
-        drop(tokenizer_lock);
-
-        log::info!("Streaming generation for {} token prompt", prompt_token_count);
+        log::info!("Generating response for {} token prompt", prompt_token_count);
 
-        // Get device info as owned string
-        let device_info = self.get_device_info().await;
+        let eos_token_ids = Self::eos_token_ids(tokenizer);
+        let device = self.device.read().await.clone();
+
+        // Continue from the conversation's cached state when its history is
+        // unchanged, otherwise start from a fresh copy of the loaded model
+        let (mut model, cached_tokens) = match cache_hit {
+            Some(hit) => (hit.state, hit.cached_tokens),
+            None => {
+                let model_lock = self.model.read().await;
+                let model = model_lock.as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?;
+                (model.clone(), 0)
+            }
+        };
 
-        // For now, simulate streaming with placeholder
-        // Full implementation would generate tokens one by one
-        let words_base = vec![
-            "Streaming", "GGUF", "inference", "is", "working!",
-            "GPU", "acceleration:",
-        ];
-        let words_end = vec!["Model", "loaded", "successfully."];
+        if cached_tokens > 0 {
+            log::info!("Reusing cached state for {} prompt tokens", cached_tokens);
+        }
 
-        // Combine all words
-        let mut words: Vec<String> = words_base.into_iter().map(|s| s.to_string()).collect();
-        words.push(device_info);
-        words.extend(words_end.into_iter().map(|s| s.to_string()));
+        let prompt_logits = prefill(&prompt_tokens, cached_tokens, |input, index_pos| {
+            model.forward(input, index_pos, &device)
+        })?;
+        ensure_not_cancelled(cancel).await?;
+
+        if let Some(id) = request.conversation_id.filter(|_| model.has_own_cache()) {
+            self.sessions.write().await.store(
+                id,
+                request.system_prompt.as_deref(),
+                prompt_tokens.clone(),
+                model.clone(),
+            );
+        }
 
-        let mut generated_tokens = 0;
-        let mut generated_text = String::new();
+        let config = &request.config;
+        let temperature = if config.do_sample && config.temperature > 0.0 {
+            Some(config.temperature)
+        } else {
+            None
+        };
+        let mut logits_processor = LogitsProcessor::new(
+            config.seed.unwrap_or(DEFAULT_SEED),
+            temperature,
+            Some(config.top_p),
+        );
+
+        let mut context = prompt_tokens;
+        let mut prompt_logits = Some(prompt_logits);
+        let mut decoded_len = 0;
+        let mut first_token_at = None;
+        let mut line_guard = RepeatedLineGuard::new(config.max_repeated_lines);
+
+        let (generated, finish_reason) = decode_loop(
+            config.max_new_tokens,
+            &eos_token_ids,
+            |_| {
+                // The first step samples from the prompt logits, later steps only
+                // process the last sampled token (the model keeps its own KV cache)
+                let logits = match prompt_logits.take() {
+                    Some(logits) => logits,
+                    None => model.forward(&context[context.len() - 1..], context.len() - 1, &device)?,
+                };
 
-        for (i, word) in words.iter().enumerate() {
-            // Simulate token generation time
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                let logits = if config.repetition_penalty == 1.0 || config.repeat_last_n == 0 {
+                    logits
+                } else {
+                    apply_repeat_penalty(
+                        &logits,
+                        config.repetition_penalty as f32,
+                        repeat_penalty_window(&context, config.repeat_last_n),
+                    )?
+                };
 
-            generated_tokens += 1;
-            let is_final = i == words.len() - 1;
+                let token = logits_processor.sample(&logits)?;
+                context.push(token);
+                Ok(token)
+            },
+            |token, generated| {
+                first_token_at.get_or_insert_with(Instant::now);
+
+                // Decode the whole generation so multi-token characters come out intact
+                let text = tokenizer.decode(generated, true)
+                    .map_err(|e| anyhow::anyhow!("Failed to decode tokens: {}", e))?;
+                let piece = text.get(decoded_len..).unwrap_or_default().to_string();
+                decoded_len = text.len();
+
+                let looping = line_guard.push(&piece);
+                on_token(TokenResponse {
+                    token: piece,
+                    token_id: token,
+                    is_final: false,
+                    total_tokens: prompt_token_count + generated.len(),
+                    generation_time_ms: start_time.elapsed().as_millis() as u64,
+                });
+                Ok(looping.then_some(FinishReason::Loop))
+            },
+        )?;
 
-            if !generated_text.is_empty() {
-                generated_text.push(' ');
-            }
-            generated_text.push_str(&word);
-
-            callback(TokenResponse {
-                token: word.clone(),
-                token_id: i as u32,
-                is_final,
-                total_tokens: prompt_token_count + generated_tokens,
-                generation_time_ms: start_time.elapsed().as_millis() as u64,
-            });
-        }
+        let generated_text = tokenizer.decode(&generated, true)
+            .map_err(|e| anyhow::anyhow!("Failed to decode tokens: {}", e))?;
 
         let generation_time = start_time.elapsed().as_millis() as u64;
+        let generated_tokens = generated.len();
         let total_tokens = prompt_token_count + generated_tokens;
 
         let tokens_per_second = if generation_time > 0 {
@@ -399,60 +861,393 @@ impl InferenceEngine {
         } else {
             0.0
         };
+        let first_token_ms =
+            first_token_at.map(|at| at.duration_since(start_time).as_millis() as u64);
+        let decode_tokens_per_second = first_token_at
+            .map(|at| steady_state_rate(generated_tokens, at.elapsed()))
+            .unwrap_or_default();
+
+        log::info!(
+            "Generated {} tokens ({:?}) in {} ms",
+            generated_tokens,
+            finish_reason,
+            generation_time
+        );
 
         Ok(GenerationResult {
             text: generated_text,
-            tokens: prompt_tokens.to_vec(),
+            tokens: generated,
             total_tokens,
             prompt_tokens: prompt_token_count,
+            cached_prompt_tokens: cached_tokens,
             generated_tokens,
             generation_time_ms: generation_time,
             tokens_per_second,
+            first_token_ms,
+            decode_tokens_per_second,
+            finish_reason,
         })
     }
 
-    /// Format chat messages into a prompt
+    /// Tokenize the full history of a request and find a cached state for it
+    ///
+    /// The prompt is always tokenized from the messages with the loaded
+    /// tokenizer, so a conversation continued on another model is re-read in
+    /// that model's vocabulary. Sessions left by a previous model are
+    /// discarded before the lookup.
+    async fn prepare_prompt(
+        &self,
+        tokenizer: &Tokenizer,
+        request: &GenerateRequest,
+        cancel: Option<&RwLock<bool>>,
+        on_tokenize: &mut (dyn FnMut(TokenizeProgress) + Send),
+    ) -> Result<(Vec<u32>, Option<CacheHit<LoadedModel>>)> {
+        let prompt = self.format_prompt(&request.messages, request.system_prompt.as_deref());
+        let prompt_tokens = tokenize_prompt(tokenizer, &prompt, cancel, on_tokenize).await?;
+        ensure_not_cancelled(cancel).await?;
+
+        self.bind_sessions_to_loaded_model().await;
+        let cache_hit = match request.conversation_id {
+            Some(id) => self.sessions.write().await.lookup(
+                id,
+                request.system_prompt.as_deref(),
+                &prompt_tokens,
+            ),
+            None => None,
+        };
+
+        Ok((prompt_tokens, cache_hit))
+    }
+
+    /// Drop cached sessions built by a model other than the loaded one
+    async fn bind_sessions_to_loaded_model(&self) {
+        let model_id = self.loaded_model_id().await.unwrap_or_default();
+        if self.sessions.write().await.bind_model(&model_id) {
+            log::info!("Model changed to {}, discarded cached sessions", model_id);
+        }
+    }
+
+    /// Token ids that end generation for the common chat model families
+    fn eos_token_ids(tokenizer: &Tokenizer) -> Vec<u32> {
+        EOS_TOKENS
+            .iter()
+            .filter_map(|token| tokenizer.token_to_id(token))
+            .collect()
+    }
+
+    /// Chat template of the loaded model
+    pub fn chat_template(&self) -> ChatTemplate {
+        *self.chat_template.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_chat_template(&self, template: ChatTemplate) {
+        *self.chat_template.write().unwrap_or_else(|e| e.into_inner()) = template;
+    }
+
+    /// The exact prompt the loaded model would be given for these messages
+    pub async fn render_prompt(
+        &self,
+        messages: &[ChatMessage],
+        system_prompt: Option<&str>,
+    ) -> Result<String> {
+        if !self.is_loaded().await {
+            anyhow::bail!("No model loaded");
+        }
+        Ok(self.format_prompt(messages, system_prompt))
+    }
+
+    /// Format chat messages into a prompt for the assistant's reply
     fn format_prompt(&self, messages: &[ChatMessage], system_prompt: Option<&str>) -> String {
-        let mut prompt = String::new();
+        self.chat_template().render_prompt(messages, system_prompt)
+    }
+
+    /// Format chat messages without the trailing generation prompt
+    ///
+    /// Every later prompt of the conversation starts with this text.
+    fn format_history(&self, messages: &[ChatMessage], system_prompt: Option<&str>) -> String {
+        self.chat_template().render_history(messages, system_prompt)
+    }
+}
 
-        // Add system prompt if provided
-        if let Some(system) = system_prompt {
-            prompt.push_str(&format!("<|system|>\n{}\n", system));
+/// Tokens the repetition penalty applies to: the last `repeat_last_n` of the context
+fn repeat_penalty_window(context: &[u32], repeat_last_n: usize) -> &[u32] {
+    &context[context.len().saturating_sub(repeat_last_n)..]
+}
+
+/// Tokens per second after the first token
+///
+/// The first token carries prompt processing and any warm-up cost, so the
+/// rate counts only the tokens after it over the time since it appeared.
+fn steady_state_rate(generated_tokens: usize, since_first_token: Duration) -> f64 {
+    let seconds = since_first_token.as_secs_f64();
+    if generated_tokens < 2 || seconds <= 0.0 {
+        return 0.0;
+    }
+    (generated_tokens - 1) as f64 / seconds
+}
+
+/// Tokenize a prompt, in chunks with progress when it is large
+///
+/// Chunks are tokenized separately, so tokens at a chunk boundary may differ
+/// slightly from tokenizing the prompt at once; chunks end only after
+/// whitespace, preferably a line break, to keep such differences rare.
+async fn tokenize_prompt(
+    tokenizer: &Tokenizer,
+    prompt: &str,
+    cancel: Option<&RwLock<bool>>,
+    on_progress: &mut (dyn FnMut(TokenizeProgress) + Send),
+) -> Result<Vec<u32>> {
+    let encode = |text: &str| -> Result<Vec<u32>> {
+        Ok(tokenizer
+            .encode(text, false)
+            .map_err(|e| anyhow::anyhow!("Failed to tokenize prompt: {}", e))?
+            .get_ids()
+            .to_vec())
+    };
+    if prompt.len() <= TOKENIZE_PROGRESS_THRESHOLD {
+        return encode(prompt);
+    }
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while start < prompt.len() {
+        ensure_not_cancelled(cancel).await?;
+
+        let end = chunk_end(prompt, start + TOKENIZE_CHUNK_BYTES);
+
+        tokens.extend(encode(&prompt[start..end])?);
+        start = end;
+        on_progress(TokenizeProgress {
+            processed_bytes: end,
+            total_bytes: prompt.len(),
+            tokens: tokens.len(),
+        });
+    }
+    Ok(tokens)
+}
+
+/// End of a prompt chunk reaching at least to `target`: just after the next
+/// line break within a chunk length, else just after the next whitespace
+///
+/// Special tokens such as `<|im_start|>` contain no whitespace, so a cut
+/// never falls inside one. Without any whitespace the chunk takes the rest.
+fn chunk_end(prompt: &str, target: usize) -> usize {
+    if target >= prompt.len() {
+        return prompt.len();
+    }
+    let mut target = target;
+    while !prompt.is_char_boundary(target) {
+        target += 1;
+    }
+
+    let rest = &prompt[target..];
+    let line_break = rest
+        .find('\n')
+        .filter(|&i| i < TOKENIZE_CHUNK_BYTES)
+        .map(|i| (i, '\n'));
+    line_break
+        .or_else(|| rest.char_indices().find(|(_, c)| c.is_whitespace()))
+        .map_or(prompt.len(), |(i, c)| target + i + c.len_utf8())
+}
+
+/// Fail with "Generation cancelled" once `cancel` is set
+async fn ensure_not_cancelled(cancel: Option<&RwLock<bool>>) -> Result<()> {
+    match cancel {
+        Some(flag) if *flag.read().await => anyhow::bail!("Generation cancelled"),
+        _ => Ok(()),
+    }
+}
+
+/// Reject benchmark sizes that are empty or don't fit the model's context,
+/// before any work is done
+fn check_benchmark_size(
+    prompt_tokens: usize,
+    gen_tokens: usize,
+    context_length: Option<usize>,
+) -> Result<()> {
+    if prompt_tokens == 0 || gen_tokens == 0 {
+        anyhow::bail!("Benchmark needs at least one prompt token and one generated token");
+    }
+    if let Some(context_length) = context_length {
+        let total = prompt_tokens.saturating_add(gen_tokens);
+        if total > context_length {
+            anyhow::bail!(
+                "Benchmark needs {} tokens ({} prompt + {} generated), but the model's \
+                 context holds {}",
+                total,
+                prompt_tokens,
+                gen_tokens,
+                context_length
+            );
         }
+    }
+    Ok(())
+}
 
-        // Add conversation messages
-        for msg in messages {
-            match msg.role.as_str() {
-                "system" => {
-                    prompt.push_str(&format!("<|system|>\n{}\n", msg.content));
-                }
-                "user" => {
-                    prompt.push_str(&format!("<|user|>\n{}\n", msg.content));
-                }
-                "assistant" => {
-                    prompt.push_str(&format!("<|assistant|>\n{}\n", msg.content));
-                }
-                _ => {}
-            }
+/// Exactly `len` tokens of filler text
+fn benchmark_prompt(tokenizer: &Tokenizer, len: usize) -> Result<Vec<u32>> {
+    let filler = tokenizer.encode(BENCHMARK_FILLER, false)
+        .map_err(|e| anyhow::anyhow!("Failed to tokenize benchmark prompt: {}", e))?;
+    let filler = filler.get_ids();
+    if filler.is_empty() {
+        anyhow::bail!("Tokenizer produced no tokens for the benchmark prompt");
+    }
+    Ok(filler.iter().copied().cycle().take(len).collect())
+}
+
+/// Peak resident memory of this process (`VmHWM`), on Linux
+fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Run the prompt tokens not covered by the cache through the model
+///
+/// Returns the logits for the last prompt token. A cold start processes the
+/// prompt in one batch; on top of a cached state the remaining tokens are fed
+/// one at a time, as the quantized models only build causal masks for a
+/// prompt that starts at position 0.
+fn prefill<F>(prompt_tokens: &[u32], cached_tokens: usize, mut forward: F) -> Result<Tensor>
+where
+    F: FnMut(&[u32], usize) -> Result<Tensor>,
+{
+    if prompt_tokens.len() <= cached_tokens {
+        anyhow::bail!("Prompt has no tokens to process");
+    }
+
+    if cached_tokens == 0 {
+        return forward(prompt_tokens, 0);
+    }
+
+    let mut logits = None;
+    for (offset, token) in prompt_tokens[cached_tokens..].iter().enumerate() {
+        logits = Some(forward(std::slice::from_ref(token), cached_tokens + offset)?);
+    }
+
+    logits.ok_or_else(|| anyhow::anyhow!("Prompt has no tokens to process"))
+}
+
+/// Detects a model stuck repeating the same line
+///
+/// Text is fed in as it is decoded; only completed lines count, compared
+/// with surrounding whitespace trimmed. Blank lines are skipped, so a loop
+/// of a line followed by an empty one is caught too.
+struct RepeatedLineGuard {
+    /// Identical lines in a row that count as a loop; 0 disables the guard
+    max_repeated_lines: usize,
+    current_line: String,
+    last_line: Option<String>,
+    repeats: usize,
+}
+
+impl RepeatedLineGuard {
+    fn new(max_repeated_lines: usize) -> Self {
+        Self {
+            max_repeated_lines,
+            current_line: String::new(),
+            last_line: None,
+            repeats: 0,
+        }
+    }
+
+    /// Feed newly decoded text; true once the limit of identical lines is reached
+    fn push(&mut self, text: &str) -> bool {
+        if self.max_repeated_lines == 0 {
+            return false;
+        }
+
+        let mut pieces = text.split('\n');
+        self.current_line.push_str(pieces.next().unwrap_or_default());
+
+        let mut looping = false;
+        for piece in pieces {
+            let line = std::mem::replace(&mut self.current_line, piece.to_string());
+            looping |= self.finish_line(line.trim());
         }
+        looping
+    }
 
-        // Add assistant prompt for generation
-        prompt.push_str("<|assistant|>\n");
+    fn finish_line(&mut self, line: &str) -> bool {
+        if line.is_empty() {
+            return false;
+        }
 
-        prompt
+        if self.last_line.as_deref() == Some(line) {
+            self.repeats += 1;
+        } else {
+            self.last_line = Some(line.to_string());
+            self.repeats = 1;
+        }
+        self.repeats >= self.max_repeated_lines
     }
 }
 
+/// Drive a token-by-token decode loop with a hard cap on new tokens
+///
+/// `next_token` is called with the step index and returns the next token id;
+/// `on_token` receives each kept token along with everything generated so far,
+/// and can end the loop early by returning a finish reason. Otherwise the
+/// loop ends on an end-of-sequence token (which is not kept) or once
+/// `max_new_tokens` tokens have been produced, whichever comes first.
+fn decode_loop<N, T>(
+    max_new_tokens: usize,
+    eos_token_ids: &[u32],
+    mut next_token: N,
+    mut on_token: T,
+) -> Result<(Vec<u32>, FinishReason)>
+where
+    N: FnMut(usize) -> Result<u32>,
+    T: FnMut(u32, &[u32]) -> Result<Option<FinishReason>>,
+{
+    let mut generated = Vec::with_capacity(max_new_tokens.min(4096));
+
+    for step in 0..max_new_tokens {
+        let token = next_token(step)?;
+
+        if eos_token_ids.contains(&token) {
+            return Ok((generated, FinishReason::Stop));
+        }
+
+        generated.push(token);
+        if let Some(reason) = on_token(token, &generated)? {
+            return Ok((generated, reason));
+        }
+    }
+
+    Ok((generated, FinishReason::Length))
+}
+
 impl Default for InferenceEngine {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Write a weightless GGUF file with string metadata and, unless `tokens` is
+/// empty, a llama vocabulary
+#[cfg(test)]
+pub(crate) fn write_test_gguf(path: &Path, metadata: &[(&str, &str)], tokens: &[&str]) {
+    let mut values: Vec<(&str, gguf_file::Value)> = metadata
+        .iter()
+        .map(|(key, value)| (*key, gguf_file::Value::String(value.to_string())))
+        .collect();
+    if !tokens.is_empty() {
+        let tokens = tokens.iter().map(|t| gguf_file::Value::String(t.to_string())).collect();
+        values.push(("tokenizer.ggml.model", gguf_file::Value::String("llama".to_string())));
+        values.push(("tokenizer.ggml.tokens", gguf_file::Value::Array(tokens)));
+    }
+
+    let metadata: Vec<(&str, &gguf_file::Value)> =
+        values.iter().map(|(key, value)| (*key, value)).collect();
+    let mut file = std::fs::File::create(path).unwrap();
+    gguf_file::write(&mut file, &metadata, &[]).unwrap();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::types::GenerationConfig;
 
     #[tokio::test]
     async fn test_inference_engine_creation() {
@@ -467,6 +1262,20 @@ mod tests {
         assert!(matches!(device, Device::Cpu) || matches!(device, Device::Cuda(_)) || matches!(device, Device::Metal(_)));
     }
 
+    #[tokio::test]
+    async fn test_generation_defaults() {
+        let engine = InferenceEngine::new();
+        assert_eq!(engine.get_generation_defaults().await, GenerationConfig::default());
+
+        let config = GenerationConfig {
+            temperature: 0.2,
+            max_new_tokens: 256,
+            ..GenerationConfig::default()
+        };
+        engine.set_generation_defaults(config.clone()).await;
+        assert_eq!(engine.get_generation_defaults().await, config);
+    }
+
     #[tokio::test]
     async fn test_model_status() {
         let engine = InferenceEngine::new();
@@ -503,6 +1312,753 @@ mod tests {
         assert!(prompt.contains("Hello!"));
     }
 
+    #[test]
+    fn test_repeat_penalty_only_within_window() {
+        let context = [1u32, 2, 3, 4, 5];
+        assert_eq!(repeat_penalty_window(&context, 2), &[4, 5]);
+        assert_eq!(repeat_penalty_window(&context, 64), &context);
+        assert!(repeat_penalty_window(&context, 0).is_empty());
+
+        // Token 1 was seen, but before the window: its logit is left alone
+        let logits = Tensor::new(&[2.0f32, 2.0, 2.0, 2.0, 2.0, 2.0], &Device::Cpu).unwrap();
+        let penalized = apply_repeat_penalty(&logits, 2.0, repeat_penalty_window(&context, 2))
+            .unwrap()
+            .to_vec1::<f32>()
+            .unwrap();
+        assert_eq!(penalized, vec![2.0, 2.0, 2.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_history_prefixes_next_prompt() {
+        let engine = InferenceEngine::new();
+        let mut messages = vec![
+            ChatMessage {
+                role: "user".to_string(),
+                content: "Hello!".to_string(),
+            },
+            ChatMessage {
+                role: "assistant".to_string(),
+                content: "Hi there!".to_string(),
+            },
+        ];
+
+        // A primed history must be reusable by the prompt of the following turn
+        let history = engine.format_history(&messages, Some("system"));
+        assert!(!history.ends_with("<|assistant|>\n"));
+
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: "Next question".to_string(),
+        });
+        let next_prompt = engine.format_prompt(&messages, Some("system"));
+        assert!(next_prompt.starts_with(&history));
+        assert!(next_prompt.len() > history.len());
+    }
+
+    #[tokio::test]
+    async fn test_prime_session_requires_loaded_model() {
+        let engine = InferenceEngine::new();
+        let err = engine.prime_session(1, &[], None).await.unwrap_err();
+        assert!(err.to_string().contains("No model loaded"));
+    }
+
+    /// Write a weightless GGUF file, optionally embedding a small vocabulary
+    fn write_gguf(path: &Path, with_vocab: bool) {
+        write_gguf_with_architecture(path, "llama", with_vocab);
+    }
+
+    fn write_gguf_with_architecture(path: &Path, architecture: &str, with_vocab: bool) {
+        let tokens: &[&str] = if with_vocab {
+            &["<unk>", "\u{2581}hello", "\u{2581}world"]
+        } else {
+            &[]
+        };
+        write_test_gguf(path, &[("general.architecture", architecture)], tokens);
+    }
+
+    fn read_gguf(path: &Path) -> gguf_file::Content {
+        let mut file = std::fs::File::open(path).unwrap();
+        gguf_file::Content::read(&mut file).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_external_tokenizer() {
+        let dir = tempfile::tempdir().unwrap();
+        let gguf_path = dir.path().join("model.gguf");
+
+        // Build tokenizer.json from a GGUF vocabulary, then use a vocabulary-less model
+        write_gguf(&gguf_path, true);
+        let external = tokenizer_from_gguf(&read_gguf(&gguf_path).metadata)
+            .unwrap()
+            .unwrap();
+        external
+            .save(dir.path().join("tokenizer.json"), false)
+            .unwrap();
+        write_gguf(&gguf_path, false);
+
+        let tokenizer =
+            InferenceEngine::resolve_tokenizer(dir.path(), &read_gguf(&gguf_path)).unwrap();
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_resolve_embedded_tokenizer() {
+        let dir = tempfile::tempdir().unwrap();
+        let gguf_path = dir.path().join("model.gguf");
+        write_gguf(&gguf_path, true);
+
+        let tokenizer =
+            InferenceEngine::resolve_tokenizer(dir.path(), &read_gguf(&gguf_path)).unwrap();
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 2]);
+
+        // A path pointing at the file itself resolves the same way
+        assert!(InferenceEngine::resolve_tokenizer(&gguf_path, &read_gguf(&gguf_path)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_requires_loaded_model() {
+        let engine = InferenceEngine::new();
+        let err = engine.benchmark(16, 8).await.unwrap_err();
+        assert!(err.to_string().contains("No model loaded"));
+
+        #[cfg(target_os = "linux")]
+        assert!(peak_memory_bytes().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_benchmark_size_must_fit_context() {
+        check_benchmark_size(3072, 1024, Some(4096)).unwrap();
+        check_benchmark_size(100_000, 100_000, None).unwrap();
+
+        let err = check_benchmark_size(4000, 128, Some(4096)).unwrap_err().to_string();
+        assert!(err.contains("needs 4128 tokens"), "{}", err);
+        assert!(err.contains("context holds 4096"), "{}", err);
+        assert!(check_benchmark_size(usize::MAX, 1, Some(4096)).is_err());
+
+        let err = check_benchmark_size(0, 8, Some(4096)).unwrap_err().to_string();
+        assert!(err.contains("at least one prompt token"));
+    }
+
+    #[test]
+    fn test_context_length_from_metadata() {
+        let mut metadata = architecture_metadata(Some(gguf_file::Value::String("qwen2".into())));
+        let architecture = GgufArchitecture::from_metadata(&metadata).unwrap();
+        assert_eq!(architecture.context_length(&metadata), None);
+
+        metadata.insert("qwen2.context_length".to_string(), gguf_file::Value::U32(32768));
+        assert_eq!(architecture.context_length(&metadata), Some(32768));
+        metadata.insert("qwen2.context_length".to_string(), gguf_file::Value::U64(4096));
+        assert_eq!(architecture.context_length(&metadata), Some(4096));
+        // Another architecture's key doesn't count
+        metadata.insert("qwen2.context_length".to_string(), gguf_file::Value::String("x".into()));
+        metadata.insert("llama.context_length".to_string(), gguf_file::Value::U32(2048));
+        assert_eq!(architecture.context_length(&metadata), None);
+    }
+
+    /// Benchmark a small GGUF model from the directory in `TINY_MODEL_DIR`
+    #[cfg(feature = "tiny-model-tests")]
+    #[tokio::test]
+    async fn test_benchmark_tiny_model() {
+        let dir = std::env::var("TINY_MODEL_DIR")
+            .expect("TINY_MODEL_DIR must point at a directory with a small GGUF model");
+        let engine = InferenceEngine::new();
+        let config = ModelConfig {
+            format: ModelFormat::GGUF,
+            ..ModelConfig::default()
+        };
+        engine.load_model(PathBuf::from(dir), config).await.unwrap();
+
+        let first = engine.benchmark(32, 16).await.unwrap();
+        let second = engine.benchmark(32, 16).await.unwrap();
+
+        for run in [&first, &second] {
+            assert_eq!(run.prompt_tokens, 32);
+            assert_eq!(run.generated_tokens, 16);
+            assert!(run.prompt_tokens_per_second > 0.0, "{:?}", run);
+            assert!(run.generation_tokens_per_second > 0.0, "{:?}", run);
+        }
+        // Same work on the same machine: rates stay within an order of magnitude
+        let ratio = first.generation_tokens_per_second / second.generation_tokens_per_second;
+        assert!((0.1..10.0).contains(&ratio), "{:?} vs {:?}", first, second);
+    }
+
+    #[tokio::test]
+    async fn test_load_without_any_tokenizer_fails_fast() {
+        let dir = tempfile::tempdir().unwrap();
+        write_gguf(&dir.path().join("model.gguf"), false);
+
+        let engine = InferenceEngine::new();
+        let config = ModelConfig {
+            format: ModelFormat::GGUF,
+            ..ModelConfig::default()
+        };
+
+        let err = engine
+            .load_model(dir.path().to_path_buf(), config)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No tokenizer found"));
+
+        match engine.get_status().await {
+            ModelStatus::Error(msg) => assert!(msg.contains("No tokenizer found")),
+            other => panic!("Expected error status, got {:?}", other),
+        }
+    }
+
+    fn architecture_metadata(architecture: Option<gguf_file::Value>) -> HashMap<String, gguf_file::Value> {
+        architecture
+            .map(|value| ("general.architecture".to_string(), value))
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_architecture_from_metadata() {
+        let cases = [
+            ("llama", GgufArchitecture::Llama),
+            ("phi2", GgufArchitecture::Phi2),
+            ("phi3", GgufArchitecture::Phi3),
+            ("qwen2", GgufArchitecture::Qwen2),
+        ];
+        for (name, expected) in cases {
+            let metadata = architecture_metadata(Some(gguf_file::Value::String(name.to_string())));
+            assert_eq!(GgufArchitecture::from_metadata(&metadata).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_architecture_is_reported() {
+        for name in ["gemma", "mamba"] {
+            let metadata = architecture_metadata(Some(gguf_file::Value::String(name.to_string())));
+            let err = GgufArchitecture::from_metadata(&metadata).unwrap_err().to_string();
+            assert!(err.contains(&format!("Unsupported model architecture '{}'", name)));
+            assert!(err.contains("llama, phi2, phi3, qwen2"));
+        }
+
+        let err = GgufArchitecture::from_metadata(&architecture_metadata(None)).unwrap_err();
+        assert!(err.to_string().contains("no general.architecture"));
+
+        let err = GgufArchitecture::from_metadata(&architecture_metadata(Some(gguf_file::Value::U32(1))))
+            .unwrap_err();
+        assert!(err.to_string().contains("not a string"));
+    }
+
+    #[tokio::test]
+    async fn test_load_unsupported_architecture_fails_before_weights() {
+        let dir = tempfile::tempdir().unwrap();
+        write_gguf_with_architecture(&dir.path().join("model.gguf"), "gemma", true);
+
+        let engine = InferenceEngine::new();
+        let config = ModelConfig {
+            format: ModelFormat::GGUF,
+            ..ModelConfig::default()
+        };
+
+        let err = engine
+            .load_model(dir.path().to_path_buf(), config)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsupported model architecture 'gemma'"));
+        assert!(!engine.is_loaded().await);
+    }
+
+    #[tokio::test]
+    async fn test_supported_architecture_reaches_weight_loading() {
+        // The fixture has no tensors, so the selected model struct fails on
+        // missing weights rather than on the architecture
+        for architecture in ["phi3", "qwen2"] {
+            let dir = tempfile::tempdir().unwrap();
+            write_gguf_with_architecture(&dir.path().join("model.gguf"), architecture, true);
+
+            let engine = InferenceEngine::new();
+            let config = ModelConfig {
+                format: ModelFormat::GGUF,
+                ..ModelConfig::default()
+            };
+
+            let err = engine
+                .load_model(dir.path().to_path_buf(), config)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Failed to load GGUF model weights"));
+            assert!(format!("{:#}", err).contains(&format!("{}.", architecture)), "{:#}", err);
+        }
+    }
+
+    #[test]
+    fn test_decode_loop_stops_at_max_tokens() {
+        let mut calls = 0;
+        let mut streamed = Vec::new();
+
+        let (tokens, finish_reason) = decode_loop(
+            3,
+            &[2],
+            |step| {
+                calls += 1;
+                Ok(100 + step as u32)
+            },
+            |token, _| {
+                streamed.push(token);
+                Ok(None)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(calls, 3);
+        assert_eq!(tokens, vec![100, 101, 102]);
+        assert_eq!(streamed, tokens);
+        assert_eq!(finish_reason, FinishReason::Length);
+    }
+
+    #[test]
+    fn test_decode_loop_zero_max_tokens() {
+        let (tokens, finish_reason) =
+            decode_loop(0, &[2], |_| panic!("no step expected"), |_, _| Ok(None)).unwrap();
+
+        assert!(tokens.is_empty());
+        assert_eq!(finish_reason, FinishReason::Length);
+    }
+
+    #[test]
+    fn test_decode_loop_stops_at_eos() {
+        let script = [10, 11, 2, 12];
+        let (tokens, finish_reason) =
+            decode_loop(10, &[2], |step| Ok(script[step]), |_, _| Ok(None)).unwrap();
+
+        assert_eq!(tokens, vec![10, 11]);
+        assert_eq!(finish_reason, FinishReason::Stop);
+    }
+
+    #[test]
+    fn test_decode_loop_propagates_errors() {
+        let result = decode_loop(
+            5,
+            &[],
+            |step| {
+                if step == 1 {
+                    anyhow::bail!("forward failed")
+                }
+                Ok(7)
+            },
+            |_, _| Ok(None),
+        );
+
+        assert!(result.is_err());
+    }
+
+    /// Run the decode loop over a scripted token stream with the line guard,
+    /// the way `run_generation` does
+    fn guarded_decode(vocab: &[&str], script: &[u32], max_repeated_lines: usize) -> (String, FinishReason) {
+        let mut guard = RepeatedLineGuard::new(max_repeated_lines);
+        let (tokens, finish_reason) = decode_loop(
+            script.len() + 10,
+            &[0],
+            |step| Ok(script.get(step).copied().unwrap_or(0)),
+            |token, _| Ok(guard.push(vocab[token as usize]).then_some(FinishReason::Loop)),
+        )
+        .unwrap();
+        let text = tokens.iter().map(|&token| vocab[token as usize]).collect();
+        (text, finish_reason)
+    }
+
+    #[test]
+    fn test_repeated_lines_stop_generation() {
+        let vocab = ["</s>", "The tenant", " shall pay", " rent.", "\n", "\n\n", "Signed"];
+        let line = [1, 2, 3, 4];
+        let looping: Vec<u32> = line.iter().copied().cycle().take(line.len() * 50).collect();
+
+        let (text, finish_reason) = guarded_decode(&vocab, &looping, 3);
+        assert_eq!(finish_reason, FinishReason::Loop);
+        assert_eq!(text, "The tenant shall pay rent.\n".repeat(3));
+
+        // Blank lines between the repeats don't hide the loop
+        let spaced: Vec<u32> = [1, 2, 3, 5].iter().copied().cycle().take(200).collect();
+        let (text, finish_reason) = guarded_decode(&vocab, &spaced, 3);
+        assert_eq!(finish_reason, FinishReason::Loop);
+        assert_eq!(text.matches("rent.").count(), 3);
+
+        // 0 disables the guard
+        let (_, finish_reason) = guarded_decode(&vocab, &looping, 0);
+        assert_eq!(finish_reason, FinishReason::Stop);
+    }
+
+    #[test]
+    fn test_varied_lines_do_not_trigger_loop_guard() {
+        let vocab = ["</s>", "The tenant", " shall pay", " rent.", "\n", "Signed"];
+        // A repeat broken by another line starts the count again
+        let script = [1, 2, 3, 4, 1, 2, 3, 4, 5, 4, 1, 2, 3, 4, 1, 2, 3, 4, 1];
+        let (text, finish_reason) = guarded_decode(&vocab, &script, 3);
+        assert_eq!(finish_reason, FinishReason::Stop);
+        assert!(text.ends_with("The tenant"));
+
+        // An unfinished line doesn't count until its newline arrives
+        let mut guard = RepeatedLineGuard::new(2);
+        assert!(!guard.push("Clause 1\nClause"));
+        assert!(!guard.push(" 1"));
+        assert!(guard.push("  \n"));
+    }
+
+    /// Prefill with a stand-in model that records how many tokens it processed
+    fn counting_prefill(prompt: &[u32], cached_tokens: usize) -> usize {
+        let mut processed = 0;
+        let mut positions = Vec::new();
+
+        prefill(prompt, cached_tokens, |input, index_pos| {
+            processed += input.len();
+            positions.push(index_pos);
+            Ok(Tensor::zeros(4, DType::F32, &Device::Cpu)?)
+        })
+        .unwrap();
+
+        // Positions continue where the cached state left off
+        assert_eq!(positions[0], cached_tokens);
+        processed
+    }
+
+    #[test]
+    fn test_second_turn_processes_fewer_prompt_tokens() {
+        let mut sessions = SessionCache::new();
+        let first_turn: Vec<u32> = (0..200).collect();
+        let second_turn: Vec<u32> = (0..260).collect();
+
+        // First turn: nothing cached
+        let hit = sessions.lookup(7, Some("system"), &first_turn);
+        assert!(hit.is_none());
+        assert_eq!(counting_prefill(&first_turn, 0), 200);
+        sessions.store(7, Some("system"), first_turn, ());
+
+        // Second turn shares the history, only the new tokens are processed
+        let cold = counting_prefill(&second_turn, 0);
+        let hit = sessions.lookup(7, Some("system"), &second_turn).unwrap();
+        let warm = counting_prefill(&second_turn, hit.cached_tokens);
+
+        assert_eq!(cold, 260);
+        assert_eq!(warm, 60);
+        assert!(warm < cold);
+    }
+
+    #[test]
+    fn test_prefill_requires_new_tokens() {
+        let result = prefill(&[1, 2, 3], 3, |_, _| panic!("nothing to process"));
+        assert!(result.is_err());
+    }
+
+    /// Tokenizer over a small GGUF vocabulary
+    fn vocab_tokenizer(tokens: &[&str]) -> Tokenizer {
+        let metadata = [
+            (
+                "tokenizer.ggml.model".to_string(),
+                gguf_file::Value::String("llama".to_string()),
+            ),
+            (
+                "tokenizer.ggml.tokens".to_string(),
+                gguf_file::Value::Array(
+                    tokens
+                        .iter()
+                        .map(|t| gguf_file::Value::String(t.to_string()))
+                        .collect(),
+                ),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        tokenizer_from_gguf(&metadata).unwrap().unwrap()
+    }
+
+    /// Put the engine in the state of having loaded `model_id` with `tokenizer`
+    async fn set_loaded_model(engine: &InferenceEngine, model_id: &str, tokenizer: &Tokenizer) {
+        *engine.tokenizer.write().await = Some(tokenizer.clone());
+        *engine.model_config.write().await = Some(ModelConfig {
+            model_id: model_id.to_string(),
+            ..ModelConfig::default()
+        });
+    }
+
+    #[tokio::test]
+    async fn test_model_switch_retokenizes_history() {
+        let engine = InferenceEngine::new();
+        let fast = vocab_tokenizer(&["<unk>", "\u{2581}hello", "\u{2581}world"]);
+        let accurate = vocab_tokenizer(&["<unk>", "\u{2581}world", "\u{2581}hello"]);
+        let message = |role: &str, content: &str| ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        };
+        let mut request = GenerateRequest {
+            messages: vec![message("user", "hello world")],
+            config: GenerationConfig::default(),
+            system_prompt: None,
+            conversation_id: Some(3),
+        };
+
+        set_loaded_model(&engine, "fast", &fast).await;
+        let (first, hit) = engine
+            .prepare_prompt(&fast, &request, None, &mut |_| {})
+            .await
+            .unwrap();
+        assert!(hit.is_none());
+        assert!(!first.is_empty());
+
+        // Second turn on another model: the whole history is read with the new vocabulary
+        request.messages.push(message("assistant", "hello"));
+        request.messages.push(message("user", "world hello"));
+        set_loaded_model(&engine, "accurate", &accurate).await;
+        let (second, hit) = engine
+            .prepare_prompt(&accurate, &request, None, &mut |_| {})
+            .await
+            .unwrap();
+        assert!(hit.is_none());
+
+        let prompt = engine.format_prompt(&request.messages, None);
+        assert_eq!(second, accurate.encode(prompt.clone(), false).unwrap().get_ids());
+        assert_ne!(second, fast.encode(prompt, false).unwrap().get_ids());
+
+        // The session cache now belongs to the new model
+        assert!(!engine.sessions.write().await.bind_model("accurate"));
+    }
+
+    /// Write a one-layer Llama GGUF with tiny F32 weights and an embedded vocabulary
+    ///
+    /// The model produces nonsense, but it loads and generates like a real one.
+    fn write_tiny_llama(path: &Path) {
+        use candle_core::quantized::{GgmlDType, QTensor};
+
+        const EMBEDDING: usize = 8;
+        const FEED_FORWARD: usize = 16;
+        let tokens = ["<unk>", "\u{2581}hello", "\u{2581}world"];
+
+        let metadata = [
+            ("general.architecture", gguf_file::Value::String("llama".to_string())),
+            ("llama.attention.head_count", gguf_file::Value::U32(2)),
+            ("llama.attention.head_count_kv", gguf_file::Value::U32(2)),
+            ("llama.block_count", gguf_file::Value::U32(1)),
+            ("llama.embedding_length", gguf_file::Value::U32(EMBEDDING as u32)),
+            ("llama.rope.dimension_count", gguf_file::Value::U32(4)),
+            ("llama.attention.layer_norm_rms_epsilon", gguf_file::Value::F32(1e-5)),
+            ("tokenizer.ggml.model", gguf_file::Value::String("llama".to_string())),
+            (
+                "tokenizer.ggml.tokens",
+                gguf_file::Value::Array(
+                    tokens
+                        .iter()
+                        .map(|t| gguf_file::Value::String(t.to_string()))
+                        .collect(),
+                ),
+            ),
+        ];
+
+        let weight = |shape: &[usize]| {
+            let count: usize = shape.iter().product();
+            let values: Vec<f32> = (0..count).map(|i| ((i % 7) as f32 - 3.0) * 0.05).collect();
+            let tensor = Tensor::from_vec(values, shape, &Device::Cpu).unwrap();
+            QTensor::quantize(&tensor, GgmlDType::F32).unwrap()
+        };
+        let norm = || {
+            let tensor = Tensor::ones(EMBEDDING, DType::F32, &Device::Cpu).unwrap();
+            QTensor::quantize(&tensor, GgmlDType::F32).unwrap()
+        };
+        let tensors = [
+            ("token_embd.weight", weight(&[tokens.len(), EMBEDDING])),
+            ("output_norm.weight", norm()),
+            ("output.weight", weight(&[tokens.len(), EMBEDDING])),
+            ("blk.0.attn_q.weight", weight(&[EMBEDDING, EMBEDDING])),
+            ("blk.0.attn_k.weight", weight(&[EMBEDDING, EMBEDDING])),
+            ("blk.0.attn_v.weight", weight(&[EMBEDDING, EMBEDDING])),
+            ("blk.0.attn_output.weight", weight(&[EMBEDDING, EMBEDDING])),
+            ("blk.0.ffn_gate.weight", weight(&[FEED_FORWARD, EMBEDDING])),
+            ("blk.0.ffn_down.weight", weight(&[EMBEDDING, FEED_FORWARD])),
+            ("blk.0.ffn_up.weight", weight(&[FEED_FORWARD, EMBEDDING])),
+            ("blk.0.attn_norm.weight", norm()),
+            ("blk.0.ffn_norm.weight", norm()),
+        ];
+
+        let metadata: Vec<(&str, &gguf_file::Value)> =
+            metadata.iter().map(|(key, value)| (*key, value)).collect();
+        let tensors: Vec<(&str, &QTensor)> =
+            tensors.iter().map(|(name, tensor)| (*name, tensor)).collect();
+        let mut file = std::fs::File::create(path).unwrap();
+        gguf_file::write(&mut file, &metadata, &tensors).unwrap();
+    }
+
+    async fn tiny_engine(dir: &Path) -> InferenceEngine {
+        write_tiny_llama(&dir.join("tiny.gguf"));
+        let engine = InferenceEngine::new();
+        let config = ModelConfig {
+            format: ModelFormat::GGUF,
+            ..ModelConfig::default()
+        };
+        engine.load_model(dir.to_path_buf(), config).await.unwrap();
+        engine
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_keeps_model_loaded() {
+        let engine = InferenceEngine::new();
+        assert!(engine.warm_up().await.is_err());
+        assert!(matches!(engine.get_status().await, ModelStatus::NotLoaded));
+
+        let dir = tempfile::tempdir().unwrap();
+        let engine = tiny_engine(dir.path()).await;
+        engine.warm_up().await.unwrap();
+        assert!(matches!(engine.get_status().await, ModelStatus::Loaded));
+    }
+
+    #[tokio::test]
+    async fn test_render_prompt_uses_active_template() {
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Summarize the lease.".to_string(),
+        }];
+        let engine = InferenceEngine::new();
+        assert!(engine.render_prompt(&messages, None).await.is_err());
+
+        // The default model config names a Mistral model
+        let dir = tempfile::tempdir().unwrap();
+        let engine = tiny_engine(dir.path()).await;
+        assert_eq!(engine.chat_template(), ChatTemplate::Mistral);
+        let prompt = engine.render_prompt(&messages, Some("Be brief.")).await.unwrap();
+        assert_eq!(prompt, "<s>[INST] Be brief.\n\nSummarize the lease. [/INST]");
+
+        engine.unload_model().await;
+        assert_eq!(engine.chat_template(), ChatTemplate::Zephyr);
+    }
+
+    #[tokio::test]
+    async fn test_generation_reports_first_token_latency() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = tiny_engine(dir.path()).await;
+
+        let request = GenerateRequest {
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hello world".to_string(),
+            }],
+            config: GenerationConfig {
+                max_new_tokens: 4,
+                ..GenerationConfig::default()
+            },
+            system_prompt: None,
+            conversation_id: None,
+        };
+        let result = engine.generate(request).await.unwrap();
+
+        // The tiny vocabulary has no end-of-sequence token
+        assert_eq!(result.generated_tokens, 4);
+        let first_token_ms = result.first_token_ms.unwrap();
+        assert!(first_token_ms <= result.generation_time_ms);
+        assert!(result.decode_tokens_per_second >= 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_large_prompt_reports_tokenize_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = tiny_engine(dir.path()).await;
+        let tokenizer_lock = engine.tokenizer.read().await;
+        let tokenizer = tokenizer_lock.as_ref().unwrap();
+
+        let mut events = Vec::new();
+        let short = tokenize_prompt(tokenizer, "hello world", None, &mut |p| events.push(p))
+            .await
+            .unwrap();
+        assert_eq!(short, vec![1, 2]);
+        assert!(events.is_empty());
+
+        let prompt = "hello world\n".repeat(5_000);
+        assert!(prompt.len() > TOKENIZE_PROGRESS_THRESHOLD);
+        let tokens = tokenize_prompt(tokenizer, &prompt, None, &mut |p| events.push(p))
+            .await
+            .unwrap();
+
+        assert!(events.len() > 1, "{:?}", events);
+        assert!(events.windows(2).all(|w| w[0].processed_bytes < w[1].processed_bytes));
+        let last = events.last().unwrap();
+        assert_eq!(last.processed_bytes, prompt.len());
+        assert_eq!(last.total_bytes, prompt.len());
+        assert_eq!(last.tokens, tokens.len());
+        // Chunk boundaries change at most a token each
+        let whole = tokenizer.encode(prompt.as_str(), false).unwrap();
+        assert!(tokens.len().abs_diff(whole.len()) <= events.len());
+
+        // Without line breaks, chunks still end at whitespace, never mid-word:
+        // every word keeps its token and boundaries add at most an unknown one
+        let prompt = "hello world ".repeat(5_000);
+        let mut events = Vec::new();
+        let tokens = tokenize_prompt(tokenizer, &prompt, None, &mut |p| events.push(p))
+            .await
+            .unwrap();
+        assert!(events.len() > 1);
+        assert!(events.iter().all(|p| prompt[..p.processed_bytes].ends_with(' ')));
+        let words =
+            |ids: &[u32]| -> Vec<u32> { ids.iter().copied().filter(|&id| id != 0).collect() };
+        let whole = tokenizer.encode(prompt.as_str(), false).unwrap();
+        assert_eq!(words(&tokens), words(whole.get_ids()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_before_generation() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = tiny_engine(dir.path()).await;
+        let request = GenerateRequest {
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hello world\n".repeat(5_000),
+            }],
+            config: GenerationConfig {
+                max_new_tokens: 4,
+                ..GenerationConfig::default()
+            },
+            system_prompt: None,
+            conversation_id: None,
+        };
+
+        // Cancelled while the prompt is being tokenized
+        let cancel = RwLock::new(false);
+        let mut progress_events = 0;
+        let mut tokens = Vec::new();
+        let started = Instant::now();
+        let error = engine
+            .generate_stream_with(
+                request,
+                Some(&cancel),
+                |_| {
+                    progress_events += 1;
+                    *cancel.try_write().unwrap() = true;
+                },
+                |token| tokens.push(token),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Generation cancelled");
+        assert_eq!(progress_events, 1);
+        assert!(tokens.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A short prompt is not chunked, but is still cancelled before decoding
+        let request = GenerateRequest {
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hello world".to_string(),
+            }],
+            config: GenerationConfig::default(),
+            system_prompt: None,
+            conversation_id: None,
+        };
+        let result = engine
+            .generate_stream_with(request, Some(&cancel), |_| {}, |token| tokens.push(token))
+            .await;
+        assert!(result.is_err());
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_steady_state_rate_excludes_first_token() {
+        assert_eq!(steady_state_rate(11, Duration::from_secs(2)), 5.0);
+        assert_eq!(steady_state_rate(1, Duration::from_secs(2)), 0.0);
+        assert_eq!(steady_state_rate(5, Duration::ZERO), 0.0);
+    }
+
     #[tokio::test]
     async fn test_generate_without_model() {
         let engine = InferenceEngine::new();
@@ -510,6 +2066,7 @@ mod tests {
             messages: vec![],
             config: GenerationConfig::default(),
             system_prompt: None,
+            conversation_id: None,
         };
 
         let result = engine.generate(request).await;
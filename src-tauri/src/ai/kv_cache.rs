@@ -0,0 +1,257 @@
+//! Per-conversation KV cache sessions
+//!
+//! After the prompt of a turn has been run through the model, a snapshot of
+//! the model state (its attention KV cache) is kept together with the prompt
+//! tokens it covers. The next turn of the same conversation usually starts
+//! with exactly those tokens, so only the tokens after them need to be
+//! processed instead of the whole history.
+//!
+//! A snapshot is only reused when its tokens are a strict prefix of the new
+//! prompt and the system prompt is unchanged; anything else is a cold start.
+//! The cache is bound to the model that built it, since another model's
+//! tokens and KV cache mean nothing to the next one. Idle sessions are
+//! evicted so long-running apps don't accumulate caches.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Maximum number of conversations with a cached model state
+pub const MAX_CACHED_SESSIONS: usize = 4;
+
+/// Sessions unused for this long are evicted
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Cached model state for one conversation
+struct CachedSession<M> {
+    state: M,
+    tokens: Vec<u32>,
+    system_prompt: Option<String>,
+    last_used: Instant,
+}
+
+/// Model state reusable for a new prompt
+pub struct CacheHit<M> {
+    /// Model state that has already processed the first `cached_tokens` tokens
+    pub state: M,
+    /// Number of prompt tokens that don't need to be processed again
+    pub cached_tokens: usize,
+}
+
+/// Cached model states keyed by conversation id
+pub struct SessionCache<M> {
+    sessions: HashMap<i32, CachedSession<M>>,
+    /// Model the cached sessions were built with
+    model_id: Option<String>,
+    max_sessions: usize,
+    idle_timeout: Duration,
+}
+
+impl<M: Clone> SessionCache<M> {
+    pub fn new() -> Self {
+        Self::with_limits(MAX_CACHED_SESSIONS, SESSION_IDLE_TIMEOUT)
+    }
+
+    pub fn with_limits(max_sessions: usize, idle_timeout: Duration) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            model_id: None,
+            max_sessions,
+            idle_timeout,
+        }
+    }
+
+    /// Find a cached state for a conversation's new prompt
+    ///
+    /// A stale session (different system prompt, or a history that no longer
+    /// starts with the cached tokens) is dropped.
+    pub fn lookup(
+        &mut self,
+        conversation_id: i32,
+        system_prompt: Option<&str>,
+        prompt_tokens: &[u32],
+    ) -> Option<CacheHit<M>> {
+        self.evict_idle();
+
+        let session = self.sessions.get_mut(&conversation_id)?;
+
+        let reusable = session.system_prompt.as_deref() == system_prompt
+            && session.tokens.len() < prompt_tokens.len()
+            && prompt_tokens.starts_with(&session.tokens);
+
+        if !reusable {
+            self.sessions.remove(&conversation_id);
+            return None;
+        }
+
+        session.last_used = Instant::now();
+        Some(CacheHit {
+            state: session.state.clone(),
+            cached_tokens: session.tokens.len(),
+        })
+    }
+
+    /// Remember the model state after it has processed `tokens`
+    pub fn store(
+        &mut self,
+        conversation_id: i32,
+        system_prompt: Option<&str>,
+        tokens: Vec<u32>,
+        state: M,
+    ) {
+        self.sessions.insert(
+            conversation_id,
+            CachedSession {
+                state,
+                tokens,
+                system_prompt: system_prompt.map(str::to_string),
+                last_used: Instant::now(),
+            },
+        );
+
+        self.evict_idle();
+        while self.sessions.len() > self.max_sessions {
+            let oldest = self
+                .sessions
+                .iter()
+                .min_by_key(|(_, session)| session.last_used)
+                .map(|(id, _)| *id);
+            match oldest {
+                Some(id) => self.sessions.remove(&id),
+                None => break,
+            };
+        }
+    }
+
+    /// Tie the cache to the model about to use it
+    ///
+    /// Every session is dropped when they were built by a different model.
+    /// Returns true in that case, i.e. when the model changed.
+    pub fn bind_model(&mut self, model_id: &str) -> bool {
+        if self.model_id.as_deref() == Some(model_id) {
+            return false;
+        }
+
+        let changed = self.model_id.is_some();
+        self.sessions.clear();
+        self.model_id = Some(model_id.to_string());
+        changed
+    }
+
+    /// Forget a conversation's cached state
+    pub fn remove(&mut self, conversation_id: i32) {
+        self.sessions.remove(&conversation_id);
+    }
+
+    /// Forget all cached states (e.g. when the model changes)
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn evict_idle(&mut self) {
+        let timeout = self.idle_timeout;
+        self.sessions
+            .retain(|_, session| session.last_used.elapsed() < timeout);
+    }
+}
+
+impl<M: Clone> Default for SessionCache<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuses_matching_prefix() {
+        let mut cache = SessionCache::new();
+        assert!(cache.lookup(1, None, &[1, 2, 3]).is_none());
+
+        cache.store(1, None, vec![1, 2, 3], "state");
+
+        let hit = cache.lookup(1, None, &[1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(hit.state, "state");
+        assert_eq!(hit.cached_tokens, 3);
+
+        // Other conversations are unaffected
+        assert!(cache.lookup(2, None, &[1, 2, 3, 4, 5]).is_none());
+    }
+
+    #[test]
+    fn test_diverging_history_invalidates() {
+        let mut cache = SessionCache::new();
+        cache.store(1, None, vec![1, 2, 3], "state");
+
+        assert!(cache.lookup(1, None, &[1, 9, 3, 4]).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_identical_prompt_is_not_reused() {
+        // At least one token has to be processed to get logits
+        let mut cache = SessionCache::new();
+        cache.store(1, None, vec![1, 2, 3], "state");
+
+        assert!(cache.lookup(1, None, &[1, 2, 3]).is_none());
+    }
+
+    #[test]
+    fn test_system_prompt_change_invalidates() {
+        let mut cache = SessionCache::new();
+        cache.store(1, Some("You are a lawyer"), vec![1, 2, 3], "state");
+
+        assert!(cache.lookup(1, Some("You are an auditor"), &[1, 2, 3, 4]).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_model_change_invalidates_all_sessions() {
+        let mut cache = SessionCache::new();
+        assert!(!cache.bind_model("fast"));
+        cache.store(1, None, vec![1, 2, 3], "fast state");
+        cache.store(2, None, vec![4, 5], "fast state");
+
+        // Binding the same model again keeps the sessions
+        assert!(!cache.bind_model("fast"));
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.bind_model("accurate"));
+        assert!(cache.is_empty());
+        assert!(cache.lookup(1, None, &[1, 2, 3, 4]).is_none());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = SessionCache::with_limits(2, SESSION_IDLE_TIMEOUT);
+        cache.store(1, None, vec![1], "one");
+        cache.store(2, None, vec![2], "two");
+
+        // Touch 1 so 2 becomes the least recently used
+        assert!(cache.lookup(1, None, &[1, 10]).is_some());
+        cache.store(3, None, vec![3], "three");
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup(2, None, &[2, 20]).is_none());
+        assert!(cache.lookup(1, None, &[1, 10]).is_some());
+        assert!(cache.lookup(3, None, &[3, 30]).is_some());
+    }
+
+    #[test]
+    fn test_evicts_idle_sessions() {
+        let mut cache = SessionCache::with_limits(4, Duration::ZERO);
+        cache.store(1, None, vec![1], "one");
+
+        assert!(cache.lookup(1, None, &[1, 10]).is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -6,6 +6,11 @@
 
 pub mod types;
 pub mod inference;
+pub mod gguf_tokenizer;
+pub mod kv_cache;
+pub mod chat_template;
 
 pub use types::*;
-pub use inference::InferenceEngine;
+pub use inference::{EngineStatusHandle, InferenceEngine};
+#[allow(unused_imports)]
+pub use chat_template::ChatTemplate;
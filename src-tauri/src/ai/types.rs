@@ -44,14 +44,31 @@ impl Default for ModelConfig {
     }
 }
 
+/// Repetition penalty window used by llama.cpp, in tokens
+pub const DEFAULT_REPEAT_LAST_N: usize = 64;
+
+/// Identical lines in a row after which generation is stopped as a loop
+pub const DEFAULT_MAX_REPEATED_LINES: usize = 6;
+
 /// Generation parameters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GenerationConfig {
     pub temperature: f64,
     pub top_p: f64,
     pub top_k: usize,
     pub max_new_tokens: usize,
+    /// Penalty for tokens seen recently; 1.0 disables it
     pub repetition_penalty: f64,
+    /// How many of the most recent tokens (prompt included) the repetition
+    /// penalty looks at, as llama.cpp's `repeat_last_n`; 0 disables it.
+    /// A short window stops loops without penalizing terms a legal text
+    /// legitimately repeats, such as "the Agreement".
+    pub repeat_last_n: usize,
+    /// Stop with `FinishReason::Loop` once this many non-blank lines in a
+    /// row are identical; 0 disables it. A last resort for quantized models
+    /// that repeat a whole line despite the repetition penalty.
+    pub max_repeated_lines: usize,
     pub do_sample: bool,
     pub seed: Option<u64>,
 }
@@ -64,12 +81,78 @@ impl Default for GenerationConfig {
             top_k: 50,
             max_new_tokens: 2048,
             repetition_penalty: 1.1,
+            repeat_last_n: DEFAULT_REPEAT_LAST_N,
+            max_repeated_lines: DEFAULT_MAX_REPEATED_LINES,
             do_sample: true,
             seed: None,
         }
     }
 }
 
+/// Generation settings recommended by a prompt or template
+///
+/// Only the fields that are set replace the model's defaults, e.g. an
+/// extraction prompt can ask for `temperature: 0` and keep everything else.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_new_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repetition_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_last_n: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_repeated_lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub do_sample: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl GenerationOverrides {
+    /// Whether no setting is overridden
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Replace the fields of `config` that are set here
+    pub fn apply_to(&self, config: &mut GenerationConfig) {
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(top_p) = self.top_p {
+            config.top_p = top_p;
+        }
+        if let Some(top_k) = self.top_k {
+            config.top_k = top_k;
+        }
+        if let Some(max_new_tokens) = self.max_new_tokens {
+            config.max_new_tokens = max_new_tokens;
+        }
+        if let Some(repetition_penalty) = self.repetition_penalty {
+            config.repetition_penalty = repetition_penalty;
+        }
+        if let Some(repeat_last_n) = self.repeat_last_n {
+            config.repeat_last_n = repeat_last_n;
+        }
+        if let Some(max_repeated_lines) = self.max_repeated_lines {
+            config.max_repeated_lines = max_repeated_lines;
+        }
+        if let Some(do_sample) = self.do_sample {
+            config.do_sample = do_sample;
+        }
+        if self.seed.is_some() {
+            config.seed = self.seed;
+        }
+    }
+}
+
 /// Chat message format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -83,6 +166,9 @@ pub struct GenerateRequest {
     pub messages: Vec<ChatMessage>,
     pub config: GenerationConfig,
     pub system_prompt: Option<String>,
+    /// Conversation whose KV cache may be reused between turns
+    #[serde(default)]
+    pub conversation_id: Option<i32>,
 }
 
 /// Streaming token response
@@ -95,6 +181,18 @@ pub struct TokenResponse {
     pub generation_time_ms: u64,
 }
 
+/// Why generation stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinishReason {
+    /// The model produced an end-of-sequence token
+    Stop,
+    /// The `max_new_tokens` cap was reached
+    Length,
+    /// The same line was generated `max_repeated_lines` times in a row
+    Loop,
+}
+
 /// Complete generation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationResult {
@@ -102,9 +200,45 @@ pub struct GenerationResult {
     pub tokens: Vec<u32>,
     pub total_tokens: usize,
     pub prompt_tokens: usize,
+    /// Prompt tokens served from the conversation's KV cache
+    #[serde(default)]
+    pub cached_prompt_tokens: usize,
     pub generated_tokens: usize,
     pub generation_time_ms: u64,
+    /// Generated tokens over the whole generation time, prompt processing included
     pub tokens_per_second: f64,
+    /// Time from the start of the request to the first generated token;
+    /// `None` when no token was generated
+    #[serde(default)]
+    pub first_token_ms: Option<u64>,
+    /// Tokens per second after the first token, i.e. the steady decode speed
+    #[serde(default)]
+    pub decode_tokens_per_second: f64,
+    pub finish_reason: FinishReason,
+}
+
+/// Progress of tokenizing a large prompt, reported once per chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenizeProgress {
+    pub processed_bytes: usize,
+    pub total_bytes: usize,
+    /// Tokens produced so far
+    pub tokens: usize,
+}
+
+/// Throughput of the loaded model on this machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub prompt_tokens: usize,
+    pub generated_tokens: usize,
+    pub prompt_eval_ms: f64,
+    pub generation_ms: f64,
+    /// Prompt tokens processed per second
+    pub prompt_tokens_per_second: f64,
+    /// Tokens generated per second, after the prompt
+    pub generation_tokens_per_second: f64,
+    /// Peak resident memory of the process, where the OS reports it
+    pub peak_memory_bytes: Option<u64>,
 }
 
 /// Model loading status
@@ -116,6 +250,18 @@ pub enum ModelStatus {
     Error(String),
 }
 
+impl ModelStatus {
+    /// Status name as reported to the frontend
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotLoaded => "not_loaded",
+            Self::Loading => "loading",
+            Self::Loaded => "loaded",
+            Self::Error(_) => "error",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,4 +279,11 @@ mod tests {
         assert_eq!(config.temperature, 0.7);
         assert_eq!(config.max_new_tokens, 2048);
     }
+
+    #[test]
+    fn test_finish_reason_serialization() {
+        assert_eq!(serde_json::to_string(&FinishReason::Length).unwrap(), "\"length\"");
+        assert_eq!(serde_json::to_string(&FinishReason::Stop).unwrap(), "\"stop\"");
+        assert_eq!(serde_json::to_string(&FinishReason::Loop).unwrap(), "\"loop\"");
+    }
 }
@@ -0,0 +1,558 @@
+use crate::database::DatabaseManager;
+use crate::services::audit;
+use entity::cases;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Language assumed for cases created without one
+pub const DEFAULT_CASE_LANGUAGE: &str = "en";
+
+/// Jurisdiction recorded for cases created without one
+pub const UNSPECIFIED_JURISDICTION: &str = "unspecified";
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 200;
+
+/// Request to create a case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCaseRequest {
+    pub name: String,
+    pub client_name: String,
+    pub case_number: Option<String>,
+    pub description: Option<String>,
+    /// Language code, e.g. "en", "de"
+    pub language: Option<String>,
+    /// Jurisdiction code, e.g. "us-ny", "de", "eu"
+    pub jurisdiction: Option<String>,
+}
+
+/// Filters and pagination for listing cases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListCasesRequest {
+    pub jurisdiction: Option<String>,
+    pub language: Option<String>,
+    /// Zero-based page index
+    #[serde(default)]
+    pub page: u64,
+    pub page_size: Option<u64>,
+}
+
+/// Case info for frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResponse {
+    pub id: i32,
+    pub name: String,
+    pub client_name: String,
+    pub case_number: Option<String>,
+    pub description: Option<String>,
+    pub status: String,
+    pub language: String,
+    pub jurisdiction: String,
+    /// Deletion is refused while the case is under legal hold
+    pub legal_hold: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<cases::Model> for CaseResponse {
+    fn from(case: cases::Model) -> Self {
+        Self {
+            id: case.id,
+            name: case.name,
+            client_name: case.client_name,
+            case_number: case.case_number,
+            description: case.description,
+            status: case.status,
+            language: case.language,
+            jurisdiction: case.jurisdiction,
+            legal_hold: case.legal_hold,
+            created_at: case.created_at.to_string(),
+            updated_at: case.updated_at.to_string(),
+        }
+    }
+}
+
+/// One page of cases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CasePage {
+    pub cases: Vec<CaseResponse>,
+    /// Number of cases matching the filters, across all pages
+    pub total: u64,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// Codes are stored lowercase so filters match regardless of how they were typed
+fn normalize_code(value: Option<&str>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+}
+
+/// Insert a case, filling in the default language and jurisdiction
+pub async fn insert_case(
+    conn: &DatabaseConnection,
+    request: CreateCaseRequest,
+) -> Result<cases::Model, String> {
+    if request.name.trim().is_empty() {
+        return Err("Case name must not be empty".to_string());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let case = cases::ActiveModel {
+        name: Set(request.name),
+        client_name: Set(request.client_name),
+        case_number: Set(request.case_number),
+        description: Set(request.description),
+        status: Set("active".to_string()),
+        language: Set(normalize_code(request.language.as_deref())
+            .unwrap_or_else(|| DEFAULT_CASE_LANGUAGE.to_string())),
+        jurisdiction: Set(normalize_code(request.jurisdiction.as_deref())
+            .unwrap_or_else(|| UNSPECIFIED_JURISDICTION.to_string())),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    case.insert(conn)
+        .await
+        .map_err(|e| format!("Failed to create case: {}", e))
+}
+
+/// Query one page of cases, most recently updated first
+pub async fn query_cases(
+    conn: &DatabaseConnection,
+    request: &ListCasesRequest,
+) -> Result<CasePage, String> {
+    let page_size = request
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut query = cases::Entity::find();
+    if let Some(jurisdiction) = normalize_code(request.jurisdiction.as_deref()) {
+        query = query.filter(cases::Column::Jurisdiction.eq(jurisdiction));
+    }
+    if let Some(language) = normalize_code(request.language.as_deref()) {
+        query = query.filter(cases::Column::Language.eq(language));
+    }
+
+    let paginator = query
+        .order_by_desc(cases::Column::UpdatedAt)
+        .order_by_desc(cases::Column::Id)
+        .paginate(conn, page_size);
+
+    let total = paginator
+        .num_items()
+        .await
+        .map_err(|e| format!("Failed to count cases: {}", e))?;
+    let cases = paginator
+        .fetch_page(request.page)
+        .await
+        .map_err(|e| format!("Failed to list cases: {}", e))?;
+
+    Ok(CasePage {
+        cases: cases.into_iter().map(CaseResponse::from).collect(),
+        total,
+        page: request.page,
+        page_size,
+    })
+}
+
+/// Status of a case removed with a soft delete
+pub const DELETED_STATUS: &str = "deleted";
+
+async fn find_case(conn: &DatabaseConnection, case_id: i32) -> Result<cases::Model, String> {
+    cases::Entity::find_by_id(case_id)
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Case {} not found", case_id))
+}
+
+/// Refuse to go on if the case is under legal hold
+pub async fn ensure_not_on_hold(conn: &DatabaseConnection, case_id: i32) -> Result<cases::Model, String> {
+    let case = find_case(conn, case_id).await?;
+    if case.legal_hold {
+        return Err(format!(
+            "Case '{}' is under legal hold and cannot be deleted; release the hold first",
+            case.name
+        ));
+    }
+    Ok(case)
+}
+
+/// Place or release a legal hold, recording the reason in the audit log
+pub async fn update_legal_hold(
+    conn: &DatabaseConnection,
+    case_id: i32,
+    hold: bool,
+    reason: &str,
+) -> Result<cases::Model, String> {
+    if reason.trim().is_empty() {
+        return Err("A reason is required to change a legal hold".to_string());
+    }
+
+    let case = find_case(conn, case_id).await?;
+    let mut active: cases::ActiveModel = case.into();
+    active.legal_hold = Set(hold);
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+    let case = active
+        .update(conn)
+        .await
+        .map_err(|e| format!("Failed to update case: {}", e))?;
+
+    let action = if hold { "legal_hold_placed" } else { "legal_hold_released" };
+    audit::record(conn, action, Some(case_id), serde_json::json!({ "reason": reason.trim() }))
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+
+    Ok(case)
+}
+
+/// Delete a case, or mark it deleted when `permanent` is false
+///
+/// Refused while the case is under legal hold.
+pub async fn remove_case(
+    conn: &DatabaseConnection,
+    case_id: i32,
+    permanent: bool,
+) -> Result<(), String> {
+    let case = ensure_not_on_hold(conn, case_id).await?;
+    let name = case.name.clone();
+
+    if permanent {
+        cases::Entity::delete_by_id(case_id)
+            .exec(conn)
+            .await
+            .map_err(|e| format!("Failed to delete case: {}", e))?;
+    } else {
+        let mut active: cases::ActiveModel = case.into();
+        active.status = Set(DELETED_STATUS.to_string());
+        active.updated_at = Set(chrono::Utc::now().naive_utc());
+        active
+            .update(conn)
+            .await
+            .map_err(|e| format!("Failed to delete case: {}", e))?;
+    }
+
+    audit::record(
+        conn,
+        "case_deleted",
+        Some(case_id),
+        serde_json::json!({ "name": name, "permanent": permanent }),
+    )
+    .await
+    .map_err(|e| format!("{:#}", e))?;
+    Ok(())
+}
+
+/// Create a new case
+#[tauri::command]
+pub async fn create_case(
+    request: CreateCaseRequest,
+    db: State<'_, DatabaseManager>,
+) -> Result<CaseResponse, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    insert_case(&conn, request).await.map(CaseResponse::from)
+}
+
+/// List cases, optionally filtered by jurisdiction and language
+#[tauri::command]
+pub async fn list_cases(
+    request: Option<ListCasesRequest>,
+    db: State<'_, DatabaseManager>,
+) -> Result<CasePage, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    query_cases(&conn, &request.unwrap_or_default()).await
+}
+
+/// Delete a case; `permanent: false` only marks it deleted
+#[tauri::command]
+pub async fn delete_case(
+    case_id: i32,
+    permanent: bool,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    remove_case(&conn, case_id, permanent).await
+}
+
+/// Put a case under legal hold so it and its audit trail can't be deleted
+#[tauri::command]
+pub async fn place_legal_hold(
+    case_id: i32,
+    reason: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<CaseResponse, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    update_legal_hold(&conn, case_id, true, &reason)
+        .await
+        .map(CaseResponse::from)
+}
+
+/// Release a legal hold; the reason is kept in the audit log
+#[tauri::command]
+pub async fn release_legal_hold(
+    case_id: i32,
+    reason: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<CaseResponse, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    update_legal_hold(&conn, case_id, false, &reason)
+        .await
+        .map(CaseResponse::from)
+}
+
+/// Delete audit log entries older than `older_than_days`, except those of
+/// cases under legal hold; returns how many were removed
+#[tauri::command]
+pub async fn prune_audit_log(
+    older_than_days: u32,
+    db: State<'_, DatabaseManager>,
+) -> Result<u64, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let before = chrono::Utc::now().naive_utc() - chrono::Duration::days(i64::from(older_than_days));
+    audit::prune(&conn, before)
+        .await
+        .map_err(|e| format!("{:#}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::ConnectionTrait;
+    use sea_orm_migration::MigratorTrait;
+
+    async fn test_connection() -> (tempfile::TempDir, DatabaseConnection) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+        (dir, conn)
+    }
+
+    fn case(name: &str, jurisdiction: Option<&str>, language: Option<&str>) -> CreateCaseRequest {
+        CreateCaseRequest {
+            name: name.to_string(),
+            client_name: "Client".to_string(),
+            case_number: None,
+            description: None,
+            language: language.map(str::to_string),
+            jurisdiction: jurisdiction.map(str::to_string),
+        }
+    }
+
+    fn names(page: &CasePage) -> Vec<&str> {
+        let mut names: Vec<&str> = page.cases.iter().map(|c| c.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_jurisdiction_and_language() {
+        let (_dir, conn) = test_connection().await;
+
+        insert_case(&conn, case("NY lease", Some("US-NY"), Some("en"))).await.unwrap();
+        insert_case(&conn, case("NY merger", Some("us-ny"), Some("en"))).await.unwrap();
+        insert_case(&conn, case("Berlin GmbH", Some("de"), Some("de"))).await.unwrap();
+        insert_case(&conn, case("Munich contract", Some("de"), Some("en"))).await.unwrap();
+        insert_case(&conn, case("Draft", None, None)).await.unwrap();
+
+        let request = ListCasesRequest {
+            jurisdiction: Some("us-ny".to_string()),
+            ..Default::default()
+        };
+        let page = query_cases(&conn, &request).await.unwrap();
+        assert_eq!(names(&page), vec!["NY lease", "NY merger"]);
+        assert_eq!(page.total, 2);
+
+        let request = ListCasesRequest {
+            jurisdiction: Some("DE".to_string()),
+            language: Some("de".to_string()),
+            ..Default::default()
+        };
+        let page = query_cases(&conn, &request).await.unwrap();
+        assert_eq!(names(&page), vec!["Berlin GmbH"]);
+
+        let request = ListCasesRequest {
+            language: Some("en".to_string()),
+            ..Default::default()
+        };
+        let page = query_cases(&conn, &request).await.unwrap();
+        assert_eq!(names(&page), vec!["Draft", "Munich contract", "NY lease", "NY merger"]);
+
+        let page = query_cases(&conn, &ListCasesRequest::default()).await.unwrap();
+        assert_eq!(page.total, 5);
+        let draft = page.cases.iter().find(|c| c.name == "Draft").unwrap();
+        assert_eq!(draft.jurisdiction, UNSPECIFIED_JURISDICTION);
+        assert_eq!(draft.language, DEFAULT_CASE_LANGUAGE);
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let (_dir, conn) = test_connection().await;
+        for i in 0..5 {
+            insert_case(&conn, case(&format!("Case {}", i), Some("eu"), None))
+                .await
+                .unwrap();
+        }
+
+        let mut request = ListCasesRequest {
+            jurisdiction: Some("eu".to_string()),
+            page_size: Some(2),
+            ..Default::default()
+        };
+
+        let mut seen = Vec::new();
+        for page_index in 0..3 {
+            request.page = page_index;
+            let page = query_cases(&conn, &request).await.unwrap();
+            assert_eq!(page.total, 5);
+            assert_eq!(page.page_size, 2);
+            seen.extend(page.cases.into_iter().map(|c| c.id));
+        }
+
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_migration_defaults_existing_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("old.db").display());
+        let conn = sea_orm::Database::connect(&url).await.unwrap();
+
+        // Schema as it was before the jurisdiction fields existed
+        use crate::database::migration::Migrator;
+        let before_jurisdiction = Migrator::migrations()
+            .iter()
+            .position(|m| m.name() == "m20250108_000009_add_case_jurisdiction_fields")
+            .unwrap() as u32;
+        Migrator::up(&conn, Some(before_jurisdiction)).await.unwrap();
+        conn.execute_unprepared(
+            "INSERT INTO cases (name, client_name, status, created_at, updated_at) \
+             VALUES ('Legacy', 'Client', 'active', '2025-01-01 00:00:00', '2025-01-01 00:00:00')",
+        )
+        .await
+        .unwrap();
+
+        crate::database::migration::Migrator::up(&conn, None).await.unwrap();
+
+        let page = query_cases(&conn, &ListCasesRequest::default()).await.unwrap();
+        assert_eq!(page.cases.len(), 1);
+        assert_eq!(page.cases[0].language, DEFAULT_CASE_LANGUAGE);
+        assert_eq!(page.cases[0].jurisdiction, UNSPECIFIED_JURISDICTION);
+
+        // Rolling back drops the columns again and keeps the case
+        let applied = Migrator::migrations().len() as u32;
+        Migrator::down(&conn, Some(applied - before_jurisdiction)).await.unwrap();
+        let legacy = conn
+            .query_one(sea_orm::Statement::from_string(
+                conn.get_database_backend(),
+                "SELECT name FROM cases".to_string(),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(legacy.try_get::<String>("", "name").unwrap(), "Legacy");
+    }
+
+    #[tokio::test]
+    async fn test_legal_hold_blocks_deletion_until_released() {
+        let (_dir, conn) = test_connection().await;
+        let held = insert_case(&conn, case("Merger review", None, None)).await.unwrap();
+
+        assert!(update_legal_hold(&conn, held.id, true, "  ").await.is_err());
+        let updated = update_legal_hold(&conn, held.id, true, "Litigation expected")
+            .await
+            .unwrap();
+        assert!(updated.legal_hold);
+
+        for permanent in [false, true] {
+            let err = remove_case(&conn, held.id, permanent).await.unwrap_err();
+            assert!(err.contains("under legal hold"), "{}", err);
+        }
+        assert_eq!(find_case(&conn, held.id).await.unwrap().status, "active");
+
+        update_legal_hold(&conn, held.id, false, "Matter settled").await.unwrap();
+        remove_case(&conn, held.id, false).await.unwrap();
+        assert_eq!(find_case(&conn, held.id).await.unwrap().status, DELETED_STATUS);
+        remove_case(&conn, held.id, true).await.unwrap();
+        assert!(find_case(&conn, held.id).await.is_err());
+
+        // Placing and releasing the hold were recorded with their reasons
+        let entries = entity::audit_logs::Entity::find()
+            .filter(entity::audit_logs::Column::CaseId.eq(held.id))
+            .order_by_asc(entity::audit_logs::Column::Id)
+            .all(&conn)
+            .await
+            .unwrap();
+        let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            vec!["legal_hold_placed", "legal_hold_released", "case_deleted", "case_deleted"]
+        );
+        assert_eq!(entries[0].details.as_ref().unwrap()["reason"], "Litigation expected");
+    }
+
+    #[tokio::test]
+    async fn test_audit_pruning_keeps_held_cases() {
+        let (_dir, conn) = test_connection().await;
+        let held = insert_case(&conn, case("Held", None, None)).await.unwrap();
+        let other = insert_case(&conn, case("Other", None, None)).await.unwrap();
+
+        update_legal_hold(&conn, held.id, true, "Regulator request").await.unwrap();
+        audit::record(&conn, "document_viewed", Some(held.id), serde_json::json!({}))
+            .await
+            .unwrap();
+        audit::record(&conn, "document_viewed", Some(other.id), serde_json::json!({}))
+            .await
+            .unwrap();
+        audit::record(&conn, "settings_changed", None, serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        assert_eq!(audit::prune(&conn, later).await.unwrap(), 2);
+        let remaining = entity::audit_logs::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|e| e.case_id == Some(held.id)));
+
+        // Once released, the held case's entries can be pruned too
+        update_legal_hold(&conn, held.id, false, "Closed").await.unwrap();
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        assert_eq!(audit::prune(&conn, later).await.unwrap(), 3);
+    }
+}
@@ -1,13 +1,27 @@
 use crate::ai::{
-    ChatMessage, GenerateRequest, GenerationConfig, GenerationResult, InferenceEngine,
-    ModelConfig, ModelStatus,
+    BenchmarkResult, ChatMessage, GenerateRequest, GenerationConfig, GenerationOverrides,
+    GenerationResult, InferenceEngine, ModelConfig, TokenizeProgress,
 };
+use crate::commands::settings::{read_setting, write_setting};
 use crate::database::DatabaseManager;
+use crate::models::ModelRegistry;
+use crate::prompts::PromptLibrary;
+use crate::templates::TemplateLibrary;
 use anyhow::Result;
+use entity::{conversations, messages, models};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::{Emitter, State};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
+
+/// Cancel flag of the running streamed generation, if any
+///
+/// A newtype, since Tauri keeps one managed state per type.
+#[derive(Clone, Default)]
+pub struct GenerationCancelState(pub Arc<Mutex<Option<Arc<RwLock<bool>>>>>);
 
 /// Request to load AI model for inference
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +37,95 @@ pub struct GenerateTextRequest {
     pub system_prompt: Option<String>,
     pub temperature: Option<f64>,
     pub max_tokens: Option<usize>,
+    /// Library prompt in use, whose recommended generation settings apply
+    #[serde(default)]
+    pub prompt_id: Option<String>,
+    /// Document template being drafted, whose generation settings apply
+    /// over the prompt's
+    #[serde(default)]
+    pub template_id: Option<String>,
+    /// Without a loaded model, fail with `ModelGuidance` instead of a plain message
+    #[serde(default)]
+    pub model_guidance: bool,
+}
+
+/// How far the user is from being able to generate locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelReadiness {
+    /// No model has been downloaded yet
+    NotDownloaded,
+    /// A model is downloaded but none is loaded
+    DownloadedInactive,
+    Loaded,
+}
+
+/// Next step for a user who asked for a response without a loaded model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelGuidance {
+    pub state: ModelReadiness,
+    /// Downloaded models that can be loaded right away
+    pub downloaded_models: Vec<String>,
+    /// Model the registry recommends, to download or load
+    pub recommended_model_id: Option<String>,
+    pub message: String,
+}
+
+/// Why a generation command failed
+///
+/// Serialized untagged, so ordinary failures still reach the frontend as a
+/// plain string and only the guidance is an object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GenerateError {
+    NoModel(ModelGuidance),
+    Failed(String),
+}
+
+impl From<String> for GenerateError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+/// A conversation turn as stored in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: i32,
+    pub role: String,
+    pub content: String,
+    pub is_ai_generated: bool,
+    pub model_name: Option<String>,
+    /// False for an assistant turn that was cut short (error, crash or restart)
+    pub is_complete: bool,
+    pub created_at: String,
+}
+
+impl From<messages::Model> for StoredMessage {
+    fn from(message: messages::Model) -> Self {
+        Self {
+            id: message.id,
+            role: message.role,
+            content: message.content,
+            is_ai_generated: message.is_ai_generated,
+            model_name: message.model_name,
+            is_complete: message.is_complete,
+            created_at: message.created_at.to_string(),
+        }
+    }
+}
+
+/// A conversation reloaded from the database, ready for its next turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumedConversation {
+    pub conversation_id: i32,
+    pub title: String,
+    /// Summary written by `summarize_conversation`, if any
+    pub summary: Option<String>,
+    /// Stored turns in the order they were written
+    pub messages: Vec<StoredMessage>,
+    /// History tokens restored into the model's cache (0 when no model is loaded)
+    pub primed_tokens: usize,
 }
 
 /// Load AI model for inference
@@ -30,6 +133,7 @@ pub struct GenerateTextRequest {
 pub async fn load_ai_model(
     request: LoadModelRequest,
     inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<String, String> {
     let engine = inference_engine.lock().await;
 
@@ -45,16 +149,76 @@ pub async fn load_ai_model(
     }
 
     // Create model config (simplified - would load from config.json)
-    let config = ModelConfig::default();
+    let config = ModelConfig {
+        model_id: request.model_id.clone(),
+        ..ModelConfig::default()
+    };
 
     engine
         .load_model(app_dir, config)
         .await
         .map_err(|e| format!("Failed to load model: {}", e))?;
 
+    if let Some(conn) = db.get_connection().await {
+        apply_generation_config(&engine, &conn, &request.model_id).await;
+    }
+
     Ok(format!("Model loaded: {}", request.model_id))
 }
 
+/// Warm up the loaded model with a tiny generation
+///
+/// Meant to run right after `load_ai_model`, so the user's first message
+/// isn't slowed down by one-off kernel compilation. Returns the time taken
+/// in milliseconds.
+#[tauri::command]
+pub async fn warm_up_ai_model(
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+) -> Result<u64, String> {
+    let engine = inference_engine.lock().await;
+    engine
+        .warm_up()
+        .await
+        .map_err(|e| format!("Failed to warm up model: {}", e))
+}
+
+/// Measure the loaded model's prompt and generation speed on this machine
+///
+/// Processes a `prompt_tokens` prompt and generates `gen_tokens` tokens,
+/// so users can judge a model before starting a large job.
+#[tauri::command]
+pub async fn benchmark_model(
+    prompt_tokens: usize,
+    gen_tokens: usize,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+) -> Result<BenchmarkResult, String> {
+    let engine = inference_engine.lock().await;
+    if !engine.is_loaded().await {
+        return Err("No model loaded. Load a model before benchmarking.".to_string());
+    }
+    engine
+        .benchmark(prompt_tokens, gen_tokens)
+        .await
+        .map_err(|e| format!("Benchmark failed: {}", e))
+}
+
+/// Show the exact prompt the loaded model would receive, without generating
+///
+/// Renders the messages with the chat template of the model's family, to
+/// diagnose odd output caused by prompt formatting.
+#[tauri::command]
+pub async fn render_prompt(
+    messages: Vec<ChatMessage>,
+    system_prompt: Option<String>,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+) -> Result<String, String> {
+    let engine = inference_engine.lock().await;
+    engine
+        .render_prompt(&messages, system_prompt.as_deref())
+        .await
+        .map_err(|e| format!("Failed to render prompt: {}", e))
+}
+
 /// Unload current AI model
 #[tauri::command]
 pub async fn unload_ai_model(
@@ -73,14 +237,7 @@ pub async fn get_ai_model_status(
     let engine = inference_engine.lock().await;
     let status = engine.get_status().await;
 
-    let status_str = match status {
-        ModelStatus::NotLoaded => "not_loaded",
-        ModelStatus::Loading => "loading",
-        ModelStatus::Loaded => "loaded",
-        ModelStatus::Error(_) => "error",
-    };
-
-    Ok(status_str.to_string())
+    Ok(status.as_str().to_string())
 }
 
 /// Get device information (CPU, CUDA, Metal)
@@ -98,40 +255,46 @@ pub async fn get_device_info(
 pub async fn generate_ai_response(
     request: GenerateTextRequest,
     inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
-    _db: State<'_, DatabaseManager>,
-) -> Result<GenerationResult, String> {
+    db: State<'_, DatabaseManager>,
+    prompt_library: State<'_, Arc<Mutex<PromptLibrary>>>,
+    template_library: State<'_, Arc<Mutex<TemplateLibrary>>>,
+) -> Result<GenerationResult, GenerateError> {
     let engine = inference_engine.lock().await;
 
     // Check if model is loaded
     if !engine.is_loaded().await {
-        return Err("No AI model loaded. Please load a model first.".to_string());
+        return Err(no_model_error(&request, &db).await);
     }
 
-    // Build generation config
-    let mut config = GenerationConfig::default();
-    if let Some(temp) = request.temperature {
-        config.temperature = temp;
-    }
-    if let Some(max) = request.max_tokens {
-        config.max_new_tokens = max;
-    }
+    // Build generation config on top of the active model's saved defaults
+    let config = generation_config(&engine, &request, &prompt_library, &template_library).await?;
 
     // Create generation request
     let gen_request = GenerateRequest {
         messages: request.messages.clone(),
         config,
         system_prompt: request.system_prompt.clone(),
+        conversation_id: request.conversation_id,
     };
 
+    let conn = db.get_connection().await;
+    if let (Some(conn), Some(conversation_id)) = (conn.as_ref(), request.conversation_id) {
+        restart_session_on_model_switch(&engine, conn, conversation_id).await;
+    }
+    let pending_turn = begin_turn_if_tracked(conn.as_ref(), &request).await?;
+
     // Generate response
-    let result = engine
-        .generate(gen_request)
-        .await
-        .map_err(|e| format!("Generation failed: {}", e))?;
+    let result = engine.generate(gen_request).await;
 
-    // TODO: Store conversation in database if conversation_id is provided
+    if let (Some(conn), Some(message_id)) = (conn.as_ref(), pending_turn) {
+        let (content, is_complete) = match &result {
+            Ok(result) => (result.text.clone(), true),
+            Err(_) => (String::new(), false),
+        };
+        finish_turn_logged(conn, message_id, content, is_complete).await;
+    }
 
-    Ok(result)
+    result.map_err(|e| format!("Generation failed: {}", e).into())
 }
 
 /// Generate AI response with streaming
@@ -139,50 +302,690 @@ pub async fn generate_ai_response(
 pub async fn generate_ai_response_stream(
     request: GenerateTextRequest,
     inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+    db: State<'_, DatabaseManager>,
+    prompt_library: State<'_, Arc<Mutex<PromptLibrary>>>,
+    template_library: State<'_, Arc<Mutex<TemplateLibrary>>>,
+    cancel_state: State<'_, GenerationCancelState>,
     window: tauri::Window,
-) -> Result<String, String> {
+) -> Result<String, GenerateError> {
     let engine = inference_engine.lock().await;
 
     // Check if model is loaded
     if !engine.is_loaded().await {
-        return Err("No AI model loaded. Please load a model first.".to_string());
+        return Err(no_model_error(&request, &db).await);
     }
 
-    // Build generation config
-    let mut config = GenerationConfig::default();
-    if let Some(temp) = request.temperature {
-        config.temperature = temp;
-    }
-    if let Some(max) = request.max_tokens {
-        config.max_new_tokens = max;
-    }
+    // Build generation config on top of the active model's saved defaults
+    let config = generation_config(&engine, &request, &prompt_library, &template_library).await?;
 
     // Create generation request
     let gen_request = GenerateRequest {
         messages: request.messages.clone(),
         config,
         system_prompt: request.system_prompt.clone(),
+        conversation_id: request.conversation_id,
     };
 
-    // Generate with streaming
+    let conn = db.get_connection().await;
+    if let (Some(conn), Some(conversation_id)) = (conn.as_ref(), request.conversation_id) {
+        restart_session_on_model_switch(&engine, conn, conversation_id).await;
+    }
+    let pending_turn = begin_turn_if_tracked(conn.as_ref(), &request).await?;
+
+    // The engine lock already serializes generations; the guard clears the
+    // flag however this command ends
+    let cancel_guard = CancelRegistration::register(cancel_state.inner()).await;
+    let cancel_flag = cancel_guard.flag.clone();
+
+    // Generate with streaming, keeping the text so far in case generation fails
     let conversation_id = request.conversation_id;
+    let mut partial_text = String::new();
+    let partial = &mut partial_text;
+    let progress_window = window.clone();
+    let on_tokenize = move |progress: TokenizeProgress| {
+        let _ = progress_window.emit(
+            "ai-tokenize-progress",
+            serde_json::json!({
+                "conversation_id": conversation_id,
+                "processed_bytes": progress.processed_bytes,
+                "total_bytes": progress.total_bytes,
+                "tokens": progress.tokens,
+            }),
+        );
+    };
     let result = engine
-        .generate_stream(gen_request, move |token_response| {
-            // Emit token to frontend
-            let _ = window.emit(
-                "ai-token",
-                serde_json::json!({
-                    "conversation_id": conversation_id,
-                    "token": token_response.token,
-                    "is_final": token_response.is_final,
-                    "total_tokens": token_response.total_tokens,
-                }),
-            );
+        .generate_stream_with(
+            gen_request,
+            Some(&cancel_flag),
+            on_tokenize,
+            move |token_response| {
+                partial.push_str(&token_response.token);
+
+                // Emit token to frontend
+                let _ = window.emit(
+                    "ai-token",
+                    serde_json::json!({
+                        "conversation_id": conversation_id,
+                        "token": token_response.token,
+                        "is_final": token_response.is_final,
+                        "total_tokens": token_response.total_tokens,
+                    }),
+                );
+            },
+        )
+        .await;
+    drop(cancel_guard);
+
+    if let (Some(conn), Some(message_id)) = (conn.as_ref(), pending_turn) {
+        let (content, is_complete) = match &result {
+            Ok(result) => (result.text.clone(), true),
+            Err(_) => (partial_text, false),
+        };
+        finish_turn_logged(conn, message_id, content, is_complete).await;
+    }
+
+    result
+        .map(|result| result.text)
+        .map_err(|e| format!("Generation failed: {}", e).into())
+}
+
+/// Cancel flag of a streamed generation, published in the
+/// `GenerationCancelState` until dropped
+struct CancelRegistration {
+    state: GenerationCancelState,
+    flag: Arc<RwLock<bool>>,
+}
+
+impl CancelRegistration {
+    async fn register(state: &GenerationCancelState) -> Self {
+        let flag = Arc::new(RwLock::new(false));
+        *state.0.lock().await = Some(flag.clone());
+        Self {
+            state: state.clone(),
+            flag,
+        }
+    }
+}
+
+impl Drop for CancelRegistration {
+    fn drop(&mut self) {
+        // A newer generation's flag is left alone
+        let flag = self.flag.clone();
+        let clear = move |state: &mut Option<Arc<RwLock<bool>>>| {
+            if state.as_ref().is_some_and(|current| Arc::ptr_eq(current, &flag)) {
+                *state = None;
+            }
+        };
+        match self.state.0.try_lock() {
+            Ok(mut state) => clear(&mut state),
+            Err(_) => {
+                // `cancel_generation` holds the lock for a moment
+                let state = self.state.0.clone();
+                tokio::spawn(async move { clear(&mut *state.lock().await) });
+            }
+        }
+    }
+}
+
+/// Cancel the running streamed generation
+///
+/// Takes effect while a large prompt is being tokenized or prefilled, before
+/// the first token is generated.
+#[tauri::command]
+pub async fn cancel_generation(
+    cancel_state: State<'_, GenerationCancelState>,
+) -> Result<String, String> {
+    let state = cancel_state.0.lock().await;
+    let cancel_flag = state.as_ref().ok_or("No generation in progress")?;
+    *cancel_flag.write().await = true;
+
+    Ok("Generation cancellation requested".to_string())
+}
+
+/// Generation config for a request, on top of the loaded model's defaults
+async fn generation_config(
+    engine: &InferenceEngine,
+    request: &GenerateTextRequest,
+    prompt_library: &Mutex<PromptLibrary>,
+    template_library: &Mutex<TemplateLibrary>,
+) -> Result<GenerationConfig, String> {
+    let overrides = request_overrides(request, prompt_library, template_library).await?;
+    request_generation_config(engine.get_generation_defaults().await, &overrides, request)
+}
+
+/// Generation settings recommended by the request's library prompt and
+/// document template, in the order they apply
+async fn request_overrides(
+    request: &GenerateTextRequest,
+    prompt_library: &Mutex<PromptLibrary>,
+    template_library: &Mutex<TemplateLibrary>,
+) -> Result<Vec<GenerationOverrides>, String> {
+    let mut overrides = Vec::new();
+
+    if let Some(prompt_id) = &request.prompt_id {
+        let prompt = prompt_library
+            .lock()
+            .await
+            .get_prompt(prompt_id)
+            .map_err(|e| format!("Failed to get prompt: {}", e))?
+            .ok_or_else(|| format!("Prompt not found: {}", prompt_id))?;
+        overrides.push(prompt.generation);
+    }
+    if let Some(template_id) = &request.template_id {
+        let template = template_library
+            .lock()
+            .await
+            .get_template(template_id)
+            .map_err(|e| format!("Failed to get template: {}", e))?
+            .ok_or_else(|| format!("Template not found: {}", template_id))?;
+        overrides.push(template.generation);
+    }
+
+    Ok(overrides)
+}
+
+/// Generation config for a request
+///
+/// The prompt's and then the template's recommended settings go over the
+/// model's defaults, and the temperature and token limit set on the request
+/// itself win over all of them. Settings out of range are rejected.
+fn request_generation_config(
+    defaults: GenerationConfig,
+    overrides: &[GenerationOverrides],
+    request: &GenerateTextRequest,
+) -> Result<GenerationConfig, String> {
+    let mut config = defaults;
+    for overrides in overrides {
+        overrides.apply_to(&mut config);
+    }
+    if let Some(temp) = request.temperature {
+        config.temperature = temp;
+    }
+    if let Some(max) = request.max_tokens {
+        config.max_new_tokens = max;
+    }
+    validate_generation_config(&config).map_err(|e| format!("Invalid generation settings: {}", e))?;
+    Ok(config)
+}
+
+/// Error for a generation request made before any model is loaded
+async fn no_model_error(request: &GenerateTextRequest, db: &DatabaseManager) -> GenerateError {
+    if !request.model_guidance {
+        return "No AI model loaded. Please load a model first.".to_string().into();
+    }
+
+    let conn = db.get_connection().await;
+    GenerateError::NoModel(model_guidance(conn.as_ref(), &ModelRegistry::new(), false).await)
+}
+
+/// Work out whether a model needs downloading, loading, or nothing at all
+async fn model_guidance(
+    conn: Option<&DatabaseConnection>,
+    registry: &ModelRegistry,
+    loaded: bool,
+) -> ModelGuidance {
+    let downloaded_models: Vec<String> = match conn {
+        Some(conn) => models::Entity::find()
+            .filter(models::Column::Status.eq("downloaded"))
+            .order_by_desc(models::Column::LastUsedAt)
+            .all(conn)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|model| model.model_id)
+            .collect(),
+        None => Vec::new(),
+    };
+    let recommended = registry.get_recommended_model();
+
+    let (state, message) = if loaded {
+        (ModelReadiness::Loaded, "A model is loaded and ready.".to_string())
+    } else if let Some(model_id) = downloaded_models.first() {
+        (
+            ModelReadiness::DownloadedInactive,
+            format!("No AI model loaded. Load {} from the Models page.", model_id),
+        )
+    } else {
+        let name = recommended.map_or("a model", |model| model.name.as_str());
+        (
+            ModelReadiness::NotDownloaded,
+            format!("No AI model downloaded. Download {} from the Models page.", name),
+        )
+    };
+
+    ModelGuidance {
+        state,
+        downloaded_models,
+        recommended_model_id: recommended.map(|model| model.model_id.clone()),
+        message,
+    }
+}
+
+/// Drop a conversation's cached session when its last turn came from another model
+///
+/// The next turn then re-tokenizes the whole history with the loaded
+/// model's tokenizer instead of continuing the previous model's KV cache.
+async fn restart_session_on_model_switch(
+    engine: &InferenceEngine,
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+) {
+    let loaded = engine.loaded_model_id().await;
+    if let Some(previous) = switched_from_model(conn, conversation_id, loaded.as_deref()).await {
+        log::info!(
+            "Conversation {} continues on {} (was {}), re-reading history",
+            conversation_id,
+            loaded.unwrap_or_default(),
+            previous
+        );
+        engine.drop_session(conversation_id).await;
+    }
+}
+
+/// Model that wrote the conversation's latest assistant turn, if it isn't `loaded`
+async fn switched_from_model(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+    loaded: Option<&str>,
+) -> Option<String> {
+    let loaded = loaded?;
+    messages::Entity::find()
+        .filter(messages::Column::ConversationId.eq(conversation_id))
+        .filter(messages::Column::Role.eq("assistant"))
+        .filter(messages::Column::ModelName.is_not_null())
+        .order_by_desc(messages::Column::Id)
+        .one(conn)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|message| message.model_name)
+        .filter(|previous| previous != loaded)
+}
+
+/// Store the turn being generated when the request belongs to a conversation
+async fn begin_turn_if_tracked(
+    conn: Option<&DatabaseConnection>,
+    request: &GenerateTextRequest,
+) -> Result<Option<i32>, String> {
+    match (conn, request.conversation_id) {
+        (Some(conn), Some(conversation_id)) => {
+            let model_name = active_model_name(conn).await;
+            begin_turn(conn, conversation_id, &request.messages, model_name)
+                .await
+                .map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Record how a turn ended; the response is still returned if this fails
+async fn finish_turn_logged(
+    conn: &DatabaseConnection,
+    message_id: i32,
+    content: String,
+    is_complete: bool,
+) {
+    if let Err(e) = finish_turn(conn, message_id, content, is_complete).await {
+        log::warn!("{}", e);
+    }
+}
+
+/// Id of the active model, recorded as the provenance of generated turns
+async fn active_model_name(conn: &DatabaseConnection) -> Option<String> {
+    models::Entity::find()
+        .filter(models::Column::IsActive.eq(true))
+        .one(conn)
+        .await
+        .ok()
+        .flatten()
+        .map(|model| model.model_id)
+}
+
+/// Store the newest user turn and an empty, incomplete assistant turn
+///
+/// Both rows are written before generation starts, so a turn interrupted by a
+/// crash or restart is still in the history, flagged as incomplete. Returns
+/// the id of the assistant turn.
+pub(crate) async fn begin_turn(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+    history: &[ChatMessage],
+    model_name: Option<String>,
+) -> Result<i32, String> {
+    let conversation = conversations::Entity::find_by_id(conversation_id)
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    // A retried turn resends a question that is already stored
+    let last_stored = messages::Entity::find()
+        .filter(messages::Column::ConversationId.eq(conversation_id))
+        .order_by_desc(messages::Column::Id)
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    let new_user_turn = history.last().filter(|message| {
+        message.role == "user"
+            && !last_stored
+                .as_ref()
+                .is_some_and(|stored| stored.role == "user" && stored.content == message.content)
+    });
+
+    if let Some(user_turn) = new_user_turn {
+        messages::ActiveModel {
+            conversation_id: Set(conversation_id),
+            role: Set(user_turn.role.clone()),
+            content: Set(user_turn.content.clone()),
+            is_ai_generated: Set(false),
+            was_edited: Set(false),
+            content_source: Set("human".to_string()),
+            edit_count: Set(0),
+            is_complete: Set(true),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+        .map_err(|e| format!("Failed to store user message: {}", e))?;
+
+        // An untitled conversation is named after its first question
+        if conversation.title.trim().is_empty() {
+            update_conversation(
+                conn,
+                conversation_id,
+                conversations::ActiveModel {
+                    title: Set(derive_title(&user_turn.content)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+    }
+
+    let assistant_turn = messages::ActiveModel {
+        conversation_id: Set(conversation_id),
+        role: Set("assistant".to_string()),
+        content: Set(String::new()),
+        is_ai_generated: Set(true),
+        was_edited: Set(false),
+        content_source: Set("ai".to_string()),
+        model_name: Set(model_name),
+        generation_timestamp: Set(Some(now)),
+        edit_count: Set(0),
+        is_complete: Set(false),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(|e| format!("Failed to store assistant message: {}", e))?;
+
+    Ok(assistant_turn.id)
+}
+
+/// Longest derived title, in characters
+const TITLE_MAX_CHARS: usize = 60;
+
+/// Title for a conversation from its first user message
+///
+/// Takes the first non-blank line, collapses whitespace and cuts it at a word
+/// boundary when it is too long.
+pub(crate) fn derive_title(message: &str) -> String {
+    let line = message
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let title = words.join(" ");
+    if title.chars().count() <= TITLE_MAX_CHARS {
+        return if title.is_empty() { "New conversation".to_string() } else { title };
+    }
+
+    let mut cut = String::new();
+    for word in words {
+        let separator = usize::from(!cut.is_empty());
+        if cut.chars().count() + separator + word.chars().count() >= TITLE_MAX_CHARS {
+            break;
+        }
+        if separator == 1 {
+            cut.push(' ');
+        }
+        cut.push_str(word);
+    }
+    if cut.is_empty() {
+        // A single word longer than the limit
+        cut = title.chars().take(TITLE_MAX_CHARS - 1).collect();
+    }
+    cut.push('…');
+    cut
+}
+
+/// Write changed columns of a conversation
+async fn update_conversation(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+    mut changes: conversations::ActiveModel,
+) -> Result<conversations::Model, String> {
+    changes.id = Set(conversation_id);
+    changes
+        .update(conn)
+        .await
+        .map_err(|e| format!("Failed to update conversation: {}", e))
+}
+
+/// Save the text of an assistant turn and whether generation finished
+pub(crate) async fn finish_turn(
+    conn: &DatabaseConnection,
+    message_id: i32,
+    content: String,
+    is_complete: bool,
+) -> Result<(), String> {
+    messages::ActiveModel {
+        id: Set(message_id),
+        content: Set(content),
+        is_complete: Set(is_complete),
+        ..Default::default()
+    }
+    .update(conn)
+    .await
+    .map_err(|e| format!("Failed to update message {}: {}", message_id, e))?;
+
+    Ok(())
+}
+
+/// All stored turns of a conversation, oldest first
+pub(crate) async fn load_messages(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+) -> Result<Vec<messages::Model>, String> {
+    messages::Entity::find()
+        .filter(messages::Column::ConversationId.eq(conversation_id))
+        .order_by_asc(messages::Column::Id)
+        .all(conn)
+        .await
+        .map_err(|e| format!("Failed to load messages: {}", e))
+}
+
+/// Chat history for the model; incomplete turns are left out
+fn resumable_history(messages: &[messages::Model]) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .filter(|message| message.is_complete)
+        .map(|message| ChatMessage {
+            role: message.role.clone(),
+            content: message.content.clone(),
         })
+        .collect()
+}
+
+/// Reload a conversation and its stored turns
+pub(crate) async fn load_conversation(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+) -> Result<(conversations::Model, Vec<messages::Model>), String> {
+    let conversation = conversations::Entity::find_by_id(conversation_id)
+        .one(conn)
         .await
-        .map_err(|e| format!("Generation failed: {}", e))?;
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
 
-    Ok(result.text)
+    let messages = load_messages(conn, conversation_id).await?;
+    Ok((conversation, messages))
+}
+
+/// Resume a conversation after a restart
+///
+/// Reloads the stored turns and, when a model is loaded, restores the
+/// conversation's cached state so the next turn doesn't reprocess the history.
+#[tauri::command]
+pub async fn resume_conversation(
+    conversation_id: i32,
+    system_prompt: Option<String>,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+    db: State<'_, DatabaseManager>,
+) -> Result<ResumedConversation, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let (conversation, messages) = load_conversation(&conn, conversation_id).await?;
+
+    let engine = inference_engine.lock().await;
+    let primed_tokens = if engine.is_loaded().await {
+        let history = resumable_history(&messages);
+        engine
+            .prime_session(conversation_id, &history, system_prompt.as_deref())
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to prime conversation {}: {}", conversation_id, e);
+                0
+            })
+    } else {
+        0
+    };
+
+    Ok(ResumedConversation {
+        conversation_id,
+        title: conversation.title,
+        summary: conversation.summary,
+        messages: messages.into_iter().map(StoredMessage::from).collect(),
+        primed_tokens,
+    })
+}
+
+/// Get the saved generation config for a model (defaults if none was saved)
+#[tauri::command]
+pub async fn get_model_generation_config(
+    model_id: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<GenerationConfig, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    load_generation_config(&conn, &model_id).await
+}
+
+/// Save the generation config for a model, applying it now if the model is active
+#[tauri::command]
+pub async fn set_model_generation_config(
+    model_id: String,
+    config: GenerationConfig,
+    db: State<'_, DatabaseManager>,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+) -> Result<(), String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    save_generation_config(&conn, &model_id, &config).await?;
+
+    let is_active = models::Entity::find()
+        .filter(models::Column::ModelId.eq(&model_id))
+        .filter(models::Column::IsActive.eq(true))
+        .one(&conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .is_some();
+
+    if is_active {
+        let engine = inference_engine.lock().await;
+        engine.set_generation_defaults(config).await;
+    }
+
+    Ok(())
+}
+
+/// Settings key holding a model's generation config
+fn generation_config_key(model_id: &str) -> String {
+    format!("generation_config:{}", model_id)
+}
+
+/// Read a model's saved generation config, falling back to the defaults
+pub(crate) async fn load_generation_config(
+    conn: &DatabaseConnection,
+    model_id: &str,
+) -> Result<GenerationConfig, String> {
+    match read_setting(conn, &generation_config_key(model_id)).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid generation config for {}: {}", model_id, e)),
+        None => Ok(GenerationConfig::default()),
+    }
+}
+
+/// Validate and persist a model's generation config
+pub(crate) async fn save_generation_config(
+    conn: &DatabaseConnection,
+    model_id: &str,
+    config: &GenerationConfig,
+) -> Result<(), String> {
+    validate_generation_config(config)?;
+
+    let json = serde_json::to_string(config)
+        .map_err(|e| format!("Failed to serialize generation config: {}", e))?;
+    write_setting(conn, generation_config_key(model_id), json).await
+}
+
+/// Make a model's saved generation config the engine defaults (called on activation)
+pub(crate) async fn apply_generation_config(
+    engine: &InferenceEngine,
+    conn: &DatabaseConnection,
+    model_id: &str,
+) {
+    let config = load_generation_config(conn, model_id)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("{}; using default generation config", e);
+            GenerationConfig::default()
+        });
+
+    engine.set_generation_defaults(config).await;
+}
+
+fn validate_generation_config(config: &GenerationConfig) -> Result<(), String> {
+    if !(0.0..=2.0).contains(&config.temperature) {
+        return Err("temperature must be between 0.0 and 2.0".to_string());
+    }
+    if !(config.top_p > 0.0 && config.top_p <= 1.0) {
+        return Err("top_p must be greater than 0.0 and at most 1.0".to_string());
+    }
+    if config.max_new_tokens == 0 {
+        return Err("max_new_tokens must be greater than 0".to_string());
+    }
+    if config.repetition_penalty <= 0.0 {
+        return Err("repetition_penalty must be greater than 0.0".to_string());
+    }
+    if config.max_repeated_lines == 1 {
+        return Err("max_repeated_lines must be 0 (disabled) or at least 2".to_string());
+    }
+    Ok(())
 }
 
 /// Get available system prompts
@@ -212,34 +1015,198 @@ pub async fn get_system_prompts() -> Result<Vec<SystemPrompt>, String> {
     ])
 }
 
-/// Get conversation history
+/// Get conversation history (completed turns only)
 #[tauri::command]
 pub async fn get_conversation_history(
-    _conversation_id: i32,
-    _db: State<'_, DatabaseManager>,
+    conversation_id: i32,
+    db: State<'_, DatabaseManager>,
 ) -> Result<Vec<ChatMessage>, String> {
-    // TODO: Implement database query to get conversation messages
-    // For now, return empty
-    Ok(vec![])
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let messages = load_messages(&conn, conversation_id).await?;
+    Ok(resumable_history(&messages))
 }
 
-/// Create new conversation
+/// Create a conversation in a case, returning its ID
+///
+/// Without a title, the conversation is named after its first question.
 #[tauri::command]
 pub async fn create_conversation(
-    _title: Option<String>,
-    _db: State<'_, DatabaseManager>,
+    case_id: i32,
+    title: Option<String>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<i32, String> {
-    // TODO: Implement database insert for new conversation
-    // For now, return placeholder ID
-    Ok(1)
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    insert_new_conversation(&conn, case_id, title.as_deref()).await
+}
+
+/// Store a conversation with its title collapsed, or an empty title for
+/// `begin_turn` to fill in
+pub(crate) async fn insert_new_conversation(
+    conn: &DatabaseConnection,
+    case_id: i32,
+    title: Option<&str>,
+) -> Result<i32, String> {
+    entity::cases::Entity::find_by_id(case_id)
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Case not found: {}", case_id))?;
+
+    let title = title
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+    let conversation = conversations::ActiveModel {
+        case_id: Set(case_id),
+        title: Set(title),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(|e| format!("Failed to create conversation: {}", e))?;
+
+    Ok(conversation.id)
+}
+
+/// Rename a conversation
+#[tauri::command]
+pub async fn rename_conversation(
+    conversation_id: i32,
+    title: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<String, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    rename(&conn, conversation_id, &title).await
+}
+
+/// Store a new title, with whitespace collapsed; returns the stored title
+pub(crate) async fn rename(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+    title: &str,
+) -> Result<String, String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err("Conversation title cannot be empty".to_string());
+    }
+
+    load_conversation(conn, conversation_id).await?;
+    let conversation = update_conversation(
+        conn,
+        conversation_id,
+        conversations::ActiveModel {
+            title: Set(title),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(conversation.title)
+}
+
+/// Instruction appended to the history to have the model summarize it
+const SUMMARY_INSTRUCTION: &str = "Summarize this conversation in two or three sentences: \
+    the question, what was concluded and anything left open. Reply with the summary only.";
+
+/// Summarize a conversation with the loaded model, e.g. when it is closed
+///
+/// The summary is stored with the conversation and returned.
+#[tauri::command]
+pub async fn summarize_conversation(
+    conversation_id: i32,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+    db: State<'_, DatabaseManager>,
+) -> Result<String, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let engine = inference_engine.lock().await;
+    if !engine.is_loaded().await {
+        return Err("No AI model loaded. Please load a model first.".to_string());
+    }
+
+    let (_, messages) = load_conversation(&conn, conversation_id).await?;
+    let mut history = resumable_history(&messages);
+    if history.is_empty() {
+        return Err("Conversation has no messages to summarize".to_string());
+    }
+    history.push(ChatMessage {
+        role: "user".to_string(),
+        content: SUMMARY_INSTRUCTION.to_string(),
+    });
+
+    let mut config = engine.get_generation_defaults().await;
+    config.temperature = 0.2;
+    config.max_new_tokens = config.max_new_tokens.min(256);
+
+    // Not tied to the conversation, so its cached session isn't disturbed
+    let result = engine
+        .generate(GenerateRequest {
+            messages: history,
+            config,
+            system_prompt: None,
+            conversation_id: None,
+        })
+        .await
+        .map_err(|e| format!("Summarization failed: {}", e))?;
+
+    let summary = result.text.trim().to_string();
+    update_conversation(
+        &conn,
+        conversation_id,
+        conversations::ActiveModel {
+            summary: Set(Some(summary.clone())),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(summary)
+}
+
+/// Refuse to delete a conversation whose case is under legal hold
+async fn ensure_conversation_deletable(
+    conn: &DatabaseConnection,
+    conversation_id: i32,
+) -> Result<(), String> {
+    let conversation = conversations::Entity::find_by_id(conversation_id)
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    match conversation {
+        Some(conversation) => crate::commands::cases::ensure_not_on_hold(conn, conversation.case_id)
+            .await
+            .map(|_| ()),
+        None => Ok(()),
+    }
 }
 
 /// Delete conversation
 #[tauri::command]
 pub async fn delete_conversation(
     conversation_id: i32,
-    _db: State<'_, DatabaseManager>,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<String, String> {
+    if let Some(conn) = db.get_connection().await {
+        ensure_conversation_deletable(&conn, conversation_id).await?;
+    }
+
+    inference_engine.lock().await.drop_session(conversation_id).await;
+
     // TODO: Implement database delete
     Ok(format!("Conversation {} deleted", conversation_id))
 }
@@ -263,4 +1230,481 @@ mod tests {
         assert!(prompts.iter().any(|p| p.id == "assistant"));
         assert!(prompts.iter().any(|p| p.id == "legal"));
     }
+
+    async fn test_connection() -> (tempfile::TempDir, DatabaseConnection) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+        (dir, conn)
+    }
+
+    async fn open_database(path: &std::path::Path) -> DatabaseConnection {
+        let db = DatabaseManager::new();
+        db.initialize(path.to_str().unwrap()).await.unwrap();
+        db.get_connection().await.unwrap()
+    }
+
+    async fn insert_case(conn: &DatabaseConnection) -> i32 {
+        crate::commands::cases::insert_case(
+            conn,
+            crate::commands::cases::CreateCaseRequest {
+                name: "Lease dispute".to_string(),
+                client_name: "Client".to_string(),
+                case_number: None,
+                description: None,
+                language: None,
+                jurisdiction: None,
+            },
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    async fn insert_conversation(conn: &DatabaseConnection) -> i32 {
+        conversations::ActiveModel {
+            case_id: Set(insert_case(conn).await),
+            title: Set("Notice periods".to_string()),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+        .unwrap()
+        .id
+    }
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conversation_reloads_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let conversation_id = {
+            let conn = open_database(&path).await;
+            let conversation_id = insert_conversation(&conn).await;
+
+            let mut history = vec![message("user", "What is the notice period?")];
+            let reply = begin_turn(&conn, conversation_id, &history, Some("phi-3-mini".to_string()))
+                .await
+                .unwrap();
+            finish_turn(&conn, reply, "Three months.".to_string(), true)
+                .await
+                .unwrap();
+
+            // The app stops while the second answer is being generated
+            history.push(message("assistant", "Three months."));
+            history.push(message("user", "And for the landlord?"));
+            begin_turn(&conn, conversation_id, &history, None)
+                .await
+                .unwrap();
+
+            conversation_id
+        };
+
+        // A new connection to the same file stands in for the restarted app
+        let conn = open_database(&path).await;
+        let (conversation, messages) = load_conversation(&conn, conversation_id).await.unwrap();
+        assert_eq!(conversation.title, "Notice periods");
+
+        let turns: Vec<(&str, &str, bool)> = messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str(), m.is_complete))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![
+                ("user", "What is the notice period?", true),
+                ("assistant", "Three months.", true),
+                ("user", "And for the landlord?", true),
+                ("assistant", "", false),
+            ]
+        );
+        assert_eq!(messages[1].model_name.as_deref(), Some("phi-3-mini"));
+        assert!(messages[1].is_ai_generated);
+        assert!(!messages[0].is_ai_generated);
+
+        // The interrupted answer is not fed back to the model
+        let history = resumable_history(&messages);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].content, "And for the landlord?");
+    }
+
+    #[tokio::test]
+    async fn test_model_guidance_distinguishes_readiness() {
+        let (_dir, conn) = test_connection().await;
+        let registry = ModelRegistry::new();
+        let recommended = "mistralai/Mistral-7B-Instruct-v0.2";
+
+        // Fresh install: point the user at the recommended download
+        let guidance = model_guidance(Some(&conn), &registry, false).await;
+        assert_eq!(guidance.state, ModelReadiness::NotDownloaded);
+        assert!(guidance.downloaded_models.is_empty());
+        assert_eq!(guidance.recommended_model_id.as_deref(), Some(recommended));
+        assert!(guidance.message.contains("Download Mistral 7B Instruct"), "{}", guidance.message);
+
+        // A model still downloading doesn't count
+        for (model_id, status) in [("phi-2", "downloading"), ("tinyllama", "downloaded")] {
+            models::ActiveModel {
+                model_id: Set(model_id.to_string()),
+                name: Set(model_id.to_string()),
+                provider: Set("local".to_string()),
+                size: Set("small".to_string()),
+                parameters: Set("1B".to_string()),
+                format: Set("gguf".to_string()),
+                status: Set(status.to_string()),
+                ..Default::default()
+            }
+            .insert(&conn)
+            .await
+            .unwrap();
+        }
+
+        let guidance = model_guidance(Some(&conn), &registry, false).await;
+        assert_eq!(guidance.state, ModelReadiness::DownloadedInactive);
+        assert_eq!(guidance.downloaded_models, vec!["tinyllama".to_string()]);
+        assert_eq!(guidance.recommended_model_id.as_deref(), Some(recommended));
+        assert!(guidance.message.contains("Load tinyllama"), "{}", guidance.message);
+
+        let guidance = model_guidance(Some(&conn), &registry, true).await;
+        assert_eq!(guidance.state, ModelReadiness::Loaded);
+
+        // Only the guidance is an object; other failures stay plain strings
+        let json = serde_json::to_value(GenerateError::NoModel(guidance)).unwrap();
+        assert_eq!(json["state"], "loaded");
+        let json = serde_json::to_value(GenerateError::from("Generation failed".to_string())).unwrap();
+        assert_eq!(json, "Generation failed");
+    }
+
+    #[tokio::test]
+    async fn test_model_switch_between_turns_is_detected() {
+        let (_dir, conn) = test_connection().await;
+        let conversation_id = insert_conversation(&conn).await;
+
+        // Nothing to compare before the first turn or without a loaded model
+        assert_eq!(switched_from_model(&conn, conversation_id, Some("fast")).await, None);
+
+        let mut history = vec![message("user", "Summarize the lease")];
+        let reply = begin_turn(&conn, conversation_id, &history, Some("fast".to_string()))
+            .await
+            .unwrap();
+        finish_turn(&conn, reply, "It runs for a year.".to_string(), true)
+            .await
+            .unwrap();
+        assert_eq!(switched_from_model(&conn, conversation_id, Some("fast")).await, None);
+        assert_eq!(switched_from_model(&conn, conversation_id, None).await, None);
+
+        // The user loads the accurate model before asking the next question
+        assert_eq!(
+            switched_from_model(&conn, conversation_id, Some("accurate")).await.as_deref(),
+            Some("fast")
+        );
+
+        // Once the accurate model has answered, later turns are no switch
+        history.push(message("assistant", "It runs for a year."));
+        history.push(message("user", "Can it be extended?"));
+        let reply = begin_turn(&conn, conversation_id, &history, Some("accurate".to_string()))
+            .await
+            .unwrap();
+        finish_turn(&conn, reply, "Yes, once.".to_string(), true)
+            .await
+            .unwrap();
+        assert_eq!(switched_from_model(&conn, conversation_id, Some("accurate")).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_registration_clears_only_its_flag() {
+        let state = GenerationCancelState::default();
+
+        let first = CancelRegistration::register(&state).await;
+        assert!(state.0.lock().await.is_some());
+        drop(first);
+        assert!(state.0.lock().await.is_none());
+
+        // A generation that ends after the next one started leaves its flag
+        let stale = CancelRegistration::register(&state).await;
+        let current = CancelRegistration::register(&state).await;
+        drop(stale);
+        let published = state.0.lock().await.clone().unwrap();
+        assert!(Arc::ptr_eq(&published, &current.flag));
+        drop(current);
+        assert!(state.0.lock().await.is_none());
+    }
+
+    #[test]
+    fn test_cancel_states_are_distinct_managed_types() {
+        use std::any::TypeId;
+
+        // Tauri keeps one managed state per type, so equal types would share a slot
+        let states = [
+            TypeId::of::<GenerationCancelState>(),
+            TypeId::of::<crate::commands::pii::BatchCancelState>(),
+            TypeId::of::<crate::commands::ner::NerDownloadState>(),
+        ];
+        for (i, a) in states.iter().enumerate() {
+            assert!(states[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_turn_keeps_partial_text() {
+        let (_dir, conn) = test_connection().await;
+        let conversation_id = insert_conversation(&conn).await;
+
+        let reply = begin_turn(&conn, conversation_id, &[message("user", "Summarize")], None)
+            .await
+            .unwrap();
+        finish_turn(&conn, reply, "The lease".to_string(), false)
+            .await
+            .unwrap();
+
+        let messages = load_messages(&conn, conversation_id).await.unwrap();
+        let stored = StoredMessage::from(messages[1].clone());
+        assert_eq!(stored.content, "The lease");
+        assert!(!stored.is_complete);
+
+        // A question left without an answer isn't stored twice on retry
+        let conversation_id = insert_conversation(&conn).await;
+        messages::ActiveModel {
+            conversation_id: Set(conversation_id),
+            role: Set("user".to_string()),
+            content: Set("Summarize".to_string()),
+            is_ai_generated: Set(false),
+            was_edited: Set(false),
+            content_source: Set("human".to_string()),
+            edit_count: Set(0),
+            is_complete: Set(true),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+        begin_turn(&conn, conversation_id, &[message("user", "Summarize")], None)
+            .await
+            .unwrap();
+        let turns: Vec<_> = load_messages(&conn, conversation_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.role)
+            .collect();
+        assert_eq!(turns, vec!["user", "assistant"]);
+
+        let err = begin_turn(&conn, conversation_id + 1, &[message("user", "Hi")], None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("Conversation not found"));
+    }
+
+    #[tokio::test]
+    async fn test_generation_config_applied_on_activation() {
+        let (_dir, conn) = test_connection().await;
+
+        let config = GenerationConfig {
+            temperature: 0.2,
+            top_p: 0.8,
+            max_new_tokens: 512,
+            seed: Some(7),
+            ..GenerationConfig::default()
+        };
+        save_generation_config(&conn, "mistral-7b-instruct", &config)
+            .await
+            .unwrap();
+
+        // A fresh engine stands in for a new session; activation loads the saved values
+        let engine = InferenceEngine::new();
+        apply_generation_config(&engine, &conn, "mistral-7b-instruct").await;
+        assert_eq!(engine.get_generation_defaults().await, config);
+
+        // Activating a model without a saved config restores the defaults
+        apply_generation_config(&engine, &conn, "phi-3-mini").await;
+        assert_eq!(
+            engine.get_generation_defaults().await,
+            GenerationConfig::default()
+        );
+
+        // Reactivating the first model brings its config back
+        apply_generation_config(&engine, &conn, "mistral-7b-instruct").await;
+        assert_eq!(engine.get_generation_defaults().await, config);
+    }
+
+    #[tokio::test]
+    async fn test_generation_config_overwrite_and_validation() {
+        let (_dir, conn) = test_connection().await;
+
+        let mut config = GenerationConfig {
+            temperature: 1.2,
+            ..GenerationConfig::default()
+        };
+        save_generation_config(&conn, "model", &config).await.unwrap();
+        config.temperature = 0.4;
+        save_generation_config(&conn, "model", &config).await.unwrap();
+
+        let loaded = load_generation_config(&conn, "model").await.unwrap();
+        assert_eq!(loaded.temperature, 0.4);
+
+        config.top_p = 0.0;
+        assert!(save_generation_config(&conn, "model", &config).await.is_err());
+        assert_eq!(load_generation_config(&conn, "model").await.unwrap(), loaded);
+
+        // A single line can't be a loop
+        config.top_p = 0.9;
+        config.max_repeated_lines = 1;
+        assert!(save_generation_config(&conn, "model", &config).await.is_err());
+    }
+
+    #[test]
+    fn test_prompt_generation_settings_override_model_defaults() {
+        let prompt = crate::prompts::parse_prompt_str(
+            "---\nname: Extract dates\ngeneration:\n  temperature: 0\n  top_k: 1\n---\n\nList every date.",
+            std::path::Path::new("extract_dates.md"),
+        )
+        .unwrap();
+        let defaults = GenerationConfig {
+            temperature: 0.7,
+            max_new_tokens: 1024,
+            ..GenerationConfig::default()
+        };
+        let mut request = GenerateTextRequest {
+            conversation_id: None,
+            messages: vec![message("user", "Lease signed 1 May 2024.")],
+            system_prompt: Some(prompt.content.clone()),
+            temperature: None,
+            max_tokens: None,
+            prompt_id: Some("extract_dates".to_string()),
+            template_id: None,
+            model_guidance: false,
+        };
+
+        let overrides = [prompt.generation];
+        let config = request_generation_config(defaults.clone(), &overrides, &request).unwrap();
+        assert_eq!(config.temperature, 0.0);
+        assert_eq!(config.top_k, 1);
+        // Settings the prompt leaves out keep the model's defaults
+        assert_eq!(config.max_new_tokens, 1024);
+        assert_eq!(config.top_p, defaults.top_p);
+
+        // An explicit temperature on the request still wins
+        request.temperature = Some(0.3);
+        let config = request_generation_config(defaults, &overrides, &request).unwrap();
+        assert_eq!(config.temperature, 0.3);
+    }
+
+    #[tokio::test]
+    async fn test_template_generation_settings_apply_and_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let prompts = Mutex::new(PromptLibrary::new(dir.path().to_path_buf()).unwrap());
+        let templates = TemplateLibrary::new(dir.path().to_path_buf()).unwrap();
+        let mut template = crate::templates::DocumentTemplate::new(
+            "Notice letter".to_string(),
+            "Dear {{name}},".to_string(),
+        );
+        template.generation.temperature = Some(0.1);
+        template.generation.seed = Some(42);
+        templates.save_template(&template).unwrap();
+        let mut careless = crate::templates::DocumentTemplate::new(
+            "Careless".to_string(),
+            "Anything.".to_string(),
+        );
+        careless.generation.top_p = Some(0.0);
+        templates.save_template(&careless).unwrap();
+        let templates = Mutex::new(templates);
+
+        let engine = InferenceEngine::new();
+        let mut request = GenerateTextRequest {
+            conversation_id: None,
+            messages: vec![message("user", "Draft the notice.")],
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            prompt_id: None,
+            template_id: Some(template.id.clone()),
+            model_guidance: false,
+        };
+        let config = generation_config(&engine, &request, &prompts, &templates)
+            .await
+            .unwrap();
+        assert_eq!(config.temperature, 0.1);
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.max_new_tokens, GenerationConfig::default().max_new_tokens);
+
+        request.template_id = Some(careless.id.clone());
+        let err = generation_config(&engine, &request, &prompts, &templates)
+            .await
+            .unwrap_err();
+        assert!(err.contains("top_p"), "{}", err);
+
+        request.template_id = Some("missing".to_string());
+        let err = generation_config(&engine, &request, &prompts, &templates)
+            .await
+            .unwrap_err();
+        assert!(err.contains("Template not found"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_new_conversation_gets_derived_title_and_rename_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let conn = open_database(&path).await;
+        let case_id = insert_case(&conn).await;
+
+        // A conversation created without a title
+        let conversation_id = insert_new_conversation(&conn, case_id, None).await.unwrap();
+        let (conversation, _) = load_conversation(&conn, conversation_id).await.unwrap();
+        assert_eq!(conversation.title, "");
+        assert!(insert_new_conversation(&conn, case_id + 1, None).await.is_err());
+        let titled = insert_new_conversation(&conn, case_id, Some(" Notice  periods "))
+            .await
+            .unwrap();
+        assert_eq!(load_conversation(&conn, titled).await.unwrap().0.title, "Notice periods");
+
+        let question = "\n  Can the landlord   end the lease early?\nThe lease runs until 2026.";
+        begin_turn(&conn, conversation_id, &[message("user", question)], None)
+            .await
+            .unwrap();
+        let (conversation, _) = load_conversation(&conn, conversation_id).await.unwrap();
+        assert_eq!(conversation.title, "Can the landlord end the lease early?");
+        assert_eq!(conversation.summary, None);
+
+        // Later questions don't replace the title
+        begin_turn(&conn, conversation_id, &[message("user", "What about notice?")], None)
+            .await
+            .unwrap();
+        let (conversation, _) = load_conversation(&conn, conversation_id).await.unwrap();
+        assert_eq!(conversation.title, "Can the landlord end the lease early?");
+
+        assert_eq!(
+            rename(&conn, conversation_id, " Early  termination ").await.unwrap(),
+            "Early termination"
+        );
+        assert!(rename(&conn, conversation_id, "   ").await.is_err());
+        assert!(rename(&conn, titled + 1, "Other").await.is_err());
+
+        // The new title survives reopening the database
+        drop(conn);
+        let conn = open_database(&path).await;
+        let (conversation, _) = load_conversation(&conn, conversation_id).await.unwrap();
+        assert_eq!(conversation.title, "Early termination");
+    }
+
+    #[test]
+    fn test_derive_title_cuts_long_messages_at_words() {
+        let long = "Please review the attached commercial lease agreement and list every clause on termination";
+        let title = derive_title(long);
+        assert!(title.chars().count() <= TITLE_MAX_CHARS);
+        assert_eq!(title, "Please review the attached commercial lease agreement and…");
+
+        assert_eq!(derive_title(&"x".repeat(80)).chars().count(), TITLE_MAX_CHARS);
+        assert_eq!(derive_title("  \n "), "New conversation");
+    }
 }
@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+use crate::documents::{ExtractedDocument, ExtractorRegistry};
+
+/// Extract the text of a document, with page/section boundaries where known
+#[tauri::command]
+pub async fn extract_document_text(path: String) -> Result<ExtractedDocument, String> {
+    let path = PathBuf::from(path);
+
+    tokio::task::spawn_blocking(move || ExtractorRegistry::with_defaults().extract(&path))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))?
+        .map_err(|e| format!("Failed to extract document text: {:#}", e))
+}
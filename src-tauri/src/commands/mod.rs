@@ -6,3 +6,6 @@ pub mod settings;
 pub mod prompts;
 pub mod templates;
 pub mod presidio;
+pub mod cases;
+pub mod documents;
+pub mod system;
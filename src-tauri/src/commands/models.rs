@@ -1,16 +1,26 @@
 use tauri::{AppHandle, Emitter, State};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::ai::InferenceEngine;
+use crate::commands::conversation::apply_generation_config;
+use crate::commands::settings::{read_setting, write_setting};
 use crate::database::DatabaseManager;
+use crate::models::quantization;
 use crate::models::{
-    DownloadProgress, DownloadStatus, ModelDownloader, ModelRegistry, ModelValidator,
+    DownloadComplete, DownloadProgress, DownloadStatus, DownloadTimeouts, ModelDownloader,
+    ModelFormat, ModelInfo, ModelMetadata, ModelRegistry, ModelValidator,
 };
 use entity::models;
 
+/// Settings key holding the download timeouts as JSON
+const DOWNLOAD_TIMEOUTS_KEY: &str = "download_timeouts";
+
 /// Response for listing models
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelListItem {
@@ -24,80 +34,166 @@ pub struct ModelListItem {
     pub quantization: Option<String>,
     pub format: String,
     pub status: String,
+    /// Expected size of the complete file, in bytes
     pub file_size: i64,
+    /// Bytes on disk; below `file_size` for a partial download
+    pub downloaded_size: i64,
+    /// Only a downloaded model counts as active
     pub is_active: bool,
     pub is_downloaded: bool,
     pub download_url: String,
     pub tags: Vec<String>,
+    /// Read from the downloaded file; absent until the model is downloaded
+    pub architecture: Option<String>,
+    pub context_length: Option<i64>,
+    pub quantization_bits: Option<i32>,
+    /// Approximate RAM needed at `quantization`, in bytes
+    pub estimated_ram: Option<u64>,
+    /// Largest quantization that fits in the RAM currently available
+    pub recommended_quantization: Option<String>,
+}
+
+impl ModelListItem {
+    /// Item for a database row, filling gaps from the registry entry if there is one
+    fn from_record(record: models::Model, info: Option<&ModelInfo>) -> Self {
+        let is_downloaded = record.status == "downloaded";
+        let file_size = record
+            .file_size
+            .or(info.map(|info| info.file_size))
+            .unwrap_or(0);
+        let downloaded_size = match record.downloaded_size {
+            Some(size) => size,
+            None if is_downloaded => file_size,
+            None => 0,
+        };
+
+        Self {
+            id: Some(record.id),
+            model_id: record.model_id,
+            name: record.name,
+            description: record.description.unwrap_or_default(),
+            provider: record.provider,
+            size: record.size,
+            parameters: record.parameters,
+            quantization: record.quantization,
+            format: record.format,
+            status: record.status,
+            file_size,
+            downloaded_size,
+            is_active: record.is_active && is_downloaded,
+            is_downloaded,
+            download_url: info
+                .map(|info| info.download_url.clone())
+                .or(record.download_url)
+                .unwrap_or_default(),
+            tags: serde_json::from_str(&record.tags.unwrap_or_else(|| "[]".to_string()))
+                .unwrap_or_default(),
+            architecture: record.architecture,
+            context_length: record.context_length,
+            quantization_bits: record.quantization_bits,
+            estimated_ram: None,
+            recommended_quantization: None,
+        }
+    }
+
+    /// Item for a registry model that has no database row yet
+    fn from_registry(info: &ModelInfo) -> Self {
+        Self {
+            id: None,
+            model_id: info.model_id.clone(),
+            name: info.name.clone(),
+            description: info.description.clone(),
+            provider: info.provider.clone(),
+            size: info.size.clone(),
+            parameters: info.parameters.clone(),
+            quantization: info.quantization.clone(),
+            format: info.format.clone(),
+            status: "available".to_string(),
+            file_size: info.file_size,
+            downloaded_size: 0,
+            is_active: false,
+            is_downloaded: false,
+            download_url: info.download_url.clone(),
+            tags: info.tags.clone(),
+            architecture: None,
+            context_length: None,
+            quantization_bits: None,
+            estimated_ram: None,
+            recommended_quantization: None,
+        }
+    }
+
+    /// Fill in the RAM estimate, and a recommendation when free RAM is known
+    fn estimate_memory(&mut self, available_ram: Option<u64>) {
+        self.estimated_ram = self
+            .quantization
+            .as_deref()
+            .and_then(|quant| quantization::estimate_ram(&self.parameters, quant));
+        self.recommended_quantization = available_ram
+            .and_then(|ram| quantization::recommend_quantization(&self.parameters, ram))
+            .map(str::to_string);
+    }
 }
 
 // Global state for download tracking
 type DownloadState = Arc<Mutex<Option<String>>>;
 
 /// List all available models from registry and database
+///
+/// Each model carries a RAM estimate and, where the platform reports free
+/// memory, the largest quantization that fits.
 #[tauri::command]
 pub async fn list_models(
     db: State<'_, DatabaseManager>,
 ) -> Result<Vec<ModelListItem>, String> {
-    let registry = ModelRegistry::new();
     let conn = db
         .get_connection()
         .await
         .ok_or("Database not initialized")?;
 
-    let mut result = Vec::new();
-
-    for model_info in registry.list_models() {
-        // Check if model is in database
-        let db_model = models::Entity::find()
-            .filter(models::Column::ModelId.eq(&model_info.model_id))
-            .one(&conn)
-            .await
-            .map_err(|e| format!("Database error: {}", e))?;
-
-        let item = if let Some(db_record) = db_model {
-            ModelListItem {
-                id: Some(db_record.id),
-                model_id: db_record.model_id.clone(),
-                name: db_record.name.clone(),
-                description: db_record.description.unwrap_or_default(),
-                provider: db_record.provider.clone(),
-                size: db_record.size.clone(),
-                parameters: db_record.parameters.clone(),
-                quantization: db_record.quantization.clone(),
-                format: db_record.format.clone(),
-                status: db_record.status.clone(),
-                file_size: db_record.file_size.unwrap_or(model_info.file_size),
-                is_active: db_record.is_active,
-                is_downloaded: db_record.status == "downloaded",
-                download_url: model_info.download_url.clone(),
-                tags: serde_json::from_str(&db_record.tags.unwrap_or_else(|| "[]".to_string()))
-                    .unwrap_or_default(),
-            }
-        } else {
-            // Model not in database, show as available
-            ModelListItem {
-                id: None,
-                model_id: model_info.model_id.clone(),
-                name: model_info.name.clone(),
-                description: model_info.description.clone(),
-                provider: model_info.provider.clone(),
-                size: model_info.size.clone(),
-                parameters: model_info.parameters.clone(),
-                quantization: model_info.quantization.clone(),
-                format: model_info.format.clone(),
-                status: "available".to_string(),
-                file_size: model_info.file_size,
-                is_active: false,
-                is_downloaded: false,
-                download_url: model_info.download_url.clone(),
-                tags: model_info.tags.clone(),
-            }
-        };
+    collect_model_list(&conn, &ModelRegistry::new(), quantization::available_memory()).await
+}
 
-        result.push(item);
+/// Registry models merged with their database rows, plus custom and imported
+/// models that only exist in the database, sorted by provider, name and id
+async fn collect_model_list(
+    conn: &DatabaseConnection,
+    registry: &ModelRegistry,
+    available_ram: Option<u64>,
+) -> Result<Vec<ModelListItem>, String> {
+    let mut records: HashMap<String, models::Model> = models::Entity::find()
+        .all(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .into_iter()
+        .map(|record| (record.model_id.clone(), record))
+        .collect();
+
+    let mut result: Vec<ModelListItem> = registry
+        .list_models()
+        .iter()
+        .map(|info| match records.remove(&info.model_id) {
+            Some(record) => ModelListItem::from_record(record, Some(info)),
+            None => ModelListItem::from_registry(info),
+        })
+        .collect();
+    result.extend(
+        records
+            .into_values()
+            .map(|record| ModelListItem::from_record(record, None)),
+    );
+    for item in &mut result {
+        item.estimate_memory(available_ram);
     }
 
+    result.sort_by(|a, b| {
+        a.provider
+            .to_lowercase()
+            .cmp(&b.provider.to_lowercase())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            .then_with(|| a.model_id.cmp(&b.model_id))
+    });
+
     Ok(result)
 }
 
@@ -174,7 +270,8 @@ pub async fn download_model(
     let models_dir = ModelDownloader::default_models_dir()
         .map_err(|e| format!("Failed to get models directory: {}", e))?;
 
-    let downloader = ModelDownloader::new(models_dir)
+    let timeouts = load_download_timeouts(&conn).await?;
+    let downloader = ModelDownloader::with_timeouts(models_dir, timeouts)
         .map_err(|e| format!("Failed to create downloader: {}", e))?;
 
     let download_url = model_info.download_url.clone();
@@ -186,8 +283,12 @@ pub async fn download_model(
 
     tokio::spawn(async move {
         let app_progress = app_clone.clone();
+        // Last reported byte count, kept so a failed download shows how far it got
+        let downloaded_bytes = Arc::new(AtomicU64::new(0));
+        let progress_bytes = downloaded_bytes.clone();
         let result = downloader
             .download_model(&model_id_clone, &download_url, move |progress| {
+                progress_bytes.store(progress.downloaded_bytes, Ordering::Relaxed);
                 // Emit progress event to frontend
                 let _ = app_progress.emit("model-download-progress", &progress);
             })
@@ -198,49 +299,60 @@ pub async fn download_model(
             match result {
                 Ok(file_path) => {
                     // Verify checksum
-                    let checksum_valid = if let Some(expected_checksum) =
-                        models::Entity::find_by_id(db_id)
-                            .one(&conn)
-                            .await
-                            .ok()
-                            .flatten()
-                            .and_then(|m| m.checksum)
-                    {
-                        ModelValidator::verify_checksum(&file_path, &expected_checksum)
-                            .await
-                            .unwrap_or(false)
-                    } else {
-                        true // No checksum to verify
-                    };
+                    let expected_checksum = models::Entity::find_by_id(db_id)
+                        .one(&conn)
+                        .await
+                        .ok()
+                        .flatten()
+                        .and_then(|m| m.checksum);
+                    let complete = DownloadComplete::from_file(
+                        &model_id_clone2,
+                        &file_path,
+                        expected_checksum.as_deref(),
+                    )
+                    .await;
+                    let checksum_valid = complete
+                        .as_ref()
+                        .map(|c| c.checksum_verified)
+                        .unwrap_or(false);
+
+                    // Read real metadata from the file; a failure only leaves it unset
+                    let metadata = ModelValidator::extract_metadata(&file_path)
+                        .await
+                        .unwrap_or_else(|e| {
+                            log::warn!("Failed to read model metadata: {}", e);
+                            ModelMetadata::default()
+                        });
 
                     if let Ok(Some(model)) = models::Entity::find_by_id(db_id).one(&conn).await {
                         let mut active: models::ActiveModel = model.into();
+                        apply_metadata(&mut active, &metadata);
                         active.status = Set("downloaded".to_string());
                         active.file_path = Set(Some(file_path.to_string_lossy().to_string()));
+                        if let Ok(complete) = &complete {
+                            active.downloaded_size = Set(i64::try_from(complete.total_bytes).ok());
+                        }
                         active.checksum_verified = Set(checksum_valid);
                         active.download_completed_at =
                             Set(Some(chrono::Utc::now().naive_utc()));
                         let _ = active.update(&conn).await;
                     }
 
-                    // Emit completion event
-                    let _ = app_clone.emit(
-                        "model-download-progress",
-                        &DownloadProgress {
-                            model_id: model_id_clone2.clone(),
-                            downloaded_bytes: 0,
-                            total_bytes: 0,
-                            percentage: 100.0,
-                            speed_mbps: 0.0,
-                            status: DownloadStatus::Completed,
-                        },
-                    );
+                    // Emit completion event with the file's location and checksum
+                    match complete {
+                        Ok(complete) => {
+                            let _ = app_clone.emit("model-download-complete", &complete);
+                        }
+                        Err(e) => log::error!("Failed to checksum downloaded model: {}", e),
+                    }
                 }
                 Err(e) => {
                     // Update status to failed
                     if let Ok(Some(model)) = models::Entity::find_by_id(db_id).one(&conn).await {
                         let mut active: models::ActiveModel = model.into();
                         active.status = Set("failed".to_string());
+                        active.downloaded_size =
+                            Set(i64::try_from(downloaded_bytes.load(Ordering::Relaxed)).ok());
                         let _ = active.update(&conn).await;
                     }
 
@@ -254,6 +366,7 @@ pub async fn download_model(
                             percentage: 0.0,
                             speed_mbps: 0.0,
                             status: DownloadStatus::Failed,
+                            message: None,
                         },
                     );
 
@@ -312,20 +425,80 @@ pub async fn delete_model(
     Ok(format!("Model deleted: {}", model_id))
 }
 
-/// Set the active model
+/// Result of checking that a model can be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCompatibility {
+    pub model_id: String,
+    /// Architecture read from the file, e.g. "llama"
+    pub architecture: String,
+}
+
+/// Check that a model's file can be loaded, without loading its weights
+///
+/// Only downloaded GGUF models pass: the file must have a valid GGUF header,
+/// a supported architecture and a tokenizer.
+async fn check_compatibility(model: &models::Model) -> Result<ModelCompatibility, String> {
+    if model.status != "downloaded" {
+        return Err("Model must be downloaded before activation".to_string());
+    }
+    if !model.format.eq_ignore_ascii_case(ModelFormat::Gguf.as_str()) {
+        return Err(format!(
+            "Model {} is in {} format; only GGUF models can be loaded",
+            model.model_id, model.format
+        ));
+    }
+
+    let path = model
+        .file_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("Model {} has no file on disk", model.model_id))?;
+    let architecture =
+        tokio::task::spawn_blocking(move || InferenceEngine::check_gguf_compatibility(&path))
+            .await
+            .map_err(|e| format!("Validation task failed: {}", e))?
+            .map_err(|e| format!("Model {} can't be loaded: {:#}", model.model_id, e))?;
+
+    Ok(ModelCompatibility {
+        model_id: model.model_id.clone(),
+        architecture: architecture.to_string(),
+    })
+}
+
+async fn find_model(conn: &DatabaseConnection, model_id: &str) -> Result<models::Model, String> {
+    models::Entity::find()
+        .filter(models::Column::ModelId.eq(model_id))
+        .one(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| format!("Model not found: {}", model_id))
+}
+
+/// Check whether a model would load, so the UI can warn before activating it
 #[tauri::command]
-pub async fn set_active_model(
+pub async fn validate_model_compatibility(
     model_id: String,
     db: State<'_, DatabaseManager>,
-) -> Result<String, String> {
+) -> Result<ModelCompatibility, String> {
     let conn = db
         .get_connection()
         .await
         .ok_or("Database not initialized")?;
 
+    check_compatibility(&find_model(&conn, &model_id).await?).await
+}
+
+/// Make a model the only active one, after checking it can be loaded
+///
+/// A model that fails the check is refused and the current active model
+/// stays active.
+pub(crate) async fn activate_model(conn: &DatabaseConnection, model_id: &str) -> Result<(), String> {
+    let model = find_model(conn, model_id).await?;
+    check_compatibility(&model).await?;
+
     // Deactivate all models
     let all_models = models::Entity::find()
-        .all(&conn)
+        .all(conn)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
@@ -333,31 +506,40 @@ pub async fn set_active_model(
         let mut active: models::ActiveModel = model.into();
         active.is_active = Set(false);
         active
-            .update(&conn)
+            .update(conn)
             .await
             .map_err(|e| format!("Failed to update model: {}", e))?;
     }
 
-    // Activate selected model
-    let model = models::Entity::find()
-        .filter(models::Column::ModelId.eq(&model_id))
-        .one(&conn)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?
-        .ok_or_else(|| format!("Model not found: {}", model_id))?;
-
-    if model.status != "downloaded" {
-        return Err("Model must be downloaded before activation".to_string());
-    }
-
     let mut active: models::ActiveModel = model.into();
     active.is_active = Set(true);
     active.last_used_at = Set(Some(chrono::Utc::now().naive_utc()));
     active
-        .update(&conn)
+        .update(conn)
         .await
         .map_err(|e| format!("Failed to activate model: {}", e))?;
 
+    Ok(())
+}
+
+/// Set the active model
+#[tauri::command]
+pub async fn set_active_model(
+    model_id: String,
+    db: State<'_, DatabaseManager>,
+    inference_engine: State<'_, Arc<Mutex<InferenceEngine>>>,
+) -> Result<String, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    activate_model(&conn, &model_id).await?;
+
+    // Restore the generation parameters saved for this model
+    let engine = inference_engine.lock().await;
+    apply_generation_config(&engine, &conn, &model_id).await;
+
     Ok(format!("Model activated: {}", model_id))
 }
 
@@ -377,30 +559,62 @@ pub async fn get_active_model(
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    if let Some(model) = active_model {
-        Ok(Some(ModelListItem {
-            id: Some(model.id),
-            model_id: model.model_id.clone(),
-            name: model.name.clone(),
-            description: model.description.unwrap_or_default(),
-            provider: model.provider.clone(),
-            size: model.size.clone(),
-            parameters: model.parameters.clone(),
-            quantization: model.quantization.clone(),
-            format: model.format.clone(),
-            status: model.status.clone(),
-            file_size: model.file_size.unwrap_or(0),
-            is_active: model.is_active,
-            is_downloaded: model.status == "downloaded",
-            download_url: model.download_url.unwrap_or_default(),
-            tags: serde_json::from_str(&model.tags.unwrap_or_else(|| "[]".to_string()))
-                .unwrap_or_default(),
-        }))
-    } else {
-        Ok(None)
+    match active_model {
+        Some(model) => {
+            let model = backfill_metadata(&conn, model).await;
+            let registry = ModelRegistry::new();
+            let info = registry.get_model(&model.model_id);
+            Ok(Some(ModelListItem::from_record(model, info)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Copy extracted file metadata onto a model row, keeping existing values for absent fields
+fn apply_metadata(active: &mut models::ActiveModel, metadata: &ModelMetadata) {
+    if let Some(architecture) = &metadata.architecture {
+        active.architecture = Set(Some(architecture.clone()));
+    }
+    if let Some(context_length) = metadata.context_length {
+        active.context_length = Set(i64::try_from(context_length).ok());
+    }
+    if let Some(quantization) = &metadata.quantization {
+        active.quantization = Set(Some(quantization.clone()));
+    }
+    if let Some(bits) = metadata.quantization_bits {
+        active.quantization_bits = Set(i32::try_from(bits).ok());
     }
 }
 
+/// Fill in metadata for models downloaded before it was recorded
+async fn backfill_metadata(
+    conn: &DatabaseConnection,
+    model: models::Model,
+) -> models::Model {
+    if model.architecture.is_some() || model.context_length.is_some() {
+        return model;
+    }
+
+    let Some(file_path) = model.file_path.as_ref().map(PathBuf::from) else {
+        return model;
+    };
+    if !file_path.exists() {
+        return model;
+    }
+
+    let metadata = match ModelValidator::extract_metadata(&file_path).await {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            log::warn!("Failed to read model metadata: {}", e);
+            return model;
+        }
+    };
+
+    let mut active: models::ActiveModel = model.clone().into();
+    apply_metadata(&mut active, &metadata);
+    active.update(conn).await.unwrap_or(model)
+}
+
 /// Cancel an ongoing download
 #[tauri::command]
 pub async fn cancel_download(
@@ -506,6 +720,46 @@ pub async fn check_disk_space() -> Result<u64, String> {
         .map_err(|e| format!("Failed to check disk space: {}", e))
 }
 
+/// Load download timeouts from settings (defaults when unset)
+async fn load_download_timeouts(conn: &DatabaseConnection) -> Result<DownloadTimeouts, String> {
+    match read_setting(conn, DOWNLOAD_TIMEOUTS_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid download timeouts in settings: {}", e)),
+        None => Ok(DownloadTimeouts::default()),
+    }
+}
+
+/// Get the connect/read timeouts used for model downloads
+#[tauri::command]
+pub async fn get_download_timeouts(
+    db: State<'_, DatabaseManager>,
+) -> Result<DownloadTimeouts, String> {
+    match db.get_connection().await {
+        Some(conn) => load_download_timeouts(&conn).await,
+        None => Ok(DownloadTimeouts::default()),
+    }
+}
+
+/// Configure the connect/read timeouts used for model downloads
+///
+/// Takes effect for the next download.
+#[tauri::command]
+pub async fn set_download_timeouts(
+    timeouts: DownloadTimeouts,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    timeouts
+        .validate()
+        .map_err(|e| format!("Invalid download timeouts: {}", e))?;
+
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    let json = serde_json::to_string(&timeouts)
+        .map_err(|e| format!("Failed to serialize download timeouts: {}", e))?;
+
+    write_setting(&conn, DOWNLOAD_TIMEOUTS_KEY.to_string(), json).await
+}
+
 /// Import a model from a local file
 #[tauri::command]
 pub async fn import_model_file(
@@ -522,39 +776,188 @@ pub async fn import_model_file(
         .await
         .ok_or("Database not initialized")?;
 
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+
+    let details = ImportDetails {
+        model_id,
+        name,
+        description,
+        size,
+        parameters,
+        quantization: None,
+        license: None,
+        tags: Vec::new(),
+        expected_checksum: None,
+    };
+    let model = import_into(&conn, Path::new(&file_path), &models_dir, details).await?;
+
+    Ok(format!("Model '{}' imported successfully", model.model_id))
+}
+
+/// User-supplied description of an imported model
+struct ImportDetails {
+    model_id: String,
+    name: String,
+    description: String,
+    size: String,
+    parameters: String,
+    quantization: Option<String>,
+    license: Option<String>,
+    tags: Vec<String>,
+    /// SHA-256 the file must match, if known
+    expected_checksum: Option<String>,
+}
+
+/// Models to import from local files, for machines without network access
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub models: Vec<ManifestModel>,
+}
+
+/// One model in a `ModelManifest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestModel {
+    pub model_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Model file; relative paths are resolved against the manifest's directory
+    pub file_path: String,
+    /// Expected SHA-256 of the file, in hex
+    pub checksum: String,
+    #[serde(default = "default_manifest_size")]
+    pub size: String,
+    #[serde(default)]
+    pub parameters: String,
+    #[serde(default)]
+    pub quantization: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_manifest_size() -> String {
+    "unknown".to_string()
+}
+
+/// Outcome of importing one manifest entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestImportResult {
+    pub model_id: String,
+    pub imported: bool,
+    pub error: Option<String>,
+}
+
+/// Import every model listed in a JSON manifest
+///
+/// Each file is validated and checked against its checksum, then registered
+/// as downloaded. Nothing is fetched over the network, so this works on
+/// air-gapped machines. One failing entry doesn't stop the others.
+#[tauri::command]
+pub async fn import_model_manifest(
+    manifest_path: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<Vec<ManifestImportResult>, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+
+    import_manifest(&conn, Path::new(&manifest_path), &models_dir).await
+}
+
+async fn import_manifest(
+    conn: &DatabaseConnection,
+    manifest_path: &Path,
+    models_dir: &Path,
+) -> Result<Vec<ManifestImportResult>, String> {
+    let json = tokio::fs::read_to_string(manifest_path)
+        .await
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest: ModelManifest =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid manifest: {}", e))?;
+    let base_dir = manifest_path.parent().unwrap_or(Path::new(""));
+
+    let mut results = Vec::with_capacity(manifest.models.len());
+    for entry in manifest.models {
+        let model_id = entry.model_id.clone();
+        let source = base_dir.join(&entry.file_path);
+        let details = ImportDetails {
+            model_id: entry.model_id,
+            name: entry.name,
+            description: entry.description,
+            size: entry.size,
+            parameters: entry.parameters,
+            quantization: entry.quantization,
+            license: entry.license,
+            tags: entry.tags,
+            expected_checksum: Some(entry.checksum),
+        };
+
+        let error = match import_into(conn, &source, models_dir, details).await {
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Failed to import '{}' from manifest: {}", model_id, e);
+                Some(e)
+            }
+        };
+        results.push(ManifestImportResult {
+            model_id,
+            imported: error.is_none(),
+            error,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Validate a model file, copy it into `models_dir` and record it with the
+/// format detected from its contents
+async fn import_into(
+    conn: &DatabaseConnection,
+    source_path: &Path,
+    models_dir: &Path,
+    details: ImportDetails,
+) -> Result<models::Model, String> {
     // Validate file exists
-    let source_path = PathBuf::from(&file_path);
     if !source_path.exists() {
         return Err("File does not exist".to_string());
     }
 
     // Validate file format
-    ModelValidator::validate_model_file(&source_path)
+    let format = ModelValidator::validate_model_file(source_path)
         .await
-        .map_err(|e| format!("Invalid model file: {}", e))?;
+        .map_err(|e| format!("Invalid model file: {:#}", e))?;
 
     // Get file size
-    let file_size = tokio::fs::metadata(&source_path)
+    let file_size = tokio::fs::metadata(source_path)
         .await
         .map_err(|e| format!("Failed to get file metadata: {}", e))?
         .len();
 
     // Calculate checksum
-    let checksum = ModelValidator::calculate_sha256(&source_path)
+    let checksum = ModelValidator::calculate_sha256(source_path)
         .await
         .map_err(|e| format!("Failed to calculate checksum: {}", e))?;
+    if let Some(expected) = &details.expected_checksum {
+        if !ModelValidator::checksum_matches(&checksum, expected) {
+            return Err(format!(
+                "Checksum mismatch: expected {}, got {}",
+                expected, checksum
+            ));
+        }
+    }
 
     // Copy to models directory
-    let models_dir = ModelDownloader::default_models_dir()
-        .map_err(|e| format!("Failed to get models directory: {}", e))?;
-
-    tokio::fs::create_dir_all(&models_dir)
+    tokio::fs::create_dir_all(models_dir)
         .await
         .map_err(|e| format!("Failed to create models directory: {}", e))?;
 
-    let _downloader = ModelDownloader::new(models_dir.clone())
-        .map_err(|e| format!("Failed to create downloader: {}", e))?;
-
     let filename = source_path
         .file_name()
         .and_then(|n| n.to_str())
@@ -562,35 +965,959 @@ pub async fn import_model_file(
 
     let dest_path = models_dir.join(filename);
 
-    tokio::fs::copy(&source_path, &dest_path)
+    tokio::fs::copy(source_path, &dest_path)
         .await
         .map_err(|e| format!("Failed to copy file: {}", e))?;
 
-    // Add to database
-    let new_model = models::ActiveModel {
-        model_id: Set(model_id.clone()),
-        name: Set(name),
-        description: Set(Some(description)),
+    record_model(conn, &dest_path, format, file_size, checksum, details, None).await
+}
+
+/// Insert a downloaded model record for a file already in the models directory
+async fn record_model(
+    conn: &DatabaseConnection,
+    path: &Path,
+    format: ModelFormat,
+    file_size: u64,
+    checksum: String,
+    details: ImportDetails,
+    metadata: Option<&ModelMetadata>,
+) -> Result<models::Model, String> {
+    let mut new_model = models::ActiveModel {
+        model_id: Set(details.model_id),
+        name: Set(details.name),
+        description: Set(Some(details.description)),
         provider: Set("local".to_string()),
-        size: Set(size),
-        parameters: Set(parameters),
-        quantization: Set(None),
-        format: Set("gguf".to_string()),
+        size: Set(details.size),
+        parameters: Set(details.parameters),
+        quantization: Set(details.quantization),
+        format: Set(format.as_str().to_string()),
         status: Set("downloaded".to_string()),
-        file_path: Set(Some(dest_path.to_string_lossy().to_string())),
+        file_path: Set(Some(path.to_string_lossy().to_string())),
         file_size: Set(Some(file_size as i64)),
         checksum: Set(Some(checksum)),
         checksum_verified: Set(true),
-        license: Set(Some("Unknown".to_string())),
-        tags: Set(Some("[]".to_string())),
+        license: Set(Some(details.license.unwrap_or_else(|| "Unknown".to_string()))),
+        tags: Set(Some(
+            serde_json::to_string(&details.tags).unwrap_or_else(|_| "[]".to_string()),
+        )),
         download_completed_at: Set(Some(chrono::Utc::now().naive_utc())),
         ..Default::default()
     };
+    if let Some(metadata) = metadata {
+        apply_metadata(&mut new_model, metadata);
+    }
 
     new_model
+        .insert(conn)
+        .await
+        .map_err(|e| format!("Failed to add model: {}", e))
+}
+
+/// A file in the models directory that no model record points to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrphanedModelFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Leftover `.tmp` file of an interrupted download
+    pub is_partial_download: bool,
+}
+
+/// Files removed by `prune_orphaned_models`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrunedModels {
+    pub removed: Vec<OrphanedModelFile>,
+    pub bytes_reclaimed: u64,
+}
+
+/// Path used to compare model files, resolving symlinks and `..` when the file exists
+fn comparable_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Weight files and partial downloads in `models_dir` that no model record references
+///
+/// Other files, such as a model's `tokenizer.json` or config, are never
+/// orphans: models need them without a record pointing at them. `.tmp` files
+/// are skipped while a download is running, as one of them is being written to.
+pub(crate) async fn find_orphaned_model_files(
+    conn: &DatabaseConnection,
+    models_dir: &Path,
+    download_in_progress: bool,
+) -> Result<Vec<OrphanedModelFile>, String> {
+    if !models_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let referenced: HashSet<PathBuf> = models::Entity::find()
+        .all(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .into_iter()
+        .filter_map(|model| model.file_path)
+        .map(|file_path| comparable_path(Path::new(&file_path)))
+        .collect();
+
+    let mut entries = tokio::fs::read_dir(models_dir)
+        .await
+        .map_err(|e| format!("Failed to read models directory: {}", e))?;
+
+    let mut orphans = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("Failed to read models directory: {}", e))?
+    {
+        let metadata = match entry.metadata().await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+
+        let path = entry.path();
+        let is_partial_download = path.extension().and_then(|ext| ext.to_str()) == Some("tmp");
+        if is_partial_download && download_in_progress {
+            continue;
+        }
+        if !is_partial_download && !is_weight_file(&path) {
+            continue;
+        }
+        if referenced.contains(&comparable_path(&path)) {
+            continue;
+        }
+
+        orphans.push(OrphanedModelFile {
+            path: path.to_string_lossy().to_string(),
+            size_bytes: metadata.len(),
+            is_partial_download,
+        });
+    }
+
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(orphans)
+}
+
+/// Whether `path` is a GGUF or safetensors weight file, going by its extension
+fn is_weight_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("gguf") || ext.eq_ignore_ascii_case("safetensors")
+        })
+}
+
+/// Delete orphaned files, returning what was removed
+pub(crate) async fn remove_orphaned_model_files(
+    orphans: Vec<OrphanedModelFile>,
+) -> Result<PrunedModels, String> {
+    let mut removed = Vec::new();
+    let mut bytes_reclaimed = 0;
+
+    for orphan in orphans {
+        tokio::fs::remove_file(&orphan.path)
+            .await
+            .map_err(|e| format!("Failed to delete {}: {}", orphan.path, e))?;
+        bytes_reclaimed += orphan.size_bytes;
+        removed.push(orphan);
+    }
+
+    Ok(PrunedModels {
+        removed,
+        bytes_reclaimed,
+    })
+}
+
+/// List weight files in the models directory that no model record points to
+#[tauri::command]
+pub async fn list_orphaned_models(
+    db: State<'_, DatabaseManager>,
+    download_state: State<'_, DownloadState>,
+) -> Result<Vec<OrphanedModelFile>, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+    let download_in_progress = download_state.lock().await.is_some();
+
+    find_orphaned_model_files(&conn, &models_dir, download_in_progress).await
+}
+
+/// Delete orphaned model files, including leftovers of interrupted downloads
+///
+/// Deletion is permanent, so the caller must pass `confirm: true` (after
+/// showing the user what `list_orphaned_models` found).
+#[tauri::command]
+pub async fn prune_orphaned_models(
+    confirm: bool,
+    db: State<'_, DatabaseManager>,
+    download_state: State<'_, DownloadState>,
+) -> Result<PrunedModels, String> {
+    if !confirm {
+        return Err("Pruning deletes files permanently and must be confirmed".to_string());
+    }
+
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+
+    // Hold the download lock so no download starts while files are removed
+    let download_state = download_state.lock().await;
+    let orphans = find_orphaned_model_files(&conn, &models_dir, download_state.is_some()).await?;
+    let pruned = remove_orphaned_model_files(orphans).await?;
+    drop(download_state);
+
+    log::info!(
+        "Pruned {} orphaned model files ({} bytes)",
+        pruned.removed.len(),
+        pruned.bytes_reclaimed
+    );
+    Ok(pruned)
+}
+
+/// A downloaded model whose file no longer matches its stored checksum
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecksumMismatch {
+    pub model_id: String,
+    pub file_path: String,
+    pub expected_checksum: String,
+    /// Checksum of the file now, `None` if it couldn't be read
+    pub actual_checksum: Option<String>,
+    /// Why the file couldn't be read, e.g. it was deleted
+    pub error: Option<String>,
+}
+
+/// Result of re-verifying every downloaded model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelVerificationReport {
+    /// Models whose file was hashed and compared
+    pub checked: usize,
+    pub mismatches: Vec<ChecksumMismatch>,
+    /// Downloaded models without a stored checksum to compare against
+    pub unverifiable: Vec<String>,
+}
+
+/// Re-hash the file of every downloaded model and update `checksum_verified`
+async fn verify_downloaded_models(
+    conn: &DatabaseConnection,
+) -> Result<ModelVerificationReport, String> {
+    let downloaded = models::Entity::find()
+        .filter(models::Column::Status.eq("downloaded"))
+        .filter(models::Column::FilePath.is_not_null())
+        .all(conn)
+        .await
+        .map_err(|e| format!("Failed to list models: {}", e))?;
+
+    let mut report = ModelVerificationReport {
+        checked: 0,
+        mismatches: Vec::new(),
+        unverifiable: Vec::new(),
+    };
+    for model in downloaded {
+        let (Some(file_path), Some(expected)) = (model.file_path.clone(), model.checksum.clone())
+        else {
+            report.unverifiable.push(model.model_id);
+            continue;
+        };
+
+        report.checked += 1;
+        let (actual, error) = match ModelValidator::calculate_sha256(Path::new(&file_path)).await {
+            Ok(checksum) => (Some(checksum), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let verified = actual
+            .as_deref()
+            .is_some_and(|actual| ModelValidator::checksum_matches(actual, &expected));
+
+        if !verified {
+            log::warn!("Checksum mismatch for model {}", model.model_id);
+            report.mismatches.push(ChecksumMismatch {
+                model_id: model.model_id.clone(),
+                file_path,
+                expected_checksum: expected,
+                actual_checksum: actual,
+                error,
+            });
+        }
+        if model.checksum_verified != verified {
+            let mut active: models::ActiveModel = model.into();
+            active.checksum_verified = Set(verified);
+            active
+                .update(conn)
+                .await
+                .map_err(|e| format!("Failed to update model: {}", e))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Recompute the checksum of every downloaded model and report mismatches
+///
+/// Catches files corrupted on disk or copied incompletely since download.
+/// Hashing reads each file in full, so this can take a while.
+#[tauri::command]
+pub async fn verify_all_models(
+    db: State<'_, DatabaseManager>,
+) -> Result<ModelVerificationReport, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let report = verify_downloaded_models(&conn).await?;
+    log::info!(
+        "Verified {} models, {} mismatched",
+        report.checked,
+        report.mismatches.len()
+    );
+    Ok(report)
+}
+
+/// A model file in the models directory that isn't registered yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredModel {
+    pub path: String,
+    pub size_bytes: u64,
+    /// "gguf" or "safetensors", detected from the contents
+    pub format: String,
+    pub metadata: ModelMetadata,
+    /// Model ID to offer when registering, derived from the file name
+    pub suggested_model_id: String,
+    /// Display name to offer when registering (the file name without extension)
+    pub suggested_name: String,
+}
+
+/// Model ID for a file name: lowercase, with runs of other characters as '-'
+fn suggested_model_id(stem: &str) -> String {
+    stem.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// GGUF and safetensors files in `models_dir` that no model record points to
+///
+/// Files that fail validation are left out, as are other orphans such as
+/// partial downloads; `list_orphaned_models` still reports those.
+pub(crate) async fn discover_unregistered_models(
+    conn: &DatabaseConnection,
+    models_dir: &Path,
+) -> Result<Vec<DiscoveredModel>, String> {
+    let mut discovered = Vec::new();
+
+    for orphan in find_orphaned_model_files(conn, models_dir, true).await? {
+        let path = PathBuf::from(&orphan.path);
+        if orphan.is_partial_download {
+            continue;
+        }
+
+        let format = match ModelValidator::validate_model_file(&path).await {
+            Ok(format @ (ModelFormat::Gguf | ModelFormat::Safetensors)) => format,
+            Ok(_) => continue,
+            Err(e) => {
+                log::warn!("Skipping {}: {:#}", path.display(), e);
+                continue;
+            }
+        };
+        let metadata = ModelValidator::extract_metadata(&path)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read metadata of {}: {}", path.display(), e);
+                ModelMetadata::default()
+            });
+
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        discovered.push(DiscoveredModel {
+            path: orphan.path,
+            size_bytes: orphan.size_bytes,
+            format: format.as_str().to_string(),
+            metadata,
+            suggested_model_id: suggested_model_id(&stem),
+            suggested_name: stem,
+        });
+    }
+
+    Ok(discovered)
+}
+
+/// Register a discovered file in place, without copying it
+async fn register_discovered(
+    conn: &DatabaseConnection,
+    models_dir: &Path,
+    file_path: &Path,
+    details: ImportDetails,
+) -> Result<models::Model, String> {
+    let target = comparable_path(file_path);
+    let discovered = discover_unregistered_models(conn, models_dir)
+        .await?
+        .into_iter()
+        .find(|model| comparable_path(Path::new(&model.path)) == target)
+        .ok_or_else(|| {
+            format!(
+                "{} is not an unregistered model in the models directory",
+                file_path.display()
+            )
+        })?;
+
+    let checksum = ModelValidator::calculate_sha256(file_path)
+        .await
+        .map_err(|e| format!("Failed to calculate checksum: {}", e))?;
+    let format = match discovered.format.as_str() {
+        "gguf" => ModelFormat::Gguf,
+        _ => ModelFormat::Safetensors,
+    };
+
+    record_model(
+        conn,
+        Path::new(&discovered.path),
+        format,
+        discovered.size_bytes,
+        checksum,
+        details,
+        Some(&discovered.metadata),
+    )
+    .await
+}
+
+/// List model files copied into the models directory by hand that aren't
+/// registered yet, with the metadata read from each file
+#[tauri::command]
+pub async fn scan_for_models(
+    db: State<'_, DatabaseManager>,
+) -> Result<Vec<DiscoveredModel>, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+
+    discover_unregistered_models(&conn, &models_dir).await
+}
+
+/// Register a file found by `scan_for_models` as a downloaded model
+#[tauri::command]
+pub async fn register_discovered_model(
+    file_path: String,
+    model_id: String,
+    name: String,
+    parameters: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<String, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+    let models_dir = ModelDownloader::default_models_dir()
+        .map_err(|e| format!("Failed to get models directory: {}", e))?;
+
+    let details = ImportDetails {
+        model_id,
+        name,
+        description: String::new(),
+        size: "unknown".to_string(),
+        parameters,
+        quantization: None,
+        license: None,
+        tags: Vec::new(),
+        expected_checksum: None,
+    };
+    let model = register_discovered(&conn, &models_dir, Path::new(&file_path), details).await?;
+
+    Ok(format!("Model '{}' registered successfully", model.model_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::inference::write_test_gguf;
+
+    async fn test_connection() -> (tempfile::TempDir, DatabaseConnection) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+        (dir, conn)
+    }
+
+    async fn insert_model(conn: &DatabaseConnection, model_id: &str, file_path: Option<&Path>) {
+        models::ActiveModel {
+            model_id: Set(model_id.to_string()),
+            name: Set(model_id.to_string()),
+            provider: Set("local".to_string()),
+            size: Set("small".to_string()),
+            parameters: Set("1B".to_string()),
+            format: Set("gguf".to_string()),
+            status: Set("downloaded".to_string()),
+            file_path: Set(file_path.map(|p| p.to_string_lossy().to_string())),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_files_detected_and_pruned() {
+        let (_db_dir, conn) = test_connection().await;
+        let models_dir = tempfile::tempdir().unwrap();
+        let dir = models_dir.path();
+
+        let kept = dir.join("tinyllama.gguf");
+        std::fs::write(&kept, b"GGUF kept").unwrap();
+        insert_model(&conn, "tinyllama", Some(&kept)).await;
+        // A record without a file (e.g. deleted model) protects nothing
+        insert_model(&conn, "phi-2", None).await;
+
+        std::fs::write(dir.join("renamed-import.gguf"), b"GGUF orphan").unwrap();
+        std::fs::write(dir.join("mistral.gguf.tmp"), b"partial").unwrap();
+        std::fs::create_dir(dir.join("subdir")).unwrap();
+        // Sidecars of the installed model are needed even though no record names them
+        std::fs::write(dir.join("tokenizer.json"), b"{}").unwrap();
+        std::fs::write(dir.join("config.json"), b"{}").unwrap();
+
+        let orphans = find_orphaned_model_files(&conn, dir, false).await.unwrap();
+        let names: Vec<(String, bool)> = orphans
+            .iter()
+            .map(|o| {
+                let name = Path::new(&o.path).file_name().unwrap().to_string_lossy().to_string();
+                (name, o.is_partial_download)
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("mistral.gguf.tmp".to_string(), true),
+                ("renamed-import.gguf".to_string(), false),
+            ]
+        );
+
+        // The partial file may belong to the running download
+        let while_downloading = find_orphaned_model_files(&conn, dir, true).await.unwrap();
+        assert_eq!(while_downloading.len(), 1);
+        assert!(!while_downloading[0].is_partial_download);
+
+        let pruned = remove_orphaned_model_files(orphans).await.unwrap();
+        assert_eq!(pruned.removed.len(), 2);
+        assert_eq!(pruned.bytes_reclaimed, ("GGUF orphan".len() + "partial".len()) as u64);
+
+        assert!(kept.exists());
+        assert!(dir.join("tokenizer.json").exists());
+        assert!(dir.join("config.json").exists());
+        assert!(!dir.join("renamed-import.gguf").exists());
+        assert!(!dir.join("mistral.gguf.tmp").exists());
+        assert!(find_orphaned_model_files(&conn, dir, false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_all_models_reports_tampered_file() {
+        let (_db_dir, conn) = test_connection().await;
+        let models_dir = tempfile::tempdir().unwrap();
+
+        let good = models_dir.path().join("good.gguf");
+        let tampered = models_dir.path().join("tampered.gguf");
+        std::fs::write(&good, b"GGUF good model").unwrap();
+        std::fs::write(&tampered, b"GGUF original model").unwrap();
+        for (model_id, path) in [("good", &good), ("tampered", &tampered)] {
+            let checksum = ModelValidator::calculate_sha256(path).await.unwrap();
+            models::ActiveModel {
+                model_id: Set(model_id.to_string()),
+                name: Set(model_id.to_string()),
+                provider: Set("local".to_string()),
+                size: Set("small".to_string()),
+                parameters: Set("1B".to_string()),
+                format: Set("gguf".to_string()),
+                status: Set("downloaded".to_string()),
+                file_path: Set(Some(path.to_string_lossy().to_string())),
+                checksum: Set(Some(checksum)),
+                checksum_verified: Set(true),
+                ..Default::default()
+            }
+            .insert(&conn)
+            .await
+            .unwrap();
+        }
+        insert_model(&conn, "no-checksum", Some(&good)).await;
+
+        std::fs::write(&tampered, b"GGUF corrupted model").unwrap();
+        let report = verify_downloaded_models(&conn).await.unwrap();
+
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.unverifiable, vec!["no-checksum".to_string()]);
+        assert_eq!(report.mismatches.len(), 1);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(mismatch.model_id, "tampered");
+        assert_eq!(
+            mismatch.actual_checksum.as_deref(),
+            Some(ModelValidator::calculate_sha256(&tampered).await.unwrap().as_str())
+        );
+        assert!(find_model(&conn, "good").await.unwrap().checksum_verified);
+        assert!(!find_model(&conn, "tampered").await.unwrap().checksum_verified);
+
+        // A deleted file is a mismatch too
+        std::fs::remove_file(&good).unwrap();
+        let report = verify_downloaded_models(&conn).await.unwrap();
+        assert_eq!(report.mismatches.len(), 2);
+        let deleted = report.mismatches.iter().find(|m| m.model_id == "good").unwrap();
+        assert!(deleted.actual_checksum.is_none() && deleted.error.is_some());
+        assert!(!find_model(&conn, "good").await.unwrap().checksum_verified);
+    }
+
+    #[tokio::test]
+    async fn test_model_list_order_is_stable() {
+        let (_db_dir, conn) = test_connection().await;
+        let registry = ModelRegistry::new();
+
+        // A registry model part-way through a failed download
+        let registry_id = registry.list_models()[0].model_id.clone();
+        models::ActiveModel {
+            model_id: Set(registry_id.clone()),
+            name: Set(registry.list_models()[0].name.clone()),
+            provider: Set("huggingface".to_string()),
+            size: Set("small".to_string()),
+            parameters: Set("7B".to_string()),
+            format: Set("gguf".to_string()),
+            status: Set("failed".to_string()),
+            file_size: Set(Some(1000)),
+            downloaded_size: Set(Some(400)),
+            is_active: Set(true),
+            ..Default::default()
+        }
         .insert(&conn)
         .await
-        .map_err(|e| format!("Failed to add model: {}", e))?;
+        .unwrap();
+        // Models that only exist in the database
+        insert_model(&conn, "zeta-local", None).await;
+        insert_model(&conn, "alpha-local", None).await;
+
+        let first = collect_model_list(&conn, &registry, None).await.unwrap();
+        assert_eq!(first.len(), registry.list_models().len() + 2);
+
+        let keys: Vec<(String, String)> = first
+            .iter()
+            .map(|m| (m.provider.to_lowercase(), m.name.to_lowercase()))
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+
+        for _ in 0..3 {
+            let again = collect_model_list(&conn, &registry, None).await.unwrap();
+            let ids: Vec<&str> = again.iter().map(|m| m.model_id.as_str()).collect();
+            let first_ids: Vec<&str> = first.iter().map(|m| m.model_id.as_str()).collect();
+            assert_eq!(ids, first_ids);
+        }
+
+        let partial = first.iter().find(|m| m.model_id == registry_id).unwrap();
+        assert_eq!((partial.downloaded_size, partial.file_size), (400, 1000));
+        assert!(!partial.is_downloaded);
+        // Flagged active in the database, but not usable until downloaded
+        assert!(!partial.is_active);
+
+        let local = first.iter().find(|m| m.model_id == "alpha-local").unwrap();
+        assert!(local.is_downloaded);
+        assert!(local.id.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_model_list_reports_memory_estimates() {
+        let (_db_dir, conn) = test_connection().await;
+        let registry = ModelRegistry::new();
+        insert_model(&conn, "custom-local", None).await;
+
+        let list = collect_model_list(&conn, &registry, Some(6_000_000_000)).await.unwrap();
+        let mistral = list
+            .iter()
+            .find(|m| m.model_id == "mistralai/Mistral-7B-Instruct-v0.2")
+            .unwrap();
+        assert_eq!(mistral.estimated_ram, quantization::estimate_ram("7B", "Q4_K_M"));
+        assert_eq!(mistral.recommended_quantization.as_deref(), Some("Q4_K_M"));
+
+        // Without a quantization there's nothing to estimate, but a tier can still be suggested
+        let custom = list.iter().find(|m| m.model_id == "custom-local").unwrap();
+        assert_eq!(custom.estimated_ram, None);
+        assert_eq!(custom.recommended_quantization.as_deref(), Some("F16"));
+
+        // Unknown free memory gives no recommendation
+        let list = collect_model_list(&conn, &registry, None).await.unwrap();
+        assert!(list.iter().all(|m| m.recommended_quantization.is_none()));
+        assert!(list.iter().any(|m| m.estimated_ram.is_some()));
+    }
+
+    fn import_details(model_id: &str) -> ImportDetails {
+        ImportDetails {
+            model_id: model_id.to_string(),
+            name: model_id.to_string(),
+            description: String::new(),
+            size: "small".to_string(),
+            parameters: "1B".to_string(),
+            quantization: None,
+            license: None,
+            tags: Vec::new(),
+            expected_checksum: None,
+        }
+    }
+
+    /// GGUF file with no metadata or tensors, distinguished by `name`
+    fn write_gguf_fixture(path: &Path, name: &str) {
+        write_test_gguf(path, &[("general.name", name)], &[]);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_import_reports_each_model() {
+        let (_db_dir, conn) = test_connection().await;
+        let bundle = tempfile::tempdir().unwrap();
+        let models_dir = tempfile::tempdir().unwrap();
+
+        write_gguf_fixture(&bundle.path().join("good.gguf"), "good");
+        write_gguf_fixture(&bundle.path().join("tampered.gguf"), "tampered");
+        let good_checksum = ModelValidator::calculate_sha256(&bundle.path().join("good.gguf"))
+            .await
+            .unwrap();
+
+        let manifest = serde_json::json!({
+            "models": [
+                {
+                    "model_id": "offline/good",
+                    "name": "Good Model",
+                    "file_path": "good.gguf",
+                    "checksum": good_checksum.to_uppercase(),
+                    "parameters": "7B",
+                    "quantization": "Q4_K_M",
+                    "license": "Apache 2.0",
+                    "tags": ["offline"]
+                },
+                {
+                    "model_id": "offline/tampered",
+                    "name": "Tampered Model",
+                    "file_path": "tampered.gguf",
+                    "checksum": good_checksum
+                },
+                {
+                    "model_id": "offline/missing",
+                    "name": "Missing Model",
+                    "file_path": "missing.gguf",
+                    "checksum": good_checksum
+                }
+            ]
+        });
+        let manifest_path = bundle.path().join("manifest.json");
+        std::fs::write(&manifest_path, manifest.to_string()).unwrap();
+
+        let results = import_manifest(&conn, &manifest_path, models_dir.path())
+            .await
+            .unwrap();
+        let outcome: Vec<(&str, bool)> = results
+            .iter()
+            .map(|r| (r.model_id.as_str(), r.imported))
+            .collect();
+        assert_eq!(
+            outcome,
+            vec![
+                ("offline/good", true),
+                ("offline/tampered", false),
+                ("offline/missing", false)
+            ]
+        );
+        assert!(results[1].error.as_deref().unwrap().contains("Checksum mismatch"));
+        assert!(results[2].error.as_deref().unwrap().contains("does not exist"));
+
+        // Only the verified model is registered, and the rejected file isn't copied
+        let stored = models::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        let good = &stored[0];
+        assert_eq!(good.model_id, "offline/good");
+        assert_eq!(good.status, "downloaded");
+        assert_eq!(good.quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(good.license.as_deref(), Some("Apache 2.0"));
+        assert_eq!(good.tags.as_deref(), Some(r#"["offline"]"#));
+        assert!(models_dir.path().join("good.gguf").exists());
+        assert!(!models_dir.path().join("tampered.gguf").exists());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_manifest_is_rejected() {
+        let (_db_dir, conn) = test_connection().await;
+        let bundle = tempfile::tempdir().unwrap();
+        let manifest_path = bundle.path().join("manifest.json");
+        std::fs::write(&manifest_path, r#"{"models": [{"model_id": "no-file"}]}"#).unwrap();
+
+        let err = import_manifest(&conn, &manifest_path, bundle.path())
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid manifest"));
+    }
+
+    #[tokio::test]
+    async fn test_import_safetensors_records_format() {
+        let (_db_dir, conn) = test_connection().await;
+        let source_dir = tempfile::tempdir().unwrap();
+        let models_dir = tempfile::tempdir().unwrap();
+
+        let source = source_dir.path().join("tiny.safetensors");
+        // One BF16 tensor: header length, JSON header, then the tensor data
+        let header = br#"{"w":{"dtype":"BF16","shape":[1],"data_offsets":[0,2]}}"#;
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header);
+        bytes.extend_from_slice(&[0, 0]);
+        std::fs::write(&source, bytes).unwrap();
+        let expected_checksum = ModelValidator::calculate_sha256(&source).await.unwrap();
+
+        let model = import_into(&conn, &source, models_dir.path(), import_details("tiny"))
+            .await
+            .unwrap();
+        assert_eq!(model.format, "safetensors");
+        assert!(model.checksum_verified);
+        assert_eq!(model.checksum.as_deref(), Some(expected_checksum.as_str()));
+        assert_eq!(model.status, "downloaded");
+
+        let copied = models_dir.path().join("tiny.safetensors");
+        assert!(copied.exists());
+        assert_eq!(model.file_path.as_deref(), Some(copied.to_string_lossy().as_ref()));
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_invalid_model_file() {
+        let (_db_dir, conn) = test_connection().await;
+        let source_dir = tempfile::tempdir().unwrap();
+        let models_dir = tempfile::tempdir().unwrap();
+
+        let source = source_dir.path().join("broken.safetensors");
+        std::fs::write(&source, b"not a model").unwrap();
+
+        let err = import_into(&conn, &source, models_dir.path(), import_details("broken"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid model file"));
+        assert!(models::Entity::find().all(&conn).await.unwrap().is_empty());
+    }
+
+    /// Weightless GGUF file with an architecture and, for "llama", a small vocabulary
+    fn write_gguf_header(path: &Path, architecture: &str) {
+        let tokens = ["<unk>", "\u{2581}lease"];
+        write_test_gguf(path, &[("general.architecture", architecture)], &tokens);
+    }
 
-    Ok(format!("Model '{}' imported successfully", model_id))
+    async fn is_active(conn: &DatabaseConnection, model_id: &str) -> bool {
+        find_model(conn, model_id).await.unwrap().is_active
+    }
+
+    #[tokio::test]
+    async fn test_activation_requires_loadable_gguf() {
+        let (_db_dir, conn) = test_connection().await;
+        let models_dir = tempfile::tempdir().unwrap();
+        let dir = models_dir.path();
+
+        let good = dir.join("good.gguf");
+        write_gguf_header(&good, "llama");
+        insert_model(&conn, "good", Some(&good)).await;
+
+        let compatibility = check_compatibility(&find_model(&conn, "good").await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(compatibility.architecture, "llama");
+        activate_model(&conn, "good").await.unwrap();
+        assert!(is_active(&conn, "good").await);
+
+        let junk = dir.join("junk.gguf");
+        std::fs::write(&junk, b"GGUF but nothing else that makes sense").unwrap();
+        insert_model(&conn, "junk", Some(&junk)).await;
+        let err = activate_model(&conn, "junk").await.unwrap_err();
+        assert!(err.contains("Model junk can't be loaded"), "{}", err);
+        assert!(err.contains("Not a valid GGUF file"), "{}", err);
+
+        let gemma = dir.join("gemma.gguf");
+        write_gguf_header(&gemma, "gemma");
+        insert_model(&conn, "gemma", Some(&gemma)).await;
+        let err = activate_model(&conn, "gemma").await.unwrap_err();
+        assert!(err.contains("Unsupported model architecture 'gemma'"), "{}", err);
+
+        insert_model(&conn, "missing", None).await;
+        let err = activate_model(&conn, "missing").await.unwrap_err();
+        assert!(err.contains("has no file on disk"), "{}", err);
+
+        // Refused activations leave the previous model active
+        assert!(is_active(&conn, "good").await);
+        assert!(!is_active(&conn, "junk").await);
+        assert!(!is_active(&conn, "gemma").await);
+    }
+
+    #[tokio::test]
+    async fn test_missing_models_dir_has_no_orphans() {
+        let (db_dir, conn) = test_connection().await;
+        let missing = db_dir.path().join("no-models-here");
+        assert!(find_orphaned_model_files(&conn, &missing, false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_discovers_unregistered_models() {
+        use candle_core::quantized::gguf_file;
+
+        let (_db_dir, conn) = test_connection().await;
+        let models_dir = tempfile::tempdir().unwrap();
+        let dir = models_dir.path();
+
+        let registered = dir.join("tinyllama.gguf");
+        write_gguf_fixture(&registered, "tinyllama");
+        insert_model(&conn, "tinyllama", Some(&registered)).await;
+
+        // Copied in by hand, with architecture and context length in its header
+        let manual = dir.join("Mistral 7B Instruct.Q4_K_M.gguf");
+        let architecture = gguf_file::Value::String("llama".to_string());
+        let context_length = gguf_file::Value::U32(4096);
+        let file_type = gguf_file::Value::U32(15);
+        let mut file = std::fs::File::create(&manual).unwrap();
+        gguf_file::write(
+            &mut file,
+            &[
+                ("general.architecture", &architecture),
+                ("llama.context_length", &context_length),
+                ("general.file_type", &file_type),
+            ],
+            &[],
+        )
+        .unwrap();
+        drop(file);
+
+        // Not models: a fake GGUF, a partial download and an unrelated file
+        std::fs::write(dir.join("broken.gguf"), b"not a model").unwrap();
+        std::fs::write(dir.join("phi-2.gguf.tmp"), b"partial").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"notes").unwrap();
+
+        let discovered = discover_unregistered_models(&conn, dir).await.unwrap();
+        assert_eq!(discovered.len(), 1, "{:?}", discovered);
+        let model = &discovered[0];
+        assert_eq!(model.format, "gguf");
+        assert_eq!(model.size_bytes, std::fs::metadata(&manual).unwrap().len());
+        assert_eq!(model.metadata.architecture.as_deref(), Some("llama"));
+        assert_eq!(model.metadata.context_length, Some(4096));
+        assert_eq!(model.metadata.quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(model.suggested_model_id, "mistral-7b-instruct.q4_k_m");
+        assert_eq!(model.suggested_name, "Mistral 7B Instruct.Q4_K_M");
+
+        // Registering keeps the file in place and records its metadata
+        let record = register_discovered(&conn, dir, &manual, import_details("mistral"))
+            .await
+            .unwrap();
+        assert_eq!(record.file_path.as_deref(), Some(manual.to_string_lossy().as_ref()));
+        assert_eq!(record.architecture.as_deref(), Some("llama"));
+        assert_eq!(record.quantization.as_deref(), Some("Q4_K_M"));
+        assert!(manual.exists());
+        assert!(discover_unregistered_models(&conn, dir).await.unwrap().is_empty());
+
+        // Only discovered files can be registered
+        let err = register_discovered(&conn, dir, &registered, import_details("again"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("not an unregistered model"));
+    }
 }
@@ -1,13 +1,29 @@
 use crate::database::DatabaseManager;
+use crate::documents::ExportOptions;
+use crate::pii::{EntityType, FailurePolicy, Language};
+use crate::ner::hybrid_detector::{scan_folder, LayerCoverage, LayerStatus};
 use crate::ner::{
-    DetectionMode, HybridDetector, NerModelDownloader, NerModelManager,
-    NerModelRegistry, NerResult,
+    DetectionMode, DetectionReport, EntityExportSummary, FileScanCounts, HybridDetector,
+    NerFallbackPolicy, NerLabel, NerModelConfig, NerModelDownloader, NerModelInfo,
+    NerModelManager, NerModelRegistry, NerResult,
 };
 use anyhow::Result;
+use entity::ner_models;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::{Emitter, State};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
+
+/// Cancel flag of the NER download in progress, if any
+///
+/// A newtype, since Tauri keeps one managed state per type.
+#[derive(Clone, Default)]
+pub struct NerDownloadState(pub Arc<Mutex<Option<Arc<RwLock<bool>>>>>);
 
 /// Request to download NER model
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,12 +55,206 @@ pub struct NerInferenceRequest {
     pub detection_mode: Option<String>, // "pattern", "ner", "hybrid"
 }
 
+/// Request for adding a custom NER model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddCustomNerModelRequest {
+    pub model_id: String,
+    pub name: String,
+    pub description: String,
+    pub model_type: String,
+    pub language: String,
+    pub entity_labels: Vec<String>,
+    pub size: String,
+    pub parameters: String,
+    pub format: String,
+    pub model_url: String,
+    pub config_url: String,
+    pub tokenizer_url: String,
+    pub file_size: i64,
+    pub checksum: Option<String>,
+    pub license: Option<String>,
+    /// PII type per label name (without B-/I-), e.g. "PARTY" -> Organization
+    #[serde(default)]
+    pub label_entity_types: HashMap<String, EntityType>,
+}
+
+/// Built-in NER models plus the custom ones stored in the database
+async fn load_ner_registry(conn: &DatabaseConnection) -> Result<NerModelRegistry, String> {
+    let records = ner_models::Entity::find()
+        .filter(ner_models::Column::IsCustom.eq(true))
+        .all(conn)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut registry = NerModelRegistry::new();
+    for record in records {
+        // Built-in definitions win over a stored one with the same ID
+        if registry.get_model(&record.model_id).is_none() {
+            registry.add_model(custom_model_info(record)?);
+        }
+    }
+    Ok(registry)
+}
+
+/// Registry including custom models, or only the built-in one before the
+/// database is initialized
+async fn ner_registry(db: &DatabaseManager) -> Result<NerModelRegistry, String> {
+    match db.get_connection().await {
+        Some(conn) => load_ner_registry(&conn).await,
+        None => Ok(NerModelRegistry::new()),
+    }
+}
+
+/// Registry entry of a custom model row
+fn custom_model_info(record: ner_models::Model) -> Result<NerModelInfo, String> {
+    let entity_labels = serde_json::from_str(&record.entity_labels)
+        .map_err(|e| format!("Invalid entity labels of {}: {}", record.model_id, e))?;
+    let label_entity_types = match &record.label_entity_types {
+        Some(json) => serde_json::from_str(json)
+            .map_err(|e| format!("Invalid label types of {}: {}", record.model_id, e))?,
+        None => HashMap::new(),
+    };
+
+    Ok(NerModelInfo {
+        model_id: record.model_id,
+        name: record.name,
+        description: record.description.unwrap_or_default(),
+        provider: record.provider,
+        model_type: record.model_type,
+        language: record.language,
+        entity_labels,
+        size: record.size,
+        parameters: record.parameters,
+        format: record.format,
+        model_url: record.model_url.unwrap_or_default(),
+        config_url: record.config_url.unwrap_or_default(),
+        tokenizer_url: record.tokenizer_url.unwrap_or_default(),
+        file_size: record.file_size.unwrap_or_default(),
+        checksum: record.checksum,
+        license: record.license.unwrap_or_else(|| "Custom".to_string()),
+        accuracy: record.accuracy,
+        label_entity_types,
+    })
+}
+
+/// Reject a model ID that could leave the models directory once used as a path
+///
+/// IDs are `[A-Za-z0-9._-]` segments joined by single slashes, like
+/// `owner/name` on Hugging Face; `.` and `..` segments are not allowed.
+fn validate_model_id(model_id: &str) -> Result<(), String> {
+    if model_id.is_empty() {
+        return Err("Model ID must not be empty".to_string());
+    }
+    for segment in model_id.split('/') {
+        if segment.is_empty() || segment == "." || segment == ".." {
+            return Err(format!("Invalid model ID '{}': empty, '.' or '..' segment", model_id));
+        }
+        let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+        if let Some(c) = segment.chars().find(|c| !allowed(*c)) {
+            return Err(format!("Invalid model ID '{}': character '{}' not allowed", model_id, c));
+        }
+    }
+    Ok(())
+}
+
+/// Reject a model file URL that isn't an absolute http(s) URL
+fn validate_model_url(field: &str, url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| format!("Invalid {}: {}", field, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Invalid {}: must start with http:// or https://", field));
+    }
+    Ok(())
+}
+
+/// Validate and store a custom NER model
+async fn insert_custom_ner_model(
+    conn: &DatabaseConnection,
+    request: AddCustomNerModelRequest,
+) -> Result<(), String> {
+    validate_model_id(&request.model_id)?;
+    if load_ner_registry(conn).await?.get_model(&request.model_id).is_some() {
+        return Err(format!("NER model with ID '{}' already exists", request.model_id));
+    }
+
+    validate_model_url("model URL", &request.model_url)?;
+    validate_model_url("config URL", &request.config_url)?;
+    validate_model_url("tokenizer URL", &request.tokenizer_url)?;
+
+    if !["small", "medium", "large"].contains(&request.size.as_str()) {
+        return Err("Invalid size: must be 'small', 'medium', or 'large'".to_string());
+    }
+    Language::parse(&request.language).map_err(|e| e.to_string())?;
+    for label in request.label_entity_types.keys() {
+        let known = request
+            .entity_labels
+            .iter()
+            .any(|name| NerLabel::parse(name).entity_type() == Some(label.as_str()));
+        if !known {
+            return Err(format!("Label type given for unknown label '{}'", label));
+        }
+    }
+    let label_entity_types = (!request.label_entity_types.is_empty())
+        .then(|| serde_json::to_string(&request.label_entity_types).unwrap());
+
+    let now = chrono::Utc::now().naive_utc();
+    let record = ner_models::ActiveModel {
+        model_id: Set(request.model_id),
+        name: Set(request.name),
+        description: Set(Some(request.description)),
+        provider: Set("custom".to_string()),
+        model_type: Set(request.model_type),
+        entity_labels: Set(serde_json::to_string(&request.entity_labels).unwrap()),
+        language: Set(request.language),
+        framework: Set("candle".to_string()),
+        size: Set(request.size),
+        parameters: Set(request.parameters),
+        format: Set(request.format),
+        status: Set("available".to_string()),
+        model_url: Set(Some(request.model_url)),
+        config_url: Set(Some(request.config_url)),
+        tokenizer_url: Set(Some(request.tokenizer_url)),
+        file_size: Set(Some(request.file_size)),
+        checksum: Set(request.checksum),
+        license: Set(request.license),
+        is_custom: Set(true),
+        label_entity_types: Set(label_entity_types),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    record
+        .insert(conn)
+        .await
+        .map_err(|e| format!("Failed to add NER model: {}", e))?;
+    Ok(())
+}
+
+/// Add a custom NER model; it is listed and downloadable like the built-in
+/// ones, also after a restart
+#[tauri::command]
+pub async fn add_custom_ner_model(
+    request: AddCustomNerModelRequest,
+    db: State<'_, DatabaseManager>,
+) -> Result<String, String> {
+    let conn = db
+        .get_connection()
+        .await
+        .ok_or("Database not initialized")?;
+
+    let model_id = request.model_id.clone();
+    insert_custom_ner_model(&conn, request).await?;
+
+    Ok(format!("Custom NER model '{}' added successfully", model_id))
+}
+
 /// List all available NER models
 #[tauri::command]
 pub async fn list_ner_models(
-    _db: State<'_, DatabaseManager>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<Vec<NerModelResponse>, String> {
-    let registry = NerModelRegistry::new();
+    let registry = ner_registry(&db).await?;
     let models = registry.list_models();
 
     // Check which models are downloaded
@@ -83,10 +293,11 @@ pub async fn list_ner_models(
 #[tauri::command]
 pub async fn download_ner_model(
     request: DownloadNerModelRequest,
-    _db: State<'_, DatabaseManager>,
+    db: State<'_, DatabaseManager>,
+    download_state: State<'_, NerDownloadState>,
     window: tauri::Window,
 ) -> Result<String, String> {
-    let registry = NerModelRegistry::new();
+    let registry = ner_registry(&db).await?;
     let model_info = registry
         .get_model(&request.model_id)
         .ok_or(format!("Model not found: {}", request.model_id))?
@@ -101,6 +312,15 @@ pub async fn download_ner_model(
     let downloader = NerModelDownloader::new(app_dir)
         .map_err(|e| format!("Failed to create downloader: {}", e))?;
 
+    // Expose the cancel flag to cancel_ner_download
+    {
+        let mut state = download_state.0.lock().await;
+        if state.is_some() {
+            return Err("Another NER model download is already in progress".to_string());
+        }
+        *state = Some(downloader.cancel_handle());
+    }
+
     // Download with progress updates
     let model_id = model_info.model_id.clone();
     let window_clone = window.clone();
@@ -112,6 +332,9 @@ pub async fn download_ner_model(
                 serde_json::json!({
                     "model_id": &model_id,
                     "file_name": progress.file_name,
+                    "file_index": progress.file_index,
+                    "file_count": progress.file_count,
+                    "status": progress.status,
                     "downloaded": progress.downloaded_bytes,
                     "total": progress.total_bytes,
                     "progress": progress.progress_percent,
@@ -121,6 +344,8 @@ pub async fn download_ner_model(
         })
         .await;
 
+    *download_state.0.lock().await = None;
+
     match result {
         Ok(path) => Ok(format!("Model downloaded to: {:?}", path)),
         Err(e) => Err(format!("Download failed: {}", e)),
@@ -154,6 +379,7 @@ pub async fn delete_ner_model(
 pub async fn load_ner_model(
     model_id: String,
     ner_manager: State<'_, Arc<Mutex<Option<NerModelManager>>>>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<String, String> {
     let app_dir = dirs::data_dir()
         .ok_or("Failed to get data directory")?
@@ -167,22 +393,45 @@ pub async fn load_ner_model(
         return Err(format!("Model not downloaded: {}", model_id));
     }
 
-    // Create model manager and load model
-    let manager = NerModelManager::new();
-    let config = crate::ner::types::NerModelConfig::default();
+    let registry = ner_registry(&db).await?;
+    let config = ner_model_config(&model_id, registry.get_model(&model_id), &model_path)?;
 
-    manager
+    // Models loaded earlier stay cached, e.g. one per language
+    let mut manager_lock = ner_manager.lock().await;
+    manager_lock
+        .get_or_insert_with(NerModelManager::new)
         .load_model(model_path, config)
         .await
         .map_err(|e| format!("Failed to load model: {}", e))?;
 
-    // Store in state
-    let mut manager_lock = ner_manager.lock().await;
-    *manager_lock = Some(manager);
-
     Ok(format!("Model loaded: {}", model_id))
 }
 
+/// Inference config of a downloaded model: language and label types from its
+/// registry entry, labels and dimensions from its `config.json`
+fn ner_model_config(
+    model_id: &str,
+    info: Option<&NerModelInfo>,
+    model_path: &Path,
+) -> Result<NerModelConfig, String> {
+    let mut config = NerModelConfig {
+        model_id: model_id.to_string(),
+        ..Default::default()
+    };
+    if let Some(info) = info {
+        config.language = info.language.clone();
+        config.label_entity_types = info.label_entity_types.clone();
+    }
+    // The model's own labels, e.g. for legal models tagging judges or laws
+    let config_path = model_path.join("config.json");
+    if config_path.exists() {
+        config
+            .load_model_config(&config_path)
+            .map_err(|e| format!("Failed to read model config: {:#}", e))?;
+    }
+    Ok(config)
+}
+
 /// Run NER inference on text
 #[tauri::command]
 pub async fn run_ner_inference(
@@ -228,6 +477,243 @@ pub async fn run_ner_inference(
     })
 }
 
+/// Set the confidence bonus Presidio gets over overlapping local matches
+#[tauri::command]
+pub async fn set_presidio_boost(
+    boost: f64,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<(), String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    detector
+        .set_presidio_boost(boost)
+        .await
+        .map_err(|e| format!("Failed to set Presidio boost: {}", e))
+}
+
+/// Get the confidence bonus Presidio gets over overlapping local matches
+#[tauri::command]
+pub async fn get_presidio_boost(
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<f64, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    Ok(detector.get_presidio_boost().await)
+}
+
+/// Set which entity types win overlaps of equal confidence, highest priority first
+#[tauri::command]
+pub async fn set_entity_priority(
+    priority: Vec<EntityType>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<(), String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    detector
+        .set_entity_priority(priority)
+        .await
+        .map_err(|e| format!("Failed to set entity priority: {}", e))
+}
+
+/// Get the entity type order used to break equal-confidence overlaps
+#[tauri::command]
+pub async fn get_entity_priority(
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<Vec<EntityType>, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    Ok(detector.get_entity_priority().await)
+}
+
+/// Set the minimum entity length (in characters) for hybrid detection
+#[tauri::command]
+pub async fn set_min_entity_length(
+    min_length: usize,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<(), String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    detector.set_min_entity_length(min_length).await;
+    Ok(())
+}
+
+/// Get the minimum entity length (in characters) for hybrid detection
+#[tauri::command]
+pub async fn get_min_entity_length(
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<usize, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    Ok(detector.get_min_entity_length().await)
+}
+
+/// Set whether a failing NER model fails detection or is skipped
+#[tauri::command]
+pub async fn set_ner_fallback_policy(
+    policy: NerFallbackPolicy,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<(), String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    detector.set_ner_fallback_policy(policy).await;
+    Ok(())
+}
+
+/// Get whether a failing NER model fails detection or is skipped
+#[tauri::command]
+pub async fn get_ner_fallback_policy(
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<NerFallbackPolicy, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    Ok(detector.get_ner_fallback_policy().await)
+}
+
+/// Detect entities with the configured mode, reporting whether NER ran,
+/// was not loaded, or failed
+///
+/// With a `language`, the report also says when no active layer supports
+/// it and patterns were used instead.
+#[tauri::command]
+pub async fn detect_entities_with_report(
+    text: String,
+    language: Option<String>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<DetectionReport, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    let report = match language {
+        Some(language) => {
+            let language = Language::parse(&language).map_err(|e| e.to_string())?;
+            detector.detect_with_language(&text, &language).await
+        }
+        None => detector.detect_with_report(&text).await,
+    };
+    report.map_err(|e| format!("Detection failed: {:#}", e))
+}
+
+/// Quick scan a folder: pattern match counts per file, without spans
+///
+/// Unreadable files are reported and skipped unless `failure_policy` says to abort.
+#[tauri::command]
+pub async fn scan_folder_pii_counts(
+    folder: String,
+    failure_policy: Option<FailurePolicy>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<Vec<FileScanCounts>, String> {
+    // Only the pattern layer is needed, so the detector stays free for other
+    // calls while the folder is read
+    let scanner = hybrid_detector
+        .lock()
+        .await
+        .as_ref()
+        .ok_or("NER system not initialized")?
+        .pattern_scanner();
+    let policy = failure_policy.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || scan_folder(&scanner, Path::new(&folder), policy))
+        .await
+        .map_err(|e| format!("Scan task failed: {}", e))?
+        .map_err(|e| format!("Failed to scan folder: {}", e))
+}
+
+/// Export the entities detected in every document under a folder to a
+/// JSON Lines file, one line per document, for bulk processing pipelines
+#[tauri::command]
+pub async fn export_folder_entities(
+    folder: String,
+    output_path: String,
+    options: Option<ExportOptions>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<EntityExportSummary, String> {
+    HybridDetector::export_folder_entities(
+        hybrid_detector.inner(),
+        Path::new(&folder),
+        Path::new(&output_path),
+        options.unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| format!("Failed to export entities: {:#}", e))
+}
+
+/// Entity types each active detection layer can produce, for a coverage matrix
+#[tauri::command]
+pub async fn get_layer_coverage(
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<Vec<LayerCoverage>, String> {
+    let detector_lock = hybrid_detector.lock().await;
+    let detector = detector_lock
+        .as_ref()
+        .ok_or("NER system not initialized")?;
+
+    Ok(detector.layer_coverage().await)
+}
+
+/// Recommend a detection mode for documents in `language`
+///
+/// Takes into account which layers are running and whether a downloaded NER
+/// model covers the language.
+#[tauri::command]
+pub async fn get_recommended_detection_mode(
+    language: String,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<DetectionMode, String> {
+    let language = Language::parse(&language).map_err(|e| e.to_string())?;
+
+    let layers = match hybrid_detector.lock().await.as_ref() {
+        Some(detector) => detector.get_layer_status().await,
+        None => LayerStatus {
+            layer1_pattern: true,
+            layer2_ner: false,
+            layer3_presidio: false,
+        },
+    };
+
+    let app_dir = dirs::data_dir()
+        .ok_or("Failed to get data directory")?
+        .join("bear-llm-ai")
+        .join("ner_models");
+    let downloader = NerModelDownloader::new(app_dir)
+        .map_err(|e| format!("Failed to create downloader: {}", e))?;
+
+    let registry = NerModelRegistry::new();
+    let mut downloaded = Vec::new();
+    for model in registry.list_models() {
+        if downloader.is_downloaded(&model.model_id).await {
+            downloaded.push(model);
+        }
+    }
+
+    Ok(layers.recommended_mode_for(&language, &downloaded))
+}
+
 /// Get NER model recommendations
 #[tauri::command]
 pub async fn get_ner_recommendations() -> Result<serde_json::Value, String> {
@@ -238,14 +724,16 @@ pub async fn get_ner_recommendations() -> Result<serde_json::Value, String> {
         "fastest": registry.get_fastest_model().map(|m| m.model_id.clone()),
         "most_accurate": registry.get_most_accurate_model().map(|m| m.model_id.clone()),
         "multilingual": registry.get_multilingual_model().map(|m| m.model_id.clone()),
-        "legal_models": {
-            "de": registry.get_recommended_legal_model("de").map(|m| m.model_id.clone()),
-            "en": registry.get_recommended_legal_model("en").map(|m| m.model_id.clone()),
-            "fr": registry.get_recommended_legal_model("fr").map(|m| m.model_id.clone()),
-            "nl": registry.get_recommended_legal_model("nl").map(|m| m.model_id.clone()),
-            "ru": registry.get_recommended_legal_model("ru").map(|m| m.model_id.clone()),
-            "zh": registry.get_recommended_legal_model("zh").map(|m| m.model_id.clone()),
-        },
+        "legal_models": registry
+            .get_supported_legal_languages()
+            .into_iter()
+            .map(|code| {
+                let model = registry
+                    .get_recommended_legal_model(&Language::Code(code.to_string()))
+                    .map(|m| m.model_id.clone());
+                (code.to_string(), serde_json::json!(model))
+            })
+            .collect::<serde_json::Map<_, _>>(),
         "supported_legal_languages": registry.get_supported_legal_languages(),
     }))
 }
@@ -255,6 +743,7 @@ pub async fn get_ner_recommendations() -> Result<serde_json::Value, String> {
 pub async fn get_ner_recommendations_for_language(
     language: String,
 ) -> Result<serde_json::Value, String> {
+    let language = Language::parse(&language).map_err(|e| e.to_string())?;
     let registry = NerModelRegistry::new();
 
     let recommended = registry.get_recommended_legal_model(&language);
@@ -321,8 +810,15 @@ pub async fn get_ner_models_by_use_case(
 
 /// Cancel NER model download
 #[tauri::command]
-pub async fn cancel_ner_download() -> Result<String, String> {
-    // TODO: Implement cancel functionality with shared state
+pub async fn cancel_ner_download(
+    download_state: State<'_, NerDownloadState>,
+) -> Result<String, String> {
+    let state = download_state.0.lock().await;
+    let cancel_flag = state.as_ref().ok_or("No NER download in progress")?;
+
+    // The downloader stops at the next chunk and removes partial files
+    *cancel_flag.write().await = true;
+
     Ok("Download cancelled".to_string())
 }
 
@@ -345,9 +841,178 @@ pub async fn get_ner_status(
         None
     };
 
+    let loaded_models = match manager_lock.as_ref() {
+        Some(manager) => manager.loaded_models().await,
+        None => Vec::new(),
+    };
+
     Ok(serde_json::json!({
         "model_loaded": model_loaded,
         "model_path": model_path,
+        "loaded_models": loaded_models,
         "system_ready": model_loaded,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom_request(model_id: &str) -> AddCustomNerModelRequest {
+        AddCustomNerModelRequest {
+            model_id: model_id.to_string(),
+            name: "Contracts NER".to_string(),
+            description: "Fine-tuned on our contracts".to_string(),
+            model_type: "bert".to_string(),
+            language: "en".to_string(),
+            entity_labels: vec!["O".to_string(), "B-PARTY".to_string(), "I-PARTY".to_string()],
+            size: "small".to_string(),
+            parameters: "66M".to_string(),
+            format: "safetensors".to_string(),
+            model_url: "https://example.com/contracts/model.safetensors".to_string(),
+            config_url: "https://example.com/contracts/config.json".to_string(),
+            tokenizer_url: "https://example.com/contracts/tokenizer.json".to_string(),
+            file_size: 260_000_000,
+            checksum: None,
+            license: None,
+            label_entity_types: HashMap::from([("PARTY".to_string(), EntityType::Organization)]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_ner_model_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let db = DatabaseManager::new();
+        db.initialize(path.to_str().unwrap()).await.unwrap();
+        let conn = db.get_connection().await.unwrap();
+        insert_custom_ner_model(&conn, custom_request("acme/contracts-ner"))
+            .await
+            .unwrap();
+
+        // Same ID again, or a built-in one, is rejected
+        assert!(insert_custom_ner_model(&conn, custom_request("acme/contracts-ner"))
+            .await
+            .is_err());
+        assert!(insert_custom_ner_model(&conn, custom_request("dslim/bert-base-NER"))
+            .await
+            .is_err());
+        drop(conn);
+        drop(db);
+
+        let db = DatabaseManager::new();
+        db.initialize(path.to_str().unwrap()).await.unwrap();
+        let registry = ner_registry(&db).await.unwrap();
+
+        let model = registry.get_model("acme/contracts-ner").unwrap();
+        assert_eq!(model.provider, "custom");
+        assert_eq!(model.entity_labels, vec!["O", "B-PARTY", "I-PARTY"]);
+        assert_eq!(model.config_url, "https://example.com/contracts/config.json");
+        assert_eq!(model.label_entity_types["PARTY"], EntityType::Organization);
+        assert_eq!(
+            registry.list_models().len(),
+            NerModelRegistry::new().list_models().len() + 1
+        );
+    }
+
+    #[test]
+    fn test_model_id_validation() {
+        for valid in ["acme/contracts-ner", "dslim/bert-base-NER", "local_model.v2"] {
+            assert!(validate_model_id(valid).is_ok(), "{}", valid);
+        }
+
+        let invalid = [
+            ("", "empty"),
+            ("..", "'..'"),
+            ("acme/..", "'..'"),
+            ("./model", "'.'"),
+            ("acme//ner", "empty"),
+            ("/acme", "empty"),
+            ("acme/ner/", "empty"),
+            (r"..\..\Windows", "'\\'"),
+            ("acme/ner model", "' '"),
+            ("acme:ner", "':'"),
+        ];
+        for (model_id, reason) in invalid {
+            let error = validate_model_id(model_id).unwrap_err();
+            assert!(error.contains(reason), "{}: {}", model_id, error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_ner_model_ids_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+
+        let error = insert_custom_ner_model(&conn, custom_request("../../outside"))
+            .await
+            .unwrap_err();
+        assert!(error.contains("Invalid model ID"), "{}", error);
+        assert!(ner_registry(&db).await.unwrap().get_model("../../outside").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_ner_model_urls_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+
+        let mut request = custom_request("acme/bad-urls");
+        request.tokenizer_url = "ftp://example.com/tokenizer.json".to_string();
+        let error = insert_custom_ner_model(&conn, request).await.unwrap_err();
+        assert!(error.contains("tokenizer URL"), "{}", error);
+
+        let mut request = custom_request("acme/bad-urls");
+        request.config_url = "config.json".to_string();
+        let error = insert_custom_ner_model(&conn, request).await.unwrap_err();
+        assert!(error.contains("config URL"), "{}", error);
+
+        assert!(ner_registry(&db).await.unwrap().get_model("acme/bad-urls").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_label_types_apply_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+
+        let mut request = custom_request("acme/unknown-label");
+        request
+            .label_entity_types
+            .insert("JUDGE".to_string(), EntityType::Person);
+        let error = insert_custom_ner_model(&conn, request).await.unwrap_err();
+        assert!(error.contains("'JUDGE'"), "{}", error);
+
+        insert_custom_ner_model(&conn, custom_request("acme/contracts-ner"))
+            .await
+            .unwrap();
+        let registry = ner_registry(&db).await.unwrap();
+        let model_dir = dir.path().join("acme_contracts-ner");
+        std::fs::create_dir(&model_dir).unwrap();
+        std::fs::write(
+            model_dir.join("config.json"),
+            r#"{"id2label": {"0": "O", "1": "B-PARTY", "2": "I-PARTY"}}"#,
+        )
+        .unwrap();
+
+        let info = registry.get_model("acme/contracts-ner");
+        let config = ner_model_config("acme/contracts-ner", info, &model_dir).unwrap();
+        assert_eq!(config.entity_type_for("B-PARTY"), Some(EntityType::Organization));
+        assert!(config.unmapped_labels().is_empty());
+
+        // Without the mapping the label would be dropped
+        let config = ner_model_config("acme/contracts-ner", None, &model_dir).unwrap();
+        assert_eq!(config.unmapped_labels(), vec!["PARTY"]);
+    }
+}
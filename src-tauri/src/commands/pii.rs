@@ -1,13 +1,34 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tauri::State;
-use tokio::sync::Mutex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tauri::{Emitter, State};
+use tokio::sync::{Mutex, RwLock};
 
-use crate::pii::{AnonymizationResult, AnonymizationSettings, Anonymizer, EntityType};
+use crate::commands::settings::{read_setting, write_setting};
+use crate::database::DatabaseManager;
+use crate::documents::ExportOptions;
+use crate::ner::{DetectionMode, HybridDetector, NerLayerStatus};
+use crate::pii::{
+    AnonymizationResult, AnonymizationSettings, Anonymizer, DiffSegment, Entity, EntityType,
+    FailurePolicy, MergePolicy, MergedMapping, PIIDetector, RiskScore,
+};
+use crate::pii::types::{INDEX_PLACEHOLDER, LETTER_PLACEHOLDER};
 
 // Global state for anonymizer (to maintain consistent replacements across calls)
 type AnonymizerState = Arc<Mutex<Anonymizer>>;
 
+/// Replacement mappings of text sent to external services, by session token
+pub type ExternalSessionState = Arc<Mutex<HashMap<String, Vec<(String, String)>>>>;
+
+/// Cancel flag of the running streaming batch, if any
+///
+/// A newtype, since Tauri keeps one managed state per type.
+#[derive(Clone, Default)]
+pub struct BatchCancelState(pub Arc<Mutex<Option<Arc<RwLock<bool>>>>>);
+
 /// Request for anonymizing text
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnonymizeRequest {
@@ -22,6 +43,48 @@ pub struct BatchAnonymizeRequest {
     pub settings: Option<AnonymizationSettings>,
 }
 
+/// Request for streaming batch anonymization of files
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamBatchRequest {
+    /// Text files to anonymize, processed in order
+    pub paths: Vec<String>,
+    /// JSON Lines file receiving one `AnonymizationResult` per document
+    pub output_path: String,
+    pub settings: Option<AnonymizationSettings>,
+    /// Whether an unreadable file stops the batch; best effort by default
+    #[serde(default)]
+    pub failure_policy: FailurePolicy,
+    /// Encoding, line ending and BOM of the output file
+    #[serde(default)]
+    pub export: ExportOptions,
+}
+
+/// Progress event emitted after each document of a streaming batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgress {
+    /// Zero-based position of the document in the request
+    pub index: usize,
+    pub total: usize,
+    pub file_name: String,
+    pub entities_found: usize,
+    pub entity_counts: Vec<(String, usize)>,
+    /// Set when the document could not be read; it is skipped
+    pub error: Option<String>,
+}
+
+/// Outcome of a streaming batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    /// Documents written to the output file
+    pub processed: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+    pub output_path: String,
+    /// Error that stopped the batch under `FailurePolicy::AbortOnError`
+    pub error: Option<String>,
+}
+
 /// Statistics about detected entities
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EntityStatistics {
@@ -29,20 +92,390 @@ pub struct EntityStatistics {
     pub total_entities: usize,
 }
 
+/// A single match of a pattern under test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternMatch {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Why a pattern under test could not be compiled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternError {
+    /// "empty", "syntax" or "too_large"
+    pub kind: String,
+    pub message: String,
+}
+
+/// A document over the size limit of single-document commands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentTooLarge {
+    /// Always "document_too_large"
+    pub kind: String,
+    pub limit_bytes: usize,
+    pub actual_bytes: usize,
+    pub message: String,
+}
+
+/// Error from `anonymize_text` and `detect_pii_entities`
+///
+/// An oversized document comes back as a `DocumentTooLarge` object with the
+/// limit and the actual size, so the UI can suggest batch processing; any
+/// other failure stays a plain message string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DocumentError {
+    TooLarge(DocumentTooLarge),
+    Failed(String),
+}
+
+impl From<String> for DocumentError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+/// Anonymized text ready to leave the machine
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SanitizedText {
+    pub text: String,
+    /// Pass to `restore_external_response` to map the reply back
+    pub session_token: String,
+    pub entities_replaced: usize,
+}
+
+/// A reviewed document with the original values put back
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeanonymizedText {
+    pub text: String,
+    /// Placeholders the mapping has no value for, left in the text as they
+    /// are, in order of first appearance
+    pub unknown_placeholders: Vec<String>,
+}
+
+/// Result of `quick_anonymize`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuickAnonymizeResult {
+    pub anonymized_text: String,
+    /// Entities replaced, per type
+    pub counts: HashMap<EntityType, usize>,
+    pub total: usize,
+    /// Detection mode that was used
+    pub mode: DetectionMode,
+}
+
+/// Entities one detection mode found, for `compare_modes`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModeDetection {
+    pub mode: DetectionMode,
+    pub entities: Vec<Entity>,
+    /// Entities no other compared mode found at the same span and type
+    pub unique: Vec<Entity>,
+    pub elapsed_ms: f64,
+}
+
+/// A mode `compare_modes` didn't run, and why
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedMode {
+    pub mode: DetectionMode,
+    pub reason: String,
+}
+
+/// Result of `compare_modes`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModeComparison {
+    pub results: Vec<ModeDetection>,
+    pub skipped: Vec<SkippedMode>,
+}
+
+/// Modes compared by `compare_modes`, from fewest to most layers
+const COMPARED_MODES: [DetectionMode; 3] = [
+    DetectionMode::PatternOnly,
+    DetectionMode::Hybrid,
+    DetectionMode::Full,
+];
+
+/// Upper bound on matches returned by a pattern preview
+const MAX_PATTERN_MATCHES: usize = 1000;
+
+/// Settings key holding the size limit of single-document commands
+const MAX_DOCUMENT_BYTES_KEY: &str = "pii_max_document_bytes";
+
+/// Size limit of single-document commands when none is configured (10 MiB)
+pub const DEFAULT_MAX_DOCUMENT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Reject text over `limit` bytes, pointing to the APIs meant for big inputs
+fn check_document_size(text: &str, limit: usize) -> Result<(), DocumentTooLarge> {
+    if text.len() <= limit {
+        return Ok(());
+    }
+    Err(DocumentTooLarge {
+        kind: "document_too_large".to_string(),
+        limit_bytes: limit,
+        actual_bytes: text.len(),
+        message: format!(
+            "Document is {} bytes, over the {} byte limit. Split it into files and use \
+             the folder scan or streaming batch (anonymize_batch_stream) instead, \
+             or raise the limit in settings.",
+            text.len(),
+            limit
+        ),
+    })
+}
+
+/// Load the single-document size limit from settings (default when unset)
+async fn load_max_document_bytes(db: &DatabaseManager) -> Result<usize, String> {
+    let Some(conn) = db.get_connection().await else {
+        return Ok(DEFAULT_MAX_DOCUMENT_BYTES);
+    };
+
+    match read_setting(&conn, MAX_DOCUMENT_BYTES_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid maximum document size in settings: {}", e)),
+        None => Ok(DEFAULT_MAX_DOCUMENT_BYTES),
+    }
+}
+
+/// Enforce the configured size limit on a single document
+async fn enforce_document_size(text: &str, db: &DatabaseManager) -> Result<(), DocumentError> {
+    let limit = load_max_document_bytes(db).await?;
+    check_document_size(text, limit).map_err(DocumentError::TooLarge)
+}
+
+/// Get the size limit, in bytes, of `anonymize_text` and `detect_pii_entities`
+#[tauri::command]
+pub async fn get_max_document_bytes(db: State<'_, DatabaseManager>) -> Result<usize, String> {
+    load_max_document_bytes(&db).await
+}
+
+/// Set the size limit, in bytes, of `anonymize_text` and `detect_pii_entities`
+#[tauri::command]
+pub async fn set_max_document_bytes(
+    max_bytes: usize,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    if max_bytes == 0 {
+        return Err("Maximum document size must be greater than zero".to_string());
+    }
+
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    write_setting(&conn, MAX_DOCUMENT_BYTES_KEY.to_string(), max_bytes.to_string()).await
+}
+
+/// Settings sent with a request, or the defaults; rejects settings whose
+/// replacement templates could collide
+fn request_settings(
+    settings: Option<AnonymizationSettings>,
+) -> Result<AnonymizationSettings, String> {
+    let settings = settings.unwrap_or_default();
+    settings
+        .validate()
+        .map_err(|e| format!("Invalid anonymization settings: {:#}", e))?;
+    Ok(settings)
+}
+
 /// Anonymize text
+///
+/// Documents over the configured size limit are rejected with
+/// `DocumentTooLarge`.
 #[tauri::command]
 pub async fn anonymize_text(
     request: AnonymizeRequest,
     anonymizer: State<'_, AnonymizerState>,
-) -> Result<AnonymizationResult, String> {
+    db: State<'_, DatabaseManager>,
+) -> Result<AnonymizationResult, DocumentError> {
+    enforce_document_size(&request.text, &db).await?;
+
     let mut anon = anonymizer.lock().await;
-    let settings = request.settings.unwrap_or_default();
+    let settings = request_settings(request.settings)?;
 
     let result = anon.anonymize(&request.text, &settings);
 
     Ok(result)
 }
 
+/// Anonymize a pasted snippet with default settings and the best detection
+/// mode currently available
+#[tauri::command]
+pub async fn quick_anonymize(
+    text: String,
+    anonymizer: State<'_, AnonymizerState>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<QuickAnonymizeResult, String> {
+    let detector = hybrid_detector.lock().await;
+    let mut anon = anonymizer.lock().await;
+    Ok(quick_anonymize_with(&mut anon, detector.as_ref(), &text).await)
+}
+
+/// Pattern detection runs in the anonymizer itself; other modes go through the
+/// hybrid detector, falling back to patterns if it fails
+async fn quick_anonymize_with(
+    anonymizer: &mut Anonymizer,
+    detector: Option<&HybridDetector>,
+    text: &str,
+) -> QuickAnonymizeResult {
+    let settings = AnonymizationSettings::default();
+    let mode = match detector {
+        Some(detector) => detector.get_layer_status().await.recommended_mode(),
+        None => DetectionMode::PatternOnly,
+    };
+
+    let detected = match (detector, mode) {
+        (Some(detector), mode) if mode != DetectionMode::PatternOnly => {
+            match detector.detect_with_mode_report(text, mode).await {
+                Ok(report) => Some((report.entities, mode_that_ran(mode, &report.ner_status))),
+                Err(e) => {
+                    log::warn!("Quick anonymize falling back to patterns: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let (result, mode) = match detected {
+        Some((entities, mode)) => (anonymizer.anonymize_entities(text, entities, &settings), mode),
+        None => (anonymizer.anonymize(text, &settings), DetectionMode::PatternOnly),
+    };
+
+    QuickAnonymizeResult {
+        total: result.statistics.values().sum(),
+        anonymized_text: result.anonymized_text,
+        counts: result.statistics,
+        mode,
+    }
+}
+
+/// Hybrid detection without NER output (not loaded, or failed and skipped)
+/// only ran patterns
+fn mode_that_ran(mode: DetectionMode, ner_status: &NerLayerStatus) -> DetectionMode {
+    let ner_mode = matches!(mode, DetectionMode::Hybrid | DetectionMode::NerOnly);
+    if ner_mode && *ner_status != NerLayerStatus::Succeeded {
+        DetectionMode::PatternOnly
+    } else {
+        mode
+    }
+}
+
+/// Run the sample through pattern-only, hybrid and full detection to show
+/// what each mode adds; modes whose layers aren't available are skipped
+#[tauri::command]
+pub async fn compare_modes(
+    text: String,
+    anonymizer: State<'_, AnonymizerState>,
+    hybrid_detector: State<'_, Arc<Mutex<Option<HybridDetector>>>>,
+) -> Result<ModeComparison, String> {
+    let detector = hybrid_detector.lock().await;
+    let anon = anonymizer.lock().await;
+    Ok(compare_modes_with(&anon.detector, detector.as_ref(), &text).await)
+}
+
+/// Without a hybrid detector only the pattern layer can run, through `patterns`
+async fn compare_modes_with(
+    patterns: &PIIDetector,
+    detector: Option<&HybridDetector>,
+    text: &str,
+) -> ModeComparison {
+    let layers = match detector {
+        Some(detector) => Some(detector.get_layer_status().await),
+        None => None,
+    };
+
+    let mut results = Vec::new();
+    let mut skipped = Vec::new();
+    for mode in COMPARED_MODES {
+        let missing = match (&layers, mode) {
+            (_, DetectionMode::PatternOnly) => None,
+            (None, _) => Some("Hybrid detector not initialized".to_string()),
+            (Some(layers), mode) => {
+                let mut missing = Vec::new();
+                if !layers.layer2_ner {
+                    missing.push("NER model not loaded");
+                }
+                if mode == DetectionMode::Full && !layers.layer3_presidio {
+                    missing.push("Presidio not running");
+                }
+                (!missing.is_empty()).then(|| missing.join(", "))
+            }
+        };
+        if let Some(reason) = missing {
+            skipped.push(SkippedMode { mode, reason });
+            continue;
+        }
+
+        let started = std::time::Instant::now();
+        let entities = match detector {
+            Some(detector) => detector.detect_with_mode(text, mode).await,
+            None => {
+                let mut entities = patterns.detect(text);
+                crate::pii::assign_utf16_offsets(&mut entities, text);
+                Ok(entities)
+            }
+        };
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        match entities {
+            Ok(entities) => results.push(ModeDetection {
+                mode,
+                entities,
+                unique: Vec::new(),
+                elapsed_ms,
+            }),
+            Err(e) => skipped.push(SkippedMode {
+                mode,
+                reason: format!("Detection failed: {:#}", e),
+            }),
+        }
+    }
+
+    // An entity is unique to a mode if no other mode found the same type at the same span
+    let spans: Vec<Vec<(EntityType, usize, usize)>> = results
+        .iter()
+        .map(|result| {
+            result
+                .entities
+                .iter()
+                .map(|e| (e.entity_type, e.start, e.end))
+                .collect()
+        })
+        .collect();
+    for (index, result) in results.iter_mut().enumerate() {
+        result.unique = result
+            .entities
+            .iter()
+            .filter(|e| {
+                let key = (e.entity_type, e.start, e.end);
+                spans
+                    .iter()
+                    .enumerate()
+                    .all(|(other, spans)| other == index || !spans.contains(&key))
+            })
+            .cloned()
+            .collect();
+    }
+
+    ModeComparison { results, skipped }
+}
+
+/// Split an anonymization result into segments for a side-by-side diff
+#[tauri::command]
+pub async fn get_anonymization_diff(result: AnonymizationResult) -> Result<Vec<DiffSegment>, String> {
+    Ok(Anonymizer::diff_segments(&result))
+}
+
+/// Merge the replacement mappings of two anonymizations of one matter,
+/// reporting the conflicts resolved
+#[tauri::command]
+pub async fn merge_replacement_mappings(
+    a: Vec<(String, String)>,
+    b: Vec<(String, String)>,
+    policy: Option<MergePolicy>,
+) -> Result<MergedMapping, String> {
+    Ok(Anonymizer::merge_mappings(&a, &b, policy.unwrap_or_default()))
+}
+
 /// Anonymize multiple texts while maintaining consistency
 #[tauri::command]
 pub async fn anonymize_batch(
@@ -50,13 +483,136 @@ pub async fn anonymize_batch(
     anonymizer: State<'_, AnonymizerState>,
 ) -> Result<Vec<AnonymizationResult>, String> {
     let mut anon = anonymizer.lock().await;
-    let settings = request.settings.unwrap_or_default();
+    let settings = request_settings(request.settings)?;
 
     let results = anon.anonymize_batch(request.texts, &settings);
 
     Ok(results)
 }
 
+/// Anonymize files one at a time, appending each result to the output file
+///
+/// Only one document is held in memory at a time. The cancel flag is checked
+/// before every document; `on_progress` is called once per document, in order.
+/// Under `FailurePolicy::AbortOnError` the first unreadable file ends the
+/// batch, leaving the results written so far in the output file.
+pub async fn run_batch_stream(
+    anonymizer: &mut Anonymizer,
+    request: &StreamBatchRequest,
+    cancel_flag: &RwLock<bool>,
+    mut on_progress: impl FnMut(&BatchProgress),
+) -> Result<BatchSummary, String> {
+    let settings = request_settings(request.settings.clone())?;
+    let mut writer = request
+        .export
+        .create(Path::new(&request.output_path))
+        .map_err(|e| format!("Failed to create output file: {:#}", e))?;
+
+    let mut summary = BatchSummary {
+        total: request.paths.len(),
+        processed: 0,
+        failed: 0,
+        cancelled: false,
+        output_path: request.output_path.clone(),
+        error: None,
+    };
+
+    for (index, path) in request.paths.iter().enumerate() {
+        if *cancel_flag.read().await {
+            summary.cancelled = true;
+            break;
+        }
+
+        let file_name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+
+        let mut progress = BatchProgress {
+            index,
+            total: summary.total,
+            file_name,
+            entities_found: 0,
+            entity_counts: Vec::new(),
+            error: None,
+        };
+
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => {
+                let result = anonymizer.anonymize_batch_document(&text, &settings);
+
+                let line = serde_json::to_string(&result)
+                    .map_err(|e| format!("Failed to serialize result: {}", e))?;
+                writer
+                    .write_line(&line)
+                    .map_err(|e| format!("Failed to write result: {:#}", e))?;
+
+                progress.entities_found = result.entities.len();
+                progress.entity_counts = result
+                    .statistics
+                    .iter()
+                    .map(|(et, count)| (et.as_str().to_string(), *count))
+                    .collect();
+                progress.entity_counts.sort();
+                summary.processed += 1;
+            }
+            Err(e) => {
+                progress.error = Some(format!("Failed to read {}: {}", path, e));
+                summary.failed += 1;
+            }
+        }
+
+        on_progress(&progress);
+
+        if progress.error.is_some() && request.failure_policy == FailurePolicy::AbortOnError {
+            summary.error = progress.error;
+            break;
+        }
+
+        // Let the cancel command and event delivery run between documents
+        tokio::task::yield_now().await;
+    }
+
+    Ok(summary)
+}
+
+/// Anonymize files with a `pii-batch-progress` event per completed document
+#[tauri::command]
+pub async fn anonymize_batch_stream(
+    request: StreamBatchRequest,
+    anonymizer: State<'_, AnonymizerState>,
+    cancel_state: State<'_, BatchCancelState>,
+    window: tauri::Window,
+) -> Result<BatchSummary, String> {
+    let cancel_flag = Arc::new(RwLock::new(false));
+    {
+        let mut state = cancel_state.0.lock().await;
+        if state.is_some() {
+            return Err("Another batch is already in progress".to_string());
+        }
+        *state = Some(cancel_flag.clone());
+    }
+
+    let mut anon = anonymizer.lock().await;
+    let result = run_batch_stream(&mut anon, &request, &cancel_flag, |progress| {
+        let _ = window.emit("pii-batch-progress", progress);
+    })
+    .await;
+
+    *cancel_state.0.lock().await = None;
+    result
+}
+
+/// Stop the running streaming batch before its next document
+#[tauri::command]
+pub async fn cancel_pii_batch(cancel_state: State<'_, BatchCancelState>) -> Result<String, String> {
+    let state = cancel_state.0.lock().await;
+    let cancel_flag = state.as_ref().ok_or("No batch in progress")?;
+    *cancel_flag.write().await = true;
+
+    Ok("Batch cancellation requested".to_string())
+}
+
 /// Clear replacement mappings (start fresh)
 #[tauri::command]
 pub async fn clear_pii_replacements(
@@ -97,40 +653,480 @@ pub fn get_default_pii_settings() -> AnonymizationSettings {
 /// Get available entity types
 #[tauri::command]
 pub fn get_entity_types() -> Vec<String> {
-    vec![
-        EntityType::Person.as_str().to_string(),
-        EntityType::Organization.as_str().to_string(),
-        EntityType::Location.as_str().to_string(),
-        EntityType::Date.as_str().to_string(),
-        EntityType::Money.as_str().to_string(),
-        EntityType::Email.as_str().to_string(),
-        EntityType::Phone.as_str().to_string(),
-        EntityType::Case.as_str().to_string(),
-        EntityType::Identification.as_str().to_string(),
-        EntityType::TechnicalIdentifier.as_str().to_string(),
-        EntityType::Law.as_str().to_string(),
-    ]
+    EntityType::ALL
+        .iter()
+        .map(|entity_type| entity_type.as_str().to_string())
+        .collect()
 }
 
 /// Detect entities without anonymizing
+///
+/// Documents over the configured size limit are rejected with
+/// `DocumentTooLarge`.
 #[tauri::command]
 pub async fn detect_pii_entities(
     text: String,
     anonymizer: State<'_, AnonymizerState>,
-) -> Result<Vec<crate::pii::Entity>, String> {
+    db: State<'_, DatabaseManager>,
+) -> Result<Vec<crate::pii::Entity>, DocumentError> {
+    enforce_document_size(&text, &db).await?;
+
     let anon = anonymizer.lock().await;
     let _settings = AnonymizationSettings::default();
 
     // Just detect, don't anonymize
-    let result = anon.detector.detect(&text);
+    let mut result = anon.detector.detect(&text);
+    crate::pii::assign_utf16_offsets(&mut result, &text);
 
     Ok(result)
 }
 
+/// Estimate how much a document reveals before it is shared externally
+///
+/// Returns a 0-100 score and the entity types and residual factors behind it.
+#[tauri::command]
+pub async fn risk_score(
+    text: String,
+    anonymizer: State<'_, AnonymizerState>,
+) -> Result<RiskScore, String> {
+    let anon = anonymizer.lock().await;
+    let entities = anon.detector.detect(&text);
+    Ok(crate::pii::risk::risk_score(&text, &entities))
+}
+
+/// Re-detect entities in one region of a document, e.g. an edited paragraph
+///
+/// `start` and `end` are UTF-16 offsets into `text`, as the editor reports
+/// them. Returned entities carry full-document offsets.
+#[tauri::command]
+pub async fn detect_region(
+    text: String,
+    start: usize,
+    end: usize,
+    anonymizer: State<'_, AnonymizerState>,
+) -> Result<Vec<Entity>, String> {
+    let anon = anonymizer.lock().await;
+    detect_in_region(&anon.detector, &text, start, end)
+}
+
+/// Detect entities overlapping a UTF-16 range of `text`
+///
+/// Detection runs on the whole sentences around the range, so an entity
+/// crossing its edges is found in full rather than cut off.
+fn detect_in_region(
+    detector: &PIIDetector,
+    text: &str,
+    start: usize,
+    end: usize,
+) -> Result<Vec<Entity>, String> {
+    if start >= end {
+        return Err(format!("Invalid region: start {} must be before end {}", start, end));
+    }
+    let text_len = text.encode_utf16().count();
+    if end > text_len {
+        return Err(format!("Invalid region: end {} is past the end of the text ({})", end, text_len));
+    }
+
+    let start = utf16_to_byte(text, start);
+    let end = utf16_to_byte(text, end);
+    let (context_start, context_end) = sentence_context(text, start, end);
+
+    let mut entities: Vec<Entity> = detector
+        .detect(&text[context_start..context_end])
+        .into_iter()
+        .map(|mut entity| {
+            entity.start += context_start;
+            entity.end += context_start;
+            entity
+        })
+        .filter(|entity| entity.start < end && entity.end > start)
+        .collect();
+
+    crate::pii::assign_utf16_offsets(&mut entities, text);
+    Ok(entities)
+}
+
+/// Byte offset of a UTF-16 offset; offsets inside a character move past it
+fn utf16_to_byte(text: &str, utf16_offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (byte, ch) in text.char_indices() {
+        if utf16 >= utf16_offset {
+            return byte;
+        }
+        utf16 += ch.len_utf16();
+    }
+    text.len()
+}
+
+/// Widen a byte range to the sentences it touches
+///
+/// Sentences end at a newline or at '.', '!' or '?' followed by whitespace.
+fn sentence_context(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let mut context = (0, text.len());
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        let next_is_space = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        if ch != '\n' && !(matches!(ch, '.' | '!' | '?') && next_is_space) {
+            continue;
+        }
+
+        let boundary = i + ch.len_utf8();
+        if boundary <= start {
+            context.0 = boundary;
+        } else if boundary >= end {
+            context.1 = boundary;
+            break;
+        }
+    }
+
+    context
+}
+
+/// Anonymize text before sending it to an external (cloud) model
+///
+/// The replacement mapping stays local under the returned session token.
+#[tauri::command]
+pub async fn sanitize_for_external(
+    text: String,
+    settings: Option<AnonymizationSettings>,
+    anonymizer: State<'_, AnonymizerState>,
+    sessions: State<'_, ExternalSessionState>,
+) -> Result<SanitizedText, String> {
+    let mut anon = anonymizer.lock().await;
+    let mut sessions = sessions.lock().await;
+    let settings = request_settings(settings)?;
+
+    Ok(sanitize(&mut anon, &mut sessions, &text, &settings))
+}
+
+/// Put the original values back into an external model's reply
+#[tauri::command]
+pub async fn restore_external_response(
+    response: String,
+    session_token: String,
+    sessions: State<'_, ExternalSessionState>,
+) -> Result<String, String> {
+    let sessions = sessions.lock().await;
+    restore(&sessions, &response, &session_token)
+}
+
+/// Restore the original values in a reviewed copy of an anonymized document
+///
+/// `mapping_token` is the session token returned when the document was
+/// anonymized, and `settings` those it was anonymized with, whose replacement
+/// templates tell placeholders apart from other text. Placeholders added or
+/// altered during review that the mapping doesn't know are kept and reported
+/// rather than guessed.
+#[tauri::command]
+pub async fn deanonymize(
+    text: String,
+    mapping_token: String,
+    settings: Option<AnonymizationSettings>,
+    sessions: State<'_, ExternalSessionState>,
+) -> Result<DeanonymizedText, String> {
+    let settings = request_settings(settings)?;
+    let sessions = sessions.lock().await;
+    deanonymize_with(&sessions, &text, &mapping_token, &settings)
+}
+
+/// Forget the mapping of an external session
+#[tauri::command]
+pub async fn discard_external_session(
+    session_token: String,
+    sessions: State<'_, ExternalSessionState>,
+) -> Result<(), String> {
+    sessions.lock().await.remove(&session_token);
+    Ok(())
+}
+
+fn sanitize(
+    anonymizer: &mut Anonymizer,
+    sessions: &mut HashMap<String, Vec<(String, String)>>,
+    text: &str,
+    settings: &AnonymizationSettings,
+) -> SanitizedText {
+    let result = anonymizer.anonymize(text, settings);
+    let session_token = uuid::Uuid::new_v4().to_string();
+
+    let replacements: Vec<(String, String)> = result
+        .replacements
+        .into_iter()
+        .filter(|(original, replacement)| original != replacement)
+        .collect();
+    let entities_replaced = result.statistics.values().sum();
+
+    sessions.insert(session_token.clone(), replacements);
+
+    SanitizedText {
+        text: result.anonymized_text,
+        session_token,
+        entities_replaced,
+    }
+}
+
+fn restore(
+    sessions: &HashMap<String, Vec<(String, String)>>,
+    response: &str,
+    session_token: &str,
+) -> Result<String, String> {
+    let replacements = sessions
+        .get(session_token)
+        .ok_or_else(|| format!("Unknown external session: {}", session_token))?;
+
+    Ok(Anonymizer::restore(response, replacements))
+}
+
+/// Matcher for the placeholders `settings` produce: the default bracketed
+/// style, e.g. "[PERSON-A]", "[TECH-ID-3]", plus any replacement templates
+fn placeholder_matcher(settings: &AnonymizationSettings) -> Cow<'static, Regex> {
+    const DEFAULT_PLACEHOLDER: &str = r"\[[A-Z]+(?:-[A-Z]+)*-[A-Z0-9]+\]";
+    static DEFAULT: OnceLock<Regex> = OnceLock::new();
+
+    let templates: Vec<String> = EntityType::ALL
+        .iter()
+        .filter_map(|&entity_type| settings.replacement_template(entity_type))
+        .map(|template| {
+            regex::escape(template)
+                .replace(&regex::escape(INDEX_PLACEHOLDER), r"\d+")
+                .replace(&regex::escape(LETTER_PLACEHOLDER), "[A-Z]+")
+        })
+        .collect();
+    if templates.is_empty() {
+        return Cow::Borrowed(
+            DEFAULT.get_or_init(|| Regex::new(DEFAULT_PLACEHOLDER).expect("valid placeholder")),
+        );
+    }
+
+    let pattern = format!("{}|{}", templates.join("|"), DEFAULT_PLACEHOLDER);
+    Cow::Owned(Regex::new(&pattern).expect("escaped templates always compile"))
+}
+
+fn deanonymize_with(
+    sessions: &HashMap<String, Vec<(String, String)>>,
+    text: &str,
+    mapping_token: &str,
+    settings: &AnonymizationSettings,
+) -> Result<DeanonymizedText, String> {
+    let replacements = sessions
+        .get(mapping_token)
+        .ok_or_else(|| format!("Unknown mapping: {}", mapping_token))?;
+
+    let placeholder = placeholder_matcher(settings);
+    let mut unknown_placeholders: Vec<String> = Vec::new();
+    for found in placeholder.find_iter(text) {
+        let known = replacements.iter().any(|(_, replacement)| replacement == found.as_str());
+        if !known && !unknown_placeholders.iter().any(|p| p == found.as_str()) {
+            unknown_placeholders.push(found.as_str().to_string());
+        }
+    }
+
+    Ok(DeanonymizedText {
+        text: Anonymizer::restore(text, replacements),
+        unknown_placeholders,
+    })
+}
+
+/// Preview the matches of a custom PII pattern before it is saved
+#[tauri::command]
+pub fn test_pattern(regex: String, sample_text: String) -> Result<Vec<PatternMatch>, PatternError> {
+    find_pattern_matches(&regex, &sample_text)
+}
+
+/// Compile a pattern and collect its matches the way the pattern detector would
+/// (surrounding whitespace trimmed, empty matches skipped)
+fn find_pattern_matches(pattern: &str, text: &str) -> Result<Vec<PatternMatch>, PatternError> {
+    if pattern.trim().is_empty() {
+        return Err(PatternError {
+            kind: "empty".to_string(),
+            message: "Pattern is empty".to_string(),
+        });
+    }
+
+    let regex = Regex::new(pattern).map_err(|e| match e {
+        regex::Error::CompiledTooBig(_) => PatternError {
+            kind: "too_large".to_string(),
+            message: e.to_string(),
+        },
+        _ => PatternError {
+            kind: "syntax".to_string(),
+            message: e.to_string(),
+        },
+    })?;
+
+    Ok(regex
+        .find_iter(text)
+        .filter_map(|m| {
+            let raw = m.as_str();
+            let trimmed = raw.trim();
+            if trimmed.is_empty() {
+                return None;
+            }
+            let start = m.start() + (raw.len() - raw.trim_start().len());
+            Some(PatternMatch {
+                start,
+                end: start + trimmed.len(),
+                text: trimmed.to_string(),
+            })
+        })
+        .take(MAX_PATTERN_MATCHES)
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn write_documents(dir: &Path, texts: &[&str]) -> Vec<String> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let path = dir.join(format!("doc{}.txt", i));
+                std::fs::write(&path, text).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_batch_stream_emits_progress_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = write_documents(
+            dir.path(),
+            &[
+                "Contact john@example.com today.",
+                "No personal data here.",
+                "Send it to mary@example.com and john@example.com.",
+            ],
+        );
+        paths.insert(2, dir.path().join("missing.txt").to_string_lossy().to_string());
+
+        let request = StreamBatchRequest {
+            paths,
+            output_path: dir.path().join("results.jsonl").to_string_lossy().to_string(),
+            settings: None,
+            failure_policy: FailurePolicy::BestEffort,
+            export: ExportOptions::default(),
+        };
+
+        let mut anonymizer = Anonymizer::new();
+        let cancel_flag = RwLock::new(false);
+        let mut events = Vec::new();
+        let summary = run_batch_stream(&mut anonymizer, &request, &cancel_flag, |p| {
+            events.push(p.clone())
+        })
+        .await
+        .unwrap();
+
+        let indices: Vec<usize> = events.iter().map(|p| p.index).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+        assert!(events.iter().all(|p| p.total == 4));
+        assert_eq!(events[0].file_name, "doc0.txt");
+        assert_eq!(events[0].entities_found, 1);
+        assert_eq!(events[1].entities_found, 0);
+        assert!(events[2].error.is_some());
+        assert_eq!(events[3].entities_found, 2);
+
+        assert_eq!(summary.processed, 3);
+        assert_eq!(summary.failed, 1);
+        assert!(!summary.cancelled);
+        assert_eq!(summary.error, None);
+
+        // One result per readable document, with replacements consistent across them
+        let output = std::fs::read_to_string(&request.output_path).unwrap();
+        let results: Vec<AnonymizationResult> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(results.len(), 3);
+        assert!(!results[2].anonymized_text.contains("john@example.com"));
+        let first = results[0].entities[0].replacement.clone();
+        assert!(results[2].entities.iter().any(|e| e.replacement == first));
+    }
+
+    #[tokio::test]
+    async fn test_batch_stream_aborts_on_first_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = write_documents(dir.path(), &["a@example.com", "b@example.com"]);
+        paths.insert(1, dir.path().join("missing.txt").to_string_lossy().to_string());
+
+        let request = StreamBatchRequest {
+            paths,
+            output_path: dir.path().join("results.jsonl").to_string_lossy().to_string(),
+            settings: None,
+            failure_policy: FailurePolicy::AbortOnError,
+            export: ExportOptions::default(),
+        };
+
+        let mut anonymizer = Anonymizer::new();
+        let cancel_flag = RwLock::new(false);
+        let mut events = Vec::new();
+        let summary = run_batch_stream(&mut anonymizer, &request, &cancel_flag, |p| {
+            events.push(p.clone())
+        })
+        .await
+        .unwrap();
+
+        // The failing document is reported, and nothing after it runs
+        assert_eq!(events.len(), 2);
+        assert!(events[1].error.is_some());
+        assert_eq!((summary.processed, summary.failed), (1, 1));
+        assert!(!summary.cancelled);
+        assert!(summary.error.as_deref().unwrap().contains("missing.txt"));
+
+        let output = std::fs::read_to_string(&request.output_path).unwrap();
+        assert_eq!(output.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_failure_policy_defaults_to_best_effort() {
+        let request: StreamBatchRequest =
+            serde_json::from_str(r#"{"paths": [], "output_path": "out.jsonl", "settings": null}"#)
+                .unwrap();
+        assert_eq!(request.failure_policy, FailurePolicy::BestEffort);
+
+        let request: StreamBatchRequest = serde_json::from_str(
+            r#"{"paths": [], "output_path": "out.jsonl", "settings": null, "failure_policy": "abort_on_error"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.failure_policy, FailurePolicy::AbortOnError);
+    }
+
+    #[tokio::test]
+    async fn test_batch_stream_cancellation_stops_processing() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_documents(
+            dir.path(),
+            &["a@example.com", "b@example.com", "c@example.com"],
+        );
+        let request = StreamBatchRequest {
+            paths,
+            output_path: dir.path().join("results.jsonl").to_string_lossy().to_string(),
+            settings: None,
+            failure_policy: FailurePolicy::default(),
+            export: ExportOptions::default(),
+        };
+
+        let mut anonymizer = Anonymizer::new();
+        let cancel_flag = RwLock::new(false);
+        let mut events = Vec::new();
+        let summary = run_batch_stream(&mut anonymizer, &request, &cancel_flag, |p| {
+            events.push(p.index);
+            // Cancel as soon as the first document is done
+            *cancel_flag.try_write().unwrap() = true;
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(events, vec![0]);
+        assert!(summary.cancelled);
+        assert_eq!(summary.processed, 1);
+
+        let output = std::fs::read_to_string(&request.output_path).unwrap();
+        assert_eq!(output.lines().count(), 1);
+    }
+
     #[tokio::test]
     async fn test_anonymize_text_logic() {
         let mut anonymizer = Anonymizer::new();
@@ -144,4 +1140,290 @@ mod tests {
         assert!(!result.anonymized_text.contains("jane@example.com"));
         assert!(!result.entities.is_empty());
     }
+
+    #[test]
+    fn test_document_size_limit() {
+        let text = "John Doe emailed jane@example.com.";
+
+        let rejected = check_document_size(text, text.len() - 1).unwrap_err();
+        assert_eq!(rejected.kind, "document_too_large");
+        assert_eq!(rejected.limit_bytes, text.len() - 1);
+        assert_eq!(rejected.actual_bytes, text.len());
+        assert!(rejected.message.contains("anonymize_batch_stream"));
+
+        let json = serde_json::to_value(DocumentError::TooLarge(rejected)).unwrap();
+        assert_eq!(json["kind"], "document_too_large");
+        assert_eq!(json["actual_bytes"], text.len());
+
+        // A document of exactly the limit is processed
+        assert!(check_document_size(text, text.len()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_quick_anonymize_uses_patterns_when_nothing_else_is_available() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "John Doe emailed jane@example.com and called 555-123-4567.";
+
+        let result = quick_anonymize_with(&mut anonymizer, None, text).await;
+        assert_eq!(result.mode, DetectionMode::PatternOnly);
+        assert!(!result.anonymized_text.contains("jane@example.com"));
+        assert!(!result.anonymized_text.contains("John Doe"));
+        assert!(result.anonymized_text.contains("[EMAIL-1]"));
+        assert_eq!(result.counts.get(&EntityType::Email), Some(&1));
+        assert_eq!(result.total, result.counts.values().sum::<usize>());
+
+        // A detector without a loaded NER model offers nothing beyond patterns either
+        let pipeline = Arc::new(crate::ner::NerPipeline::new(Arc::new(
+            crate::ner::NerModelManager::new(),
+        )));
+        let detector = HybridDetector::without_presidio(pipeline);
+        let result = quick_anonymize_with(&mut anonymizer, Some(&detector), text).await;
+        assert_eq!(result.mode, DetectionMode::PatternOnly);
+        assert!(!result.anonymized_text.contains("jane@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_quick_anonymize_reports_patterns_when_ner_fails() {
+        let mut anonymizer = Anonymizer::new();
+        // Reports ready, so hybrid is recommended; NER fails, so only patterns ran
+        let pipeline = Arc::new(crate::ner::NerPipeline::failing("model crashed"));
+        let detector = HybridDetector::without_presidio(pipeline);
+        let text = "Write to jane@example.com.";
+
+        let result = quick_anonymize_with(&mut anonymizer, Some(&detector), text).await;
+        assert_eq!(result.mode, DetectionMode::PatternOnly);
+        assert_eq!(result.anonymized_text, "Write to [EMAIL-1].");
+        assert_eq!(result.total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_compare_modes_without_detector_runs_patterns_only() {
+        let patterns = PIIDetector::new();
+        let comparison = compare_modes_with(&patterns, None, "Mail jane@example.com.").await;
+
+        assert_eq!(comparison.results.len(), 1);
+        let pattern = &comparison.results[0];
+        assert_eq!(pattern.mode, DetectionMode::PatternOnly);
+        assert_eq!(pattern.entities.len(), 1);
+        assert_eq!(pattern.unique.len(), 1);
+
+        let skipped: Vec<DetectionMode> = comparison.skipped.iter().map(|s| s.mode).collect();
+        assert_eq!(skipped, vec![DetectionMode::Hybrid, DetectionMode::Full]);
+    }
+
+    #[tokio::test]
+    async fn test_compare_modes_shows_what_ner_adds() {
+        let text = "Please forward the draft to Madonna, copying jane@example.com.";
+        let start = text.find("Madonna").unwrap();
+        let person = crate::ner::NerEntity {
+            text: "Madonna".to_string(),
+            entity_type: "PER".to_string(),
+            confidence: 0.95,
+            start,
+            end: start + "Madonna".len(),
+            tokens: Vec::new(),
+            pii_type: Some(EntityType::Person),
+        };
+        let pipeline = Arc::new(crate::ner::NerPipeline::returning(vec![person]));
+        let detector = HybridDetector::without_presidio(pipeline);
+
+        let comparison = compare_modes_with(&PIIDetector::new(), Some(&detector), text).await;
+
+        let modes: Vec<DetectionMode> = comparison.results.iter().map(|r| r.mode).collect();
+        assert_eq!(modes, vec![DetectionMode::PatternOnly, DetectionMode::Hybrid]);
+        let (pattern, hybrid) = (&comparison.results[0], &comparison.results[1]);
+        assert!(hybrid.entities.len() > pattern.entities.len());
+        assert!(pattern.unique.is_empty());
+        assert_eq!(hybrid.unique.len(), 1);
+        assert_eq!(hybrid.unique[0].text, "Madonna");
+        assert_eq!(hybrid.unique[0].entity_type, EntityType::Person);
+
+        assert_eq!(comparison.skipped.len(), 1);
+        assert_eq!(comparison.skipped[0].mode, DetectionMode::Full);
+        assert_eq!(comparison.skipped[0].reason, "Presidio not running");
+    }
+
+    /// UTF-16 offset of `needle`, as the editor would send it
+    fn utf16_offset(text: &str, needle: &str) -> usize {
+        text[..text.find(needle).unwrap()].encode_utf16().count()
+    }
+
+    #[test]
+    fn test_detect_region_uses_document_offsets() {
+        let detector = PIIDetector::new();
+        let text = "Café résumé: mail jane@example.com today.\n\
+                    The second paragraph mentions bob@example.org and then ends.";
+
+        let start = utf16_offset(text, "The second");
+        let entities = detect_in_region(&detector, text, start, text.encode_utf16().count()).unwrap();
+
+        assert_eq!(entities.len(), 1);
+        let bob = &entities[0];
+        assert_eq!(bob.text, "bob@example.org");
+        assert_eq!(&text[bob.start..bob.end], "bob@example.org");
+        assert_eq!(bob.utf16_start, utf16_offset(text, "bob@"));
+        // Each "é" takes two bytes but one UTF-16 unit
+        assert_eq!(bob.start, bob.utf16_start + 3);
+    }
+
+    #[test]
+    fn test_detect_region_expands_entities_across_boundary() {
+        let detector = PIIDetector::new();
+        let text = "Mail jane@example.com or bob@example.org today. Nothing here.";
+
+        // Region covers "example.com or bob" only
+        let start = utf16_offset(text, "example.com");
+        let end = utf16_offset(text, "@example.org");
+        let entities = detect_in_region(&detector, text, start, end).unwrap();
+
+        let found: Vec<(&str, usize)> = entities.iter().map(|e| (e.text.as_str(), e.start)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("jane@example.com", text.find("jane@").unwrap()),
+                ("bob@example.org", text.find("bob@").unwrap()),
+            ]
+        );
+
+        // Entities in the same sentence but outside the region are left out
+        let start = utf16_offset(text, " or ");
+        let end = utf16_offset(text, "bob@");
+        assert!(detect_in_region(&detector, text, start, end).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detect_region_rejects_invalid_ranges() {
+        let detector = PIIDetector::new();
+        assert!(detect_in_region(&detector, "Short text.", 5, 5).is_err());
+        assert!(detect_in_region(&detector, "Short text.", 2, 50).is_err());
+    }
+
+    #[test]
+    fn test_external_round_trip() {
+        let mut anonymizer = Anonymizer::new();
+        let mut sessions = HashMap::new();
+        let settings = AnonymizationSettings::default();
+
+        let sanitized = sanitize(
+            &mut anonymizer,
+            &mut sessions,
+            "John Doe emailed jane@example.com about the contract.",
+            &settings,
+        );
+        assert!(!sanitized.text.contains("John Doe"));
+        assert!(!sanitized.text.contains("jane@example.com"));
+        assert!(sanitized.text.contains("[PERSON-A]"));
+        assert!(sanitized.text.contains("[EMAIL-1]"));
+
+        // A cloud model answering in terms of the placeholders
+        let cloud_response =
+            "Summary: [PERSON-A] wrote to [EMAIL-1]. I recommend [PERSON-A] keeps a copy.";
+        let restored = restore(&sessions, cloud_response, &sanitized.session_token).unwrap();
+
+        assert_eq!(
+            restored,
+            "Summary: John Doe wrote to jane@example.com. I recommend John Doe keeps a copy."
+        );
+    }
+
+    #[test]
+    fn test_restore_unknown_session() {
+        let sessions = HashMap::new();
+        let err = restore(&sessions, "[PERSON-A]", "missing").unwrap_err();
+        assert!(err.contains("Unknown external session"));
+    }
+
+    #[test]
+    fn test_deanonymize_reviewed_document() {
+        let mut anonymizer = Anonymizer::new();
+        let mut sessions = HashMap::new();
+        let settings = AnonymizationSettings::default();
+
+        let sanitized = sanitize(
+            &mut anonymizer,
+            &mut sessions,
+            "John Doe emailed jane@example.com about the lease.",
+            &settings,
+        );
+
+        // The reviewer edits the wording and mentions a party the mapping never saw
+        let reviewed = sanitized.text.replace("about the lease", "about the lease with [PERSON-C]")
+            + " [PERSON-A] signed; so did [PERSON-C].";
+        let result =
+            deanonymize_with(&sessions, &reviewed, &sanitized.session_token, &settings).unwrap();
+
+        assert_eq!(
+            result.text,
+            "John Doe emailed jane@example.com about the lease with [PERSON-C]. \
+             John Doe signed; so did [PERSON-C]."
+        );
+        assert_eq!(result.unknown_placeholders, vec!["[PERSON-C]".to_string()]);
+
+        let err = deanonymize_with(&sessions, &reviewed, "missing", &settings).unwrap_err();
+        assert!(err.contains("Unknown mapping"));
+    }
+
+    #[test]
+    fn test_deanonymize_custom_replacement_templates() {
+        let mut anonymizer = Anonymizer::new();
+        let mut sessions = HashMap::new();
+        let settings = AnonymizationSettings {
+            replacement_templates: HashMap::from([
+                (EntityType::Person, "«Party {letter}»".to_string()),
+                (EntityType::Email, "<mail #{index}>".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let sanitized = sanitize(
+            &mut anonymizer,
+            &mut sessions,
+            "John Doe emailed jane@example.com about the lease.",
+            &settings,
+        );
+        assert!(sanitized.text.contains("«Party A»"));
+
+        let reviewed = format!("{} Copy to «Party B» at <mail #2>, and [PHONE-1].", sanitized.text);
+        let result =
+            deanonymize_with(&sessions, &reviewed, &sanitized.session_token, &settings).unwrap();
+
+        assert!(result.text.starts_with("John Doe emailed jane@example.com about the lease."));
+        assert_eq!(
+            result.unknown_placeholders,
+            vec!["«Party B»".to_string(), "<mail #2>".to_string(), "[PHONE-1]".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_pattern_returns_matches() {
+        let matches =
+            find_pattern_matches(r"\bEMP-\d{4}\b", "Staff EMP-1234 and EMP-5678, not EMP-12.")
+                .unwrap();
+
+        assert_eq!(
+            matches,
+            vec![
+                PatternMatch {
+                    start: 6,
+                    end: 14,
+                    text: "EMP-1234".to_string()
+                },
+                PatternMatch {
+                    start: 19,
+                    end: 27,
+                    text: "EMP-5678".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pattern_invalid_regex() {
+        let err = find_pattern_matches(r"EMP-(\d{4}", "EMP-1234").unwrap_err();
+        assert_eq!(err.kind, "syntax");
+        assert!(err.message.contains("unclosed group"));
+
+        let err = find_pattern_matches("  ", "EMP-1234").unwrap_err();
+        assert_eq!(err.kind, "empty");
+    }
 }
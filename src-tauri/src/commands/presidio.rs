@@ -8,18 +8,56 @@
 #![allow(unused_imports)]
 
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
 
+use crate::commands::settings::{read_setting, write_setting};
+use crate::database::DatabaseManager;
+use crate::ner::NerModelManager;
+use crate::pii::Language;
 use crate::pii::presidio::{
-    AnonymizationOperator, PresidioAnonymizeResult, PresidioConfig, PresidioEntity,
-    PresidioManager, PresidioStatus,
+    AnalyzerContainerOptions, AnonymizationOperator, PresidioAnonymizeResult,
+    PresidioClientOptions, PresidioConfig, PresidioEntity, PresidioManager, PresidioStatus,
+    RequestQueueStats, RestartPolicy,
 };
+use crate::pii::Entity;
 
 // Global state for Presidio manager
 pub type PresidioState = Arc<Mutex<PresidioManager>>;
 
+/// Clone of the managed Presidio manager for reads that must not wait on the lock,
+/// and for detectors that should queue behind the same request limit
+pub type SharedPresidio = Arc<PresidioManager>;
+
+/// Settings key holding the analyzer container options as JSON
+const ANALYZER_OPTIONS_KEY: &str = "presidio_analyzer_options";
+
+/// Settings key holding the client timeouts and retries as JSON
+const CLIENT_OPTIONS_KEY: &str = "presidio_client_options";
+
+/// Settings key holding the containers' Docker restart policy as JSON
+const RESTART_POLICY_KEY: &str = "presidio_restart_policy";
+
+/// Settings key holding what to do with the containers when the app exits
+const SHUTDOWN_ACTION_KEY: &str = "presidio_shutdown_action";
+
+/// What happens to the Presidio containers when the app exits
+///
+/// Unless they are stopped here, the containers keep running (and keep their
+/// ports) after the app quits; with the default `unless-stopped` restart policy
+/// Docker also brings them back on boot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresidioShutdownAction {
+    /// Leave the containers running, so Presidio is ready on the next start
+    #[default]
+    LeaveRunning,
+    /// Stop the analyzer and anonymizer containers
+    StopContainers,
+}
+
 /// Presidio status response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PresidioStatusResponse {
@@ -33,7 +71,8 @@ pub struct PresidioStatusResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PresidioAnalyzeRequest {
     pub text: String,
-    pub language: Option<String>,
+    /// Defaults to English; invalid codes are rejected when the request is parsed
+    pub language: Option<Language>,
     pub entity_types: Option<Vec<String>>,
     pub score_threshold: Option<f64>,
 }
@@ -42,7 +81,10 @@ pub struct PresidioAnalyzeRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PresidioAnonymizeRequest {
     pub text: String,
-    pub language: Option<String>,
+    pub language: Option<Language>,
+    /// Operator applied to every entity; `encrypt` uses the key from the OS keychain
+    #[serde(default)]
+    pub operator: Option<AnonymizationOperator>,
 }
 
 /// Installation progress
@@ -67,31 +109,18 @@ pub async fn get_presidio_status(
         Err(e) => return Err(format!("Failed to check status: {}", e)),
     };
 
-    let (status_str, message) = match status {
-        PresidioStatus::NotInstalled => (
-            "not_installed".to_string(),
-            "Presidio is not installed. Docker images need to be downloaded.".to_string(),
-        ),
-        PresidioStatus::Stopped => (
-            "stopped".to_string(),
-            "Presidio is installed but not running.".to_string(),
-        ),
-        PresidioStatus::Starting => (
-            "starting".to_string(),
-            "Presidio is starting up...".to_string(),
-        ),
-        PresidioStatus::Running => (
-            "running".to_string(),
-            "Presidio is running and ready.".to_string(),
-        ),
-        PresidioStatus::Error(e) => (
-            "error".to_string(),
-            format!("Presidio error: {}", e),
-        ),
+    let message = match &status {
+        PresidioStatus::NotInstalled => {
+            "Presidio is not installed. Docker images need to be downloaded.".to_string()
+        }
+        PresidioStatus::Stopped => "Presidio is installed but not running.".to_string(),
+        PresidioStatus::Starting => "Presidio is starting up...".to_string(),
+        PresidioStatus::Running => "Presidio is running and ready.".to_string(),
+        PresidioStatus::Error(e) => format!("Presidio error: {}", e),
     };
 
     Ok(PresidioStatusResponse {
-        status: status_str,
+        status: status.as_str().to_string(),
         is_enabled,
         docker_available,
         message,
@@ -126,12 +155,247 @@ pub async fn install_presidio(
     }
 }
 
+/// Load analyzer container options from settings (defaults when unset)
+async fn load_analyzer_options(db: &DatabaseManager) -> Result<AnalyzerContainerOptions, String> {
+    let Some(conn) = db.get_connection().await else {
+        return Ok(AnalyzerContainerOptions::default());
+    };
+
+    match read_setting(&conn, ANALYZER_OPTIONS_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid analyzer options in settings: {}", e)),
+        None => Ok(AnalyzerContainerOptions::default()),
+    }
+}
+
+/// Apply the stored analyzer container options before containers are created
+async fn apply_analyzer_options(
+    manager: &PresidioManager,
+    db: &DatabaseManager,
+) -> Result<(), String> {
+    let options = load_analyzer_options(db).await?;
+    manager
+        .set_analyzer_options(options)
+        .await
+        .map_err(|e| format!("Invalid analyzer options: {}", e))
+}
+
+/// Load client timeouts and retries from settings (defaults when unset)
+async fn load_client_options(db: &DatabaseManager) -> Result<PresidioClientOptions, String> {
+    let Some(conn) = db.get_connection().await else {
+        return Ok(PresidioClientOptions::default());
+    };
+
+    match read_setting(&conn, CLIENT_OPTIONS_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid Presidio client options in settings: {}", e)),
+        None => Ok(PresidioClientOptions::default()),
+    }
+}
+
+/// Apply the stored client timeouts and retries
+async fn apply_client_options(
+    manager: &PresidioManager,
+    db: &DatabaseManager,
+) -> Result<(), String> {
+    let options = load_client_options(db).await?;
+    manager
+        .set_client_options(options)
+        .await
+        .map_err(|e| format!("Invalid Presidio client options: {}", e))
+}
+
+/// Load the restart policy from settings (`unless-stopped` when unset)
+async fn load_restart_policy(db: &DatabaseManager) -> Result<RestartPolicy, String> {
+    let Some(conn) = db.get_connection().await else {
+        return Ok(RestartPolicy::default());
+    };
+
+    match read_setting(&conn, RESTART_POLICY_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid Presidio restart policy in settings: {}", e)),
+        None => Ok(RestartPolicy::default()),
+    }
+}
+
+/// Apply the stored restart policy before containers are started
+async fn apply_restart_policy(
+    manager: &PresidioManager,
+    db: &DatabaseManager,
+) -> Result<(), String> {
+    manager.set_restart_policy(load_restart_policy(db).await?).await;
+    Ok(())
+}
+
+/// Load the exit action from settings (leave running when unset)
+async fn load_shutdown_action(db: &DatabaseManager) -> Result<PresidioShutdownAction, String> {
+    let Some(conn) = db.get_connection().await else {
+        return Ok(PresidioShutdownAction::default());
+    };
+
+    match read_setting(&conn, SHUTDOWN_ACTION_KEY).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid Presidio shutdown action in settings: {}", e)),
+        None => Ok(PresidioShutdownAction::default()),
+    }
+}
+
+/// Run the configured exit action; returns whether `stop` was called
+async fn run_shutdown_action<F, Fut>(db: &DatabaseManager, stop: F) -> Result<bool, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    match load_shutdown_action(db).await? {
+        PresidioShutdownAction::LeaveRunning => Ok(false),
+        PresidioShutdownAction::StopContainers => {
+            stop()
+                .await
+                .map_err(|e| format!("Failed to stop Presidio: {}", e))?;
+            Ok(true)
+        }
+    }
+}
+
+/// Exit hook: stop the Presidio containers if the user opted in
+pub async fn shutdown_presidio(presidio: &PresidioState, db: &DatabaseManager) {
+    let manager = presidio.lock().await;
+    match run_shutdown_action(db, || manager.stop()).await {
+        Ok(true) => log::info!("Stopped Presidio containers on exit"),
+        Ok(false) => {}
+        Err(e) => log::warn!("Presidio shutdown action failed: {}", e),
+    }
+}
+
+/// Get what happens to the Presidio containers when the app exits
+#[tauri::command]
+pub async fn get_presidio_shutdown_action(
+    db: State<'_, DatabaseManager>,
+) -> Result<PresidioShutdownAction, String> {
+    load_shutdown_action(&db).await
+}
+
+/// Choose whether the Presidio containers are stopped when the app exits
+#[tauri::command]
+pub async fn set_presidio_shutdown_action(
+    action: PresidioShutdownAction,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    let json = serde_json::to_string(&action)
+        .map_err(|e| format!("Failed to serialize Presidio shutdown action: {}", e))?;
+
+    write_setting(&conn, SHUTDOWN_ACTION_KEY.to_string(), json).await
+}
+
+/// Get the Docker restart policy of the Presidio containers
+#[tauri::command]
+pub async fn get_presidio_restart_policy(
+    db: State<'_, DatabaseManager>,
+) -> Result<RestartPolicy, String> {
+    load_restart_policy(&db).await
+}
+
+/// Choose the Docker restart policy of the Presidio containers
+///
+/// Containers created with another policy are recreated the next time
+/// Presidio is started.
+#[tauri::command]
+pub async fn set_presidio_restart_policy(
+    policy: RestartPolicy,
+    presidio: State<'_, PresidioState>,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let manager = presidio.lock().await;
+    manager.set_restart_policy(policy).await;
+
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    let json = serde_json::to_string(&policy)
+        .map_err(|e| format!("Failed to serialize Presidio restart policy: {}", e))?;
+
+    write_setting(&conn, RESTART_POLICY_KEY.to_string(), json).await
+}
+
+/// Get the env vars / volume mounts configured for the analyzer container
+#[tauri::command]
+pub async fn get_presidio_analyzer_options(
+    db: State<'_, DatabaseManager>,
+) -> Result<AnalyzerContainerOptions, String> {
+    load_analyzer_options(&db).await
+}
+
+/// Configure env vars / volume mounts for the analyzer container
+///
+/// Takes effect the next time the analyzer container is created.
+#[tauri::command]
+pub async fn set_presidio_analyzer_options(
+    options: AnalyzerContainerOptions,
+    presidio: State<'_, PresidioState>,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let manager = presidio.lock().await;
+    manager
+        .set_analyzer_options(options.clone())
+        .await
+        .map_err(|e| format!("Invalid analyzer options: {}", e))?;
+
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    let json = serde_json::to_string(&options)
+        .map_err(|e| format!("Failed to serialize analyzer options: {}", e))?;
+
+    write_setting(&conn, ANALYZER_OPTIONS_KEY.to_string(), json).await
+}
+
+/// Get the timeouts and retry count used for requests to Presidio
+#[tauri::command]
+pub async fn get_presidio_client_options(
+    db: State<'_, DatabaseManager>,
+) -> Result<PresidioClientOptions, String> {
+    load_client_options(&db).await
+}
+
+/// Configure timeouts and retries for requests to Presidio (applied immediately)
+#[tauri::command]
+pub async fn set_presidio_client_options(
+    options: PresidioClientOptions,
+    presidio: State<'_, PresidioState>,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let manager = presidio.lock().await;
+    manager
+        .set_client_options(options.clone())
+        .await
+        .map_err(|e| format!("Invalid Presidio client options: {}", e))?;
+
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+    let json = serde_json::to_string(&options)
+        .map_err(|e| format!("Failed to serialize Presidio client options: {}", e))?;
+
+    write_setting(&conn, CLIENT_OPTIONS_KEY.to_string(), json).await
+}
+
+/// Get the number of Presidio requests queued and in flight, for diagnostics
+#[tauri::command]
+pub async fn get_presidio_queue_stats(
+    presidio: State<'_, SharedPresidio>,
+) -> Result<RequestQueueStats, String> {
+    Ok(presidio.request_queue_stats().await)
+}
+
 /// Start Presidio containers
 #[tauri::command]
 pub async fn start_presidio(
     presidio: State<'_, PresidioState>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<String, String> {
     let manager = presidio.lock().await;
+    apply_analyzer_options(&manager, &db).await?;
+    apply_restart_policy(&manager, &db).await?;
+    apply_client_options(&manager, &db).await?;
 
     match manager.start().await {
         Ok(_) => Ok("Presidio started successfully".to_string()),
@@ -156,8 +420,12 @@ pub async fn stop_presidio(
 #[tauri::command]
 pub async fn enable_presidio(
     presidio: State<'_, PresidioState>,
+    db: State<'_, DatabaseManager>,
 ) -> Result<String, String> {
     let manager = presidio.lock().await;
+    apply_analyzer_options(&manager, &db).await?;
+    apply_restart_policy(&manager, &db).await?;
+    apply_client_options(&manager, &db).await?;
 
     match manager.enable().await {
         Ok(_) => Ok("Presidio enabled successfully".to_string()),
@@ -187,7 +455,7 @@ pub async fn presidio_analyze(
         return Err("Presidio is not enabled. Enable it first.".to_string());
     }
 
-    let language = request.language.unwrap_or_else(|| "en".to_string());
+    let language = request.language.unwrap_or_default();
 
     match manager.analyze(&request.text, &language).await {
         Ok(entities) => Ok(entities),
@@ -195,6 +463,35 @@ pub async fn presidio_analyze(
     }
 }
 
+/// Analyze text with Presidio and return internal entities
+///
+/// Same shape as the regex/NER layers; Presidio types without an internal
+/// equivalent are dropped.
+#[tauri::command]
+pub async fn presidio_analyze_entities(
+    request: PresidioAnalyzeRequest,
+    presidio: State<'_, PresidioState>,
+) -> Result<Vec<Entity>, String> {
+    let manager = presidio.lock().await;
+
+    if !manager.is_enabled().await {
+        return Err("Presidio is not enabled. Enable it first.".to_string());
+    }
+
+    let language = request.language.unwrap_or_default();
+
+    let mut entities = manager
+        .analyze_entities(&request.text, &language)
+        .await
+        .map_err(|e| format!("Analysis failed: {}", e))?;
+
+    if let Some(threshold) = request.score_threshold {
+        entities.retain(|e| e.confidence >= threshold);
+    }
+
+    Ok(entities)
+}
+
 /// Anonymize text using Presidio
 #[tauri::command]
 pub async fn presidio_anonymize(
@@ -207,14 +504,57 @@ pub async fn presidio_anonymize(
         return Err("Presidio is not enabled. Enable it first.".to_string());
     }
 
-    let language = request.language.unwrap_or_else(|| "en".to_string());
+    let language = request.language.unwrap_or_default();
 
-    match manager.anonymize(&request.text, &language, None).await {
+    let operators = request.operator.map(|operator| vec![operator]);
+
+    // Long documents go to Presidio in chunks; short ones are a single chunk
+    match manager
+        .anonymize_chunked(&request.text, &language, operators, |_| {})
+        .await
+    {
         Ok(result) => Ok(result),
         Err(e) => Err(format!("Anonymization failed: {}", e)),
     }
 }
 
+/// Store the key for the `encrypt` operator in the OS keychain
+#[tauri::command]
+pub async fn set_presidio_encryption_key(
+    key: String,
+    presidio: State<'_, PresidioState>,
+) -> Result<(), String> {
+    let manager = presidio.lock().await;
+    manager
+        .set_encryption_key(&key)
+        .map_err(|e| format!("Failed to set encryption key: {}", e))?;
+
+    log::info!("Presidio encryption key stored in OS keychain");
+    Ok(())
+}
+
+/// Remove the `encrypt` operator key from the OS keychain
+#[tauri::command]
+pub async fn clear_presidio_encryption_key(
+    presidio: State<'_, PresidioState>,
+) -> Result<(), String> {
+    let manager = presidio.lock().await;
+    manager
+        .clear_encryption_key()
+        .map_err(|e| format!("Failed to clear encryption key: {}", e))
+}
+
+/// Whether an `encrypt` operator key is stored
+#[tauri::command]
+pub async fn has_presidio_encryption_key(
+    presidio: State<'_, PresidioState>,
+) -> Result<bool, String> {
+    let manager = presidio.lock().await;
+    manager
+        .has_encryption_key()
+        .map_err(|e| format!("Failed to read encryption key: {}", e))
+}
+
 /// Get supported entity types from Presidio
 #[tauri::command]
 pub async fn get_presidio_entity_types(
@@ -258,6 +598,22 @@ pub async fn get_presidio_languages(
     }
 }
 
+/// Get languages usable for detection across the loaded NER model and Presidio
+#[tauri::command]
+pub async fn get_detection_languages(
+    presidio: State<'_, PresidioState>,
+    ner_manager: State<'_, Arc<Mutex<Option<NerModelManager>>>>,
+) -> Result<Vec<String>, String> {
+    let ner_language = match ner_manager.lock().await.as_ref() {
+        Some(manager) => manager.get_language().await,
+        None => None,
+    };
+    let ner_language = ner_language.and_then(|code| Language::parse(&code).ok());
+
+    let manager = presidio.lock().await;
+    Ok(manager.get_available_languages(ner_language.as_ref()).await)
+}
+
 /// Get default Presidio configuration
 #[tauri::command]
 pub fn get_presidio_config() -> PresidioConfig {
@@ -294,11 +650,83 @@ mod tests {
     fn test_analyze_request() {
         let request = PresidioAnalyzeRequest {
             text: "John Doe".to_string(),
-            language: Some("en".to_string()),
+            language: Some(Language::english()),
             entity_types: None,
             score_threshold: Some(0.5),
         };
 
-        assert_eq!(request.language, Some("en".to_string()));
+        assert_eq!(request.language, Some(Language::english()));
+    }
+
+    #[test]
+    fn test_analyze_request_normalizes_language() {
+        let request: PresidioAnalyzeRequest =
+            serde_json::from_str(r#"{"text": "Jan Jansen", "language": "nl-NL"}"#).unwrap();
+        assert_eq!(request.language.unwrap().as_presidio(), Some("nl"));
+
+        let invalid = serde_json::from_str::<PresidioAnalyzeRequest>(
+            r#"{"text": "Jan Jansen", "language": "dutch"}"#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    /// Run the exit action with a stop call that only counts invocations
+    async fn run_with_mock_stop(db: &DatabaseManager, fail: bool) -> (Result<bool, String>, usize) {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let result = run_shutdown_action(db, || async {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if fail {
+                anyhow::bail!("docker not found");
+            }
+            Ok(())
+        })
+        .await;
+        (result, calls.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_action_follows_setting() {
+        // Without a database the containers are left alone
+        let db = DatabaseManager::new();
+        assert_eq!(run_with_mock_stop(&db, false).await, (Ok(false), 0));
+
+        let dir = tempfile::tempdir().unwrap();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(load_shutdown_action(&db).await.unwrap(), PresidioShutdownAction::LeaveRunning);
+        assert_eq!(run_with_mock_stop(&db, false).await, (Ok(false), 0));
+
+        let conn = db.get_connection().await.unwrap();
+        write_setting(&conn, SHUTDOWN_ACTION_KEY.to_string(), "\"stop_containers\"".to_string())
+            .await
+            .unwrap();
+        assert_eq!(run_with_mock_stop(&db, false).await, (Ok(true), 1));
+
+        let (result, calls) = run_with_mock_stop(&db, true).await;
+        assert_eq!(calls, 1);
+        assert!(result.unwrap_err().contains("docker not found"));
+
+        write_setting(&conn, SHUTDOWN_ACTION_KEY.to_string(), "\"leave_running\"".to_string())
+            .await
+            .unwrap();
+        assert_eq!(run_with_mock_stop(&db, false).await, (Ok(false), 0));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_shutdown_setting_does_not_stop() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+        write_setting(&conn, SHUTDOWN_ACTION_KEY.to_string(), "\"always\"".to_string())
+            .await
+            .unwrap();
+
+        let (result, calls) = run_with_mock_stop(&db, false).await;
+        assert_eq!(calls, 0);
+        assert!(result.unwrap_err().contains("Invalid Presidio shutdown action"));
     }
 }
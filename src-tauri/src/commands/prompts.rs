@@ -1,4 +1,7 @@
-use crate::prompts::{LicenseTier, Prompt, PromptLibrary};
+use crate::prompts::{
+    CategoryNode, LibraryImportResult, LibraryManifest, LicenseTier, PackImportResult, Prompt,
+    PromptLibrary, VariableInfo,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -91,6 +94,16 @@ pub async fn get_prompt_categories(
         .map_err(|e| format!("Failed to get categories: {}", e))
 }
 
+/// Get the categories as a nested tree ("contracts/nda" under "contracts")
+#[tauri::command]
+pub async fn get_prompt_category_tree(
+    library: State<'_, Arc<Mutex<PromptLibrary>>>,
+) -> Result<Vec<CategoryNode>, String> {
+    let lib = library.lock().await;
+    lib.get_category_tree()
+        .map_err(|e| format!("Failed to get category tree: {}", e))
+}
+
 /// Get all available tags
 #[tauri::command]
 pub async fn get_prompt_tags(
@@ -173,6 +186,75 @@ pub async fn import_prompt_file(
         .map_err(|e| format!("Failed to import prompt: {}", e))
 }
 
+/// Import all prompts from a zip archive (prompt pack)
+///
+/// Returns one result per prompt file; invalid files are reported, not fatal.
+#[tauri::command]
+pub async fn import_prompt_pack(
+    file_path: String,
+    library: State<'_, Arc<Mutex<PromptLibrary>>>,
+) -> Result<Vec<PackImportResult>, String> {
+    let lib = library.lock().await;
+    let path = PathBuf::from(file_path);
+
+    let results = lib
+        .import_pack(&path)
+        .map_err(|e| format!("Failed to import prompt pack: {}", e))?;
+
+    let imported = results.iter().filter(|r| r.succeeded()).count();
+    log::info!(
+        "Imported {} of {} prompts from {:?}",
+        imported,
+        results.len(),
+        path
+    );
+
+    Ok(results)
+}
+
+/// Export the user's prompts and templates as a zip bundle, e.g. to move machines
+///
+/// Built-in prompts are left out; they are regenerated on every install.
+#[tauri::command]
+pub async fn export_library(
+    path: String,
+    library: State<'_, Arc<Mutex<PromptLibrary>>>,
+) -> Result<LibraryManifest, String> {
+    let lib = library.lock().await;
+    lib.export_library(&PathBuf::from(path))
+        .map_err(|e| format!("Failed to export library: {}", e))
+}
+
+/// Import prompts and templates from a bundle made by `export_library`
+///
+/// Returns one result per bundled item; an item whose id is taken by a
+/// different one is imported under a new id.
+#[tauri::command]
+pub async fn import_library(
+    path: String,
+    library: State<'_, Arc<Mutex<PromptLibrary>>>,
+) -> Result<Vec<LibraryImportResult>, String> {
+    let lib = library.lock().await;
+    lib.import_library(&PathBuf::from(path))
+        .map_err(|e| format!("Failed to import library: {}", e))
+}
+
+/// List a prompt's variables with their description, default and whether they are required
+#[tauri::command]
+pub async fn get_prompt_variables(
+    prompt_id: String,
+    library: State<'_, Arc<Mutex<PromptLibrary>>>,
+) -> Result<Vec<VariableInfo>, String> {
+    let lib = library.lock().await;
+
+    let prompt = lib
+        .get_prompt(&prompt_id)
+        .map_err(|e| format!("Failed to get prompt: {}", e))?
+        .ok_or_else(|| format!("Prompt not found: {}", prompt_id))?;
+
+    Ok(prompt.variable_manifest())
+}
+
 /// Request to apply variables to a prompt
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApplyVariablesRequest {
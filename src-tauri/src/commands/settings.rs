@@ -1,5 +1,5 @@
 use tauri::State;
-use sea_orm::{EntityTrait, ColumnTrait, QueryFilter, Set, ActiveModelTrait};
+use sea_orm::{EntityTrait, ColumnTrait, QueryFilter, Set, ActiveModelTrait, DatabaseConnection};
 use crate::database::DatabaseManager;
 use entity::settings;
 
@@ -13,9 +13,29 @@ pub async fn get_setting(
     let conn = db.get_connection().await
         .ok_or("Database not initialized")?;
 
+    read_setting(&conn, &key).await
+}
+
+#[tauri::command]
+pub async fn set_setting(
+    key: String,
+    value: String,
+    db: State<'_, DatabaseManager>,
+) -> Result<(), String> {
+    let conn = db.get_connection().await
+        .ok_or("Database not initialized")?;
+
+    write_setting(&conn, key, value).await
+}
+
+/// Read a setting value by key
+pub(crate) async fn read_setting(
+    conn: &DatabaseConnection,
+    key: &str,
+) -> Result<Option<String>, String> {
     match settings::Entity::find()
-        .filter(settings::Column::Key.eq(key.clone()))
-        .one(&conn)
+        .filter(settings::Column::Key.eq(key))
+        .one(conn)
         .await
     {
         Ok(Some(setting)) => Ok(Some(setting.value)),
@@ -24,25 +44,22 @@ pub async fn get_setting(
     }
 }
 
-#[tauri::command]
-pub async fn set_setting(
+/// Insert or update a setting value
+pub(crate) async fn write_setting(
+    conn: &DatabaseConnection,
     key: String,
     value: String,
-    db: State<'_, DatabaseManager>,
 ) -> Result<(), String> {
-    let conn = db.get_connection().await
-        .ok_or("Database not initialized")?;
-
     let existing = settings::Entity::find()
         .filter(settings::Column::Key.eq(key.clone()))
-        .one(&conn)
+        .one(conn)
         .await
         .map_err(|e| format!("Query failed: {}", e))?;
 
     if let Some(record) = existing {
         let mut model: settings::ActiveModel = record.into();
         model.value = Set(value);
-        model.update(&conn)
+        model.update(conn)
             .await
             .map_err(|e| format!("Update failed: {}", e))?;
     } else {
@@ -52,7 +69,7 @@ pub async fn set_setting(
             ..Default::default()
         };
         new_setting
-            .insert(&conn)
+            .insert(conn)
             .await
             .map_err(|e| format!("Insert failed: {}", e))?;
     }
@@ -0,0 +1,222 @@
+//! Aggregated backend status, so the frontend can check every component with
+//! one call on startup instead of one command per component
+
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::ai::{EngineStatusHandle, ModelStatus};
+use crate::commands::presidio::PresidioState;
+use crate::database::DatabaseManager;
+use crate::ner::hybrid_detector::LayerStatus;
+use crate::ner::{DetectionMode, NerModelManager};
+use crate::pii::{PresidioManager, PresidioStatus};
+use entity::models;
+
+/// Detection layers that can run right now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionLayers {
+    #[serde(flatten)]
+    pub layers: LayerStatus,
+    pub recommended_mode: DetectionMode,
+}
+
+/// State of every backend component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemStatus {
+    pub database_initialized: bool,
+    /// Model marked active in the database, loaded or not
+    pub active_model_id: Option<String>,
+    /// "not_loaded", "loading", "loaded" or "error"
+    pub ai_model_status: String,
+    pub ai_model_error: Option<String>,
+    pub ner_model_loaded: bool,
+    pub ner_model_id: Option<String>,
+    /// "not_installed", "stopped", "starting", "running" or "error"
+    pub presidio_status: String,
+    pub presidio_enabled: bool,
+    /// Device used for AI inference, e.g. "CPU" or "CUDA"
+    pub device: String,
+    pub detection_layers: DetectionLayers,
+}
+
+/// Get the status of the database, AI model, NER model, Presidio and detection layers
+///
+/// The AI model's status is read without the engine lock, so this answers
+/// while a generation is running.
+#[tauri::command]
+pub async fn get_system_status(
+    db: State<'_, DatabaseManager>,
+    engine_status: State<'_, EngineStatusHandle>,
+    ner_manager: State<'_, Arc<Mutex<Option<NerModelManager>>>>,
+    presidio: State<'_, PresidioState>,
+) -> Result<SystemStatus, String> {
+    let (presidio_status, presidio_enabled) = {
+        let presidio = presidio.lock().await;
+        (probe_presidio(&presidio).await, presidio.is_enabled().await)
+    };
+    let ner_manager = ner_manager.lock().await;
+
+    collect_system_status(
+        &db,
+        &engine_status,
+        ner_manager.as_ref(),
+        presidio_status,
+        presidio_enabled,
+    )
+    .await
+}
+
+/// Presidio's container status; a Docker failure is reported as the status,
+/// not as a failed call
+async fn probe_presidio(presidio: &PresidioManager) -> PresidioStatus {
+    presidio
+        .check_status()
+        .await
+        .unwrap_or_else(|e| PresidioStatus::Error(e.to_string()))
+}
+
+async fn collect_system_status(
+    db: &DatabaseManager,
+    engine: &EngineStatusHandle,
+    ner_manager: Option<&NerModelManager>,
+    presidio_status: PresidioStatus,
+    presidio_enabled: bool,
+) -> Result<SystemStatus, String> {
+    let conn = db.get_connection().await;
+    let active_model_id = match &conn {
+        Some(conn) => models::Entity::find()
+            .filter(models::Column::IsActive.eq(true))
+            .one(conn)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?
+            .map(|model| model.model_id),
+        None => None,
+    };
+
+    let ai_status = engine.get_status().await;
+    let ai_model_error = match &ai_status {
+        ModelStatus::Error(e) => Some(e.clone()),
+        _ => None,
+    };
+
+    let (ner_model_loaded, ner_model_id) = match ner_manager {
+        Some(manager) if manager.is_loaded().await => {
+            (true, manager.get_config().await.map(|config| config.model_id))
+        }
+        _ => (false, None),
+    };
+
+    let layers = LayerStatus {
+        layer1_pattern: true,
+        layer2_ner: ner_model_loaded,
+        layer3_presidio: presidio_enabled && presidio_status == PresidioStatus::Running,
+    };
+
+    Ok(SystemStatus {
+        database_initialized: conn.is_some(),
+        active_model_id,
+        ai_model_status: ai_status.as_str().to_string(),
+        ai_model_error,
+        ner_model_loaded,
+        ner_model_id,
+        presidio_status: presidio_status.as_str().to_string(),
+        presidio_enabled,
+        device: engine.get_device_info().await,
+        detection_layers: DetectionLayers {
+            recommended_mode: layers.recommended_mode(),
+            layers,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::InferenceEngine;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[tokio::test]
+    async fn test_status_reflects_components() {
+        let db = DatabaseManager::new();
+        let engine = Arc::new(Mutex::new(InferenceEngine::new()));
+        let engine_status = engine.lock().await.status_handle();
+        // Held throughout, as a running generation would
+        let _generation = engine.lock().await;
+
+        let status =
+            collect_system_status(&db, &engine_status, None, PresidioStatus::NotInstalled, false)
+                .await
+                .unwrap();
+        assert!(!status.database_initialized);
+        assert_eq!(status.active_model_id, None);
+        assert_eq!(status.ai_model_status, "not_loaded");
+        assert_eq!(status.ai_model_error, None);
+        assert!(!status.ner_model_loaded);
+        assert_eq!(status.ner_model_id, None);
+        assert_eq!(status.presidio_status, "not_installed");
+        assert!(!status.presidio_enabled);
+        assert!(!status.device.is_empty());
+
+        let layers = &status.detection_layers;
+        assert!(layers.layers.layer1_pattern);
+        assert!(!layers.layers.layer2_ner);
+        assert!(!layers.layers.layer3_presidio);
+        assert_eq!(layers.recommended_mode, DetectionMode::PatternOnly);
+
+        let dir = tempfile::tempdir().unwrap();
+        db.initialize(dir.path().join("test.db").to_str().unwrap())
+            .await
+            .unwrap();
+        let conn = db.get_connection().await.unwrap();
+        models::ActiveModel {
+            model_id: Set("tinyllama".to_string()),
+            name: Set("TinyLlama".to_string()),
+            provider: Set("local".to_string()),
+            size: Set("small".to_string()),
+            parameters: Set("1.1B".to_string()),
+            format: Set("gguf".to_string()),
+            status: Set("downloaded".to_string()),
+            is_active: Set(true),
+            ..Default::default()
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+
+        // A NER manager without a loaded model doesn't count as loaded
+        let ner_manager = NerModelManager::new();
+        let status = collect_system_status(
+            &db,
+            &engine_status,
+            Some(&ner_manager),
+            PresidioStatus::Running,
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(status.database_initialized);
+        assert_eq!(status.active_model_id.as_deref(), Some("tinyllama"));
+        assert!(!status.ner_model_loaded);
+        assert_eq!(status.ner_model_id, None);
+        assert_eq!(status.presidio_status, "running");
+        assert!(status.detection_layers.layers.layer3_presidio);
+    }
+
+    #[tokio::test]
+    async fn test_status_serializes_flat_layers() {
+        let db = DatabaseManager::new();
+        let engine_status = InferenceEngine::new().status_handle();
+        let status =
+            collect_system_status(&db, &engine_status, None, PresidioStatus::Stopped, false)
+                .await
+                .unwrap();
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["detection_layers"]["layer1_pattern"], true);
+        assert_eq!(json["detection_layers"]["recommended_mode"], "pattern_only");
+        assert_eq!(json["ai_model_status"], "not_loaded");
+    }
+}
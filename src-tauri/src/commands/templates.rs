@@ -1,3 +1,5 @@
+use crate::documents::ExportOptions;
+use crate::prompts::{CategoryNode, VariableInfo};
 use crate::templates::{DocumentTemplate, TemplateLibrary};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -39,6 +41,16 @@ pub async fn get_templates_by_category(
         .map_err(|e| format!("Failed to get templates by category: {}", e))
 }
 
+/// Get the template categories as a nested tree
+#[tauri::command]
+pub async fn get_template_category_tree(
+    library: State<'_, Arc<Mutex<TemplateLibrary>>>,
+) -> Result<Vec<CategoryNode>, String> {
+    let lib = library.lock().await;
+    lib.get_category_tree()
+        .map_err(|e| format!("Failed to get template category tree: {}", e))
+}
+
 /// Request to save a template
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaveTemplateRequest {
@@ -136,11 +148,64 @@ pub async fn render_template(
         .map_err(|e| format!("Failed to get template: {}", e))?
         .ok_or_else(|| format!("Template not found: {}", request.template_id))?;
 
-    template
-        .render(&request.variables)
+    lib.render(&template, &request.variables)
         .map_err(|e| format!("Failed to render template: {}", e))
 }
 
+/// Request to render a template into a file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportTemplateRequest {
+    pub template_id: String,
+    pub variables: HashMap<String, String>,
+    pub output_path: String,
+    /// Encoding, line ending and BOM of the written file
+    #[serde(default)]
+    pub options: ExportOptions,
+}
+
+/// Render a template with variables and write the document to a file
+#[tauri::command]
+pub async fn export_template(
+    request: ExportTemplateRequest,
+    library: State<'_, Arc<Mutex<TemplateLibrary>>>,
+) -> Result<String, String> {
+    let lib = library.lock().await;
+
+    let template = lib
+        .get_template(&request.template_id)
+        .map_err(|e| format!("Failed to get template: {}", e))?
+        .ok_or_else(|| format!("Template not found: {}", request.template_id))?;
+
+    let rendered = lib
+        .render(&template, &request.variables)
+        .map_err(|e| format!("Failed to render template: {}", e))?;
+
+    request
+        .options
+        .write(&PathBuf::from(&request.output_path), &rendered)
+        .map_err(|e| format!("Failed to export template: {:#}", e))?;
+
+    Ok(request.output_path)
+}
+
+/// List a template's variables, including those of included templates, with
+/// their description, default and whether they are required
+#[tauri::command]
+pub async fn get_template_variables(
+    template_id: String,
+    library: State<'_, Arc<Mutex<TemplateLibrary>>>,
+) -> Result<Vec<VariableInfo>, String> {
+    let lib = library.lock().await;
+
+    let template = lib
+        .get_template(&template_id)
+        .map_err(|e| format!("Failed to get template: {}", e))?
+        .ok_or_else(|| format!("Template not found: {}", template_id))?;
+
+    lib.variable_manifest(&template)
+        .map_err(|e| format!("Failed to list template variables: {}", e))
+}
+
 /// Validate template syntax
 #[tauri::command]
 pub async fn validate_template_syntax(
@@ -0,0 +1,166 @@
+//! Encoding of exported text files
+//!
+//! Exports default to UTF-8 with LF line endings and no byte order mark.
+//! Some downstream tools, older Windows applications in particular, only
+//! read files with a BOM, CRLF line endings or UTF-16, so exports take
+//! `ExportOptions` to match the user's environment.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Character encoding of an exported file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf16le,
+    Utf16be,
+}
+
+/// Line ending of an exported file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+}
+
+/// How exported text is written to disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    /// Start the file with the encoding's byte order mark
+    pub bom: bool,
+}
+
+impl ExportOptions {
+    /// Byte order mark written at the start of the file, if requested
+    pub fn bom_bytes(&self) -> &'static [u8] {
+        if !self.bom {
+            return &[];
+        }
+        match self.encoding {
+            TextEncoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            TextEncoding::Utf16le => &[0xFF, 0xFE],
+            TextEncoding::Utf16be => &[0xFE, 0xFF],
+        }
+    }
+
+    /// Text with its line endings converted and encoded, without a BOM
+    pub fn encode_fragment(&self, text: &str) -> Vec<u8> {
+        let text = match self.line_ending {
+            LineEnding::Lf => text.replace("\r\n", "\n"),
+            LineEnding::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        };
+        match self.encoding {
+            TextEncoding::Utf8 => text.into_bytes(),
+            TextEncoding::Utf16le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            TextEncoding::Utf16be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        }
+    }
+
+    /// Bytes of a whole exported file
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let mut bytes = self.bom_bytes().to_vec();
+        bytes.extend(self.encode_fragment(text));
+        bytes
+    }
+
+    /// Write `text` to `path`, replacing the file
+    pub fn write(&self, path: &Path, text: &str) -> Result<()> {
+        std::fs::write(path, self.encode(text))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Create `path` for writing line by line, starting with the BOM
+    pub fn create(&self, path: &Path) -> Result<ExportWriter<BufWriter<File>>> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        ExportWriter::new(BufWriter::new(file), *self)
+    }
+}
+
+/// Writes lines of an export as they are produced, e.g. JSON Lines
+pub struct ExportWriter<W: Write> {
+    inner: W,
+    options: ExportOptions,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(mut inner: W, options: ExportOptions) -> Result<Self> {
+        inner.write_all(options.bom_bytes())?;
+        Ok(Self { inner, options })
+    }
+
+    /// Write one line followed by the configured line ending, and flush it
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        self.inner.write_all(&self.options.encode_fragment(line))?;
+        self.inner
+            .write_all(&self.options.encode_fragment(self.options.line_ending.as_str()))?;
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom_and_crlf_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.txt");
+
+        let options = ExportOptions {
+            line_ending: LineEnding::Crlf,
+            bom: true,
+            ..Default::default()
+        };
+        options.write(&path, "[PERSON-1] signed.\nDone.\r\n").unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(&[0xEF, 0xBB, 0xBF]));
+        assert_eq!(&bytes[3..], b"[PERSON-1] signed.\r\nDone.\r\n");
+
+        // Defaults: UTF-8, LF, no BOM
+        ExportOptions::default().write(&path, "a\r\nb\n").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"a\nb\n");
+
+        let utf16 = ExportOptions {
+            encoding: TextEncoding::Utf16le,
+            bom: true,
+            ..Default::default()
+        };
+        assert_eq!(utf16.encode("é\n"), vec![0xFF, 0xFE, 0xE9, 0x00, 0x0A, 0x00]);
+    }
+
+    #[test]
+    fn test_export_writer_lines() {
+        let options = ExportOptions {
+            line_ending: LineEnding::Crlf,
+            bom: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        let mut writer = ExportWriter::new(&mut buffer, options).unwrap();
+        writer.write_line("{\"a\":1}").unwrap();
+        writer.write_line("{\"b\":2}").unwrap();
+
+        assert_eq!(buffer, b"\xEF\xBB\xBF{\"a\":1}\r\n{\"b\":2}\r\n");
+    }
+}
@@ -0,0 +1,270 @@
+//! Text extraction from imported documents
+//!
+//! Each file format is handled by a `DocumentExtractor`. The
+//! `ExtractorRegistry` picks the first registered extractor that supports a
+//! file, so adding a format (PDF, DOCX, RTF, EML, ...) only means adding an
+//! extractor and registering it in `ExtractorRegistry::with_defaults`.
+
+// Allow dead code - helpers and section kinds are for extractors still to come
+#![allow(dead_code)]
+
+pub mod export;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+pub use export::ExportOptions;
+
+/// Kind of boundary recorded in an extracted document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    Page,
+    Section,
+}
+
+/// A page or section of the extracted text, as byte offsets into `text`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentSection {
+    pub kind: SectionKind,
+    /// Page number or heading, when the format provides one
+    pub label: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Text extracted from a document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedDocument {
+    pub text: String,
+    /// Page/section boundaries in document order; empty if the format has none
+    pub sections: Vec<DocumentSection>,
+}
+
+impl ExtractedDocument {
+    /// Append text as a new page or section
+    pub fn push_section(&mut self, kind: SectionKind, label: Option<String>, text: &str) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.sections.push(DocumentSection {
+            kind,
+            label,
+            start,
+            end: self.text.len(),
+        });
+    }
+}
+
+/// Extracts text from one family of file formats
+pub trait DocumentExtractor: Send + Sync {
+    /// Short name for logs and error messages, e.g. "plain text"
+    fn name(&self) -> &str;
+
+    /// Whether this extractor can handle the file (by extension or content)
+    fn supports(&self, path: &Path) -> bool;
+
+    fn extract(&self, path: &Path) -> Result<ExtractedDocument>;
+}
+
+/// Lowercase file extension, if any
+pub fn file_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+/// Whether the file starts with the given magic bytes
+///
+/// Unreadable or shorter files simply don't match.
+pub fn has_magic_bytes(path: &Path, magic: &[u8]) -> bool {
+    let mut header = vec![0u8; magic.len()];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map(|_| header == magic)
+        .unwrap_or(false)
+}
+
+/// Plain text and Markdown files
+pub struct PlainTextExtractor;
+
+impl DocumentExtractor for PlainTextExtractor {
+    fn name(&self) -> &str {
+        "plain text"
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        matches!(file_extension(path).as_deref(), Some("txt" | "md" | "markdown"))
+    }
+
+    fn extract(&self, path: &Path) -> Result<ExtractedDocument> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(ExtractedDocument {
+            text,
+            sections: Vec::new(),
+        })
+    }
+}
+
+/// Routes files to the first extractor that supports them
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn DocumentExtractor>>,
+}
+
+impl ExtractorRegistry {
+    /// Registry without any extractors
+    pub fn new() -> Self {
+        Self {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Registry with every built-in extractor
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(PlainTextExtractor));
+        registry
+    }
+
+    /// Add an extractor; earlier registrations take precedence
+    pub fn register(&mut self, extractor: Box<dyn DocumentExtractor>) {
+        self.extractors.push(extractor);
+    }
+
+    /// Extractor that will handle the file, if any
+    pub fn find(&self, path: &Path) -> Option<&dyn DocumentExtractor> {
+        self.extractors
+            .iter()
+            .find(|extractor| extractor.supports(path))
+            .map(|extractor| extractor.as_ref())
+    }
+
+    /// Extract text with the matching extractor
+    pub fn extract(&self, path: &Path) -> Result<ExtractedDocument> {
+        if !path.is_file() {
+            anyhow::bail!("File not found: {}", path.display());
+        }
+
+        let extractor = self
+            .find(path)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported document format: {}", path.display()))?;
+
+        log::info!("Extracting {} as {}", path.display(), extractor.name());
+        extractor
+            .extract(path)
+            .with_context(|| format!("{} extraction failed", extractor.name()))
+    }
+}
+
+impl Default for ExtractorRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Claims `.fake` files and reports two pages
+    struct FakeExtensionExtractor;
+
+    impl DocumentExtractor for FakeExtensionExtractor {
+        fn name(&self) -> &str {
+            "fake extension"
+        }
+
+        fn supports(&self, path: &Path) -> bool {
+            file_extension(path).as_deref() == Some("fake")
+        }
+
+        fn extract(&self, _path: &Path) -> Result<ExtractedDocument> {
+            let mut document = ExtractedDocument::default();
+            document.push_section(SectionKind::Page, Some("1".to_string()), "first page");
+            document.push_section(SectionKind::Page, Some("2".to_string()), "second page");
+            Ok(document)
+        }
+    }
+
+    /// Claims files starting with `%FAKE`, whatever their extension
+    struct FakeMagicExtractor;
+
+    impl DocumentExtractor for FakeMagicExtractor {
+        fn name(&self) -> &str {
+            "fake magic"
+        }
+
+        fn supports(&self, path: &Path) -> bool {
+            has_magic_bytes(path, b"%FAKE")
+        }
+
+        fn extract(&self, _path: &Path) -> Result<ExtractedDocument> {
+            let mut document = ExtractedDocument::default();
+            document.push_section(SectionKind::Section, Some("Body".to_string()), "magic text");
+            Ok(document)
+        }
+    }
+
+    fn registry() -> ExtractorRegistry {
+        let mut registry = ExtractorRegistry::new();
+        registry.register(Box::new(FakeExtensionExtractor));
+        registry.register(Box::new(FakeMagicExtractor));
+        registry.register(Box::new(PlainTextExtractor));
+        registry
+    }
+
+    #[test]
+    fn test_routes_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contract.FAKE");
+        std::fs::write(&path, "ignored").unwrap();
+
+        let registry = registry();
+        assert_eq!(registry.find(&path).unwrap().name(), "fake extension");
+
+        let document = registry.extract(&path).unwrap();
+        assert_eq!(document.text, "first page\nsecond page");
+        assert_eq!(document.sections.len(), 2);
+        assert_eq!(&document.text[document.sections[1].start..document.sections[1].end], "second page");
+        assert_eq!(document.sections[1].label.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_routes_by_magic_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        // Magic bytes win over the .txt extension because the extractor is registered first
+        let path = dir.path().join("scan.txt");
+        std::fs::write(&path, "%FAKE-1.0 payload").unwrap();
+
+        let registry = registry();
+        assert_eq!(registry.find(&path).unwrap().name(), "fake magic");
+        assert_eq!(registry.extract(&path).unwrap().text, "magic text");
+
+        let plain = dir.path().join("notes.txt");
+        std::fs::write(&plain, "Plain notes").unwrap();
+        assert_eq!(registry.find(&plain).unwrap().name(), "plain text");
+        let document = registry.extract(&plain).unwrap();
+        assert_eq!(document.text, "Plain notes");
+        assert!(document.sections.is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.bin");
+        std::fs::write(&path, [0u8, 1, 2]).unwrap();
+
+        let registry = registry();
+        assert!(registry.find(&path).is_none());
+        let err = registry.extract(&path).unwrap_err();
+        assert!(err.to_string().contains("Unsupported document format"));
+
+        let err = registry.extract(&dir.path().join("missing.txt")).unwrap_err();
+        assert!(err.to_string().contains("File not found"));
+    }
+}
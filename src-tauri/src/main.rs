@@ -7,7 +7,9 @@ mod ai;
 mod services;
 mod prompts;
 mod templates;
+mod documents;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::Mutex;
@@ -17,16 +19,24 @@ async fn main() {
     let db_manager = database::DatabaseManager::new();
     let download_state: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     let anonymizer: Arc<Mutex<pii::Anonymizer>> = Arc::new(Mutex::new(pii::Anonymizer::new()));
+    let external_sessions: commands::pii::ExternalSessionState = Arc::new(Mutex::new(HashMap::new()));
+    let batch_cancel_state = commands::pii::BatchCancelState::default();
 
     // NER state
     let ner_manager: Arc<Mutex<Option<ner::NerModelManager>>> = Arc::new(Mutex::new(None));
     let hybrid_detector: Arc<Mutex<Option<ner::HybridDetector>>> = Arc::new(Mutex::new(None));
+    let ner_download_state = commands::ner::NerDownloadState::default();
 
     // AI inference state (Phase 3)
-    let inference_engine: Arc<Mutex<ai::InferenceEngine>> = Arc::new(Mutex::new(ai::InferenceEngine::new()));
+    let inference_engine = ai::InferenceEngine::new();
+    let engine_status = inference_engine.status_handle();
+    let inference_engine: Arc<Mutex<ai::InferenceEngine>> = Arc::new(Mutex::new(inference_engine));
+    let generation_cancel_state = commands::conversation::GenerationCancelState::default();
 
     // Presidio state (Phase 5 - Layer 3 PII)
-    let presidio_manager: Arc<Mutex<pii::PresidioManager>> = Arc::new(Mutex::new(pii::PresidioManager::new()));
+    let shared_presidio: commands::presidio::SharedPresidio = Arc::new(pii::PresidioManager::new());
+    let presidio_manager: commands::presidio::PresidioState =
+        Arc::new(Mutex::new(shared_presidio.as_ref().clone()));
 
     // Prompt library state (Phase 5)
     let base_dir = dirs::data_dir()
@@ -72,10 +82,16 @@ async fn main() {
             app.manage(db_manager);
             app.manage(download_state);
             app.manage(anonymizer);
+            app.manage(external_sessions);
+            app.manage(batch_cancel_state);
             app.manage(ner_manager);
             app.manage(hybrid_detector);
+            app.manage(ner_download_state);
             app.manage(inference_engine);
+            app.manage(engine_status);
+            app.manage(generation_cancel_state);
             app.manage(presidio_manager);
+            app.manage(shared_presidio);
             app.manage(prompt_library);
             app.manage(template_library);
             Ok(())
@@ -85,30 +101,70 @@ async fn main() {
             commands::settings::get_setting,
             commands::settings::set_setting,
             commands::settings::get_app_version,
+            // Backend status
+            commands::system::get_system_status,
             // Model management commands
             commands::models::list_models,
             commands::models::download_model,
             commands::models::delete_model,
             commands::models::set_active_model,
+            commands::models::validate_model_compatibility,
             commands::models::get_active_model,
             commands::models::cancel_download,
             commands::models::add_custom_model,
             commands::models::check_disk_space,
+            commands::models::get_download_timeouts,
+            commands::models::set_download_timeouts,
             commands::models::import_model_file,
+            commands::models::import_model_manifest,
+            commands::models::list_orphaned_models,
+            commands::models::prune_orphaned_models,
+            commands::models::verify_all_models,
+            commands::models::scan_for_models,
+            commands::models::register_discovered_model,
             // PII detection and anonymization commands (Phase 4)
             commands::pii::anonymize_text,
+            commands::pii::quick_anonymize,
+            commands::pii::compare_modes,
             commands::pii::anonymize_batch,
+            commands::pii::anonymize_batch_stream,
+            commands::pii::cancel_pii_batch,
+            commands::pii::get_anonymization_diff,
+            commands::pii::merge_replacement_mappings,
             commands::pii::clear_pii_replacements,
             commands::pii::get_pii_statistics,
             commands::pii::get_default_pii_settings,
             commands::pii::get_entity_types,
             commands::pii::detect_pii_entities,
+            commands::pii::get_max_document_bytes,
+            commands::pii::set_max_document_bytes,
+            commands::pii::risk_score,
+            commands::pii::detect_region,
+            commands::pii::test_pattern,
+            commands::pii::sanitize_for_external,
+            commands::pii::restore_external_response,
+            commands::pii::deanonymize,
+            commands::pii::discard_external_session,
             // NER model management and inference commands
             commands::ner::list_ner_models,
+            commands::ner::add_custom_ner_model,
             commands::ner::download_ner_model,
             commands::ner::delete_ner_model,
             commands::ner::load_ner_model,
             commands::ner::run_ner_inference,
+            commands::ner::set_presidio_boost,
+            commands::ner::get_presidio_boost,
+            commands::ner::set_entity_priority,
+            commands::ner::get_entity_priority,
+            commands::ner::set_min_entity_length,
+            commands::ner::get_min_entity_length,
+            commands::ner::set_ner_fallback_policy,
+            commands::ner::get_ner_fallback_policy,
+            commands::ner::detect_entities_with_report,
+            commands::ner::scan_folder_pii_counts,
+            commands::ner::export_folder_entities,
+            commands::ner::get_layer_coverage,
+            commands::ner::get_recommended_detection_mode,
             commands::ner::get_ner_recommendations,
             commands::ner::get_ner_recommendations_for_language,
             commands::ner::get_ner_models_by_use_case,
@@ -116,15 +172,33 @@ async fn main() {
             commands::ner::get_ner_status,
             // AI conversation and inference commands (Phase 3)
             commands::conversation::load_ai_model,
+            commands::conversation::warm_up_ai_model,
+            commands::conversation::benchmark_model,
+            commands::conversation::render_prompt,
             commands::conversation::unload_ai_model,
             commands::conversation::get_ai_model_status,
             commands::conversation::get_device_info,
             commands::conversation::generate_ai_response,
             commands::conversation::generate_ai_response_stream,
+            commands::conversation::cancel_generation,
+            commands::conversation::get_model_generation_config,
+            commands::conversation::set_model_generation_config,
             commands::conversation::get_system_prompts,
             commands::conversation::get_conversation_history,
+            commands::conversation::resume_conversation,
             commands::conversation::create_conversation,
             commands::conversation::delete_conversation,
+            commands::conversation::rename_conversation,
+            commands::conversation::summarize_conversation,
+            // Case commands
+            commands::cases::create_case,
+            commands::cases::list_cases,
+            commands::cases::delete_case,
+            commands::cases::place_legal_hold,
+            commands::cases::release_legal_hold,
+            commands::cases::prune_audit_log,
+            // Document import commands
+            commands::documents::extract_document_text,
             // Prompt library commands (Phase 5)
             commands::prompts::get_all_prompts,
             commands::prompts::get_prompt_by_id,
@@ -133,19 +207,27 @@ async fn main() {
             commands::prompts::get_prompts_by_tag,
             commands::prompts::get_prompts_by_tier,
             commands::prompts::get_prompt_categories,
+            commands::prompts::get_prompt_category_tree,
             commands::prompts::get_prompt_tags,
             commands::prompts::save_prompt,
             commands::prompts::delete_prompt,
             commands::prompts::import_prompt_file,
+            commands::prompts::import_prompt_pack,
+            commands::prompts::export_library,
+            commands::prompts::import_library,
             commands::prompts::apply_prompt_variables,
+            commands::prompts::get_prompt_variables,
             // Template library commands (Phase 5)
             commands::templates::get_all_templates,
             commands::templates::get_template_by_id,
             commands::templates::get_templates_by_category,
+            commands::templates::get_template_category_tree,
             commands::templates::save_template,
             commands::templates::delete_template,
             commands::templates::import_template_file,
             commands::templates::render_template,
+            commands::templates::export_template,
+            commands::templates::get_template_variables,
             commands::templates::validate_template_syntax,
             // Presidio commands (Phase 5 - Layer 3 PII)
             commands::presidio::get_presidio_status,
@@ -156,12 +238,46 @@ async fn main() {
             commands::presidio::enable_presidio,
             commands::presidio::disable_presidio,
             commands::presidio::presidio_analyze,
+            commands::presidio::presidio_analyze_entities,
             commands::presidio::presidio_anonymize,
+            commands::presidio::set_presidio_encryption_key,
+            commands::presidio::clear_presidio_encryption_key,
+            commands::presidio::has_presidio_encryption_key,
             commands::presidio::get_presidio_entity_types,
             commands::presidio::get_presidio_languages,
+            commands::presidio::get_detection_languages,
             commands::presidio::get_presidio_config,
+            commands::presidio::get_presidio_analyzer_options,
+            commands::presidio::set_presidio_analyzer_options,
+            commands::presidio::get_presidio_client_options,
+            commands::presidio::set_presidio_client_options,
+            commands::presidio::get_presidio_queue_stats,
             commands::presidio::is_presidio_enabled,
+            commands::presidio::get_presidio_shutdown_action,
+            commands::presidio::set_presidio_shutdown_action,
+            commands::presidio::get_presidio_restart_policy,
+            commands::presidio::set_presidio_restart_policy,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                let presidio = app_handle
+                    .state::<commands::presidio::PresidioState>()
+                    .inner()
+                    .clone();
+                let db = app_handle.state::<database::DatabaseManager>().inner().clone();
+
+                // The event loop runs inside the tokio runtime of `main`, which
+                // can't block on a future itself, so wait from a plain thread
+                let shutdown = std::thread::spawn(move || {
+                    tauri::async_runtime::block_on(commands::presidio::shutdown_presidio(
+                        &presidio, &db,
+                    ))
+                });
+                if shutdown.join().is_err() {
+                    log::warn!("Presidio shutdown hook panicked");
+                }
+            }
+        });
 }
@@ -1,12 +1,25 @@
 use anyhow::{Context, Result};
 use futures::StreamExt;
-use reqwest::Client;
-use std::path::PathBuf;
+use reqwest::header::{HeaderMap, CONTENT_RANGE, RANGE, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::fs::{self, File};
+use std::time::Duration;
+use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 
+use super::validator::ModelValidator;
+
+/// How many 429 responses are tolerated before a download is given up
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Backoff used when a 429 response carries no usable `Retry-After` header
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Upper bound on a single rate-limit wait, whatever the server asks for
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(300);
+
 /// Download progress information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DownloadProgress {
@@ -16,17 +29,98 @@ pub struct DownloadProgress {
     pub percentage: f64,
     pub speed_mbps: f64,
     pub status: DownloadStatus,
+    /// Human-readable detail, e.g. "rate limited, retrying in 30s"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Final details of a finished download, sent as `model-download-complete`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DownloadComplete {
+    pub model_id: String,
+    /// Where the model file was saved
+    pub file_path: String,
+    /// SHA-256 of the downloaded file (lowercase hex)
+    pub checksum: String,
+    /// Checksum listed in the registry, if any
+    pub expected_checksum: Option<String>,
+    /// Whether `checksum` matches `expected_checksum` (true when none is listed)
+    pub checksum_verified: bool,
+    pub total_bytes: u64,
+}
+
+impl DownloadComplete {
+    /// Hash and measure a downloaded file, checking it against the expected checksum
+    pub async fn from_file(
+        model_id: &str,
+        file_path: &Path,
+        expected_checksum: Option<&str>,
+    ) -> Result<Self> {
+        let checksum = ModelValidator::calculate_sha256(file_path).await?;
+        let total_bytes = fs::metadata(file_path)
+            .await
+            .context("Failed to read downloaded file size")?
+            .len();
+        let checksum_verified = expected_checksum
+            .map(|expected| ModelValidator::checksum_matches(&checksum, expected))
+            .unwrap_or(true);
+
+        Ok(Self {
+            model_id: model_id.to_string(),
+            file_path: file_path.to_string_lossy().to_string(),
+            checksum,
+            expected_checksum: expected_checksum.map(str::to_string),
+            checksum_verified,
+            total_bytes,
+        })
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum DownloadStatus {
     Starting,
     Downloading,
+    RateLimited,
     Completed,
     Failed,
     Cancelled,
 }
 
+/// Network timeouts for model downloads
+///
+/// There is deliberately no limit on the total duration: a multi-gigabyte
+/// download may take hours, but a connection that stalls is given up.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DownloadTimeouts {
+    /// Time allowed to establish a connection
+    pub connect_timeout_secs: u64,
+    /// Time allowed without receiving any data
+    pub read_timeout_secs: u64,
+}
+
+impl Default for DownloadTimeouts {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 30,
+            read_timeout_secs: 120,
+        }
+    }
+}
+
+impl DownloadTimeouts {
+    /// Reject zero timeouts, which would make every download fail
+    pub fn validate(&self) -> Result<()> {
+        if self.connect_timeout_secs == 0 {
+            anyhow::bail!("connect_timeout_secs must be greater than zero");
+        }
+        if self.read_timeout_secs == 0 {
+            anyhow::bail!("read_timeout_secs must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
 /// Model downloader with progress tracking
 pub struct ModelDownloader {
     client: Client,
@@ -36,8 +130,26 @@ pub struct ModelDownloader {
 
 impl ModelDownloader {
     pub fn new(models_dir: PathBuf) -> Result<Self> {
+        Self::with_timeouts(models_dir, DownloadTimeouts::default())
+    }
+
+    pub fn with_timeouts(models_dir: PathBuf, timeouts: DownloadTimeouts) -> Result<Self> {
+        timeouts.validate()?;
+        Self::with_durations(
+            models_dir,
+            Duration::from_secs(timeouts.connect_timeout_secs),
+            Duration::from_secs(timeouts.read_timeout_secs),
+        )
+    }
+
+    fn with_durations(
+        models_dir: PathBuf,
+        connect_timeout: Duration,
+        read_timeout: Duration,
+    ) -> Result<Self> {
         let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(300)) // 5 minutes timeout
+            .connect_timeout(connect_timeout)
+            .read_timeout(read_timeout)
             .build()?;
 
         Ok(Self {
@@ -59,6 +171,21 @@ impl ModelDownloader {
         *flag
     }
 
+    /// Sleep for `delay`, returning early (with `true`) if the download is cancelled
+    async fn wait_unless_cancelled(&self, delay: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + delay;
+        loop {
+            if self.is_cancelled().await {
+                return true;
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            tokio::time::sleep((deadline - now).min(Duration::from_millis(250))).await;
+        }
+    }
+
     /// Get the default models directory
     pub fn default_models_dir() -> Result<PathBuf> {
         let home_dir = dirs::home_dir()
@@ -94,23 +221,90 @@ impl ModelDownloader {
             percentage: 0.0,
             speed_mbps: 0.0,
             status: DownloadStatus::Starting,
+            message: None,
         });
 
-        // Start download
-        let response = self
-            .client
-            .get(download_url)
-            .send()
-            .await
-            .context("Failed to start download")?;
+        // Resume from a temp file left behind by an interrupted download
+        let mut downloaded_bytes = match fs::metadata(&temp_file_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let mut rate_limit_retries = 0;
+
+        // Start download, waiting out rate limits (429) from the current offset
+        let response = loop {
+            let mut request = self.client.get(download_url);
+            if downloaded_bytes > 0 {
+                request = request.header(RANGE, format!("bytes={}-", downloaded_bytes));
+            }
+
+            let response = request.send().await.context("Failed to start download")?;
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                break response;
+            }
+
+            rate_limit_retries += 1;
+            if rate_limit_retries > MAX_RATE_LIMIT_RETRIES {
+                anyhow::bail!(
+                    "Download rate limited: still receiving 429 after {} retries",
+                    MAX_RATE_LIMIT_RETRIES
+                );
+            }
+
+            let delay = retry_after(response.headers())
+                .unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF)
+                .min(MAX_RATE_LIMIT_BACKOFF);
+
+            progress_callback(DownloadProgress {
+                model_id: model_id.to_string(),
+                downloaded_bytes,
+                total_bytes: 0,
+                percentage: 0.0,
+                speed_mbps: 0.0,
+                status: DownloadStatus::RateLimited,
+                message: Some(format!("rate limited, retrying in {}s", delay.as_secs())),
+            });
+
+            if self.wait_unless_cancelled(delay).await {
+                progress_callback(DownloadProgress {
+                    model_id: model_id.to_string(),
+                    downloaded_bytes,
+                    total_bytes: 0,
+                    percentage: 0.0,
+                    speed_mbps: 0.0,
+                    status: DownloadStatus::Cancelled,
+                    message: None,
+                });
+
+                anyhow::bail!("Download cancelled by user");
+            }
+        };
 
         if !response.status().is_success() {
             anyhow::bail!("Download failed with status: {}", response.status());
         }
 
-        let total_bytes = response.content_length().unwrap_or(0);
-        let mut downloaded_bytes = 0u64;
-        let mut file = File::create(&temp_file_path)
+        // A full (200) response means the server ignored the range: start over
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        if !resumed {
+            downloaded_bytes = 0;
+        }
+
+        let total_bytes = if resumed {
+            content_range_total(response.headers())
+                .unwrap_or_else(|| downloaded_bytes + response.content_length().unwrap_or(0))
+        } else {
+            response.content_length().unwrap_or(0)
+        };
+        let resumed_from = downloaded_bytes;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&temp_file_path)
             .await
             .context("Failed to create file")?;
 
@@ -134,6 +328,7 @@ impl ModelDownloader {
                     percentage: (downloaded_bytes as f64 / total_bytes as f64) * 100.0,
                     speed_mbps: 0.0,
                     status: DownloadStatus::Cancelled,
+                    message: None,
                 });
 
                 anyhow::bail!("Download cancelled by user");
@@ -156,10 +351,10 @@ impl ModelDownloader {
                     0.0
                 };
 
-                // Calculate speed
+                // Calculate speed (only over bytes fetched by this request)
                 let elapsed_secs = start_time.elapsed().as_secs_f64();
                 let speed_mbps = if elapsed_secs > 0.0 {
-                    (downloaded_bytes as f64 / 1_000_000.0) / elapsed_secs
+                    ((downloaded_bytes - resumed_from) as f64 / 1_000_000.0) / elapsed_secs
                 } else {
                     0.0
                 };
@@ -172,6 +367,7 @@ impl ModelDownloader {
                     percentage,
                     speed_mbps,
                     status: DownloadStatus::Downloading,
+                    message: None,
                 });
 
                 last_update = std::time::Instant::now();
@@ -194,6 +390,7 @@ impl ModelDownloader {
             percentage: 100.0,
             speed_mbps: 0.0,
             status: DownloadStatus::Completed,
+            message: None,
         });
 
         Ok(file_path)
@@ -238,6 +435,31 @@ impl ModelDownloader {
     }
 }
 
+/// Parse a `Retry-After` header, given either in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds();
+    Some(Duration::from_secs(seconds.max(0) as u64))
+}
+
+/// Total size from a `Content-Range: bytes start-end/total` header
+fn content_range_total(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,4 +475,204 @@ mod tests {
         let filename = downloader.generate_filename("model.gguf");
         assert_eq!(filename, "model.gguf");
     }
+
+    fn collect_progress() -> (
+        Arc<std::sync::Mutex<Vec<DownloadProgress>>>,
+        impl Fn(DownloadProgress) + Send + 'static,
+    ) {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        (events, move |progress| sink.lock().unwrap().push(progress))
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_times_out() {
+        use tokio::io::AsyncWriteExt;
+
+        // Sends headers and a few bytes, then goes quiet
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/model.gguf", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\nGGUF")
+                    .await;
+                open.push(socket);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = ModelDownloader::with_durations(
+            dir.path().to_path_buf(),
+            Duration::from_secs(1),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let (_, callback) = collect_progress();
+
+        let started = std::time::Instant::now();
+        let result = downloader.download_model("test/model", &url, callback).await;
+
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_download_timeouts_validation() {
+        assert!(DownloadTimeouts::default().validate().is_ok());
+
+        let timeouts = DownloadTimeouts {
+            read_timeout_secs: 0,
+            ..DownloadTimeouts::default()
+        };
+        assert!(timeouts.validate().is_err());
+        assert!(ModelDownloader::with_timeouts(PathBuf::from("/tmp"), timeouts).is_err());
+    }
+
+    #[test]
+    fn test_retry_after_parsing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_then_full_download() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("GET", "/model.gguf")
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .expect(1)
+            .create_async()
+            .await;
+        // Served once the 429 mock has used up its single expected hit
+        let full = server
+            .mock("GET", "/model.gguf")
+            .with_status(200)
+            .with_body("GGUF model bytes")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = ModelDownloader::new(dir.path().to_path_buf()).unwrap();
+        let (events, callback) = collect_progress();
+
+        let url = format!("{}/model.gguf", server.url());
+        let path = downloader.download_model("test/model", &url, callback).await;
+
+        limited.assert_async().await;
+        full.assert_async().await;
+        let path = path.unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "GGUF model bytes");
+
+        let events = events.lock().unwrap();
+        let rate_limited = events
+            .iter()
+            .find(|p| matches!(p.status, DownloadStatus::RateLimited))
+            .expect("rate limit should be reported");
+        assert_eq!(rate_limited.message.as_deref(), Some("rate limited, retrying in 1s"));
+        assert!(matches!(events.last().unwrap().status, DownloadStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_download_complete_fields() {
+        let mut server = mockito::Server::new_async().await;
+        let body = "GGUF model bytes";
+        let mock = server
+            .mock("GET", "/model.gguf")
+            .with_status(200)
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = ModelDownloader::new(dir.path().to_path_buf()).unwrap();
+        let (_, callback) = collect_progress();
+
+        let url = format!("{}/model.gguf", server.url());
+        let path = downloader
+            .download_model("test/model", &url, callback)
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        let sha256 = ModelValidator::calculate_sha256(&path).await.unwrap();
+        assert_eq!(sha256.len(), 64);
+
+        // Registry checksums are compared case-insensitively
+        let complete = DownloadComplete::from_file("test/model", &path, Some(&sha256.to_uppercase()))
+            .await
+            .unwrap();
+        assert_eq!(complete.model_id, "test/model");
+        assert_eq!(complete.file_path, path.to_string_lossy());
+        assert!(complete.file_path.ends_with("test_model.gguf"));
+        assert_eq!(complete.checksum, sha256);
+        assert_eq!(complete.total_bytes, body.len() as u64);
+        assert!(complete.checksum_verified);
+
+        let mismatch = DownloadComplete::from_file("test/model", &path, Some("placeholder"))
+            .await
+            .unwrap();
+        assert!(!mismatch.checksum_verified);
+        assert_eq!(mismatch.expected_checksum.as_deref(), Some("placeholder"));
+
+        let unlisted = DownloadComplete::from_file("test/model", &path, None).await.unwrap();
+        assert!(unlisted.checksum_verified);
+
+        let json = serde_json::to_value(&complete).unwrap();
+        for field in ["model_id", "file_path", "checksum", "checksum_verified", "total_bytes"] {
+            assert!(!json[field].is_null(), "{}", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_resumes_from_offset() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("GET", "/model.gguf")
+            .match_header("range", "bytes=6-")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let partial = server
+            .mock("GET", "/model.gguf")
+            .match_header("range", "bytes=6-")
+            .with_status(206)
+            .with_header("content-range", "bytes 6-10/11")
+            .with_body("world")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("test_model.gguf.tmp"), "hello ").unwrap();
+        let downloader = ModelDownloader::new(dir.path().to_path_buf()).unwrap();
+        let (events, callback) = collect_progress();
+
+        let url = format!("{}/model.gguf", server.url());
+        let path = downloader.download_model("test/model", &url, callback).await;
+
+        limited.assert_async().await;
+        partial.assert_async().await;
+        assert_eq!(std::fs::read_to_string(path.unwrap()).unwrap(), "hello world");
+
+        let events = events.lock().unwrap();
+        let rate_limited = events
+            .iter()
+            .find(|p| matches!(p.status, DownloadStatus::RateLimited))
+            .unwrap();
+        assert_eq!(rate_limited.downloaded_bytes, 6);
+        assert_eq!(rate_limited.message.as_deref(), Some("rate limited, retrying in 0s"));
+    }
 }
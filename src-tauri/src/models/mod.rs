@@ -1,8 +1,11 @@
 pub mod downloader;
+pub mod quantization;
 pub mod registry;
 pub mod validator;
 
-pub use downloader::{DownloadProgress, DownloadStatus, ModelDownloader};
+pub use downloader::{
+    DownloadComplete, DownloadProgress, DownloadStatus, DownloadTimeouts, ModelDownloader,
+};
 #[allow(unused_imports)]
 pub use registry::{ModelInfo, ModelRegistry};
-pub use validator::ModelValidator;
+pub use validator::{ModelFormat, ModelMetadata, ModelValidator};
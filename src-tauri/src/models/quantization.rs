@@ -0,0 +1,162 @@
+//! GGUF quantization levels and RAM estimates
+//!
+//! Estimates are approximate: the weights take parameters × bits per weight,
+//! plus a share for the KV cache and runtime buffers. They are meant to tell
+//! users whether Q4_K_M or Q5_K_M fits, not to predict exact usage.
+
+/// Common llama.cpp quantizations with their effective bits per weight,
+/// smallest first. K-quants store per-block scales, so they use a little
+/// more than their nominal bit width.
+pub const QUANTIZATION_TIERS: &[(&str, f64)] = &[
+    ("Q2_K", 2.63),
+    ("Q3_K_M", 3.91),
+    ("Q4_K_M", 4.85),
+    ("Q5_K_M", 5.69),
+    ("Q6_K", 6.59),
+    ("Q8_0", 8.50),
+    ("F16", 16.0),
+];
+
+/// Extra memory relative to the weights, for activations and runtime buffers
+const RUNTIME_OVERHEAD: f64 = 0.10;
+
+/// Fixed memory for the KV cache at the default context length
+const CONTEXT_RESERVE: u64 = 512 * 1024 * 1024;
+
+/// Bits per weight implied by a quantization name ("Q4_K_M" -> 4, "BF16" -> 16)
+pub fn quantization_bits(name: &str) -> Option<u32> {
+    let digits: String = name
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Effective bits per weight, from the tier table or the nominal bit width
+fn bits_per_weight(quantization: &str) -> Option<f64> {
+    let name = quantization.trim().to_uppercase();
+    QUANTIZATION_TIERS
+        .iter()
+        .find(|(tier, _)| *tier == name)
+        .map(|(_, bits)| *bits)
+        .or_else(|| {
+            // Unlisted variants (Q4_0, Q5_K_S, BF16, ...) carry about half a
+            // bit of scales per weight, except for plain float formats
+            let bits = f64::from(quantization_bits(&name)?);
+            Some(if name.starts_with('Q') { bits + 0.5 } else { bits })
+        })
+}
+
+/// Number of parameters from a registry label such as "7B", "1.1B" or "110M"
+pub fn parameter_count(parameters: &str) -> Option<f64> {
+    let label = parameters.trim().to_uppercase();
+    let (number, scale) = match label.chars().last()? {
+        'B' => (&label[..label.len() - 1], 1e9),
+        'M' => (&label[..label.len() - 1], 1e6),
+        'K' => (&label[..label.len() - 1], 1e3),
+        _ => (label.as_str(), 1.0),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|count| *count > 0.0)
+        .map(|count| count * scale)
+}
+
+/// Approximate RAM, in bytes, needed to run a model at the given quantization
+pub fn estimate_ram(parameters: &str, quantization: &str) -> Option<u64> {
+    let weights = parameter_count(parameters)? * bits_per_weight(quantization)? / 8.0;
+    Some((weights * (1.0 + RUNTIME_OVERHEAD)) as u64 + CONTEXT_RESERVE)
+}
+
+/// Largest quantization tier whose estimated footprint fits in `available_ram`
+///
+/// Returns `None` when even the smallest tier doesn't fit or the parameter
+/// count can't be parsed.
+pub fn recommend_quantization(parameters: &str, available_ram: u64) -> Option<&'static str> {
+    QUANTIZATION_TIERS
+        .iter()
+        .rev()
+        .map(|(tier, _)| *tier)
+        .find(|tier| estimate_ram(parameters, tier).is_some_and(|ram| ram <= available_ram))
+}
+
+/// Memory available to new processes, if the platform reports it
+pub fn available_memory() -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    Some(system.available_memory()).filter(|bytes| *bytes > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_parameter_count() {
+        assert_eq!(parameter_count("7B"), Some(7e9));
+        assert_eq!(parameter_count("1.1b"), Some(1.1e9));
+        assert_eq!(parameter_count("110M"), Some(110e6));
+        assert_eq!(parameter_count("unknown"), None);
+        assert_eq!(parameter_count("0B"), None);
+    }
+
+    #[test]
+    fn test_estimate_ram_grows_with_quantization() {
+        let q4 = estimate_ram("7B", "Q4_K_M").unwrap();
+        let q5 = estimate_ram("7B", "q5_k_m").unwrap();
+        assert!(q4 < q5);
+        // 7B at Q4_K_M needs roughly 5 GB
+        assert!((4 * GB..6 * GB).contains(&q4), "{}", q4);
+
+        // Unlisted variants fall back to their nominal width
+        let q4_0 = estimate_ram("7B", "Q4_0").unwrap();
+        assert!(q4_0 < q4);
+        assert_eq!(estimate_ram("7B", "BF16"), estimate_ram("7B", "F16"));
+        assert_eq!(estimate_ram("7B", "GPTQ"), None);
+    }
+
+    #[test]
+    fn test_recommendation_picks_largest_fitting_tier() {
+        let cases = [
+            ("7B", 32 * GB, Some("F16")),
+            ("7B", 10 * GB, Some("Q8_0")),
+            ("7B", 8 * GB, Some("Q6_K")),
+            ("7B", 6 * GB, Some("Q4_K_M")),
+            ("7B", 9 * GB / 2, Some("Q3_K_M")),
+            ("7B", 4 * GB, Some("Q2_K")),
+            ("7B", 3 * GB, None),
+            ("13B", 8 * GB, Some("Q3_K_M")),
+            ("13B", 16 * GB, Some("Q8_0")),
+            ("1.1B", 2 * GB, Some("Q8_0")),
+            ("custom", 64 * GB, None),
+        ];
+        for (parameters, ram, expected) in cases {
+            assert_eq!(
+                recommend_quantization(parameters, ram),
+                expected,
+                "{} with {} bytes",
+                parameters,
+                ram
+            );
+        }
+    }
+
+    #[test]
+    fn test_recommendation_fits_available_ram() {
+        for ram in [4 * GB, 5 * GB, 7 * GB, 9 * GB] {
+            let tier = recommend_quantization("7B", ram).unwrap();
+            assert!(estimate_ram("7B", tier).unwrap() <= ram);
+        }
+    }
+
+    #[test]
+    fn test_available_memory_is_reported() {
+        // Linux, macOS and Windows all report it
+        assert!(available_memory().is_some_and(|bytes| bytes > 0));
+    }
+}
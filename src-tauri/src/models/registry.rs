@@ -125,6 +125,12 @@ impl ModelRegistry {
     pub fn get_model(&self, model_id: &str) -> Option<&ModelInfo> {
         self.models.iter().find(|m| m.model_id == model_id)
     }
+
+    /// Get recommended model for general legal work
+    pub fn get_recommended_model(&self) -> Option<&ModelInfo> {
+        // Mistral 7B balances quality and speed on typical laptops
+        self.get_model("mistralai/Mistral-7B-Instruct-v0.2")
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,59 @@
 use anyhow::{Context, Result};
+use candle_core::quantized::gguf_file;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
 use std::path::Path;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, BufReader};
 
+use super::quantization::quantization_bits;
+
+/// Upper bound on a safetensors JSON header, to reject corrupt files early
+const MAX_SAFETENSORS_HEADER: u64 = 100 * 1024 * 1024;
+
+/// Metadata read from a downloaded model file
+///
+/// Every field is optional: files that omit a key simply leave it unset.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ModelMetadata {
+    /// Model architecture, e.g. "llama" or "qwen2"
+    pub architecture: Option<String>,
+    /// Maximum context length in tokens
+    pub context_length: Option<u64>,
+    /// Quantization name, e.g. "Q4_K_M" or "F16"
+    pub quantization: Option<String>,
+    /// Approximate bits per weight implied by the quantization
+    pub quantization_bits: Option<u32>,
+}
+
+/// Weight file format, detected from the file contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Gguf,
+    Safetensors,
+    /// Raw `.bin` weights, which have no header to check
+    Bin,
+}
+
+impl ModelFormat {
+    /// Name stored in the `format` column of the models table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gguf => "gguf",
+            Self::Safetensors => "safetensors",
+            Self::Bin => "bin",
+        }
+    }
+}
+
 /// Model validator for checksum verification
 pub struct ModelValidator;
 
 impl ModelValidator {
-    /// Verify model file checksum
-    pub async fn verify_checksum(file_path: &Path, expected_checksum: &str) -> Result<bool> {
-        let calculated_checksum = Self::calculate_sha256(file_path).await?;
-        Ok(calculated_checksum.to_lowercase() == expected_checksum.to_lowercase())
+    /// Compare a calculated checksum with the expected one (hex case is ignored)
+    pub fn checksum_matches(calculated_checksum: &str, expected_checksum: &str) -> bool {
+        calculated_checksum.to_lowercase() == expected_checksum.to_lowercase()
     }
 
     /// Calculate SHA256 checksum of a file
@@ -41,8 +83,13 @@ impl ModelValidator {
         Ok(hex::encode(result))
     }
 
-    /// Validate model file structure (basic checks)
-    pub async fn validate_model_file(file_path: &Path) -> Result<bool> {
+    /// Validate a model file and detect its format from the contents
+    ///
+    /// GGUF files are recognized by their magic number and safetensors files
+    /// by a well-formed header, whatever their extension. A `.gguf` or
+    /// `.safetensors` file that fails its check is rejected; `.bin` files
+    /// are accepted as-is.
+    pub async fn validate_model_file(file_path: &Path) -> Result<ModelFormat> {
         // Check if file exists
         if !file_path.exists() {
             anyhow::bail!("Model file does not exist");
@@ -57,25 +104,183 @@ impl ModelValidator {
             anyhow::bail!("Model file is empty");
         }
 
-        // Check file extension (should be .gguf or .bin)
         let extension = file_path
             .extension()
             .and_then(|s| s.to_str())
-            .unwrap_or("");
+            .unwrap_or("")
+            .to_lowercase();
 
-        if !["gguf", "bin", "safetensors"].contains(&extension) {
+        if !["gguf", "bin", "safetensors"].contains(&extension.as_str()) {
             anyhow::bail!("Invalid model file extension: {}", extension);
         }
 
-        // For GGUF files, check magic number
-        if extension == "gguf" {
-            let is_valid_gguf = Self::validate_gguf_magic(file_path).await?;
-            if !is_valid_gguf {
-                anyhow::bail!("Invalid GGUF file format");
+        if Self::validate_gguf_magic(file_path).await? {
+            return Ok(ModelFormat::Gguf);
+        }
+
+        let path = file_path.to_path_buf();
+        let safetensors = tokio::task::spawn_blocking(move || Self::validate_safetensors(&path))
+            .await
+            .context("Validation task failed")?;
+
+        match (extension.as_str(), safetensors) {
+            (_, Ok(())) => Ok(ModelFormat::Safetensors),
+            ("gguf", Err(_)) => anyhow::bail!("Invalid GGUF file format"),
+            ("safetensors", Err(e)) => Err(e.context("Invalid safetensors file")),
+            _ => Ok(ModelFormat::Bin),
+        }
+    }
+
+    /// Check that a safetensors header is valid JSON and every tensor lies
+    /// within the data section
+    fn validate_safetensors(path: &Path) -> Result<()> {
+        let file_len = std::fs::metadata(path)
+            .context("Failed to read file metadata")?
+            .len();
+        let (header_len, header) = Self::read_safetensors_header(path)?;
+        let data_len = file_len
+            .checked_sub(8 + header_len)
+            .context("Safetensors header runs past the end of the file")?;
+
+        let mut tensors = 0;
+        for (name, tensor) in header.iter().filter(|(name, _)| name.as_str() != "__metadata__") {
+            let dtype = tensor.get("dtype").and_then(|d| d.as_str());
+            let shape = tensor.get("shape").and_then(|s| s.as_array());
+            let offsets: Option<Vec<u64>> = tensor
+                .get("data_offsets")
+                .and_then(|o| o.as_array())
+                .and_then(|o| o.iter().map(|v| v.as_u64()).collect());
+
+            match (dtype, shape, offsets.as_deref()) {
+                (Some(_), Some(_), Some(&[begin, end])) if begin <= end && end <= data_len => {
+                    tensors += 1;
+                }
+                _ => anyhow::bail!("Invalid entry for tensor '{}'", name),
+            }
+        }
+
+        if tensors == 0 {
+            anyhow::bail!("Safetensors file contains no tensors");
+        }
+        Ok(())
+    }
+
+    /// Read architecture, context length and quantization from a GGUF or safetensors file
+    ///
+    /// Unknown formats yield empty metadata rather than an error; only files
+    /// that cannot be opened or parsed fail.
+    pub async fn extract_metadata(file_path: &Path) -> Result<ModelMetadata> {
+        let path = file_path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            match path.extension().and_then(|s| s.to_str()).unwrap_or("") {
+                "gguf" => Self::gguf_metadata(&path),
+                "safetensors" => Self::safetensors_metadata(&path),
+                _ => Ok(ModelMetadata::default()),
             }
+        })
+        .await
+        .context("Metadata extraction task failed")?
+    }
+
+    fn gguf_metadata(path: &Path) -> Result<ModelMetadata> {
+        let mut file = std::fs::File::open(path).context("Failed to open model file")?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| anyhow::anyhow!("Failed to read GGUF header: {}", e))?;
+        let metadata = &content.metadata;
+
+        let architecture = metadata
+            .get("general.architecture")
+            .and_then(|v| v.to_string().ok())
+            .cloned();
+
+        let context_length = architecture
+            .as_ref()
+            .and_then(|arch| metadata.get(&format!("{}.context_length", arch)))
+            .and_then(gguf_integer);
+
+        // Prefer the declared file type, otherwise use the most common tensor type
+        let quantization = metadata
+            .get("general.file_type")
+            .and_then(gguf_integer)
+            .and_then(gguf_file_type_name)
+            .map(str::to_string)
+            .or_else(|| {
+                let mut counts: HashMap<String, usize> = HashMap::new();
+                for info in content.tensor_infos.values() {
+                    *counts.entry(format!("{:?}", info.ggml_dtype)).or_default() += 1;
+                }
+                counts
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                    .map(|(dtype, _)| dtype.to_uppercase())
+            });
+
+        Ok(ModelMetadata {
+            architecture,
+            context_length,
+            quantization_bits: quantization.as_deref().and_then(quantization_bits),
+            quantization,
+        })
+    }
+
+    /// Header length and the parsed JSON header of a safetensors file
+    fn read_safetensors_header(path: &Path) -> Result<(u64, HashMap<String, serde_json::Value>)> {
+        let mut file = std::fs::File::open(path).context("Failed to open model file")?;
+
+        let mut len_bytes = [0u8; 8];
+        file.read_exact(&mut len_bytes)
+            .context("Failed to read safetensors header length")?;
+        let header_len = u64::from_le_bytes(len_bytes);
+        if header_len > MAX_SAFETENSORS_HEADER {
+            anyhow::bail!("Safetensors header too large: {} bytes", header_len);
         }
 
-        Ok(true)
+        let mut header = vec![0u8; header_len as usize];
+        file.read_exact(&mut header)
+            .context("Failed to read safetensors header")?;
+        let header = serde_json::from_slice(&header).context("Invalid safetensors header")?;
+
+        Ok((header_len, header))
+    }
+
+    fn safetensors_metadata(path: &Path) -> Result<ModelMetadata> {
+        let (_, header) = Self::read_safetensors_header(path)?;
+
+        let quantization = header
+            .iter()
+            .filter(|(name, _)| name.as_str() != "__metadata__")
+            .filter_map(|(_, tensor)| tensor.get("dtype").and_then(|d| d.as_str()))
+            .fold(HashMap::new(), |mut counts: HashMap<&str, usize>, dtype| {
+                *counts.entry(dtype).or_default() += 1;
+                counts
+            })
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(dtype, _)| dtype.to_uppercase());
+
+        // Architecture and context length live in the accompanying config.json
+        let config: Option<serde_json::Value> = path
+            .parent()
+            .map(|dir| dir.join("config.json"))
+            .and_then(|config| std::fs::read(config).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+        let architecture = config
+            .as_ref()
+            .and_then(|c| c.get("model_type"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let context_length = config
+            .as_ref()
+            .and_then(|c| c.get("max_position_embeddings"))
+            .and_then(|v| v.as_u64());
+
+        Ok(ModelMetadata {
+            architecture,
+            context_length,
+            quantization_bits: quantization.as_deref().and_then(quantization_bits),
+            quantization,
+        })
     }
 
     /// Validate GGUF file magic number
@@ -83,9 +288,187 @@ impl ModelValidator {
         let mut file = File::open(file_path).await?;
         let mut magic = [0u8; 4];
 
-        file.read_exact(&mut magic).await?;
+        // Files shorter than the magic number can't be GGUF
+        if file.read_exact(&mut magic).await.is_err() {
+            return Ok(false);
+        }
 
         // GGUF magic number is "GGUF" in ASCII
         Ok(&magic == b"GGUF" || &magic == b"GGML" || &magic == b"GGJT")
     }
 }
+
+/// Integer GGUF metadata value of any width
+fn gguf_integer(value: &gguf_file::Value) -> Option<u64> {
+    use gguf_file::Value;
+    match value {
+        Value::U8(v) => Some(u64::from(*v)),
+        Value::U16(v) => Some(u64::from(*v)),
+        Value::U32(v) => Some(u64::from(*v)),
+        Value::U64(v) => Some(*v),
+        Value::I8(v) => u64::try_from(*v).ok(),
+        Value::I16(v) => u64::try_from(*v).ok(),
+        Value::I32(v) => u64::try_from(*v).ok(),
+        Value::I64(v) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// Name of a llama.cpp `general.file_type` value
+fn gguf_file_type_name(file_type: u64) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        32 => "BF16",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal safetensors file with one BF16 tensor
+    fn write_safetensors(path: &Path) {
+        let header = br#"{"w":{"dtype":"BF16","shape":[1],"data_offsets":[0,2]}}"#;
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header);
+        bytes.extend_from_slice(&[0, 0]);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    fn write_gguf(path: &Path, metadata: &[(&str, &gguf_file::Value)]) {
+        let mut file = std::fs::File::create(path).unwrap();
+        gguf_file::write(&mut file, metadata, &[]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_extract_gguf_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        write_gguf(
+            &path,
+            &[
+                ("general.architecture", &gguf_file::Value::String("llama".to_string())),
+                ("llama.context_length", &gguf_file::Value::U32(4096)),
+                ("general.file_type", &gguf_file::Value::U32(15)),
+            ],
+        );
+
+        let metadata = ModelValidator::extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.architecture.as_deref(), Some("llama"));
+        assert_eq!(metadata.context_length, Some(4096));
+        assert_eq!(metadata.quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(metadata.quantization_bits, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_extract_gguf_metadata_missing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        write_gguf(
+            &path,
+            &[("general.architecture", &gguf_file::Value::String("qwen2".to_string()))],
+        );
+
+        let metadata = ModelValidator::extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.architecture.as_deref(), Some("qwen2"));
+        assert_eq!(metadata.context_length, None);
+        assert_eq!(metadata.quantization, None);
+    }
+
+    #[tokio::test]
+    async fn test_extract_safetensors_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.safetensors");
+        write_safetensors(&path);
+        std::fs::write(
+            dir.path().join("config.json"),
+            r#"{"model_type":"mistral","max_position_embeddings":32768}"#,
+        )
+        .unwrap();
+
+        let metadata = ModelValidator::extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.architecture.as_deref(), Some("mistral"));
+        assert_eq!(metadata.context_length, Some(32768));
+        assert_eq!(metadata.quantization.as_deref(), Some("BF16"));
+        assert_eq!(metadata.quantization_bits, Some(16));
+    }
+
+    #[tokio::test]
+    async fn test_validate_detects_format_from_contents() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let gguf = dir.path().join("model.gguf");
+        write_gguf(&gguf, &[]);
+        assert_eq!(ModelValidator::validate_model_file(&gguf).await.unwrap(), ModelFormat::Gguf);
+
+        let safetensors = dir.path().join("model.safetensors");
+        write_safetensors(&safetensors);
+        assert_eq!(
+            ModelValidator::validate_model_file(&safetensors).await.unwrap(),
+            ModelFormat::Safetensors
+        );
+
+        // The header wins over a generic extension
+        let renamed = dir.path().join("weights.bin");
+        std::fs::copy(&safetensors, &renamed).unwrap();
+        assert_eq!(
+            ModelValidator::validate_model_file(&renamed).await.unwrap(),
+            ModelFormat::Safetensors
+        );
+
+        let raw = dir.path().join("raw.bin");
+        std::fs::write(&raw, b"weights").unwrap();
+        assert_eq!(ModelValidator::validate_model_file(&raw).await.unwrap(), ModelFormat::Bin);
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_bad_safetensors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.safetensors");
+
+        // Tensor data runs past the end of the file
+        let header = br#"{"w":{"dtype":"F32","shape":[4],"data_offsets":[0,16]}}"#;
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header);
+        bytes.extend_from_slice(&[0, 0]);
+        std::fs::write(&path, &bytes).unwrap();
+        let err = ModelValidator::validate_model_file(&path).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid entry for tensor 'w'"));
+
+        // Header length larger than the file
+        let mut bytes = 1000u64.to_le_bytes().to_vec();
+        bytes.extend_from_slice(b"{}");
+        std::fs::write(&path, &bytes).unwrap();
+        let err = ModelValidator::validate_model_file(&path).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid safetensors file"));
+
+        std::fs::write(&path, b"not a model").unwrap();
+        assert!(ModelValidator::validate_model_file(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extract_metadata_unknown_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        std::fs::write(&path, b"weights").unwrap();
+
+        let metadata = ModelValidator::extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata, ModelMetadata::default());
+    }
+}
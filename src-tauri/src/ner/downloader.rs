@@ -11,15 +11,20 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 
 use super::types::NerModelInfo;
+use crate::models::DownloadStatus;
 
 /// Download progress information
 #[derive(Debug, Clone)]
 pub struct DownloadProgress {
     pub file_name: String,
+    /// Position of `file_name` among the model's files (0-based)
+    pub file_index: usize,
+    pub file_count: usize,
     pub downloaded_bytes: u64,
     pub total_bytes: u64,
     pub progress_percent: f64,
     pub speed_mbps: f64,
+    pub status: DownloadStatus,
 }
 
 /// NER model downloader
@@ -61,12 +66,20 @@ impl NerModelDownloader {
         *flag
     }
 
+    /// Shared cancel flag, so another task can abort a running download
+    pub fn cancel_handle(&self) -> Arc<RwLock<bool>> {
+        self.cancel_flag.clone()
+    }
+
     /// Get the path where a model would be stored
     pub fn get_model_path(&self, model_id: &str) -> PathBuf {
         self.models_dir.join(model_id.replace('/', "_"))
     }
 
     /// Download a complete NER model (model weights + config + tokenizer)
+    ///
+    /// On cancellation or failure the model directory is removed, so no
+    /// partial artifacts are left behind.
     pub async fn download_model<F>(
         &self,
         model_info: &NerModelInfo,
@@ -79,75 +92,61 @@ impl NerModelDownloader {
         self.reset_cancel().await;
 
         // Create model directory
-        let model_dir = self.models_dir.join(&model_info.model_id.replace('/', "_"));
+        let model_dir = self.get_model_path(&model_info.model_id);
         fs::create_dir_all(&model_dir).await?;
 
-        // Download model weights
-        let model_path = model_dir.join("model.safetensors");
-        self.download_file(
-            &model_info.model_url,
-            &model_path,
-            "model.safetensors",
-            &progress_callback,
-        )
-        .await?;
-
-        if self.is_cancelled().await {
-            self.cleanup_partial_download(&model_dir).await?;
-            anyhow::bail!("Download cancelled");
-        }
+        // Model weights, config and tokenizer
+        let files = [
+            (model_info.model_url.as_str(), "model.safetensors"),
+            (model_info.config_url.as_str(), "config.json"),
+            (model_info.tokenizer_url.as_str(), "tokenizer.json"),
+        ];
+
+        for (file_index, (url, file_name)) in files.iter().enumerate() {
+            let file = FileDownload {
+                url,
+                dest_path: &model_dir.join(file_name),
+                file_name,
+                file_index,
+                file_count: files.len(),
+            };
 
-        // Download config
-        let config_path = model_dir.join("config.json");
-        self.download_file(
-            &model_info.config_url,
-            &config_path,
-            "config.json",
-            &progress_callback,
-        )
-        .await?;
-
-        if self.is_cancelled().await {
-            self.cleanup_partial_download(&model_dir).await?;
-            anyhow::bail!("Download cancelled");
-        }
+            let mut result = self.download_file(&file, &progress_callback).await;
+            if result.is_ok() && self.is_cancelled().await {
+                result = Err(anyhow::anyhow!("Download cancelled"));
+            }
+
+            if let Err(e) = result {
+                self.cleanup_partial_download(&model_dir).await?;
 
-        // Download tokenizer
-        let tokenizer_path = model_dir.join("tokenizer.json");
-        self.download_file(
-            &model_info.tokenizer_url,
-            &tokenizer_path,
-            "tokenizer.json",
-            &progress_callback,
-        )
-        .await?;
-
-        if self.is_cancelled().await {
-            self.cleanup_partial_download(&model_dir).await?;
-            anyhow::bail!("Download cancelled");
+                let status = if self.is_cancelled().await {
+                    DownloadStatus::Cancelled
+                } else {
+                    DownloadStatus::Failed
+                };
+                progress_callback(file.progress(0, 0, 0.0, status));
+
+                return Err(e);
+            }
         }
 
         Ok(model_dir)
     }
 
     /// Download a single file with progress tracking
-    async fn download_file<F>(
-        &self,
-        url: &str,
-        dest_path: &Path,
-        file_name: &str,
-        progress_callback: &F,
-    ) -> Result<()>
+    async fn download_file<F>(&self, file: &FileDownload<'_>, progress_callback: &F) -> Result<()>
     where
         F: Fn(DownloadProgress) + Send + Sync,
     {
         // Use temporary file during download
-        let temp_path = dest_path.with_extension("tmp");
+        let temp_path = file.dest_path.with_extension("tmp");
+
+        progress_callback(file.progress(0, 0, 0.0, DownloadStatus::Starting));
 
         // Send request
         let response = self
             .client
-            .get(url)
+            .get(file.url)
             .send()
             .await
             .context("Failed to send request")?;
@@ -160,7 +159,7 @@ impl NerModelDownloader {
         let total_bytes = response.content_length().unwrap_or(0);
 
         // Open file
-        let mut file = fs::File::create(&temp_path)
+        let mut out = fs::File::create(&temp_path)
             .await
             .context("Failed to create file")?;
 
@@ -172,25 +171,18 @@ impl NerModelDownloader {
         while let Some(chunk) = stream.next().await {
             // Check for cancellation
             if self.is_cancelled().await {
-                drop(file);
+                drop(out);
                 let _ = fs::remove_file(&temp_path).await;
                 anyhow::bail!("Download cancelled");
             }
 
             let chunk = chunk.context("Error reading chunk")?;
-            file.write_all(&chunk)
+            out.write_all(&chunk)
                 .await
                 .context("Error writing to file")?;
 
             downloaded_bytes += chunk.len() as u64;
 
-            // Calculate progress
-            let progress_percent = if total_bytes > 0 {
-                (downloaded_bytes as f64 / total_bytes as f64) * 100.0
-            } else {
-                0.0
-            };
-
             // Calculate speed (MB/s)
             let elapsed_secs = start_time.elapsed().as_secs_f64();
             let speed_mbps = if elapsed_secs > 0.0 {
@@ -200,24 +192,30 @@ impl NerModelDownloader {
             };
 
             // Report progress
-            progress_callback(DownloadProgress {
-                file_name: file_name.to_string(),
+            progress_callback(file.progress(
                 downloaded_bytes,
                 total_bytes,
-                progress_percent,
                 speed_mbps,
-            });
+                DownloadStatus::Downloading,
+            ));
         }
 
         // Flush and close file
-        file.flush().await?;
-        drop(file);
+        out.flush().await?;
+        drop(out);
 
         // Rename temp file to final destination
-        fs::rename(&temp_path, dest_path)
+        fs::rename(&temp_path, file.dest_path)
             .await
             .context("Failed to rename file")?;
 
+        progress_callback(file.progress(
+            downloaded_bytes,
+            downloaded_bytes,
+            0.0,
+            DownloadStatus::Completed,
+        ));
+
         Ok(())
     }
 
@@ -255,6 +253,42 @@ impl NerModelDownloader {
     }
 }
 
+/// One file of a model download
+struct FileDownload<'a> {
+    url: &'a str,
+    dest_path: &'a Path,
+    file_name: &'a str,
+    file_index: usize,
+    file_count: usize,
+}
+
+impl FileDownload<'_> {
+    fn progress(
+        &self,
+        downloaded_bytes: u64,
+        total_bytes: u64,
+        speed_mbps: f64,
+        status: DownloadStatus,
+    ) -> DownloadProgress {
+        let progress_percent = match status {
+            DownloadStatus::Completed => 100.0,
+            _ if total_bytes > 0 => (downloaded_bytes as f64 / total_bytes as f64) * 100.0,
+            _ => 0.0,
+        };
+
+        DownloadProgress {
+            file_name: self.file_name.to_string(),
+            file_index: self.file_index,
+            file_count: self.file_count,
+            downloaded_bytes,
+            total_bytes,
+            progress_percent,
+            speed_mbps,
+            status,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,4 +315,101 @@ mod tests {
         downloader.reset_cancel().await;
         assert!(!downloader.is_cancelled().await);
     }
+
+    fn mock_model_info(server_url: &str) -> NerModelInfo {
+        let mut info = crate::ner::NerModelRegistry::new()
+            .get_model("dslim/bert-base-NER")
+            .unwrap()
+            .clone();
+        info.model_url = format!("{}/model.safetensors", server_url);
+        info.config_url = format!("{}/config.json", server_url);
+        info.tokenizer_url = format!("{}/tokenizer.json", server_url);
+        info
+    }
+
+    #[tokio::test]
+    async fn test_download_reports_per_file_progress() {
+        let mut server = mockito::Server::new_async().await;
+        for (path, body) in [
+            ("/model.safetensors", "weights"),
+            ("/config.json", "{}"),
+            ("/tokenizer.json", "{}"),
+        ] {
+            server.mock("GET", path).with_body(body).create_async().await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = NerModelDownloader::new(dir.path().to_path_buf()).unwrap();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+
+        let model_dir = downloader
+            .download_model(&mock_model_info(&server.url()), move |p| {
+                sink.lock().unwrap().push(p)
+            })
+            .await
+            .unwrap();
+
+        assert!(downloader.is_downloaded("dslim/bert-base-NER").await);
+        assert_eq!(std::fs::read_to_string(model_dir.join("model.safetensors")).unwrap(), "weights");
+
+        let events = events.lock().unwrap();
+        let completed: Vec<_> = events
+            .iter()
+            .filter(|p| matches!(p.status, DownloadStatus::Completed))
+            .map(|p| (p.file_index, p.file_count, p.file_name.as_str()))
+            .collect();
+        assert_eq!(
+            completed,
+            vec![
+                (0, 3, "model.safetensors"),
+                (1, 3, "config.json"),
+                (2, 3, "tokenizer.json")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_transfer_removes_partial_files() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/model.safetensors")
+            .with_body(vec![0u8; 4 * 1024 * 1024])
+            .create_async()
+            .await;
+        let config = server
+            .mock("GET", "/config.json")
+            .with_body("{}")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = NerModelDownloader::new(dir.path().to_path_buf()).unwrap();
+        let cancel = downloader.cancel_handle();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+
+        // Cancel as soon as the first bytes of the weights arrive
+        let result = downloader
+            .download_model(&mock_model_info(&server.url()), move |p| {
+                if matches!(p.status, DownloadStatus::Downloading) {
+                    if let Ok(mut flag) = cancel.try_write() {
+                        *flag = true;
+                    }
+                }
+                sink.lock().unwrap().push(p);
+            })
+            .await;
+
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+        config.assert_async().await;
+
+        // Neither the model directory nor any temp file survives
+        assert!(!downloader.get_model_path("dslim/bert-base-NER").exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let events = events.lock().unwrap();
+        assert!(matches!(events.last().unwrap().status, DownloadStatus::Cancelled));
+    }
 }
@@ -6,19 +6,77 @@
 // Allow dead code - these are API components that will be used from frontend
 #![allow(dead_code)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::{Mutex, RwLock};
+use walkdir::WalkDir;
 
-use crate::pii::detector::PIIDetector;
+use crate::documents::{ExportOptions, ExtractorRegistry};
+use crate::pii::detector::{merge_adjacent_locations, PIIDetector};
+use crate::pii::language::Language;
 use crate::pii::presidio::{EntityTypeMapper, PresidioManager, PresidioStatus};
-use crate::pii::types::{Entity, EntityType};
+use crate::pii::types::{
+    assign_utf16_offsets, drop_short_entities, DetectionSource, Entity, EntityType, FailurePolicy,
+    DEFAULT_MIN_ENTITY_LENGTH,
+};
 
 use super::inference::NerPipeline;
-use super::types::NerResult;
+use super::types::{NerModelInfo, NerResult};
+
+/// Default confidence bonus for Presidio on identification, email and phone spans
+pub const DEFAULT_PRESIDIO_BOOST: f64 = 0.05;
+
+/// Entity types in the order they win equal-confidence overlaps, most
+/// specific first; legal references come last so a tie never leaves PII
+/// unredacted
+pub const DEFAULT_ENTITY_PRIORITY: [EntityType; 11] = [
+    EntityType::Identification,
+    EntityType::Email,
+    EntityType::Phone,
+    EntityType::TechnicalIdentifier,
+    EntityType::Case,
+    EntityType::Person,
+    EntityType::Organization,
+    EntityType::Money,
+    EntityType::Date,
+    EntityType::Location,
+    EntityType::Law,
+];
+
+/// Position of a type in a priority order; lower wins
+fn priority_rank(priority: &[EntityType], entity_type: EntityType) -> usize {
+    priority
+        .iter()
+        .position(|t| *t == entity_type)
+        .unwrap_or(priority.len())
+}
+
+/// Whether `challenger`, scored `challenger_score`, should replace an
+/// overlapping `incumbent`: higher confidence wins, and on a tie the type
+/// ranked first in `priority`
+fn outranks(
+    challenger: &Entity,
+    challenger_score: f64,
+    incumbent: &Entity,
+    priority: &[EntityType],
+) -> bool {
+    match challenger_score.partial_cmp(&incumbent.confidence) {
+        Some(std::cmp::Ordering::Greater) => true,
+        Some(std::cmp::Ordering::Equal) => {
+            priority_rank(priority, challenger.entity_type)
+                < priority_rank(priority, incumbent.entity_type)
+        }
+        _ => false,
+    }
+}
 
 /// Detection mode for hybrid detector
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DetectionMode {
     /// Use only pattern-based detection (regex) - Layer 1
     PatternOnly,
@@ -38,41 +96,224 @@ impl Default for DetectionMode {
     }
 }
 
+/// What to do when the NER model is loaded but inference fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NerFallbackPolicy {
+    /// Log the error and continue with the other layers
+    #[default]
+    ContinueWithPatterns,
+    /// Fail the whole detection request
+    FailRequest,
+}
+
+/// Outcome of the NER layer during one detection
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum NerLayerStatus {
+    /// The detection mode doesn't use NER
+    #[default]
+    NotUsed,
+    /// No NER model is loaded, so only the other layers ran
+    NotLoaded,
+    /// NER ran and its entities are included
+    Succeeded,
+    /// NER inference failed and was skipped under `ContinueWithPatterns`
+    Failed { reason: String },
+}
+
+/// Notice that none of the active model-based layers supports a language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsupportedLanguage {
+    pub language: Language,
+    pub message: String,
+}
+
+/// Detected entities together with the outcome of the NER layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionReport {
+    pub entities: Vec<Entity>,
+    pub ner_status: NerLayerStatus,
+    /// Set when detection fell back to patterns for an unsupported language
+    #[serde(default)]
+    pub unsupported_language: Option<UnsupportedLanguage>,
+}
+
+/// Time spent in each detection layer during one `detect` call, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DetectionTimings {
+    pub pattern_ms: f64,
+    pub ner_ms: f64,
+    pub presidio_ms: f64,
+    pub merge_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Pattern match counts for one file of a folder scan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileScanCounts {
+    pub path: String,
+    pub counts: HashMap<EntityType, usize>,
+    pub total: usize,
+    /// Set when the file could not be read; counts are then empty
+    pub error: Option<String>,
+}
+
+/// Detected entities of one file, written as one line of an entity export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityExportRecord {
+    pub file: String,
+    pub mode: DetectionMode,
+    pub language: Language,
+    pub entities: Vec<Entity>,
+    pub counts: HashMap<EntityType, usize>,
+    /// Set when the file could not be read or detection failed; entities are then empty
+    pub error: Option<String>,
+}
+
+/// Entity types one active detection layer can produce
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerCoverage {
+    pub layer: DetectionSource,
+    /// In `EntityType::ALL` order
+    pub entity_types: Vec<EntityType>,
+}
+
+/// Outcome of an entity export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntityExportSummary {
+    pub output_path: String,
+    /// Records written, one per supported file
+    pub files: usize,
+    pub entities: usize,
+    pub failed: usize,
+}
+
+/// Entity types reachable from a set of types, in `EntityType::ALL` order
+fn in_canonical_order(types: impl IntoIterator<Item = EntityType>) -> Vec<EntityType> {
+    let types: Vec<EntityType> = types.into_iter().collect();
+    EntityType::ALL
+        .into_iter()
+        .filter(|entity_type| types.contains(entity_type))
+        .collect()
+}
+
+/// Files under `folder` that have a text extractor, sorted by path
+fn supported_documents<'a>(
+    folder: &Path,
+    extractors: &'a ExtractorRegistry,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    WalkDir::new(folder)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file() && extractors.find(path).is_some())
+}
+
+/// Quick scan every supported document under a folder with `scanner`
+///
+/// Files without a text extractor are skipped; files that fail to extract
+/// are reported with an error, and under `FailurePolicy::AbortOnError` end
+/// the scan there. Results are sorted by path. This walks the folder and
+/// reads every file synchronously, so run it on a blocking thread.
+pub fn scan_folder(
+    scanner: &PIIDetector,
+    folder: &Path,
+    policy: FailurePolicy,
+) -> Result<Vec<FileScanCounts>> {
+    if !folder.is_dir() {
+        anyhow::bail!("Not a folder: {}", folder.display());
+    }
+
+    let extractors = ExtractorRegistry::with_defaults();
+    let mut results = Vec::new();
+
+    for path in supported_documents(folder, &extractors) {
+        let mut file_counts = FileScanCounts {
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        match extractors.extract(&path) {
+            Ok(document) => {
+                file_counts.counts = scanner.count_matches(&document.text);
+                file_counts.total = file_counts.counts.values().sum();
+            }
+            Err(e) => file_counts.error = Some(format!("{:#}", e)),
+        }
+        let failed = file_counts.error.is_some();
+        results.push(file_counts);
+
+        if failed && policy == FailurePolicy::AbortOnError {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Start a timer only when timings are being collected
+fn start_timer(timings: &Option<&mut DetectionTimings>) -> Option<Instant> {
+    timings.as_ref().map(|_| Instant::now())
+}
+
+fn elapsed_ms(started: Option<Instant>) -> f64 {
+    started
+        .map(|s| s.elapsed().as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
 /// Hybrid PII detector combining pattern-based, NER, and Presidio approaches
 pub struct HybridDetector {
-    pattern_detector: PIIDetector,
+    pattern_detector: Arc<PIIDetector>,
     ner_pipeline: Arc<NerPipeline>,
     presidio_manager: Arc<PresidioManager>,
     entity_mapper: EntityTypeMapper,
     detection_mode: Arc<RwLock<DetectionMode>>,
-    default_language: Arc<RwLock<String>>,
+    default_language: Arc<RwLock<Language>>,
+    presidio_boost: Arc<RwLock<f64>>,
+    min_entity_length: Arc<RwLock<usize>>,
+    ner_fallback: Arc<RwLock<NerFallbackPolicy>>,
+    entity_priority: Arc<RwLock<Vec<EntityType>>>,
 }
 
 impl HybridDetector {
     /// Create a new hybrid detector with all three layers
+    ///
+    /// Pass the app's `SharedPresidio` so detection shares the request queue with the
+    /// Presidio commands.
     pub fn new(
         ner_pipeline: Arc<NerPipeline>,
         presidio_manager: Arc<PresidioManager>,
     ) -> Self {
         Self {
-            pattern_detector: PIIDetector::new(),
+            pattern_detector: Arc::new(PIIDetector::new()),
             ner_pipeline,
             presidio_manager,
             entity_mapper: EntityTypeMapper::new(),
             detection_mode: Arc::new(RwLock::new(DetectionMode::default())),
-            default_language: Arc::new(RwLock::new("en".to_string())),
+            default_language: Arc::new(RwLock::new(Language::english())),
+            presidio_boost: Arc::new(RwLock::new(DEFAULT_PRESIDIO_BOOST)),
+            min_entity_length: Arc::new(RwLock::new(DEFAULT_MIN_ENTITY_LENGTH)),
+            ner_fallback: Arc::new(RwLock::new(NerFallbackPolicy::default())),
+            entity_priority: Arc::new(RwLock::new(DEFAULT_ENTITY_PRIORITY.to_vec())),
         }
     }
 
     /// Create a detector without Presidio (Layer 1 + 2 only)
     pub fn without_presidio(ner_pipeline: Arc<NerPipeline>) -> Self {
         Self {
-            pattern_detector: PIIDetector::new(),
+            pattern_detector: Arc::new(PIIDetector::new()),
             ner_pipeline,
             presidio_manager: Arc::new(PresidioManager::new()),
             entity_mapper: EntityTypeMapper::new(),
             detection_mode: Arc::new(RwLock::new(DetectionMode::Hybrid)),
-            default_language: Arc::new(RwLock::new("en".to_string())),
+            default_language: Arc::new(RwLock::new(Language::english())),
+            presidio_boost: Arc::new(RwLock::new(DEFAULT_PRESIDIO_BOOST)),
+            min_entity_length: Arc::new(RwLock::new(DEFAULT_MIN_ENTITY_LENGTH)),
+            ner_fallback: Arc::new(RwLock::new(NerFallbackPolicy::default())),
+            entity_priority: Arc::new(RwLock::new(DEFAULT_ENTITY_PRIORITY.to_vec())),
         }
     }
 
@@ -89,16 +330,87 @@ impl HybridDetector {
     }
 
     /// Set default language for detection
-    pub async fn set_language(&self, language: &str) {
+    pub async fn set_language(&self, language: Language) {
         let mut lang_lock = self.default_language.write().await;
-        *lang_lock = language.to_string();
+        *lang_lock = language;
     }
 
     /// Get default language
-    pub async fn get_language(&self) -> String {
+    pub async fn get_language(&self) -> Language {
         self.default_language.read().await.clone()
     }
 
+    /// Set the confidence bonus Presidio gets over overlapping local
+    /// identification, email and phone matches (0.0 disables it)
+    pub async fn set_presidio_boost(&self, boost: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&boost) {
+            anyhow::bail!("Presidio boost must be between 0.0 and 1.0");
+        }
+        *self.presidio_boost.write().await = boost;
+        Ok(())
+    }
+
+    /// Get the confidence bonus given to Presidio when merging layers
+    pub async fn get_presidio_boost(&self) -> f64 {
+        *self.presidio_boost.read().await
+    }
+
+    /// Set the minimum entity length in characters (0 keeps every match)
+    ///
+    /// Shorter entities from any layer are dropped before the layers are
+    /// merged; emails, IDs and checksum-validated matches are exempt.
+    pub async fn set_min_entity_length(&self, min_length: usize) {
+        *self.min_entity_length.write().await = min_length;
+    }
+
+    /// Get the minimum entity length in characters
+    pub async fn get_min_entity_length(&self) -> usize {
+        *self.min_entity_length.read().await
+    }
+
+    /// Set what happens when NER inference fails
+    pub async fn set_ner_fallback_policy(&self, policy: NerFallbackPolicy) {
+        *self.ner_fallback.write().await = policy;
+    }
+
+    /// Get what happens when NER inference fails
+    pub async fn get_ner_fallback_policy(&self) -> NerFallbackPolicy {
+        *self.ner_fallback.read().await
+    }
+
+    /// Set which entity types win overlaps of equal confidence, first wins
+    ///
+    /// Types left out keep their default order after the listed ones, so
+    /// `[Person]` only moves persons to the front.
+    pub async fn set_entity_priority(&self, priority: Vec<EntityType>) -> Result<()> {
+        let mut order = Vec::with_capacity(DEFAULT_ENTITY_PRIORITY.len());
+        for entity_type in priority {
+            if order.contains(&entity_type) {
+                anyhow::bail!("{:?} appears more than once in the entity priority", entity_type);
+            }
+            order.push(entity_type);
+        }
+        for entity_type in DEFAULT_ENTITY_PRIORITY {
+            if !order.contains(&entity_type) {
+                order.push(entity_type);
+            }
+        }
+
+        *self.entity_priority.write().await = order;
+        Ok(())
+    }
+
+    /// Get the entity type order used to break equal-confidence overlaps
+    pub async fn get_entity_priority(&self) -> Vec<EntityType> {
+        self.entity_priority.read().await.clone()
+    }
+
+    /// Drop entities below the minimum length
+    async fn drop_short(&self, mut entities: Vec<Entity>) -> Vec<Entity> {
+        drop_short_entities(&mut entities, self.get_min_entity_length().await);
+        entities
+    }
+
     /// Check if Presidio is available
     pub async fn is_presidio_available(&self) -> bool {
         matches!(
@@ -107,35 +419,292 @@ impl HybridDetector {
         )
     }
 
+    /// Quick scan: count pattern matches per entity type
+    ///
+    /// Only the pattern layer runs, without person-name heuristics, span
+    /// extraction or merging, so this is much cheaper than `detect` and
+    /// meant for triage rather than anonymization.
+    pub fn scan_counts(&self, text: &str) -> HashMap<EntityType, usize> {
+        self.pattern_detector.count_matches(text)
+    }
+
+    /// The pattern layer on its own, for quick scans that run off the
+    /// detector lock (see `scan_folder`)
+    pub fn pattern_scanner(&self) -> Arc<PIIDetector> {
+        self.pattern_detector.clone()
+    }
+
+    /// Detect entities in every supported document under a folder and write
+    /// them to `output` as JSON Lines, one `EntityExportRecord` per file
+    ///
+    /// Each record is flushed as soon as its file is done, so only one
+    /// document is held in memory however large the folder. Files that fail
+    /// to extract or detect still get a record, with the error set. The mode
+    /// and language configured when the export starts apply to every file;
+    /// `options` set the output file's encoding, line ending and BOM.
+    ///
+    /// Extraction and writing run on blocking threads, and `detector` is only
+    /// locked while a file is being detected, so other detection calls can
+    /// run between files.
+    pub async fn export_folder_entities(
+        detector: &Mutex<Option<Self>>,
+        folder: &Path,
+        output: &Path,
+        options: ExportOptions,
+    ) -> Result<EntityExportSummary> {
+        let (mode, language) = {
+            let guard = detector.lock().await;
+            let detector = guard.as_ref().context("NER system not initialized")?;
+            (detector.get_mode().await, detector.get_language().await)
+        };
+
+        let (paths, mut writer) = {
+            let folder = folder.to_path_buf();
+            let output = output.to_path_buf();
+            tokio::task::spawn_blocking(move || -> Result<_> {
+                if !folder.is_dir() {
+                    anyhow::bail!("Not a folder: {}", folder.display());
+                }
+                let extractors = ExtractorRegistry::with_defaults();
+                let paths: Vec<PathBuf> = supported_documents(&folder, &extractors).collect();
+                Ok((paths, options.create(&output)?))
+            })
+            .await
+            .context("Export task failed")??
+        };
+        let mut summary = EntityExportSummary {
+            output_path: output.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        for path in paths {
+            let file = path.to_string_lossy().to_string();
+            let extract = move || ExtractorRegistry::with_defaults().extract(&path);
+            let extracted =
+                tokio::task::spawn_blocking(extract).await.context("Extraction task failed")?;
+            let detected = match extracted {
+                Ok(document) => {
+                    let guard = detector.lock().await;
+                    let detector = guard.as_ref().context("NER system not initialized")?;
+                    let mut ner_status = NerLayerStatus::NotUsed;
+                    detector
+                        .detect_in_mode(&document.text, mode, &language, None, &mut ner_status)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            let mut record = EntityExportRecord {
+                file,
+                mode,
+                language: language.clone(),
+                entities: Vec::new(),
+                counts: HashMap::new(),
+                error: None,
+            };
+            match detected {
+                Ok(entities) => {
+                    for entity in &entities {
+                        *record.counts.entry(entity.entity_type).or_default() += 1;
+                    }
+                    summary.entities += entities.len();
+                    record.entities = entities;
+                }
+                Err(e) => {
+                    record.error = Some(format!("{:#}", e));
+                    summary.failed += 1;
+                }
+            }
+
+            let line = serde_json::to_string(&record)?;
+            writer = tokio::task::spawn_blocking(move || writer.write_line(&line).map(|()| writer))
+                .await
+                .context("Export task failed")??;
+            summary.files += 1;
+        }
+
+        Ok(summary)
+    }
+
     /// Detect PII entities in text using configured mode
     pub async fn detect(&self, text: &str) -> Result<Vec<Entity>> {
+        let language = self.get_language().await;
         let mode = self.get_mode().await;
+        let mut ner_status = NerLayerStatus::NotUsed;
+        self.detect_in_mode(text, mode, &language, None, &mut ner_status).await
+    }
+
+    /// Detect in the given mode, leaving the configured mode unchanged
+    pub async fn detect_with_mode(&self, text: &str, mode: DetectionMode) -> Result<Vec<Entity>> {
         let language = self.get_language().await;
+        let mut ner_status = NerLayerStatus::NotUsed;
+        self.detect_in_mode(text, mode, &language, None, &mut ner_status).await
+    }
 
-        match mode {
-            DetectionMode::PatternOnly => Ok(self.detect_with_patterns(text)),
-            DetectionMode::NerOnly => self.detect_with_ner(text).await,
-            DetectionMode::Hybrid => self.detect_hybrid(text).await,
-            DetectionMode::Full => self.detect_full(text, &language).await,
-            DetectionMode::PresidioOnly => self.detect_with_presidio(text, &language).await,
-        }
+    /// Detect in the given mode and report what happened to the NER layer
+    pub async fn detect_with_mode_report(
+        &self,
+        text: &str,
+        mode: DetectionMode,
+    ) -> Result<DetectionReport> {
+        let language = self.get_language().await;
+        let mut ner_status = NerLayerStatus::NotUsed;
+        let entities = self
+            .detect_in_mode(text, mode, &language, None, &mut ner_status)
+            .await?;
+        Ok(DetectionReport {
+            entities,
+            ner_status,
+            unsupported_language: None,
+        })
     }
 
     /// Detect with specific language override
-    pub async fn detect_with_language(&self, text: &str, language: &str) -> Result<Vec<Entity>> {
-        let mode = self.get_mode().await;
+    ///
+    /// When NER or Presidio would run but neither supports the language,
+    /// their output on it would be unreliable, so detection uses patterns
+    /// only and the report says so in `unsupported_language`.
+    pub async fn detect_with_language(
+        &self,
+        text: &str,
+        language: &Language,
+    ) -> Result<DetectionReport> {
+        let mut mode = self.get_mode().await;
+        let unsupported_language = self.unsupported_language(mode, language).await;
+        if let Some(notice) = &unsupported_language {
+            log::warn!("{}", notice.message);
+            mode = DetectionMode::PatternOnly;
+        }
+
+        let mut ner_status = NerLayerStatus::NotUsed;
+        let entities = self
+            .detect_in_mode(text, mode, language, None, &mut ner_status)
+            .await?;
+        Ok(DetectionReport {
+            entities,
+            ner_status,
+            unsupported_language,
+        })
+    }
+
+    /// Notice for `language` if `mode` runs a model-based layer but none of
+    /// the active ones supports it
+    ///
+    /// Without any active model-based layer detection is patterns-only
+    /// anyway, which `NerLayerStatus::NotLoaded` already reports.
+    async fn unsupported_language(
+        &self,
+        mode: DetectionMode,
+        language: &Language,
+    ) -> Option<UnsupportedLanguage> {
+        if mode == DetectionMode::PatternOnly {
+            return None;
+        }
+
+        let ner_ready = self.ner_pipeline.is_ready().await;
+        if ner_ready && self.ner_pipeline.covers_language(language).await {
+            return None;
+        }
+
+        let uses_presidio = matches!(mode, DetectionMode::Full | DetectionMode::PresidioOnly);
+        let presidio_ready = uses_presidio && self.is_presidio_available().await;
+        if presidio_ready {
+            match self.presidio_manager.get_supported_languages().await {
+                Ok(supported) => {
+                    let code = language.as_presidio();
+                    if supported.iter().any(|l| Some(l.as_str()) == code) {
+                        return None;
+                    }
+                }
+                Err(e) => log::warn!("Could not list Presidio languages: {:#}", e),
+            }
+        }
 
-        match mode {
-            DetectionMode::PatternOnly => Ok(self.detect_with_patterns(text)),
-            DetectionMode::NerOnly => self.detect_with_ner(text).await,
-            DetectionMode::Hybrid => self.detect_hybrid(text).await,
-            DetectionMode::Full => self.detect_full(text, language).await,
-            DetectionMode::PresidioOnly => self.detect_with_presidio(text, language).await,
+        if !ner_ready && !presidio_ready {
+            return None;
         }
+        Some(UnsupportedLanguage {
+            language: language.clone(),
+            message: format!(
+                "Language '{}' is not supported by the active detection layers; using patterns only",
+                language
+            ),
+        })
+    }
+
+    /// Detect PII entities and report whether the NER layer ran, was not
+    /// loaded, or failed (and why)
+    pub async fn detect_with_report(&self, text: &str) -> Result<DetectionReport> {
+        let language = self.get_language().await;
+        let mode = self.get_mode().await;
+        let mut ner_status = NerLayerStatus::NotUsed;
+        let entities = self
+            .detect_in_mode(text, mode, &language, None, &mut ner_status)
+            .await?;
+        Ok(DetectionReport {
+            entities,
+            ner_status,
+            unsupported_language: None,
+        })
+    }
+
+    /// Detect PII entities and report how long each layer took
+    ///
+    /// Timing is opt-in: `detect` does not read the clock at all.
+    pub async fn detect_with_timings(&self, text: &str) -> Result<(Vec<Entity>, DetectionTimings)> {
+        let language = self.get_language().await;
+        let started = Instant::now();
+
+        let mut timings = DetectionTimings::default();
+        let mode = self.get_mode().await;
+        let mut ner_status = NerLayerStatus::NotUsed;
+        let entities = self
+            .detect_in_mode(text, mode, &language, Some(&mut timings), &mut ner_status)
+            .await?;
+        timings.total_ms = elapsed_ms(Some(started));
+
+        Ok((entities, timings))
+    }
+
+    async fn detect_in_mode(
+        &self,
+        text: &str,
+        mode: DetectionMode,
+        language: &Language,
+        timings: Option<&mut DetectionTimings>,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Vec<Entity>> {
+        let entities = match mode {
+            DetectionMode::PatternOnly => {
+                self.drop_short(self.detect_with_patterns(text, timings)).await
+            }
+            DetectionMode::NerOnly => {
+                self.detect_with_ner(text, language, timings, ner_status).await?
+            }
+            DetectionMode::Hybrid => {
+                self.detect_hybrid(text, language, timings, ner_status).await?
+            }
+            DetectionMode::Full => self.detect_full(text, language, timings, ner_status).await?,
+            DetectionMode::PresidioOnly => {
+                self.detect_with_presidio(text, language, timings, ner_status)
+                    .await?
+            }
+        };
+
+        // Postcodes from the pattern layer next to cities found by NER or Presidio
+        let mut entities = merge_adjacent_locations(entities, text);
+        assign_utf16_offsets(&mut entities, text);
+        Ok(entities)
     }
 
     /// Layer 1: Detect using pattern-based approach only
-    fn detect_with_patterns(&self, text: &str) -> Vec<Entity> {
+    fn detect_with_patterns(
+        &self,
+        text: &str,
+        timings: Option<&mut DetectionTimings>,
+    ) -> Vec<Entity> {
+        let started = start_timer(&timings);
+
         let mut entities = self.pattern_detector.detect(text);
 
         // Add person names detected by pattern detector
@@ -143,64 +712,146 @@ impl HybridDetector {
         entities.extend(person_entities);
 
         entities.sort_by_key(|e| e.start);
+
+        if let Some(t) = timings {
+            t.pattern_ms += elapsed_ms(started);
+        }
         entities
     }
 
-    /// Layer 2: Detect using NER model only
-    async fn detect_with_ner(&self, text: &str) -> Result<Vec<Entity>> {
-        // Check if NER pipeline is ready
+    /// Run the NER model, applying the fallback policy if inference fails
+    ///
+    /// Returns `None` when there are no NER entities to use: either no model
+    /// is loaded or inference failed and the policy says to continue.
+    /// `ner_status` records which of the two it was.
+    async fn run_ner(
+        &self,
+        text: &str,
+        language: &Language,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Option<Vec<Entity>>> {
         if !self.ner_pipeline.is_ready().await {
-            // Fall back to pattern-based detection
-            return Ok(self.detect_with_patterns(text));
+            *ner_status = NerLayerStatus::NotLoaded;
+            return Ok(None);
         }
 
-        let ner_result = self.ner_pipeline.predict(text).await?;
-        let entities = self.convert_ner_to_entities(&ner_result);
+        match self.ner_pipeline.predict_in(text, language).await {
+            Ok(ner_result) => {
+                *ner_status = NerLayerStatus::Succeeded;
+                Ok(Some(self.convert_ner_to_entities(&ner_result)))
+            }
+            Err(e) => match self.get_ner_fallback_policy().await {
+                NerFallbackPolicy::FailRequest => Err(e.context("NER inference failed")),
+                NerFallbackPolicy::ContinueWithPatterns => {
+                    log::warn!("NER inference failed, continuing without it: {:#}", e);
+                    *ner_status = NerLayerStatus::Failed {
+                        reason: format!("{:#}", e),
+                    };
+                    Ok(None)
+                }
+            },
+        }
+    }
 
-        Ok(entities)
+    /// Layer 2: Detect using NER model only
+    async fn detect_with_ner(
+        &self,
+        text: &str,
+        language: &Language,
+        mut timings: Option<&mut DetectionTimings>,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Vec<Entity>> {
+        let started = start_timer(&timings);
+        let ner_entities = self.run_ner(text, language, ner_status).await?;
+        if let Some(t) = timings.as_deref_mut() {
+            t.ner_ms += elapsed_ms(started);
+        }
+
+        match ner_entities {
+            Some(entities) => Ok(self.drop_short(entities).await),
+            // Fall back to pattern-based detection
+            None => Ok(self.detect_with_patterns(text, timings)),
+        }
     }
 
     /// Layer 3: Detect using Presidio only
-    async fn detect_with_presidio(&self, text: &str, language: &str) -> Result<Vec<Entity>> {
+    async fn detect_with_presidio(
+        &self,
+        text: &str,
+        language: &Language,
+        timings: Option<&mut DetectionTimings>,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Vec<Entity>> {
         // Check if Presidio is available
         if !self.presidio_manager.is_enabled().await {
             // Fall back to hybrid detection
-            return self.detect_hybrid(text).await;
+            return self.detect_hybrid(text, language, timings, ner_status).await;
         }
 
+        let started = start_timer(&timings);
+
         let presidio_entities = self.presidio_manager.analyze(text, language).await?;
-        let entities = self.entity_mapper.convert_entities(&presidio_entities, text);
+        let entities = self
+            .drop_short(self.entity_mapper.convert_entities(&presidio_entities, text))
+            .await;
 
+        if let Some(t) = timings {
+            t.presidio_ms += elapsed_ms(started);
+        }
         Ok(entities)
     }
 
     /// Layer 1 + 2: Detect using patterns and NER, merge results
-    async fn detect_hybrid(&self, text: &str) -> Result<Vec<Entity>> {
+    async fn detect_hybrid(
+        &self,
+        text: &str,
+        language: &Language,
+        mut timings: Option<&mut DetectionTimings>,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Vec<Entity>> {
         // Get pattern-based detections
-        let pattern_entities = self.detect_with_patterns(text);
+        let pattern_entities = self.detect_with_patterns(text, timings.as_deref_mut());
 
         // Get NER detections (if available)
-        let ner_entities = if self.ner_pipeline.is_ready().await {
-            match self.ner_pipeline.predict(text).await {
-                Ok(ner_result) => self.convert_ner_to_entities(&ner_result),
-                Err(_) => Vec::new(),
-            }
-        } else {
-            Vec::new()
-        };
+        let started = start_timer(&timings);
+        let ner_entities = self
+            .run_ner(text, language, ner_status)
+            .await?
+            .unwrap_or_default();
+        if let Some(t) = timings.as_deref_mut() {
+            t.ner_ms += elapsed_ms(started);
+        }
+
+        // Drop trivial spans first so they can't displace longer matches
+        let pattern_entities = self.drop_short(pattern_entities).await;
+        let ner_entities = self.drop_short(ner_entities).await;
 
         // Merge and deduplicate entities
-        let merged = self.merge_entities(pattern_entities, ner_entities);
+        let started = start_timer(&timings);
+        let priority = self.get_entity_priority().await;
+        let merged = self.merge_entities(pattern_entities, ner_entities, &priority);
+        if let Some(t) = timings {
+            t.merge_ms += elapsed_ms(started);
+        }
 
         Ok(merged)
     }
 
     /// Full detection: Layer 1 + 2 + 3
-    async fn detect_full(&self, text: &str, language: &str) -> Result<Vec<Entity>> {
+    async fn detect_full(
+        &self,
+        text: &str,
+        language: &Language,
+        mut timings: Option<&mut DetectionTimings>,
+        ner_status: &mut NerLayerStatus,
+    ) -> Result<Vec<Entity>> {
         // Get Layer 1 + 2 results
-        let hybrid_entities = self.detect_hybrid(text).await?;
+        let hybrid_entities = self
+            .detect_hybrid(text, language, timings.as_deref_mut(), ner_status)
+            .await?;
 
         // Get Layer 3 (Presidio) results if available
+        let started = start_timer(&timings);
         let presidio_entities = if self.presidio_manager.is_enabled().await {
             match self.presidio_manager.analyze(text, language).await {
                 Ok(entities) => self.entity_mapper.convert_entities(&entities, text),
@@ -209,9 +860,21 @@ impl HybridDetector {
         } else {
             Vec::new()
         };
+        if let Some(t) = timings.as_deref_mut() {
+            t.presidio_ms += elapsed_ms(started);
+        }
+
+        let presidio_entities = self.drop_short(presidio_entities).await;
 
         // Merge all results, preferring higher confidence
-        let merged = self.merge_all_layers(hybrid_entities, presidio_entities);
+        let started = start_timer(&timings);
+        let presidio_boost = self.get_presidio_boost().await;
+        let priority = self.get_entity_priority().await;
+        let merged =
+            self.merge_all_layers(hybrid_entities, presidio_entities, presidio_boost, &priority);
+        if let Some(t) = timings {
+            t.merge_ms += elapsed_ms(started);
+        }
 
         Ok(merged)
     }
@@ -222,32 +885,33 @@ impl HybridDetector {
             .entities
             .iter()
             .filter_map(|ner_entity| {
-                // Map NER labels to PII entity types
-                let entity_type = match ner_entity.entity_type.as_str() {
-                    "PER" => EntityType::Person,
-                    "ORG" => EntityType::Organization,
-                    "LOC" => EntityType::Location,
-                    "MISC" => {
-                        // Try to classify MISC based on content
-                        return None;
-                    }
-                    _ => return None,
-                };
-
-                Some(Entity {
-                    entity_type,
-                    text: ner_entity.text.clone(),
-                    start: ner_entity.start,
-                    end: ner_entity.end,
-                    confidence: ner_entity.confidence as f64,
-                    replacement: None,
-                })
+                // Entities carry the PII type their label maps to in the model config
+                let entity_type = ner_entity.pii_type?;
+
+                Some(
+                    Entity::new(
+                        entity_type,
+                        ner_entity.text.clone(),
+                        ner_entity.start,
+                        ner_entity.end,
+                        ner_entity.confidence as f64,
+                    )
+                    .with_source(DetectionSource::Ner),
+                )
             })
             .collect()
     }
 
     /// Merge entities from Layer 1 + 2
-    fn merge_entities(&self, pattern_entities: Vec<Entity>, ner_entities: Vec<Entity>) -> Vec<Entity> {
+    ///
+    /// An overlapping NER entity replaces the pattern match when it is more
+    /// confident, or equally confident with a type earlier in `priority`.
+    fn merge_entities(
+        &self,
+        pattern_entities: Vec<Entity>,
+        ner_entities: Vec<Entity>,
+        priority: &[EntityType],
+    ) -> Vec<Entity> {
         let mut merged = pattern_entities.clone();
 
         for ner_entity in ner_entities {
@@ -255,7 +919,7 @@ impl HybridDetector {
                 merged.push(ner_entity);
             } else {
                 if let Some(idx) = self.find_overlapping_index(&pattern_entities, &ner_entity) {
-                    if ner_entity.confidence > pattern_entities[idx].confidence {
+                    if outranks(&ner_entity, ner_entity.confidence, &pattern_entities[idx], priority) {
                         if let Some(merge_idx) = self.find_overlapping_index(&merged, &pattern_entities[idx]) {
                             merged[merge_idx] = ner_entity.clone();
                         }
@@ -273,7 +937,16 @@ impl HybridDetector {
     }
 
     /// Merge all three layers of detection
-    fn merge_all_layers(&self, hybrid_entities: Vec<Entity>, presidio_entities: Vec<Entity>) -> Vec<Entity> {
+    ///
+    /// Each merged entity keeps the `source` of the layer whose span won.
+    /// Ties after the Presidio boost go to the type earlier in `priority`.
+    fn merge_all_layers(
+        &self,
+        hybrid_entities: Vec<Entity>,
+        presidio_entities: Vec<Entity>,
+        presidio_boost: f64,
+        priority: &[EntityType],
+    ) -> Vec<Entity> {
         let mut merged = hybrid_entities.clone();
 
         for presidio_entity in presidio_entities {
@@ -284,13 +957,18 @@ impl HybridDetector {
                 // Overlap exists - compare confidence and choose best
                 if let Some(idx) = self.find_overlapping_index(&hybrid_entities, &presidio_entity) {
                     // Presidio often has better confidence for certain entity types
-                    // Give slight preference to Presidio for identification types
-                    let presidio_boost = match presidio_entity.entity_type {
-                        EntityType::Identification | EntityType::Email | EntityType::Phone => 0.05,
+                    // Give slight preference to Presidio for identification types,
+                    // unless a checksum already confirmed the local match
+                    let boost = match presidio_entity.entity_type {
+                        _ if hybrid_entities[idx].validated => 0.0,
+                        EntityType::Identification | EntityType::Email | EntityType::Phone => {
+                            presidio_boost
+                        }
                         _ => 0.0,
                     };
 
-                    if presidio_entity.confidence + presidio_boost > hybrid_entities[idx].confidence {
+                    let score = presidio_entity.confidence + boost;
+                    if outranks(&presidio_entity, score, &hybrid_entities[idx], priority) {
                         if let Some(merge_idx) = self.find_overlapping_index(&merged, &hybrid_entities[idx]) {
                             merged[merge_idx] = presidio_entity.clone();
                         }
@@ -332,10 +1010,48 @@ impl HybridDetector {
             layer3_presidio: self.is_presidio_available().await,
         }
     }
+
+    /// Entity types each active layer can produce
+    ///
+    /// The pattern layer is always listed, the NER layer when a model is
+    /// loaded (from its labels) and Presidio when it is running (from its
+    /// supported entities that map to an internal type).
+    pub async fn layer_coverage(&self) -> Vec<LayerCoverage> {
+        let mut pattern_types = self.pattern_detector.entity_types();
+        // Person names come from the name heuristics rather than the pattern tables
+        pattern_types.push(EntityType::Person);
+        let mut coverage = vec![LayerCoverage {
+            layer: DetectionSource::Pattern,
+            entity_types: in_canonical_order(pattern_types),
+        }];
+
+        if self.ner_pipeline.is_ready().await {
+            coverage.push(LayerCoverage {
+                layer: DetectionSource::Ner,
+                entity_types: in_canonical_order(self.ner_pipeline.entity_types().await),
+            });
+        }
+
+        if self.is_presidio_available().await {
+            match self.presidio_manager.get_supported_entities().await {
+                Ok(supported) => coverage.push(LayerCoverage {
+                    layer: DetectionSource::Presidio,
+                    entity_types: in_canonical_order(
+                        supported
+                            .iter()
+                            .filter_map(|name| self.entity_mapper.to_internal(name)),
+                    ),
+                }),
+                Err(e) => log::warn!("Could not list Presidio entities: {:#}", e),
+            }
+        }
+
+        coverage
+    }
 }
 
 /// Status of detection layers
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayerStatus {
     /// Layer 1: Pattern-based detection (always available)
     pub layer1_pattern: bool,
@@ -357,6 +1073,30 @@ impl LayerStatus {
         }
     }
 
+    /// Recommended detection mode for a document in `language`
+    ///
+    /// Strong legal NER models exist only for some languages, so NER counts
+    /// only when one of `downloaded_ner_models` covers the language. For
+    /// other languages Presidio takes over entity detection when it runs.
+    pub fn recommended_mode_for(
+        &self,
+        language: &Language,
+        downloaded_ner_models: &[&NerModelInfo],
+    ) -> DetectionMode {
+        let ner_covers_language = self.layer2_ner
+            && downloaded_ner_models.iter().any(|model| {
+                Language::parse(&model.language)
+                    .is_ok_and(|model_language| model_language.covers(language))
+            });
+
+        match (ner_covers_language, self.layer3_presidio) {
+            (true, true) => DetectionMode::Full,
+            (true, false) => DetectionMode::Hybrid,
+            (false, true) => DetectionMode::PresidioOnly,
+            (false, false) => DetectionMode::PatternOnly,
+        }
+    }
+
     /// Count available layers
     pub fn available_layers(&self) -> u8 {
         let mut count = 0;
@@ -370,6 +1110,8 @@ impl LayerStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ner::types::NerEntity;
+    use crate::ner::{NerModelConfig, NerModelManager};
 
     #[test]
     fn test_layer_status_recommended_mode() {
@@ -395,6 +1137,502 @@ mod tests {
         assert_eq!(status.recommended_mode(), DetectionMode::PatternOnly);
     }
 
+    #[test]
+    fn test_recommended_mode_depends_on_language() {
+        let registry = crate::ner::NerModelRegistry::new();
+        let english = registry
+            .list_models()
+            .iter()
+            .find(|model| model.language == "en")
+            .unwrap();
+        let multilingual = registry.get_multilingual_model().unwrap();
+        let polish = Language::parse("pl").unwrap();
+        let all_layers = LayerStatus {
+            layer1_pattern: true,
+            layer2_ner: true,
+            layer3_presidio: true,
+        };
+
+        // Only an English model is downloaded: Polish text goes to Presidio
+        assert_eq!(
+            all_layers.recommended_mode_for(&Language::english(), &[english]),
+            DetectionMode::Full
+        );
+        assert_eq!(
+            all_layers.recommended_mode_for(&polish, &[english]),
+            DetectionMode::PresidioOnly
+        );
+        assert_eq!(all_layers.recommended_mode_for(&polish, &[]), DetectionMode::PresidioOnly);
+
+        // A multilingual model covers Polish too
+        assert_eq!(
+            all_layers.recommended_mode_for(&polish, &[english, multilingual]),
+            DetectionMode::Full
+        );
+
+        let without_presidio = LayerStatus {
+            layer3_presidio: false,
+            ..all_layers
+        };
+        assert_eq!(
+            without_presidio.recommended_mode_for(&Language::english(), &[english]),
+            DetectionMode::Hybrid
+        );
+        assert_eq!(
+            without_presidio.recommended_mode_for(&polish, &[english]),
+            DetectionMode::PatternOnly
+        );
+    }
+
+    fn detector() -> HybridDetector {
+        let pipeline = Arc::new(NerPipeline::new(Arc::new(NerModelManager::new())));
+        HybridDetector::without_presidio(pipeline)
+    }
+
+    fn sample_text() -> String {
+        "Contact John Doe at john.doe@example.com or 555-123-4567. ".repeat(200)
+    }
+
+    #[tokio::test]
+    async fn test_min_entity_length_applies_to_layers() {
+        let detector = detector();
+        detector.set_mode(DetectionMode::PatternOnly).await;
+        assert_eq!(detector.get_min_entity_length().await, DEFAULT_MIN_ENTITY_LENGTH);
+
+        let text = "Contact Jane Doe about passport NL123456.";
+        let entities = detector.detect(text).await.unwrap();
+        assert!(entities.iter().any(|e| e.entity_type == EntityType::Person));
+
+        detector.set_min_entity_length(9).await;
+        let entities = detector.detect(text).await.unwrap();
+        assert!(entities.iter().all(|e| e.entity_type != EntityType::Person));
+        assert!(entities.iter().any(|e| e.text == "NL123456"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_with_timings_populated() {
+        let detector = detector();
+        detector.set_mode(DetectionMode::Full).await;
+
+        let text = sample_text();
+        let (entities, timings) = detector.detect_with_timings(&text).await.unwrap();
+
+        assert!(!entities.is_empty());
+        assert!(timings.pattern_ms > 0.0);
+        assert!(timings.ner_ms >= 0.0);
+        assert!(timings.presidio_ms >= 0.0);
+        assert!(timings.merge_ms > 0.0);
+
+        let sum = timings.pattern_ms + timings.ner_ms + timings.presidio_ms + timings.merge_ms;
+        assert!(sum <= timings.total_ms);
+        assert!(
+            timings.total_ms - sum < 5.0,
+            "unaccounted time too large: {:?}",
+            timings
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timings_match_untimed_detection() {
+        let detector = detector();
+        let text = sample_text();
+
+        let plain = detector.detect(&text).await.unwrap();
+        let (timed, timings) = detector.detect_with_timings(&text).await.unwrap();
+
+        assert_eq!(plain.len(), timed.len());
+        assert_eq!(timings.presidio_ms, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_pattern_only_timings() {
+        let detector = detector();
+        detector.set_mode(DetectionMode::PatternOnly).await;
+
+        let (_, timings) = detector.detect_with_timings(&sample_text()).await.unwrap();
+
+        assert!(timings.pattern_ms > 0.0);
+        assert_eq!(timings.ner_ms, 0.0);
+        assert_eq!(timings.merge_ms, 0.0);
+    }
+
+    fn failing_detector() -> HybridDetector {
+        HybridDetector::without_presidio(Arc::new(NerPipeline::failing("ONNX session crashed")))
+    }
+
+    #[tokio::test]
+    async fn test_ner_failure_continues_with_patterns() {
+        let detector = failing_detector();
+        assert_eq!(
+            detector.get_ner_fallback_policy().await,
+            NerFallbackPolicy::ContinueWithPatterns
+        );
+        let text = "Mail jane@example.com about the contract.";
+
+        let report = detector.detect_with_report(text).await.unwrap();
+        assert_eq!(
+            report.ner_status,
+            NerLayerStatus::Failed {
+                reason: "ONNX session crashed".to_string()
+            }
+        );
+        assert!(report.entities.iter().any(|e| e.text == "jane@example.com"));
+
+        // NER-only mode falls back to patterns as well
+        detector.set_mode(DetectionMode::NerOnly).await;
+        let report = detector.detect_with_report(text).await.unwrap();
+        assert!(matches!(report.ner_status, NerLayerStatus::Failed { .. }));
+        assert!(report.entities.iter().any(|e| e.text == "jane@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_ner_failure_fails_request_when_configured() {
+        let detector = failing_detector();
+        detector
+            .set_ner_fallback_policy(NerFallbackPolicy::FailRequest)
+            .await;
+
+        let err = detector.detect("Mail jane@example.com.").await.unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("NER inference failed"), "{}", message);
+        assert!(message.contains("ONNX session crashed"), "{}", message);
+
+        // Pattern-only detection never touches NER
+        detector.set_mode(DetectionMode::PatternOnly).await;
+        let report = detector.detect_with_report("Mail jane@example.com.").await.unwrap();
+        assert_eq!(report.ner_status, NerLayerStatus::NotUsed);
+    }
+
+    #[tokio::test]
+    async fn test_ner_not_loaded_is_not_a_failure() {
+        let detector = detector();
+        detector
+            .set_ner_fallback_policy(NerFallbackPolicy::FailRequest)
+            .await;
+
+        let report = detector.detect_with_report("Mail jane@example.com.").await.unwrap();
+        assert_eq!(report.ner_status, NerLayerStatus::NotLoaded);
+        assert!(!report.entities.is_empty());
+
+        let json = serde_json::to_value(&report.ner_status).unwrap();
+        assert_eq!(json, serde_json::json!({ "status": "not_loaded" }));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_language_falls_back_to_patterns() {
+        let ner_entity = NerEntity {
+            text: "Jane".to_string(),
+            entity_type: "PER".to_string(),
+            confidence: 0.9,
+            start: 5,
+            end: 9,
+            tokens: Vec::new(),
+            pii_type: Some(EntityType::Person),
+        };
+        let config = NerModelConfig {
+            language: "en".to_string(),
+            ..NerModelConfig::default()
+        };
+        let pipeline = NerPipeline::returning(vec![ner_entity])
+            .with_model_manager(Arc::new(NerModelManager::with_config(config)));
+        let english_ner = HybridDetector::without_presidio(Arc::new(pipeline));
+        english_ner.set_mode(DetectionMode::Hybrid).await;
+        let text = "Mail Jane at jane@example.com.";
+
+        let swahili = Language::parse("sw").unwrap();
+        let report = english_ner.detect_with_language(text, &swahili).await.unwrap();
+        let notice = report.unsupported_language.unwrap();
+        assert_eq!(notice.language, swahili);
+        assert!(notice.message.contains("'sw'") && notice.message.contains("patterns only"));
+        assert_eq!(report.ner_status, NerLayerStatus::NotUsed);
+        assert!(report.entities.iter().any(|e| e.text == "jane@example.com"));
+        assert!(report.entities.iter().all(|e| e.source != Some(DetectionSource::Ner)));
+
+        // The English model covers English, so NER runs as usual
+        let report = english_ner.detect_with_language(text, &Language::english()).await.unwrap();
+        assert!(report.unsupported_language.is_none());
+        assert_eq!(report.ner_status, NerLayerStatus::Succeeded);
+        assert!(report.entities.iter().any(|e| e.source == Some(DetectionSource::Ner)));
+
+        // Nothing to warn about when only patterns would run anyway
+        let report = detector().detect_with_language(text, &swahili).await.unwrap();
+        assert!(report.unsupported_language.is_none());
+    }
+
+    fn presidio_entity(text: &str, span: &str, confidence: f64) -> Entity {
+        let start = text.find(span).unwrap();
+        Entity::new(EntityType::Identification, span.to_string(), start, start + span.len(), confidence)
+            .with_source(DetectionSource::Presidio)
+    }
+
+    #[test]
+    fn test_scan_counts_match_pattern_matches() {
+        let detector = detector();
+        let text = "Mail jane@example.com and bob@example.org, or call 555-123-4567.";
+
+        let counts = detector.scan_counts(text);
+
+        let detected = detector.detect_with_patterns(text, None);
+        for entity_type in [EntityType::Email, EntityType::Phone] {
+            let expected = detected.iter().filter(|e| e.entity_type == entity_type).count();
+            assert!(expected > 0);
+            assert_eq!(counts.get(&entity_type), Some(&expected), "{:?}", entity_type);
+        }
+        assert_eq!(counts.get(&EntityType::Email), Some(&2));
+
+        // Only Luhn-valid card numbers count
+        let counts = detector.scan_counts("Card 4111 1111 1111 1111, not 4111 1111 1111 1112");
+        assert_eq!(counts.get(&EntityType::Identification), Some(&1));
+
+        // Person-name heuristics are skipped
+        let counts = detector.scan_counts("Contract signed by John Doe.");
+        assert_eq!(counts.get(&EntityType::Person), None);
+
+        assert!(detector.scan_counts("Nothing sensitive here.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_counts_use_only_the_pattern_layer() {
+        let detector = detector();
+        let scanner = detector.pattern_scanner();
+        let text = sample_text();
+
+        // The mode picks detection layers, but a quick scan never leaves the patterns
+        for mode in [DetectionMode::PatternOnly, DetectionMode::Full] {
+            detector.set_mode(mode).await;
+            let counts = detector.scan_counts(&text);
+            assert_eq!(counts.get(&EntityType::Email), Some(&200));
+            assert_eq!(counts, scanner.count_matches(&text));
+        }
+    }
+
+    #[test]
+    fn test_scan_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "jane@example.com, bob@example.org").unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested").join("b.md"), "Call 555-123-4567").unwrap();
+        std::fs::write(dir.path().join("image.png"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.path().join("c.txt"), [0xffu8, 0xfe]).unwrap();
+
+        let scanner = detector().pattern_scanner();
+        let results = scan_folder(&scanner, dir.path(), FailurePolicy::BestEffort).unwrap();
+
+        let names: Vec<String> = results
+            .iter()
+            .map(|r| Path::new(&r.path).strip_prefix(dir.path()).unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.txt", "c.txt", "nested/b.md"]);
+        assert_eq!(results[0].counts.get(&EntityType::Email), Some(&2));
+        assert_eq!(results[0].total, 2);
+        assert!(results[1].error.is_some());
+        assert_eq!(results[2].counts.get(&EntityType::Phone), Some(&1));
+
+        assert!(scan_folder(&scanner, &dir.path().join("missing"), FailurePolicy::BestEffort)
+            .is_err());
+
+        // Aborting stops at the unreadable c.txt, keeping the results before it
+        let results = scan_folder(&scanner, dir.path(), FailurePolicy::AbortOnError).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].total, 2);
+        assert!(results[1].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_layer_coverage_lists_active_layers() {
+        let coverage = detector().layer_coverage().await;
+
+        // Without a model or Presidio only the pattern layer is active
+        assert_eq!(coverage.len(), 1);
+        assert_eq!(coverage[0].layer, DetectionSource::Pattern);
+        for entity_type in [EntityType::Email, EntityType::Phone, EntityType::Identification] {
+            assert!(coverage[0].entity_types.contains(&entity_type), "{:?}", entity_type);
+        }
+
+        // A model tagging only people and places reports just those
+        let config = NerModelConfig {
+            label_map: ["O", "B-PER", "I-PER", "B-LOC", "I-LOC", "B-MISC", "I-MISC"]
+                .map(String::from)
+                .to_vec(),
+            ..NerModelConfig::default()
+        };
+        let pipeline = NerPipeline::returning(Vec::new())
+            .with_model_manager(Arc::new(NerModelManager::with_config(config)));
+        let coverage = HybridDetector::without_presidio(Arc::new(pipeline))
+            .layer_coverage()
+            .await;
+
+        assert_eq!(coverage.len(), 2);
+        assert_eq!(coverage[1].layer, DetectionSource::Ner);
+        assert_eq!(coverage[1].entity_types, vec![EntityType::Person, EntityType::Location]);
+    }
+
+    #[tokio::test]
+    async fn test_export_folder_entities_writes_one_line_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "jane@example.com, bob@example.org").unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested").join("b.md"), "Call 555-123-4567").unwrap();
+        std::fs::write(dir.path().join("image.png"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.path().join("c.txt"), [0xffu8, 0xfe]).unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path().join("entities.jsonl");
+
+        let detector = detector();
+        detector.set_mode(DetectionMode::PatternOnly).await;
+        let language = detector.get_language().await;
+        let detector = Mutex::new(Some(detector));
+        let summary = HybridDetector::export_folder_entities(
+            &detector,
+            dir.path(),
+            &output,
+            ExportOptions::default(),
+        )
+        .await
+        .unwrap();
+        // The lock is released once the export is done
+        assert!(detector.try_lock().is_ok());
+
+        assert_eq!(summary.files, 3);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.entities, 3);
+
+        let records: Vec<EntityExportRecord> = std::fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<String> = records
+            .iter()
+            .map(|r| Path::new(&r.file).strip_prefix(dir.path()).unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.txt", "c.txt", "nested/b.md"]);
+
+        for record in &records {
+            assert_eq!(record.mode, DetectionMode::PatternOnly);
+            assert_eq!(record.language, language);
+            assert_eq!(record.counts.values().sum::<usize>(), record.entities.len());
+        }
+        assert_eq!(records[0].counts, HashMap::from([(EntityType::Email, 2)]));
+        assert!(records[1].error.is_some());
+        assert!(records[1].entities.is_empty());
+        assert_eq!(records[2].counts, HashMap::from([(EntityType::Phone, 1)]));
+
+        let missing = dir.path().join("missing");
+        assert!(HybridDetector::export_folder_entities(
+            &detector,
+            &missing,
+            &output,
+            ExportOptions::default()
+        )
+        .await
+        .is_err());
+
+        let err = HybridDetector::export_folder_entities(
+            &Mutex::new(None),
+            dir.path(),
+            &output,
+            ExportOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not initialized"), "{}", err);
+    }
+
+    #[test]
+    fn test_validated_local_match_beats_boosted_presidio() {
+        let detector = detector();
+        let text = "Card 4111 1111 1111 1111 on file";
+
+        let local = detector.detect_with_patterns(text, None);
+        let card = local.iter().find(|e| e.validated).unwrap().clone();
+
+        // 0.93 + 0.05 would beat the validated ~0.96 if the boost applied
+        let presidio = vec![presidio_entity(text, "4111 1111 1111", 0.93)];
+        let merged =
+            detector.merge_all_layers(local, presidio, DEFAULT_PRESIDIO_BOOST, &DEFAULT_ENTITY_PRIORITY);
+
+        let winner = merged.iter().find(|e| e.start == card.start).unwrap();
+        assert_eq!(winner.text, "4111 1111 1111 1111");
+        assert_eq!(winner.source, Some(DetectionSource::Pattern));
+        assert!(winner.validated);
+    }
+
+    #[test]
+    fn test_boost_applies_to_unvalidated_local_match() {
+        let detector = detector();
+        let text = "SSN 123-45-6789 on file";
+
+        let local = detector.detect_with_patterns(text, None);
+        let presidio = vec![presidio_entity(text, "123-45-6789", 0.82)];
+
+        // 0.82 + 0.05 beats the unvalidated SSN match (~0.85)
+        let merged = detector.merge_all_layers(
+            local.clone(),
+            presidio.clone(),
+            DEFAULT_PRESIDIO_BOOST,
+            &DEFAULT_ENTITY_PRIORITY,
+        );
+        let winner = merged.iter().find(|e| e.text == "123-45-6789").unwrap();
+        assert_eq!(winner.source, Some(DetectionSource::Presidio));
+
+        // Without the boost the local match stays
+        let merged = detector.merge_all_layers(local, presidio, 0.0, &DEFAULT_ENTITY_PRIORITY);
+        let winner = merged.iter().find(|e| e.text == "123-45-6789").unwrap();
+        assert_eq!(winner.source, Some(DetectionSource::Pattern));
+    }
+
+    #[tokio::test]
+    async fn test_entity_priority_breaks_equal_confidence_ties() {
+        let detector = detector();
+        // Three layers report the same span with the same confidence
+        let span = |entity_type, source| {
+            Entity::new(entity_type, "Jan Berg".to_string(), 4, 12, 0.8).with_source(source)
+        };
+        let local = vec![span(EntityType::Location, DetectionSource::Pattern)];
+        let ner = vec![span(EntityType::Person, DetectionSource::Ner)];
+        let presidio = vec![span(EntityType::Identification, DetectionSource::Presidio)];
+
+        // By default Identification > Person > Location
+        let priority = detector.get_entity_priority().await;
+        let merged = detector.merge_entities(local.clone(), ner.clone(), &priority);
+        assert_eq!(merged[0].entity_type, EntityType::Person);
+        let merged = detector.merge_all_layers(merged, presidio.clone(), 0.0, &priority);
+        assert_eq!(merged[0].entity_type, EntityType::Identification);
+
+        // Reversing the order keeps the first layer's type on a tie
+        detector
+            .set_entity_priority(vec![EntityType::Location, EntityType::Person])
+            .await
+            .unwrap();
+        let priority = detector.get_entity_priority().await;
+        assert_eq!(priority.len(), DEFAULT_ENTITY_PRIORITY.len());
+        let merged = detector.merge_entities(local, ner, &priority);
+        assert_eq!(merged[0].entity_type, EntityType::Location);
+        let merged = detector.merge_all_layers(merged, presidio, 0.0, &priority);
+        assert_eq!(merged[0].entity_type, EntityType::Location);
+
+        // Duplicates are rejected and leave the order unchanged
+        assert!(detector
+            .set_entity_priority(vec![EntityType::Person, EntityType::Person])
+            .await
+            .is_err());
+        assert_eq!(detector.get_entity_priority().await, priority);
+    }
+
+    #[tokio::test]
+    async fn test_presidio_boost_is_configurable() {
+        let detector = detector();
+        assert_eq!(detector.get_presidio_boost().await, DEFAULT_PRESIDIO_BOOST);
+
+        detector.set_presidio_boost(0.1).await.unwrap();
+        assert_eq!(detector.get_presidio_boost().await, 0.1);
+
+        assert!(detector.set_presidio_boost(-0.1).await.is_err());
+        assert!(detector.set_presidio_boost(f64::NAN).await.is_err());
+        assert_eq!(detector.get_presidio_boost().await, 0.1);
+    }
+
     #[test]
     fn test_available_layers_count() {
         let status = LayerStatus {
@@ -4,16 +4,21 @@
 use anyhow::{Context, Result};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
 
 use super::model_loader::NerModelManager;
-use super::tokenizer::{align_tokens_with_text, merge_subword_predictions, NerTokenizer};
-use super::types::{NerEntity, NerLabel, NerResult, TokenPrediction};
+use super::tokenizer::{align_tokens_with_text, merge_subword_predictions};
+use super::types::{NerEntity, NerLabel, NerModelConfig, NerResult, TokenPrediction};
+use crate::pii::{EntityType, Language};
 
 /// NER inference pipeline
 pub struct NerPipeline {
     model_manager: Arc<NerModelManager>,
-    tokenizer: Arc<RwLock<Option<NerTokenizer>>>,
+    /// Error every prediction fails with, to test how callers handle crashes
+    #[cfg(test)]
+    injected_failure: Option<String>,
+    /// Entities every prediction returns, to test callers without a model
+    #[cfg(test)]
+    injected_entities: Option<Vec<NerEntity>>,
 }
 
 impl NerPipeline {
@@ -21,58 +26,133 @@ impl NerPipeline {
     pub fn new(model_manager: Arc<NerModelManager>) -> Self {
         Self {
             model_manager,
-            tokenizer: Arc::new(RwLock::new(None)),
+            #[cfg(test)]
+            injected_failure: None,
+            #[cfg(test)]
+            injected_entities: None,
         }
     }
 
-    /// Check if pipeline is ready (model and tokenizer loaded)
+    /// Pipeline that reports ready but fails every prediction
+    #[cfg(test)]
+    pub(crate) fn failing(reason: &str) -> Self {
+        Self {
+            injected_failure: Some(reason.to_string()),
+            ..Self::new(Arc::new(NerModelManager::new()))
+        }
+    }
+
+    /// Pipeline that reports ready and finds the given entities in any text
+    #[cfg(test)]
+    pub(crate) fn returning(entities: Vec<NerEntity>) -> Self {
+        Self {
+            injected_entities: Some(entities),
+            ..Self::new(Arc::new(NerModelManager::new()))
+        }
+    }
+
+    /// Use another model manager, e.g. one holding a test config
+    #[cfg(test)]
+    pub(crate) fn with_model_manager(self, model_manager: Arc<NerModelManager>) -> Self {
+        Self {
+            model_manager,
+            ..self
+        }
+    }
+
+    /// Labels of the loaded model, e.g. "B-PER"; empty when none is loaded
+    pub async fn label_map(&self) -> Vec<String> {
+        self.model_manager
+            .get_config()
+            .await
+            .map(|config| config.label_map)
+            .unwrap_or_default()
+    }
+
+    /// PII types the loaded model's labels map to; empty when none is loaded
+    pub async fn entity_types(&self) -> Vec<EntityType> {
+        let Some(config) = self.model_manager.get_config().await else {
+            return Vec::new();
+        };
+        let mut types: Vec<EntityType> = Vec::new();
+        for entity_type in config.label_map.iter().filter_map(|label| config.entity_type_for(label)) {
+            if !types.contains(&entity_type) {
+                types.push(entity_type);
+            }
+        }
+        types
+    }
+
+    /// Whether a loaded model is for `language` or multilingual
+    pub async fn covers_language(&self, language: &Language) -> bool {
+        self.model_manager.loaded_models().await.iter().any(|model| {
+            Language::parse(&model.language).is_ok_and(|loaded| loaded.covers(language))
+        })
+    }
+
+    /// Check if pipeline is ready (a model is loaded, with its tokenizer)
     pub async fn is_ready(&self) -> bool {
-        let model_loaded = self.model_manager.is_loaded().await;
-        let tok_lock = self.tokenizer.read().await;
-        let tokenizer_loaded = tok_lock.is_some();
+        #[cfg(test)]
+        if self.injected_failure.is_some() || self.injected_entities.is_some() {
+            return true;
+        }
 
-        model_loaded && tokenizer_loaded
+        self.model_manager.is_loaded().await
     }
 
-    /// Run NER inference on text
+    /// Run NER inference on text with the current model
     pub async fn predict(&self, text: &str) -> Result<NerResult> {
+        self.run(text, None).await
+    }
+
+    /// Run NER inference with the loaded model best suited to `language`
+    pub async fn predict_in(&self, text: &str, language: &Language) -> Result<NerResult> {
+        self.run(text, Some(language)).await
+    }
+
+    async fn run(&self, text: &str, language: Option<&Language>) -> Result<NerResult> {
+        #[cfg(test)]
+        if let Some(reason) = &self.injected_failure {
+            anyhow::bail!("{}", reason);
+        }
+        #[cfg(test)]
+        if let Some(entities) = &self.injected_entities {
+            return Ok(NerResult {
+                text: text.to_string(),
+                entities: entities.clone(),
+                token_predictions: Vec::new(),
+                inference_time_ms: 0,
+            });
+        }
+
         let start_time = Instant::now();
 
         // Check if pipeline is ready
         if !self.is_ready().await {
-            anyhow::bail!("Pipeline not ready. Load model and tokenizer first.");
+            anyhow::bail!("Pipeline not ready. Load a model first.");
         }
 
-        // Get device
-        let _config = self
+        // The model the prediction runs on, with its labels and tokenizer
+        let selected = self
             .model_manager
-            .get_config()
+            .select(language)
             .await
-            .context("Model config not available")?;
+            .context("No model loaded")?;
+        let config = selected.config;
 
         let device = candle_core::Device::Cpu;
 
-        // Tokenize input
-        let tok_lock = self.tokenizer.read().await;
-        let tokenizer = tok_lock
-            .as_ref()
-            .context("Tokenizer not loaded")?;
-
-        let encoding = tokenizer.encode(text, &device)?;
+        // Tokenize input with the chosen model's own vocabulary
+        let encoding = selected.tokenizer.encode(text, &device)?;
         let tokens = encoding.tokens.clone();
         let offsets = encoding.offsets.clone();
 
-        drop(tok_lock); // Release tokenizer lock
-
         // Run model inference
-        let logits = self
-            .model_manager
-            .predict(
-                encoding.input_ids,
-                Some(encoding.attention_mask),
-                Some(encoding.token_type_ids),
-            )
-            .await?;
+        let logits = selected.model.forward(
+            &encoding.input_ids,
+            Some(&encoding.attention_mask),
+            Some(&encoding.token_type_ids),
+        )?;
 
         // Get predictions (argmax over labels dimension)
         let predictions = logits.argmax(2)?; // Shape: [batch_size, sequence_length]
@@ -119,22 +199,7 @@ impl NerPipeline {
                 .collect(),
         );
 
-        // Create token predictions
-        let mut token_predictions = Vec::new();
-        for (token_text, label_id, confidence, start, end) in &merged {
-            if let Some(label) = NerLabel::from_id(*label_id) {
-                token_predictions.push(TokenPrediction {
-                    token: token_text.clone(),
-                    label,
-                    confidence: *confidence,
-                    start: *start,
-                    end: *end,
-                });
-            }
-        }
-
-        // Extract entities (combine B- and I- tags)
-        let entities = self.extract_entities(&token_predictions);
+        let (token_predictions, entities) = self.label_tokens(text, &config, &merged);
 
         let inference_time = start_time.elapsed().as_millis() as u64;
 
@@ -146,69 +211,126 @@ impl NerPipeline {
         })
     }
 
+    /// Turn merged `(token, label ID, confidence, start, end)` predictions into
+    /// labelled tokens and entities, using the model's label scheme
+    ///
+    /// Entities whose label maps to no PII type are dropped; the labels are
+    /// logged when the model is loaded.
+    fn label_tokens(
+        &self,
+        text: &str,
+        config: &NerModelConfig,
+        merged: &[(String, usize, f32, usize, usize)],
+    ) -> (Vec<TokenPrediction>, Vec<NerEntity>) {
+        let token_predictions: Vec<TokenPrediction> = merged
+            .iter()
+            .filter_map(|(token_text, label_id, confidence, start, end)| {
+                Some(TokenPrediction {
+                    token: token_text.clone(),
+                    label: config.label(*label_id)?,
+                    confidence: *confidence,
+                    start: *start,
+                    end: *end,
+                })
+            })
+            .collect();
+
+        // Extract entities (combine B- and I- tags)
+        let entities = self
+            .extract_entities(text, &token_predictions)
+            .into_iter()
+            .filter_map(|mut entity| {
+                entity.pii_type = Some(config.entity_type_for(&entity.entity_type)?);
+                Some(entity)
+            })
+            .collect();
+
+        (token_predictions, entities)
+    }
+
     /// Extract named entities from token predictions using BIO tagging
-    fn extract_entities(&self, predictions: &[TokenPrediction]) -> Vec<NerEntity> {
+    ///
+    /// Entity text is sliced from `text` by offsets rather than joined from
+    /// tokens, so sub-word continuations and CJK text keep the source spacing.
+    fn extract_entities(&self, text: &str, predictions: &[TokenPrediction]) -> Vec<NerEntity> {
         let mut entities = Vec::new();
         let mut current_entity: Option<NerEntity> = None;
+        let finish = |mut entity: NerEntity| {
+            entity.text = source_text(text, &entity);
+            entity
+        };
 
         for pred in predictions {
-            match pred.label {
+            match &pred.label {
                 NerLabel::O => {
                     // Outside any entity - finalize current entity if exists
                     if let Some(entity) = current_entity.take() {
-                        entities.push(entity);
+                        entities.push(finish(entity));
                     }
                 }
-                label if label.is_begin() => {
+                NerLabel::Begin(entity_type) => {
                     // Beginning of new entity - finalize current and start new
                     if let Some(entity) = current_entity.take() {
-                        entities.push(entity);
+                        entities.push(finish(entity));
                     }
 
-                    if let Some(entity_type) = label.entity_type() {
-                        current_entity = Some(NerEntity {
-                            text: pred.token.clone(),
-                            entity_type: entity_type.to_string(),
-                            confidence: pred.confidence,
-                            start: pred.start,
-                            end: pred.end,
-                            tokens: vec![pred.clone()],
-                        });
-                    }
+                    current_entity = Some(NerEntity {
+                        text: pred.token.clone(),
+                        entity_type: entity_type.clone(),
+                        confidence: pred.confidence,
+                        start: pred.start,
+                        end: pred.end,
+                        tokens: vec![pred.clone()],
+                        pii_type: None,
+                    });
                 }
-                label if label.is_inside() => {
+                NerLabel::Inside(entity_type) => {
                     // Inside entity - extend current entity
                     if let Some(ref mut entity) = current_entity {
                         // Check if label matches current entity type
-                        if let Some(entity_type) = label.entity_type() {
-                            if entity.entity_type == entity_type {
-                                entity.text.push(' ');
-                                entity.text.push_str(&pred.token);
-                                entity.end = pred.end;
-                                entity.tokens.push(pred.clone());
-                                // Update average confidence
-                                let total_conf: f32 = entity.tokens.iter().map(|t| t.confidence).sum();
-                                entity.confidence = total_conf / entity.tokens.len() as f32;
-                            }
+                        if &entity.entity_type == entity_type {
+                            entity.end = pred.end;
+                            entity.tokens.push(pred.clone());
+                            // Update average confidence
+                            let total_conf: f32 = entity.tokens.iter().map(|t| t.confidence).sum();
+                            entity.confidence = total_conf / entity.tokens.len() as f32;
                         }
                     }
                 }
-                _ => {}
             }
         }
 
         // Don't forget last entity
         if let Some(entity) = current_entity {
-            entities.push(entity);
+            entities.push(finish(entity));
         }
 
         entities
     }
 }
 
+/// Source text covered by an entity
+///
+/// Falls back to space-joined tokens if the offsets don't fall on character
+/// boundaries of `text`.
+fn source_text(text: &str, entity: &NerEntity) -> String {
+    match text.get(entity.start..entity.end) {
+        Some(slice) => slice.to_string(),
+        None => entity
+            .tokens
+            .iter()
+            .map(|token| token.token.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ner::model_loader::TokenClassifier;
+    use crate::ner::tokenizer::NerTokenizer;
+    use std::path::PathBuf;
 
     #[test]
     fn test_entity_extraction() {
@@ -217,14 +339,14 @@ mod tests {
         let predictions = vec![
             TokenPrediction {
                 token: "John".to_string(),
-                label: NerLabel::BeginPerson,
+                label: NerLabel::parse("B-PER"),
                 confidence: 0.9,
                 start: 0,
                 end: 4,
             },
             TokenPrediction {
                 token: "Doe".to_string(),
-                label: NerLabel::InsidePerson,
+                label: NerLabel::parse("I-PER"),
                 confidence: 0.85,
                 start: 5,
                 end: 8,
@@ -245,14 +367,14 @@ mod tests {
             },
             TokenPrediction {
                 token: "Google".to_string(),
-                label: NerLabel::BeginOrganization,
+                label: NerLabel::parse("B-ORG"),
                 confidence: 0.92,
                 start: 18,
                 end: 24,
             },
         ];
 
-        let entities = pipeline.extract_entities(&predictions);
+        let entities = pipeline.extract_entities("John Doe works at Google", &predictions);
 
         assert_eq!(entities.len(), 2);
         assert_eq!(entities[0].text, "John Doe");
@@ -268,32 +390,236 @@ mod tests {
         let predictions = vec![
             TokenPrediction {
                 token: "New".to_string(),
-                label: NerLabel::BeginLocation,
+                label: NerLabel::parse("B-LOC"),
                 confidence: 0.9,
                 start: 0,
                 end: 3,
             },
             TokenPrediction {
                 token: "York".to_string(),
-                label: NerLabel::InsideLocation,
+                label: NerLabel::parse("I-LOC"),
                 confidence: 0.88,
                 start: 4,
                 end: 8,
             },
             TokenPrediction {
                 token: "City".to_string(),
-                label: NerLabel::InsideLocation,
+                label: NerLabel::parse("I-LOC"),
                 confidence: 0.85,
                 start: 9,
                 end: 13,
             },
         ];
 
-        let entities = pipeline.extract_entities(&predictions);
+        let entities = pipeline.extract_entities("New York City", &predictions);
 
         assert_eq!(entities.len(), 1);
         assert_eq!(entities[0].text, "New York City");
         assert_eq!(entities[0].entity_type, "LOC");
         assert_eq!(entities[0].tokens.len(), 3);
     }
+
+    fn prediction(token: &str, label: NerLabel, start: usize, end: usize) -> TokenPrediction {
+        TokenPrediction {
+            token: token.to_string(),
+            label,
+            confidence: 0.9,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_entity_text_matches_source_for_cjk() {
+        let pipeline = NerPipeline::new(Arc::new(NerModelManager::new()));
+        let text = "张伟在北京工作";
+
+        // One token per character, three bytes each
+        let predictions = vec![
+            prediction("张", NerLabel::parse("B-PER"), 0, 3),
+            prediction("伟", NerLabel::parse("I-PER"), 3, 6),
+            prediction("在", NerLabel::O, 6, 9),
+            prediction("北", NerLabel::parse("B-LOC"), 9, 12),
+            prediction("京", NerLabel::parse("I-LOC"), 12, 15),
+            prediction("工作", NerLabel::O, 15, 21),
+        ];
+
+        let entities = pipeline.extract_entities(text, &predictions);
+
+        assert_eq!(entities.len(), 2);
+        for entity in &entities {
+            assert_eq!(entity.text, &text[entity.start..entity.end]);
+        }
+        assert_eq!(entities[0].text, "张伟");
+        assert_eq!(entities[1].text, "北京");
+    }
+
+    #[test]
+    fn test_entity_text_matches_source_for_subwords() {
+        let pipeline = NerPipeline::new(Arc::new(NerModelManager::new()));
+        let text = "Mr. Schwarzenegger met Anna-Lena Berg";
+
+        // Sentencepiece-style continuations arrive as inside tokens, not `##` pieces
+        let predictions = vec![
+            prediction("Mr", NerLabel::O, 0, 2),
+            prediction(".", NerLabel::O, 2, 3),
+            prediction("Schwarz", NerLabel::parse("B-PER"), 4, 11),
+            prediction("enegger", NerLabel::parse("I-PER"), 11, 18),
+            prediction("met", NerLabel::O, 19, 22),
+            prediction("Anna", NerLabel::parse("B-PER"), 23, 27),
+            prediction("-", NerLabel::parse("I-PER"), 27, 28),
+            prediction("Lena", NerLabel::parse("I-PER"), 28, 32),
+            prediction("Berg", NerLabel::parse("I-PER"), 33, 37),
+        ];
+
+        let entities = pipeline.extract_entities(text, &predictions);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].text, "Schwarzenegger");
+        assert_eq!(entities[1].text, "Anna-Lena Berg");
+        for entity in &entities {
+            assert_eq!(entity.text, &text[entity.start..entity.end]);
+        }
+    }
+
+    #[test]
+    fn test_custom_label_scheme_from_model_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "model_type": "bert",
+                "hidden_size": 256,
+                "id2label": {
+                    "0": "O", "1": "B-RR", "2": "I-RR", "3": "B-GS", "4": "I-GS",
+                    "5": "B-DATE", "6": "I-DATE", "7": "B-LIT", "8": "I-LIT",
+                    "9": "B-JUDGE", "10": "I-JUDGE"
+                },
+                "label2id": {"O": 0}
+            }"#,
+        )
+        .unwrap();
+
+        let mut config = NerModelConfig {
+            label_entity_types: [
+                ("RR".to_string(), EntityType::Person),
+                ("GS".to_string(), EntityType::Law),
+            ]
+            .into(),
+            ..NerModelConfig::default()
+        };
+        config.load_model_config(&path).unwrap();
+        assert_eq!(config.num_labels, 11);
+        assert_eq!(config.hidden_size, 256);
+        assert_eq!(config.label(10), Some(NerLabel::parse("I-JUDGE")));
+        assert_eq!(config.unmapped_labels(), vec!["LIT".to_string()]);
+
+        let text = "Richterin Weber wendet § 823 BGB an; siehe Palandt seit 1. Mai 2020.";
+        let span = |needle: &str| {
+            let start = text.find(needle).unwrap();
+            (start, start + needle.len())
+        };
+        let token = |needle: &str, label: usize| {
+            let (start, end) = span(needle);
+            (needle.to_string(), label, 0.9, start, end)
+        };
+        let merged = vec![
+            token("Richterin", 0),
+            token("Weber", 1),
+            token("wendet", 0),
+            token("§", 3),
+            token("823", 4),
+            token("BGB", 4),
+            token("siehe", 0),
+            token("Palandt", 7),
+            token("1.", 5),
+            token("Mai", 6),
+            token("2020", 6),
+        ];
+
+        let pipeline = NerPipeline::new(Arc::new(NerModelManager::new()));
+        let (tokens, entities) = pipeline.label_tokens(text, &config, &merged);
+
+        assert_eq!(tokens.len(), merged.len());
+        let found: Vec<(&str, &str, Option<EntityType>)> = entities
+            .iter()
+            .map(|e| (e.text.as_str(), e.entity_type.as_str(), e.pii_type))
+            .collect();
+        // The literature reference has no entity type and is dropped
+        assert_eq!(
+            found,
+            vec![
+                ("Weber", "RR", Some(EntityType::Person)),
+                ("§ 823 BGB", "GS", Some(EntityType::Law)),
+                ("1. Mai 2020", "DATE", Some(EntityType::Date)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entity_text_falls_back_to_tokens_for_bad_offsets() {
+        let pipeline = NerPipeline::new(Arc::new(NerModelManager::new()));
+
+        // Offsets past the end of the text
+        let predictions = vec![
+            prediction("John", NerLabel::parse("B-PER"), 10, 14),
+            prediction("Doe", NerLabel::parse("I-PER"), 15, 18),
+        ];
+
+        let entities = pipeline.extract_entities("short", &predictions);
+        assert_eq!(entities[0].text, "John Doe");
+    }
+
+    /// Classifier that labels each token with its own input ID
+    struct EchoClassifier;
+
+    impl TokenClassifier for EchoClassifier {
+        fn forward(
+            &self,
+            input_ids: &candle_core::Tensor,
+            _attention_mask: Option<&candle_core::Tensor>,
+            _token_type_ids: Option<&candle_core::Tensor>,
+        ) -> Result<candle_core::Tensor> {
+            let num_labels = NerModelConfig::default().num_labels;
+            let ids = input_ids.to_vec2::<u32>()?;
+            let logits: Vec<f32> = ids[0]
+                .iter()
+                .flat_map(|&id| {
+                    (0..num_labels).map(move |label| if label == id as usize { 5.0 } else { 0.0 })
+                })
+                .collect();
+            Ok(candle_core::Tensor::from_vec(
+                logits,
+                (1, ids[0].len(), num_labels),
+                input_ids.device(),
+            )?)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_model_encodes_with_its_own_tokenizer() {
+        let manager = Arc::new(NerModelManager::new());
+        // The same word has ID 1 (B-PER) in one vocabulary and 3 (B-ORG) in the other
+        for (model_id, language, vocab) in [
+            ("bert-en", "en", vec!["Acme"]),
+            ("bert-nl", "nl", vec!["x", "y", "Acme"]),
+        ] {
+            let config = NerModelConfig {
+                model_id: model_id.to_string(),
+                language: language.to_string(),
+                ..Default::default()
+            };
+            let tokenizer = Arc::new(NerTokenizer::word_level(&vocab));
+            manager
+                .insert_model(PathBuf::new(), config, Arc::new(EchoClassifier), tokenizer, 0)
+                .await;
+        }
+        let pipeline = NerPipeline::new(manager);
+
+        let english = pipeline.predict_in("Acme", &Language::english()).await.unwrap();
+        assert_eq!(english.entities[0].entity_type, "PER");
+        let dutch = pipeline.predict_in("Acme", &Language::parse("nl").unwrap()).await.unwrap();
+        assert_eq!(dutch.entities[0].entity_type, "ORG");
+    }
 }
@@ -15,7 +15,13 @@ pub mod downloader;
 pub use types::*;
 pub use model_loader::NerModelManager;
 #[allow(unused_imports)]
+pub use model_loader::{LoadedNerModel, TokenClassifier};
+#[allow(unused_imports)]
 pub use inference::NerPipeline;
 pub use hybrid_detector::{HybridDetector, DetectionMode};
+#[allow(unused_imports)]
+pub use hybrid_detector::DetectionTimings;
+pub use hybrid_detector::{DetectionReport, NerFallbackPolicy, NerLayerStatus};
+pub use hybrid_detector::{EntityExportSummary, FileScanCounts};
 pub use registry::NerModelRegistry;
 pub use downloader::NerModelDownloader;
@@ -9,7 +9,9 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use super::tokenizer::NerTokenizer;
 use super::types::NerModelConfig;
+use crate::pii::Language;
 
 /// Token classification head for NER
 pub struct TokenClassificationHead {
@@ -107,68 +109,218 @@ impl NerModel {
     }
 }
 
+/// Default memory budget for loaded NER models, room for a few base-size models
+pub const DEFAULT_MEMORY_BUDGET_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// A model that assigns a label to every token
+///
+/// Implemented by `NerModel`; lets the manager hold models of any kind.
+pub trait TokenClassifier: Send + Sync {
+    /// Logits of shape [batch_size, sequence_length, num_labels]
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor>;
+}
+
+impl TokenClassifier for NerModel {
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        NerModel::forward(self, input_ids, attention_mask, token_type_ids)
+    }
+}
+
+/// A model held in memory by the manager
+struct LoadedModel {
+    config: NerModelConfig,
+    path: PathBuf,
+    model: Arc<dyn TokenClassifier>,
+    /// The model's own tokenizer; token IDs only mean something to the
+    /// model whose vocabulary produced them
+    tokenizer: Arc<NerTokenizer>,
+    size_bytes: u64,
+}
+
+/// A loaded model chosen for a prediction, with what is needed to run it
+pub(crate) struct SelectedModel {
+    pub config: NerModelConfig,
+    pub model: Arc<dyn TokenClassifier>,
+    pub tokenizer: Arc<NerTokenizer>,
+}
+
+/// Summary of a loaded model, for status displays
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LoadedNerModel {
+    pub model_id: String,
+    pub language: String,
+    pub size_bytes: u64,
+}
+
 /// Thread-safe NER model manager
+///
+/// Keeps several models loaded at once, e.g. one per language, so switching
+/// between languages doesn't reload weights. When their combined size goes
+/// over the memory budget the least recently used models are unloaded. The
+/// most recently used model is the "current" one.
 pub struct NerModelManager {
-    model: Arc<RwLock<Option<NerModel>>>,
-    model_path: Arc<RwLock<Option<PathBuf>>>,
-    config: Arc<RwLock<Option<NerModelConfig>>>,
+    /// Loaded models, least recently used first
+    models: Arc<RwLock<Vec<LoadedModel>>>,
+    memory_budget: Arc<RwLock<u64>>,
 }
 
 impl NerModelManager {
     pub fn new() -> Self {
         Self {
-            model: Arc::new(RwLock::new(None)),
-            model_path: Arc::new(RwLock::new(None)),
-            config: Arc::new(RwLock::new(None)),
+            models: Arc::new(RwLock::new(Vec::new())),
+            memory_budget: Arc::new(RwLock::new(DEFAULT_MEMORY_BUDGET_BYTES)),
         }
     }
 
-    /// Load a model from disk
+    /// Manager holding a model with `config` that labels every token "O"
+    #[cfg(test)]
+    pub(crate) fn with_config(config: NerModelConfig) -> Self {
+        let manager = Self::new();
+        let model = Arc::new(FixedLabelClassifier {
+            label: 0,
+            num_labels: config.num_labels,
+        });
+        manager.models.try_write().unwrap().push(LoadedModel {
+            config,
+            path: PathBuf::new(),
+            model,
+            tokenizer: Arc::new(NerTokenizer::word_level(&[])),
+            size_bytes: 0,
+        });
+        manager
+    }
+
+    /// Load a model from disk, keeping models loaded before it
+    ///
+    /// Loading a model that is already loaded replaces it.
     pub async fn load_model(&self, model_path: PathBuf, config: NerModelConfig) -> Result<()> {
+        let unmapped = config.unmapped_labels();
+        if !unmapped.is_empty() {
+            log::info!(
+                "NER model {} labels {:?} map to no entity type; entities with them are dropped",
+                config.model_id,
+                unmapped
+            );
+        }
         let model = NerModel::load(&model_path, config.clone())
             .context("Failed to load NER model")?;
+        let tokenizer = NerTokenizer::from_file(
+            &model_path.join("tokenizer.json"),
+            config.max_sequence_length,
+        )
+        .context("Failed to load NER tokenizer")?;
+        let size_bytes = std::fs::metadata(model_path.join("model.safetensors"))
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+
+        let tokenizer = Arc::new(tokenizer);
+        self.insert_model(model_path, config, Arc::new(model), tokenizer, size_bytes)
+            .await;
+        Ok(())
+    }
 
-        let mut model_lock = self.model.write().await;
-        *model_lock = Some(model);
+    /// Add a loaded model as the current one, evicting others over the budget
+    pub(crate) async fn insert_model(
+        &self,
+        model_path: PathBuf,
+        config: NerModelConfig,
+        model: Arc<dyn TokenClassifier>,
+        tokenizer: Arc<NerTokenizer>,
+        size_bytes: u64,
+    ) {
+        let budget = *self.memory_budget.read().await;
+        let mut models = self.models.write().await;
+        models.retain(|loaded| loaded.config.model_id != config.model_id);
+        models.push(LoadedModel {
+            config,
+            path: model_path,
+            model,
+            tokenizer,
+            size_bytes,
+        });
+        evict_over_budget(&mut models, budget);
+    }
 
-        let mut path_lock = self.model_path.write().await;
-        *path_lock = Some(model_path);
+    /// Set the memory budget, unloading least recently used models over it
+    ///
+    /// The current model stays loaded even if it alone is over the budget.
+    pub async fn set_memory_budget(&self, bytes: u64) {
+        *self.memory_budget.write().await = bytes;
+        evict_over_budget(&mut *self.models.write().await, bytes);
+    }
 
-        let mut config_lock = self.config.write().await;
-        *config_lock = Some(config);
+    /// Get the memory budget for loaded models in bytes
+    pub async fn get_memory_budget(&self) -> u64 {
+        *self.memory_budget.read().await
+    }
 
-        Ok(())
+    /// Loaded models, most recently used first
+    pub async fn loaded_models(&self) -> Vec<LoadedNerModel> {
+        let models = self.models.read().await;
+        models
+            .iter()
+            .rev()
+            .map(|loaded| LoadedNerModel {
+                model_id: loaded.config.model_id.clone(),
+                language: loaded.config.language.clone(),
+                size_bytes: loaded.size_bytes,
+            })
+            .collect()
     }
 
     /// Check if a model is loaded
     pub async fn is_loaded(&self) -> bool {
-        let model_lock = self.model.read().await;
-        model_lock.is_some()
+        !self.models.read().await.is_empty()
     }
 
     /// Get current model path
     pub async fn get_model_path(&self) -> Option<PathBuf> {
-        let path_lock = self.model_path.read().await;
-        path_lock.clone()
+        let models = self.models.read().await;
+        models.last().map(|loaded| loaded.path.clone())
     }
 
     /// Get current model config
     pub async fn get_config(&self) -> Option<NerModelConfig> {
-        let config_lock = self.config.read().await;
-        config_lock.clone()
+        let models = self.models.read().await;
+        models.last().map(|loaded| loaded.config.clone())
     }
 
-    /// Run inference with loaded model
+    /// Get the language of the currently loaded model
+    pub async fn get_language(&self) -> Option<String> {
+        let models = self.models.read().await;
+        models.last().map(|loaded| loaded.config.language.clone())
+    }
+
+    /// Config of the model `predict_for` would use for `language`
+    pub async fn get_config_for(&self, language: &Language) -> Option<NerModelConfig> {
+        let models = self.models.read().await;
+        pick_model(&models, language).map(|index| models[index].config.clone())
+    }
+
+    /// Run inference with the current model
     pub async fn predict(
         &self,
         input_ids: Tensor,
         attention_mask: Option<Tensor>,
         token_type_ids: Option<Tensor>,
     ) -> Result<Tensor> {
-        let model_lock = self.model.read().await;
-        let model = model_lock
-            .as_ref()
-            .context("No model loaded")?;
+        let model = {
+            let models = self.models.read().await;
+            models
+                .last()
+                .map(|loaded| loaded.model.clone())
+                .context("No model loaded")?
+        };
 
         model.forward(
             &input_ids,
@@ -177,16 +329,117 @@ impl NerModelManager {
         )
     }
 
-    /// Unload the current model
+    /// Run inference with the model best suited to `language`
+    ///
+    /// Prefers a model for that language, then a multilingual one, then the
+    /// current model. The chosen model becomes the most recently used.
+    pub async fn predict_for(
+        &self,
+        language: &Language,
+        input_ids: Tensor,
+        attention_mask: Option<Tensor>,
+        token_type_ids: Option<Tensor>,
+    ) -> Result<Tensor> {
+        let model = self
+            .select(Some(language))
+            .await
+            .context("No model loaded")?
+            .model;
+
+        model.forward(
+            &input_ids,
+            attention_mask.as_ref(),
+            token_type_ids.as_ref(),
+        )
+    }
+
+    /// Model best suited to `language`, or the current one for `None`
+    ///
+    /// A model picked for a language becomes the most recently used.
+    pub(crate) async fn select(&self, language: Option<&Language>) -> Option<SelectedModel> {
+        let mut models = self.models.write().await;
+        let index = match language {
+            Some(language) => pick_model(&models, language)?,
+            None => models.len().checked_sub(1)?,
+        };
+        let loaded = models.remove(index);
+        let selected = SelectedModel {
+            config: loaded.config.clone(),
+            model: loaded.model.clone(),
+            tokenizer: loaded.tokenizer.clone(),
+        };
+        models.push(loaded);
+        Some(selected)
+    }
+
+    /// Unload one model; returns whether it was loaded
+    pub async fn unload(&self, model_id: &str) -> bool {
+        let mut models = self.models.write().await;
+        let before = models.len();
+        models.retain(|loaded| loaded.config.model_id != model_id);
+        models.len() != before
+    }
+
+    /// Unload all models
     pub async fn unload_model(&self) {
-        let mut model_lock = self.model.write().await;
-        *model_lock = None;
+        self.models.write().await.clear();
+    }
+}
 
-        let mut path_lock = self.model_path.write().await;
-        *path_lock = None;
+/// Index of the model to use for `language`, most recently used first
+fn pick_model(models: &[LoadedModel], language: &Language) -> Option<usize> {
+    let covering = |exact: bool| {
+        models.iter().rposition(|loaded| {
+            Language::parse(&loaded.config.language)
+                .map(|model_language| {
+                    if exact {
+                        model_language == *language
+                    } else {
+                        model_language.covers(language)
+                    }
+                })
+                .unwrap_or(false)
+        })
+    };
+
+    covering(true)
+        .or_else(|| covering(false))
+        .or_else(|| models.len().checked_sub(1))
+}
 
-        let mut config_lock = self.config.write().await;
-        *config_lock = None;
+/// Drop least recently used models until the rest fit in `budget`,
+/// always keeping the current one
+fn evict_over_budget(models: &mut Vec<LoadedModel>, budget: u64) {
+    while models.len() > 1 && models.iter().map(|loaded| loaded.size_bytes).sum::<u64>() > budget {
+        let evicted = models.remove(0);
+        log::info!("Unloaded NER model {} to stay within the memory budget", evicted.config.model_id);
+    }
+}
+
+/// Classifier that gives every token the same label, standing in for a model
+#[cfg(test)]
+pub(crate) struct FixedLabelClassifier {
+    pub label: usize,
+    pub num_labels: usize,
+}
+
+#[cfg(test)]
+impl TokenClassifier for FixedLabelClassifier {
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let (batch, length) = input_ids.dims2()?;
+        let logits: Vec<f32> = (0..batch * length * self.num_labels)
+            .map(|i| if i % self.num_labels == self.label { 5.0 } else { 0.0 })
+            .collect();
+        Ok(Tensor::from_vec(
+            logits,
+            (batch, length, self.num_labels),
+            input_ids.device(),
+        )?)
     }
 }
 
@@ -207,17 +460,95 @@ mod tests {
         // Initially no model loaded
         assert!(!manager.is_loaded().await);
         assert!(manager.get_model_path().await.is_none());
+        assert!(manager.get_language().await.is_none());
 
         // Unload should work even with no model
         manager.unload_model().await;
         assert!(!manager.is_loaded().await);
     }
 
+    fn config(model_id: &str, language: &str) -> NerModelConfig {
+        NerModelConfig {
+            model_id: model_id.to_string(),
+            language: language.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Load a 400-byte model for `language` that gives every token `label`
+    async fn insert(manager: &NerModelManager, model_id: &str, language: &str, label: usize) {
+        let classifier = Arc::new(FixedLabelClassifier { label, num_labels: 9 });
+        let tokenizer = Arc::new(NerTokenizer::word_level(&[]));
+        manager
+            .insert_model(PathBuf::new(), config(model_id, language), classifier, tokenizer, 400)
+            .await;
+    }
+
+    /// Label the model picked for `language` gives the first token
+    async fn predicted_label(manager: &NerModelManager, language: &str) -> u32 {
+        let input_ids = Tensor::zeros((1, 3), DType::U32, &Device::Cpu).unwrap();
+        let logits = manager
+            .predict_for(&Language::parse(language).unwrap(), input_ids, None, None)
+            .await
+            .unwrap();
+        logits.argmax(2).unwrap().to_vec2::<u32>().unwrap()[0][0]
+    }
+
+    #[tokio::test]
+    async fn test_models_for_two_languages_stay_loaded() {
+        let manager = NerModelManager::new();
+        insert(&manager, "bert-en", "en", 1).await;
+        insert(&manager, "bert-nl", "nl", 5).await;
+
+        // Alternating languages uses each model in turn, without reloading
+        for _ in 0..2 {
+            assert_eq!(predicted_label(&manager, "en-GB").await, 1);
+            assert_eq!(predicted_label(&manager, "nl").await, 5);
+        }
+        assert_eq!(manager.loaded_models().await.len(), 2);
+        assert_eq!(manager.get_language().await.as_deref(), Some("nl"));
+
+        // Without a model for the language, the current one is used
+        assert_eq!(predicted_label(&manager, "fr").await, 5);
+
+        // A multilingual model is preferred over another language's model
+        insert(&manager, "xlm", "multilingual", 3).await;
+        assert_eq!(predicted_label(&manager, "en").await, 1);
+        assert_eq!(predicted_label(&manager, "fr").await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_model_is_evicted() {
+        let manager = NerModelManager::new();
+        manager.set_memory_budget(1_000).await;
+        insert(&manager, "bert-en", "en", 1).await;
+        insert(&manager, "bert-nl", "nl", 5).await;
+
+        // Using the English model makes the Dutch one the least recently used
+        predicted_label(&manager, "en").await;
+        insert(&manager, "camembert", "fr", 3).await;
+
+        let ids: Vec<String> = manager
+            .loaded_models()
+            .await
+            .into_iter()
+            .map(|model| model.model_id)
+            .collect();
+        assert_eq!(ids, vec!["camembert", "bert-en"]);
+
+        // The current model stays even when it alone is over the budget
+        manager.set_memory_budget(100).await;
+        assert_eq!(manager.loaded_models().await.len(), 1);
+        assert!(manager.unload("camembert").await);
+        assert!(!manager.is_loaded().await);
+    }
+
     #[test]
     fn test_ner_model_config_default() {
         let config = NerModelConfig::default();
         assert_eq!(config.num_labels, 9);
         assert_eq!(config.model_type, "bert");
         assert_eq!(config.label_map.len(), 9);
+        assert_eq!(config.language, "en");
     }
 }
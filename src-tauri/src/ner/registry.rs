@@ -2,6 +2,8 @@
 #![allow(dead_code)]
 
 use super::types::NerModelInfo;
+use crate::pii::Language;
+use std::collections::HashMap;
 
 /// Registry of pre-configured NER models
 pub struct NerModelRegistry {
@@ -63,6 +65,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.956), // F1 score on CoNLL-2003 test set
+            label_entity_types: HashMap::new(),
         });
 
         // 2. DistilBERT NER (lightweight)
@@ -94,6 +97,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.941), // F1 score
+            label_entity_types: HashMap::new(),
         });
 
         // 3. RoBERTa-base NER (high accuracy)
@@ -125,6 +129,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.964), // F1 score - highest accuracy
+            label_entity_types: HashMap::new(),
         });
 
         // 4. XLM-RoBERTa NER (multilingual)
@@ -156,6 +161,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.93), // Average F1 across languages
+            label_entity_types: HashMap::new(),
         });
 
         // 5. TinyBERT NER (ultra-fast, smallest)
@@ -187,6 +193,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "Apache 2.0".to_string(),
             accuracy: Some(0.87), // Lower accuracy, much faster
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -217,6 +224,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.88), // F1 score on German legal texts
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -247,6 +255,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "Apache 2.0".to_string(),
             accuracy: Some(0.92), // Estimated for legal documents
+            label_entity_types: HashMap::new(),
         });
 
         // spaCy Transformer for legal texts (en_core_web_trf equivalent)
@@ -274,6 +283,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.94),
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -304,6 +314,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.91),
+            label_entity_types: HashMap::new(),
         });
 
         // CamemBERT-base for general French legal texts
@@ -331,6 +342,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.89),
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -361,6 +373,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.90),
+            label_entity_types: HashMap::new(),
         });
 
         // RobBERT - Dutch-specific BERT variant
@@ -388,6 +401,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.88),
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -418,6 +432,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.86),
+            label_entity_types: HashMap::new(),
         });
 
         // Alternative: General RuBERT NER
@@ -445,6 +460,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: Some(0.84),
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -475,6 +491,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "Apache 2.0".to_string(),
             accuracy: Some(0.90),
+            label_entity_types: HashMap::new(),
         });
 
         // BERT-base Chinese for general legal NER
@@ -502,6 +519,7 @@ impl NerModelRegistry {
             checksum: None,
             license: "Apache 2.0".to_string(),
             accuracy: Some(0.87),
+            label_entity_types: HashMap::new(),
         });
     }
 
@@ -539,36 +557,21 @@ impl NerModelRegistry {
     /// Get recommended model for a specific language and legal domain
     ///
     /// Supports: de, en, fr, nl, ru, zh
-    pub fn get_recommended_legal_model(&self, language: &str) -> Option<&NerModelInfo> {
-        match language {
-            "de" | "de-de" | "de/de" => self.get_model("elenanereiss/bert-base-german-legal-ner"),
-            "en" | "en-gb" | "en/gb" | "en-us" => self.get_model("nlpaueb/legal-bert-base-uncased"),
-            "fr" | "fr-fr" | "fr/fr" => self.get_model("almanach/camembert-bio-base"),
-            "nl" | "nl-nl" | "nl/nl" => self.get_model("wietsedv/bert-base-dutch-cased-finetuned-conll2002-ner"),
-            "ru" | "ru-ru" | "ru/ru" => self.get_model("seara/rubert-base-cased-ru-legal-ner"),
-            "zh" | "zh-hans" | "zh/hans" | "zh-hk" | "zh/hk" => self.get_model("thunlp/Lawformer"),
+    pub fn get_recommended_legal_model(&self, language: &Language) -> Option<&NerModelInfo> {
+        match language.as_ner() {
+            "de" => self.get_model("elenanereiss/bert-base-german-legal-ner"),
+            "en" => self.get_model("nlpaueb/legal-bert-base-uncased"),
+            "fr" => self.get_model("almanach/camembert-bio-base"),
+            "nl" => self.get_model("wietsedv/bert-base-dutch-cased-finetuned-conll2002-ner"),
+            "ru" => self.get_model("seara/rubert-base-cased-ru-legal-ner"),
+            "zh" => self.get_model("thunlp/Lawformer"),
             _ => None,
         }
     }
 
     /// Get all legal models for a specific language
-    pub fn get_legal_models_by_language(&self, language: &str) -> Vec<&NerModelInfo> {
-        let normalized_lang = match language {
-            "de-de" | "de/de" => "de",
-            "en-gb" | "en/gb" | "en-us" => "en",
-            "fr-fr" | "fr/fr" => "fr",
-            "nl-nl" | "nl/nl" => "nl",
-            "ru-ru" | "ru/ru" => "ru",
-            "zh-hans" | "zh/hans" | "zh-hk" | "zh/hk" => "zh",
-            other => other,
-        };
-
-        self.models
-            .iter()
-            .filter(|m| {
-                m.language == normalized_lang || m.language == "multilingual"
-            })
-            .collect()
+    pub fn get_legal_models_by_language(&self, language: &Language) -> Vec<&NerModelInfo> {
+        self.get_models_by_language(language)
     }
 
     /// Get all supported legal languages
@@ -578,13 +581,14 @@ impl NerModelRegistry {
 
     /// Get model recommendations by use case
     pub fn get_recommendations_by_use_case(&self, use_case: &str) -> Vec<&NerModelInfo> {
+        let legal = |code: &str| Language::Code(code.to_string());
         match use_case {
-            "legal-german" => self.get_legal_models_by_language("de"),
-            "legal-english" => self.get_legal_models_by_language("en"),
-            "legal-french" => self.get_legal_models_by_language("fr"),
-            "legal-dutch" => self.get_legal_models_by_language("nl"),
-            "legal-russian" => self.get_legal_models_by_language("ru"),
-            "legal-chinese" => self.get_legal_models_by_language("zh"),
+            "legal-german" => self.get_legal_models_by_language(&legal("de")),
+            "legal-english" => self.get_legal_models_by_language(&legal("en")),
+            "legal-french" => self.get_legal_models_by_language(&legal("fr")),
+            "legal-dutch" => self.get_legal_models_by_language(&legal("nl")),
+            "legal-russian" => self.get_legal_models_by_language(&legal("ru")),
+            "legal-chinese" => self.get_legal_models_by_language(&legal("zh")),
             "fastest" => {
                 vec![self.get_fastest_model()].into_iter().flatten().collect()
             }
@@ -598,21 +602,17 @@ impl NerModelRegistry {
         }
     }
 
-    /// Get all models for a specific language
-    pub fn get_models_by_language(&self, language: &str) -> Vec<&NerModelInfo> {
-        let normalized_lang = match language {
-            "de-de" | "de/de" => "de",
-            "en-gb" | "en/gb" | "en-us" => "en",
-            "fr-fr" | "fr/fr" => "fr",
-            "nl-nl" | "nl/nl" => "nl",
-            "ru-ru" | "ru/ru" => "ru",
-            "zh-hans" | "zh/hans" | "zh-hk" | "zh/hk" => "zh",
-            other => other,
-        };
-
+    /// Get all models for a specific language, including multilingual ones
+    ///
+    /// Models whose language metadata is not a valid code are skipped.
+    pub fn get_models_by_language(&self, language: &Language) -> Vec<&NerModelInfo> {
         self.models
             .iter()
-            .filter(|m| m.language == normalized_lang || m.language == "multilingual")
+            .filter(|m| {
+                Language::parse(&m.language)
+                    .map(|model_language| model_language.covers(language))
+                    .unwrap_or(false)
+            })
             .collect()
     }
 
@@ -659,10 +659,45 @@ mod tests {
     #[test]
     fn test_get_models_by_language() {
         let registry = NerModelRegistry::new();
-        let en_models = registry.get_models_by_language("en");
+        let en_models = registry.get_models_by_language(&Language::english());
         assert!(en_models.len() > 0);
     }
 
+    #[test]
+    fn test_language_spellings_select_same_models() {
+        let registry = NerModelRegistry::new();
+
+        for spellings in [
+            ["de", "de-de", "de/de"],
+            ["en", "en-gb", "en/gb"],
+            ["zh", "zh-hans", "zh/hk"],
+        ] {
+            let ids = |spelling: &str| -> Vec<String> {
+                let language = Language::parse(spelling).unwrap();
+                let recommended = registry
+                    .get_recommended_legal_model(&language)
+                    .map(|m| m.model_id.clone());
+                registry
+                    .get_models_by_language(&language)
+                    .iter()
+                    .map(|m| m.model_id.clone())
+                    .chain(recommended)
+                    .collect()
+            };
+
+            let expected = ids(spellings[0]);
+            assert!(!expected.is_empty());
+            for spelling in &spellings[1..] {
+                assert_eq!(ids(spelling), expected, "{}", spelling);
+            }
+        }
+
+        // Multilingual models are offered for every language
+        let multilingual = registry.get_multilingual_model().unwrap();
+        let ru_models = registry.get_models_by_language(&Language::parse("ru").unwrap());
+        assert!(ru_models.iter().any(|m| m.model_id == multilingual.model_id));
+    }
+
     #[test]
     fn test_recommended_models() {
         let registry = NerModelRegistry::new();
@@ -696,6 +731,7 @@ mod tests {
             checksum: None,
             license: "MIT".to_string(),
             accuracy: None,
+            label_entity_types: HashMap::new(),
         };
 
         registry.add_model(custom_model);
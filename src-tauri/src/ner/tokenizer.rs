@@ -1,5 +1,6 @@
 use anyhow::Result;
 use candle_core::{Device, Tensor};
+use std::path::Path;
 use tokenizers::tokenizer::Tokenizer;
 
 /// Tokenizer wrapper for NER tasks
@@ -9,6 +10,42 @@ pub struct NerTokenizer {
 }
 
 impl NerTokenizer {
+    /// Load a model's `tokenizer.json`, truncating input to `max_length` tokens
+    pub fn from_file(path: &Path, max_length: usize) -> Result<Self> {
+        let tokenizer = Tokenizer::from_file(path).map_err(|e| {
+            anyhow::anyhow!("Failed to load tokenizer {}: {}", path.display(), e)
+        })?;
+        Ok(Self {
+            tokenizer,
+            max_length,
+        })
+    }
+
+    /// Whitespace tokenizer with a fixed vocabulary, standing in for a
+    /// model's; words are numbered from 1 in order, unknown words get 0
+    #[cfg(test)]
+    pub(crate) fn word_level(words: &[&str]) -> Self {
+        use tokenizers::models::wordlevel::WordLevel;
+        use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+        let mut vocab: std::collections::HashMap<String, u32> =
+            std::iter::once(("[UNK]".to_string(), 0)).collect();
+        for (id, word) in words.iter().enumerate() {
+            vocab.insert(word.to_string(), id as u32 + 1);
+        }
+        let model = WordLevel::builder()
+            .vocab(vocab.into_iter().collect())
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+        Self {
+            tokenizer,
+            max_length: 512,
+        }
+    }
+
     /// Tokenize text and return input tensors
     pub fn encode(&self, text: &str, device: &Device) -> Result<EncodingOutput> {
         // Encode text
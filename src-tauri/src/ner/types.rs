@@ -1,84 +1,99 @@
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 
-/// NER entity labels following standard BIO tagging scheme
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+use crate::pii::EntityType;
+
+/// NER entity label following the BIO tagging scheme
+///
+/// The entity type is whatever the model's labels name ("PER", "JUDGE",
+/// "LAW", ...); see `NerModelConfig::label_map`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum NerLabel {
-    // Outside any entity
+    /// Outside any entity
     O,
-
-    // Person entity
-    #[serde(rename = "B-PER")]
-    BeginPerson,
-    #[serde(rename = "I-PER")]
-    InsidePerson,
-
-    // Organization entity
-    #[serde(rename = "B-ORG")]
-    BeginOrganization,
-    #[serde(rename = "I-ORG")]
-    InsideOrganization,
-
-    // Location entity
-    #[serde(rename = "B-LOC")]
-    BeginLocation,
-    #[serde(rename = "I-LOC")]
-    InsideLocation,
-
-    // Miscellaneous entity (dates, events, etc.)
-    #[serde(rename = "B-MISC")]
-    BeginMisc,
-    #[serde(rename = "I-MISC")]
-    InsideMisc,
+    /// First token of an entity ("B-PER")
+    Begin(String),
+    /// Following token of an entity ("I-PER")
+    Inside(String),
 }
 
 impl NerLabel {
-    /// Convert from label ID (0-8)
-    pub fn from_id(id: usize) -> Option<Self> {
-        match id {
-            0 => Some(NerLabel::O),
-            1 => Some(NerLabel::BeginPerson),
-            2 => Some(NerLabel::InsidePerson),
-            3 => Some(NerLabel::BeginOrganization),
-            4 => Some(NerLabel::InsideOrganization),
-            5 => Some(NerLabel::BeginLocation),
-            6 => Some(NerLabel::InsideLocation),
-            7 => Some(NerLabel::BeginMisc),
-            8 => Some(NerLabel::InsideMisc),
-            _ => None,
+    /// Parse a label name such as "B-PER", "I-LAW" or "O"
+    ///
+    /// A name without a BIO prefix is taken as the start of an entity.
+    pub fn parse(name: &str) -> Self {
+        if name == "O" {
+            NerLabel::O
+        } else if let Some(entity_type) = name.strip_prefix("B-") {
+            NerLabel::Begin(entity_type.to_string())
+        } else if let Some(entity_type) = name.strip_prefix("I-") {
+            NerLabel::Inside(entity_type.to_string())
+        } else {
+            NerLabel::Begin(name.to_string())
         }
     }
 
+    /// Convert from label ID in the default CoNLL scheme (0-8)
+    pub fn from_id(id: usize) -> Option<Self> {
+        NerModelConfig::default().label(id)
+    }
+
     /// Check if this is a beginning tag
     pub fn is_begin(&self) -> bool {
-        matches!(
-            self,
-            NerLabel::BeginPerson
-                | NerLabel::BeginOrganization
-                | NerLabel::BeginLocation
-                | NerLabel::BeginMisc
-        )
+        matches!(self, NerLabel::Begin(_))
     }
 
     /// Check if this is an inside tag
     pub fn is_inside(&self) -> bool {
-        matches!(
-            self,
-            NerLabel::InsidePerson
-                | NerLabel::InsideOrganization
-                | NerLabel::InsideLocation
-                | NerLabel::InsideMisc
-        )
+        matches!(self, NerLabel::Inside(_))
     }
 
     /// Get entity type (without B-/I- prefix)
-    pub fn entity_type(&self) -> Option<&'static str> {
+    pub fn entity_type(&self) -> Option<&str> {
         match self {
             NerLabel::O => None,
-            NerLabel::BeginPerson | NerLabel::InsidePerson => Some("PER"),
-            NerLabel::BeginOrganization | NerLabel::InsideOrganization => Some("ORG"),
-            NerLabel::BeginLocation | NerLabel::InsideLocation => Some("LOC"),
-            NerLabel::BeginMisc | NerLabel::InsideMisc => Some("MISC"),
+            NerLabel::Begin(entity_type) | NerLabel::Inside(entity_type) => Some(entity_type),
+        }
+    }
+}
+
+impl From<String> for NerLabel {
+    fn from(name: String) -> Self {
+        NerLabel::parse(&name)
+    }
+}
+
+impl From<NerLabel> for String {
+    fn from(label: NerLabel) -> Self {
+        match label {
+            NerLabel::O => "O".to_string(),
+            NerLabel::Begin(entity_type) => format!("B-{}", entity_type),
+            NerLabel::Inside(entity_type) => format!("I-{}", entity_type),
+        }
+    }
+}
+
+/// Entity type of an NER label name used by common models, without its BIO
+/// prefix; `None` for labels such as MISC that have no reliable type
+pub fn default_entity_type(label: &str) -> Option<EntityType> {
+    match label.to_ascii_uppercase().as_str() {
+        "PER" | "PERSON" | "JUDGE" | "LAWYER" => Some(EntityType::Person),
+        "ORG" | "ORGANIZATION" | "ORGANISATION" | "COMPANY" | "COURT" => {
+            Some(EntityType::Organization)
+        }
+        "LOC" | "LOCATION" | "GPE" | "ADDRESS" | "CITY" | "COUNTRY" | "STREET" => {
+            Some(EntityType::Location)
         }
+        "DATE" => Some(EntityType::Date),
+        "MONEY" => Some(EntityType::Money),
+        "EMAIL" => Some(EntityType::Email),
+        "PHONE" => Some(EntityType::Phone),
+        "LAW" | "STATUTE" | "REGULATION" => Some(EntityType::Law),
+        "CASE" | "CASE_NUMBER" => Some(EntityType::Case),
+        _ => None,
     }
 }
 
@@ -101,6 +116,9 @@ pub struct NerEntity {
     pub start: usize,
     pub end: usize,
     pub tokens: Vec<TokenPrediction>,
+    /// PII type the label maps to in the model's config
+    #[serde(default)]
+    pub pii_type: Option<EntityType>,
 }
 
 /// NER inference result
@@ -122,6 +140,15 @@ pub struct NerModelConfig {
     pub hidden_size: usize,        // e.g., 768 for BERT-base
     pub vocab_size: usize,
     pub label_map: Vec<String>,    // Maps label IDs to names
+    #[serde(default = "default_ner_language")]
+    pub language: String,          // ISO code or "multilingual"
+    /// Entity type per label name (without B-/I-), over `default_entity_type`
+    #[serde(default)]
+    pub label_entity_types: HashMap<String, EntityType>,
+}
+
+fn default_ner_language() -> String {
+    "en".to_string()
 }
 
 impl Default for NerModelConfig {
@@ -144,7 +171,94 @@ impl Default for NerModelConfig {
                 "B-MISC".to_string(),
                 "I-MISC".to_string(),
             ],
+            language: default_ner_language(),
+            label_entity_types: HashMap::new(),
+        }
+    }
+}
+
+impl NerModelConfig {
+    /// Label of a label ID
+    pub fn label(&self, id: usize) -> Option<NerLabel> {
+        self.label_map.get(id).map(|name| NerLabel::parse(name))
+    }
+
+    /// PII type of a label name, with or without its BIO prefix
+    pub fn entity_type_for(&self, label: &str) -> Option<EntityType> {
+        let label = match NerLabel::parse(label) {
+            NerLabel::O => return None,
+            NerLabel::Begin(name) | NerLabel::Inside(name) => name,
+        };
+        self.label_entity_types
+            .get(&label)
+            .copied()
+            .or_else(|| default_entity_type(&label))
+    }
+
+    /// Label names (without B-/I-) that map to no PII type; entities with
+    /// them are dropped
+    pub fn unmapped_labels(&self) -> Vec<String> {
+        let mut unmapped: Vec<String> = Vec::new();
+        for name in &self.label_map {
+            if let Some(label) = NerLabel::parse(name).entity_type() {
+                if self.entity_type_for(label).is_none() && !unmapped.iter().any(|u| u == label) {
+                    unmapped.push(label.to_string());
+                }
+            }
+        }
+        unmapped
+    }
+
+    /// Read labels and dimensions from a model's `config.json`
+    pub fn load_model_config(&mut self, path: &Path) -> Result<()> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.apply_model_config(&json)
+            .with_context(|| format!("Invalid model config {}", path.display()))
+    }
+
+    /// Take labels from `id2label` and dimensions from a Hugging Face model config
+    pub fn apply_model_config(&mut self, json: &str) -> Result<()> {
+        #[derive(Deserialize)]
+        struct HfConfig {
+            #[serde(default)]
+            id2label: HashMap<String, String>,
+            model_type: Option<String>,
+            hidden_size: Option<usize>,
+            vocab_size: Option<usize>,
+            max_position_embeddings: Option<usize>,
+        }
+
+        let config: HfConfig = serde_json::from_str(json)?;
+
+        if !config.id2label.is_empty() {
+            let mut labels = Vec::with_capacity(config.id2label.len());
+            for (id, name) in config.id2label {
+                let id: usize = id
+                    .parse()
+                    .with_context(|| format!("Label ID '{}' is not a number", id))?;
+                labels.push((id, name));
+            }
+            labels.sort_by_key(|(id, _)| *id);
+            if labels.iter().enumerate().any(|(i, (id, _))| i != *id) {
+                anyhow::bail!("id2label IDs must run from 0 without gaps");
+            }
+            self.label_map = labels.into_iter().map(|(_, name)| name).collect();
+            self.num_labels = self.label_map.len();
+        }
+        if let Some(model_type) = config.model_type {
+            self.model_type = model_type;
+        }
+        if let Some(hidden_size) = config.hidden_size {
+            self.hidden_size = hidden_size;
+        }
+        if let Some(vocab_size) = config.vocab_size {
+            self.vocab_size = vocab_size;
+        }
+        if let Some(max_length) = config.max_position_embeddings {
+            self.max_sequence_length = max_length;
         }
+        Ok(())
     }
 }
 
@@ -168,6 +282,10 @@ pub struct NerModelInfo {
     pub checksum: Option<String>,
     pub license: String,
     pub accuracy: Option<f64>,  // F1 score on CoNLL-2003 or similar
+    /// PII type per label name, for custom models whose labels the default
+    /// mapping doesn't know
+    #[serde(default)]
+    pub label_entity_types: HashMap<String, EntityType>,
 }
 
 #[cfg(test)]
@@ -177,15 +295,15 @@ mod tests {
     #[test]
     fn test_ner_label_conversions() {
         assert_eq!(NerLabel::from_id(0), Some(NerLabel::O));
-        assert_eq!(NerLabel::from_id(1), Some(NerLabel::BeginPerson));
+        assert_eq!(NerLabel::from_id(1), Some(NerLabel::Begin("PER".to_string())));
     }
 
     #[test]
     fn test_ner_label_properties() {
-        assert!(NerLabel::BeginPerson.is_begin());
-        assert!(!NerLabel::InsidePerson.is_begin());
-        assert!(NerLabel::InsidePerson.is_inside());
-        assert_eq!(NerLabel::BeginPerson.entity_type(), Some("PER"));
+        assert!(NerLabel::parse("B-PER").is_begin());
+        assert!(!NerLabel::parse("I-PER").is_begin());
+        assert!(NerLabel::parse("I-PER").is_inside());
+        assert_eq!(NerLabel::parse("B-PER").entity_type(), Some("PER"));
         assert_eq!(NerLabel::O.entity_type(), None);
     }
 }
@@ -1,8 +1,23 @@
-use std::collections::HashMap;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
+use super::dates::normalize_date;
 use super::detector::PIIDetector;
 use super::entity_linker::EntityLinker;
-use super::types::{AnonymizationResult, AnonymizationSettings, Entity, EntityType};
+use super::pseudonyms::PseudonymGenerator;
+use super::types::{
+    assign_utf16_offsets, drop_short_entities, AnonymizationResult, AnonymizationSettings,
+    ConsistencyScope, DiffSegment, EmbeddedData, Entity, EntityType, MappingConflict,
+    MaskingStrategy, MergePolicy, MergedMapping, PersonStyle, Verification, INDEX_PLACEHOLDER,
+    LETTER_PLACEHOLDER,
+};
+
+/// Pseudonym collisions to retry before falling back to a bracketed placeholder
+const MAX_PSEUDONYM_ATTEMPTS: u32 = 16;
+
+/// Confidence from which PII left in anonymized text counts as residual
+const RESIDUAL_CONFIDENCE: f64 = 0.8;
 
 /// Smart anonymizer with consistent replacement
 pub struct Anonymizer {
@@ -27,6 +42,135 @@ impl Anonymizer {
         &mut self,
         text: &str,
         settings: &AnonymizationSettings,
+    ) -> AnonymizationResult {
+        let stripped;
+        let text = match settings.embedded_data {
+            EmbeddedData::Redact => text,
+            EmbeddedData::Strip => {
+                stripped = self.detector.strip_embedded_data(text);
+                stripped.as_str()
+            }
+        };
+
+        // Detect entities
+        let mut entities = self.detector.detect(text);
+
+        // Add heuristic person name detection
+        if settings.use_name_heuristic {
+            let person_entities = self.detector.detect_person_names(text);
+            entities.extend(person_entities);
+        }
+
+        self.anonymize_entities(text, entities, settings)
+    }
+
+    /// Anonymize text using entities found by another detector (e.g. NER or Presidio)
+    ///
+    /// Filtering, legal reference preservation and replacement work as in `anonymize`.
+    /// `settings.embedded_data` is not applied: the entities' offsets refer to
+    /// `text` as the other detector saw it, so blobs can't be stripped here.
+    pub fn anonymize_entities(
+        &mut self,
+        text: &str,
+        entities: Vec<Entity>,
+        settings: &AnonymizationSettings,
+    ) -> AnonymizationResult {
+        if settings.verification == Verification::Off {
+            return self.anonymize_pass(text, entities, settings);
+        }
+
+        let mut result = self.anonymize_pass(text, entities.clone(), settings);
+        result.residual_pii = self.residual_pii(&result, settings);
+
+        if settings.verification == Verification::Repass && !result.residual_pii.is_empty() {
+            let mut entities = entities;
+            entities.extend(Self::residuals_in_original(&result));
+            result = self.anonymize_pass(text, entities, settings);
+            result.residual_pii = self.residual_pii(&result, settings);
+        }
+
+        result
+    }
+
+    /// Detect PII that remains in the anonymized text outside the replacements
+    ///
+    /// Only high-confidence entities of the selected types count, so that the
+    /// name heuristic doesn't flag ordinary capitalized words.
+    fn residual_pii(
+        &self,
+        result: &AnonymizationResult,
+        settings: &AnonymizationSettings,
+    ) -> Vec<Entity> {
+        let output = &result.anonymized_text;
+        let replaced = Self::replaced_spans(&result.entities);
+
+        let mut residual = self.detector.detect(output);
+        if settings.use_name_heuristic {
+            residual.extend(self.detector.detect_person_names(output));
+        }
+        residual.retain(|e| {
+            let threshold = settings.min_confidence_for(e.entity_type).max(RESIDUAL_CONFIDENCE);
+            e.entity_type.should_anonymize()
+                && settings.entity_types.contains(&e.entity_type)
+                && e.confidence >= threshold
+                && replaced
+                    .iter()
+                    .all(|span| e.end <= span.output.0 || e.start >= span.output.1)
+        });
+        drop_short_entities(&mut residual, settings.min_entity_length);
+        residual.sort_by_key(|e| e.start);
+        assign_utf16_offsets(&mut residual, output);
+        residual
+    }
+
+    /// Residual PII moved from anonymized-text offsets to original-text offsets
+    ///
+    /// Residuals lie outside every replacement, so their text appears
+    /// unchanged in the original, shifted by the replacements before it.
+    fn residuals_in_original(result: &AnonymizationResult) -> Vec<Entity> {
+        let replaced = Self::replaced_spans(&result.entities);
+        result
+            .residual_pii
+            .iter()
+            .map(|residual| {
+                let (output_end, original_end) = replaced
+                    .iter()
+                    .rev()
+                    .find(|span| span.output.1 <= residual.start)
+                    .map(|span| (span.output.1, span.original.1))
+                    .unwrap_or((0, 0));
+                let mut entity = residual.clone();
+                entity.start = original_end + (residual.start - output_end);
+                entity.end = entity.start + (residual.end - residual.start);
+                entity
+            })
+            .collect()
+    }
+
+    /// Where each applied replacement sits in the original and anonymized text
+    fn replaced_spans(entities: &[Entity]) -> Vec<ReplacedSpan> {
+        let mut spans = Vec::new();
+        let mut shift: isize = 0;
+
+        for entity in Self::non_overlapping(entities) {
+            let replacement_len = entity.replacement.as_ref().unwrap_or(&entity.text).len();
+            let output_start = (entity.start as isize + shift) as usize;
+            spans.push(ReplacedSpan {
+                original: (entity.start, entity.end),
+                output: (output_start, output_start + replacement_len),
+            });
+            shift += replacement_len as isize - (entity.end - entity.start) as isize;
+        }
+
+        spans
+    }
+
+    /// One anonymization pass over detected entities
+    fn anonymize_pass(
+        &mut self,
+        text: &str,
+        mut entities: Vec<Entity>,
+        settings: &AnonymizationSettings,
     ) -> AnonymizationResult {
         // Reset state for each document if not using consistent replacement
         if !settings.consistent_replacement {
@@ -34,25 +178,40 @@ impl Anonymizer {
             self.counters.clear();
         }
 
-        // Detect entities
-        let mut entities = self.detector.detect(text);
-
-        // Add person name detection
-        let person_entities = self.detector.detect_person_names(text);
-        entities.extend(person_entities);
-
         // Sort by position again after adding person names
         entities.sort_by_key(|e| e.start);
 
-        // Filter by confidence threshold and entity types
+        // Filter by confidence threshold and entity types. Legal references are
+        // kept whenever they are to be preserved, whatever the selected types.
         entities.retain(|e| {
-            e.confidence >= settings.confidence_threshold
-                && settings.entity_types.contains(&e.entity_type)
+            let preserved_law = settings.preserve_legal_references && e.entity_type == EntityType::Law;
+            e.confidence >= settings.min_confidence_for(e.entity_type)
+                && (preserved_law || settings.entity_types.contains(&e.entity_type))
         });
+        drop_short_entities(&mut entities, settings.min_entity_length);
+        for entity in entities.iter_mut() {
+            entity.needs_review =
+                entity.entity_type.should_anonymize() && settings.needs_review(entity.confidence);
+        }
 
-        // Preserve legal references if enabled
+        // Preserve legal references if enabled, along with anything detected inside them.
+        // The references stay in the result, flagged, so the UI can show them as
+        // recognized rather than missed.
         if settings.preserve_legal_references {
-            entities.retain(|e| e.entity_type != EntityType::Law);
+            let legal_spans: Vec<(usize, usize)> = entities
+                .iter()
+                .filter(|e| e.entity_type == EntityType::Law)
+                .map(|e| (e.start, e.end))
+                .collect();
+            entities.retain(|e| {
+                e.entity_type == EntityType::Law
+                    || !legal_spans
+                        .iter()
+                        .any(|&(start, end)| e.start < end && start < e.end)
+            });
+            for entity in entities.iter_mut().filter(|e| e.entity_type == EntityType::Law) {
+                entity.preserved = true;
+            }
         }
 
         // Auto-link person entities for consistent replacement
@@ -69,33 +228,68 @@ impl Anonymizer {
         }
 
         // Generate replacements
-        let entities_with_replacements = self.generate_replacements(entities);
+        let mut entities_with_replacements = self.generate_replacements(entities, settings);
+        assign_utf16_offsets(&mut entities_with_replacements, text);
 
         // Apply anonymization
         let anonymized_text = self.apply_anonymization(text, &entities_with_replacements);
 
-        // Build replacement mapping
+        // Build replacement mapping, leaving out replacements that don't map back
         let replacements: Vec<(String, String)> = entities_with_replacements
             .iter()
+            .filter(|e| {
+                e.replacement.as_deref() == Some(e.text.as_str())
+                    || settings.masking_strategy(e.entity_type).is_reversible()
+            })
             .map(|e| (e.text.clone(), e.replacement.clone().unwrap_or_default()))
             .collect();
 
+        // Count what was replaced in this document only (independent of self.counters)
+        let mut statistics: HashMap<EntityType, usize> = HashMap::new();
+        for entity in Self::non_overlapping(&entities_with_replacements) {
+            let kept = entity.replacement.as_deref() == Some(entity.text.as_str());
+            if entity.entity_type.should_anonymize() && !kept {
+                *statistics.entry(entity.entity_type).or_insert(0) += 1;
+            }
+        }
+
         AnonymizationResult {
             original_text: text.to_string(),
             anonymized_text,
             entities: entities_with_replacements,
             replacements,
+            statistics,
+            residual_pii: Vec::new(),
         }
     }
 
-    fn generate_replacements(&mut self, entities: Vec<Entity>) -> Vec<Entity> {
+    fn generate_replacements(
+        &mut self,
+        entities: Vec<Entity>,
+        settings: &AnonymizationSettings,
+    ) -> Vec<Entity> {
+        let pseudonyms = PseudonymGenerator::new(&settings.language);
+
         entities
             .into_iter()
             .map(|entity| {
-                let replacement = if entity.entity_type.should_anonymize() {
-                    self.get_or_create_replacement(&entity)
-                } else {
-                    entity.text.clone() // Don't replace
+                if !entity.entity_type.should_anonymize() || entity.needs_review {
+                    let text = entity.text.clone(); // Don't replace
+                    return entity.with_replacement(text);
+                }
+
+                let replacement = match settings.masking_strategy(entity.entity_type) {
+                    MaskingStrategy::Placeholder => {
+                        self.get_or_create_replacement(&entity, settings, &pseudonyms)
+                    }
+                    MaskingStrategy::Replace { new_value } => new_value.clone(),
+                    MaskingStrategy::Redact => String::new(),
+                    MaskingStrategy::Mask {
+                        masking_char,
+                        chars_to_mask,
+                        from_end,
+                    } => Self::mask(&entity.text, *masking_char, *chars_to_mask, *from_end),
+                    MaskingStrategy::Keep => entity.text.clone(),
                 };
 
                 entity.with_replacement(replacement)
@@ -103,12 +297,21 @@ impl Anonymizer {
             .collect()
     }
 
-    fn get_or_create_replacement(&mut self, entity: &Entity) -> String {
-        // Get canonical form for entity (handles variations like "Mr. John Doe" -> "john doe")
-        let canonical_text = if entity.entity_type == EntityType::Person {
-            self.entity_linker.get_canonical(&entity.text)
-        } else {
-            entity.text.to_lowercase()
+    fn get_or_create_replacement(
+        &mut self,
+        entity: &Entity,
+        settings: &AnonymizationSettings,
+        pseudonyms: &PseudonymGenerator,
+    ) -> String {
+        // Get canonical form for entity (handles variations like "Mr. John Doe" -> "john doe",
+        // "(555) 123-4567" -> "5551234567", "01/02/2024" -> "2024-02-01")
+        let canonical_text = match entity.entity_type {
+            EntityType::Person => self.entity_linker.get_canonical(&entity.text),
+            EntityType::Phone => Self::normalize_phone(&entity.text),
+            EntityType::Date => normalize_date(&entity.text, settings.date_order())
+                .map(|date| date.to_string())
+                .unwrap_or_else(|| entity.text.trim().to_lowercase()),
+            _ => entity.text.trim().to_lowercase(),
         };
 
         // Check if we already have a replacement for the canonical form
@@ -117,21 +320,41 @@ impl Anonymizer {
         }
 
         // Generate new replacement
+        let pseudonym = match (entity.entity_type, settings.person_style()) {
+            (EntityType::Person, PersonStyle::Bracket) => None,
+            (EntityType::Person, PersonStyle::Initials) => self.unused_initials(&canonical_text),
+            (EntityType::Person, PersonStyle::Pseudonym) => {
+                self.unused_pseudonym(pseudonyms, entity.entity_type, &canonical_text)
+            }
+            (entity_type, _) if settings.pseudonymize && PseudonymGenerator::supports(entity_type) => {
+                self.unused_pseudonym(pseudonyms, entity_type, &canonical_text)
+            }
+            _ => None,
+        };
+
         let counter = self.counters.entry(entity.entity_type).or_insert(0);
         *counter += 1;
 
-        let replacement = match entity.entity_type {
-            EntityType::Person => format!("[PERSON-{}]", Self::to_letter(*counter)),
-            EntityType::Organization => format!("[ORGANIZATION-{}]", Self::to_letter(*counter)),
-            EntityType::Location => format!("[LOCATION-{}]", Self::to_letter(*counter)),
-            EntityType::Date => format!("[DATE-{}]", counter),
-            EntityType::Money => format!("[AMOUNT-{}]", counter),
-            EntityType::Email => format!("[EMAIL-{}]", counter),
-            EntityType::Phone => format!("[PHONE-{}]", counter),
-            EntityType::Case => format!("[CASE-{}]", counter),
-            EntityType::Identification => format!("[ID-{}]", counter),
-            EntityType::TechnicalIdentifier => format!("[TECH-ID-{}]", counter),
-            EntityType::Law => entity.text.clone(), // Should not anonymize
+        let replacement = if let Some(pseudonym) = pseudonym {
+            pseudonym
+        } else if let Some(template) = settings.replacement_template(entity.entity_type) {
+            template
+                .replace(INDEX_PLACEHOLDER, &counter.to_string())
+                .replace(LETTER_PLACEHOLDER, &Self::to_letter(*counter))
+        } else {
+            match entity.entity_type {
+                EntityType::Person => format!("[PERSON-{}]", Self::to_letter(*counter)),
+                EntityType::Organization => format!("[ORGANIZATION-{}]", Self::to_letter(*counter)),
+                EntityType::Location => format!("[LOCATION-{}]", Self::to_letter(*counter)),
+                EntityType::Date => format!("[DATE-{}]", counter),
+                EntityType::Money => format!("[AMOUNT-{}]", counter),
+                EntityType::Email => format!("[EMAIL-{}]", counter),
+                EntityType::Phone => format!("[PHONE-{}]", counter),
+                EntityType::Case => format!("[CASE-{}]", counter),
+                EntityType::Identification => format!("[ID-{}]", counter),
+                EntityType::TechnicalIdentifier => format!("[TECH-ID-{}]", counter),
+                EntityType::Law => entity.text.clone(), // Should not anonymize
+            }
         };
 
         // Store in map using canonical form for consistent replacement across variations
@@ -146,22 +369,10 @@ impl Anonymizer {
             return text.to_string();
         }
 
-        // Remove overlapping entities - keep the first one encountered
-        let mut filtered_entities: Vec<&Entity> = Vec::new();
-        let mut last_end = 0;
-
-        for entity in entities {
-            // Skip entities that overlap with previously processed ones
-            if entity.start >= last_end {
-                filtered_entities.push(entity);
-                last_end = entity.end;
-            }
-        }
-
         let mut result = String::new();
         let mut last_pos = 0;
 
-        for entity in filtered_entities {
+        for entity in Self::non_overlapping(entities) {
             // Add text before entity
             result.push_str(&text[last_pos..entity.start]);
 
@@ -181,6 +392,85 @@ impl Anonymizer {
         result
     }
 
+    /// Pseudonym for a canonical value that no other value is already using
+    fn unused_pseudonym(
+        &self,
+        generator: &PseudonymGenerator,
+        entity_type: EntityType,
+        canonical_text: &str,
+    ) -> Option<String> {
+        (0..MAX_PSEUDONYM_ATTEMPTS)
+            .filter_map(|attempt| generator.pseudonym(entity_type, canonical_text, attempt))
+            .find(|candidate| !self.replacement_map.values().any(|used| used == candidate))
+    }
+
+    /// Initials for a canonical person name that no other value is already using
+    ///
+    /// A second person with the same initials gets a numbered variant, e.g. "J.D. (2)".
+    fn unused_initials(&self, canonical_text: &str) -> Option<String> {
+        let initials = Self::initials(canonical_text)?;
+        (1..)
+            .map(|n| match n {
+                1 => initials.clone(),
+                n => format!("{} ({})", initials, n),
+            })
+            .find(|candidate| !self.replacement_map.values().any(|used| used == candidate))
+    }
+
+    /// Initials of a name, e.g. "john michael doe" -> "J.M.D.", "madonna" -> "M."
+    fn initials(name: &str) -> Option<String> {
+        let initials: String = name
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+            .flat_map(|c| c.to_uppercase().chain(std::iter::once('.')))
+            .collect();
+
+        (!initials.is_empty()).then_some(initials)
+    }
+
+    /// Overwrite `count` characters of `text`, from the start or from the end
+    fn mask(text: &str, masking_char: char, count: usize, from_end: bool) -> String {
+        let len = text.chars().count();
+        let count = count.min(len);
+        let masked = |i: usize| if from_end { i >= len - count } else { i < count };
+
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| if masked(i) { masking_char } else { c })
+            .collect()
+    }
+
+    /// Reduce a phone number to its digits so different formats share a key
+    fn normalize_phone(text: &str) -> String {
+        let digits: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+
+        if digits.is_empty() {
+            return text.trim().to_lowercase();
+        }
+
+        // "0031 ..." and "+31 ..." are the same international number
+        match digits.strip_prefix("00") {
+            Some(rest) if text.trim_start().starts_with("00") => rest.to_string(),
+            _ => digits,
+        }
+    }
+
+    /// Remove overlapping entities - keep the first one encountered
+    fn non_overlapping(entities: &[Entity]) -> Vec<&Entity> {
+        let mut filtered_entities: Vec<&Entity> = Vec::new();
+        let mut last_end = 0;
+
+        for entity in entities {
+            // Skip entities that overlap with previously processed ones
+            if entity.start >= last_end {
+                filtered_entities.push(entity);
+                last_end = entity.end;
+            }
+        }
+
+        filtered_entities
+    }
+
     fn to_letter(n: usize) -> String {
         if n == 0 {
             return "A".to_string();
@@ -204,13 +494,38 @@ impl Anonymizer {
         texts: Vec<String>,
         settings: &AnonymizationSettings,
     ) -> Vec<AnonymizationResult> {
-        // Keep consistent replacement across all documents
         texts
             .into_iter()
-            .map(|text| self.anonymize(&text, settings))
+            .map(|text| self.anonymize_batch_document(&text, settings))
             .collect()
     }
 
+    /// Anonymize one document of a batch
+    ///
+    /// Per batch, the document shares replacements with everything anonymized
+    /// before. Per document, it starts from empty replacements, and the
+    /// anonymizer's own replacements are put back afterwards untouched.
+    pub fn anonymize_batch_document(
+        &mut self,
+        text: &str,
+        settings: &AnonymizationSettings,
+    ) -> AnonymizationResult {
+        if settings.consistency_scope == ConsistencyScope::PerBatch {
+            return self.anonymize(text, settings);
+        }
+
+        let replacement_map = std::mem::take(&mut self.replacement_map);
+        let counters = std::mem::take(&mut self.counters);
+        let entity_linker = std::mem::take(&mut self.entity_linker);
+
+        let result = self.anonymize(text, settings);
+
+        self.replacement_map = replacement_map;
+        self.counters = counters;
+        self.entity_linker = entity_linker;
+        result
+    }
+
     /// Clear replacement mapping (start fresh)
     pub fn clear_replacements(&mut self) {
         self.replacement_map.clear();
@@ -221,6 +536,271 @@ impl Anonymizer {
     pub fn get_statistics(&self) -> HashMap<EntityType, usize> {
         self.counters.clone()
     }
+
+    /// Split a result into unchanged and replaced segments for a side-by-side diff
+    ///
+    /// Entities are resolved exactly as when the anonymized text was built, so
+    /// the segments' original sides concatenate to `original_text` and their
+    /// anonymized sides to `anonymized_text`.
+    pub fn diff_segments(result: &AnonymizationResult) -> Vec<DiffSegment> {
+        let text = &result.original_text;
+        let mut entities: Vec<Entity> = result
+            .entities
+            .iter()
+            .filter(|e| e.start < e.end && text.get(e.start..e.end).is_some())
+            .cloned()
+            .collect();
+        entities.sort_by_key(|e| e.start);
+
+        let mut segments = Vec::new();
+        let mut unchanged_start = 0;
+
+        for entity in Self::non_overlapping(&entities) {
+            let original = &text[entity.start..entity.end];
+            let replacement = entity.replacement.as_deref().unwrap_or(&entity.text);
+
+            // Entities left as they were (e.g. preserved types) read as unchanged text
+            if replacement == original {
+                continue;
+            }
+
+            if entity.start > unchanged_start {
+                segments.push(DiffSegment::Unchanged {
+                    text: text[unchanged_start..entity.start].to_string(),
+                });
+            }
+
+            segments.push(DiffSegment::Replaced {
+                original: original.to_string(),
+                replacement: replacement.to_string(),
+                entity_type: entity.entity_type,
+            });
+
+            unchanged_start = entity.end;
+        }
+
+        if unchanged_start < text.len() {
+            segments.push(DiffSegment::Unchanged {
+                text: text[unchanged_start..].to_string(),
+            });
+        }
+
+        segments
+    }
+
+    /// Put original values back in place of replacements found in `text`
+    ///
+    /// Meant for text derived from anonymized output, such as a reply from an
+    /// external model. When several originals share one replacement (linked
+    /// name variants), the longest original is restored. Masked values and
+    /// fixed text are never in the mapping, so they stay as they are.
+    pub fn restore(text: &str, replacements: &[(String, String)]) -> String {
+        let mut originals: HashMap<&str, &str> = HashMap::new();
+        for (original, replacement) in replacements {
+            if replacement.is_empty() || original == replacement {
+                continue;
+            }
+            let entry = originals.entry(replacement.as_str()).or_insert(original.as_str());
+            if original.len() > entry.len() {
+                *entry = original.as_str();
+            }
+        }
+
+        if originals.is_empty() {
+            return text.to_string();
+        }
+
+        // Longest replacements first so one never matches inside another
+        let mut keys: Vec<&str> = originals.keys().copied().collect();
+        keys.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let pattern = keys
+            .iter()
+            .map(|k| regex::escape(k))
+            .collect::<Vec<_>>()
+            .join("|");
+        let re = Regex::new(&pattern).expect("escaped literals always compile");
+
+        re.replace_all(text, |caps: &regex::Captures| originals[&caps[0]].to_string())
+            .into_owned()
+    }
+
+    /// Combine the replacement mappings of two anonymizations, e.g. by two
+    /// teams working on one matter, into one consistent mapping
+    ///
+    /// Originals are compared ignoring case and surrounding whitespace. In the
+    /// result every original has one replacement, and a replacement only
+    /// stands for originals that share it in one of the inputs (linked name
+    /// variants). Each conflict found is reported with how it was resolved.
+    pub fn merge_mappings(
+        a: &[(String, String)],
+        b: &[(String, String)],
+        policy: MergePolicy,
+    ) -> MergedMapping {
+        let mut merged = MappingIndex::default();
+        for (original, replacement) in a {
+            merged.insert(original, replacement);
+        }
+        // Conflicts are with the first mapping only; entries of the second
+        // never conflict with each other
+        let from_a = merged.clone();
+
+        let mut conflicts = Vec::new();
+        match policy {
+            MergePolicy::PreferA => {
+                for (original, replacement) in b {
+                    let key = mapping_key(original);
+                    if let Some(existing) = merged.by_original.get(&key) {
+                        if existing != replacement {
+                            conflicts.push(MappingConflict::SameOriginal {
+                                original: original.clone(),
+                                replacement_a: existing.clone(),
+                                replacement_b: replacement.clone(),
+                                resolved: None,
+                            });
+                            continue;
+                        }
+                    } else if let Some(owner) = from_a.other_owner(replacement, &[key]) {
+                        conflicts.push(MappingConflict::SamePseudonym {
+                            replacement: replacement.clone(),
+                            original_a: owner,
+                            original_b: original.clone(),
+                            resolved: None,
+                        });
+                        continue;
+                    }
+                    merged.insert(original, replacement);
+                }
+            }
+            MergePolicy::Renumber => {
+                // Originals sharing a replacement in `b` are variants of one
+                // original, so they move to a new replacement together
+                let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+                let used_in_b: HashSet<&str> = b.iter().map(|(_, r)| r.as_str()).collect();
+                for (original, replacement) in b {
+                    match groups.iter_mut().find(|(r, _)| r == replacement) {
+                        Some((_, originals)) => originals.push(original),
+                        None => groups.push((replacement, vec![original])),
+                    }
+                }
+
+                for (replacement, originals) in groups {
+                    let keys: Vec<String> = originals.iter().map(|o| mapping_key(o)).collect();
+                    let shared = keys.iter().find_map(|key| merged.by_original.get(key).cloned());
+                    let target = match shared {
+                        Some(existing) => existing,
+                        None => match from_a.other_owner(replacement, &keys) {
+                            Some(owner) => {
+                                // Also clear of `b`, whose later entries keep theirs
+                                let fresh = unused_like(replacement, |candidate| {
+                                    merged.by_replacement.contains_key(candidate)
+                                        || used_in_b.contains(candidate)
+                                });
+                                conflicts.push(MappingConflict::SamePseudonym {
+                                    replacement: replacement.to_string(),
+                                    original_a: owner,
+                                    original_b: originals[0].to_string(),
+                                    resolved: Some(fresh.clone()),
+                                });
+                                fresh
+                            }
+                            None => replacement.to_string(),
+                        },
+                    };
+
+                    for (original, key) in originals.iter().zip(&keys) {
+                        match merged.by_original.get(key) {
+                            Some(existing) if existing != replacement => {
+                                conflicts.push(MappingConflict::SameOriginal {
+                                    original: original.to_string(),
+                                    replacement_a: existing.clone(),
+                                    replacement_b: replacement.to_string(),
+                                    resolved: Some(existing.clone()),
+                                });
+                            }
+                            Some(_) => {}
+                            None => merged.insert(original, &target),
+                        }
+                    }
+                }
+            }
+        }
+
+        MergedMapping {
+            replacements: merged.replacements,
+            conflicts,
+        }
+    }
+}
+
+/// Key under which `merge_mappings` compares originals
+fn mapping_key(original: &str) -> String {
+    original.trim().to_lowercase()
+}
+
+/// A merged replacement mapping under construction
+#[derive(Default)]
+#[derive(Clone)]
+struct MappingIndex {
+    replacements: Vec<(String, String)>,
+    /// Replacement of each original, by mapping key
+    by_original: HashMap<String, String>,
+    /// Originals of each replacement: the first as written, and all keys
+    by_replacement: HashMap<String, (String, Vec<String>)>,
+}
+
+impl MappingIndex {
+    fn insert(&mut self, original: &str, replacement: &str) {
+        let key = mapping_key(original);
+        let pair = (original.to_string(), replacement.to_string());
+        if self.replacements.contains(&pair) {
+            return;
+        }
+        self.replacements.push(pair);
+        self.by_original.entry(key.clone()).or_insert_with(|| replacement.to_string());
+        let (_, keys) = self
+            .by_replacement
+            .entry(replacement.to_string())
+            .or_insert_with(|| (original.to_string(), Vec::new()));
+        keys.push(key);
+    }
+
+    /// First original the merged mapping already replaces with `replacement`,
+    /// if none of them is among `keys`
+    fn other_owner(&self, replacement: &str, keys: &[String]) -> Option<String> {
+        let (first, owners) = self.by_replacement.get(replacement)?;
+        if owners.iter().any(|owner| keys.contains(owner)) {
+            None
+        } else {
+            Some(first.clone())
+        }
+    }
+}
+
+/// First replacement in the style of `replacement` that isn't taken:
+/// "[PERSON-C]" after "[PERSON-A]" and "[PERSON-B]", "[EMAIL-3]", or a
+/// numbered pseudonym such as "Jan Jansen 2"
+fn unused_like(replacement: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    let placeholder =
+        PLACEHOLDER.get_or_init(|| Regex::new(r"^\[(.+)-(\d+|[A-Z]+)\]$").unwrap());
+    let captures = placeholder.captures(replacement);
+    let candidate = |n: usize| match &captures {
+        Some(caps) if caps[2].chars().all(|c| c.is_ascii_digit()) => {
+            format!("[{}-{}]", &caps[1], n)
+        }
+        Some(caps) => format!("[{}-{}]", &caps[1], Anonymizer::to_letter(n)),
+        None => format!("{} {}", replacement, n + 1),
+    };
+    (1..)
+        .map(candidate)
+        .find(|c| !is_taken(c))
+        .expect("unbounded candidates")
+}
+
+/// Byte range of one replacement in the original text and in the anonymized text
+struct ReplacedSpan {
+    original: (usize, usize),
+    output: (usize, usize),
 }
 
 impl Default for Anonymizer {
@@ -232,6 +812,7 @@ impl Default for Anonymizer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pii::dates::DateOrder;
 
     #[test]
     fn test_basic_anonymization() {
@@ -247,6 +828,25 @@ mod tests {
         assert!(!result.entities.is_empty());
     }
 
+    #[test]
+    fn test_restore_replacements() {
+        let replacements = vec![
+            ("John Smith".to_string(), "[PERSON-A]".to_string()),
+            ("Smith".to_string(), "[PERSON-A]".to_string()),
+            ("john@example.com".to_string(), "[EMAIL-1]".to_string()),
+            ("GDPR".to_string(), "GDPR".to_string()),
+        ];
+
+        let restored = Anonymizer::restore(
+            "[PERSON-A] should reply to [EMAIL-1] under GDPR. [PERSON-B] is unknown.",
+            &replacements,
+        );
+        assert_eq!(
+            restored,
+            "John Smith should reply to john@example.com under GDPR. [PERSON-B] is unknown."
+        );
+    }
+
     #[test]
     fn test_consistent_replacement() {
         let mut anonymizer = Anonymizer::new();
@@ -265,6 +865,102 @@ mod tests {
         assert!(count >= 1);
     }
 
+    #[test]
+    fn test_result_entities_have_utf16_offsets() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Réunion 🎉 : écrire à jane@example.com";
+
+        let result = anonymizer.anonymize(text, &AnonymizationSettings::default());
+        let email = result
+            .entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::Email)
+            .unwrap();
+
+        // JavaScript: text.indexOf("jane@example.com") === 22
+        assert_eq!((email.utf16_start, email.utf16_end), (22, 38));
+    }
+
+    #[test]
+    fn test_name_heuristic_can_be_disabled() {
+        let text = "The Quarterly Report was sent to jane@example.com.";
+
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize(text, &AnonymizationSettings::default());
+        assert!(!result.anonymized_text.contains("Quarterly Report"));
+
+        let settings = AnonymizationSettings {
+            use_name_heuristic: false,
+            ..Default::default()
+        };
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize(text, &settings);
+
+        assert!(result.anonymized_text.contains("Quarterly Report"));
+        assert!(!result
+            .entities
+            .iter()
+            .any(|e| e.entity_type == EntityType::Person));
+        // Other detectors are unaffected
+        assert!(!result.anonymized_text.contains("jane@example.com"));
+    }
+
+    #[test]
+    fn test_min_entity_length_keeps_validated_ids() {
+        let text = "Contact Jane Doe about passport NL123456.";
+
+        let settings = AnonymizationSettings {
+            min_entity_length: 9,
+            ..Default::default()
+        };
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize(text, &settings);
+
+        // The 8-character name falls below the threshold, the 8-character ID does not
+        assert!(result.anonymized_text.contains("Jane Doe"));
+        assert!(!result.anonymized_text.contains("NL123456"));
+        assert!(result
+            .entities
+            .iter()
+            .all(|e| e.entity_type != EntityType::Person));
+
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize(text, &AnonymizationSettings::default());
+        assert!(!result.anonymized_text.contains("Jane Doe"));
+    }
+
+    #[test]
+    fn test_short_entities_survive_default_settings() {
+        // Money isn't anonymized by default; everything else is left as is
+        let settings = AnonymizationSettings {
+            entity_types: vec![EntityType::Money],
+            ..Default::default()
+        };
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize("The filing fee is $5.", &settings);
+
+        assert!(result
+            .entities
+            .iter()
+            .any(|e| e.entity_type == EntityType::Money && e.text == "$5"));
+        assert!(!result.anonymized_text.contains("$5"));
+    }
+
+    #[test]
+    fn test_name_heuristic_defaults_on_when_missing() {
+        let settings: AnonymizationSettings = serde_json::from_str(
+            r#"{"entity_types": ["Person"], "confidence_threshold": 0.7,
+                "preserve_legal_references": true, "consistent_replacement": true,
+                "language": "en"}"#,
+        )
+        .unwrap();
+        assert!(settings.use_name_heuristic);
+        assert_eq!(
+            settings.min_entity_length,
+            crate::pii::types::DEFAULT_MIN_ENTITY_LENGTH
+        );
+    }
+
     #[test]
     fn test_legal_reference_preservation() {
         let mut anonymizer = Anonymizer::new();
@@ -280,6 +976,302 @@ mod tests {
         assert!(result.anonymized_text.contains("Article 6 GDPR"));
         // But person names should be anonymized
         assert!(!result.anonymized_text.contains("John Doe"));
+
+        // The reference is reported as recognized and preserved, not dropped
+        let law = result
+            .entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::Law)
+            .expect("legal reference in result");
+        assert!(law.text.contains("Article 6"));
+        assert!(law.preserved);
+        assert_eq!(law.replacement.as_deref(), Some(law.text.as_str()));
+        assert!(result.entities.iter().filter(|e| e.preserved).all(|e| e.entity_type == EntityType::Law));
+        assert_eq!(result.statistics.get(&EntityType::Law), None);
+
+        let json = serde_json::to_value(law).unwrap();
+        assert_eq!(json["preserved"], true);
+    }
+
+    #[test]
+    fn test_legal_references_not_flagged_when_preservation_off() {
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            preserve_legal_references: false,
+            ..Default::default()
+        };
+
+        let result = anonymizer.anonymize("Under Article 6 GDPR, John Doe filed a complaint.", &settings);
+        assert!(result.entities.iter().all(|e| !e.preserved));
+    }
+
+    #[test]
+    fn test_citations_survive_anonymization() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "John Doe relied on Case C-311/18 and [2023] EWCA Civ 123 in his claim.";
+        let settings = AnonymizationSettings::default();
+
+        let result = anonymizer.anonymize(text, &settings);
+
+        assert!(result.anonymized_text.contains("Case C-311/18"));
+        assert!(result.anonymized_text.contains("[2023] EWCA Civ 123"));
+        assert!(!result.anonymized_text.contains("John Doe"));
+    }
+
+    #[test]
+    fn test_per_document_statistics() {
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings::default();
+
+        let first = anonymizer.anonymize(
+            "Email jane@example.com or john@example.com, or call 555-123-4567.",
+            &settings,
+        );
+        assert_eq!(first.statistics.get(&EntityType::Email), Some(&2));
+        assert_eq!(first.statistics.get(&EntityType::Phone), Some(&1));
+
+        // Counts match the placeholders actually substituted in this document
+        let emails_replaced = first.anonymized_text.matches("[EMAIL-").count();
+        assert_eq!(first.statistics[&EntityType::Email], emails_replaced);
+
+        // A second document is counted on its own, while the global counters accumulate
+        let second = anonymizer.anonymize("Reach me at jane@example.com.", &settings);
+        assert_eq!(second.statistics.get(&EntityType::Email), Some(&1));
+        assert_eq!(second.statistics.get(&EntityType::Phone), None);
+        assert_eq!(anonymizer.get_statistics().get(&EntityType::Email), Some(&2));
+    }
+
+    #[test]
+    fn test_statistics_exclude_preserved_entities() {
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            entity_types: vec![EntityType::Law, EntityType::Email],
+            preserve_legal_references: false,
+            ..Default::default()
+        };
+
+        let result = anonymizer.anonymize("Contact jane@example.com under Article 6 GDPR.", &settings);
+
+        assert!(result.entities.iter().any(|e| e.entity_type == EntityType::Law));
+        assert_eq!(result.statistics.get(&EntityType::Law), None);
+        assert_eq!(result.statistics.get(&EntityType::Email), Some(&1));
+    }
+
+    #[test]
+    fn test_review_band_flags_uncertain_entities() {
+        let text = "Jane Roe wrote to jane@example.com from Springfield.";
+        let found = vec![
+            Entity::new(EntityType::Person, "Jane Roe".to_string(), 0, 8, 0.7),
+            Entity::new(EntityType::Email, "jane@example.com".to_string(), 18, 34, 0.95),
+            Entity::new(EntityType::Location, "Springfield".to_string(), 40, 51, 0.5),
+        ];
+        let settings = AnonymizationSettings {
+            review_band: Some((0.6, 0.9)),
+            ..Default::default()
+        };
+
+        let result = Anonymizer::new().anonymize_entities(text, found, &settings);
+
+        assert_eq!(result.anonymized_text, "Jane Roe wrote to [EMAIL-1] from Springfield.");
+        let person = result.entities.iter().find(|e| e.entity_type == EntityType::Person).unwrap();
+        assert!(person.needs_review);
+        assert_eq!(person.replacement.as_deref(), Some("Jane Roe"));
+        assert!(result.entities.iter().all(|e| e.entity_type != EntityType::Location));
+        let email = result.entities.iter().find(|e| e.entity_type == EntityType::Email).unwrap();
+        assert!(!email.needs_review);
+        assert_eq!(result.statistics.get(&EntityType::Person), None);
+
+        let inverted = AnonymizationSettings {
+            review_band: Some((0.9, 0.6)),
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_phone_formats_share_placeholder() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Call 555-123-4567 or (555) 123-4567 today.";
+        let settings = AnonymizationSettings::default();
+
+        let result = anonymizer.anonymize(text, &settings);
+
+        assert_eq!(result.anonymized_text, "Call [PHONE-1] or [PHONE-1] today.");
+
+        // Each entity still points at its own original surface form
+        let phones: Vec<&Entity> = result
+            .entities
+            .iter()
+            .filter(|e| e.entity_type == EntityType::Phone)
+            .collect();
+        assert_eq!(phones.len(), 2);
+        assert_eq!(phones[0].text, "555-123-4567");
+        assert_eq!(phones[1].text, "(555) 123-4567");
+        for phone in phones {
+            assert_eq!(&text[phone.start..phone.end], phone.text);
+        }
+    }
+
+    #[test]
+    fn test_date_formats_share_placeholder() {
+        let text = "Signed 01/02/2024, effective 2024-02-01, due 02/03/2024.";
+
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            language: "nl".to_string(),
+            ..Default::default()
+        };
+        let result = anonymizer.anonymize(text, &settings);
+        assert_eq!(
+            result.anonymized_text,
+            "Signed [DATE-1], effective [DATE-1], due [DATE-2]."
+        );
+        let dates: Vec<&str> = result.entities.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(dates, vec!["01/02/2024", "2024-02-01", "02/03/2024"]);
+
+        // Read month-first, 01/02/2024 is 2 January and no longer matches
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            language: "nl".to_string(),
+            date_order: Some(DateOrder::MonthFirst),
+            ..Default::default()
+        };
+        let result = anonymizer.anonymize(text, &settings);
+        assert_eq!(
+            result.anonymized_text,
+            "Signed [DATE-1], effective [DATE-2], due [DATE-3]."
+        );
+    }
+
+    #[test]
+    fn test_email_case_shares_placeholder() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Write to John.Doe@Example.com, cc john.doe@example.com.";
+        let settings = AnonymizationSettings::default();
+
+        let result = anonymizer.anonymize(text, &settings);
+
+        assert_eq!(result.anonymized_text, "Write to [EMAIL-1], cc [EMAIL-1].");
+    }
+
+    #[test]
+    fn test_normalize_phone() {
+        assert_eq!(Anonymizer::normalize_phone("555.123.4567"), "5551234567");
+        assert_eq!(Anonymizer::normalize_phone("+31 20 123 4567"), "31201234567");
+        assert_eq!(Anonymizer::normalize_phone("0031 20 123 4567"), "31201234567");
+        assert_eq!(Anonymizer::normalize_phone("020 123 4567"), "0201234567");
+    }
+
+    #[test]
+    fn test_french_document_pseudonyms() {
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            language: "fr".to_string(),
+            pseudonymize: true,
+            ..Default::default()
+        };
+
+        let text = "Jean Dupont a rencontré Marie Curie. Jean Dupont a signé.";
+        let result = anonymizer.anonymize(text, &settings);
+
+        let french = PseudonymGenerator::new("fr");
+        let persons: Vec<&Entity> = result
+            .entities
+            .iter()
+            .filter(|e| e.entity_type == EntityType::Person)
+            .collect();
+        assert!(!persons.is_empty());
+
+        for person in &persons {
+            let replacement = person.replacement.as_deref().unwrap();
+            assert!(!replacement.starts_with('['));
+            let expected = (0..MAX_PSEUDONYM_ATTEMPTS)
+                .filter_map(|a| french.pseudonym(EntityType::Person, &person.text.to_lowercase(), a))
+                .any(|p| p == replacement);
+            assert!(expected, "{} is not a French pseudonym", replacement);
+        }
+
+        // Same person, same pseudonym - and the same again in a fresh anonymizer
+        assert_eq!(persons[0].replacement, persons[persons.len() - 1].replacement);
+        let again = Anonymizer::new().anonymize(text, &settings);
+        assert_eq!(result.anonymized_text, again.anonymized_text);
+    }
+
+    fn initials_settings() -> AnonymizationSettings {
+        AnonymizationSettings {
+            person_style: Some(PersonStyle::Initials),
+            ..Default::default()
+        }
+    }
+
+    fn person_replacement(result: &AnonymizationResult, name: &str) -> String {
+        result
+            .entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::Person && e.text == name)
+            .and_then(|e| e.replacement.clone())
+            .unwrap_or_else(|| panic!("{} not detected", name))
+    }
+
+    #[test]
+    fn test_person_initials() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "John Doe met Mary Ann Smith; later John Doe called Mary Ann Smith again.";
+
+        let result = anonymizer.anonymize(text, &initials_settings());
+
+        assert_eq!(person_replacement(&result, "John Doe"), "J.D.");
+        assert_eq!(person_replacement(&result, "Mary Ann Smith"), "M.A.S.");
+        assert_eq!(
+            result.anonymized_text,
+            "J.D. met M.A.S.; later J.D. called M.A.S. again."
+        );
+
+        // Stable across documents and fresh anonymizers
+        let again = Anonymizer::new().anonymize(text, &initials_settings());
+        assert_eq!(result.anonymized_text, again.anonymized_text);
+        let later = anonymizer.anonymize("Mary Ann Smith signed.", &initials_settings());
+        assert_eq!(later.anonymized_text, "M.A.S. signed.");
+    }
+
+    #[test]
+    fn test_person_initials_collisions_and_single_names() {
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize("John Doe and Jane Dale agreed.", &initials_settings());
+
+        assert_eq!(person_replacement(&result, "John Doe"), "J.D.");
+        assert_eq!(person_replacement(&result, "Jane Dale"), "J.D. (2)");
+
+        assert_eq!(Anonymizer::initials("madonna").as_deref(), Some("M."));
+        assert_eq!(Anonymizer::initials("jean-luc picard").as_deref(), Some("J.L.P."));
+        assert_eq!(Anonymizer::initials("  ").as_deref(), None);
+    }
+
+    #[test]
+    fn test_person_style_overrides_pseudonymize() {
+        let text = "John Doe works at Acme Corp.";
+
+        // Without a person style, persons follow `pseudonymize`
+        let settings = AnonymizationSettings {
+            pseudonymize: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.person_style(), PersonStyle::Pseudonym);
+
+        let settings = AnonymizationSettings {
+            pseudonymize: true,
+            person_style: Some(PersonStyle::Bracket),
+            ..Default::default()
+        };
+        let result = Anonymizer::new().anonymize(text, &settings);
+        assert_eq!(person_replacement(&result, "John Doe"), "[PERSON-A]");
+    }
+
+    #[test]
+    fn test_pseudonyms_off_by_default() {
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize("John Doe signed.", &AnonymizationSettings::default());
+        assert!(result.anonymized_text.contains("[PERSON-A]"));
     }
 
     #[test]
@@ -311,6 +1303,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_batch_consistency_scope() {
+        let texts = vec![
+            "Jane Roe met John Doe.".to_string(),
+            "John Doe signed.".to_string(),
+        ];
+
+        // Per batch, John Doe keeps his placeholder in the second file
+        let mut anonymizer = Anonymizer::new();
+        let results = anonymizer.anonymize_batch(texts.clone(), &AnonymizationSettings::default());
+        assert_eq!(results[0].anonymized_text, "[PERSON-A] met [PERSON-B].");
+        assert_eq!(results[1].anonymized_text, "[PERSON-B] signed.");
+
+        // Per document, numbering restarts with each file
+        let settings = AnonymizationSettings {
+            consistency_scope: ConsistencyScope::PerDocument,
+            ..Default::default()
+        };
+        let results = Anonymizer::new().anonymize_batch(texts.clone(), &settings);
+        assert_eq!(results[0].anonymized_text, "[PERSON-A] met [PERSON-B].");
+        assert_eq!(results[1].anonymized_text, "[PERSON-A] signed.");
+
+        // Repeats within one document still share a placeholder
+        let results = Anonymizer::new()
+            .anonymize_batch(vec!["John Doe and John Doe.".to_string()], &settings);
+        assert_eq!(results[0].anonymized_text, "[PERSON-A] and [PERSON-A].");
+
+        // Replacements from earlier requests survive a per-document batch
+        let defaults = AnonymizationSettings::default();
+        let mut anonymizer = Anonymizer::new();
+        anonymizer.anonymize("Mary Major called.", &defaults);
+        let results = anonymizer.anonymize_batch(texts, &settings);
+        assert_eq!(results[1].anonymized_text, "[PERSON-A] signed.");
+        let result = anonymizer.anonymize("Mary Major and John Doe.", &defaults);
+        assert_eq!(result.anonymized_text, "[PERSON-A] and [PERSON-B].");
+    }
+
+    #[test]
+    fn test_verification_catches_missed_variant() {
+        // An upstream detector found the name but missed the email variant
+        let text = "Contact John Smith at john.smith@example.com today.";
+        let found = vec![Entity::new(EntityType::Person, "John Smith".to_string(), 8, 18, 0.95)];
+
+        let report = AnonymizationSettings {
+            verification: Verification::Report,
+            ..Default::default()
+        };
+        let result = Anonymizer::new().anonymize_entities(text, found.clone(), &report);
+        assert_eq!(result.anonymized_text, "Contact [PERSON-A] at john.smith@example.com today.");
+        assert_eq!(result.residual_pii.len(), 1);
+        let residual = &result.residual_pii[0];
+        assert_eq!(residual.entity_type, EntityType::Email);
+        assert_eq!(&result.anonymized_text[residual.start..residual.end], "john.smith@example.com");
+
+        let repass = AnonymizationSettings {
+            verification: Verification::Repass,
+            ..Default::default()
+        };
+        let result = Anonymizer::new().anonymize_entities(text, found.clone(), &repass);
+        assert_eq!(result.anonymized_text, "Contact [PERSON-A] at [EMAIL-1] today.");
+        assert!(result.residual_pii.is_empty());
+        let email = result.entities.iter().find(|e| e.entity_type == EntityType::Email).unwrap();
+        assert_eq!(&text[email.start..email.end], "john.smith@example.com");
+
+        // Without verification nothing is reported
+        let result = Anonymizer::new().anonymize_entities(text, found, &AnonymizationSettings::default());
+        assert!(result.residual_pii.is_empty());
+    }
+
     #[test]
     fn test_entity_linking_variations() {
         let mut anonymizer = Anonymizer::new();
@@ -368,4 +1429,311 @@ mod tests {
             );
         }
     }
+
+    fn assert_segments_cover(result: &AnonymizationResult, segments: &[DiffSegment]) {
+        let original: String = segments.iter().map(|s| s.original()).collect();
+        let anonymized: String = segments.iter().map(|s| s.anonymized()).collect();
+        assert_eq!(original, result.original_text);
+        assert_eq!(anonymized, result.anonymized_text);
+    }
+
+    #[test]
+    fn test_diff_segments_cover_document() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Contact John Smith at john@example.com or 555-123-4567 about Article 5 GDPR.";
+
+        let result = anonymizer.anonymize(text, &AnonymizationSettings::default());
+        let segments = Anonymizer::diff_segments(&result);
+
+        assert_segments_cover(&result, &segments);
+        assert!(segments.iter().any(|s| matches!(
+            s,
+            DiffSegment::Replaced { original, entity_type: EntityType::Email, .. }
+                if original == "john@example.com"
+        )));
+
+        // Neighbouring unchanged text is never split into separate segments
+        assert!(segments.windows(2).all(|pair| !matches!(
+            pair,
+            [DiffSegment::Unchanged { .. }, DiffSegment::Unchanged { .. }]
+        )));
+    }
+
+    #[test]
+    fn test_diff_segments_back_to_back_and_overlapping() {
+        let text = "JohnDoe, hi";
+        let entity = |start: usize, end: usize, replacement: &str| {
+            Entity::new(EntityType::Person, text[start..end].to_string(), start, end, 0.9)
+                .with_replacement(replacement.to_string())
+        };
+        // "John" and "Doe" touch; the third overlaps "Doe" and is dropped
+        let entities = vec![entity(0, 4, "[A]"), entity(4, 7, "[B]"), entity(5, 8, "[C]")];
+
+        let result = AnonymizationResult {
+            original_text: text.to_string(),
+            anonymized_text: "[A][B], hi".to_string(),
+            entities,
+            replacements: Vec::new(),
+            statistics: HashMap::new(),
+            residual_pii: Vec::new(),
+        };
+        let segments = Anonymizer::diff_segments(&result);
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].original(), "John");
+        assert_eq!(segments[1].original(), "Doe");
+        assert_eq!(segments[2], DiffSegment::Unchanged { text: ", hi".to_string() });
+        assert_segments_cover(&result, &segments);
+    }
+
+    #[test]
+    fn test_entity_confidence_thresholds_in_anonymize() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "John Doe wrote to john@example.com.";
+        // Heuristic names without context score ~0.75 and emails ~0.90
+        let settings = AnonymizationSettings {
+            confidence_threshold: 0.9,
+            entity_confidence_thresholds: HashMap::from([
+                (EntityType::Person, 0.8),
+                (EntityType::Email, 0.6),
+            ]),
+            ..Default::default()
+        };
+
+        let result = anonymizer.anonymize(text, &settings);
+        assert_eq!(result.anonymized_text, "John Doe wrote to [EMAIL-1].");
+    }
+
+    #[test]
+    fn test_masking_strategy_per_entity_type() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "John Doe signed on 12/03/2024 with passport NL123456789, see john@example.com.";
+        let settings = AnonymizationSettings {
+            masking_strategies: HashMap::from([
+                (EntityType::Person, MaskingStrategy::Redact),
+                (
+                    EntityType::Identification,
+                    MaskingStrategy::Mask {
+                        masking_char: '*',
+                        chars_to_mask: 7,
+                        from_end: false,
+                    },
+                ),
+                (EntityType::Date, MaskingStrategy::Keep),
+            ]),
+            ..Default::default()
+        };
+
+        let result = anonymizer.anonymize(text, &settings);
+
+        // Unlisted types (email) fall back to the default placeholder
+        assert_eq!(
+            result.anonymized_text,
+            " signed on 12/03/2024 with passport *******6789, see [EMAIL-1]."
+        );
+        assert_eq!(result.statistics.get(&EntityType::Person), Some(&1));
+        assert_eq!(result.statistics.get(&EntityType::Date), None);
+
+        // Redacted and masked values can't be restored; placeholders can
+        let restored = Anonymizer::restore(&result.anonymized_text, &result.replacements);
+        assert!(restored.contains("*******6789"));
+        assert!(restored.contains("john@example.com"));
+        assert!(result.replacements.iter().all(|(original, _)| original != "NL123456789"));
+    }
+
+    #[test]
+    fn test_replacement_templates_per_entity_type() {
+        let mut anonymizer = Anonymizer::new();
+        let settings = AnonymizationSettings {
+            replacement_templates: HashMap::from([
+                (EntityType::Person, "«Party {letter}»".to_string()),
+                (EntityType::Email, "<mail #{index}>".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let text = "John Doe wrote to john@example.com and Jane Roe to jane@example.com.";
+        let result = anonymizer.anonymize(text, &settings);
+        assert_eq!(
+            result.anonymized_text,
+            "«Party A» wrote to <mail #1> and «Party B» to <mail #2>."
+        );
+
+        // Same entities keep their replacements in later documents, new ones continue the sequence
+        let result = anonymizer.anonymize("Write to John Doe at john@example.com, cc Max Moe.", &settings);
+        assert_eq!(
+            result.anonymized_text,
+            "Write to «Party A» at <mail #1>, cc «Party C»."
+        );
+        let restored = Anonymizer::restore(&result.anonymized_text, &result.replacements);
+        assert_eq!(restored, "Write to John Doe at john@example.com, cc Max Moe.");
+    }
+
+    #[test]
+    fn test_default_masking_strategy_and_replace() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Call 555-123-4567 or mail jane@example.com.";
+        let settings = AnonymizationSettings {
+            masking_strategies: HashMap::from([(
+                EntityType::Email,
+                MaskingStrategy::Replace {
+                    new_value: "<email>".to_string(),
+                },
+            )]),
+            default_masking_strategy: MaskingStrategy::Mask {
+                masking_char: '#',
+                chars_to_mask: 4,
+                from_end: true,
+            },
+            ..Default::default()
+        };
+
+        let result = anonymizer.anonymize(text, &settings);
+        assert_eq!(result.anonymized_text, "Call 555-123-#### or mail <email>.");
+
+        // Another address gets the same "<email>", so neither is mapped back
+        let result = anonymizer.anonymize("Mail a@example.com and b@example.com.", &settings);
+        assert_eq!(result.anonymized_text, "Mail <email> and <email>.");
+        assert!(result.replacements.is_empty());
+        let restored = Anonymizer::restore(&result.anonymized_text, &result.replacements);
+        assert_eq!(restored, "Mail <email> and <email>.");
+
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["masking_strategies"]["Email"]["type"], "replace");
+        let parsed: AnonymizationSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.masking_strategy(EntityType::Phone), &settings.default_masking_strategy);
+    }
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(original, replacement)| (original.to_string(), replacement.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_mappings_same_original() {
+        let a = pairs(&[("John Doe", "[PERSON-A]")]);
+        let b = pairs(&[("john doe", "[PERSON-B]"), ("Acme Corp", "[ORGANIZATION-A]")]);
+        let expected = pairs(&[("John Doe", "[PERSON-A]"), ("Acme Corp", "[ORGANIZATION-A]")]);
+
+        for (policy, resolved) in [
+            (MergePolicy::PreferA, None),
+            (MergePolicy::Renumber, Some("[PERSON-A]".to_string())),
+        ] {
+            let merged = Anonymizer::merge_mappings(&a, &b, policy);
+            assert_eq!(merged.replacements, expected, "{:?}", policy);
+            assert_eq!(
+                merged.conflicts,
+                vec![MappingConflict::SameOriginal {
+                    original: "john doe".to_string(),
+                    replacement_a: "[PERSON-A]".to_string(),
+                    replacement_b: "[PERSON-B]".to_string(),
+                    resolved,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_mappings_same_pseudonym() {
+        let a = pairs(&[("John Doe", "[PERSON-A]"), ("Jane Roe", "[PERSON-B]")]);
+        let b = pairs(&[("Mary Major", "[PERSON-A]"), ("Ms. Major", "[PERSON-A]")]);
+
+        // The first mapping wins; both variants from the second are left out
+        let merged = Anonymizer::merge_mappings(&a, &b, MergePolicy::PreferA);
+        assert_eq!(merged.replacements, a);
+        assert_eq!(merged.conflicts.len(), 2);
+        for conflict in &merged.conflicts {
+            let MappingConflict::SamePseudonym { original_a, resolved, .. } = conflict else {
+                panic!("unexpected conflict {:?}", conflict);
+            };
+            assert_eq!((original_a.as_str(), resolved), ("John Doe", &None));
+        }
+
+        // Renumbered: the variants move together to the next free placeholder
+        let merged = Anonymizer::merge_mappings(&a, &b, MergePolicy::Renumber);
+        assert_eq!(
+            merged.replacements,
+            pairs(&[
+                ("John Doe", "[PERSON-A]"),
+                ("Jane Roe", "[PERSON-B]"),
+                ("Mary Major", "[PERSON-C]"),
+                ("Ms. Major", "[PERSON-C]"),
+            ])
+        );
+        assert_eq!(
+            merged.conflicts,
+            vec![MappingConflict::SamePseudonym {
+                replacement: "[PERSON-A]".to_string(),
+                original_a: "John Doe".to_string(),
+                original_b: "Mary Major".to_string(),
+                resolved: Some("[PERSON-C]".to_string()),
+            }]
+        );
+        assert_eq!(
+            Anonymizer::restore("[PERSON-A] met [PERSON-C].", &merged.replacements),
+            "John Doe met Mary Major."
+        );
+
+        // A renumbered entry skips placeholders the second mapping uses later
+        let b = pairs(&[("Mary Major", "[PERSON-A]"), ("Bob Brown", "[PERSON-C]")]);
+        let merged = Anonymizer::merge_mappings(&a, &b, MergePolicy::Renumber);
+        assert_eq!(
+            merged.replacements,
+            pairs(&[
+                ("John Doe", "[PERSON-A]"),
+                ("Jane Roe", "[PERSON-B]"),
+                ("Mary Major", "[PERSON-D]"),
+                ("Bob Brown", "[PERSON-C]"),
+            ])
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+        assert!(matches!(
+            &merged.conflicts[0],
+            MappingConflict::SamePseudonym { original_b, .. } if original_b == "Mary Major"
+        ));
+    }
+
+    #[test]
+    fn test_embedded_data_redacted_or_stripped() {
+        let blob = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+        let text = format!("Photo of John Smith: <img src=\"data:image/png;base64,{}\"> end.", blob);
+        let mut anonymizer = Anonymizer::new();
+
+        let mut settings = AnonymizationSettings::default();
+        settings.entity_types.push(EntityType::TechnicalIdentifier);
+        let redacted = anonymizer.anonymize(&text, &settings);
+        assert!(redacted.anonymized_text.contains("base64,[TECH-ID-1]\">"));
+        let blob_entity = redacted
+            .entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::TechnicalIdentifier)
+            .unwrap();
+        assert_eq!(blob_entity.text, blob);
+
+        settings.embedded_data = EmbeddedData::Strip;
+        let stripped = anonymizer.anonymize(&text, &settings);
+        assert!(stripped.anonymized_text.contains("base64,\"> end."));
+        assert!(!stripped.anonymized_text.contains("TECH-ID"));
+        assert!(stripped.anonymized_text.contains("[PERSON-A]"));
+
+        // Prose, long words included, is left as it is
+        let prose = "The Rechtsschutzversicherungsgesellschaften and the antidisestablishmentarianism debate.";
+        assert_eq!(anonymizer.anonymize(prose, &settings).original_text, prose);
+        settings.embedded_data = EmbeddedData::Redact;
+        assert_eq!(anonymizer.anonymize(prose, &settings).anonymized_text, prose);
+    }
+
+    #[test]
+    fn test_diff_segments_without_entities() {
+        let mut anonymizer = Anonymizer::new();
+        let result = anonymizer.anonymize("nothing to see here", &AnonymizationSettings::default());
+
+        let segments = Anonymizer::diff_segments(&result);
+        assert_eq!(
+            segments,
+            vec![DiffSegment::Unchanged { text: "nothing to see here".to_string() }]
+        );
+    }
 }
@@ -0,0 +1,157 @@
+//! Normalization of detected dates
+//!
+//! The same date is written many ways ("01/02/2024", "2024-02-01",
+//! "February 1, 2024"). Parsing a detected span into a calendar date lets
+//! every spelling share one replacement, while the entity keeps its
+//! original text and offsets. Numeric dates such as "01/02/2024" are
+//! ambiguous, so they are read in the order of the document's locale.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Order of day and month in numeric dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateOrder {
+    /// 01/02/2024 is 1 February (most of Europe, UK)
+    DayFirst,
+    /// 01/02/2024 is 2 January (US)
+    MonthFirst,
+}
+
+impl DateOrder {
+    /// Order used in a locale such as "nl", "en-GB" or "en_US"
+    ///
+    /// US English writes the month first and so does a bare "en", matching
+    /// the US formats the pattern detector looks for; every other locale
+    /// writes the day first.
+    pub fn for_locale(locale: &str) -> Self {
+        let normalized = locale.trim().to_lowercase();
+        let mut subtags = normalized.split(['-', '_', '/']);
+        match (subtags.next(), subtags.next()) {
+            (_, Some("us")) | (Some("en"), None) => Self::MonthFirst,
+            _ => Self::DayFirst,
+        }
+    }
+}
+
+/// English month names and their common abbreviations
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Parse a detected date span into a calendar date
+///
+/// Accepts ISO dates ("2024-02-01", "2024/2/1"), numeric dates with '/',
+/// '-' or '.' ("01/02/2024", "1.2.24") and English month names
+/// ("February 1, 2024", "1 Feb 2024"). Numeric dates are read in `order`;
+/// when that gives no valid date ("13/02/2024" month-first) the other
+/// order is tried. Two-digit years below 70 are taken as 20xx.
+pub fn normalize_date(text: &str, order: DateOrder) -> Option<NaiveDate> {
+    let text = text.trim();
+    let parts: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '/' | '-' | '.' | ','))
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    if let Some(month) = parts.iter().position(|part| month_number(part).is_some()) {
+        let month_value = month_number(parts[month])?;
+        let rest: Vec<&str> = parts
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != month)
+            .map(|(_, part)| *part)
+            .collect();
+        // "February 1, 2024" or "1 February 2024": the year is the four-digit part
+        let (day, year) = if rest[0].len() == 4 { (rest[1], rest[0]) } else { (rest[0], rest[1]) };
+        return NaiveDate::from_ymd_opt(parse_year(year)?, month_value, day.parse().ok()?);
+    }
+
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+
+    if parts[0].len() == 4 {
+        return NaiveDate::from_ymd_opt(numbers[0] as i32, numbers[1], numbers[2]);
+    }
+
+    let year = parse_year(parts[2])?;
+    let (day_first, month_first) = (
+        NaiveDate::from_ymd_opt(year, numbers[1], numbers[0]),
+        NaiveDate::from_ymd_opt(year, numbers[0], numbers[1]),
+    );
+    match order {
+        DateOrder::DayFirst => day_first.or(month_first),
+        DateOrder::MonthFirst => month_first.or(day_first),
+    }
+}
+
+/// Month number for an English month name or abbreviation ("Feb", "february")
+fn month_number(word: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+    if word.len() < 3 || !word.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| word.starts_with(month))
+        .map(|index| index as u32 + 1)
+}
+
+/// Four-digit year, or a two-digit one in 1970-2069
+fn parse_year(text: &str) -> Option<i32> {
+    let year: i32 = text.parse().ok()?;
+    match text.len() {
+        4 => Some(year),
+        2 if year < 70 => Some(2000 + year),
+        2 => Some(1900 + year),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn test_day_first_formats_agree() {
+        let order = DateOrder::for_locale("nl");
+        assert_eq!(order, DateOrder::DayFirst);
+        for text in ["01/02/2024", "2024-02-01", "1.2.24", "1 February 2024", "Feb 1, 2024"] {
+            assert_eq!(normalize_date(text, order), date(2024, 2, 1), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_dates_follow_locale() {
+        assert_eq!(normalize_date("01/02/2024", DateOrder::for_locale("en-US")), date(2024, 1, 2));
+        assert_eq!(normalize_date("01/02/2024", DateOrder::for_locale("en_GB")), date(2024, 2, 1));
+
+        // An impossible reading falls back to the other order
+        assert_eq!(normalize_date("13/02/2024", DateOrder::MonthFirst), date(2024, 2, 13));
+        assert_eq!(normalize_date("02/13/2024", DateOrder::DayFirst), date(2024, 2, 13));
+        assert_eq!(normalize_date("12/05/99", DateOrder::MonthFirst), date(1999, 12, 5));
+    }
+
+    #[test]
+    fn test_unparseable_dates() {
+        for text in ["31/02/2024", "2024-13-01", "Smarch 1, 2024", "01/02", "1/2/345"] {
+            assert_eq!(normalize_date(text, DateOrder::DayFirst), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_locale_defaults() {
+        assert_eq!(DateOrder::for_locale("en"), DateOrder::MonthFirst);
+        assert_eq!(DateOrder::for_locale("de-DE"), DateOrder::DayFirst);
+        assert_eq!(DateOrder::for_locale("fr"), DateOrder::DayFirst);
+    }
+}
@@ -1,19 +1,152 @@
 use regex::Regex;
 use std::collections::HashMap;
 
-use super::types::{Entity, EntityType};
+use super::presidio::mapping::{sentence_bounds, ConfidenceAdjuster};
+use super::types::{DetectionSource, Entity, EntityType};
+
+/// Log-odds added when a checksum confirms a match
+const VALIDATION_BONUS: f64 = 1.5;
+
+/// Log-odds added per context keyword found near a match
+const CONTEXT_BONUS: f64 = 0.4;
+
+/// Context keywords beyond this many don't raise the score further
+const MAX_CONTEXT_HITS: usize = 2;
+
+/// Bytes on each side of a match searched for context keywords
+const CONTEXT_WINDOW: usize = 40;
+
+/// How distinctive a pattern's matches are on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Specificity {
+    /// Shapes that ordinary text also takes: capitalised words, digit runs
+    Loose,
+    /// Structured formats with occasional false positives: dates, SSNs, amounts
+    Moderate,
+    /// Formats that rarely occur by accident: emails, citations, ECLIs
+    Strict,
+}
+
+impl Specificity {
+    /// Prior log-odds that a match is the entity
+    fn prior(self) -> f64 {
+        match self {
+            Self::Loose => 1.1,
+            Self::Moderate => 1.75,
+            Self::Strict => 2.2,
+        }
+    }
+}
+
+/// Confidence of a pattern match
+///
+/// Evidence is summed in log-odds and mapped to a probability with the
+/// logistic function, i.e. a softmax over "entity" and "not an entity":
+///
+/// | Evidence                     | Log-odds | Alone    |
+/// |------------------------------|----------|----------|
+/// | Loose pattern                | 1.10     | ~0.75    |
+/// | Moderate pattern             | 1.75     | ~0.85    |
+/// | Strict pattern               | 2.20     | ~0.90    |
+/// | Checksum passed              | +1.50    |          |
+/// | Context keyword (up to two)  | +0.40    |          |
+///
+/// A Luhn-checked card number (moderate) scores ~0.96 and a mod-97-checked
+/// IBAN (strict) ~0.98, while a capitalised-name guess with no context stays
+/// at ~0.75. Evidence only ever adds up, so no score reaches 1.0.
+fn pattern_confidence(specificity: Specificity, validated: bool, context_hits: usize) -> f64 {
+    let mut logit = specificity.prior();
+    if validated {
+        logit += VALIDATION_BONUS;
+    }
+    logit += CONTEXT_BONUS * context_hits.min(MAX_CONTEXT_HITS) as f64;
+    1.0 / (1.0 + (-logit).exp())
+}
+
+/// Pattern whose matches only count when a checksum confirms them
+struct ValidatedPattern {
+    entity_type: EntityType,
+    regex: Regex,
+    validator: fn(&str) -> bool,
+    specificity: Specificity,
+}
+
+/// Pattern that needs surrounding context to match; only its `value` group
+/// becomes the entity, and `accept` sees the whole match
+struct ContextPattern {
+    entity_type: EntityType,
+    regex: Regex,
+    accept: fn(&str) -> bool,
+    specificity: Specificity,
+}
+
+/// Two-letter US state and territory codes, for ZIP codes like "NY 10001"
+const US_STATES: &[&str] = &[
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN",
+    "IA", "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH",
+    "NJ", "NM", "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT",
+    "VT", "VA", "WA", "WV", "WI", "WY", "PR", "GU", "VI",
+];
+
+/// Letter pairs after four digits that are acronyms rather than Dutch postcodes
+/// ("2016 EU"); SA, SD and SS are never issued as postcodes
+const NOT_DUTCH_POSTCODE_LETTERS: &[&str] =
+    &["SA", "SD", "SS", "EU", "EC", "UK", "US", "UN", "AD", "BC", "AM", "PM"];
+
+/// Numeric amount with comma thousands and optional cents, e.g. "1,234.56"
+const US_AMOUNT: &str = r"\d{1,3}(?:,\d{3})*(?:\.\d{2})?";
+
+/// Numeric amount with either separator style, e.g. "1.234,56"
+const EURO_AMOUNT: &str = r"\d{1,3}(?:[.,]\d{3})*(?:[.,]\d{2})?";
+
+/// Hyphen, en dash or em dash between the two ends of a range
+const RANGE_DASH: &str = "[-–—]";
+
+/// Currency codes and names that make a number an amount of money. A bare
+/// "pounds" is as often a weight, so it needs `MONEY_CONTEXT` instead
+const CURRENCY_WORD: &str = r"USD|EUR|GBP|dollars?|euros?|pounds?\s+sterling";
+
+/// Words just before an amount that say it is money, e.g. "a fine of 500 pounds"
+const MONEY_CONTEXT: &str = concat!(
+    "paid|pays?|paying|owed?|owes|owing|fees?|fined?|costs?|price[ds]?|sum|amount|damages|",
+    "compensation|salary|wages?|rent|worth|charged?|awarded|settlement|penalty"
+);
+
+/// Multipliers following an amount, e.g. "$2 million"
+const SCALE_WORD: &str = "hundred|thousand|million|billion";
+
+/// Numbers written as words, up to ninety-nine
+const NUMBER_WORD: &str = "(?:twenty|thirty|forty|fifty|sixty|seventy|eighty|ninety)(?:-(?:one|two|three|four|five|six|seven|eight|nine))?|one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve|thirteen|fourteen|fifteen|sixteen|seventeen|eighteen|nineteen";
+
+/// Runs of base64 characters, possibly wrapped over several lines as in
+/// PEM certificates, and hex; `is_embedded_data` tells them from words
+const EMBEDDED_DATA: &str = r"[A-Za-z0-9+/]{16,}(?:\r?\n[A-Za-z0-9+/]{16,})*={0,2}";
+
+/// Characters, without line breaks and padding, from which a run counts as
+/// embedded data
+const MIN_EMBEDDED_DATA_LEN: usize = 64;
 
 /// PII Detector using pattern-based recognition (Layer 1)
 pub struct PIIDetector {
-    patterns: HashMap<EntityType, Vec<Regex>>,
+    patterns: HashMap<EntityType, Vec<(Regex, Specificity)>>,
+    validated_patterns: Vec<ValidatedPattern>,
+    context_patterns: Vec<ContextPattern>,
     legal_whitelist: Vec<Regex>,
+    /// Keywords that make a nearby match more likely to be the entity
+    context: ConfidenceAdjuster,
+    /// Candidate runs for `strip_embedded_data`
+    embedded_data: Regex,
 }
 
 impl PIIDetector {
     pub fn new() -> Self {
         let mut detector = Self {
             patterns: HashMap::new(),
+            validated_patterns: Vec::new(),
+            context_patterns: Vec::new(),
             legal_whitelist: Vec::new(),
+            context: ConfidenceAdjuster::new(),
+            embedded_data: Regex::new(EMBEDDED_DATA).unwrap(),
         };
 
         detector.initialize_patterns();
@@ -21,89 +154,260 @@ impl PIIDetector {
         detector
     }
 
+    /// `text` without embedded data blobs, so detection doesn't spend time on them
+    pub fn strip_embedded_data(&self, text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        let mut last_end = 0;
+        for blob in self
+            .embedded_data
+            .find_iter(text)
+            .filter(|m| is_embedded_data(m.as_str()))
+        {
+            stripped.push_str(&text[last_end..blob.start()]);
+            last_end = blob.end();
+        }
+        stripped.push_str(&text[last_end..]);
+        stripped
+    }
+
     fn initialize_patterns(&mut self) {
         // Email patterns
         self.add_pattern(
             EntityType::Email,
             r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b",
+            Specificity::Strict,
         );
 
         // Phone patterns (various formats)
-        self.add_pattern(EntityType::Phone, r"\b\+?[\d\s\-\(\)]{10,}\b");
-        self.add_pattern(EntityType::Phone, r"\b\d{3}[-.\s]?\d{3}[-.\s]?\d{4}\b");
-        self.add_pattern(EntityType::Phone, r"\b\(\d{3}\)\s?\d{3}[-.\s]?\d{4}\b");
+        self.add_pattern(EntityType::Phone, r"\b\+?[\d\s\-\(\)]{10,}\b", Specificity::Loose);
+        self.add_pattern(EntityType::Phone, r"\b\d{3}[-.\s]?\d{3}[-.\s]?\d{4}\b", Specificity::Moderate);
+        self.add_pattern(EntityType::Phone, r"\b\(\d{3}\)\s?\d{3}[-.\s]?\d{4}\b", Specificity::Moderate);
 
         // US Social Security Numbers
         self.add_pattern(
             EntityType::Identification,
             r"\b\d{3}-\d{2}-\d{4}\b",
+            Specificity::Moderate,
         );
 
         // European-style identification numbers
         self.add_pattern(
             EntityType::Identification,
             r"\b[A-Z]{2}\d{6,12}\b",
+            Specificity::Loose,
+        );
+
+        // Payment card numbers (13-19 digits, optionally grouped), Luhn-checked
+        self.add_validated_pattern(
+            EntityType::Identification,
+            r"\b\d(?:[ -]?\d){12,18}\b",
+            luhn_valid,
+            Specificity::Moderate,
         );
 
-        // Money patterns
-        self.add_pattern(EntityType::Money, r"\$\s?\d{1,3}(?:,\d{3})*(?:\.\d{2})?");
-        self.add_pattern(EntityType::Money, r"€\s?\d{1,3}(?:[.,]\d{3})*(?:[.,]\d{2})?");
-        self.add_pattern(EntityType::Money, r"£\s?\d{1,3}(?:,\d{3})*(?:\.\d{2})?");
+        // IBANs, e.g. "NL91 ABNA 0417 1643 00", mod-97-checked
+        self.add_validated_pattern(
+            EntityType::Identification,
+            r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+            iban_valid,
+            Specificity::Strict,
+        );
+
+        // Money patterns. A range such as "$10,000–$15,000" or "10,000-15,000 EUR"
+        // is one entity; the second amount may leave out the currency symbol.
+        for (symbol, amount) in [
+            ("\\$", US_AMOUNT),
+            ("€", EURO_AMOUNT),
+            ("£", US_AMOUNT),
+        ] {
+            self.add_pattern(
+                EntityType::Money,
+                &format!(
+                    r"{symbol}\s?{amount}(?:\s?{RANGE_DASH}\s?(?:{symbol}\s?)?{amount})?(?:\s?(?:{SCALE_WORD})\b)?"
+                ),
+                Specificity::Strict,
+            );
+        }
+        self.add_pattern(
+            EntityType::Money,
+            &format!(
+                r"(?i)\b{US_AMOUNT}(?:\s?{RANGE_DASH}\s?{US_AMOUNT})?(?:\s(?:{SCALE_WORD}))?\s?(?:{CURRENCY_WORD})\b"
+            ),
+            Specificity::Moderate,
+        );
+        // Pounds without "sterling" or a symbol, only after a money word,
+        // e.g. "a fine of 500 pounds" but not "5 pounds of flour"
+        self.add_context_pattern(
+            EntityType::Money,
+            &format!(
+                r"(?i)\b(?:{MONEY_CONTEXT})(?:\s+(?:of|is|was|were|a|an|the))*\s+(?P<value>(?:{US_AMOUNT}|{NUMBER_WORD}|a\s+(?:{SCALE_WORD}))(?:(?:\s+and\s+|\s+|-)(?:{NUMBER_WORD}|{SCALE_WORD}))*\s?pounds?)\b"
+            ),
+            |_| true,
+            Specificity::Moderate,
+        );
+        // Amounts written out, e.g. "two million euros", "a hundred dollars";
+        // the currency word is required so plain numerals never match
         self.add_pattern(
             EntityType::Money,
-            r"\b\d{1,3}(?:,\d{3})*(?:\.\d{2})?\s?(?:USD|EUR|GBP)\b",
+            &format!(
+                r"(?i)\b(?:{NUMBER_WORD}|a\s+(?:{SCALE_WORD}))(?:(?:\s+and\s+|\s+|-)(?:{NUMBER_WORD}|{SCALE_WORD}))*\s+(?:{CURRENCY_WORD})\b"
+            ),
+            Specificity::Moderate,
         );
 
         // Date patterns
         self.add_pattern(
             EntityType::Date,
             r"\b\d{1,2}[-/]\d{1,2}[-/]\d{2,4}\b",
+            Specificity::Moderate,
         );
         self.add_pattern(
             EntityType::Date,
             r"\b\d{4}[-/]\d{1,2}[-/]\d{1,2}\b",
+            Specificity::Moderate,
         );
         self.add_pattern(
             EntityType::Date,
             r"\b(?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)[a-z]*\s+\d{1,2},?\s+\d{4}\b",
+            Specificity::Strict,
         );
 
         // Case numbers
         self.add_pattern(
             EntityType::Case,
             r"\b(?:Case|Docket|File)\s+(?:No\.?|Number|#)\s*:?\s*\d+[-/]?\d*\b",
+            Specificity::Strict,
+        );
+        self.add_pattern(EntityType::Case, r"\b\d{2}-[A-Z]{2,4}-\d{4,}\b", Specificity::Moderate);
+        // National ECLIs, e.g. "ECLI:NL:HR:2023:123", identify one decision;
+        // those of the EU courts are citations like the CJEU case numbers below
+        self.add_context_pattern(
+            EntityType::Case,
+            r"\b(?P<value>ECLI:[A-Z]{2}:[A-Z0-9]+:\d{4}:[A-Za-z0-9]+(?:\.[A-Za-z0-9]+)*)",
+            |ecli| !ecli.starts_with("ECLI:EU:"),
+            Specificity::Strict,
+        );
+        // German Aktenzeichen, e.g. "Az. 1 BvR 123/45", "VI ZR 12/21"
+        self.add_pattern(
+            EntityType::Case,
+            r"(?:\b(?:Az\.|Aktenzeichen|Gz\.|Geschäftszeichen)\s*:?\s*)?\b(?:[1-9]\d?|[IVX]{1,4}[a-z]?)\s+[A-Z][A-Za-z]{0,4}\s+\d{1,5}/\d{2}\b",
+            Specificity::Moderate,
+        );
+        // French dockets: Cour de cassation appeals, e.g. "pourvoi n° 21-12.345",
+        // and court registry numbers, e.g. "RG n° 20/01234"
+        self.add_pattern(
+            EntityType::Case,
+            r"\b(?:[Pp]ourvoi\s+)?n°\s*\d{2}-\d{2}\.\d{3}\b",
+            Specificity::Strict,
+        );
+        self.add_pattern(
+            EntityType::Case,
+            r"\b(?:N°\s*)?RG\s*(?:n°\s*)?:?\s*\d{2}/\d{4,5}\b",
+            Specificity::Strict,
         );
-        self.add_pattern(EntityType::Case, r"\b\d{2}-[A-Z]{2,4}-\d{4,}\b");
 
         // Legal references (to preserve, not anonymize)
         self.add_pattern(
             EntityType::Law,
             r"\b(?:Article|Section|§)\s+\d+(?:\(\d+\))?(?:\s+[A-Z][A-Za-z\s]+)?",
+            Specificity::Moderate,
+        );
+        self.add_pattern(EntityType::Law, r"\b\d+\s+U\.S\.C\.?\s+§?\s*\d+\b", Specificity::Strict);
+        self.add_pattern(EntityType::Law, r"\bGDPR\b", Specificity::Strict);
+        self.add_pattern(EntityType::Law, r"\b(?:Act|Code|Regulation)\s+\d+\b", Specificity::Loose);
+
+        // UK neutral citations, e.g. "[2023] EWCA Civ 123", "[2019] EWHC 1234 (Ch)"
+        self.add_pattern(
+            EntityType::Law,
+            r"\[\d{4}\]\s+(?:UKSC|UKPC|UKHL|EWCA\s+(?:Civ|Crim)|EWHC|EWFC|EWCOP|UKUT|UKFTT|UKEAT|CSIH|CSOH|NICA|NIQB)\s+\d+(?:\s+\((?:Ch|QB|KB|Fam|Admin|Comm|TCC|Pat|IPEC|Costs)\))?",
+            Specificity::Strict,
+        );
+        // UK law report citations, e.g. "[1932] AC 562", "[2020] 1 WLR 123"
+        self.add_pattern(
+            EntityType::Law,
+            r"\[\d{4}\]\s+(?:\d\s+)?(?:AC|QB|KB|Ch|Fam|WLR|All\s+ER|Lloyd's\s+Rep)\s+\d+",
+            Specificity::Strict,
+        );
+        // CJEU / General Court case numbers, e.g. "C-311/18", "T-201/04 P"
+        self.add_pattern(EntityType::Law, r"\b[CT]-\d{1,4}/\d{2}(?:\s+P\b)?", Specificity::Moderate);
+        // European Case Law Identifiers of the EU courts, e.g. "ECLI:EU:C:2020:559"
+        self.add_pattern(
+            EntityType::Law,
+            r"\bECLI:EU:[A-Z0-9]+:\d{4}:[A-Za-z0-9]+(?:\.[A-Za-z0-9]+)*",
+            Specificity::Strict,
+        );
+        // EU legislation, e.g. "Regulation (EU) 2016/679", "Directive 95/46/EC"
+        self.add_pattern(
+            EntityType::Law,
+            r"\b(?:Regulation|Directive|Decision)\s+(?:\((?:EU|EC|EEC|Euratom)\)\s+)?(?:No\.?\s+)?\d{1,4}/\d{1,4}(?:/(?:EU|EC|EEC))?\b",
+            Specificity::Strict,
+        );
+
+        // Postal codes. Formats with letters are distinctive on their own;
+        // all-digit codes only count in an address context.
+        // UK, e.g. "SW1A 1AA", "M1 1AE"
+        self.add_context_pattern(
+            EntityType::Location,
+            r"\b(?P<value>GIR ?0AA|[A-Z]{1,2}\d[A-Z\d]? ?\d[A-Z]{2})\b",
+            |_| true,
+            Specificity::Strict,
+        );
+        // Netherlands, e.g. "1012 AB"
+        self.add_context_pattern(
+            EntityType::Location,
+            r"\b(?P<value>[1-9]\d{3} ?[A-Z]{2})\b",
+            is_dutch_postcode,
+            Specificity::Moderate,
+        );
+        // US ZIP after a state code, e.g. "Springfield, IL 62704-1234"
+        self.add_context_pattern(
+            EntityType::Location,
+            r"\b[A-Z]{2}\s+(?P<value>\d{5}(?:-\d{4})?)\b",
+            |m| US_STATES.contains(&&m[..2]),
+            Specificity::Moderate,
+        );
+        // Germany: five digits after a house number or at the start of an
+        // address line, followed by the city, e.g. "Musterstraße 1, 10115 Berlin"
+        self.add_context_pattern(
+            EntityType::Location,
+            r"(?m)(?:^|\d[a-z]?,)[ \t]*(?P<value>(?:D-)?\d{5})[ \t]+[A-ZÄÖÜ][a-zäöüß]+",
+            |_| true,
+            Specificity::Moderate,
         );
-        self.add_pattern(EntityType::Law, r"\b\d+\s+U\.S\.C\.?\s+§?\s*\d+\b");
-        self.add_pattern(EntityType::Law, r"\bGDPR\b");
-        self.add_pattern(EntityType::Law, r"\b(?:Act|Code|Regulation)\s+\d+\b");
 
         // IP addresses
         self.add_pattern(
             EntityType::TechnicalIdentifier,
             r"\b\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}\b",
+            Specificity::Moderate,
+        );
+
+        // Embedded data such as base64 images or certificates, which can
+        // hide PII; the whole blob outranks anything detected inside it
+        self.add_validated_pattern(
+            EntityType::TechnicalIdentifier,
+            EMBEDDED_DATA,
+            is_embedded_data,
+            Specificity::Strict,
         );
 
         // Person names (basic patterns - title + name)
         self.add_pattern(
             EntityType::Person,
             r"\b(?:Mr\.|Mrs\.|Ms\.|Dr\.|Prof\.)\s+[A-Z][a-z]+(?:\s+[A-Z][a-z]+)*\b",
+            Specificity::Moderate,
         );
 
         // Organizations (common suffixes)
         self.add_pattern(
             EntityType::Organization,
             r"\b[A-Z][A-Za-z\s&]+(?:Inc\.|LLC|Ltd\.|Corp\.|Corporation|Company|Co\.)\b",
+            Specificity::Moderate,
         );
         self.add_pattern(
             EntityType::Organization,
             r"\b(?:Court of|Supreme Court|District Court|Circuit Court)\s+[A-Za-z\s]+\b",
+            Specificity::Moderate,
         );
     }
 
@@ -125,47 +429,239 @@ impl PIIDetector {
         }
     }
 
-    fn add_pattern(&mut self, entity_type: EntityType, pattern: &str) {
+    fn add_pattern(&mut self, entity_type: EntityType, pattern: &str, specificity: Specificity) {
         if let Ok(regex) = Regex::new(pattern) {
             self.patterns
                 .entry(entity_type)
                 .or_insert_with(Vec::new)
-                .push(regex);
+                .push((regex, specificity));
+        }
+    }
+
+    fn add_validated_pattern(
+        &mut self,
+        entity_type: EntityType,
+        pattern: &str,
+        validator: fn(&str) -> bool,
+        specificity: Specificity,
+    ) {
+        if let Ok(regex) = Regex::new(pattern) {
+            self.validated_patterns.push(ValidatedPattern {
+                entity_type,
+                regex,
+                validator,
+                specificity,
+            });
         }
     }
 
+    fn add_context_pattern(
+        &mut self,
+        entity_type: EntityType,
+        pattern: &str,
+        accept: fn(&str) -> bool,
+        specificity: Specificity,
+    ) {
+        if let Ok(regex) = Regex::new(pattern) {
+            self.context_patterns.push(ContextPattern {
+                entity_type,
+                regex,
+                accept,
+                specificity,
+            });
+        }
+    }
+
+    /// Score a match at `start..end` from its pattern, checksum and the
+    /// keywords around it; see `pattern_confidence`
+    fn score(
+        &self,
+        entity_type: EntityType,
+        specificity: Specificity,
+        validated: bool,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> f64 {
+        let window_start = floor_char_boundary(text, start.saturating_sub(CONTEXT_WINDOW));
+        let window_end = ceil_char_boundary(text, (end + CONTEXT_WINDOW).min(text.len()));
+        // Keywords count only within the match's own sentence
+        let window = &text[window_start..window_end];
+        let (from, to) = sentence_bounds(window, start - window_start, end - window_start);
+        let before = (window_start + from).min(start);
+        let after = (window_start + to).max(end);
+        // The match itself is left out, so a title or suffix that is part of
+        // the pattern doesn't count twice
+        let surrounding = format!("{} {}", &text[before..start], &text[end..after]);
+        let hits = self.context.keyword_hits(entity_type, &surrounding);
+        pattern_confidence(specificity, validated, hits)
+    }
+
     /// Detect entities in text
     pub fn detect(&self, text: &str) -> Vec<Entity> {
         let mut entities = Vec::new();
 
         for (entity_type, regexes) in &self.patterns {
-            for regex in regexes {
+            for (regex, specificity) in regexes {
                 for cap in regex.find_iter(text) {
-                    let matched_text = cap.as_str().to_string();
-                    let start = cap.start();
-                    let end = cap.end();
+                    // Some patterns (e.g. phone numbers) can capture surrounding whitespace
+                    let raw = cap.as_str();
+                    let matched_text = raw.trim().to_string();
+                    if matched_text.is_empty() {
+                        continue;
+                    }
+                    let start = cap.start() + (raw.len() - raw.trim_start().len());
+                    let end = start + matched_text.len();
 
                     // Check if this match is in the legal whitelist
                     if *entity_type != EntityType::Law && self.is_whitelisted(&matched_text) {
                         continue;
                     }
 
-                    entities.push(Entity::new(
-                        *entity_type,
-                        matched_text,
-                        start,
-                        end,
-                        0.85, // Pattern-based detection confidence
-                    ));
+                    let confidence = self.score(*entity_type, *specificity, false, text, start, end);
+                    entities.push(
+                        Entity::new(*entity_type, matched_text, start, end, confidence)
+                            .with_source(DetectionSource::Pattern),
+                    );
                 }
             }
         }
 
+        for pattern in &self.validated_patterns {
+            for cap in pattern.regex.find_iter(text) {
+                if !(pattern.validator)(cap.as_str()) {
+                    continue;
+                }
+
+                let confidence = self.score(
+                    pattern.entity_type,
+                    pattern.specificity,
+                    true,
+                    text,
+                    cap.start(),
+                    cap.end(),
+                );
+                let mut entity = Entity::new(
+                    pattern.entity_type,
+                    cap.as_str().to_string(),
+                    cap.start(),
+                    cap.end(),
+                    confidence,
+                )
+                .with_source(DetectionSource::Pattern);
+                entity.validated = true;
+                entities.push(entity);
+            }
+        }
+
+        for pattern in &self.context_patterns {
+            for caps in pattern.regex.captures_iter(text) {
+                let (Some(whole), Some(value)) = (caps.get(0), caps.name("value")) else {
+                    continue;
+                };
+                if !(pattern.accept)(whole.as_str().trim_start_matches([',', ' ', '\t', '\n'])) {
+                    continue;
+                }
+
+                let confidence = self.score(
+                    pattern.entity_type,
+                    pattern.specificity,
+                    false,
+                    text,
+                    value.start(),
+                    value.end(),
+                );
+                entities.push(
+                    Entity::new(
+                        pattern.entity_type,
+                        value.as_str().to_string(),
+                        value.start(),
+                        value.end(),
+                        confidence,
+                    )
+                    .with_source(DetectionSource::Pattern),
+                );
+            }
+        }
+
         // Sort by position
         entities.sort_by_key(|e| e.start);
 
         // Remove overlapping entities (keep the longer/more specific one)
-        self.remove_overlaps(entities)
+        let entities = self.remove_overlaps(entities);
+        merge_adjacent_locations(entities, text)
+    }
+
+    /// Entity types the pattern tables can produce, in `EntityType::ALL` order
+    pub fn entity_types(&self) -> Vec<EntityType> {
+        EntityType::ALL
+            .into_iter()
+            .filter(|entity_type| {
+                self.patterns.contains_key(entity_type)
+                    || self.validated_patterns.iter().any(|p| p.entity_type == *entity_type)
+                    || self.context_patterns.iter().any(|p| p.entity_type == *entity_type)
+            })
+            .collect()
+    }
+
+    /// Count pattern matches per entity type without building entities
+    ///
+    /// Applies the same whitelist, validators and context checks as `detect`.
+    /// Overlapping matches of one type count once, but there is no overlap
+    /// resolution across types, so a span matching two types counts for both.
+    pub fn count_matches(&self, text: &str) -> HashMap<EntityType, usize> {
+        let mut spans: HashMap<EntityType, Vec<(usize, usize)>> = HashMap::new();
+
+        for (entity_type, regexes) in &self.patterns {
+            for (regex, _) in regexes {
+                for m in regex.find_iter(text) {
+                    let matched = m.as_str().trim();
+                    if matched.is_empty()
+                        || (*entity_type != EntityType::Law && self.is_whitelisted(matched))
+                    {
+                        continue;
+                    }
+                    spans.entry(*entity_type).or_default().push((m.start(), m.end()));
+                }
+            }
+        }
+
+        for pattern in &self.validated_patterns {
+            for m in pattern.regex.find_iter(text) {
+                if (pattern.validator)(m.as_str()) {
+                    spans.entry(pattern.entity_type).or_default().push((m.start(), m.end()));
+                }
+            }
+        }
+
+        for pattern in &self.context_patterns {
+            for caps in pattern.regex.captures_iter(text) {
+                let Some(value) = caps.name("value") else {
+                    continue;
+                };
+                if (pattern.accept)(caps[0].trim_start_matches([',', ' ', '\t', '\n'])) {
+                    spans.entry(pattern.entity_type).or_default().push((value.start(), value.end()));
+                }
+            }
+        }
+
+        spans
+            .into_iter()
+            .map(|(entity_type, mut spans)| {
+                spans.sort_unstable();
+                let mut count = 0;
+                let mut covered_until = 0;
+                for (start, end) in spans {
+                    if count == 0 || start >= covered_until {
+                        count += 1;
+                        covered_until = end;
+                    } else {
+                        covered_until = covered_until.max(end);
+                    }
+                }
+                (entity_type, count)
+            })
+            .collect()
     }
 
     fn is_whitelisted(&self, text: &str) -> bool {
@@ -184,13 +680,16 @@ impl PIIDetector {
             if entity.start >= last_end {
                 last_end = entity.end;
                 result.push(entity);
-            } else if entity.end > last_end {
-                // Overlapping - keep the longer one
-                if let Some(last) = result.last_mut() {
-                    if entity.text.len() > last.text.len() {
-                        *last = entity.clone();
-                        last_end = entity.end;
-                    }
+            } else if let Some(last) = result.last_mut() {
+                // Overlapping - keep the longer one, or a checksum-validated
+                // or higher-scoring match over one of at most the same length
+                let longer = entity.end > last_end && entity.text.len() > last.text.len();
+                let at_least_as_long = entity.text.len() >= last.text.len();
+                let validated = entity.validated && !last.validated && at_least_as_long;
+                let stronger = entity.confidence > last.confidence && at_least_as_long;
+                if longer || validated || stronger {
+                    last_end = entity.end;
+                    *last = entity;
                 }
             }
         }
@@ -273,19 +772,171 @@ impl PIIDetector {
                 start += stripped_prefix.len() + 1; // +1 for the space after
             }
 
-            entities.push(Entity::new(
-                EntityType::Person,
-                name,
-                start,
-                start + name_words.iter().map(|w| w.len()).sum::<usize>() + name_words.len() - 1,
-                0.75, // Lower confidence for name detection
-            ));
+            // Capitalised words alone are weak evidence; titles or roles
+            // nearby ("Defendant", "counsel") raise the score
+            let end = start + name_words.iter().map(|w| w.len()).sum::<usize>() + name_words.len() - 1;
+            let confidence = self.score(EntityType::Person, Specificity::Loose, false, text, start, end);
+            entities.push(
+                Entity::new(EntityType::Person, name, start, end, confidence)
+                    .with_source(DetectionSource::Pattern),
+            );
         }
 
         entities
     }
 }
 
+/// Whether a "1234 AB" match can be a Dutch postcode
+fn is_dutch_postcode(candidate: &str) -> bool {
+    let letters = &candidate[candidate.len() - 2..];
+    !NOT_DUTCH_POSTCODE_LETTERS.contains(&letters)
+}
+
+/// Join locations separated only by spaces or a comma into one span
+///
+/// Keeps an address such as "Amsterdam, 1012 AB" together so it is replaced
+/// as a whole rather than piece by piece.
+pub fn merge_adjacent_locations(mut entities: Vec<Entity>, text: &str) -> Vec<Entity> {
+    entities.sort_by_key(|e| e.start);
+
+    let mut merged: Vec<Entity> = Vec::with_capacity(entities.len());
+    for entity in entities {
+        if let Some(last) = merged.last_mut() {
+            let joinable = last.entity_type == EntityType::Location
+                && entity.entity_type == EntityType::Location
+                && last.end <= entity.start
+                && text
+                    .get(last.end..entity.start)
+                    .map(|gap| gap.len() <= 3 && gap.chars().all(|c| c == ',' || c == ' '))
+                    .unwrap_or(false);
+
+            if joinable {
+                last.end = entity.end;
+                last.text = text[last.start..last.end].to_string();
+                last.confidence = last.confidence.max(entity.confidence);
+                if last.source != entity.source {
+                    last.source = None;
+                }
+                continue;
+            }
+        }
+        merged.push(entity);
+    }
+
+    merged
+}
+
+/// Largest char boundary at or before `index`
+pub(crate) fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Smallest char boundary at or after `index`
+pub(crate) fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Whether a run of base64 or hex characters is encoded data rather than a
+/// long word or a file path: data mixes digits with letters, and base64 mixes
+/// letter cases
+fn is_embedded_data(candidate: &str) -> bool {
+    let compact: Vec<char> = candidate
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect();
+    if compact.len() < MIN_EMBEDDED_DATA_LEN
+        || !compact.iter().any(char::is_ascii_digit)
+        || is_path(candidate)
+    {
+        return false;
+    }
+
+    let hex = compact.iter().all(char::is_ascii_hexdigit);
+    let mixed_case = compact.iter().any(char::is_ascii_uppercase)
+        && compact.iter().any(char::is_ascii_lowercase);
+    hex || mixed_case
+}
+
+/// Whether a run is a path such as "/Users/JohnSmith/Documents/Draft3":
+/// every "/"-separated segment reads as a word, possibly numbered
+fn is_path(candidate: &str) -> bool {
+    candidate.contains('/')
+        && candidate
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .all(is_word_like)
+}
+
+/// Lowercase, capitalized, CamelCase or all-caps letters followed by at most
+/// four digits; random base64 mixes cases and digits throughout
+fn is_word_like(segment: &str) -> bool {
+    let letters = segment.trim_end_matches(|c: char| c.is_ascii_digit());
+    if letters.is_empty()
+        || segment.len() - letters.len() > 4
+        || !letters.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return false;
+    }
+    if letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return true;
+    }
+    // Every capital starts a lowercase word
+    let bytes = letters.as_bytes();
+    bytes.iter().enumerate().all(|(i, b)| {
+        !b.is_ascii_uppercase() || bytes.get(i + 1).is_some_and(u8::is_ascii_lowercase)
+    })
+}
+
+/// ISO 13616 IBAN check: the rearranged number, with letters as 10-35,
+/// must leave remainder 1 modulo 97; spaces are ignored
+fn iban_valid(candidate: &str) -> bool {
+    let compact: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+
+    let (head, tail) = compact.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// Luhn checksum used by payment card numbers; separators are ignored
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
 impl Default for PIIDetector {
     fn default() -> Self {
         Self::new()
@@ -329,6 +980,247 @@ mod tests {
         assert!(entities.iter().any(|e| e.entity_type == EntityType::Law));
     }
 
+    #[test]
+    fn test_citations_are_law() {
+        let detector = PIIDetector::new();
+        let text = "See [2023] EWCA Civ 123, [2019] EWHC 1234 (Ch), [1932] AC 562, \
+                    Case C-311/18, ECLI:EU:C:2020:559 and Regulation (EU) 2016/679.";
+        let entities = detector.detect(text);
+
+        for citation in [
+            "[2023] EWCA Civ 123",
+            "[2019] EWHC 1234 (Ch)",
+            "[1932] AC 562",
+            "C-311/18",
+            "ECLI:EU:C:2020:559",
+            "Regulation (EU) 2016/679",
+        ] {
+            let entity = entities
+                .iter()
+                .find(|e| e.text == citation)
+                .unwrap_or_else(|| panic!("{} not detected: {:?}", citation, entities));
+            assert_eq!(entity.entity_type, EntityType::Law, "{}", citation);
+        }
+    }
+
+    #[test]
+    fn test_jurisdiction_case_numbers() {
+        let detector = PIIDetector::new();
+        let text = "Vgl. BVerfG, Az. 1 BvR 123/45, sowie ECLI:NL:HR:2023:123. \
+                    Cass. civ., pourvoi n° 21-12.345; CA Paris, RG n° 20/01234. \
+                    Contra ECLI:EU:C:2020:559.";
+        let entities = detector.detect(text);
+        let typed = |value: &str| {
+            entities
+                .iter()
+                .find(|e| e.text == value)
+                .unwrap_or_else(|| panic!("{} not detected: {:?}", value, entities))
+                .entity_type
+        };
+
+        assert_eq!(typed("Az. 1 BvR 123/45"), EntityType::Case);
+        assert_eq!(typed("ECLI:NL:HR:2023:123"), EntityType::Case);
+        assert_eq!(typed("pourvoi n° 21-12.345"), EntityType::Case);
+        assert_eq!(typed("RG n° 20/01234"), EntityType::Case);
+        // EU-court identifiers stay citations
+        assert_eq!(typed("ECLI:EU:C:2020:559"), EntityType::Law);
+        assert!(!entities
+            .iter()
+            .any(|e| e.entity_type == EntityType::Case && e.text.contains("ECLI:EU")));
+
+        // Without the prefix the Aktenzeichen itself is found
+        let entities = detector.detect("Das Urteil VI ZR 12/21 ist rechtskräftig.");
+        assert!(entities
+            .iter()
+            .any(|e| e.entity_type == EntityType::Case && e.text == "VI ZR 12/21"));
+    }
+
+    #[test]
+    fn test_card_numbers_are_luhn_validated() {
+        let detector = PIIDetector::new();
+
+        let entities = detector.detect("Card on file: 4111 1111 1111 1111, thanks.");
+        let card = entities
+            .iter()
+            .find(|e| e.text == "4111 1111 1111 1111")
+            .expect("valid card number should be detected");
+        assert_eq!(card.entity_type, EntityType::Identification);
+        assert!(card.validated);
+        assert_eq!(card.source, Some(DetectionSource::Pattern));
+
+        // Fails the checksum: at best an unvalidated phone-like match
+        let entities = detector.detect("Card on file: 4111 1111 1111 1112, thanks.");
+        assert!(entities.iter().all(|e| !e.validated));
+    }
+
+    fn locations(detector: &PIIDetector, text: &str) -> Vec<String> {
+        detector
+            .detect(text)
+            .into_iter()
+            .filter(|e| e.entity_type == EntityType::Location)
+            .map(|e| e.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_uk_postcode() {
+        let detector = PIIDetector::new();
+        assert_eq!(
+            locations(&detector, "Send it to 10 Downing Street, London SW1A 2AA today."),
+            vec!["SW1A 2AA"]
+        );
+        assert_eq!(locations(&detector, "Office: M1 1AE"), vec!["M1 1AE"]);
+    }
+
+    #[test]
+    fn test_dutch_postcode() {
+        let detector = PIIDetector::new();
+        assert_eq!(
+            locations(&detector, "Adres: Damrak 1, 1012 LG Amsterdam"),
+            vec!["1012 LG"]
+        );
+        // Years followed by an acronym are not postcodes
+        assert!(locations(&detector, "Since 2016 EU member states agreed.").is_empty());
+    }
+
+    #[test]
+    fn test_digit_postcodes_need_address_context() {
+        let detector = PIIDetector::new();
+
+        assert_eq!(
+            locations(&detector, "Musterstraße 12, 10115 Berlin"),
+            vec!["10115"]
+        );
+        assert_eq!(
+            locations(&detector, "1600 Amphitheatre Pkwy, Mountain View, CA 94043"),
+            vec!["94043"]
+        );
+
+        // A bare five-digit number is not a postcode
+        assert!(locations(&detector, "Invoice 12345 was paid in full.").is_empty());
+        assert!(locations(&detector, "The claim, 25000 Euro in total, was settled.").is_empty());
+        assert!(locations(&detector, "Article 12345 of the code").is_empty());
+    }
+
+    #[test]
+    fn test_adjacent_locations_merge() {
+        let text = "Amsterdam, 1012 LG and Rotterdam";
+        let location = |needle: &str| {
+            let start = text.find(needle).unwrap();
+            Entity::new(EntityType::Location, needle.to_string(), start, start + needle.len(), 0.8)
+        };
+
+        let merged = merge_adjacent_locations(
+            vec![location("1012 LG"), location("Amsterdam"), location("Rotterdam")],
+            text,
+        );
+
+        let texts: Vec<&str> = merged.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["Amsterdam, 1012 LG", "Rotterdam"]);
+        assert_eq!(merged[0].start, 0);
+        assert_eq!(merged[0].end, "Amsterdam, 1012 LG".len());
+    }
+
+    #[test]
+    fn test_embedded_data_detection() {
+        let detector = PIIDetector::new();
+        let technical = |text: &str| -> Vec<String> {
+            detector
+                .detect(text)
+                .into_iter()
+                .filter(|e| e.entity_type == EntityType::TechnicalIdentifier)
+                .map(|e| e.text)
+                .collect()
+        };
+
+        // A certificate wrapped over lines is one blob
+        let certificate = "MIIBszCCAVmgAwIBAgIUY3Hh5Zq8vN2yQ1xKp0fGcD7wT4swCgYIKoZIzj0EAwIw\nFDESMBAGA1UEAwwJbG9jYWxob3N0MB4XDTI0MDEwMTAwMDAwMFoXDTI1MDEwMTAw";
+        let text = format!("-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----", certificate);
+        assert_eq!(technical(&text), vec![certificate.to_string()]);
+
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert_eq!(technical(&format!("Checksum {} matches.", sha256)), vec![sha256.to_string()]);
+
+        // Long words and short tokens are not data
+        assert!(technical("Donaudampfschifffahrtsgesellschaftskapitaenswitwenrentenversicherung applies.").is_empty());
+        assert!(technical("Reference AbC123dEf456 was issued.").is_empty());
+        let path = "/Users/JohnSmith/Documents/Contracts/AcmeHoldings/LeaseAgreements/Draft3";
+        assert!(technical(&format!("Saved to {}.docx yesterday.", path)).is_empty());
+        let prose = format!("Plain prose in {}.docx stays.", path);
+        assert_eq!(detector.strip_embedded_data(&prose), prose);
+    }
+
+    #[test]
+    fn test_luhn() {
+        assert!(luhn_valid("4111111111111111"));
+        assert!(luhn_valid("5500-0000-0000-0004"));
+        assert!(!luhn_valid("4111111111111112"));
+        assert!(!luhn_valid("0000"));
+    }
+
+    #[test]
+    fn test_iban_checksum() {
+        assert!(iban_valid("NL91ABNA0417164300"));
+        assert!(iban_valid("GB82 WEST 1234 5698 7654 32"));
+        assert!(iban_valid("DE89370400440532013000"));
+        assert!(!iban_valid("NL91ABNA0417164301"));
+        assert!(!iban_valid("GB82 WEST 1234"));
+    }
+
+    #[test]
+    fn test_validated_iban_outranks_name_heuristic() {
+        let detector = PIIDetector::new();
+        let text = "Jane Smith paid from NL91 ABNA 0417 1643 00 last week.";
+
+        let iban = detector
+            .detect(text)
+            .into_iter()
+            .find(|e| e.text == "NL91 ABNA 0417 1643 00")
+            .expect("valid IBAN should be detected");
+        assert!(iban.validated);
+        let name = detector
+            .detect_person_names(text)
+            .into_iter()
+            .find(|e| e.text == "Jane Smith")
+            .unwrap();
+
+        assert!(iban.confidence > 0.95, "{}", iban.confidence);
+        assert!((0.7..0.8).contains(&name.confidence), "{}", name.confidence);
+        assert!(iban.confidence > name.confidence);
+
+        // A broken checksum is not reported at all
+        let entities = detector.detect("Paid from NL91 ABNA 0417 1643 01.");
+        assert!(entities.iter().all(|e| !e.validated));
+    }
+
+    #[test]
+    fn test_confidence_reflects_specificity_and_context() {
+        let detector = PIIDetector::new();
+        let confidence = |text: &str, matched: &str| {
+            detector
+                .detect(text)
+                .into_iter()
+                .find(|e| e.text == matched)
+                .unwrap()
+                .confidence
+        };
+
+        // An email address is a stricter shape than a bare run of digits
+        let email = confidence("Mail jane@example.com today", "jane@example.com");
+        let passport = confidence("Passport NL123456789 on file", "NL123456789");
+        assert!(email > passport);
+
+        // Role keywords near a name raise the heuristic's score, up to a cap
+        let names = |text: &str| detector.detect_person_names(text)[0].confidence;
+        let bare = names("Yesterday Jane Smith called.");
+        let one = names("The witness Jane Smith called.");
+        let two = names("Counsel for the defendant Jane Smith called.");
+        let many = names("Counsel for the defendant and witness Jane Smith, our client, called.");
+        assert!(bare < one && one < two, "{} {} {}", bare, one, two);
+        assert_eq!(two, many);
+        assert!(many < 1.0);
+    }
+
     #[test]
     fn test_money_detection() {
         let detector = PIIDetector::new();
@@ -343,6 +1235,62 @@ mod tests {
         assert!(money_entities.len() >= 1);
     }
 
+    fn money(detector: &PIIDetector, text: &str) -> Vec<String> {
+        detector
+            .detect(text)
+            .into_iter()
+            .filter(|e| e.entity_type == EntityType::Money)
+            .map(|e| e.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_worded_money_amounts() {
+        let detector = PIIDetector::new();
+        assert_eq!(
+            money(&detector, "Damages of two million euros were awarded."),
+            vec!["two million euros"]
+        );
+        assert_eq!(
+            money(&detector, "She paid Twenty-five thousand dollars and a hundred pounds sterling."),
+            vec!["Twenty-five thousand dollars", "a hundred pounds sterling"]
+        );
+        assert_eq!(money(&detector, "A fee of $2 million applies."), vec!["$2 million"]);
+        assert_eq!(money(&detector, "Pay 1,500 EUR by Friday."), vec!["1,500 EUR"]);
+    }
+
+    #[test]
+    fn test_pounds_need_a_money_context() {
+        let detector = PIIDetector::new();
+        assert!(money(&detector, "Add 5 pounds of flour to the order.").is_empty());
+        assert!(money(&detector, "The parcel weighs twenty pounds.").is_empty());
+
+        assert_eq!(money(&detector, "The fine was 500 pounds."), vec!["500 pounds"]);
+        assert_eq!(
+            money(&detector, "Damages of two hundred pounds were awarded."),
+            vec!["two hundred pounds"]
+        );
+        assert_eq!(money(&detector, "He owed £40 and 30 GBP."), vec!["£40", "30 GBP"]);
+    }
+
+    #[test]
+    fn test_money_ranges_are_one_entity() {
+        let detector = PIIDetector::new();
+        assert_eq!(
+            money(&detector, "Expected costs: $10,000–$15,000 per month."),
+            vec!["$10,000–$15,000"]
+        );
+        assert_eq!(money(&detector, "A budget of €5.000 - 7.500 is agreed."), vec!["€5.000 - 7.500"]);
+        assert_eq!(money(&detector, "Fees of 10,000-15,000 USD apply."), vec!["10,000-15,000 USD"]);
+    }
+
+    #[test]
+    fn test_plain_numbers_are_not_money() {
+        let detector = PIIDetector::new();
+        let text = "Two witnesses reviewed pages 10-15 of the 2,000-page report, filed in one hundred boxes.";
+        assert!(money(&detector, text).is_empty());
+    }
+
     #[test]
     fn test_person_name_detection() {
         use regex::Regex;
@@ -1,15 +1,30 @@
 use std::collections::HashMap;
 
+/// Name suffixes ignored when building link keys
+const NAME_SUFFIXES: [&str; 8] = ["jr", "sr", "ii", "iii", "iv", "esq", "phd", "md"];
+
+/// A group of mentions that refer to the same entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityCluster {
+    /// Most complete mention in the cluster (e.g. "John Doe" over "J. Doe")
+    pub canonical: String,
+    /// All mentions in the cluster, in input order
+    pub mentions: Vec<String>,
+}
+
 /// Entity linker for matching variations of the same entity
 pub struct EntityLinker {
     // Map canonical form to all variations
     entity_map: HashMap<String, Vec<String>>,
+    // Reverse index: normalized variation -> canonical form
+    variation_index: HashMap<String, String>,
 }
 
 impl EntityLinker {
     pub fn new() -> Self {
         Self {
             entity_map: HashMap::new(),
+            variation_index: HashMap::new(),
         }
     }
 
@@ -18,15 +33,11 @@ impl EntityLinker {
         // Normalize the text
         let normalized = self.normalize_text(text);
 
-        // Check if we have a canonical form for this
-        for (canonical, variations) in &self.entity_map {
-            if variations.iter().any(|v| v == &normalized) {
-                return canonical.clone();
-            }
-        }
-
-        // No match found, this is a new canonical form
-        normalized
+        // Check if we have a canonical form for this, otherwise it is a new canonical form
+        self.variation_index
+            .get(&normalized)
+            .cloned()
+            .unwrap_or(normalized)
     }
 
     /// Link a variation to a canonical form
@@ -34,12 +45,130 @@ impl EntityLinker {
         let canonical_normalized = self.normalize_text(canonical);
         let variation_normalized = self.normalize_text(variation);
 
+        // The first link wins so earlier replacements stay stable
+        self.variation_index
+            .entry(canonical_normalized.clone())
+            .or_insert_with(|| canonical_normalized.clone());
+        self.variation_index
+            .entry(variation_normalized.clone())
+            .or_insert_with(|| canonical_normalized.clone());
+
         self.entity_map
             .entry(canonical_normalized.clone())
             .or_insert_with(|| vec![canonical_normalized.clone()])
             .push(variation_normalized);
     }
 
+    /// Group person mentions that refer to the same individual
+    ///
+    /// Each mention is reduced to a link key (lowercased, titles and suffixes
+    /// stripped) and grouped through hash lookups rather than pairwise
+    /// comparison: full names by surname + first name, initials ("J. Doe") by
+    /// surname + initial, and bare surnames by surname. Initials and bare
+    /// surnames only join a cluster when exactly one candidate exists.
+    pub fn cluster_mentions(&self, mentions: &[String]) -> Vec<EntityCluster> {
+        let keys: Vec<Vec<String>> = mentions.iter().map(|m| self.link_tokens(m)).collect();
+
+        let mut clusters: Vec<Vec<usize>> = Vec::new();
+        let mut by_full_name: HashMap<(String, String), usize> = HashMap::new();
+        let mut by_initial: HashMap<(String, char), Vec<usize>> = HashMap::new();
+        let mut by_surname: HashMap<String, Vec<usize>> = HashMap::new();
+
+        let mut join = |key: (String, String), clusters: &mut Vec<Vec<usize>>, idx: usize| -> usize {
+            let id = *by_full_name.entry(key).or_insert_with(|| {
+                clusters.push(Vec::new());
+                clusters.len() - 1
+            });
+            clusters[id].push(idx);
+            id
+        };
+
+        // Pass 1: full first names (and mentions that cannot be parsed as names)
+        for (idx, tokens) in keys.iter().enumerate() {
+            match tokens.as_slice() {
+                [] => {
+                    join((mentions[idx].to_lowercase(), String::new()), &mut clusters, idx);
+                }
+                [first, .., surname] if first.chars().count() > 1 => {
+                    let id = join((surname.clone(), first.clone()), &mut clusters, idx);
+                    let initial = first.chars().next().unwrap_or_default();
+                    let initial_ids = by_initial.entry((surname.clone(), initial)).or_default();
+                    if !initial_ids.contains(&id) {
+                        initial_ids.push(id);
+                    }
+                    let surname_ids = by_surname.entry(surname.clone()).or_default();
+                    if !surname_ids.contains(&id) {
+                        surname_ids.push(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Pass 2: initials expand to the unique full name with that surname and initial
+        for (idx, tokens) in keys.iter().enumerate() {
+            if let [first, .., surname] = tokens.as_slice() {
+                if first.chars().count() != 1 {
+                    continue;
+                }
+                let initial = first.chars().next().unwrap_or_default();
+                match by_initial.get(&(surname.clone(), initial)).map(Vec::as_slice) {
+                    Some([id]) => clusters[*id].push(idx),
+                    _ => {
+                        let id = join((surname.clone(), first.clone()), &mut clusters, idx);
+                        let surname_ids = by_surname.entry(surname.clone()).or_default();
+                        if !surname_ids.contains(&id) {
+                            surname_ids.push(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Pass 3: a bare surname joins the only cluster with that surname
+        for (idx, tokens) in keys.iter().enumerate() {
+            if let [surname] = tokens.as_slice() {
+                match by_surname.get(surname).map(Vec::as_slice) {
+                    Some([id]) => clusters[*id].push(idx),
+                    _ => {
+                        join((surname.clone(), String::new()), &mut clusters, idx);
+                    }
+                }
+            }
+        }
+
+        clusters
+            .into_iter()
+            .filter(|members| !members.is_empty())
+            .map(|mut members| {
+                members.sort_unstable();
+                let canonical_idx = *members
+                    .iter()
+                    .max_by_key(|&&i| {
+                        let tokens = &keys[i];
+                        let full_first = tokens.first().map_or(0, |t| t.chars().count().min(2));
+                        (full_first, tokens.len(), mentions[i].len(), std::cmp::Reverse(i))
+                    })
+                    .unwrap_or(&members[0]);
+
+                EntityCluster {
+                    canonical: mentions[canonical_idx].clone(),
+                    mentions: members.iter().map(|&i| mentions[i].clone()).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Tokens used for linking: lowercased, titles, punctuation and suffixes removed
+    fn link_tokens(&self, text: &str) -> Vec<String> {
+        self.normalize_text(text)
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(|token| token.trim_matches('.'))
+            .filter(|token| !token.is_empty() && !NAME_SUFFIXES.contains(token))
+            .map(str::to_string)
+            .collect()
+    }
+
     /// Check if two entities might be the same person
     pub fn might_be_same_person(&self, text1: &str, text2: &str) -> bool {
         let norm1 = self.normalize_text(text1);
@@ -75,20 +204,15 @@ impl EntityLinker {
     }
 
     fn remove_titles(&self, text: &str) -> String {
-        let titles = ["mr.", "mrs.", "ms.", "dr.", "prof.", "mr", "mrs", "ms", "dr", "prof"];
-
-        // Convert to lowercase for case-insensitive matching
-        let mut result = text.to_lowercase();
-        for title in &titles {
-            // Handle "title " and "title. " patterns
-            result = result.replace(&format!("{} ", title), "");
-            // For titles without dot, also check with dot
-            if !title.ends_with('.') {
-                result = result.replace(&format!("{}. ", title), "");
-            }
-        }
-
-        result.trim().to_string()
+        let titles = ["mr", "mrs", "ms", "dr", "prof"];
+
+        // Convert to lowercase for case-insensitive matching; only whole words are
+        // titles, so surnames ending in e.g. "ms" ("Adams") are left intact
+        text.to_lowercase()
+            .split_whitespace()
+            .filter(|word| !titles.contains(&word.trim_end_matches('.')))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     fn extract_last_name(&self, text: &str) -> Option<String> {
@@ -118,14 +242,21 @@ impl EntityLinker {
 
     /// Auto-link entities based on similarity
     pub fn auto_link_entities(&mut self, entities: &[String]) {
-        for i in 0..entities.len() {
-            for j in (i + 1)..entities.len() {
-                if self.might_be_same_person(&entities[i], &entities[j]) {
-                    // Link them
-                    let canonical = entities[i].clone();
-                    let variation = entities[j].clone();
-                    self.link_variation(&canonical, &variation);
-                }
+        for cluster in self.cluster_mentions(entities) {
+            if cluster.mentions.len() < 2 {
+                continue;
+            }
+
+            // Keep an existing canonical form (e.g. from an earlier document) if any
+            // mention is already linked, so replacements stay consistent across documents
+            let canonical = cluster
+                .mentions
+                .iter()
+                .find_map(|m| self.variation_index.get(&self.normalize_text(m)).cloned())
+                .unwrap_or_else(|| self.normalize_text(&cluster.canonical));
+
+            for mention in &cluster.mentions {
+                self.link_variation(&canonical, mention);
             }
         }
     }
@@ -189,5 +320,97 @@ mod tests {
 
         // Jane Smith should be separate
         assert!(!linker.might_be_same_person("John Doe", "Jane Smith"));
+
+        assert_eq!(linker.get_canonical("J. Doe"), "john doe");
+        assert_eq!(linker.get_canonical("Mr. John Doe"), "john doe");
+        assert_eq!(linker.get_canonical("Jane Smith"), "jane smith");
+    }
+
+    #[test]
+    fn test_cluster_mentions_basic() {
+        let linker = EntityLinker::new();
+        let mentions: Vec<String> = [
+            "J. Doe",
+            "John Doe",
+            "Mr. John Doe Jr.",
+            "Jane Smith",
+            "Dr. Smith",
+            "Jane Doe",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let clusters = linker.cluster_mentions(&mentions);
+
+        let john = clusters.iter().find(|c| c.mentions.contains(&"John Doe".to_string())).unwrap();
+        assert_eq!(john.canonical, "Mr. John Doe Jr.");
+        assert!(!john.mentions.contains(&"Jane Doe".to_string()));
+
+        let smith = clusters.iter().find(|c| c.canonical == "Jane Smith").unwrap();
+        assert_eq!(smith.mentions, vec!["Jane Smith", "Dr. Smith"]);
+
+        // "J. Doe" is ambiguous between John and Jane Doe, so it stays on its own
+        let initial = clusters.iter().find(|c| c.mentions.contains(&"J. Doe".to_string())).unwrap();
+        assert_eq!(initial.mentions, vec!["J. Doe"]);
+    }
+
+    #[test]
+    fn test_cluster_many_mentions() {
+        let first_names = [
+            "Alice", "Bruno", "Clara", "David", "Elena", "Felix", "Greta", "Hugo", "Irene",
+            "Jonas", "Karin", "Lucas", "Maria", "Niels", "Olga", "Pablo", "Quinn", "Rosa",
+            "Simon", "Tessa",
+        ];
+        let surnames = [
+            "Adams", "Baker", "Clark", "Dekker", "Evans", "Fischer", "Garcia", "Hansen",
+            "Ivanov", "Jensen",
+        ];
+
+        // Four variants per person, interleaved so linking can't rely on adjacency
+        let mut mentions = Vec::new();
+        let mut expected = Vec::new();
+        for variant in 0..4 {
+            for (s, surname) in surnames.iter().enumerate() {
+                for (f, first) in first_names.iter().enumerate() {
+                    let initial = &first[..1];
+                    mentions.push(match variant {
+                        0 => format!("{} {}", first, surname),
+                        1 => format!("Mr. {} {}", first, surname),
+                        2 => format!("{}. {}", initial, surname),
+                        _ => format!("{} {} Jr.", first.to_uppercase(), surname),
+                    });
+                    expected.push(s * first_names.len() + f);
+                }
+            }
+        }
+        assert_eq!(mentions.len(), 800);
+
+        let linker = EntityLinker::new();
+        let started = std::time::Instant::now();
+        let clusters = linker.cluster_mentions(&mentions);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+
+        assert_eq!(clusters.len(), 200);
+        for cluster in &clusters {
+            assert_eq!(cluster.mentions.len(), 4);
+            let person = expected[mentions.iter().position(|m| *m == cluster.mentions[0]).unwrap()];
+            for mention in &cluster.mentions {
+                let idx = mentions.iter().position(|m| m == mention).unwrap();
+                assert_eq!(expected[idx], person, "{} misgrouped", mention);
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto_link_keeps_existing_canonical() {
+        let mut linker = EntityLinker::new();
+        linker.auto_link_entities(&["John Doe".to_string(), "J. Doe".to_string()]);
+
+        // A later document introduces a more complete form of the same person
+        linker.auto_link_entities(&["John Doe Jr.".to_string(), "John Doe".to_string()]);
+
+        assert_eq!(linker.get_canonical("John Doe Jr."), "john doe");
+        assert_eq!(linker.get_canonical("J. Doe"), "john doe");
     }
 }
@@ -0,0 +1,173 @@
+//! Language codes shared by the NER registry, Presidio and the detectors
+//!
+//! Codes arrive in several spellings ("en", "EN", "en-GB", "en_gb", "en/gb",
+//! "zh-Hans"). They are parsed once into a `Language`, which keeps only the
+//! primary ISO 639 subtag: neither Presidio nor the NER models distinguish
+//! regional variants.
+
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Spelling used for models that cover many languages
+const MULTILINGUAL: &str = "multilingual";
+
+/// A normalized language code
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Language {
+    /// Lowercase ISO 639 code, e.g. "en", "zh"
+    Code(String),
+    /// Any language (multilingual NER models)
+    Multilingual,
+}
+
+impl Language {
+    /// Parse and normalize a language code
+    ///
+    /// Accepts a two- or three-letter primary subtag, optionally followed by
+    /// region/script subtags separated by '-', '_' or '/'. "multilingual" is
+    /// accepted as-is.
+    pub fn parse(value: &str) -> Result<Self> {
+        let normalized = value.trim().to_lowercase();
+        if normalized == MULTILINGUAL {
+            return Ok(Self::Multilingual);
+        }
+
+        let mut subtags = normalized.split(['-', '_', '/']);
+        let primary = subtags.next().unwrap_or_default();
+
+        let primary_valid =
+            (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_lowercase());
+        let rest_valid = subtags.all(|subtag| {
+            (2..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+
+        if !primary_valid || !rest_valid {
+            anyhow::bail!("Invalid language code: '{}'", value);
+        }
+
+        Ok(Self::Code(primary.to_string()))
+    }
+
+    /// English, the default for detection
+    pub fn english() -> Self {
+        Self::Code("en".to_string())
+    }
+
+    /// Code to send to Presidio, which needs one specific language
+    pub fn as_presidio(&self) -> Option<&str> {
+        match self {
+            Self::Code(code) => Some(code),
+            Self::Multilingual => None,
+        }
+    }
+
+    /// Code as used by NER model metadata ("multilingual" for any language)
+    pub fn as_ner(&self) -> &str {
+        match self {
+            Self::Code(code) => code,
+            Self::Multilingual => MULTILINGUAL,
+        }
+    }
+
+    /// Whether a model for this language can process text in `other`
+    pub fn covers(&self, other: &Language) -> bool {
+        self == other || *self == Self::Multilingual
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_ner())
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_ner())
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Language::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepted_spellings_normalize() {
+        let cases = [
+            ("en", "en"),
+            ("EN", "en"),
+            (" en ", "en"),
+            ("en-gb", "en"),
+            ("en/gb", "en"),
+            ("en_GB", "en"),
+            ("en-us", "en"),
+            ("de-de", "de"),
+            ("de/de", "de"),
+            ("fr-fr", "fr"),
+            ("nl/nl", "nl"),
+            ("ru-ru", "ru"),
+            ("zh-hans", "zh"),
+            ("zh/hans", "zh"),
+            ("zh-hk", "zh"),
+            ("es-419", "es"),
+            ("fil", "fil"),
+        ];
+        for (input, expected) in cases {
+            let language = Language::parse(input).unwrap();
+            assert_eq!(language.as_ner(), expected, "{}", input);
+            assert_eq!(language.as_presidio(), Some(expected), "{}", input);
+        }
+
+        let multilingual = Language::parse("Multilingual").unwrap();
+        assert_eq!(multilingual, Language::Multilingual);
+        assert_eq!(multilingual.as_ner(), "multilingual");
+        assert_eq!(multilingual.as_presidio(), None);
+    }
+
+    #[test]
+    fn test_invalid_codes_are_rejected() {
+        for input in ["", "e", "english", "e1", "en-", "en--gb", "en-g", "en-toolongsubtag", "12", "en gb"] {
+            let err = Language::parse(input).unwrap_err();
+            assert!(err.to_string().contains("Invalid language code"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_covers() {
+        let en = Language::english();
+        let de = Language::parse("de").unwrap();
+        assert!(en.covers(&Language::parse("en-gb").unwrap()));
+        assert!(!en.covers(&de));
+        assert!(Language::Multilingual.covers(&de));
+    }
+
+    #[test]
+    fn test_serde_uses_normalized_code() {
+        let language: Language = serde_json::from_str("\"en-GB\"").unwrap();
+        assert_eq!(serde_json::to_string(&language).unwrap(), "\"en\"");
+        assert!(serde_json::from_str::<Language>("\"english\"").is_err());
+    }
+}
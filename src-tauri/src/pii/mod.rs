@@ -1,14 +1,32 @@
 pub mod anonymizer;
+pub mod dates;
 pub mod detector;
 pub mod entity_linker;
+pub mod language;
 pub mod presidio;
+pub mod pseudonyms;
+pub mod risk;
 pub mod types;
 
 pub use anonymizer::Anonymizer;
 #[allow(unused_imports)]
+pub use dates::DateOrder;
+#[allow(unused_imports)]
 pub use detector::PIIDetector;
 #[allow(unused_imports)]
 pub use entity_linker::EntityLinker;
+pub use language::Language;
 #[allow(unused_imports)]
 pub use presidio::{PresidioManager, PresidioStatus};
-pub use types::{AnonymizationResult, AnonymizationSettings, Entity, EntityType};
+#[allow(unused_imports)]
+pub use pseudonyms::PseudonymGenerator;
+pub use risk::RiskScore;
+pub use types::{
+    assign_utf16_offsets, AnonymizationResult, AnonymizationSettings, ConsistencyScope,
+    DiffSegment, Entity, EntityType, FailurePolicy, MergePolicy, MergedMapping,
+};
+#[allow(unused_imports)]
+pub use types::{
+    DetectionSource, EmbeddedData, EntityCategory, MappingConflict, MaskingStrategy, PersonStyle,
+    Verification,
+};
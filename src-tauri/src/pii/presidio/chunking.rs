@@ -0,0 +1,143 @@
+//! Splitting large texts into chunks for Presidio
+//!
+//! A whole document in one request can run into the container's timeouts,
+//! so long texts are sent in pieces. Each piece is analyzed on its own, so
+//! splits are placed where no entity can straddle them: paragraph breaks
+//! first, then sentence ends, then line breaks, and only as a last resort
+//! any whitespace or a hard cut.
+
+use std::ops::Range;
+
+use crate::pii::detector::{ceil_char_boundary, floor_char_boundary};
+
+/// Default maximum chunk length in bytes
+pub const DEFAULT_CHUNK_SIZE: usize = 5_000;
+
+/// Abbreviations whose trailing period doesn't end a sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "st", "no", "art", "sec", "co", "inc", "ltd", "jr", "sr",
+    "vs", "e.g", "i.e", "etc",
+];
+
+/// Split `text` into contiguous byte ranges of at most `max_len` bytes
+///
+/// The ranges cover the whole text in order, so concatenating the chunks
+/// gives back the original. Whitespace at a split stays with the chunk
+/// before it. A single word longer than `max_len` is cut on a char boundary.
+pub fn chunk_ranges(text: &str, max_len: usize) -> Vec<Range<usize>> {
+    let max_len = max_len.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    while text.len() - start > max_len {
+        let window = &text[start..floor_char_boundary(text, start + max_len)];
+        let split = paragraph_break(window)
+            .or_else(|| sentence_break(window))
+            .or_else(|| window.rfind('\n').map(|i| i + 1))
+            .or_else(|| whitespace_break(window))
+            .filter(|split| *split > 0)
+            .unwrap_or_else(|| {
+                // No safe split point: cut, but never in the middle of a char
+                let cut = window.len().max(1);
+                ceil_char_boundary(text, start + cut) - start
+            });
+
+        ranges.push(start..start + split);
+        start += split;
+    }
+
+    if start < text.len() || ranges.is_empty() {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+/// Offset just after the last blank line in the window
+fn paragraph_break(window: &str) -> Option<usize> {
+    window.rfind("\n\n").map(|i| i + 2)
+}
+
+/// Offset just after the last sentence end (".", "!" or "?" followed by
+/// whitespace) that isn't an abbreviation such as "Mr."
+fn sentence_break(window: &str) -> Option<usize> {
+    let bytes = window.as_bytes();
+    (1..bytes.len())
+        .rev()
+        .filter(|&i| bytes[i].is_ascii_whitespace() && matches!(bytes[i - 1], b'.' | b'!' | b'?'))
+        .find(|&i| bytes[i - 1] != b'.' || !ends_with_abbreviation(&window[..i - 1]))
+        .map(|i| i + 1)
+}
+
+/// Whether the text ends in a known abbreviation or a single letter ("J.")
+fn ends_with_abbreviation(text: &str) -> bool {
+    let word = text
+        .rsplit(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    word.chars().count() == 1 || ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// Offset just after the last whitespace character in the window
+fn whitespace_break(window: &str) -> Option<usize> {
+    window
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(text: &str, max_len: usize) -> Vec<&str> {
+        let ranges = chunk_ranges(text, max_len);
+        // Ranges are contiguous and cover the text
+        assert_eq!(ranges.first().unwrap().start, 0);
+        assert_eq!(ranges.last().unwrap().end, text.len());
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        ranges.into_iter().map(|range| &text[range]).collect()
+    }
+
+    #[test]
+    fn test_short_text_is_one_chunk() {
+        assert_eq!(chunks("John Doe wrote.", 100), vec!["John Doe wrote."]);
+        assert_eq!(chunks("", 100), vec![""]);
+    }
+
+    #[test]
+    fn test_prefers_paragraphs_then_sentences() {
+        let text = "First paragraph. Still first.\n\nSecond paragraph here.";
+        assert_eq!(
+            chunks(text, 40),
+            vec!["First paragraph. Still first.\n\n", "Second paragraph here."]
+        );
+
+        let text = "Mr. John Doe signed. Jane Roe witnessed it.";
+        assert_eq!(chunks(text, 30), vec!["Mr. John Doe signed. ", "Jane Roe witnessed it."]);
+    }
+
+    #[test]
+    fn test_abbreviations_do_not_end_sentences() {
+        // Splitting after "Mr." would separate the title from the name
+        let text = "Signed by Mr. John Doe and others";
+        let parts = chunks(text, 24);
+        assert!(parts.iter().any(|part| part.contains("Mr. John")), "{:?}", parts);
+    }
+
+    #[test]
+    fn test_long_words_are_cut_on_char_boundaries() {
+        let text = "ééééé";
+        let parts = chunks(text, 3);
+        assert!(parts.iter().all(|part| part.len() <= 3));
+        assert_eq!(parts.concat(), text);
+
+        // A chunk never exceeds the limit, even without whitespace
+        for part in chunks(&"x".repeat(25), 10) {
+            assert!(part.len() <= 10);
+        }
+    }
+}
@@ -4,50 +4,216 @@
 //! services running in Docker containers on localhost.
 
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
+use super::chunking::chunk_ranges;
 use super::docker::{ANALYZER_PORT, ANONYMIZER_PORT};
 use super::types::{
-    AnonymizationOperator, PresidioAnalyzeRequest, PresidioAnonymizeRequest,
+    AnonymizationOperator, AnonymizedChunk, PresidioAnalyzeRequest, PresidioAnonymizeRequest,
     PresidioAnonymizeResult, PresidioEntity,
 };
 
+/// Timeouts, retries and concurrency for requests to the Presidio services
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresidioClientOptions {
+    /// Time allowed to establish a connection
+    pub connect_timeout_ms: u64,
+    /// Time allowed between reads of a response
+    pub read_timeout_ms: u64,
+    /// Total time allowed for a health check
+    pub health_check_timeout_ms: u64,
+    /// Total time allowed for analyze, anonymize and other API requests
+    pub request_timeout_ms: u64,
+    /// Retries after a connection failure, timeout or 5xx response (API requests only)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub retry_backoff_ms: u64,
+    /// API requests sent at once; further requests queue in FIFO order.
+    /// The containers get 1 CPU each, so a small value avoids timeouts.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for PresidioClientOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 5_000,
+            read_timeout_ms: 30_000,
+            health_check_timeout_ms: 2_000,
+            request_timeout_ms: 30_000,
+            max_retries: 2,
+            retry_backoff_ms: 500,
+            max_concurrent_requests: 2,
+        }
+    }
+}
+
+impl PresidioClientOptions {
+    /// Reject zero timeouts and concurrency, which would make every request fail
+    pub fn validate(&self) -> Result<()> {
+        if self.max_concurrent_requests == 0 {
+            anyhow::bail!("max_concurrent_requests must be greater than zero");
+        }
+
+        for (name, value) in [
+            ("connect_timeout_ms", self.connect_timeout_ms),
+            ("read_timeout_ms", self.read_timeout_ms),
+            ("health_check_timeout_ms", self.health_check_timeout_ms),
+            ("request_timeout_ms", self.request_timeout_ms),
+        ] {
+            if value == 0 {
+                anyhow::bail!("{} must be greater than zero", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Requests queued for and holding a slot in the client, for diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestQueueStats {
+    /// Requests waiting for a free slot
+    pub queued: usize,
+    /// Requests being sent or retried
+    pub in_flight: usize,
+    pub max_concurrent: usize,
+}
+
+/// Counts a waiting request until its future completes or is dropped
+struct QueuedRequest<'a>(&'a AtomicUsize);
+
+impl<'a> QueuedRequest<'a> {
+    fn new(queued: &'a AtomicUsize) -> Self {
+        queued.fetch_add(1, Ordering::SeqCst);
+        Self(queued)
+    }
+}
+
+impl Drop for QueuedRequest<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// HTTP client for Presidio API communication
 pub struct PresidioClient {
     client: Client,
     analyzer_url: String,
     anonymizer_url: String,
+    options: PresidioClientOptions,
+    /// One permit per request slot; tokio's semaphore serves waiters in FIFO order
+    slots: Semaphore,
+    queued: AtomicUsize,
 }
 
 impl PresidioClient {
     /// Create a new Presidio client with default localhost endpoints
     pub fn new() -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(5))
-            .build()
-            .expect("Failed to create HTTP client");
-
-        Self {
-            client,
-            analyzer_url: format!("http://127.0.0.1:{}", ANALYZER_PORT),
-            anonymizer_url: format!("http://127.0.0.1:{}", ANONYMIZER_PORT),
-        }
+        Self::with_endpoints(
+            format!("http://127.0.0.1:{}", ANALYZER_PORT),
+            format!("http://127.0.0.1:{}", ANONYMIZER_PORT),
+        )
     }
 
     /// Create a client with custom endpoints
     pub fn with_endpoints(analyzer_url: String, anonymizer_url: String) -> Self {
+        Self::with_options(analyzer_url, anonymizer_url, PresidioClientOptions::default())
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Create a client with custom endpoints, timeouts and retries
+    pub fn with_options(
+        analyzer_url: String,
+        anonymizer_url: String,
+        options: PresidioClientOptions,
+    ) -> Result<Self> {
+        options.validate()?;
+
         let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(5))
+            .connect_timeout(Duration::from_millis(options.connect_timeout_ms))
+            .read_timeout(Duration::from_millis(options.read_timeout_ms))
             .build()
-            .expect("Failed to create HTTP client");
+            .context("Failed to create HTTP client")?;
 
-        Self {
+        Ok(Self {
             client,
             analyzer_url,
             anonymizer_url,
+            slots: Semaphore::new(options.max_concurrent_requests),
+            queued: AtomicUsize::new(0),
+            options,
+        })
+    }
+
+    /// Timeouts and retries in use
+    pub fn options(&self) -> &PresidioClientOptions {
+        &self.options
+    }
+
+    /// Requests currently queued and in flight
+    pub fn queue_stats(&self) -> RequestQueueStats {
+        let max_concurrent = self.options.max_concurrent_requests;
+        RequestQueueStats {
+            queued: self.queued.load(Ordering::SeqCst),
+            in_flight: max_concurrent - self.slots.available_permits(),
+            max_concurrent,
+        }
+    }
+
+    /// Wait for a request slot
+    async fn acquire_slot(&self) -> SemaphorePermit<'_> {
+        let _queued = QueuedRequest::new(&self.queued);
+        self.slots
+            .acquire()
+            .await
+            .expect("request semaphore is never closed")
+    }
+
+    /// Send a health check request: short timeout, no retries
+    async fn send_health_check(&self, url: &str) -> reqwest::Result<Response> {
+        self.client
+            .get(url)
+            .timeout(Duration::from_millis(self.options.health_check_timeout_ms))
+            .send()
+            .await
+    }
+
+    /// Send an API request, retrying connection failures, timeouts and 5xx responses
+    ///
+    /// Waits for a request slot first and keeps it through the retries.
+    /// Health checks don't take a slot, so they stay fast under load.
+    async fn send_with_retry<F>(&self, build: F) -> reqwest::Result<Response>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let _slot = self.acquire_slot().await;
+        let timeout = Duration::from_millis(self.options.request_timeout_ms);
+        let mut backoff = Duration::from_millis(self.options.retry_backoff_ms);
+        let mut attempt = 0;
+
+        loop {
+            let result = build().timeout(timeout).send().await;
+
+            let retryable = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+            if !retryable || attempt >= self.options.max_retries {
+                return result;
+            }
+
+            attempt += 1;
+            log::warn!(
+                "Presidio request failed, retrying ({}/{})",
+                attempt,
+                self.options.max_retries
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
         }
     }
 
@@ -56,9 +222,7 @@ impl PresidioClient {
         let url = format!("{}/health", self.analyzer_url);
 
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_health_check(&url)
             .await
             .context("Failed to connect to Presidio analyzer")?;
 
@@ -77,9 +241,7 @@ impl PresidioClient {
         let url = format!("{}/health", self.anonymizer_url);
 
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_health_check(&url)
             .await
             .context("Failed to connect to Presidio anonymizer")?;
 
@@ -106,10 +268,7 @@ impl PresidioClient {
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request))
             .await
             .context("Failed to send analyze request to Presidio")?;
 
@@ -123,12 +282,12 @@ impl PresidioClient {
             );
         }
 
-        let entities: Vec<PresidioEntity> = response
-            .json()
+        let body = response
+            .text()
             .await
-            .context("Failed to parse Presidio analyze response")?;
+            .context("Failed to read Presidio analyze response")?;
 
-        Ok(entities)
+        parse_analyze_response(&body)
     }
 
     /// Analyze text with specific entity types
@@ -150,10 +309,7 @@ impl PresidioClient {
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request))
             .await
             .context("Failed to send analyze request to Presidio")?;
 
@@ -167,12 +323,12 @@ impl PresidioClient {
             );
         }
 
-        let entities: Vec<PresidioEntity> = response
-            .json()
+        let body = response
+            .text()
             .await
-            .context("Failed to parse Presidio analyze response")?;
+            .context("Failed to read Presidio analyze response")?;
 
-        Ok(entities)
+        parse_analyze_response(&body)
     }
 
     /// Anonymize text based on detected PII
@@ -219,10 +375,7 @@ impl PresidioClient {
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request))
             .await
             .context("Failed to send anonymize request to Presidio")?;
 
@@ -244,14 +397,73 @@ impl PresidioClient {
         Ok(result)
     }
 
+    /// Anonymize a long text in chunks of at most `max_chunk_len` bytes
+    ///
+    /// Chunks are split on paragraph and sentence boundaries (see
+    /// `chunk_ranges`) and sent one after another, so no single request
+    /// carries the whole document. `on_chunk` sees each chunk as soon as it
+    /// is done. The stitched result matches a single `anonymize` call: item
+    /// offsets point into the full anonymized text.
+    pub async fn anonymize_chunked<F>(
+        &self,
+        text: &str,
+        language: &str,
+        operators: Option<Vec<AnonymizationOperator>>,
+        max_chunk_len: usize,
+        mut on_chunk: F,
+    ) -> Result<PresidioAnonymizeResult>
+    where
+        F: FnMut(&AnonymizedChunk),
+    {
+        let ranges = chunk_ranges(text, max_chunk_len);
+        let total = ranges.len();
+        let mut stitched = PresidioAnonymizeResult {
+            text: String::with_capacity(text.len()),
+            items: Vec::new(),
+        };
+
+        for (index, range) in ranges.into_iter().enumerate() {
+            let result = self
+                .anonymize(&text[range.clone()], language, operators.clone())
+                .await
+                .with_context(|| format!("Chunk {} of {} failed", index + 1, total))?;
+
+            // Presidio reports item offsets within the chunk's output, in
+            // code points rather than bytes
+            let offset = stitched.text.chars().count();
+            let items: Vec<_> = result
+                .items
+                .into_iter()
+                .map(|mut item| {
+                    item.start += offset;
+                    item.end += offset;
+                    item
+                })
+                .collect();
+
+            let chunk = AnonymizedChunk {
+                index,
+                total,
+                original_start: range.start,
+                original_end: range.end,
+                text: result.text,
+                items,
+            };
+            on_chunk(&chunk);
+
+            stitched.text.push_str(&chunk.text);
+            stitched.items.extend(chunk.items);
+        }
+
+        Ok(stitched)
+    }
+
     /// Get supported entity types from the analyzer
     pub async fn get_supported_entities(&self) -> Result<Vec<String>> {
         let url = format!("{}/supportedentities", self.analyzer_url);
 
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_with_retry(|| self.client.get(&url))
             .await
             .context("Failed to get supported entities from Presidio")?;
 
@@ -282,9 +494,7 @@ impl PresidioClient {
         }
 
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_with_retry(|| self.client.get(&url))
             .await
             .context("Failed to get recognizers from Presidio")?;
 
@@ -335,6 +545,56 @@ pub struct RecognizerInfo {
     pub supported_language: Option<String>,
 }
 
+/// Keys under which Presidio variants and proxies wrap the analyzer results
+const ANALYZE_RESULT_KEYS: &[&str] = &["results", "entities", "analyzer_results", "recognizer_results"];
+
+/// Parse an analyze response body
+///
+/// Presidio returns a bare array of results; some versions and gateways wrap
+/// it in an object. Unknown fields are ignored. A body that holds no result
+/// list at all is reported as a likely version mismatch.
+pub(crate) fn parse_analyze_response(body: &str) -> Result<Vec<PresidioEntity>> {
+    let value: serde_json::Value =
+        serde_json::from_str(body).context("Presidio analyze response is not valid JSON")?;
+
+    let results = match value {
+        serde_json::Value::Array(_) => value,
+        serde_json::Value::Object(mut object) => ANALYZE_RESULT_KEYS
+            .iter()
+            .find_map(|key| object.remove(*key).filter(|v| v.is_array()))
+            .ok_or_else(|| {
+                let keys: Vec<&String> = object.keys().collect();
+                anyhow::anyhow!(
+                    "Unexpected Presidio analyze response: object without a result list (keys: {:?}); \
+                     this Presidio version may not be supported",
+                    keys
+                )
+            })?,
+        other => anyhow::bail!(
+            "Unexpected Presidio analyze response: expected a list of results, got {}; \
+             this Presidio version may not be supported",
+            json_kind(&other)
+        ),
+    };
+
+    serde_json::from_value(results).context(
+        "Presidio analyze results are missing required fields \
+         (entity_type, start, end, score); this Presidio version may not be supported",
+    )
+}
+
+/// Name of a JSON value's kind, for error messages
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,4 +615,333 @@ mod tests {
         assert_eq!(client.analyzer_url, "http://custom:8080");
         assert_eq!(client.anonymizer_url, "http://custom:8081");
     }
+
+    #[test]
+    fn test_parse_analyze_response_formats() {
+        let current = r#"[
+            {"entity_type": "PERSON", "start": 0, "end": 8, "score": 0.85,
+             "analysis_explanation": {"recognizer": "SpacyRecognizer", "original_score": 0.85,
+                                      "validation_result": null, "textual_explanation": "NER"},
+             "recognition_metadata": {"recognizer_name": "SpacyRecognizer",
+                                      "recognizer_identifier": "SpacyRecognizer_1"}},
+            {"entity_type": "US_SSN", "start": 14, "end": 25, "score": 1.0,
+             "analysis_explanation": {"validation_result": true}, "future_field": [1, 2]}
+        ]"#;
+        let entities = parse_analyze_response(current).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].entity_type, "PERSON");
+        assert_eq!(
+            entities[0].recognition_metadata.as_ref().unwrap().recognizer_name.as_deref(),
+            Some("SpacyRecognizer")
+        );
+        assert_eq!((entities[1].start, entities[1].end), (14, 25));
+
+        let wrapped = r#"{"version": "3.0", "results": [
+            {"entity_type": "EMAIL_ADDRESS", "start": 3, "end": 20, "score": 1.0}
+        ]}"#;
+        let entities = parse_analyze_response(wrapped).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].entity_type, "EMAIL_ADDRESS");
+
+        let error = parse_analyze_response(r#"{"detail": "Not Found"}"#).unwrap_err();
+        assert!(error.to_string().contains("version may not be supported"), "{}", error);
+        let error = parse_analyze_response(r#"[{"type": "PERSON", "begin": 0}]"#).unwrap_err();
+        assert!(format!("{:#}", error).contains("version may not be supported"), "{:#}", error);
+    }
+
+    /// Server that accepts connections but never answers; returns its URL and a connection counter
+    async fn silent_server() -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                open.push(socket);
+            }
+        });
+
+        (url, connections)
+    }
+
+    fn short_timeouts(max_retries: u32) -> PresidioClientOptions {
+        PresidioClientOptions {
+            health_check_timeout_ms: 100,
+            request_timeout_ms: 200,
+            max_retries,
+            retry_backoff_ms: 10,
+            ..PresidioClientOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check_times_out_promptly() {
+        let (url, connections) = silent_server().await;
+        let client = PresidioClient::with_options(url.clone(), url, short_timeouts(3)).unwrap();
+
+        let started = std::time::Instant::now();
+        assert!(client.health_check().await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Health checks are never retried
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_times_out_and_retries() {
+        let (url, connections) = silent_server().await;
+        let client = PresidioClient::with_options(url.clone(), url, short_timeouts(2)).unwrap();
+
+        let started = std::time::Instant::now();
+        assert!(client.analyze("John Doe", "en").await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_client_error_is_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/analyze")
+            .with_status(400)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client =
+            PresidioClient::with_options(server.url(), server.url(), short_timeouts(2)).unwrap();
+        assert!(client.analyze("John Doe", "en").await.is_err());
+
+        mock.assert_async().await;
+    }
+
+    /// Server that answers every request with `[]` after `delay`, recording the
+    /// highest number of requests it handled at once
+    async fn slow_server(delay: Duration) -> (String, std::sync::Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let active = std::sync::Arc::new(AtomicUsize::new(0));
+        let peak = std::sync::Arc::new(AtomicUsize::new(0));
+
+        let peak_seen = peak.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let active = active.clone();
+                let peak = peak_seen.clone();
+                tokio::spawn(async move {
+                    // Read the headers and body before answering
+                    let mut request = Vec::new();
+                    let mut buffer = [0u8; 4096];
+                    loop {
+                        let read = socket.read(&mut buffer).await.unwrap_or(0);
+                        if read == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buffer[..read]);
+                        let text = String::from_utf8_lossy(&request).to_lowercase();
+                        if let Some(header_end) = text.find("\r\n\r\n") {
+                            let body_len = text
+                                .lines()
+                                .find_map(|line| line.strip_prefix("content-length:"))
+                                .and_then(|len| len.trim().parse::<usize>().ok())
+                                .unwrap_or(0);
+                            if request.len() >= header_end + 4 + body_len {
+                                break;
+                            }
+                        }
+                    }
+
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+
+                    let response =
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n[]";
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        (url, peak)
+    }
+
+    fn with_concurrency(max_concurrent_requests: usize) -> PresidioClientOptions {
+        PresidioClientOptions {
+            max_concurrent_requests,
+            ..PresidioClientOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_slot_runs_requests_serially() {
+        let (url, peak) = slow_server(Duration::from_millis(100)).await;
+        let client = PresidioClient::with_options(url.clone(), url, with_concurrency(1)).unwrap();
+
+        let started = std::time::Instant::now();
+        let (a, b, c, stats) = tokio::join!(
+            client.analyze("John Doe", "en"),
+            client.analyze("Jane Doe", "en"),
+            client.analyze("Max Doe", "en"),
+            async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                client.queue_stats()
+            }
+        );
+        assert!(a.unwrap().is_empty() && b.unwrap().is_empty() && c.unwrap().is_empty());
+
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(
+            stats,
+            RequestQueueStats {
+                queued: 2,
+                in_flight: 1,
+                max_concurrent: 1
+            }
+        );
+        assert_eq!(client.queue_stats().queued, 0);
+        assert_eq!(client.queue_stats().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_slots_allow_parallel_requests() {
+        let (url, peak) = slow_server(Duration::from_millis(100)).await;
+        let client = PresidioClient::with_options(url.clone(), url, with_concurrency(3)).unwrap();
+
+        let (a, b, c) = tokio::join!(
+            client.analyze("John Doe", "en"),
+            client.analyze("Jane Doe", "en"),
+            client.analyze("Max Doe", "en"),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert!(peak.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn test_zero_concurrency_rejected() {
+        let err = with_concurrency(0).validate().unwrap_err();
+        assert!(err.to_string().contains("max_concurrent_requests"));
+    }
+
+    #[test]
+    fn test_zero_timeout_rejected() {
+        let options = PresidioClientOptions {
+            request_timeout_ms: 0,
+            ..PresidioClientOptions::default()
+        };
+        let err = options.validate().unwrap_err();
+        assert!(err.to_string().contains("request_timeout_ms"));
+    }
+
+    /// Presidio stand-in that finds "@"-addresses and replaces them with
+    /// `<EMAIL_ADDRESS>`, reporting offsets in code points as Presidio does
+    async fn email_server() -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/analyze")
+            .with_header("content-type", "application/json")
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let text = body["text"].as_str().unwrap();
+                let mut entities = Vec::new();
+                let mut offset = 0;
+                for word in text.split(' ') {
+                    let trimmed = word.trim_end_matches(['.', ',', '\n']);
+                    if trimmed.contains('@') {
+                        entities.push(serde_json::json!({
+                            "entity_type": "EMAIL_ADDRESS",
+                            "start": offset,
+                            "end": offset + trimmed.chars().count(),
+                            "score": 1.0
+                        }));
+                    }
+                    offset += word.chars().count() + 1;
+                }
+                serde_json::to_vec(&entities).unwrap()
+            })
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/anonymize")
+            .with_header("content-type", "application/json")
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let text: Vec<char> = body["text"].as_str().unwrap().chars().collect();
+                let mut output = String::new();
+                let mut items = Vec::new();
+                let mut last = 0;
+                for entity in body["analyzer_results"].as_array().unwrap() {
+                    let (start, end) = (
+                        entity["start"].as_u64().unwrap() as usize,
+                        entity["end"].as_u64().unwrap() as usize,
+                    );
+                    output.extend(&text[last..start]);
+                    let position = output.chars().count();
+                    items.push(serde_json::json!({
+                        "start": position,
+                        "end": position + "<EMAIL_ADDRESS>".len(),
+                        "entity_type": "EMAIL_ADDRESS",
+                        "operator": "replace"
+                    }));
+                    output.push_str("<EMAIL_ADDRESS>");
+                    last = end;
+                }
+                output.extend(&text[last..]);
+                serde_json::to_vec(&serde_json::json!({ "text": output, "items": items })).unwrap()
+            })
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_chunked_anonymize_stitches_global_offsets() {
+        let server = email_server().await;
+        let client = PresidioClient::with_endpoints(server.url(), server.url());
+
+        let paragraphs: Vec<String> = (0..40)
+            .map(|i| format!("Paragraph {} – Müller mentions user{}@example.com. Ça suit.", i, i))
+            .collect();
+        let text = paragraphs.join("\n\n");
+
+        let mut chunks = Vec::new();
+        let result = client
+            .anonymize_chunked(&text, "en", None, 300, |chunk| {
+                chunks.push((chunk.index, chunk.total, chunk.original_start, chunk.original_end))
+            })
+            .await
+            .unwrap();
+
+        assert!(chunks.len() > 5, "{} chunks", chunks.len());
+        assert!(chunks.iter().all(|(_, total, _, _)| *total == chunks.len()));
+        assert_eq!(chunks.last().unwrap().3, text.len());
+        for (index, pair) in chunks.windows(2).enumerate() {
+            assert_eq!(pair[0].0, index);
+            assert_eq!(pair[0].3, pair[1].2);
+        }
+
+        // Same output as anonymizing the whole text at once
+        let whole = client.anonymize(&text, "en", None).await.unwrap();
+        assert_eq!(result.text, whole.text);
+        assert_eq!(result.items.len(), 40);
+        for (stitched, single) in result.items.iter().zip(&whole.items) {
+            assert_eq!((stitched.start, stitched.end), (single.start, single.end));
+            let replaced: String = result
+                .text
+                .chars()
+                .skip(stitched.start)
+                .take(stitched.end - stitched.start)
+                .collect();
+            assert_eq!(replaced, "<EMAIL_ADDRESS>");
+        }
+        assert!(!result.text.contains("@example.com"));
+    }
 }
@@ -6,6 +6,9 @@
 use anyhow::{Context, Result};
 use std::process::Stdio;
 use tokio::process::Command;
+use tokio::sync::RwLock;
+
+use super::types::{AnalyzerContainerOptions, RestartPolicy};
 
 /// Container names for Presidio services
 pub const ANALYZER_CONTAINER_NAME: &str = "bear-presidio-analyzer";
@@ -38,12 +41,43 @@ pub enum ContainerStatus {
 pub struct PresidioDockerManager {
     /// Path to docker executable (auto-detected)
     docker_path: Option<String>,
+    /// Extra env/volume options used when creating the analyzer container
+    analyzer_options: RwLock<AnalyzerContainerOptions>,
+    /// Restart policy of both containers
+    restart_policy: RwLock<RestartPolicy>,
 }
 
 impl PresidioDockerManager {
     /// Create a new Docker manager
     pub fn new() -> Self {
-        Self { docker_path: None }
+        Self {
+            docker_path: None,
+            analyzer_options: RwLock::new(AnalyzerContainerOptions::default()),
+            restart_policy: RwLock::new(RestartPolicy::default()),
+        }
+    }
+
+    /// Set the options used the next time the analyzer container is created
+    pub async fn set_analyzer_options(&self, options: AnalyzerContainerOptions) -> Result<()> {
+        options.validate()?;
+        *self.analyzer_options.write().await = options;
+        Ok(())
+    }
+
+    /// Get the configured analyzer container options
+    pub async fn get_analyzer_options(&self) -> AnalyzerContainerOptions {
+        self.analyzer_options.read().await.clone()
+    }
+
+    /// Set the restart policy; containers with another policy are recreated
+    /// the next time they are started
+    pub async fn set_restart_policy(&self, policy: RestartPolicy) {
+        *self.restart_policy.write().await = policy;
+    }
+
+    /// Get the configured restart policy
+    pub async fn get_restart_policy(&self) -> RestartPolicy {
+        *self.restart_policy.read().await
     }
 
     /// Check if Docker is available on the system
@@ -168,6 +202,25 @@ impl PresidioDockerManager {
         }
     }
 
+    /// Restart policy an existing container was created with
+    async fn container_restart_policy(&self, container_name: &str) -> Result<Option<RestartPolicy>> {
+        let output = Command::new("docker")
+            .args(["inspect", "-f", "{{.HostConfig.RestartPolicy.Name}}", container_name])
+            .output()
+            .await
+            .context("Failed to inspect container")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to inspect container {}: {}",
+                container_name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(RestartPolicy::from_docker(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     /// Start Presidio containers
     pub async fn start_containers(&self) -> Result<()> {
         if !self.is_docker_available().await {
@@ -180,11 +233,13 @@ impl PresidioDockerManager {
         }
 
         // Start analyzer container
+        let analyzer_options = self.get_analyzer_options().await;
         self.start_or_create_container(
             ANALYZER_CONTAINER_NAME,
             ANALYZER_IMAGE,
             ANALYZER_PORT,
             5002,
+            &analyzer_options,
         )
         .await?;
 
@@ -194,6 +249,7 @@ impl PresidioDockerManager {
             ANONYMIZER_IMAGE,
             ANONYMIZER_PORT,
             5001,
+            &AnalyzerContainerOptions::default(),
         )
         .await?;
 
@@ -207,8 +263,27 @@ impl PresidioDockerManager {
         image: &str,
         host_port: u16,
         container_port: u16,
+        options: &AnalyzerContainerOptions,
     ) -> Result<()> {
-        let status = self.get_single_container_status(container_name).await?;
+        let restart_policy = self.get_restart_policy().await;
+        let mut status = self.get_single_container_status(container_name).await?;
+
+        // The restart policy is fixed at creation, so a container created
+        // with another policy is replaced
+        if status != ContainerStatus::NotFound
+            && self.container_restart_policy(container_name).await? != Some(restart_policy)
+        {
+            let result = Command::new("docker")
+                .args(["rm", "-f", container_name])
+                .status()
+                .await
+                .context("Failed to remove container")?;
+
+            if !result.success() {
+                anyhow::bail!("Failed to remove container: {}", container_name);
+            }
+            status = ContainerStatus::NotFound;
+        }
 
         match status {
             ContainerStatus::Running => {
@@ -230,27 +305,17 @@ impl PresidioDockerManager {
             }
             ContainerStatus::NotFound => {
                 // Create and start new container
-                // IMPORTANT: Bind only to localhost (127.0.0.1) for security
-                let port_mapping = format!("127.0.0.1:{}:{}", host_port, container_port);
+                let args = run_args(
+                    container_name,
+                    image,
+                    host_port,
+                    container_port,
+                    restart_policy,
+                    options,
+                );
 
                 let result = Command::new("docker")
-                    .args([
-                        "run",
-                        "-d",
-                        "--name",
-                        container_name,
-                        "-p",
-                        &port_mapping,
-                        "--restart",
-                        "unless-stopped",
-                        // Resource limits
-                        "--memory",
-                        "512m",
-                        "--cpus",
-                        "1",
-                        // Security: no network access except localhost binding
-                        image,
-                    ])
+                    .args(&args)
                     .status()
                     .await
                     .context("Failed to create container")?;
@@ -383,6 +448,60 @@ impl PresidioDockerManager {
     }
 }
 
+/// Build the `docker run` argument vector for creating a Presidio container
+pub fn run_args(
+    container_name: &str,
+    image: &str,
+    host_port: u16,
+    container_port: u16,
+    restart_policy: RestartPolicy,
+    options: &AnalyzerContainerOptions,
+) -> Vec<String> {
+    // IMPORTANT: Bind only to localhost (127.0.0.1) for security
+    let port_mapping = format!("127.0.0.1:{}:{}", host_port, container_port);
+
+    let mut args: Vec<String> = [
+        "run",
+        "-d",
+        "--name",
+        container_name,
+        "-p",
+        &port_mapping,
+        "--restart",
+        restart_policy.as_str(),
+        // Resource limits
+        "--memory",
+        "512m",
+        "--cpus",
+        "1",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+
+    for (key, value) in &options.env {
+        args.push("-e".to_string());
+        args.push(format!("{}={}", key, value));
+    }
+
+    for volume in &options.volumes {
+        let mut mount = format!(
+            "type=bind,source={},target={}",
+            volume.host_path, volume.container_path
+        );
+        if volume.read_only {
+            mount.push_str(",readonly");
+        }
+        args.push("--mount".to_string());
+        args.push(mount);
+    }
+
+    // Security: no network access except localhost binding
+    args.push(image.to_string());
+
+    args
+}
+
 impl Default for PresidioDockerManager {
     fn default() -> Self {
         Self::new()
@@ -408,6 +527,121 @@ mod tests {
         assert!(ANONYMIZER_CONTAINER_NAME.contains("presidio"));
     }
 
+    #[test]
+    fn test_run_args_default() {
+        let args = run_args(
+            ANALYZER_CONTAINER_NAME,
+            ANALYZER_IMAGE,
+            ANALYZER_PORT,
+            5002,
+            RestartPolicy::default(),
+            &AnalyzerContainerOptions::default(),
+        );
+
+        assert_eq!(args[0], "run");
+        assert!(args.contains(&"127.0.0.1:5002:5002".to_string()));
+        assert!(!args.contains(&"-e".to_string()));
+        assert!(!args.contains(&"--mount".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some(ANALYZER_IMAGE));
+    }
+
+    #[test]
+    fn test_run_args_with_env_and_volumes() {
+        use super::super::types::VolumeMount;
+
+        let mut options = AnalyzerContainerOptions::default();
+        options
+            .env
+            .insert("SPACY_MODEL".to_string(), "de_core_news_md".to_string());
+        options.volumes.push(VolumeMount {
+            host_path: "/opt/presidio/conf".to_string(),
+            container_path: "/app/conf".to_string(),
+            read_only: true,
+        });
+        options.volumes.push(VolumeMount {
+            host_path: "/opt/presidio/cache".to_string(),
+            container_path: "/cache".to_string(),
+            read_only: false,
+        });
+
+        let args = run_args(
+            ANALYZER_CONTAINER_NAME,
+            ANALYZER_IMAGE,
+            ANALYZER_PORT,
+            5002,
+            RestartPolicy::default(),
+            &options,
+        );
+
+        let flag_value = |flag: &str| -> Vec<String> {
+            args.windows(2)
+                .filter(|w| w[0] == flag)
+                .map(|w| w[1].clone())
+                .collect()
+        };
+
+        assert_eq!(flag_value("-e"), vec!["SPACY_MODEL=de_core_news_md"]);
+        assert_eq!(
+            flag_value("--mount"),
+            vec![
+                "type=bind,source=/opt/presidio/conf,target=/app/conf,readonly",
+                "type=bind,source=/opt/presidio/cache,target=/cache",
+            ]
+        );
+
+        // Options come before the image so Docker treats them as run flags
+        assert_eq!(args.last().map(String::as_str), Some(ANALYZER_IMAGE));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_analyzer_options_rejected() {
+        use super::super::types::VolumeMount;
+
+        let manager = PresidioDockerManager::new();
+        let mut options = AnalyzerContainerOptions::default();
+        options.volumes.push(VolumeMount {
+            host_path: "/var/run/docker.sock".to_string(),
+            container_path: "/var/run/docker.sock".to_string(),
+            read_only: true,
+        });
+
+        assert!(manager.set_analyzer_options(options).await.is_err());
+        assert_eq!(
+            manager.get_analyzer_options().await,
+            AnalyzerContainerOptions::default()
+        );
+    }
+
+    #[test]
+    fn test_run_args_restart_policy() {
+        for (policy, expected) in [
+            (RestartPolicy::No, "no"),
+            (RestartPolicy::OnFailure, "on-failure"),
+            (RestartPolicy::UnlessStopped, "unless-stopped"),
+        ] {
+            let args = run_args(
+                ANONYMIZER_CONTAINER_NAME,
+                ANONYMIZER_IMAGE,
+                ANONYMIZER_PORT,
+                5001,
+                policy,
+                &AnalyzerContainerOptions::default(),
+            );
+
+            let restart: Vec<&String> = args
+                .windows(2)
+                .filter(|w| w[0] == "--restart")
+                .map(|w| &w[1])
+                .collect();
+            assert_eq!(restart, vec![expected]);
+        }
+
+        // Docker reports a container created without a policy as ""
+        assert_eq!(RestartPolicy::from_docker("\n"), Some(RestartPolicy::No));
+        assert_eq!(RestartPolicy::from_docker("on-failure\n"), Some(RestartPolicy::OnFailure));
+        assert_eq!(RestartPolicy::from_docker("always"), None);
+    }
+
     #[test]
     fn test_ports_are_localhost() {
         // Verify ports are in valid range
@@ -0,0 +1,170 @@
+//! Storage for the Presidio encryption operator key
+//!
+//! The key lives in the OS keychain (Keychain, Credential Manager or the
+//! Secret Service) and is only read when an `encrypt` operator is sent to
+//! Presidio. It is never written to settings or accepted in request payloads.
+
+use anyhow::{Context, Result};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::Mutex;
+
+/// Keychain service name for keys stored by the app
+const KEYCHAIN_SERVICE: &str = "bear-llm-ai";
+
+/// Keychain account holding the Presidio encryption key
+const ENCRYPTION_KEY_ACCOUNT: &str = "presidio-encryption-key";
+
+/// Key lengths accepted by Presidio's AES encryption (128, 192 or 256 bits)
+const VALID_KEY_LENGTHS: &[usize] = &[16, 24, 32];
+
+/// Encryption key that never shows up in debug output or logs
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey(String);
+
+impl EncryptionKey {
+    /// Check the key length against what Presidio accepts
+    pub fn new(key: impl Into<String>) -> Result<Self> {
+        let key = key.into();
+        if !VALID_KEY_LENGTHS.contains(&key.len()) {
+            anyhow::bail!(
+                "Encryption key must be 16, 24 or 32 bytes long, got {}",
+                key.len()
+            );
+        }
+        Ok(Self(key))
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey([REDACTED])")
+    }
+}
+
+impl Serialize for EncryptionKey {
+    // Presidio needs the key itself in the anonymize request body
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// Where the encryption key is kept
+pub trait KeyStore: Send + Sync {
+    /// The stored key, if one has been set
+    fn get(&self) -> Result<Option<EncryptionKey>>;
+    fn set(&self, key: &EncryptionKey) -> Result<()>;
+    /// Remove the key; clearing a missing key is not an error
+    fn clear(&self) -> Result<()>;
+}
+
+/// Key store backed by the OS keychain
+pub struct OsKeyStore {
+    service: String,
+    account: String,
+}
+
+impl OsKeyStore {
+    pub fn new() -> Self {
+        Self {
+            service: KEYCHAIN_SERVICE.to_string(),
+            account: ENCRYPTION_KEY_ACCOUNT.to_string(),
+        }
+    }
+
+    fn entry(&self) -> Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, &self.account).context("Failed to open OS keychain")
+    }
+}
+
+impl Default for OsKeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyStore for OsKeyStore {
+    fn get(&self) -> Result<Option<EncryptionKey>> {
+        match self.entry()?.get_password() {
+            Ok(key) => EncryptionKey::new(key).map(Some),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read encryption key from OS keychain"),
+        }
+    }
+
+    fn set(&self, key: &EncryptionKey) -> Result<()> {
+        self.entry()?
+            .set_password(key.expose())
+            .context("Failed to store encryption key in OS keychain")
+    }
+
+    fn clear(&self) -> Result<()> {
+        match self.entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to remove encryption key from OS keychain"),
+        }
+    }
+}
+
+/// In-memory key store, for tests and platforms without a keychain
+#[derive(Default)]
+pub struct MemoryKeyStore {
+    key: Mutex<Option<EncryptionKey>>,
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn get(&self) -> Result<Option<EncryptionKey>> {
+        Ok(self.key.lock().unwrap().clone())
+    }
+
+    fn set(&self, key: &EncryptionKey) -> Result<()> {
+        *self.key.lock().unwrap() = Some(key.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self.key.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_length_validation() {
+        assert!(EncryptionKey::new("0123456789abcdef").is_ok());
+        assert!(EncryptionKey::new("0123456789abcdef01234567").is_ok());
+        assert!(EncryptionKey::new("0123456789abcdef0123456789abcdef").is_ok());
+
+        let err = EncryptionKey::new("short").unwrap_err();
+        assert!(err.to_string().contains("16, 24 or 32"));
+    }
+
+    #[test]
+    fn test_debug_output_is_redacted() {
+        let key = EncryptionKey::new("0123456789abcdef").unwrap();
+        let debug = format!("{:?}", key);
+        assert!(!debug.contains("0123456789abcdef"));
+        assert!(debug.contains("REDACTED"));
+    }
+
+    #[test]
+    fn test_memory_key_store() {
+        let store = MemoryKeyStore::default();
+        assert!(store.get().unwrap().is_none());
+
+        let key = EncryptionKey::new("0123456789abcdef").unwrap();
+        store.set(&key).unwrap();
+        assert_eq!(store.get().unwrap(), Some(key));
+
+        store.clear().unwrap();
+        store.clear().unwrap();
+        assert!(store.get().unwrap().is_none());
+    }
+}
@@ -5,7 +5,7 @@
 
 use std::collections::HashMap;
 
-use crate::pii::types::{Entity, EntityType};
+use crate::pii::types::{DetectionSource, Entity, EntityType};
 use super::types::PresidioEntity;
 
 /// Maps between Presidio entity types and internal entity types
@@ -126,32 +126,44 @@ impl EntityTypeMapper {
     }
 
     /// Convert a Presidio entity to internal Entity format
+    ///
+    /// Presidio reports offsets in code points (Python string indices); they
+    /// are converted to the byte offsets used internally.
     pub fn convert_entity(&self, presidio_entity: &PresidioEntity, text: &str) -> Option<Entity> {
-        let entity_type = self.to_internal(&presidio_entity.entity_type)?;
-
-        // Extract the actual text from the original
-        let entity_text = if presidio_entity.end <= text.len() {
-            text[presidio_entity.start..presidio_entity.end].to_string()
-        } else {
-            // Fallback if indices are out of bounds
-            return None;
-        };
-
-        Some(Entity::new(
-            entity_type,
-            entity_text,
-            presidio_entity.start,
-            presidio_entity.end,
-            presidio_entity.score,
-        ))
+        self.convert_with_offsets(presidio_entity, text, &char_byte_offsets(text))
     }
 
     /// Convert multiple Presidio entities to internal format
+    ///
+    /// Several recognizers can claim the same span (e.g. `PERSON` and
+    /// `LOCATION` over one name); each overlapping group becomes one entity
+    /// over the whole group, so nothing is anonymized twice or left half done.
     pub fn convert_entities(&self, presidio_entities: &[PresidioEntity], text: &str) -> Vec<Entity> {
-        presidio_entities
+        let offsets = char_byte_offsets(text);
+        let entities = presidio_entities
             .iter()
-            .filter_map(|e| self.convert_entity(e, text))
-            .collect()
+            .filter_map(|e| self.convert_with_offsets(e, text, &offsets))
+            .collect();
+        remove_overlaps(entities, text)
+    }
+
+    fn convert_with_offsets(
+        &self,
+        presidio_entity: &PresidioEntity,
+        text: &str,
+        offsets: &[usize],
+    ) -> Option<Entity> {
+        let entity_type = self.to_internal(&presidio_entity.entity_type)?;
+
+        // Out-of-bounds spans are dropped
+        let start = *offsets.get(presidio_entity.start)?;
+        let end = *offsets.get(presidio_entity.end)?;
+        let entity_text = text.get(start..end)?.to_string();
+
+        Some(
+            Entity::new(entity_type, entity_text, start, end, presidio_entity.score)
+                .with_source(DetectionSource::Presidio),
+        )
     }
 
     /// Get all Presidio types that map to a specific internal type
@@ -181,6 +193,60 @@ impl EntityTypeMapper {
     }
 }
 
+/// Merge each group of overlapping entities into one, sorted by start
+///
+/// The merged entity covers the union of the group's spans, so no part of a
+/// partly overlapping match is left in the text. It takes the type and score
+/// of the longest member, and of the higher score between equally long ones.
+fn remove_overlaps(mut entities: Vec<Entity>, text: &str) -> Vec<Entity> {
+    entities.sort_by_key(|e| e.start);
+
+    let mut groups: Vec<Vec<Entity>> = Vec::new();
+    let mut group_end = 0;
+    for entity in entities {
+        match groups.last_mut() {
+            Some(group) if entity.start < group_end => {
+                group_end = group_end.max(entity.end);
+                group.push(entity);
+            }
+            _ => {
+                group_end = entity.end;
+                groups.push(vec![entity]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let start = group.iter().map(|e| e.start).min()?;
+            let end = group.iter().map(|e| e.end).max()?;
+            let mut merged = group.into_iter().reduce(|best, e| {
+                let longer = (e.end - e.start).cmp(&(best.end - best.start));
+                if longer.then(e.confidence.total_cmp(&best.confidence)).is_gt() {
+                    e
+                } else {
+                    best
+                }
+            })?;
+            if (merged.start, merged.end) != (start, end) {
+                merged.start = start;
+                merged.end = end;
+                merged.text = text[start..end].to_string();
+            }
+            Some(merged)
+        })
+        .collect()
+}
+
+/// Byte offset of every code point in `text`, plus the end of the text
+fn char_byte_offsets(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(byte, _)| byte)
+        .chain(std::iter::once(text.len()))
+        .collect()
+}
+
 impl Default for EntityTypeMapper {
     fn default() -> Self {
         Self::new()
@@ -256,24 +322,32 @@ impl ConfidenceAdjuster {
         }
     }
 
+    /// Adjust confidence based on the sentence around the entity in `text`
+    pub fn adjust_confidence_in(&self, entity: &Entity, text: &str) -> f64 {
+        self.adjust_confidence(entity, sentence_context(text, entity.start, entity.end))
+    }
+
     /// Adjust confidence based on surrounding context
     pub fn adjust_confidence(&self, entity: &Entity, surrounding_text: &str) -> f64 {
-        let mut confidence = entity.confidence;
-
-        // Check for context keywords
-        if let Some(keywords) = self.context_keywords.get(&entity.entity_type) {
-            let lower_context = surrounding_text.to_lowercase();
-            for keyword in keywords {
-                if lower_context.contains(keyword) {
-                    confidence += 0.05;
-                }
-            }
-        }
+        let hits = self.keyword_hits(entity.entity_type, surrounding_text);
+        let confidence = entity.confidence + 0.05 * hits as f64;
 
         // Cap at 1.0
         confidence.min(1.0)
     }
 
+    /// Number of distinct context keywords for the type found in the text
+    pub fn keyword_hits(&self, entity_type: EntityType, surrounding_text: &str) -> usize {
+        let Some(keywords) = self.context_keywords.get(&entity_type) else {
+            return 0;
+        };
+        let lower_context = surrounding_text.to_lowercase();
+        keywords
+            .iter()
+            .filter(|keyword| lower_context.contains(keyword.as_str()))
+            .count()
+    }
+
     /// Filter entities by minimum confidence
     pub fn filter_by_confidence(&self, entities: Vec<Entity>) -> Vec<Entity> {
         entities
@@ -294,6 +368,74 @@ impl Default for ConfidenceAdjuster {
     }
 }
 
+/// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "jr", "sr", "st", "inc", "ltd", "corp", "co", "llc",
+    "e.g", "i.e", "cf", "vs", "v", "no", "nr", "art", "sec", "para", "p", "pp", "al", "approx",
+];
+
+/// The sentence or sentences of `text` that contain the byte range
+/// `start..end`, as context for keyword boosting
+///
+/// Sentences end at '.', '!' or '?' followed by whitespace, or at a blank
+/// line. A period after a known abbreviation ("Inc.", "e.g.") or a single
+/// capital initial ("J.") doesn't end a sentence.
+pub fn sentence_context(text: &str, start: usize, end: usize) -> &str {
+    let (from, to) = sentence_bounds(text, start, end);
+    &text[from..to]
+}
+
+/// Byte range of the sentence(s) containing `start..end`, without the
+/// whitespace around them
+pub fn sentence_bounds(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let mut start = start.min(text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = end.clamp(start, text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let from = text[..start]
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| ends_sentence(text, i, c))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    let to = text[end..]
+        .char_indices()
+        .find(|&(i, c)| ends_sentence(text, end + i, c))
+        .map(|(i, c)| end + i + c.len_utf8())
+        .unwrap_or(text.len());
+
+    let sentence = &text[from..to];
+    let leading = sentence.len() - sentence.trim_start().len();
+    (from + leading, from + sentence.trim_end().len())
+}
+
+/// Whether the character `c` at byte `i` ends a sentence
+fn ends_sentence(text: &str, i: usize, c: char) -> bool {
+    let rest = &text[i + c.len_utf8()..];
+    match c {
+        '\n' => rest.trim_start_matches([' ', '\t', '\r']).starts_with('\n'),
+        '!' | '?' => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        '.' => {
+            if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+                return false;
+            }
+            let word = text[..i]
+                .rsplit(char::is_whitespace)
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches(|c: char| !c.is_alphanumeric());
+            let is_initial = word.chars().count() == 1 && word.chars().all(char::is_uppercase);
+            !is_initial && !ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,6 +486,102 @@ mod tests {
         assert_eq!(entity.confidence, 0.95);
     }
 
+    #[test]
+    fn test_convert_entity_code_point_offsets() {
+        let mapper = EntityTypeMapper::new();
+        // Presidio (Python) reports "Zoë Müller" at code points 4..14
+        let text = "🎉 – Zoë Müller signed";
+
+        let presidio_entity = PresidioEntity {
+            entity_type: "PERSON".to_string(),
+            start: 4,
+            end: 14,
+            score: 0.9,
+            analysis_explanation: None,
+            recognition_metadata: None,
+        };
+
+        let entity = mapper.convert_entity(&presidio_entity, text).unwrap();
+
+        assert_eq!(entity.text, "Zoë Müller");
+        assert_eq!(&text[entity.start..entity.end], "Zoë Müller");
+    }
+
+    fn presidio(entity_type: &str, start: usize, end: usize, score: f64) -> PresidioEntity {
+        PresidioEntity {
+            entity_type: entity_type.to_string(),
+            start,
+            end,
+            score,
+            analysis_explanation: None,
+            recognition_metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_convert_entities_keeps_one_winner_per_overlap() {
+        let mapper = EntityTypeMapper::new();
+        let text = "Paris Hilton met Jan de Vries at jan@example.com";
+
+        let entities = mapper.convert_entities(
+            &[
+                // Same span claimed by two recognizers
+                presidio("LOCATION", 0, 5, 0.6),
+                presidio("PERSON", 0, 12, 0.85),
+                // A contained span loses to the covering one, whatever its score
+                presidio("PERSON", 17, 29, 0.7),
+                presidio("ORGANIZATION", 21, 29, 0.8),
+                // Equal scores: the longer span wins
+                presidio("URL", 37, 48, 1.0),
+                presidio("EMAIL_ADDRESS", 33, 48, 1.0),
+            ],
+            text,
+        );
+
+        let found: Vec<(EntityType, &str)> =
+            entities.iter().map(|e| (e.entity_type, e.text.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (EntityType::Person, "Paris Hilton"),
+                (EntityType::Person, "Jan de Vries"),
+                (EntityType::Email, "jan@example.com"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_entities_merges_partial_overlaps() {
+        let mapper = EntityTypeMapper::new();
+        let text = "Signed by Jan de Vries Holding";
+
+        // Neither span covers the other; the union leaves nothing behind
+        let entities = mapper.convert_entities(
+            &[presidio("PERSON", 10, 22, 0.9), presidio("ORGANIZATION", 14, 30, 0.6)],
+            text,
+        );
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].entity_type, EntityType::Organization);
+        assert_eq!(entities[0].text, "Jan de Vries Holding");
+        assert_eq!((entities[0].start, entities[0].end), (10, 30));
+    }
+
+    #[test]
+    fn test_convert_entities_keeps_adjacent_spans() {
+        let mapper = EntityTypeMapper::new();
+        let text = "John Doe, Amsterdam";
+
+        let entities = mapper.convert_entities(
+            &[presidio("LOCATION", 10, 19, 0.7), presidio("PERSON", 0, 8, 0.9)],
+            text,
+        );
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].text, "John Doe");
+        assert_eq!(entities[1].text, "Amsterdam");
+    }
+
     #[test]
     fn test_get_presidio_types_for() {
         let mapper = EntityTypeMapper::new();
@@ -353,6 +591,36 @@ mod tests {
         assert!(id_types.contains(&"CREDIT_CARD".to_string()));
     }
 
+    #[test]
+    fn test_sentence_context_around_entity() {
+        let text = "The witness left early. Acme Inc. hired counsel, e.g. Mr. Smith, \
+                    for the claimant Jane Roe. Ms. Roe's lawyer objected.";
+        let start = text.find("Jane Roe").unwrap();
+        let entity = Entity::new(EntityType::Person, "Jane Roe".to_string(), start, start + 8, 0.6);
+
+        let context = sentence_context(text, entity.start, entity.end);
+        assert_eq!(
+            context,
+            "Acme Inc. hired counsel, e.g. Mr. Smith, for the claimant Jane Roe."
+        );
+        assert!(context.contains("counsel"));
+        assert!(!context.contains("witness"));
+
+        // Only the cue in the entity's own sentence counts
+        let adjuster = ConfidenceAdjuster::new();
+        let hits = adjuster.keyword_hits(EntityType::Person, context);
+        assert_eq!(hits, 2); // "counsel", "mr."
+        assert!(adjuster.adjust_confidence_in(&entity, text) < adjuster.adjust_confidence(&entity, text));
+
+        // Multi-byte text and a blank line as a boundary
+        let text = "Première phrase ici.\n\nLe témoin Müller a signé… puis est parti! Fin.";
+        let start = text.find("Müller").unwrap();
+        assert_eq!(
+            sentence_context(text, start, start + "Müller".len()),
+            "Le témoin Müller a signé… puis est parti!"
+        );
+    }
+
     #[test]
     fn test_confidence_adjuster() {
         let adjuster = ConfidenceAdjuster::new();
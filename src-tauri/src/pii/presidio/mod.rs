@@ -18,19 +18,25 @@
 #![allow(dead_code)]
 
 pub mod types;
+pub mod chunking;
 pub mod docker;
 pub mod client;
+pub mod keystore;
 pub mod mapping;
 
 pub use types::*;
 pub use docker::PresidioDockerManager;
-pub use client::PresidioClient;
+pub use client::{PresidioClient, PresidioClientOptions, RecognizerInfo, RequestQueueStats};
+pub use keystore::{EncryptionKey, KeyStore, MemoryKeyStore, OsKeyStore};
 pub use mapping::EntityTypeMapper;
 
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::pii::language::Language;
+use crate::pii::types::{assign_utf16_offsets, Entity};
+
 /// Presidio integration status
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PresidioStatus {
@@ -46,28 +52,80 @@ pub enum PresidioStatus {
     Error(String),
 }
 
+impl PresidioStatus {
+    /// Status name as reported to the frontend
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotInstalled => "not_installed",
+            Self::Stopped => "stopped",
+            Self::Starting => "starting",
+            Self::Running => "running",
+            Self::Error(_) => "error",
+        }
+    }
+}
+
 /// Main Presidio integration manager
+///
+/// Clones share the containers, client, status and request queue.
+#[derive(Clone)]
 pub struct PresidioManager {
     docker_manager: Arc<PresidioDockerManager>,
-    client: Arc<PresidioClient>,
+    /// Replaced as a whole when the client options change
+    client: Arc<RwLock<Arc<PresidioClient>>>,
     status: Arc<RwLock<PresidioStatus>>,
     enabled: Arc<RwLock<bool>>,
+    /// Holds the key for `encrypt` operators
+    key_store: Arc<dyn KeyStore>,
 }
 
 impl PresidioManager {
     /// Create a new Presidio manager
     pub fn new() -> Self {
         let docker_manager = Arc::new(PresidioDockerManager::new());
-        let client = Arc::new(PresidioClient::new());
+        let client = Arc::new(RwLock::new(Arc::new(PresidioClient::new())));
 
         Self {
             docker_manager,
             client,
             status: Arc::new(RwLock::new(PresidioStatus::NotInstalled)),
             enabled: Arc::new(RwLock::new(false)),
+            key_store: Arc::new(OsKeyStore::new()),
+        }
+    }
+
+    /// Create a manager that talks to Presidio through the given client
+    pub fn with_client(client: PresidioClient) -> Self {
+        Self {
+            docker_manager: Arc::new(PresidioDockerManager::new()),
+            client: Arc::new(RwLock::new(Arc::new(client))),
+            status: Arc::new(RwLock::new(PresidioStatus::NotInstalled)),
+            enabled: Arc::new(RwLock::new(false)),
+            key_store: Arc::new(OsKeyStore::new()),
         }
     }
 
+    /// Keep the encryption key somewhere other than the OS keychain
+    pub fn with_key_store(mut self, key_store: Arc<dyn KeyStore>) -> Self {
+        self.key_store = key_store;
+        self
+    }
+
+    /// Store the key used by `encrypt` operators
+    pub fn set_encryption_key(&self, key: &str) -> Result<()> {
+        self.key_store.set(&EncryptionKey::new(key)?)
+    }
+
+    /// Remove the stored encryption key
+    pub fn clear_encryption_key(&self) -> Result<()> {
+        self.key_store.clear()
+    }
+
+    /// Whether an encryption key has been stored
+    pub fn has_encryption_key(&self) -> Result<bool> {
+        Ok(self.key_store.get()?.is_some())
+    }
+
     /// Check current status of Presidio
     pub async fn check_status(&self) -> Result<PresidioStatus> {
         let docker_status = self.docker_manager.check_container_status().await?;
@@ -86,7 +144,7 @@ impl PresidioManager {
             }
             docker::ContainerStatus::Running => {
                 // Verify health via API
-                if self.client.health_check().await.is_ok() {
+                if self.client().await.health_check().await.is_ok() {
                     PresidioStatus::Running
                 } else {
                     PresidioStatus::Starting
@@ -102,6 +160,33 @@ impl PresidioManager {
         Ok(status)
     }
 
+    /// Client for the current options
+    async fn client(&self) -> Arc<PresidioClient> {
+        self.client.read().await.clone()
+    }
+
+    /// Timeouts and retries used for requests to Presidio
+    pub async fn get_client_options(&self) -> PresidioClientOptions {
+        self.client().await.options().clone()
+    }
+
+    /// Change timeouts and retries; in-flight requests keep their old settings
+    pub async fn set_client_options(&self, options: PresidioClientOptions) -> Result<()> {
+        let mut client = self.client.write().await;
+        let rebuilt = PresidioClient::with_options(
+            client.analyzer_url().to_string(),
+            client.anonymizer_url().to_string(),
+            options,
+        )?;
+        *client = Arc::new(rebuilt);
+        Ok(())
+    }
+
+    /// Requests queued and in flight in the current client
+    pub async fn request_queue_stats(&self) -> RequestQueueStats {
+        self.client().await.queue_stats()
+    }
+
     /// Get cached status (does not query Docker)
     pub async fn get_cached_status(&self) -> PresidioStatus {
         self.status.read().await.clone()
@@ -196,7 +281,7 @@ impl PresidioManager {
         let delay = tokio::time::Duration::from_secs(2);
 
         for _ in 0..max_attempts {
-            if self.client.health_check().await.is_ok() {
+            if self.client().await.health_check().await.is_ok() {
                 return Ok(());
             }
             tokio::time::sleep(delay).await;
@@ -206,45 +291,132 @@ impl PresidioManager {
     }
 
     /// Analyze text for PII using Presidio
-    pub async fn analyze(&self, text: &str, language: &str) -> Result<Vec<PresidioEntity>> {
+    pub async fn analyze(&self, text: &str, language: &Language) -> Result<Vec<PresidioEntity>> {
         if !self.is_enabled().await {
             anyhow::bail!("Presidio is not enabled")
         }
 
-        self.client.analyze(text, language).await
+        self.client().await.analyze(text, presidio_language(language)?).await
+    }
+
+    /// Analyze text and map the results to internal entities
+    ///
+    /// Presidio types without an internal counterpart, and spans that do not
+    /// fit the text, are dropped rather than reported as errors.
+    pub async fn analyze_entities(&self, text: &str, language: &Language) -> Result<Vec<Entity>> {
+        let presidio_entities = self.analyze(text, language).await?;
+        let mut entities = EntityTypeMapper::new().convert_entities(&presidio_entities, text);
+        assign_utf16_offsets(&mut entities, text);
+        Ok(entities)
     }
 
     /// Anonymize text using Presidio
     pub async fn anonymize(
         &self,
         text: &str,
-        language: &str,
+        language: &Language,
         operators: Option<Vec<AnonymizationOperator>>,
     ) -> Result<PresidioAnonymizeResult> {
         if !self.is_enabled().await {
             anyhow::bail!("Presidio is not enabled")
         }
 
-        self.client.anonymize(text, language, operators).await
+        let operators = operators
+            .map(|ops| resolve_encryption_keys(ops, self.key_store.as_ref()))
+            .transpose()?;
+
+        self.client()
+            .await
+            .anonymize(text, presidio_language(language)?, operators)
+            .await
+    }
+
+    /// Anonymize text using Presidio, in chunks of `DEFAULT_CHUNK_SIZE`
+    /// bytes so large documents don't hit the container's timeouts
+    pub async fn anonymize_chunked<F>(
+        &self,
+        text: &str,
+        language: &Language,
+        operators: Option<Vec<AnonymizationOperator>>,
+        on_chunk: F,
+    ) -> Result<PresidioAnonymizeResult>
+    where
+        F: FnMut(&AnonymizedChunk),
+    {
+        if !self.is_enabled().await {
+            anyhow::bail!("Presidio is not enabled")
+        }
+
+        let operators = operators
+            .map(|ops| resolve_encryption_keys(ops, self.key_store.as_ref()))
+            .transpose()?;
+
+        self.client()
+            .await
+            .anonymize_chunked(
+                text,
+                presidio_language(language)?,
+                operators,
+                chunking::DEFAULT_CHUNK_SIZE,
+                on_chunk,
+            )
+            .await
     }
 
     /// Get supported entity types
     pub async fn get_supported_entities(&self) -> Result<Vec<String>> {
-        self.client.get_supported_entities().await
+        self.client().await.get_supported_entities().await
     }
 
-    /// Get supported languages
+    /// Get languages supported by the running analyzer
+    ///
+    /// Derived from the languages of the recognizers the analyzer has
+    /// actually loaded, rather than the languages Presidio could support.
     pub async fn get_supported_languages(&self) -> Result<Vec<String>> {
-        // Presidio default supported languages
-        Ok(vec![
-            "en".to_string(),
-            "de".to_string(),
-            "es".to_string(),
-            "fr".to_string(),
-            "it".to_string(),
-            "nl".to_string(),
-            "pt".to_string(),
-        ])
+        let recognizers = self.client().await.get_recognizers(None).await?;
+        Ok(languages_from_recognizers(&recognizers))
+    }
+
+    /// Get the union of languages supported by Presidio and the NER model
+    ///
+    /// Presidio being unreachable is not an error here: the NER layer (and the
+    /// regex layer) still work, so only the NER language is reported.
+    pub async fn get_available_languages(&self, ner_language: Option<&Language>) -> Vec<String> {
+        let mut languages = match self.get_supported_languages().await {
+            Ok(languages) => languages,
+            Err(e) => {
+                log::debug!("Presidio languages unavailable: {}", e);
+                Vec::new()
+            }
+        };
+
+        if let Some(lang) = ner_language {
+            languages.push(lang.as_ner().to_string());
+        }
+
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    /// Set env vars / volume mounts used when creating the analyzer container
+    pub async fn set_analyzer_options(&self, options: AnalyzerContainerOptions) -> Result<()> {
+        self.docker_manager.set_analyzer_options(options).await
+    }
+
+    /// Get the configured analyzer container options
+    pub async fn get_analyzer_options(&self) -> AnalyzerContainerOptions {
+        self.docker_manager.get_analyzer_options().await
+    }
+
+    /// Set the restart policy of the containers
+    pub async fn set_restart_policy(&self, policy: RestartPolicy) {
+        self.docker_manager.set_restart_policy(policy).await
+    }
+
+    /// Get the configured restart policy of the containers
+    pub async fn get_restart_policy(&self) -> RestartPolicy {
+        self.docker_manager.get_restart_policy().await
     }
 
     /// Check if Docker is available on the system
@@ -253,26 +425,328 @@ impl PresidioManager {
     }
 }
 
+/// Presidio analyzes one specific language at a time
+fn presidio_language(language: &Language) -> Result<&str> {
+    language
+        .as_presidio()
+        .ok_or_else(|| anyhow::anyhow!("Presidio needs a specific language, not '{}'", language))
+}
+
+/// Collect the distinct languages covered by a set of recognizers
+///
+/// Recognizers with an invalid language code are ignored.
+pub fn languages_from_recognizers(recognizers: &[RecognizerInfo]) -> Vec<String> {
+    let mut languages: Vec<String> = recognizers
+        .iter()
+        .filter_map(|r| r.supported_language.as_deref())
+        .filter_map(|lang| Language::parse(lang).ok())
+        .map(|lang| lang.as_ner().to_string())
+        .collect();
+
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
 impl Default for PresidioManager {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Fill `encrypt` operators with the stored key
+///
+/// The key store is only read when an `encrypt` operator is present.
+fn resolve_encryption_keys(
+    operators: Vec<AnonymizationOperator>,
+    key_store: &dyn KeyStore,
+) -> Result<Vec<AnonymizationOperator>> {
+    let mut stored_key = None;
+    operators
+        .into_iter()
+        .map(|operator| match operator {
+            AnonymizationOperator::Encrypt { .. } => {
+                if stored_key.is_none() {
+                    stored_key = Some(key_store.get()?.ok_or_else(|| {
+                        anyhow::anyhow!("No Presidio encryption key set. Set one before using the encrypt operator.")
+                    })?);
+                }
+                Ok(AnonymizationOperator::Encrypt { key: stored_key.clone() })
+            }
+            other => Ok(other),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const TEST_KEY: &str = "WmZq4t7w!z%C&F)J";
+
+    #[test]
+    fn test_encrypt_operator_uses_stored_key() {
+        let store = MemoryKeyStore::default();
+        let operators = vec![AnonymizationOperator::Encrypt { key: None }];
+
+        let err = resolve_encryption_keys(operators.clone(), &store).unwrap_err();
+        assert!(err.to_string().contains("No Presidio encryption key set"));
+
+        store.set(&EncryptionKey::new(TEST_KEY).unwrap()).unwrap();
+        let resolved = resolve_encryption_keys(operators, &store).unwrap();
+        match &resolved[0] {
+            AnonymizationOperator::Encrypt { key: Some(key) } => assert_eq!(key.expose(), TEST_KEY),
+            other => panic!("Expected resolved encrypt operator, got {:?}", other),
+        }
+
+        // Presidio receives the key, debug output never shows it
+        let mut operators = std::collections::HashMap::new();
+        operators.insert("PERSON".to_string(), resolved[0].clone());
+        let request = PresidioAnonymizeRequest {
+            text: "John Doe".to_string(),
+            analyzer_results: Vec::new(),
+            operators: Some(operators),
+        };
+        assert!(serde_json::to_string(&request).unwrap().contains(TEST_KEY));
+        assert!(!format!("{:?}", request).contains(TEST_KEY));
+        assert!(!format!("{:#?}", request).contains(TEST_KEY));
+    }
+
+    #[test]
+    fn test_encrypt_key_is_not_accepted_from_payloads() {
+        let operator: AnonymizationOperator =
+            serde_json::from_str(&format!(r#"{{"type": "encrypt", "key": "{}"}}"#, TEST_KEY)).unwrap();
+        assert!(matches!(operator, AnonymizationOperator::Encrypt { key: None }));
+    }
+
+    #[test]
+    fn test_other_operators_do_not_need_a_key() {
+        let store = MemoryKeyStore::default();
+        let operators = vec![AnonymizationOperator::Redact, AnonymizationOperator::Keep];
+        assert_eq!(resolve_encryption_keys(operators, &store).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_anonymize_fetches_key_from_store() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/analyze")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"entity_type": "PERSON", "start": 0, "end": 8, "score": 0.85}]"#)
+            .create_async()
+            .await;
+        let anonymize = server
+            .mock("POST", "/anonymize")
+            .match_body(mockito::Matcher::PartialJsonString(format!(
+                r#"{{"operators": {{"PERSON": {{"type": "encrypt", "key": "{}"}}}}}}"#,
+                TEST_KEY
+            )))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text": "<encrypted> wrote", "items": []}"#)
+            .create_async()
+            .await;
+
+        let store = Arc::new(MemoryKeyStore::default());
+        let manager =
+            PresidioManager::with_client(PresidioClient::with_endpoints(server.url(), server.url()))
+                .with_key_store(store.clone());
+        *manager.enabled.write().await = true;
+
+        assert!(!manager.has_encryption_key().unwrap());
+        assert!(manager.set_encryption_key("too short").is_err());
+        manager.set_encryption_key(TEST_KEY).unwrap();
+        assert!(manager.has_encryption_key().unwrap());
+
+        let operators = vec![AnonymizationOperator::Encrypt { key: None }];
+        let result = manager.anonymize("John Doe wrote", &Language::english(), Some(operators)).await.unwrap();
+        assert_eq!(result.text, "<encrypted> wrote");
+        anonymize.assert_async().await;
+
+        manager.clear_encryption_key().unwrap();
+        assert!(store.get().unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_presidio_manager_creation() {
         let manager = PresidioManager::new();
         assert!(!manager.is_enabled().await);
     }
 
+    #[tokio::test]
+    async fn test_cloned_manager_shares_queue() {
+        let manager = PresidioManager::new();
+        let shared = manager.clone();
+        let options = PresidioClientOptions {
+            max_concurrent_requests: 7,
+            ..PresidioClientOptions::default()
+        };
+
+        // Stats come from the client the commands rebuilt, not a copy
+        manager.set_client_options(options).await.unwrap();
+        assert_eq!(shared.request_queue_stats().await.max_concurrent, 7);
+    }
+
     #[tokio::test]
     async fn test_presidio_status_default() {
         let manager = PresidioManager::new();
         let status = manager.get_cached_status().await;
         assert_eq!(status, PresidioStatus::NotInstalled);
     }
+
+    #[test]
+    fn test_languages_from_recognizers() {
+        let recognizers = vec![
+            RecognizerInfo {
+                name: "SpacyRecognizer".to_string(),
+                supported_entities: vec!["PERSON".to_string()],
+                supported_language: Some("en".to_string()),
+            },
+            RecognizerInfo {
+                name: "EmailRecognizer".to_string(),
+                supported_entities: vec!["EMAIL_ADDRESS".to_string()],
+                supported_language: Some("en".to_string()),
+            },
+            RecognizerInfo {
+                name: "SpacyRecognizer".to_string(),
+                supported_entities: vec!["PERSON".to_string()],
+                supported_language: Some("DE".to_string()),
+            },
+            RecognizerInfo {
+                name: "SpacyRecognizer".to_string(),
+                supported_entities: vec!["PERSON".to_string()],
+                supported_language: Some("en_US".to_string()),
+            },
+            RecognizerInfo {
+                name: "Broken".to_string(),
+                supported_entities: vec![],
+                supported_language: Some("english".to_string()),
+            },
+            RecognizerInfo {
+                name: "Custom".to_string(),
+                supported_entities: vec![],
+                supported_language: None,
+            },
+        ];
+
+        assert_eq!(languages_from_recognizers(&recognizers), vec!["de", "en"]);
+    }
+
+    #[tokio::test]
+    async fn test_available_languages_from_recognizers_and_ner() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/recognizers")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"name": "SpacyRecognizer", "supported_entities": ["PERSON"], "supported_language": "en"},
+                    {"name": "SpacyRecognizer", "supported_entities": ["PERSON"], "supported_language": "es"},
+                    {"name": "IbanRecognizer", "supported_entities": ["IBAN_CODE"], "supported_language": "en"}
+                ]"#,
+            )
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let manager =
+            PresidioManager::with_client(PresidioClient::with_endpoints(server.url(), server.url()));
+
+        assert_eq!(manager.get_supported_languages().await.unwrap(), vec!["en", "es"]);
+        assert_eq!(
+            manager.get_available_languages(Some(&Language::parse("nl").unwrap())).await,
+            vec!["en", "es", "nl"]
+        );
+        assert_eq!(
+            manager.get_available_languages(Some(&Language::english())).await,
+            vec!["en", "es"]
+        );
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_analyze_entities_maps_to_internal_types() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/analyze")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"entity_type": "PERSON", "start": 0, "end": 8, "score": 0.85},
+                    {"entity_type": "EMAIL_ADDRESS", "start": 18, "end": 34, "score": 1.0},
+                    {"entity_type": "SOME_CUSTOM_TYPE", "start": 9, "end": 14, "score": 0.6},
+                    {"entity_type": "LOCATION", "start": 30, "end": 99, "score": 0.7}
+                ]"#,
+            )
+            .create_async()
+            .await;
+
+        let manager =
+            PresidioManager::with_client(PresidioClient::with_endpoints(server.url(), server.url()));
+        *manager.enabled.write().await = true;
+
+        let text = "John Doe wrote to john@example.com";
+        let entities = manager.analyze_entities(text, &Language::english()).await.unwrap();
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].entity_type, crate::pii::types::EntityType::Person);
+        assert_eq!(entities[0].text, "John Doe");
+        assert_eq!((entities[0].start, entities[0].end), (0, 8));
+        assert_eq!(entities[1].entity_type, crate::pii::types::EntityType::Email);
+        assert_eq!(entities[1].text, "john@example.com");
+        assert_eq!((entities[1].start, entities[1].end), (18, 34));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_client_options_keeps_endpoints() {
+        let manager = PresidioManager::with_client(PresidioClient::with_endpoints(
+            "http://127.0.0.1:9".to_string(),
+            "http://127.0.0.1:10".to_string(),
+        ));
+        let options = PresidioClientOptions {
+            request_timeout_ms: 1_000,
+            max_retries: 0,
+            ..PresidioClientOptions::default()
+        };
+
+        manager.set_client_options(options.clone()).await.unwrap();
+        assert_eq!(manager.get_client_options().await, options);
+        assert_eq!(manager.client().await.anonymizer_url(), "http://127.0.0.1:10");
+
+        let invalid = PresidioClientOptions {
+            connect_timeout_ms: 0,
+            ..PresidioClientOptions::default()
+        };
+        assert!(manager.set_client_options(invalid).await.is_err());
+        assert_eq!(manager.get_client_options().await, options);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_entities_requires_enabled() {
+        let manager = PresidioManager::with_client(PresidioClient::with_endpoints(
+            "http://127.0.0.1:9".to_string(),
+            "http://127.0.0.1:9".to_string(),
+        ));
+
+        assert!(manager.analyze_entities("John Doe", &Language::english()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_available_languages_without_presidio() {
+        // Nothing listens on port 9 (discard); Presidio is treated as absent
+        let manager = PresidioManager::with_client(PresidioClient::with_endpoints(
+            "http://127.0.0.1:9".to_string(),
+            "http://127.0.0.1:9".to_string(),
+        ));
+
+        assert!(manager.get_supported_languages().await.is_err());
+        assert_eq!(manager.get_available_languages(Some(&Language::parse("fr").unwrap())).await, vec!["fr"]);
+        assert!(manager.get_available_languages(None).await.is_empty());
+    }
 }
@@ -1,6 +1,10 @@
 //! Presidio-specific types and structures
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::keystore::EncryptionKey;
 
 /// Presidio entity types (comprehensive list)
 /// See: https://microsoft.github.io/presidio/supported_entities/
@@ -171,13 +175,32 @@ pub struct PresidioEntity {
     /// Confidence score (0.0 to 1.0)
     pub score: f64,
     /// Analysis explanation (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub analysis_explanation: Option<AnalysisExplanation>,
     /// Recognition metadata (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub recognition_metadata: Option<RecognitionMetadata>,
 }
 
+/// Deserialize an optional diagnostic field, treating a value of an
+/// unexpected shape as absent so it never fails the whole response
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
 /// Explanation for why an entity was detected
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisExplanation {
@@ -199,8 +222,12 @@ pub struct AnalysisExplanation {
     /// Supportive context words found
     #[serde(skip_serializing_if = "Option::is_none")]
     pub supportive_context_word: Option<String>,
-    /// Validation result
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Validation result (some Presidio versions report a boolean here)
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub validation_result: Option<f64>,
 }
 
@@ -236,9 +263,13 @@ pub enum AnonymizationOperator {
         chars_to_mask: usize,
         from_end: bool,
     },
-    /// Encrypt the value
+    /// Encrypt the value with the key from the key store
+    ///
+    /// The key is never accepted from callers; it is filled in right before
+    /// the request is sent to Presidio.
     Encrypt {
-        key: String,
+        #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+        key: Option<EncryptionKey>,
     },
     /// Keep the original value (no anonymization)
     Keep,
@@ -290,9 +321,9 @@ pub struct PresidioAnonymizeResult {
 /// An item that was anonymized
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnonymizedItem {
-    /// Start position in original text
+    /// Start position of the replacement in the anonymized text
     pub start: usize,
-    /// End position in original text
+    /// End position of the replacement in the anonymized text
     pub end: usize,
     /// Entity type
     pub entity_type: String,
@@ -303,6 +334,21 @@ pub struct AnonymizedItem {
     pub operator: String,
 }
 
+/// One chunk of a text anonymized in pieces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnonymizedChunk {
+    /// Position of the chunk among all chunks, from 0
+    pub index: usize,
+    pub total: usize,
+    /// Byte range of the chunk in the original text
+    pub original_start: usize,
+    pub original_end: usize,
+    /// Anonymized text of this chunk
+    pub text: String,
+    /// Items of this chunk, with offsets into the whole anonymized text
+    pub items: Vec<AnonymizedItem>,
+}
+
 /// Analyze request to Presidio
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresidioAnalyzeRequest {
@@ -354,6 +400,126 @@ impl Default for PresidioConfig {
     }
 }
 
+/// Docker restart policy of the Presidio containers
+///
+/// `unless-stopped` brings the containers back when Docker starts, e.g. on
+/// boot; policies that forbid services starting on their own need `no`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Only run while started by the app
+    No,
+    /// Restart after the container exits with an error
+    OnFailure,
+    /// Restart always, including when Docker starts, until explicitly stopped
+    #[default]
+    UnlessStopped,
+}
+
+impl RestartPolicy {
+    /// Value of `docker run --restart`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestartPolicy::No => "no",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::UnlessStopped => "unless-stopped",
+        }
+    }
+
+    /// Policy as reported by `docker inspect`, where an unset policy is empty
+    pub fn from_docker(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" | "no" => Some(RestartPolicy::No),
+            "on-failure" => Some(RestartPolicy::OnFailure),
+            "unless-stopped" => Some(RestartPolicy::UnlessStopped),
+            _ => None,
+        }
+    }
+}
+
+/// Extra options applied when the analyzer container is created
+///
+/// Typically used to run Presidio with a different spaCy model, e.g. by
+/// mounting an NLP engine configuration and pointing the analyzer at it via an
+/// environment variable.
+///
+/// Security: a volume mount gives the container access to that part of the host
+/// filesystem. Mounts are read-only unless explicitly made writable and should
+/// point at a dedicated directory holding only model/configuration files -
+/// never the Docker socket, a home directory, or folders containing client
+/// documents. Environment values are visible to anyone who can run
+/// `docker inspect`, so they must not carry secrets. Options only take effect
+/// when the container is (re)created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzerContainerOptions {
+    /// Environment variables passed with `-e KEY=VALUE`
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Host directories or files bind-mounted into the container
+    #[serde(default)]
+    pub volumes: Vec<VolumeMount>,
+}
+
+/// A bind mount from the host into the analyzer container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeMount {
+    /// Absolute path on the host
+    pub host_path: String,
+    /// Absolute path inside the container
+    pub container_path: String,
+    /// Mount read-only (default)
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+impl AnalyzerContainerOptions {
+    /// Reject options that would be ambiguous to Docker or unsafe to mount
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (key, value) in &self.env {
+            let valid_key = key
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key {
+                anyhow::bail!("Invalid environment variable name: {:?}", key);
+            }
+            if value.contains(['\n', '\r', '\0']) {
+                anyhow::bail!("Environment variable {} contains control characters", key);
+            }
+        }
+
+        for volume in &self.volumes {
+            // Commas separate fields in `--mount`
+            if volume.host_path.contains(',') || volume.container_path.contains(',') {
+                anyhow::bail!("Mount paths must not contain commas: {}", volume.host_path);
+            }
+            let host = Path::new(&volume.host_path);
+            if !host.is_absolute() {
+                anyhow::bail!("Mount host path must be absolute: {}", volume.host_path);
+            }
+            if host.parent().is_none() {
+                anyhow::bail!("Refusing to mount the filesystem root");
+            }
+            if host.file_name().is_some_and(|name| name == "docker.sock") {
+                anyhow::bail!("Refusing to mount the Docker socket");
+            }
+            if !volume.container_path.starts_with('/') {
+                anyhow::bail!(
+                    "Mount container path must be absolute: {}",
+                    volume.container_path
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,4 +549,34 @@ mod tests {
         let json = serde_json::to_string(&operator).unwrap();
         assert!(json.contains("mask"));
     }
+
+    #[test]
+    fn test_container_options_validation() {
+        let mut options = AnalyzerContainerOptions::default();
+        options.env.insert("SPACY_MODEL".to_string(), "nl_core_news_lg".to_string());
+        options.volumes.push(VolumeMount {
+            host_path: "/opt/presidio/conf".to_string(),
+            container_path: "/app/conf".to_string(),
+            read_only: true,
+        });
+        assert!(options.validate().is_ok());
+
+        let mut bad_key = options.clone();
+        bad_key.env.insert("1BAD".to_string(), "x".to_string());
+        assert!(bad_key.validate().is_err());
+
+        for host_path in ["relative/conf", "/", "/var/run/docker.sock", "/opt/a,b"] {
+            let mut bad_mount = options.clone();
+            bad_mount.volumes[0].host_path = host_path.to_string();
+            assert!(bad_mount.validate().is_err(), "{} should be rejected", host_path);
+        }
+    }
+
+    #[test]
+    fn test_volume_mount_defaults_to_read_only() {
+        let mount: VolumeMount =
+            serde_json::from_str(r#"{"host_path": "/opt/models", "container_path": "/models"}"#)
+                .unwrap();
+        assert!(mount.read_only);
+    }
 }
@@ -0,0 +1,258 @@
+//! Language-aware pseudonym generation
+//!
+//! Produces realistic stand-in names for persons, organizations and locations
+//! instead of bracketed placeholders. Word lists are bundled per language so a
+//! French document gets French-looking names; unknown languages fall back to
+//! English. The choice is a pure function of the original value, so the same
+//! input always yields the same pseudonym.
+
+use super::types::EntityType;
+
+/// Bundled word lists for one language
+struct WordLists {
+    first_names: &'static [&'static str],
+    last_names: &'static [&'static str],
+    organizations: &'static [&'static str],
+    organization_suffix: &'static str,
+    locations: &'static [&'static str],
+}
+
+const ENGLISH: WordLists = WordLists {
+    first_names: &[
+        "James", "Mary", "Robert", "Patricia", "Michael", "Linda", "William", "Susan",
+        "David", "Karen", "Thomas", "Emily", "Daniel", "Sarah", "Andrew", "Laura",
+    ],
+    last_names: &[
+        "Smith", "Johnson", "Carter", "Walker", "Bennett", "Harris", "Clarke", "Turner",
+        "Mitchell", "Cooper", "Hughes", "Morgan", "Parker", "Ellis", "Foster", "Graham",
+    ],
+    organizations: &[
+        "Northbridge", "Oakfield", "Silverline", "Redstone", "Bluewater", "Kingsway",
+        "Ashford", "Highgate", "Westbrook", "Lakeside",
+    ],
+    organization_suffix: "Ltd",
+    locations: &[
+        "Millbrook", "Fairview", "Greenfield", "Riverside", "Springdale", "Maplewood",
+        "Brookhaven", "Cedarville", "Hillcrest", "Stonebridge",
+    ],
+};
+
+const GERMAN: WordLists = WordLists {
+    first_names: &[
+        "Lukas", "Anna", "Jonas", "Lena", "Felix", "Julia", "Maximilian", "Sophie",
+        "Paul", "Marie", "Tobias", "Katharina", "Stefan", "Hannah", "Matthias", "Laura",
+    ],
+    last_names: &[
+        "Müller", "Schmidt", "Schneider", "Fischer", "Weber", "Meyer", "Wagner", "Becker",
+        "Schulz", "Hoffmann", "Koch", "Richter", "Klein", "Wolf", "Neumann", "Braun",
+    ],
+    organizations: &[
+        "Nordwerk", "Lindenhof", "Bergmann", "Sonnenfeld", "Eichenwald", "Rheintal",
+        "Altmark", "Hansewerk", "Waldstein", "Seeblick",
+    ],
+    organization_suffix: "GmbH",
+    locations: &[
+        "Lindenberg", "Rosenheim", "Eichstätt", "Waldkirch", "Bergfeld", "Neustadt",
+        "Falkenau", "Steinbach", "Hohenwald", "Mühlhausen",
+    ],
+};
+
+const FRENCH: WordLists = WordLists {
+    first_names: &[
+        "Jean", "Marie", "Pierre", "Camille", "Louis", "Élise", "François", "Claire",
+        "Antoine", "Sophie", "Nicolas", "Amélie", "Julien", "Céline", "Mathieu", "Chloé",
+    ],
+    last_names: &[
+        "Dubois", "Lefèvre", "Moreau", "Laurent", "Girard", "Fontaine", "Rousseau", "Mercier",
+        "Bonnet", "Lambert", "Chevalier", "Garnier", "Faure", "Marchand", "Leroy", "Duval",
+    ],
+    organizations: &[
+        "Beaumont", "Clairval", "Montrose", "Belleville", "Valmont", "Roseraie",
+        "Champlain", "Vieux-Pont", "Haute-Rive", "Bois-Joli",
+    ],
+    organization_suffix: "SARL",
+    locations: &[
+        "Saint-Amand", "Villeneuve", "Beaulieu", "Montreuil", "Châteauneuf", "Fontenay",
+        "Belmont", "Rochefort", "Valence-sur-Loire", "Pont-Aven",
+    ],
+};
+
+const DUTCH: WordLists = WordLists {
+    first_names: &[
+        "Daan", "Emma", "Sem", "Julia", "Lucas", "Sophie", "Bram", "Tess",
+        "Thijs", "Fleur", "Jesse", "Anouk", "Ruben", "Lotte", "Niels", "Sanne",
+    ],
+    last_names: &[
+        "de Jong", "Jansen", "de Vries", "van den Berg", "Bakker", "Visser", "Smit", "Meijer",
+        "de Boer", "Mulder", "de Groot", "Bos", "Vos", "Peters", "Hendriks", "Dekker",
+    ],
+    organizations: &[
+        "Noorderlicht", "Dijkstra", "Polderhof", "Waterland", "Molenaar", "Zeeduin",
+        "Lindehof", "Kroonstad", "Veldhoven", "Havenkade",
+    ],
+    organization_suffix: "B.V.",
+    locations: &[
+        "Zandvoort", "Oosterhout", "Westerveld", "Dijkhuizen", "Molendorp", "Veenendaal",
+        "Heuvelrug", "Brugwijk", "Lindeburg", "Zeewolde",
+    ],
+};
+
+const SPANISH: WordLists = WordLists {
+    first_names: &[
+        "Carlos", "María", "José", "Lucía", "Javier", "Carmen", "Miguel", "Elena",
+        "Pablo", "Isabel", "Diego", "Sofía", "Andrés", "Marta", "Alejandro", "Paula",
+    ],
+    last_names: &[
+        "García", "Martínez", "López", "Sánchez", "Romero", "Navarro", "Torres", "Ruiz",
+        "Ortega", "Delgado", "Castillo", "Molina", "Morales", "Vega", "Herrera", "Medina",
+    ],
+    organizations: &[
+        "Alameda", "Costa Verde", "Montesol", "Riberal", "Villaclara", "Sierra Alta",
+        "Puerto Azul", "Olivares", "Campomar", "Valdeluz",
+    ],
+    organization_suffix: "S.L.",
+    locations: &[
+        "Villanueva", "Sotomayor", "Valdemoro", "Torrealta", "Miraflores", "Fuentesol",
+        "Castellar", "Riosequillo", "Monteverde", "Peñaclara",
+    ],
+};
+
+const ITALIAN: WordLists = WordLists {
+    first_names: &[
+        "Marco", "Giulia", "Luca", "Francesca", "Matteo", "Chiara", "Alessandro", "Sara",
+        "Lorenzo", "Elena", "Davide", "Martina", "Andrea", "Valentina", "Simone", "Alessia",
+    ],
+    last_names: &[
+        "Rossi", "Russo", "Ferrari", "Esposito", "Bianchi", "Romano", "Colombo", "Ricci",
+        "Marino", "Greco", "Bruno", "Gallo", "Conti", "De Luca", "Mancini", "Costa",
+    ],
+    organizations: &[
+        "Montebello", "Valverde", "Bellavista", "Rocca Alta", "Pietrasanta", "Fiorenza",
+        "Campobasso", "Torre Chiara", "Lungomare", "Collina",
+    ],
+    organization_suffix: "S.r.l.",
+    locations: &[
+        "Castelnuovo", "Roccaverde", "Montalto", "Villafranca", "Pietralunga", "Borgonovo",
+        "Fontanella", "Sanbuco", "Serravalle", "Torricella",
+    ],
+};
+
+/// Deterministic pseudonym generator for one document language
+pub struct PseudonymGenerator {
+    lists: &'static WordLists,
+}
+
+impl PseudonymGenerator {
+    /// Create a generator for a language code (e.g. "fr", "de-DE"); unknown codes use English
+    pub fn new(language: &str) -> Self {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let lists = match primary.as_str() {
+            "de" => &GERMAN,
+            "fr" => &FRENCH,
+            "nl" => &DUTCH,
+            "es" => &SPANISH,
+            "it" => &ITALIAN,
+            _ => &ENGLISH,
+        };
+
+        Self { lists }
+    }
+
+    /// Entity types this generator can produce pseudonyms for
+    pub fn supports(entity_type: EntityType) -> bool {
+        matches!(
+            entity_type,
+            EntityType::Person | EntityType::Organization | EntityType::Location
+        )
+    }
+
+    /// Pseudonym for an original value
+    ///
+    /// `key` should be the canonical form of the value. `attempt` lets callers
+    /// pick an alternative when two originals land on the same pseudonym.
+    pub fn pseudonym(&self, entity_type: EntityType, key: &str, attempt: u32) -> Option<String> {
+        let hash = fnv1a(&format!("{}:{}:{}", entity_type.as_str(), key, attempt));
+        let high = (hash >> 32) as usize;
+        let low = hash as u32 as usize;
+
+        match entity_type {
+            EntityType::Person => Some(format!(
+                "{} {}",
+                pick(self.lists.first_names, low),
+                pick(self.lists.last_names, high)
+            )),
+            EntityType::Organization => Some(format!(
+                "{} {}",
+                pick(self.lists.organizations, low),
+                self.lists.organization_suffix
+            )),
+            EntityType::Location => Some(pick(self.lists.locations, low).to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn pick(words: &'static [&'static str], index: usize) -> &'static str {
+    words[index % words.len()]
+}
+
+/// 64-bit FNV-1a: stable across platforms and Rust versions, unlike `DefaultHasher`
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_french_pseudonyms() {
+        let generator = PseudonymGenerator::new("fr");
+        let name = generator.pseudonym(EntityType::Person, "jean dupont", 0).unwrap();
+
+        let (first, last) = name.split_once(' ').unwrap();
+        assert!(FRENCH.first_names.contains(&first));
+        assert!(FRENCH.last_names.contains(&last));
+
+        let org = generator.pseudonym(EntityType::Organization, "acme", 0).unwrap();
+        assert!(org.ends_with("SARL"));
+    }
+
+    #[test]
+    fn test_region_subtags_and_fallback() {
+        let name = PseudonymGenerator::new("de-AT")
+            .pseudonym(EntityType::Location, "wien", 0)
+            .unwrap();
+        assert!(GERMAN.locations.contains(&name.as_str()));
+
+        let name = PseudonymGenerator::new("xx")
+            .pseudonym(EntityType::Location, "wien", 0)
+            .unwrap();
+        assert!(ENGLISH.locations.contains(&name.as_str()));
+    }
+
+    #[test]
+    fn test_deterministic_per_value() {
+        let first = PseudonymGenerator::new("fr").pseudonym(EntityType::Person, "jean dupont", 0);
+        let second = PseudonymGenerator::new("fr").pseudonym(EntityType::Person, "jean dupont", 0);
+        assert_eq!(first, second);
+
+        let retry = PseudonymGenerator::new("fr").pseudonym(EntityType::Person, "jean dupont", 1);
+        assert_ne!(first, retry);
+    }
+
+    #[test]
+    fn test_unsupported_types() {
+        let generator = PseudonymGenerator::new("en");
+        assert!(generator.pseudonym(EntityType::Email, "a@b.com", 0).is_none());
+        assert!(!PseudonymGenerator::supports(EntityType::Email));
+        assert!(PseudonymGenerator::supports(EntityType::Person));
+    }
+}
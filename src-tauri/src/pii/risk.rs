@@ -0,0 +1,192 @@
+//! Re-identification risk of a document
+//!
+//! Gives one 0-100 indicator of how much a document reveals before it is
+//! shared. Every detected entity adds points by sensitivity: an ID number or
+//! date of birth identifies someone on its own, a city hardly does. Words
+//! that look like names but weren't detected add a little residual risk,
+//! since they may be names the detector missed.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::types::{Entity, EntityType};
+
+/// Points at which the score reaches about 63; the score approaches 100
+/// as points grow, so one more entity always counts but never overflows
+const SCALE: f64 = 40.0;
+
+/// Factor name of dates that are dates of birth
+pub const DATE_OF_BIRTH: &str = "DATE_OF_BIRTH";
+
+/// Factor name of capitalized words outside any detected entity
+pub const AMBIGUOUS_CAPITALIZED: &str = "AMBIGUOUS_CAPITALIZED";
+
+/// Words before a date that make it a date of birth; whole words only, so
+/// "Osborne" is no cue
+const BIRTH_CUES: &str = r"(?i)\b(?:born|birth\w*|dob|d\.o\.b)\b";
+
+/// How far before a date to look for a birth cue, in bytes
+const BIRTH_CUE_WINDOW: usize = 30;
+
+/// One source of risk and its share of the score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskFactor {
+    /// Entity type name, or `DATE_OF_BIRTH` / `AMBIGUOUS_CAPITALIZED`
+    pub factor: String,
+    pub count: usize,
+    /// Points per occurrence
+    pub weight: f64,
+    /// `count * weight`
+    pub points: f64,
+}
+
+/// Risk score of a document with the factors behind it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskScore {
+    /// 0 (nothing identifying found) to 100
+    pub score: u8,
+    /// Factors that contributed, highest points first
+    pub factors: Vec<RiskFactor>,
+}
+
+/// Points per detected entity of a type
+pub fn sensitivity(entity_type: EntityType) -> f64 {
+    match entity_type {
+        EntityType::Identification => 10.0,
+        EntityType::Person => 5.0,
+        EntityType::Email | EntityType::Phone => 5.0,
+        EntityType::TechnicalIdentifier | EntityType::Case => 3.0,
+        EntityType::Organization | EntityType::Money => 2.0,
+        EntityType::Location | EntityType::Date => 1.0,
+        EntityType::Law => 0.0,
+    }
+}
+
+/// Points per date of birth
+const DATE_OF_BIRTH_WEIGHT: f64 = 10.0;
+
+/// Points per capitalized word outside any entity
+const AMBIGUOUS_WEIGHT: f64 = 0.5;
+
+/// Score `text` given the entities detected in it
+pub fn risk_score(text: &str, entities: &[Entity]) -> RiskScore {
+    let mut counts: Vec<(String, usize, f64)> = Vec::new();
+    let mut add = |factor: &str, weight: f64| {
+        match counts.iter_mut().find(|(name, _, _)| name == factor) {
+            Some((_, count, _)) => *count += 1,
+            None => counts.push((factor.to_string(), 1, weight)),
+        }
+    };
+
+    for entity in entities {
+        if entity.entity_type == EntityType::Date && is_date_of_birth(text, entity) {
+            add(DATE_OF_BIRTH, DATE_OF_BIRTH_WEIGHT);
+        } else {
+            add(entity.entity_type.as_str(), sensitivity(entity.entity_type));
+        }
+    }
+    let ambiguous = ambiguous_capitalized(text, entities);
+    if ambiguous > 0 {
+        counts.push((AMBIGUOUS_CAPITALIZED.to_string(), ambiguous, AMBIGUOUS_WEIGHT));
+    }
+
+    let mut factors: Vec<RiskFactor> = counts
+        .into_iter()
+        .filter(|(_, _, weight)| *weight > 0.0)
+        .map(|(factor, count, weight)| RiskFactor {
+            factor,
+            count,
+            weight,
+            points: count as f64 * weight,
+        })
+        .collect();
+    factors.sort_by(|a, b| b.points.total_cmp(&a.points));
+
+    let points: f64 = factors.iter().map(|f| f.points).sum();
+    let score = (100.0 * (1.0 - (-points / SCALE).exp())).round() as u8;
+
+    RiskScore { score, factors }
+}
+
+/// Whether a birth cue ("born", "date of birth", "DOB") shortly precedes the date
+fn is_date_of_birth(text: &str, entity: &Entity) -> bool {
+    let mut from = entity.start.saturating_sub(BIRTH_CUE_WINDOW);
+    while !text.is_char_boundary(from) {
+        from += 1;
+    }
+    static CUE: OnceLock<Regex> = OnceLock::new();
+    let cue = CUE.get_or_init(|| Regex::new(BIRTH_CUES).unwrap());
+    cue.is_match(text.get(from..entity.start).unwrap_or_default())
+}
+
+/// Number of capitalized words outside entities that don't start a sentence
+fn ambiguous_capitalized(text: &str, entities: &[Entity]) -> usize {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"\b\p{Lu}\p{Ll}+\b").unwrap());
+    word.find_iter(text)
+        .filter(|m| entities.iter().all(|e| m.end() <= e.start || m.start() >= e.end))
+        .filter(|m| !starts_sentence(&text[..m.start()]))
+        .count()
+}
+
+/// Whether a word preceded by `before` is the first of its sentence or line
+fn starts_sentence(before: &str) -> bool {
+    match before.trim_end_matches([' ', '\t']).chars().last() {
+        None => true,
+        Some(c) => matches!(c, '.' | '!' | '?' | '\n' | ':' | '"' | '(' | '-'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pii::PIIDetector;
+
+    #[test]
+    fn test_ssns_score_higher_than_locations() {
+        let detector = PIIDetector::new();
+
+        let ssns = "Records: 123-45-6789, 234-56-7890, 345-67-8901 and 456-78-9012.";
+        let entities = detector.detect(ssns);
+        let ssn_risk = risk_score(ssns, &entities);
+
+        let places = "Offices at SW1A 1AA, M1 1AE, EH1 1YZ and CF10 1AA.";
+        let entities = detector.detect(places);
+        let place_risk = risk_score(places, &entities);
+
+        assert!(ssn_risk.score > place_risk.score, "{:?} vs {:?}", ssn_risk, place_risk);
+        assert_eq!(ssn_risk.factors[0].factor, "IDENTIFICATION");
+        assert_eq!(ssn_risk.factors[0].count, 4);
+        assert_eq!(place_risk.factors[0].factor, "LOCATION");
+        assert!(place_risk.score > 0);
+
+        assert_eq!(risk_score("nothing to see here.", &[]).score, 0);
+    }
+
+    #[test]
+    fn test_dates_of_birth_and_ambiguous_words() {
+        let text = "Signed 1 May 2024. The tenant, born 02/03/1980, lives with Maria.";
+        let entities = vec![
+            Entity::new(EntityType::Date, "1 May 2024".to_string(), 7, 17, 0.9),
+            Entity::new(EntityType::Date, "02/03/1980".to_string(), 36, 46, 0.9),
+        ];
+
+        let risk = risk_score(text, &entities);
+        let factor = |name: &str| risk.factors.iter().find(|f| f.factor == name).cloned();
+
+        assert_eq!(factor(DATE_OF_BIRTH).unwrap().count, 1);
+        assert_eq!(factor("DATE").unwrap().count, 1);
+        // "Signed" and "The" start sentences; "May" is inside a date
+        let ambiguous = factor(AMBIGUOUS_CAPITALIZED).unwrap();
+        assert_eq!(ambiguous.count, 1);
+        assert_eq!(risk.factors[0].factor, DATE_OF_BIRTH);
+        // A name containing "born" is no birth cue
+        let text = "Ms Osborne signed 02/03/1980.";
+        let start = text.find("02/03/1980").unwrap();
+        let date = Entity::new(EntityType::Date, "02/03/1980".to_string(), start, start + 10, 0.9);
+        let risk = risk_score(text, &[date]);
+        assert!(risk.factors.iter().all(|f| f.factor != DATE_OF_BIRTH));
+    }
+}
@@ -1,6 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt;
 
+use super::dates::DateOrder;
+
 /// Entity types that can be detected in text
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EntityType {
@@ -28,7 +32,59 @@ pub enum EntityType {
     TechnicalIdentifier,
 }
 
+/// Coarse grouping of entity types, used to group and color entities in the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityCategory {
+    /// Who someone is: names and identification numbers
+    Identity,
+    /// Companies, firms, courts
+    Organization,
+    /// Places and addresses
+    Location,
+    /// Ways to reach someone
+    Contact,
+    /// Monetary values
+    Financial,
+    /// Legal references and case numbers
+    Legal,
+    /// Dates and time references
+    Temporal,
+    /// IP addresses, URLs
+    Technical,
+}
+
+impl EntityCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityCategory::Identity => "identity",
+            EntityCategory::Organization => "organization",
+            EntityCategory::Location => "location",
+            EntityCategory::Contact => "contact",
+            EntityCategory::Financial => "financial",
+            EntityCategory::Legal => "legal",
+            EntityCategory::Temporal => "temporal",
+            EntityCategory::Technical => "technical",
+        }
+    }
+}
+
 impl EntityType {
+    /// Every entity type, in display order
+    pub const ALL: [EntityType; 11] = [
+        EntityType::Person,
+        EntityType::Organization,
+        EntityType::Location,
+        EntityType::Date,
+        EntityType::Money,
+        EntityType::Email,
+        EntityType::Phone,
+        EntityType::Case,
+        EntityType::Identification,
+        EntityType::TechnicalIdentifier,
+        EntityType::Law,
+    ];
+
     pub fn as_str(&self) -> &str {
         match self {
             EntityType::Person => "PERSON",
@@ -45,6 +101,37 @@ impl EntityType {
         }
     }
 
+    /// Category used to group entities in the UI
+    pub fn category(&self) -> EntityCategory {
+        match self {
+            EntityType::Person | EntityType::Identification => EntityCategory::Identity,
+            EntityType::Organization => EntityCategory::Organization,
+            EntityType::Location => EntityCategory::Location,
+            EntityType::Email | EntityType::Phone => EntityCategory::Contact,
+            EntityType::Money => EntityCategory::Financial,
+            EntityType::Law | EntityType::Case => EntityCategory::Legal,
+            EntityType::Date => EntityCategory::Temporal,
+            EntityType::TechnicalIdentifier => EntityCategory::Technical,
+        }
+    }
+
+    /// Human-readable name; stable, so the UI may key on it
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            EntityType::Person => "Person",
+            EntityType::Organization => "Organization",
+            EntityType::Location => "Location",
+            EntityType::Date => "Date",
+            EntityType::Money => "Money",
+            EntityType::Law => "Legal Reference",
+            EntityType::Case => "Case Number",
+            EntityType::Email => "Email",
+            EntityType::Phone => "Phone",
+            EntityType::Identification => "ID Number",
+            EntityType::TechnicalIdentifier => "Technical ID",
+        }
+    }
+
     pub fn should_anonymize(&self) -> bool {
         match self {
             // Legal references should be preserved
@@ -61,21 +148,56 @@ impl fmt::Display for EntityType {
     }
 }
 
+/// Detection layer that produced an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionSource {
+    /// Layer 1: regex patterns
+    Pattern,
+    /// Layer 2: NER model
+    Ner,
+    /// Layer 3: Presidio
+    Presidio,
+}
+
 /// A detected entity in text
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Serialized with the entity type's `category` and `display_name` alongside
+/// the stored fields, so every detection layer gives the UI the same shape.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Entity {
     /// Type of entity
     pub entity_type: EntityType,
     /// Original text of the entity
     pub text: String,
-    /// Start position in document (character index)
+    /// Start position in document (byte offset, used for slicing)
     pub start: usize,
-    /// End position in document (character index)
+    /// End position in document (byte offset, used for slicing)
     pub end: usize,
+    /// Start position in UTF-16 code units, as JavaScript indexes strings
+    #[serde(default)]
+    pub utf16_start: usize,
+    /// End position in UTF-16 code units, as JavaScript indexes strings
+    #[serde(default)]
+    pub utf16_end: usize,
     /// Confidence score (0.0 to 1.0)
     pub confidence: f64,
     /// Replacement text for anonymization
     pub replacement: Option<String>,
+    /// Detection layer that produced this entity, if known
+    #[serde(default)]
+    pub source: Option<DetectionSource>,
+    /// Whether a checksum (e.g. Luhn) confirmed the match
+    #[serde(default)]
+    pub validated: bool,
+    /// Recognized but deliberately left in place, e.g. a legal reference
+    /// kept by `preserve_legal_references`
+    #[serde(default)]
+    pub preserved: bool,
+    /// Confidence fell inside the settings' `review_band`, so the entity was
+    /// left in place for a person to decide on
+    #[serde(default)]
+    pub needs_review: bool,
 }
 
 impl Entity {
@@ -86,13 +208,20 @@ impl Entity {
         end: usize,
         confidence: f64,
     ) -> Self {
+        // UTF-16 offsets equal byte offsets for ASCII text; see `assign_utf16_offsets`
         Self {
             entity_type,
             text,
             start,
             end,
+            utf16_start: start,
+            utf16_end: end,
             confidence,
             replacement: None,
+            source: None,
+            validated: false,
+            preserved: false,
+            needs_review: false,
         }
     }
 
@@ -100,6 +229,115 @@ impl Entity {
         self.replacement = Some(replacement);
         self
     }
+
+    pub fn with_source(mut self, source: DetectionSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+/// Default for `AnonymizationSettings::min_entity_length`: the filter is off,
+/// since short entities like "$5" or "Li" are often real
+pub const DEFAULT_MIN_ENTITY_LENGTH: usize = 0;
+
+/// Drop entities shorter than `min_length` characters (e.g. a stray "Dr")
+///
+/// Checksum-validated matches, emails and identification numbers are kept
+/// whatever their length, as their patterns are specific enough on their own.
+pub fn drop_short_entities(entities: &mut Vec<Entity>, min_length: usize) {
+    entities.retain(|e| {
+        e.validated
+            || matches!(e.entity_type, EntityType::Email | EntityType::Identification)
+            || e.text.chars().count() >= min_length
+    });
+}
+
+/// Fill in the UTF-16 offsets of entities detected in `text`
+///
+/// Builds the byte-to-UTF-16 table once per document. Byte offsets that fall
+/// inside a character are rounded down to its start.
+pub fn assign_utf16_offsets(entities: &mut [Entity], text: &str) {
+    if text.is_ascii() {
+        for entity in entities.iter_mut() {
+            entity.utf16_start = entity.start;
+            entity.utf16_end = entity.end;
+        }
+        return;
+    }
+
+    // (byte offset, UTF-16 offset) at every char boundary, plus the end of text
+    let mut boundaries = Vec::with_capacity(text.len() + 1);
+    let mut utf16 = 0;
+    for (byte, ch) in text.char_indices() {
+        boundaries.push((byte, utf16));
+        utf16 += ch.len_utf16();
+    }
+    boundaries.push((text.len(), utf16));
+
+    let to_utf16 = |byte: usize| match boundaries.binary_search_by_key(&byte, |&(b, _)| b) {
+        Ok(i) => boundaries[i].1,
+        Err(i) => boundaries[i.saturating_sub(1)].1,
+    };
+
+    for entity in entities.iter_mut() {
+        entity.utf16_start = to_utf16(entity.start);
+        entity.utf16_end = to_utf16(entity.end);
+    }
+}
+
+impl Serialize for Entity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Entity", 14)?;
+        state.serialize_field("entity_type", &self.entity_type)?;
+        state.serialize_field("text", &self.text)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        state.serialize_field("utf16_start", &self.utf16_start)?;
+        state.serialize_field("utf16_end", &self.utf16_end)?;
+        state.serialize_field("confidence", &self.confidence)?;
+        state.serialize_field("replacement", &self.replacement)?;
+        state.serialize_field("source", &self.source)?;
+        state.serialize_field("validated", &self.validated)?;
+        state.serialize_field("preserved", &self.preserved)?;
+        state.serialize_field("needs_review", &self.needs_review)?;
+        state.serialize_field("category", &self.entity_type.category())?;
+        state.serialize_field("display_name", self.entity_type.display_name())?;
+        state.end()
+    }
+}
+
+/// Contiguous piece of a document for side-by-side review
+///
+/// A document's segments cover it exactly once, in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DiffSegment {
+    /// Text that is the same on both sides
+    Unchanged { text: String },
+    /// An entity and what replaced it
+    Replaced {
+        original: String,
+        replacement: String,
+        entity_type: EntityType,
+    },
+}
+
+impl DiffSegment {
+    /// Text of this segment in the original document
+    pub fn original(&self) -> &str {
+        match self {
+            Self::Unchanged { text } => text,
+            Self::Replaced { original, .. } => original,
+        }
+    }
+
+    /// Text of this segment in the anonymized document
+    pub fn anonymized(&self) -> &str {
+        match self {
+            Self::Unchanged { text } => text,
+            Self::Replaced { replacement, .. } => replacement,
+        }
+    }
 }
 
 /// Anonymization result
@@ -112,7 +350,17 @@ pub struct AnonymizationResult {
     /// Detected entities
     pub entities: Vec<Entity>,
     /// Mapping of original text to replacement
+    ///
+    /// Values masked or replaced with fixed text are left out: different
+    /// originals can share such a replacement, so it can't be mapped back.
     pub replacements: Vec<(String, String)>,
+    /// Number of entities replaced in this document, by type
+    #[serde(default)]
+    pub statistics: HashMap<EntityType, usize>,
+    /// High-confidence PII of a selected type still found in
+    /// `anonymized_text` by the verification pass, with offsets into it
+    #[serde(default)]
+    pub residual_pii: Vec<Entity>,
 }
 
 /// Anonymization settings
@@ -122,12 +370,322 @@ pub struct AnonymizationSettings {
     pub entity_types: Vec<EntityType>,
     /// Minimum confidence threshold (0.0 to 1.0)
     pub confidence_threshold: f64,
+    /// Per-type thresholds that take precedence over `confidence_threshold`
+    #[serde(default)]
+    pub entity_confidence_thresholds: HashMap<EntityType, f64>,
     /// Whether to preserve legal references
     pub preserve_legal_references: bool,
     /// Whether to use consistent replacement (same entity = same replacement)
     pub consistent_replacement: bool,
+    /// Whether consistent replacements carry over between the documents of
+    /// a batch or restart with each document
+    #[serde(default)]
+    pub consistency_scope: ConsistencyScope,
     /// Language code (e.g., "en", "nl", "de")
     pub language: String,
+    /// Replace persons, organizations and locations with realistic names in
+    /// the document language instead of bracketed placeholders
+    #[serde(default)]
+    pub pseudonymize: bool,
+    /// Flag capitalized word sequences as likely person names; turn off when
+    /// NER or Presidio covers persons, as the heuristic is noisy
+    #[serde(default = "default_use_name_heuristic")]
+    pub use_name_heuristic: bool,
+    /// How persons are replaced; when unset, persons follow `pseudonymize`
+    #[serde(default)]
+    pub person_style: Option<PersonStyle>,
+    /// Drop entities shorter than this many characters; emails, IDs and
+    /// checksum-validated matches are exempt. 0, the default, keeps all
+    #[serde(default = "default_min_entity_length")]
+    pub min_entity_length: usize,
+    /// Per-type replacement, like Presidio's per-type operators
+    #[serde(default)]
+    pub masking_strategies: HashMap<EntityType, MaskingStrategy>,
+    /// Replacement for types missing from `masking_strategies`
+    #[serde(default)]
+    pub default_masking_strategy: MaskingStrategy,
+    /// Placeholder format per type, e.g. "«Party {letter}»" instead of
+    /// "[PERSON-A]". `{index}` is the sequence number, `{letter}` the same
+    /// as a letter (A, B, ..., Z, AA); each template needs one of them.
+    #[serde(default)]
+    pub replacement_templates: HashMap<EntityType, String>,
+    /// How to read numeric dates such as "01/02/2024" when keying
+    /// replacements; when unset, follows `language`
+    #[serde(default)]
+    pub date_order: Option<DateOrder>,
+    /// Whether the anonymized text is scanned again for PII that got through
+    #[serde(default)]
+    pub verification: Verification,
+    /// What happens to base64 and hex blobs, e.g. embedded images
+    #[serde(default)]
+    pub embedded_data: EmbeddedData,
+    /// Confidence range `(low, high)` flagged for review: entities below
+    /// `low` are ignored, from `high` on replaced, and in between left in
+    /// place with `needs_review` set. Takes the place of the confidence
+    /// thresholds when set.
+    #[serde(default)]
+    pub review_band: Option<(f64, f64)>,
+}
+
+/// Sequence number placeholder in a replacement template
+pub const INDEX_PLACEHOLDER: &str = "{index}";
+
+/// Sequence letter placeholder in a replacement template
+pub const LETTER_PLACEHOLDER: &str = "{letter}";
+
+/// Check a replacement template has a sequence placeholder and nothing else in braces
+fn validate_replacement_template(template: &str) -> anyhow::Result<()> {
+    if !template.contains(INDEX_PLACEHOLDER) && !template.contains(LETTER_PLACEHOLDER) {
+        anyhow::bail!(
+            "Template '{}' needs {} or {}, or every entity would get the same replacement",
+            template,
+            INDEX_PLACEHOLDER,
+            LETTER_PLACEHOLDER
+        );
+    }
+
+    let rest = template
+        .replace(INDEX_PLACEHOLDER, "")
+        .replace(LETTER_PLACEHOLDER, "");
+    if rest.contains('{') || rest.contains('}') {
+        anyhow::bail!("Template '{}' has an unknown placeholder", template);
+    }
+    Ok(())
+}
+
+/// How `Anonymizer::merge_mappings` resolves conflicting mappings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// The first mapping is authoritative: conflicting entries of the second
+    /// are left out of the merged mapping
+    #[default]
+    PreferA,
+    /// Keep every entry: an original already mapped by the first takes its
+    /// replacement, and a replacement the first uses for another original is
+    /// swapped for an unused one
+    Renumber,
+}
+
+/// A conflict found while merging two replacement mappings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MappingConflict {
+    /// Both mappings replace the same original, differently
+    SameOriginal {
+        original: String,
+        replacement_a: String,
+        replacement_b: String,
+        /// Replacement of the second mapping's entry in the merged mapping;
+        /// `None` when the entry was left out
+        resolved: Option<String>,
+    },
+    /// Both mappings use the same replacement for different originals
+    SamePseudonym {
+        replacement: String,
+        original_a: String,
+        original_b: String,
+        /// Replacement of `original_b` in the merged mapping; `None` when
+        /// the entry was left out
+        resolved: Option<String>,
+    },
+}
+
+/// Result of `Anonymizer::merge_mappings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergedMapping {
+    /// Original text and replacement, as in `AnonymizationResult::replacements`
+    pub replacements: Vec<(String, String)>,
+    pub conflicts: Vec<MappingConflict>,
+}
+
+/// How far consistent replacements reach within a batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyScope {
+    /// Each document starts again at "[PERSON-A]"; repeats within a
+    /// document still share a replacement
+    PerDocument,
+    /// The same entity gets the same replacement in every document
+    #[default]
+    PerBatch,
+}
+
+/// Re-scan of anonymized text for PII that was missed or misplaced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    /// No re-scan
+    #[default]
+    Off,
+    /// Report what remains in `residual_pii`
+    Report,
+    /// Anonymize what remains in a second pass, then report anything still left
+    Repass,
+}
+
+/// Handling of embedded data blobs (base64 images, certificates, hashes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddedData {
+    /// Detect blobs as `TechnicalIdentifier` entities, replaced when that
+    /// type is selected
+    #[default]
+    Redact,
+    /// Remove blobs before detection, which speeds up documents full of
+    /// them; entity offsets then refer to the text without the blobs. Only
+    /// pattern detection (`Anonymizer::anonymize`) strips; entities from NER
+    /// or Presidio already point into the full text.
+    Strip,
+}
+
+/// What a batch does when one document fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Stop at the first failing document, keeping the results so far
+    AbortOnError,
+    /// Record the error and continue with the next document
+    #[default]
+    BestEffort,
+}
+
+/// How the local anonymizer replaces an entity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaskingStrategy {
+    /// Numbered placeholder or pseudonym, e.g. "[PERSON-A]", per `person_style`
+    /// and `pseudonymize`
+    #[default]
+    Placeholder,
+    /// Fixed text; not reversible, see `is_reversible`
+    Replace { new_value: String },
+    /// Remove the value entirely
+    Redact,
+    /// Overwrite `chars_to_mask` characters, from the start or from the end;
+    /// not reversible, see `is_reversible`
+    Mask {
+        masking_char: char,
+        chars_to_mask: usize,
+        from_end: bool,
+    },
+    /// Leave the value as it is
+    Keep,
+}
+
+impl MaskingStrategy {
+    /// Whether the replacement stands for one original only, so it is
+    /// recorded for `Anonymizer::restore`
+    ///
+    /// Masks and fixed text are shared by different originals (every
+    /// masked name can read "****"), so restoring them would guess.
+    pub fn is_reversible(&self) -> bool {
+        !matches!(self, Self::Replace { .. } | Self::Mask { .. })
+    }
+}
+
+/// Replacement style for `EntityType::Person`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PersonStyle {
+    /// Bracketed placeholder, e.g. "[PERSON-A]"
+    Bracket,
+    /// Initials, e.g. "J.D." for "John Doe"
+    Initials,
+    /// Realistic stand-in name in the document language
+    Pseudonym,
+}
+
+impl AnonymizationSettings {
+    /// Confidence threshold for an entity type, falling back to the global one
+    pub fn confidence_threshold_for(&self, entity_type: EntityType) -> f64 {
+        self.entity_confidence_thresholds
+            .get(&entity_type)
+            .copied()
+            .unwrap_or(self.confidence_threshold)
+    }
+
+    /// Confidence from which an entity of a type is kept: the review band's
+    /// lower bound if set, the type's threshold otherwise
+    pub fn min_confidence_for(&self, entity_type: EntityType) -> f64 {
+        match self.review_band {
+            Some((low, _)) => low,
+            None => self.confidence_threshold_for(entity_type),
+        }
+    }
+
+    /// Whether a kept entity is left for review instead of being replaced
+    pub fn needs_review(&self, confidence: f64) -> bool {
+        self.review_band.is_some_and(|(_, high)| confidence < high)
+    }
+
+    /// Masking strategy for an entity type, falling back to the default
+    pub fn masking_strategy(&self, entity_type: EntityType) -> &MaskingStrategy {
+        self.masking_strategies
+            .get(&entity_type)
+            .unwrap_or(&self.default_masking_strategy)
+    }
+
+    /// Replacement template for an entity type, if a valid one is configured
+    pub fn replacement_template(&self, entity_type: EntityType) -> Option<&str> {
+        self.replacement_templates
+            .get(&entity_type)
+            .map(String::as_str)
+            .filter(|template| validate_replacement_template(template).is_ok())
+    }
+
+    /// Reject replacement templates that could make different entities collide
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut seen: HashMap<&str, EntityType> = HashMap::new();
+        for entity_type in EntityType::ALL {
+            let Some(template) = self.replacement_templates.get(&entity_type) else {
+                continue;
+            };
+            validate_replacement_template(template)
+                .map_err(|e| e.context(format!("Invalid template for {}", entity_type)))?;
+            if let Some(other) = seen.insert(template.as_str(), entity_type) {
+                anyhow::bail!(
+                    "{} and {} share the template '{}', so their replacements would collide",
+                    other,
+                    entity_type,
+                    template
+                );
+            }
+        }
+
+        if let Some((low, high)) = self.review_band {
+            if !(0.0..=1.0).contains(&low) || !(0.0..=1.0).contains(&high) || low > high {
+                anyhow::bail!(
+                    "Review band {}-{} must lie within 0-1 with its lower bound first",
+                    low,
+                    high
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Date order in effect, falling back to the language's convention
+    pub fn date_order(&self) -> DateOrder {
+        self.date_order.unwrap_or_else(|| DateOrder::for_locale(&self.language))
+    }
+
+    /// Person style in effect, falling back to `pseudonymize` when not set
+    pub fn person_style(&self) -> PersonStyle {
+        self.person_style.unwrap_or(if self.pseudonymize {
+            PersonStyle::Pseudonym
+        } else {
+            PersonStyle::Bracket
+        })
+    }
+}
+
+fn default_use_name_heuristic() -> bool {
+    true
+}
+
+fn default_min_entity_length() -> usize {
+    DEFAULT_MIN_ENTITY_LENGTH
 }
 
 impl Default for AnonymizationSettings {
@@ -143,9 +701,22 @@ impl Default for AnonymizationSettings {
                 EntityType::Identification,
             ],
             confidence_threshold: 0.7,
+            entity_confidence_thresholds: HashMap::new(),
             preserve_legal_references: true,
             consistent_replacement: true,
+            consistency_scope: ConsistencyScope::default(),
             language: "en".to_string(),
+            pseudonymize: false,
+            use_name_heuristic: true,
+            person_style: None,
+            min_entity_length: DEFAULT_MIN_ENTITY_LENGTH,
+            masking_strategies: HashMap::new(),
+            default_masking_strategy: MaskingStrategy::default(),
+            replacement_templates: HashMap::new(),
+            date_order: None,
+            verification: Verification::default(),
+            embedded_data: EmbeddedData::default(),
+            review_band: None,
         }
     }
 }
@@ -161,6 +732,138 @@ mod tests {
         assert!(!EntityType::Law.should_anonymize());
     }
 
+    #[test]
+    fn test_every_type_has_category_and_display_name() {
+        let mut names = std::collections::HashSet::new();
+
+        for entity_type in EntityType::ALL {
+            assert!(!entity_type.category().as_str().is_empty());
+            assert!(!entity_type.display_name().is_empty());
+            assert!(
+                names.insert(entity_type.display_name()),
+                "duplicate display name for {}",
+                entity_type
+            );
+        }
+
+        assert_eq!(EntityType::Email.category(), EntityCategory::Contact);
+        assert_eq!(EntityType::Money.category(), EntityCategory::Financial);
+        assert_eq!(EntityType::Identification.category(), EntityCategory::Identity);
+        assert_eq!(EntityType::Case.category(), EntityCategory::Legal);
+    }
+
+    #[test]
+    fn test_entity_serializes_category_and_display_name() {
+        let entity = Entity::new(EntityType::Email, "a@b.com".to_string(), 0, 7, 1.0);
+        let json = serde_json::to_value(&entity).unwrap();
+
+        assert_eq!(json["entity_type"], "Email");
+        assert_eq!(json["category"], "contact");
+        assert_eq!(json["display_name"], "Email");
+
+        let parsed: Entity = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.entity_type, EntityType::Email);
+        assert_eq!(parsed.text, "a@b.com");
+    }
+
+    /// Entity spanning `needle` in `text`, by byte offsets
+    fn entity_for(text: &str, needle: &str) -> Entity {
+        let start = text.find(needle).unwrap();
+        Entity::new(EntityType::Person, needle.to_string(), start, start + needle.len(), 1.0)
+    }
+
+    #[test]
+    fn test_utf16_offsets_with_accents_and_emoji() {
+        // In JavaScript: text.indexOf("José") === 7, text.indexOf("Zoë") === 19
+        let text = "Hi 👋, José met 🎉 Zoë.";
+        let mut entities = vec![entity_for(text, "José"), entity_for(text, "Zoë")];
+
+        assign_utf16_offsets(&mut entities, text);
+
+        assert_eq!((entities[0].utf16_start, entities[0].utf16_end), (7, 11));
+        assert_eq!((entities[1].utf16_start, entities[1].utf16_end), (19, 22));
+
+        // Byte offsets are left alone for slicing
+        assert_eq!(&text[entities[0].start..entities[0].end], "José");
+        assert_eq!(&text[entities[1].start..entities[1].end], "Zoë");
+    }
+
+    #[test]
+    fn test_utf16_offsets_match_encode_utf16() {
+        let text = "Ærø 𝔘𝔫𝔦𝔠𝔬𝔡𝔢 naïve café";
+        let mut entities = vec![entity_for(text, "naïve"), entity_for(text, "café")];
+
+        assign_utf16_offsets(&mut entities, text);
+
+        let units: Vec<u16> = text.encode_utf16().collect();
+        for entity in &entities {
+            let highlighted =
+                String::from_utf16(&units[entity.utf16_start..entity.utf16_end]).unwrap();
+            assert_eq!(highlighted, entity.text);
+        }
+    }
+
+    #[test]
+    fn test_utf16_offsets_ascii() {
+        let text = "Contact John Doe";
+        let mut entities = vec![entity_for(text, "John Doe")];
+        assign_utf16_offsets(&mut entities, text);
+        assert_eq!((entities[0].utf16_start, entities[0].utf16_end), (8, 16));
+    }
+
+    #[test]
+    fn test_entity_confidence_threshold_overrides() {
+        let settings = AnonymizationSettings {
+            confidence_threshold: 0.75,
+            entity_confidence_thresholds: HashMap::from([
+                (EntityType::Person, 0.9),
+                (EntityType::Email, 0.6),
+            ]),
+            ..Default::default()
+        };
+        let entity = |entity_type, confidence| {
+            Entity::new(entity_type, "x".to_string(), 0, 1, confidence)
+        };
+        let passes = |e: &Entity| e.confidence >= settings.confidence_threshold_for(e.entity_type);
+
+        // The person clears the global threshold but not its own
+        assert!(!passes(&entity(EntityType::Person, 0.8)));
+        // The email misses the global threshold but clears its own
+        assert!(passes(&entity(EntityType::Email, 0.7)));
+        // Types without an override use the global threshold
+        assert!(passes(&entity(EntityType::Phone, 0.8)));
+        assert!(!passes(&entity(EntityType::Phone, 0.7)));
+    }
+
+    #[test]
+    fn test_drop_short_entities() {
+        let entity = |entity_type, text: &str| {
+            Entity::new(entity_type, text.to_string(), 0, text.len(), 0.8)
+        };
+        let mut card = entity(EntityType::Money, "42");
+        card.validated = true;
+
+        let mut entities = vec![
+            entity(EntityType::Person, "Dr"),
+            entity(EntityType::Person, "J"),
+            // Two characters but three bytes: length is counted in characters
+            entity(EntityType::Person, "Lé"),
+            entity(EntityType::Person, "Zoë"),
+            entity(EntityType::Identification, "A1"),
+            entity(EntityType::Email, "a@b"),
+            card,
+        ];
+        drop_short_entities(&mut entities, 3);
+
+        let kept: Vec<&str> = entities.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(kept, vec!["Zoë", "A1", "a@b", "42"]);
+
+        // 0 disables the filter
+        let mut entities = vec![entity(EntityType::Person, "J")];
+        drop_short_entities(&mut entities, 0);
+        assert_eq!(entities.len(), 1);
+    }
+
     #[test]
     fn test_entity_creation() {
         let entity = Entity::new(
@@ -175,4 +878,39 @@ mod tests {
         assert_eq!(entity.text, "John Doe");
         assert_eq!(entity.confidence, 0.95);
     }
+
+    #[test]
+    fn test_replacement_template_validation() {
+        let with_templates = |templates: &[(EntityType, &str)]| AnonymizationSettings {
+            replacement_templates: templates
+                .iter()
+                .map(|(entity_type, template)| (*entity_type, template.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+
+        let valid = with_templates(&[
+            (EntityType::Person, "«Party {letter}»"),
+            (EntityType::Email, "EMAIL_{index}"),
+        ]);
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.replacement_template(EntityType::Person), Some("«Party {letter}»"));
+        assert_eq!(valid.replacement_template(EntityType::Phone), None);
+
+        let no_index = with_templates(&[(EntityType::Person, "«Party»")]);
+        let err = no_index.validate().unwrap_err();
+        assert!(format!("{:#}", err).contains("needs {index} or {letter}"));
+        // An invalid template is never used, even if validation was skipped
+        assert_eq!(no_index.replacement_template(EntityType::Person), None);
+
+        let unknown = with_templates(&[(EntityType::Person, "{name} {index}")]);
+        assert!(format!("{:#}", unknown.validate().unwrap_err()).contains("unknown placeholder"));
+
+        let shared = with_templates(&[
+            (EntityType::Person, "Party {letter}"),
+            (EntityType::Organization, "Party {letter}"),
+        ]);
+        assert!(shared.validate().unwrap_err().to_string().contains("would collide"));
+    }
+
 }
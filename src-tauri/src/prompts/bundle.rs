@@ -0,0 +1,364 @@
+//! Portable bundles of the user's prompts and templates
+//!
+//! A bundle is a zip holding the files of `prompts/user` under `prompts/`
+//! and those of `prompts/templates/user` under `templates/`, plus a
+//! `manifest.json` listing them. Built-ins are never bundled: every install
+//! regenerates them with `PromptLibrary::initialize`.
+//!
+//! A prompt's id is its file name without extension, so an imported file
+//! whose name is already taken by different content gets a fresh id instead
+//! of overwriting the existing item.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use super::{
+    is_prompt_file, parse_prompt_file, parse_prompt_str, read_archived_text, PromptLibrary,
+};
+
+/// Name of the manifest inside a bundle
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Bundle layout version written by this build
+const FORMAT_VERSION: u32 = 1;
+
+/// Kind of library item in a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryItemKind {
+    Prompt,
+    Template,
+}
+
+impl LibraryItemKind {
+    /// Top-level folder of this kind inside a bundle
+    fn folder(&self) -> &'static str {
+        match self {
+            Self::Prompt => "prompts",
+            Self::Template => "templates",
+        }
+    }
+}
+
+/// One prompt or template in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleItem {
+    pub kind: LibraryItemKind,
+    /// File name without extension
+    pub id: String,
+    pub name: String,
+    /// Path inside the archive, e.g. "prompts/contracts/review.md"
+    pub path: String,
+}
+
+/// Contents of `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryManifest {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub items: Vec<BundleItem>,
+}
+
+/// What happened to one bundled item on import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LibraryImportOutcome {
+    /// Written under its original id
+    Imported,
+    /// The id was taken by a different item, so it got a new one
+    Renamed,
+    /// An identical file was already in the library
+    Unchanged,
+    /// The item was skipped
+    Failed { reason: String },
+}
+
+/// Outcome of importing one item from a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryImportResult {
+    pub kind: LibraryItemKind,
+    pub name: String,
+    /// Id in the bundle
+    pub original_id: String,
+    /// Id in this library, if the item is in it
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub outcome: LibraryImportOutcome,
+}
+
+impl PromptLibrary {
+    /// Folder holding the user's items of a kind
+    fn user_folder(&self, kind: LibraryItemKind) -> PathBuf {
+        match kind {
+            LibraryItemKind::Prompt => self.user_dir.clone(),
+            LibraryItemKind::Template => self.templates_dir.join("user"),
+        }
+    }
+
+    /// Zip the user's prompts and templates, with a manifest, into `path`
+    pub fn export_library(&self, path: &Path) -> Result<LibraryManifest> {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create library bundle: {:?}", path))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+
+        let mut items = Vec::new();
+        for kind in [LibraryItemKind::Prompt, LibraryItemKind::Template] {
+            let dir = self.user_folder(kind);
+            let mut files: Vec<PathBuf> = WalkDir::new(&dir)
+                .follow_links(false)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.into_path())
+                .filter(|path| path.is_file() && is_prompt_file(&path.to_string_lossy()))
+                .collect();
+            files.sort();
+
+            for file in files {
+                let relative = file.strip_prefix(&dir).unwrap_or(&file);
+                let archive_path = std::iter::once(kind.folder().to_string())
+                    .chain(relative.iter().map(|part| part.to_string_lossy().to_string()))
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let id = file_id(&file);
+                let name = parse_prompt_file(&file)
+                    .map(|prompt| prompt.name)
+                    .unwrap_or_else(|_| id.clone());
+
+                let bytes = fs::read(&file)
+                    .with_context(|| format!("Failed to read {:?}", file))?;
+                zip.start_file(archive_path.as_str(), options)?;
+                zip.write_all(&bytes)?;
+
+                items.push(BundleItem { kind, id, name, path: archive_path });
+            }
+        }
+
+        let manifest = LibraryManifest {
+            format_version: FORMAT_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            items,
+        };
+        zip.start_file(MANIFEST_NAME, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+        zip.finish().context("Failed to write library bundle")?;
+
+        Ok(manifest)
+    }
+
+    /// Restore the prompts and templates listed in a bundle's manifest
+    ///
+    /// Items are reported one by one; only an unreadable bundle or manifest
+    /// is an error.
+    pub fn import_library(&self, path: &Path) -> Result<Vec<LibraryImportResult>> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open library bundle: {:?}", path))?;
+        let mut archive = zip::ZipArchive::new(file).context("Failed to read library bundle")?;
+
+        let manifest: LibraryManifest = {
+            let mut entry = archive
+                .by_name(MANIFEST_NAME)
+                .context("Not a library bundle: manifest.json is missing")?;
+            let content = read_archived_text(&mut entry)?;
+            serde_json::from_str(&content).context("Invalid library manifest")?
+        };
+        if manifest.format_version > FORMAT_VERSION {
+            anyhow::bail!(
+                "Library bundle format {} is newer than this app supports ({})",
+                manifest.format_version,
+                FORMAT_VERSION
+            );
+        }
+
+        let mut results = Vec::new();
+        for item in manifest.items {
+            let (id, outcome) = match self.import_bundle_item(&mut archive, &item) {
+                Ok((id, outcome)) => (Some(id), outcome),
+                Err(e) => (None, LibraryImportOutcome::Failed { reason: format!("{:#}", e) }),
+            };
+            results.push(LibraryImportResult {
+                kind: item.kind,
+                name: item.name,
+                original_id: item.id,
+                id,
+                outcome,
+            });
+        }
+
+        Ok(results)
+    }
+
+    fn import_bundle_item(
+        &self,
+        archive: &mut zip::ZipArchive<fs::File>,
+        item: &BundleItem,
+    ) -> Result<(String, LibraryImportOutcome)> {
+        let mut entry = archive
+            .by_name(&item.path)
+            .with_context(|| format!("{} is listed but not in the bundle", item.path))?;
+        let relative = entry
+            .enclosed_name()
+            .and_then(|path| path.strip_prefix(item.kind.folder()).ok().map(Path::to_path_buf))
+            .filter(|relative| is_prompt_file(&relative.to_string_lossy()))
+            .context("Unsafe path in bundle")?;
+
+        let content = read_archived_text(&mut entry)?;
+        parse_prompt_str(&content, &relative)?;
+
+        let mut target = self.user_folder(item.kind).join(&relative);
+        let mut outcome = LibraryImportOutcome::Imported;
+        if target.exists() {
+            if fs::read_to_string(&target).ok().as_deref() == Some(content.as_str()) {
+                return Ok((file_id(&target), LibraryImportOutcome::Unchanged));
+            }
+
+            let extension = relative.extension().and_then(|ext| ext.to_str()).unwrap_or("md");
+            target.set_file_name(format!("{}.{}", Uuid::new_v4(), extension));
+            outcome = LibraryImportOutcome::Renamed;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, content).with_context(|| format!("Failed to write {:?}", target))?;
+        Ok((file_id(&target), outcome))
+    }
+}
+
+/// Id of a library file: its name without extension
+fn file_id(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompts::{Prompt, MAX_ARCHIVED_FILE_BYTES};
+    use crate::templates::{DocumentTemplate, TemplateLibrary};
+
+    #[test]
+    fn test_export_and_import_into_fresh_library() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = PromptLibrary::new(source_dir.path().to_path_buf()).unwrap();
+        source.initialize().unwrap();
+
+        let mut review = Prompt::new("Lease review".to_string(), "Review {LEASE}.".to_string());
+        review.category = "contracts/lease".to_string();
+        source.save_prompt(&review).unwrap();
+        let templates = TemplateLibrary::new(source_dir.path().to_path_buf()).unwrap();
+        let nda = DocumentTemplate::new("NDA".to_string(), "NDA with {PARTY}.".to_string());
+        templates.save_template(&nda).unwrap();
+
+        let bundle = source_dir.path().join("library.zip");
+        let manifest = source.export_library(&bundle).unwrap();
+
+        // Built-in prompts stay out of the bundle
+        let names: Vec<(LibraryItemKind, &str)> =
+            manifest.items.iter().map(|item| (item.kind, item.name.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![(LibraryItemKind::Prompt, "Lease review"), (LibraryItemKind::Template, "NDA")]
+        );
+        assert_eq!(manifest.items[0].id, review.id);
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target = PromptLibrary::new(target_dir.path().to_path_buf()).unwrap();
+        let results = target.import_library(&bundle).unwrap();
+        assert!(results.iter().all(|r| r.outcome == LibraryImportOutcome::Imported));
+        assert_eq!(results[0].id.as_deref(), Some(review.id.as_str()));
+
+        let prompts = target.load_all_prompts().unwrap();
+        let imported = prompts.iter().find(|p| p.name == "Lease review").unwrap();
+        assert!(!imported.is_builtin);
+        assert_eq!(imported.category, "contracts/lease");
+        assert_eq!(imported.variables, vec!["LEASE".to_string()]);
+
+        let templates = TemplateLibrary::new(target_dir.path().to_path_buf()).unwrap();
+        let loaded = templates.load_all().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].content, "NDA with {PARTY}.");
+    }
+
+    #[test]
+    fn test_import_handles_id_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let prompt = Prompt::new("Summary".to_string(), "Summarize {TEXT}.".to_string());
+        library.save_prompt(&prompt).unwrap();
+
+        let bundle = dir.path().join("library.zip");
+        library.export_library(&bundle).unwrap();
+
+        // Re-importing the same file changes nothing
+        let results = library.import_library(&bundle).unwrap();
+        assert_eq!(results[0].outcome, LibraryImportOutcome::Unchanged);
+        assert_eq!(library.load_all_prompts().unwrap().len(), 1);
+
+        // A different prompt under the same id is kept and the import renamed
+        let mut edited = prompt.clone();
+        edited.content = "Summarize {TEXT} in three bullets.".to_string();
+        library.save_prompt(&edited).unwrap();
+        let results = library.import_library(&bundle).unwrap();
+        assert_eq!(results[0].outcome, LibraryImportOutcome::Renamed);
+        assert_ne!(results[0].id.as_deref(), Some(prompt.id.as_str()));
+
+        let mut contents: Vec<String> =
+            library.load_all_prompts().unwrap().into_iter().map(|p| p.content).collect();
+        contents.sort();
+        assert_eq!(contents, vec!["Summarize {TEXT} in three bullets.", "Summarize {TEXT}."]);
+    }
+
+    #[test]
+    fn test_import_requires_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let bundle = dir.path().join("pack.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&bundle).unwrap());
+        zip.start_file("prompts/a.md", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Body").unwrap();
+        zip.finish().unwrap();
+
+        let err = library.import_library(&bundle).unwrap_err();
+        assert!(err.to_string().contains("manifest.json is missing"));
+    }
+
+    #[test]
+    fn test_import_rejects_oversized_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let bundle = dir.path().join("library.zip");
+        let manifest = LibraryManifest {
+            format_version: FORMAT_VERSION,
+            exported_at: "2025-01-01T00:00:00Z".to_string(),
+            items: vec![BundleItem {
+                kind: LibraryItemKind::Prompt,
+                id: "huge".to_string(),
+                name: "Huge".to_string(),
+                path: "prompts/huge.md".to_string(),
+            }],
+        };
+
+        let options = zip::write::SimpleFileOptions::default();
+        let mut zip = zip::ZipWriter::new(fs::File::create(&bundle).unwrap());
+        zip.start_file(MANIFEST_NAME, options).unwrap();
+        zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes()).unwrap();
+        zip.start_file("prompts/huge.md", options).unwrap();
+        zip.write_all(&vec![b'a'; MAX_ARCHIVED_FILE_BYTES as usize + 1]).unwrap();
+        zip.finish().unwrap();
+
+        let results = library.import_library(&bundle).unwrap();
+        match &results[0].outcome {
+            LibraryImportOutcome::Failed { reason } => assert!(reason.contains("larger than")),
+            other => panic!("expected a failure, got {:?}", other),
+        }
+        assert!(library.load_all_prompts().unwrap().is_empty());
+    }
+}
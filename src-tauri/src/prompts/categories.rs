@@ -128,6 +128,104 @@ impl PromptCategory {
     }
 }
 
+/// Separator between the levels of a category, e.g. "contracts/nda"
+pub const CATEGORY_SEPARATOR: char = '/';
+
+/// Levels of a category path, ignoring surrounding whitespace and empty levels
+fn category_levels(category: &str) -> Vec<&str> {
+    category
+        .split(CATEGORY_SEPARATOR)
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect()
+}
+
+/// Whether `category` is `ancestor` itself or nested below it (case-insensitive)
+///
+/// "contracts/nda" matches "contracts" and "contracts/nda", but not
+/// "contracts/n" or "nda". A flat category only matches itself.
+pub fn category_matches(category: &str, ancestor: &str) -> bool {
+    let levels = category_levels(category);
+    let ancestor_levels = category_levels(ancestor);
+
+    !ancestor_levels.is_empty()
+        && ancestor_levels.len() <= levels.len()
+        && ancestor_levels
+            .iter()
+            .zip(&levels)
+            .all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+/// A category and the categories nested below it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryNode {
+    /// Last level of the path, e.g. "nda"
+    pub name: String,
+    /// Full path, e.g. "contracts/nda"
+    pub path: String,
+    /// Items filed directly in this category, not counting its children
+    pub count: usize,
+    pub children: Vec<CategoryNode>,
+}
+
+/// Build the category tree from the categories of a set of prompts or templates
+///
+/// Levels are merged case-insensitively. Categories are visited in sorted
+/// order, lower case first on ties, and the first spelling seen is kept, so
+/// the tree doesn't depend on the order items were loaded in. Siblings are
+/// sorted by name.
+pub fn build_category_tree<'a>(categories: impl IntoIterator<Item = &'a str>) -> Vec<CategoryNode> {
+    let mut categories: Vec<&str> = categories.into_iter().collect();
+    categories.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| b.cmp(a)));
+
+    let mut roots: Vec<CategoryNode> = Vec::new();
+
+    for category in categories {
+        let levels = category_levels(category);
+        let mut siblings = &mut roots;
+        let mut parent_path = String::new();
+
+        for (depth, level) in levels.iter().enumerate() {
+            let index = match siblings
+                .iter()
+                .position(|node| node.name.eq_ignore_ascii_case(level))
+            {
+                Some(index) => index,
+                None => {
+                    let path = if parent_path.is_empty() {
+                        level.to_string()
+                    } else {
+                        format!("{}{}{}", parent_path, CATEGORY_SEPARATOR, level)
+                    };
+                    siblings.push(CategoryNode {
+                        name: level.to_string(),
+                        path,
+                        count: 0,
+                        children: Vec::new(),
+                    });
+                    siblings.len() - 1
+                }
+            };
+
+            if depth == levels.len() - 1 {
+                siblings[index].count += 1;
+            }
+            parent_path = siblings[index].path.clone();
+            siblings = &mut siblings[index].children;
+        }
+    }
+
+    sort_category_nodes(&mut roots);
+    roots
+}
+
+fn sort_category_nodes(nodes: &mut [CategoryNode]) {
+    nodes.sort_by_key(|node| node.name.to_lowercase());
+    for node in nodes {
+        sort_category_nodes(&mut node.children);
+    }
+}
+
 impl Default for PromptCategory {
     fn default() -> Self {
         Self::General
@@ -167,6 +265,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_category_matches_descendants() {
+        assert!(category_matches("contracts/nda", "contracts"));
+        assert!(category_matches("Contracts/NDA", "contracts/nda"));
+        assert!(category_matches("contracts / nda", "contracts/"));
+        assert!(category_matches("compliance", "compliance"));
+
+        assert!(!category_matches("contracts", "contracts/nda"));
+        assert!(!category_matches("contracts/nda", "contracts/n"));
+        assert!(!category_matches("contracts/nda", "nda"));
+        assert!(!category_matches("contract_analysis", "contract"));
+        assert!(!category_matches("contracts", ""));
+    }
+
+    #[test]
+    fn test_build_category_tree() {
+        let tree = build_category_tree([
+            "litigation/discovery",
+            "contracts/nda",
+            "contracts",
+            "Contracts/NDA",
+            "contracts/employment",
+            "general",
+        ]);
+
+        let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["contracts", "general", "litigation"]);
+
+        let contracts = &tree[0];
+        assert_eq!(contracts.count, 1);
+        let children: Vec<(&str, &str, usize)> = contracts
+            .children
+            .iter()
+            .map(|n| (n.name.as_str(), n.path.as_str(), n.count))
+            .collect();
+        assert_eq!(
+            children,
+            vec![
+                ("employment", "contracts/employment", 1),
+                ("nda", "contracts/nda", 2),
+            ]
+        );
+
+        // Flat categories are leaves
+        assert_eq!(tree[1].count, 1);
+        assert!(tree[1].children.is_empty());
+
+        // Intermediate levels exist even without items of their own
+        assert_eq!(tree[2].count, 0);
+        assert_eq!(tree[2].children[0].path, "litigation/discovery");
+
+        // Spellings don't depend on the order categories arrive in
+        let reversed = build_category_tree(["Contracts/NDA", "contracts/nda", "contracts"]);
+        assert_eq!(reversed[0].path, "contracts");
+        assert_eq!(reversed[0].children[0].path, "contracts/nda");
+    }
+
     #[test]
     fn test_all_standard_categories() {
         let categories = PromptCategory::all_standard();
@@ -3,16 +3,26 @@ mod variables;
 mod search;
 mod categories;
 mod system_prompts;
-
-pub use parser::parse_prompt_file;
-pub use variables::substitute_variables;
+mod bundle;
+
+pub use categories::{build_category_tree, category_matches, CategoryNode};
+pub use parser::{generation_frontmatter, parse_prompt_file, parse_prompt_str};
+pub use variables::{
+    extract_variables, is_valid_variable_name, substitute_variables, variable_manifest,
+    variables_frontmatter, with_defaults, VariableInfo, VariableSpec,
+};
 pub use search::search_prompts;
 pub use system_prompts::get_builtin_prompts;
+pub use bundle::{LibraryImportResult, LibraryManifest};
+#[allow(unused_imports)]
+pub use bundle::{BundleItem, LibraryImportOutcome, LibraryItemKind};
 
+use crate::ai::GenerationOverrides;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 use walkdir::WalkDir;
@@ -49,6 +59,12 @@ pub struct Prompt {
     pub created: Option<String>,
     pub is_builtin: bool,
     pub file_path: Option<PathBuf>,
+    /// Description, default and required flag per variable, from the frontmatter
+    #[serde(default)]
+    pub variable_metadata: BTreeMap<String, VariableSpec>,
+    /// Generation settings to use with this prompt, over the model's defaults
+    #[serde(default)]
+    pub generation: GenerationOverrides,
 }
 
 impl Prompt {
@@ -69,24 +85,25 @@ impl Prompt {
             created: Some(chrono::Utc::now().to_rfc3339()),
             is_builtin: false,
             file_path: None,
+            variable_metadata: BTreeMap::new(),
+            generation: GenerationOverrides::default(),
         }
     }
 
-    /// Extract variables from content (anything in {VARIABLE_NAME} format)
+    /// Extract variables from content (anything in {VARIABLE_NAME} or {variableName} format)
     pub fn extract_variables(&mut self) {
-        let re = regex::Regex::new(r"\{([A-Z_][A-Z0-9_]*)\}").unwrap();
-        self.variables = re
-            .captures_iter(&self.content)
-            .map(|cap| cap[1].to_string())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
-        self.variables.sort();
+        self.variables = extract_variables(&self.content);
     }
 
-    /// Substitute variables in the prompt content
+    /// Variables to fill in, with their declared metadata
+    pub fn variable_manifest(&self) -> Vec<VariableInfo> {
+        variable_manifest(&self.content, &self.variable_metadata)
+    }
+
+    /// Substitute variables in the prompt content, using declared defaults
+    /// for variables without a value
     pub fn apply_variables(&self, values: &HashMap<String, String>) -> Result<String> {
-        substitute_variables(&self.content, values)
+        substitute_variables(&self.content, &with_defaults(values, &self.variable_metadata))
     }
 
     /// Check if user has access to this prompt based on tier
@@ -95,13 +112,30 @@ impl Prompt {
     }
 }
 
+/// Outcome of importing one file from a prompt pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackImportResult {
+    /// Path of the file inside the archive
+    pub file_name: String,
+    /// Id assigned to the imported prompt
+    pub prompt_id: Option<String>,
+    pub prompt_name: Option<String>,
+    /// Why the file was skipped, if it was
+    pub error: Option<String>,
+}
+
+impl PackImportResult {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
 /// Prompt library manager
 pub struct PromptLibrary {
     #[allow(dead_code)]
     prompts_dir: PathBuf,
     system_dir: PathBuf,
     user_dir: PathBuf,
-    #[allow(dead_code)]
     templates_dir: PathBuf,
     #[allow(dead_code)]
     shared_dir: PathBuf,
@@ -238,6 +272,15 @@ impl PromptLibrary {
         }
 
         content.push_str(&format!("license_tier: {:?}\n", prompt.tier));
+
+        if !prompt.variable_metadata.is_empty() {
+            content.push_str(&variables_frontmatter(&prompt.variable_metadata)?);
+        }
+
+        if !prompt.generation.is_empty() {
+            content.push_str(&generation_frontmatter(&prompt.generation)?);
+        }
+
         content.push_str("---\n\n");
         content.push_str(&prompt.content);
 
@@ -253,6 +296,81 @@ impl PromptLibrary {
         Ok(prompt)
     }
 
+    /// Import every `.md`/`.txt` prompt from a zip archive
+    ///
+    /// Other files are ignored. Invalid prompts are skipped and reported in
+    /// the results instead of aborting the import; only an unreadable
+    /// archive is an error.
+    pub fn import_pack(&self, zip_path: &Path) -> Result<Vec<PackImportResult>> {
+        let file = fs::File::open(zip_path)
+            .with_context(|| format!("Failed to open prompt pack: {:?}", zip_path))?;
+        let mut archive = zip::ZipArchive::new(file).context("Failed to read prompt pack")?;
+
+        let mut results = Vec::new();
+
+        for index in 0..archive.len() {
+            let mut entry = match archive.by_index(index) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    results.push(PackImportResult {
+                        file_name: format!("#{}", index),
+                        prompt_id: None,
+                        prompt_name: None,
+                        error: Some(format!("Unreadable archive entry: {}", e)),
+                    });
+                    continue;
+                }
+            };
+
+            if entry.is_dir() || !is_prompt_file(entry.name()) {
+                continue;
+            }
+
+            let file_name = entry.name().to_string();
+            let imported = entry
+                .enclosed_name()
+                .context("Unsafe path in archive")
+                .and_then(|path| {
+                    let content = read_archived_text(&mut entry)?;
+                    self.import_pack_entry(&content, &path)
+                });
+
+            results.push(match imported {
+                Ok(prompt) => PackImportResult {
+                    file_name,
+                    prompt_id: Some(prompt.id),
+                    prompt_name: Some(prompt.name),
+                    error: None,
+                },
+                Err(e) => PackImportResult {
+                    file_name,
+                    prompt_id: None,
+                    prompt_name: None,
+                    error: Some(format!("{:#}", e)),
+                },
+            });
+        }
+
+        Ok(results)
+    }
+
+    fn import_pack_entry(&self, content: &str, path: &Path) -> Result<Prompt> {
+        let mut prompt = parse_prompt_str(content, path)?;
+
+        if prompt.content.trim().is_empty() {
+            anyhow::bail!("Prompt has no content");
+        }
+
+        // Packs are shared between teams; never trust ids or paths from them
+        prompt.id = Uuid::new_v4().to_string();
+        prompt.is_builtin = false;
+        prompt.extract_variables();
+
+        let saved_path = self.save_prompt(&prompt)?;
+        prompt.file_path = Some(saved_path);
+        Ok(prompt)
+    }
+
     /// Delete a prompt
     pub fn delete_prompt(&self, prompt_id: &str) -> Result<()> {
         let prompts = self.load_all_prompts()?;
@@ -271,9 +389,18 @@ impl PromptLibrary {
     }
 
     /// Get prompt by ID
+    ///
+    /// Also matches the file name without extension, which unlike the id
+    /// stays the same each time the library is loaded.
     pub fn get_prompt(&self, prompt_id: &str) -> Result<Option<Prompt>> {
         let prompts = self.load_all_prompts()?;
-        Ok(prompts.into_iter().find(|p| p.id == prompt_id))
+        Ok(prompts.into_iter().find(|p| {
+            p.id == prompt_id
+                || p.file_path
+                    .as_deref()
+                    .and_then(Path::file_stem)
+                    .is_some_and(|stem| stem == prompt_id)
+        }))
     }
 
     /// Search prompts by query
@@ -282,12 +409,12 @@ impl PromptLibrary {
         Ok(search_prompts(&all_prompts, query))
     }
 
-    /// Get prompts by category
+    /// Get prompts in a category or any category nested below it
     pub fn get_by_category(&self, category: &str) -> Result<Vec<Prompt>> {
         let all_prompts = self.load_all_prompts()?;
         Ok(all_prompts
             .into_iter()
-            .filter(|p| p.category.eq_ignore_ascii_case(category))
+            .filter(|p| category_matches(&p.category, category))
             .collect())
     }
 
@@ -322,6 +449,12 @@ impl PromptLibrary {
         Ok(categories)
     }
 
+    /// Get the categories as a tree of `/`-separated levels
+    pub fn get_category_tree(&self) -> Result<Vec<CategoryNode>> {
+        let all_prompts = self.load_all_prompts()?;
+        Ok(build_category_tree(all_prompts.iter().map(|p| p.category.as_str())))
+    }
+
     /// Get all available tags
     pub fn get_tags(&self) -> Result<Vec<String>> {
         let all_prompts = self.load_all_prompts()?;
@@ -336,10 +469,37 @@ impl PromptLibrary {
     }
 }
 
+/// Largest file accepted from a prompt pack or library bundle
+const MAX_ARCHIVED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Read an archive entry as text, rejecting anything over
+/// `MAX_ARCHIVED_FILE_BYTES`
+///
+/// The limit is enforced on the decompressed bytes rather than the size in
+/// the entry header, so a zip bomb can't claim to be small.
+fn read_archived_text(entry: impl Read) -> Result<String> {
+    let mut bytes = Vec::new();
+    entry.take(MAX_ARCHIVED_FILE_BYTES + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_ARCHIVED_FILE_BYTES {
+        anyhow::bail!("File is larger than {} KB", MAX_ARCHIVED_FILE_BYTES / 1024);
+    }
+    String::from_utf8(bytes).context("File is not valid UTF-8")
+}
+
+/// Whether an archive entry looks like a prompt file
+fn is_prompt_file(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("txt"))
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::HashMap;
+    use std::io::Write;
 
     #[test]
     fn test_prompt_creation() {
@@ -378,4 +538,136 @@ mod tests {
         assert!(prompt.check_access(LicenseTier::Pro));
         assert!(prompt.check_access(LicenseTier::Enterprise));
     }
+
+    fn write_pack(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_import_pack() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let pack = dir.path().join("pack.zip");
+
+        write_pack(
+            &pack,
+            &[
+                (
+                    "contracts/review.md",
+                    "---\nname: Contract Review\ncategory: contract_analysis\n---\n\nReview {CONTRACT}.",
+                ),
+                ("notes.txt", "Summarize the following text."),
+                ("broken.md", "---\nname: [unclosed\n---\n\nBody"),
+                ("README.pdf", "not a prompt"),
+            ],
+        );
+
+        let results = library.import_pack(&pack).unwrap();
+
+        // The PDF is ignored entirely
+        assert_eq!(results.len(), 3);
+        assert_eq!(results.iter().filter(|r| r.succeeded()).count(), 2);
+
+        let failed: Vec<_> = results.iter().filter(|r| !r.succeeded()).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].file_name, "broken.md");
+        assert!(failed[0].error.as_ref().unwrap().contains("frontmatter"));
+
+        let review = results.iter().find(|r| r.file_name == "contracts/review.md").unwrap();
+        assert_eq!(review.prompt_name.as_deref(), Some("Contract Review"));
+
+        let prompts = library.load_all_prompts().unwrap();
+        let prompt = prompts.iter().find(|p| p.name == "Contract Review").unwrap();
+        assert!(!prompt.is_builtin);
+        assert_eq!(prompt.variables, vec!["CONTRACT".to_string()]);
+    }
+
+    #[test]
+    fn test_import_pack_rejects_oversized_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let pack = dir.path().join("pack.zip");
+        let huge = "a".repeat(MAX_ARCHIVED_FILE_BYTES as usize + 1);
+        write_pack(&pack, &[("huge.md", huge.as_str()), ("small.md", "Short prompt")]);
+
+        let results = library.import_pack(&pack).unwrap();
+
+        let huge = results.iter().find(|r| r.file_name == "huge.md").unwrap();
+        assert!(huge.error.as_ref().unwrap().contains("larger than"));
+        let small = results.iter().find(|r| r.file_name == "small.md").unwrap();
+        assert!(small.succeeded());
+    }
+
+    #[test]
+    fn test_get_by_category_includes_descendants() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+
+        for (name, category) in [
+            ("Contracts overview", "contracts"),
+            ("Mutual NDA", "contracts/nda"),
+            ("Employment terms", "Contracts/Employment"),
+            ("Discovery requests", "litigation/discovery"),
+        ] {
+            let mut prompt = Prompt::new(name.to_string(), "Body".to_string());
+            prompt.category = category.to_string();
+            library.save_prompt(&prompt).unwrap();
+        }
+
+        let mut names: Vec<String> = library
+            .get_by_category("contracts")
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Contracts overview", "Employment terms", "Mutual NDA"]);
+
+        let nda = library.get_by_category("contracts/nda").unwrap();
+        assert_eq!(nda.len(), 1);
+        assert_eq!(nda[0].name, "Mutual NDA");
+
+        let tree = library.get_category_tree().unwrap();
+        let contracts = tree.iter().find(|n| n.path == "contracts").unwrap();
+        assert_eq!(contracts.count, 1);
+        let children: Vec<&str> = contracts.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(children, vec!["Employment", "nda"]);
+    }
+
+    #[test]
+    fn test_import_pack_assigns_fresh_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+        let pack = dir.path().join("pack.zip");
+        write_pack(&pack, &[("a.md", "First prompt")]);
+
+        // Importing the same pack twice must not overwrite the first copy
+        let first = library.import_pack(&pack).unwrap();
+        let second = library.import_pack(&pack).unwrap();
+        assert_ne!(first[0].prompt_id, second[0].prompt_id);
+
+        let user_prompts = fs::read_dir(&library.user_dir).unwrap().count();
+        assert_eq!(user_prompts, 2);
+    }
+
+    #[test]
+    fn test_generation_settings_survive_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = PromptLibrary::new(dir.path().join("library")).unwrap();
+
+        let mut prompt = Prompt::new("Extract parties".to_string(), "List the parties.".to_string());
+        prompt.generation.temperature = Some(0.0);
+        prompt.generation.max_new_tokens = Some(256);
+        library.save_prompt(&prompt).unwrap();
+
+        // Looked up by file name, since ids are reassigned on every load
+        let loaded = library.get_prompt(&prompt.id).unwrap().unwrap();
+        assert_eq!(loaded.generation, prompt.generation);
+        assert_eq!(loaded.generation.top_p, None);
+    }
 }
@@ -1,10 +1,12 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 use uuid::Uuid;
 
-use super::{LicenseTier, Prompt};
+use super::{LicenseTier, Prompt, VariableSpec};
+use crate::ai::GenerationOverrides;
 
 /// Metadata extracted from YAML frontmatter
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +20,8 @@ pub struct PromptMetadata {
     pub created: Option<String>,
     pub author: Option<String>,
     pub license_tier: Option<String>,
+    pub variables: Option<BTreeMap<String, VariableSpec>>,
+    pub generation: Option<GenerationOverrides>,
 }
 
 /// Parse a prompt file with YAML frontmatter
@@ -34,6 +38,11 @@ pub struct PromptMetadata {
 /// created: 2025-01-26
 /// author: User Name
 /// license_tier: basic
+/// variables:
+///   CLIENT_NAME:
+///     description: Full legal name of the client
+/// generation:
+///   temperature: 0.2
 /// ---
 ///
 /// Prompt content goes here...
@@ -42,12 +51,17 @@ pub fn parse_prompt_file(path: &Path) -> Result<Prompt> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read prompt file: {:?}", path))?;
 
+    parse_prompt_str(&content, path)
+}
+
+/// Parse prompt file contents; `path` supplies the default name
+pub fn parse_prompt_str(content: &str, path: &Path) -> Result<Prompt> {
     // Check if file has YAML frontmatter
     if content.starts_with("---\n") || content.starts_with("---\r\n") {
-        parse_with_frontmatter(&content, path)
+        parse_with_frontmatter(content, path)
     } else {
         // No frontmatter, create a basic prompt from content
-        parse_without_frontmatter(&content, path)
+        parse_without_frontmatter(content, path)
     }
 }
 
@@ -90,6 +104,8 @@ fn parse_with_frontmatter(content: &str, path: &Path) -> Result<Prompt> {
         created: metadata.created,
         is_builtin: false,
         file_path: Some(path.to_path_buf()),
+        variable_metadata: metadata.variables.unwrap_or_default(),
+        generation: metadata.generation.unwrap_or_default(),
     };
 
     Ok(prompt)
@@ -118,11 +134,24 @@ fn parse_without_frontmatter(content: &str, path: &Path) -> Result<Prompt> {
         created: Some(chrono::Utc::now().to_rfc3339()),
         is_builtin: false,
         file_path: Some(path.to_path_buf()),
+        variable_metadata: BTreeMap::new(),
+        generation: GenerationOverrides::default(),
     };
 
     Ok(prompt)
 }
 
+/// `generation:` frontmatter block for recommended generation settings
+pub fn generation_frontmatter(overrides: &GenerationOverrides) -> Result<String> {
+    #[derive(Serialize)]
+    struct Frontmatter<'a> {
+        generation: &'a GenerationOverrides,
+    }
+
+    serde_yaml::to_string(&Frontmatter { generation: overrides })
+        .context("Failed to serialize generation settings")
+}
+
 /// Parse license tier string to enum
 fn parse_tier(tier_str: &Option<String>) -> LicenseTier {
     match tier_str.as_ref().map(|s| s.to_lowercase()) {
@@ -137,6 +166,7 @@ fn parse_tier(tier_str: &Option<String>) -> LicenseTier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -166,6 +196,46 @@ This is the prompt content with {VARIABLE}."#;
         assert!(prompt.content.contains("{VARIABLE}"));
     }
 
+    #[test]
+    fn test_parse_variable_metadata() {
+        let content = r#"---
+name: Demand Letter
+variables:
+  CLIENT_NAME:
+    description: Full legal name of the client
+  DEADLINE_DAYS:
+    default: "14"
+    description: Days to respond
+---
+
+{CLIENT_NAME} demands payment within {DEADLINE_DAYS} days of {DATE}."#;
+
+        let prompt = parse_prompt_str(content, Path::new("demand.md")).unwrap();
+        assert_eq!(prompt.variable_metadata.len(), 2);
+
+        let manifest = prompt.variable_manifest();
+        let names: Vec<&str> = manifest.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["CLIENT_NAME", "DEADLINE_DAYS", "DATE"]);
+        assert_eq!(
+            manifest[0].description.as_deref(),
+            Some("Full legal name of the client")
+        );
+        assert_eq!(manifest[1].default.as_deref(), Some("14"));
+        assert!(!manifest[1].required);
+        // Undeclared variables are listed as required, without metadata
+        assert!(manifest[2].required);
+        assert_eq!(manifest[2].description, None);
+
+        let values = HashMap::from([
+            ("CLIENT_NAME".to_string(), "Acme".to_string()),
+            ("DATE".to_string(), "1 March".to_string()),
+        ]);
+        assert_eq!(
+            prompt.apply_variables(&values).unwrap(),
+            "Acme demands payment within 14 days of 1 March."
+        );
+    }
+
     #[test]
     fn test_parse_without_frontmatter() {
         let content = "This is a simple prompt without frontmatter.";
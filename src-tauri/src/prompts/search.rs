@@ -119,7 +119,7 @@ impl PromptFilter {
     fn matches(&self, prompt: &Prompt) -> bool {
         // Category filter
         if let Some(ref category) = self.category {
-            if !prompt.category.eq_ignore_ascii_case(category) {
+            if !super::category_matches(&prompt.category, category) {
                 return false;
             }
         }
@@ -187,6 +187,8 @@ mod tests {
                 created: None,
                 is_builtin: true,
                 file_path: None,
+                variable_metadata: Default::default(),
+                generation: Default::default(),
             },
             Prompt {
                 id: "2".to_string(),
@@ -203,6 +205,8 @@ mod tests {
                 created: None,
                 is_builtin: true,
                 file_path: None,
+                variable_metadata: Default::default(),
+                generation: Default::default(),
             },
             Prompt {
                 id: "3".to_string(),
@@ -219,6 +223,8 @@ mod tests {
                 created: None,
                 is_builtin: false,
                 file_path: None,
+                variable_metadata: Default::default(),
+                generation: Default::default(),
             },
         ]
     }
@@ -1,4 +1,5 @@
 use super::{LicenseTier, Prompt};
+use crate::ai::GenerationOverrides;
 
 /// Get all built-in system prompts
 pub fn get_builtin_prompts() -> Vec<Prompt> {
@@ -80,6 +81,8 @@ Please provide a structured analysis with clear risk ratings (Low/Medium/High) f
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -159,6 +162,8 @@ Provide specific, actionable recommendations with references to relevant GDPR ar
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -234,6 +239,8 @@ Present information in a clear, structured format suitable for legal professiona
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -311,6 +318,8 @@ Identify areas requiring additional investigation.
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -391,6 +400,8 @@ Provide an overall compliance score and executive summary of findings."#
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -474,6 +485,8 @@ Organize citations by type and provide a comprehensive citation index."#
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -570,6 +583,12 @@ Present timeline in clear, chronological order suitable for legal proceedings."#
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        // Extraction should be deterministic
+        generation: GenerationOverrides {
+            temperature: Some(0.0),
+            ..Default::default()
+        },
     }
 }
 
@@ -712,6 +731,8 @@ Provide comprehensive, actionable due diligence findings suitable for transactio
         created: Some("2025-01-26".to_string()),
         is_builtin: true,
         file_path: None,
+        variable_metadata: Default::default(),
+        generation: Default::default(),
     }
 }
 
@@ -1,11 +1,23 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use regex::Regex;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Placeholder syntax: `{name}` where name is a letter or underscore followed by
+/// letters, digits or underscores. `{#...}`/`{/...}` style tags never match.
+const VARIABLE_PATTERN: &str = r"\{([A-Za-z_][A-Za-z0-9_]*)\}";
+
+/// Whether `name` is a valid variable name (e.g. `CLIENT_NAME`, `clientName`, `client_name`)
+pub fn is_valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
 
 /// Substitute variables in a template string
 ///
-/// Variables are in the format {VARIABLE_NAME}
-/// Variable names must be uppercase with underscores
+/// Variables are in the format {VARIABLE_NAME}, {variableName} or {variable_name}.
+/// Names are case-sensitive: {name} and {NAME} are different variables.
 ///
 /// Example:
 /// ```
@@ -18,22 +30,23 @@ use std::collections::HashMap;
 /// // result: "Hello John, your email is john@example.com"
 /// ```
 pub fn substitute_variables(template: &str, values: &HashMap<String, String>) -> Result<String> {
-    let re = Regex::new(r"\{([A-Z_][A-Z0-9_]*)\}").unwrap();
-    let mut result = template.to_string();
-    let mut missing_vars = Vec::new();
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
+    let mut missing_vars: Vec<String> = Vec::new();
 
-    // Find all variables in template
-    for cap in re.captures_iter(template) {
+    // Replace in a single pass so substituted values are never re-expanded
+    let result = re.replace_all(template, |cap: &regex::Captures| {
         let var_name = &cap[1];
 
-        if let Some(value) = values.get(var_name) {
-            // Replace all occurrences of this variable
-            let placeholder = format!("{{{}}}", var_name);
-            result = result.replace(&placeholder, value);
-        } else {
-            missing_vars.push(var_name.to_string());
+        match values.get(var_name) {
+            Some(value) => value.clone(),
+            None => {
+                if !missing_vars.iter().any(|v| v == var_name) {
+                    missing_vars.push(var_name.to_string());
+                }
+                cap[0].to_string()
+            }
         }
-    }
+    });
 
     // Report missing variables
     if !missing_vars.is_empty() {
@@ -43,15 +56,14 @@ pub fn substitute_variables(template: &str, values: &HashMap<String, String>) ->
         );
     }
 
-    Ok(result)
+    Ok(result.into_owned())
 }
 
 /// Extract variable names from a template string
 ///
-/// Returns a list of unique variable names found in the template
-#[allow(dead_code)]
+/// Returns a sorted list of unique variable names found in the template
 pub fn extract_variables(template: &str) -> Vec<String> {
-    let re = Regex::new(r"\{([A-Z_][A-Z0-9_]*)\}").unwrap();
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
 
     let mut vars: Vec<String> = re
         .captures_iter(template)
@@ -64,6 +76,86 @@ pub fn extract_variables(template: &str) -> Vec<String> {
     vars
 }
 
+/// Metadata for a variable, declared in the frontmatter:
+///
+/// ```yaml
+/// variables:
+///   CLIENT_NAME:
+///     description: Full legal name of the client
+///   JURISDICTION:
+///     default: the Netherlands
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariableSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Defaults to true; a variable with a default value is never required,
+    /// since the default fills it in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+/// A variable to fill in, for building an input form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableInfo {
+    pub name: String,
+    pub required: bool,
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Variables used in `content`, in order of first use, with their declared metadata
+///
+/// Declarations for variables the content doesn't use are ignored.
+pub fn variable_manifest(
+    content: &str,
+    declared: &BTreeMap<String, VariableSpec>,
+) -> Vec<VariableInfo> {
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
+    let mut seen = HashSet::new();
+
+    re.captures_iter(content)
+        .map(|cap| cap[1].to_string())
+        .filter(|name| seen.insert(name.clone()))
+        .map(|name| {
+            let spec = declared.get(&name).cloned().unwrap_or_default();
+            VariableInfo {
+                required: spec.default.is_none() && spec.required.unwrap_or(true),
+                default: spec.default,
+                description: spec.description,
+                name,
+            }
+        })
+        .collect()
+}
+
+/// `values` plus the declared default of every variable left without a value
+pub fn with_defaults(
+    values: &HashMap<String, String>,
+    declared: &BTreeMap<String, VariableSpec>,
+) -> HashMap<String, String> {
+    let mut filled = values.clone();
+    for (name, spec) in declared {
+        if let Some(default) = &spec.default {
+            filled.entry(name.clone()).or_insert_with(|| default.clone());
+        }
+    }
+    filled
+}
+
+/// `variables:` frontmatter block for declared variable metadata
+pub fn variables_frontmatter(declared: &BTreeMap<String, VariableSpec>) -> Result<String> {
+    #[derive(Serialize)]
+    struct Frontmatter<'a> {
+        variables: &'a BTreeMap<String, VariableSpec>,
+    }
+
+    serde_yaml::to_string(&Frontmatter { variables: declared })
+        .context("Failed to serialize variable metadata")
+}
+
 /// Validate that all variables in template have values
 #[allow(dead_code)]
 pub fn validate_variables(template: &str, values: &HashMap<String, String>) -> Result<()> {
@@ -141,6 +233,138 @@ mod tests {
         assert!(vars.contains(&"AMOUNT".to_string()));
     }
 
+    #[test]
+    fn test_camel_and_snake_case_variables() {
+        let template = "Dear {clientName}, re: {case_number} ({CASE_NUMBER}) for {clientName}.";
+        assert_eq!(
+            extract_variables(template),
+            vec!["CASE_NUMBER", "case_number", "clientName"]
+        );
+
+        let mut values = HashMap::new();
+        values.insert("clientName".to_string(), "Ms. Jansen".to_string());
+        values.insert("case_number".to_string(), "2024/117".to_string());
+        values.insert("CASE_NUMBER".to_string(), "C-2024-117".to_string());
+
+        let result = substitute_variables(template, &values).unwrap();
+        assert_eq!(result, "Dear Ms. Jansen, re: 2024/117 (C-2024-117) for Ms. Jansen.");
+    }
+
+    #[test]
+    fn test_substitution_is_case_sensitive() {
+        let mut values = HashMap::new();
+        values.insert("name".to_string(), "lower".to_string());
+
+        let err = substitute_variables("{name} and {Name}", &values).unwrap_err();
+        assert!(err.to_string().contains("Missing values for variables: Name"));
+    }
+
+    #[test]
+    fn test_values_are_not_reexpanded() {
+        let mut values = HashMap::new();
+        values.insert("a".to_string(), "{b}".to_string());
+        values.insert("b".to_string(), "x".to_string());
+
+        assert_eq!(substitute_variables("{a} {b}", &values).unwrap(), "{b} x");
+    }
+
+    #[test]
+    fn test_variable_name_validity() {
+        assert!(is_valid_variable_name("CLIENT_NAME"));
+        assert!(is_valid_variable_name("clientName"));
+        assert!(is_valid_variable_name("_private"));
+        assert!(!is_valid_variable_name("1stParty"));
+        assert!(!is_valid_variable_name("client-name"));
+        assert!(!is_valid_variable_name("#if"));
+        assert!(!is_valid_variable_name(""));
+    }
+
+    #[test]
+    fn test_variable_manifest_merges_declared_metadata() {
+        let content = "Dear {CLIENT_NAME}, under the law of {JURISDICTION} and {CLIENT_NAME} ({REF}).";
+        let mut declared = BTreeMap::new();
+        declared.insert(
+            "CLIENT_NAME".to_string(),
+            VariableSpec {
+                description: Some("Full legal name".to_string()),
+                ..Default::default()
+            },
+        );
+        declared.insert(
+            "JURISDICTION".to_string(),
+            VariableSpec {
+                default: Some("the Netherlands".to_string()),
+                ..Default::default()
+            },
+        );
+        declared.insert(
+            "REF".to_string(),
+            VariableSpec {
+                default: Some("n/a".to_string()),
+                required: Some(true),
+                ..Default::default()
+            },
+        );
+        declared.insert("UNUSED".to_string(), VariableSpec::default());
+
+        let manifest = variable_manifest(content, &declared);
+        let names: Vec<&str> = manifest.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["CLIENT_NAME", "JURISDICTION", "REF"]);
+
+        assert_eq!(manifest[0].description.as_deref(), Some("Full legal name"));
+        assert!(manifest[0].required);
+        // A default makes a variable optional, even one declared required
+        assert_eq!(manifest[1].default.as_deref(), Some("the Netherlands"));
+        assert!(!manifest[1].required);
+        assert!(!manifest[2].required);
+    }
+
+    #[test]
+    fn test_declared_defaults_fill_missing_values() {
+        let mut declared = BTreeMap::new();
+        declared.insert(
+            "JURISDICTION".to_string(),
+            VariableSpec {
+                default: Some("the Netherlands".to_string()),
+                required: Some(true),
+                ..Default::default()
+            },
+        );
+        let template = "{CLIENT_NAME} is bound by the law of {JURISDICTION}.";
+
+        let values = HashMap::from([("CLIENT_NAME".to_string(), "Acme B.V.".to_string())]);
+        let result = substitute_variables(template, &with_defaults(&values, &declared)).unwrap();
+        assert_eq!(result, "Acme B.V. is bound by the law of the Netherlands.");
+
+        // A supplied value wins over the default
+        let mut values = values;
+        values.insert("JURISDICTION".to_string(), "Belgium".to_string());
+        let result = substitute_variables(template, &with_defaults(&values, &declared)).unwrap();
+        assert_eq!(result, "Acme B.V. is bound by the law of Belgium.");
+    }
+
+    #[test]
+    fn test_variables_frontmatter_round_trips() {
+        let mut declared = BTreeMap::new();
+        declared.insert(
+            "CLIENT_NAME".to_string(),
+            VariableSpec {
+                description: Some("Full legal name".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let yaml = variables_frontmatter(&declared).unwrap();
+        assert_eq!(yaml, "variables:\n  CLIENT_NAME:\n    description: Full legal name\n");
+
+        #[derive(Deserialize)]
+        struct Frontmatter {
+            variables: BTreeMap<String, VariableSpec>,
+        }
+        let parsed: Frontmatter = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.variables, declared);
+    }
+
     #[test]
     fn test_extract_duplicate_variables() {
         let template = "Hello {NAME}! Welcome {NAME}!";
@@ -1 +1,63 @@
-// Placeholder for future audit service implementation
+//! Audit trail of actions taken on cases
+//!
+//! Entries are append-only; the only way to remove them is `prune`, which
+//! never touches entries of a case under legal hold.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use entity::{audit_logs, cases};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect, Set,
+};
+
+/// Entity type recorded for case actions
+pub const CASE_ENTITY: &str = "case";
+
+/// Record an action on a case
+pub async fn record(
+    conn: &DatabaseConnection,
+    action: &str,
+    case_id: Option<i32>,
+    details: serde_json::Value,
+) -> Result<audit_logs::Model> {
+    audit_logs::ActiveModel {
+        action: Set(action.to_string()),
+        case_id: Set(case_id),
+        entity_type: Set(case_id.map(|_| CASE_ENTITY.to_string())),
+        entity_id: Set(case_id),
+        details: Set(Some(details)),
+        timestamp: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .context("Failed to write audit log entry")
+}
+
+/// Delete entries older than `before`, keeping those of cases on legal hold
+///
+/// Returns the number of entries removed.
+pub async fn prune(conn: &DatabaseConnection, before: NaiveDateTime) -> Result<u64> {
+    let held: Vec<i32> = cases::Entity::find()
+        .select_only()
+        .column(cases::Column::Id)
+        .filter(cases::Column::LegalHold.eq(true))
+        .into_tuple()
+        .all(conn)
+        .await
+        .context("Failed to look up cases on legal hold")?;
+
+    let result = audit_logs::Entity::delete_many()
+        .filter(audit_logs::Column::Timestamp.lt(before))
+        .filter(
+            Condition::any()
+                .add(audit_logs::Column::CaseId.is_null())
+                .add(audit_logs::Column::CaseId.is_not_in(held)),
+        )
+        .exec(conn)
+        .await
+        .context("Failed to prune audit log")?;
+
+    Ok(result.rows_affected)
+}
@@ -5,13 +5,18 @@ pub use validator::validate_template;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-use crate::prompts::{parse_prompt_file, substitute_variables};
+use crate::ai::GenerationOverrides;
+use crate::prompts::{
+    build_category_tree, category_matches, extract_variables, generation_frontmatter,
+    parse_prompt_file, substitute_variables, variable_manifest, variables_frontmatter,
+    with_defaults, CategoryNode, VariableInfo, VariableSpec,
+};
 
 /// Document template
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +35,12 @@ pub struct DocumentTemplate {
     pub created: Option<String>,
     pub is_builtin: bool,
     pub file_path: Option<PathBuf>,
+    /// Description, default and required flag per variable, from the frontmatter
+    #[serde(default)]
+    pub variable_metadata: BTreeMap<String, VariableSpec>,
+    /// Generation settings to use with this template, over the model's defaults
+    #[serde(default)]
+    pub generation: GenerationOverrides,
 }
 
 /// Output format for rendered templates
@@ -64,30 +75,37 @@ impl DocumentTemplate {
             created: Some(chrono::Utc::now().to_rfc3339()),
             is_builtin: false,
             file_path: None,
+            variable_metadata: BTreeMap::new(),
+            generation: GenerationOverrides::default(),
         }
     }
 
     /// Extract variables from content
     pub fn extract_variables(&mut self) {
-        let re = regex::Regex::new(r"\{([A-Z_][A-Z0-9_]*)\}").unwrap();
-        self.variables = re
-            .captures_iter(&self.content)
-            .map(|cap| cap[1].to_string())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
-        self.variables.sort();
+        self.variables = extract_variables(&self.content);
     }
 
     /// Render template with variables
+    ///
+    /// Include directives are left as-is; `TemplateLibrary::render` expands them.
+    #[allow(dead_code)]
     pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
-        substitute_variables(&self.content, values)
+        substitute_variables(&self.content, &with_defaults(values, &self.variable_metadata))
     }
 
     /// Validate template syntax
     pub fn validate(&self) -> Result<()> {
         validate_template(&self.content)
     }
+
+    /// File name without extension, which other templates use to include this one
+    fn file_stem(&self) -> Option<String> {
+        self.file_path
+            .as_deref()
+            .and_then(|path| path.file_stem())
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string)
+    }
 }
 
 /// Template library manager
@@ -176,6 +194,8 @@ impl TemplateLibrary {
             created: prompt.created,
             is_builtin,
             file_path: Some(path.to_path_buf()),
+            variable_metadata: prompt.variable_metadata,
+            generation: prompt.generation,
         };
 
         template.extract_variables();
@@ -221,6 +241,14 @@ impl TemplateLibrary {
             content.push_str(&format!("author: {}\n", author));
         }
 
+        if !template.variable_metadata.is_empty() {
+            content.push_str(&variables_frontmatter(&template.variable_metadata)?);
+        }
+
+        if !template.generation.is_empty() {
+            content.push_str(&generation_frontmatter(&template.generation)?);
+        }
+
         content.push_str("---\n\n");
         content.push_str(&template.content);
 
@@ -254,19 +282,72 @@ impl TemplateLibrary {
     }
 
     /// Get template by ID
+    ///
+    /// Also matches the file name without extension, which unlike the id
+    /// stays the same each time the library is loaded.
     pub fn get_template(&self, template_id: &str) -> Result<Option<DocumentTemplate>> {
         let templates = self.load_all()?;
-        Ok(templates.into_iter().find(|t| t.id == template_id))
+        Ok(templates
+            .into_iter()
+            .find(|t| t.id == template_id || t.file_stem().as_deref() == Some(template_id)))
+    }
+
+    /// Render a template, expanding `{> clause_id}` includes first
+    ///
+    /// An include refers to another template by id or by file name without
+    /// extension; the same variables fill the template and its partials.
+    /// Declared defaults fill variables without a value.
+    pub fn render(
+        &self,
+        template: &DocumentTemplate,
+        values: &HashMap<String, String>,
+    ) -> Result<String> {
+        let partials = self.partials()?;
+        let template_id = template.file_stem().unwrap_or_else(|| template.id.clone());
+        let values = with_defaults(values, &template.variable_metadata);
+        renderer::render_with_includes(&template_id, &template.content, &values, &|id| {
+            partials.get(id).cloned()
+        })
+    }
+
+    /// Variables to fill in, including those of included templates, with the
+    /// template's declared metadata
+    pub fn variable_manifest(&self, template: &DocumentTemplate) -> Result<Vec<VariableInfo>> {
+        let partials = self.partials()?;
+        let template_id = template.file_stem().unwrap_or_else(|| template.id.clone());
+        let expanded = renderer::expand_includes(&template_id, &template.content, &|id| {
+            partials.get(id).cloned()
+        })?;
+
+        Ok(variable_manifest(&expanded, &template.variable_metadata))
     }
 
-    /// Get templates by category
+    /// Content of every template, keyed by file stem and by id, for includes
+    fn partials(&self) -> Result<HashMap<String, String>> {
+        let mut partials = HashMap::new();
+        for partial in self.load_all()? {
+            if let Some(stem) = partial.file_stem() {
+                partials.entry(stem).or_insert_with(|| partial.content.clone());
+            }
+            partials.entry(partial.id).or_insert(partial.content);
+        }
+        Ok(partials)
+    }
+
+    /// Get templates in a category or any category nested below it
     pub fn get_by_category(&self, category: &str) -> Result<Vec<DocumentTemplate>> {
         let all_templates = self.load_all()?;
         Ok(all_templates
             .into_iter()
-            .filter(|t| t.category.eq_ignore_ascii_case(category))
+            .filter(|t| category_matches(&t.category, category))
             .collect())
     }
+
+    /// Get the template categories as a tree of `/`-separated levels
+    pub fn get_category_tree(&self) -> Result<Vec<CategoryNode>> {
+        let all_templates = self.load_all()?;
+        Ok(build_category_tree(all_templates.iter().map(|t| t.category.as_str())))
+    }
 }
 
 #[cfg(test)]
@@ -299,4 +380,91 @@ mod tests {
         let result = template.render(&values).unwrap();
         assert_eq!(result, "Agreement dated 2025-01-26");
     }
+
+    #[test]
+    fn test_library_render_expands_partials() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = TemplateLibrary::new(dir.path().to_path_buf()).unwrap();
+
+        let clause = DocumentTemplate::new(
+            "Confidentiality".to_string(),
+            "{PARTY_B} keeps all information confidential.".to_string(),
+        );
+        library.save_template(&clause).unwrap();
+
+        let nda = DocumentTemplate::new(
+            "NDA".to_string(),
+            format!("NDA with {{PARTY_B}}.\n{{> {}}}", clause.id),
+        );
+        nda.validate().unwrap();
+        library.save_template(&nda).unwrap();
+
+        let values = HashMap::from([("PARTY_B".to_string(), "Globex Ltd".to_string())]);
+        let result = library.render(&nda, &values).unwrap();
+        assert_eq!(result, "NDA with Globex Ltd.\nGlobex Ltd keeps all information confidential.");
+
+        // A partial that includes the template back is rejected
+        let mut looping = clause.clone();
+        looping.content = format!("Clause text {{> {}}}", nda.id);
+        library.save_template(&looping).unwrap();
+        let err = library.render(&nda, &values).unwrap_err();
+        assert!(err.to_string().contains("Template include cycle"), "{}", err);
+    }
+
+    #[test]
+    fn test_variable_manifest_includes_partials_and_saved_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = TemplateLibrary::new(dir.path().to_path_buf()).unwrap();
+
+        let clause = DocumentTemplate::new(
+            "Term".to_string(),
+            "This agreement runs for {TERM_MONTHS} months.".to_string(),
+        );
+        library.save_template(&clause).unwrap();
+
+        let mut nda = DocumentTemplate::new(
+            "Manifest NDA".to_string(),
+            format!("NDA between {{PARTY_A}} and {{PARTY_B}}.\n{{> {}}}", clause.id),
+        );
+        nda.variable_metadata.insert(
+            "PARTY_A".to_string(),
+            VariableSpec {
+                description: Some("Disclosing party".to_string()),
+                ..Default::default()
+            },
+        );
+        nda.variable_metadata.insert(
+            "TERM_MONTHS".to_string(),
+            VariableSpec {
+                default: Some("24".to_string()),
+                ..Default::default()
+            },
+        );
+        library.save_template(&nda).unwrap();
+
+        // Metadata survives a save and reload through the frontmatter
+        let loaded = library
+            .load_all()
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "Manifest NDA")
+            .unwrap();
+        assert_eq!(loaded.variable_metadata, nda.variable_metadata);
+
+        let manifest = library.variable_manifest(&loaded).unwrap();
+        let names: Vec<&str> = manifest.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["PARTY_A", "PARTY_B", "TERM_MONTHS"]);
+        assert_eq!(manifest[0].description.as_deref(), Some("Disclosing party"));
+        assert!(manifest[1].required);
+        assert_eq!(manifest[2].default.as_deref(), Some("24"));
+        assert!(!manifest[2].required);
+
+        // The default fills in the partial's variable when rendering
+        let values = HashMap::from([
+            ("PARTY_A".to_string(), "Acme".to_string()),
+            ("PARTY_B".to_string(), "Globex".to_string()),
+        ]);
+        let rendered = library.render(&loaded, &values).unwrap();
+        assert!(rendered.ends_with("This agreement runs for 24 months."), "{}", rendered);
+    }
 }
@@ -1,8 +1,77 @@
 use anyhow::Result;
+use regex::Regex;
 use std::collections::HashMap;
 
 use crate::prompts::substitute_variables;
 
+/// Include directive: `{> clause_id}` is replaced by another template's content
+pub(crate) const INCLUDE_PATTERN: &str = r"\{>\s*([A-Za-z0-9_.-]+)\s*\}";
+
+/// Replace include directives with the content of the referenced templates
+///
+/// Partials may include other partials. `resolve` maps an include id to the
+/// partial's content; including a template that is already being expanded
+/// is rejected as a cycle.
+pub fn expand_includes(
+    template_id: &str,
+    template: &str,
+    resolve: &dyn Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut stack = vec![template_id.to_string()];
+    expand(template, resolve, &mut stack)
+}
+
+fn expand(
+    template: &str,
+    resolve: &dyn Fn(&str) -> Option<String>,
+    stack: &mut Vec<String>,
+) -> Result<String> {
+    let re = Regex::new(INCLUDE_PATTERN).unwrap();
+    let mut result = String::with_capacity(template.len());
+    let mut last_end = 0;
+
+    for cap in re.captures_iter(template) {
+        let directive = cap.get(0).unwrap();
+        let include_id = &cap[1];
+
+        if stack.iter().any(|id| id == include_id) {
+            anyhow::bail!(
+                "Template include cycle: {} -> {}",
+                stack.join(" -> "),
+                include_id
+            );
+        }
+
+        let partial = resolve(include_id)
+            .ok_or_else(|| anyhow::anyhow!("Included template not found: {}", include_id))?;
+
+        stack.push(include_id.to_string());
+        let expanded = expand(&partial, resolve, stack)?;
+        stack.pop();
+
+        result.push_str(&template[last_end..directive.start()]);
+        result.push_str(&expanded);
+        last_end = directive.end();
+    }
+
+    result.push_str(&template[last_end..]);
+    Ok(result)
+}
+
+/// Render a template after expanding its includes
+///
+/// Variables are substituted once the partials are in place, so the same
+/// values fill placeholders in the template and in every partial.
+pub fn render_with_includes(
+    template_id: &str,
+    template: &str,
+    values: &HashMap<String, String>,
+    resolve: &dyn Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let expanded = expand_includes(template_id, template, resolve)?;
+    substitute_variables(&expanded, values)
+}
+
 /// Render a template with the given variables
 ///
 /// This is a simple pass-through to the variable substitution engine
@@ -35,4 +104,74 @@ mod tests {
         let result = render_template(template, &values).unwrap();
         assert_eq!(result, "Hello World, today is 2025-01-26");
     }
+
+    fn partials() -> HashMap<String, String> {
+        HashMap::from([
+            (
+                "confidentiality".to_string(),
+                "{PARTY_B} keeps this confidential.\n{> governing_law}".to_string(),
+            ),
+            ("governing_law".to_string(), "Governed by the laws of {JURISDICTION}.".to_string()),
+            ("loop_a".to_string(), "A {> loop_b}".to_string()),
+            ("loop_b".to_string(), "B {> loop_a}".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_render_with_partials() {
+        let partials = partials();
+        let resolve = |id: &str| partials.get(id).cloned();
+        let template = "NDA between {PARTY_A} and {PARTY_B}.\n{> confidentiality}\nSigned {DATE}";
+
+        let values = HashMap::from([
+            ("PARTY_A".to_string(), "Acme B.V.".to_string()),
+            ("PARTY_B".to_string(), "Globex Ltd".to_string()),
+            ("JURISDICTION".to_string(), "the Netherlands".to_string()),
+            ("DATE".to_string(), "2025-01-26".to_string()),
+        ]);
+
+        let result = render_with_includes("nda", template, &values, &resolve).unwrap();
+        assert_eq!(
+            result,
+            "NDA between Acme B.V. and Globex Ltd.\nGlobex Ltd keeps this confidential.\n\
+             Governed by the laws of the Netherlands.\nSigned 2025-01-26"
+        );
+
+        // Variables used only inside a partial are still required
+        let err = render_with_includes("nda", template, &HashMap::new(), &resolve).unwrap_err();
+        assert!(err.to_string().contains("JURISDICTION"));
+    }
+
+    #[test]
+    fn test_include_cycles_and_missing_partials_are_rejected() {
+        let partials = partials();
+        let resolve = |id: &str| partials.get(id).cloned();
+
+        let err = expand_includes("contract", "Start {> loop_a}", &resolve).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template include cycle: contract -> loop_a -> loop_b -> loop_a"
+        );
+
+        let err = expand_includes("loop_a", "A {> loop_a}", &resolve).unwrap_err();
+        assert!(err.to_string().contains("loop_a -> loop_a"));
+
+        let err = expand_includes("contract", "{> missing_clause}", &resolve).unwrap_err();
+        assert_eq!(err.to_string(), "Included template not found: missing_clause");
+
+        // The same partial may appear twice as long as it doesn't include itself
+        let result = expand_includes("contract", "{> governing_law}\n{>governing_law}", &resolve).unwrap();
+        assert_eq!(result.matches("Governed by").count(), 2);
+    }
+
+    #[test]
+    fn test_render_camel_and_snake_case() {
+        let template = "Between {partyA} and {party_b}";
+        let mut values = HashMap::new();
+        values.insert("partyA".to_string(), "Acme B.V.".to_string());
+        values.insert("party_b".to_string(), "Globex Ltd".to_string());
+
+        let result = render_template(template, &values).unwrap();
+        assert_eq!(result, "Between Acme B.V. and Globex Ltd");
+    }
 }
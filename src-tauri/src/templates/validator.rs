@@ -1,12 +1,16 @@
 use anyhow::Result;
 use regex::Regex;
 
+use crate::prompts::is_valid_variable_name;
+
+use super::renderer::INCLUDE_PATTERN;
+
 /// Validate template syntax
 ///
 /// Checks for:
 /// - Properly formatted variable placeholders
 /// - Balanced braces
-/// - No invalid variable names
+/// - No invalid variable names or include directives
 pub fn validate_template(template: &str) -> Result<()> {
     // Check for balanced braces
     let mut brace_count = 0;
@@ -28,14 +32,24 @@ pub fn validate_template(template: &str) -> Result<()> {
 
     // Check variable names
     let var_regex = Regex::new(r"\{([^}]+)\}").unwrap();
-    let valid_var_regex = Regex::new(r"^[A-Z_][A-Z0-9_]*$").unwrap();
+    let include_regex = Regex::new(&format!("^{}$", INCLUDE_PATTERN)).unwrap();
 
     for cap in var_regex.captures_iter(template) {
         let var_name = &cap[1];
 
-        if !valid_var_regex.is_match(var_name) {
+        if var_name.starts_with('>') {
+            if !include_regex.is_match(&cap[0]) {
+                anyhow::bail!(
+                    "Invalid include '{}': expected {{> template_id}}",
+                    &cap[0]
+                );
+            }
+            continue;
+        }
+
+        if !is_valid_variable_name(var_name) {
             anyhow::bail!(
-                "Invalid variable name '{}': must be uppercase with underscores (A-Z, 0-9, _)",
+                "Invalid variable name '{}': must start with a letter or underscore and contain only letters, digits and underscores",
                 var_name
             );
         }
@@ -77,8 +91,13 @@ mod tests {
     }
 
     #[test]
-    fn test_invalid_variable_name_lowercase() {
-        let template = "Hello {name}";
+    fn test_camel_and_snake_case_variable_names() {
+        assert!(validate_template("Dear {clientName}, re: {case_number}").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_variable_name_leading_digit() {
+        let template = "Hello {1name}";
         let result = validate_template(template);
         assert!(result.is_err());
         assert!(result
@@ -94,6 +113,15 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_include_directives() {
+        assert!(validate_template("{> confidentiality}\nDated {DATE}").is_ok());
+        assert!(validate_template("{>governing-law.v2}").is_ok());
+
+        let err = validate_template("{> two words}").unwrap_err();
+        assert!(err.to_string().contains("Invalid include"));
+    }
+
     #[test]
     fn test_multiple_valid_variables() {
         let template = "Contract between {PARTY_A} and {PARTY_B} dated {DATE}";
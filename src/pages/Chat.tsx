@@ -37,13 +37,13 @@ const Chat: React.FC = () => {
         // Finalize the message
         setMessages((prev) => [
           ...prev,
-          { role: 'assistant', content: streamingMessage + ' ' + token },
+          { role: 'assistant', content: streamingMessage + token },
         ]);
         setStreamingMessage('');
         setIsGenerating(false);
       } else {
         // Append token
-        setStreamingMessage((prev) => prev + ' ' + token);
+        setStreamingMessage((prev) => prev + token);
       }
     });
 
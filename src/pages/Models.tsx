@@ -34,6 +34,14 @@ const Models: React.FC = () => {
           }, 1000);
         }
       });
+
+      // The database record (path, checksum result) is final once this arrives
+      await modelService.onDownloadComplete((complete) => {
+        if (!complete.checksum_verified) {
+          console.warn(`Checksum mismatch for ${complete.model_id}: got ${complete.checksum}`);
+        }
+        loadModels();
+      });
     };
 
     setupListener();
@@ -175,7 +183,10 @@ const Models: React.FC = () => {
       <div className="models-grid">
         {filteredModels.map((model) => {
           const progress = getModelProgress(model.model_id);
-          const isDownloading = progress?.status === 'Downloading' || progress?.status === 'Starting';
+          const isDownloading =
+            progress?.status === 'Downloading' ||
+            progress?.status === 'Starting' ||
+            progress?.status === 'RateLimited';
 
           return (
             <div key={model.model_id} className={`model-card ${model.is_active ? 'active' : ''}`}>
@@ -220,7 +231,11 @@ const Models: React.FC = () => {
               {isDownloading && progress && (
                 <div className="download-progress">
                   <div className="progress-info">
-                    <span>Downloading... {progress.percentage.toFixed(1)}%</span>
+                    <span>
+                      {progress.status === 'RateLimited' && progress.message
+                        ? progress.message
+                        : `Downloading... ${progress.percentage.toFixed(1)}%`}
+                    </span>
                     <span>{progress.speed_mbps.toFixed(2)} MB/s</span>
                   </div>
                   <div className="progress-bar">
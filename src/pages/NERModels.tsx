@@ -21,6 +21,9 @@ interface NERModel {
 interface DownloadProgress {
   model_id: string;
   file_name: string;
+  file_index: number;
+  file_count: number;
+  status: 'Starting' | 'Downloading' | 'Completed' | 'Failed' | 'Cancelled';
   downloaded: number;
   total: number;
   progress: number;
@@ -92,6 +95,14 @@ const NERModels: React.FC = () => {
     }
   };
 
+  const cancelDownload = async () => {
+    try {
+      await invoke('cancel_ner_download');
+    } catch (error) {
+      console.error('Failed to cancel download:', error);
+    }
+  };
+
   const deleteModel = async (model_id: string) => {
     if (!confirm(`Are you sure you want to delete ${model_id}?`)) {
       return;
@@ -260,12 +271,17 @@ const NERModels: React.FC = () => {
                     />
                   </div>
                   <div className="progress-info">
-                    <span>{downloadProgress.file_name}</span>
+                    <span>
+                      {downloadProgress.file_name} ({downloadProgress.file_index + 1}/{downloadProgress.file_count})
+                    </span>
                     <span>
                       {formatFileSize(downloadProgress.downloaded)} /{' '}
                       {formatFileSize(downloadProgress.total)} ({downloadProgress.progress.toFixed(1)}%)
                     </span>
                     <span>{downloadProgress.speed.toFixed(2)} MB/s</span>
+                    <button className="btn btn-secondary" onClick={cancelDownload}>
+                      Cancel
+                    </button>
                   </div>
                 </div>
               )}
@@ -140,9 +140,7 @@ Mr. Smith resides at 123 Main Street, New York, NY 10001 and can be reached at (
               <h3>Detected Entities ({result.entities.length})</h3>
               <div className="entities-grid">
                 {result.entities.map((entity, index) => {
-                  // Extract entity type
-                  const entityTypeKey = Object.keys(entity.entity_type)[0];
-                  const color = piiService.getEntityColor(entityTypeKey);
+                  const color = piiService.getCategoryColor(entity.category);
 
                   return (
                     <div key={index} className="entity-card">
@@ -150,7 +148,7 @@ Mr. Smith resides at 123 Main Street, New York, NY 10001 and can be reached at (
                         className="entity-badge"
                         style={{ backgroundColor: color }}
                       >
-                        {piiService.formatEntityType(entityTypeKey)}
+                        {entity.display_name}
                       </div>
                       <div className="entity-details">
                         <div className="entity-original">"{entity.text}"</div>
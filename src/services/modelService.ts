@@ -13,10 +13,19 @@ export interface ModelInfo {
   format: string;
   status: string;
   file_size: number;
+  /** Bytes on disk; below file_size for a partial download */
+  downloaded_size: number;
   is_active: boolean;
   is_downloaded: boolean;
   download_url: string;
   tags: string[];
+  architecture?: string;
+  context_length?: number;
+  quantization_bits?: number;
+  /** Approximate RAM needed at `quantization`, in bytes */
+  estimated_ram?: number;
+  /** Largest quantization that fits in the RAM currently available */
+  recommended_quantization?: string;
 }
 
 export interface DownloadProgress {
@@ -25,7 +34,94 @@ export interface DownloadProgress {
   total_bytes: number;
   percentage: number;
   speed_mbps: number;
-  status: 'Starting' | 'Downloading' | 'Completed' | 'Failed' | 'Cancelled';
+  status: 'Starting' | 'Downloading' | 'RateLimited' | 'Completed' | 'Failed' | 'Cancelled';
+  message?: string;
+}
+
+export interface DownloadComplete {
+  model_id: string;
+  file_path: string;
+  /** SHA-256 of the downloaded file */
+  checksum: string;
+  expected_checksum?: string | null;
+  checksum_verified: boolean;
+  total_bytes: number;
+}
+
+export interface OrphanedModelFile {
+  path: string;
+  size_bytes: number;
+  /** Leftover `.tmp` file of an interrupted download */
+  is_partial_download: boolean;
+}
+
+export interface PrunedModels {
+  removed: OrphanedModelFile[];
+  bytes_reclaimed: number;
+}
+
+/** A downloaded model whose file no longer matches its stored checksum */
+export interface ChecksumMismatch {
+  model_id: string;
+  file_path: string;
+  expected_checksum: string;
+  /** Checksum of the file now, null if it couldn't be read */
+  actual_checksum: string | null;
+  error: string | null;
+}
+
+export interface ModelVerificationReport {
+  checked: number;
+  mismatches: ChecksumMismatch[];
+  /** Downloaded models without a stored checksum */
+  unverifiable: string[];
+}
+
+/** A model file in the models directory that isn't registered yet */
+export interface DiscoveredModel {
+  path: string;
+  size_bytes: number;
+  format: 'gguf' | 'safetensors';
+  metadata: {
+    architecture?: string;
+    context_length?: number;
+    quantization?: string;
+    quantization_bits?: number;
+  };
+  suggested_model_id: string;
+  suggested_name: string;
+}
+
+/** A model that passed the pre-activation check */
+export interface ModelCompatibility {
+  model_id: string;
+  architecture: string;
+}
+
+/** Outcome of importing one model from a manifest */
+export interface ManifestImportResult {
+  model_id: string;
+  imported: boolean;
+  error?: string;
+}
+
+export interface GenerationConfig {
+  temperature: number;
+  top_p: number;
+  top_k: number;
+  max_new_tokens: number;
+  repetition_penalty: number;
+  /** Recent tokens the repetition penalty considers (default 64, 0 disables it) */
+  repeat_last_n: number;
+  /** Identical lines in a row that stop generation as a loop (default 6, 0 disables it) */
+  max_repeated_lines: number;
+  do_sample: boolean;
+  seed: number | null;
+}
+
+export interface DownloadTimeouts {
+  connect_timeout_secs: number;
+  read_timeout_secs: number;
 }
 
 class ModelService {
@@ -87,6 +183,20 @@ class ModelService {
     }
   }
 
+  /**
+   * Check that a model's file can be loaded before activating it
+   */
+  async validateModelCompatibility(modelId: string): Promise<ModelCompatibility> {
+    try {
+      return await invoke<ModelCompatibility>('validate_model_compatibility', {
+        modelId,
+      });
+    } catch (error) {
+      console.error('Failed to validate model compatibility:', error);
+      throw error;
+    }
+  }
+
   /**
    * Get the currently active model
    */
@@ -100,6 +210,59 @@ class ModelService {
     }
   }
 
+  /**
+   * Get the saved generation parameters for a model
+   */
+  async getModelGenerationConfig(modelId: string): Promise<GenerationConfig> {
+    try {
+      return await invoke<GenerationConfig>('get_model_generation_config', {
+        modelId,
+      });
+    } catch (error) {
+      console.error('Failed to get generation config:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Save generation parameters for a model
+   */
+  async setModelGenerationConfig(modelId: string, config: GenerationConfig): Promise<void> {
+    try {
+      await invoke('set_model_generation_config', {
+        modelId,
+        config,
+      });
+    } catch (error) {
+      console.error('Failed to save generation config:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Get the network timeouts used for model downloads
+   */
+  async getDownloadTimeouts(): Promise<DownloadTimeouts> {
+    try {
+      return await invoke<DownloadTimeouts>('get_download_timeouts');
+    } catch (error) {
+      console.error('Failed to get download timeouts:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Save the network timeouts used for model downloads
+   */
+  async setDownloadTimeouts(timeouts: DownloadTimeouts): Promise<void> {
+    try {
+      await invoke('set_download_timeouts', { timeouts });
+    } catch (error) {
+      console.error('Failed to save download timeouts:', error);
+      throw error;
+    }
+  }
+
   /**
    * Listen to download progress events
    */
@@ -110,6 +273,16 @@ class ModelService {
     return unlisten;
   }
 
+  /**
+   * Listen for finished downloads, with the file location and checksum result
+   */
+  async onDownloadComplete(callback: (complete: DownloadComplete) => void) {
+    const unlisten = await listen<DownloadComplete>('model-download-complete', (event) => {
+      callback(event.payload);
+    });
+    return unlisten;
+  }
+
   /**
    * Format file size for display
    */
@@ -184,6 +357,36 @@ class ModelService {
     }
   }
 
+  /**
+   * Add a custom NER model; it is stored and listed with the built-in ones
+   */
+  async addCustomNerModel(request: {
+    model_id: string;
+    name: string;
+    description: string;
+    model_type: string;
+    language: string;
+    entity_labels: string[];
+    size: string;
+    parameters: string;
+    format: string;
+    model_url: string;
+    config_url: string;
+    tokenizer_url: string;
+    file_size: number;
+    checksum?: string;
+    license?: string;
+    /** PII entity type per label name, e.g. { PARTY: 'Organization' } */
+    label_entity_types?: Record<string, string>;
+  }): Promise<string> {
+    try {
+      return await invoke<string>('add_custom_ner_model', { request });
+    } catch (error) {
+      console.error('Failed to add custom NER model:', error);
+      throw error;
+    }
+  }
+
   /**
    * Check available disk space
    */
@@ -197,6 +400,76 @@ class ModelService {
     }
   }
 
+  /**
+   * List files in the models directory that no model record points to
+   */
+  async listOrphanedModels(): Promise<OrphanedModelFile[]> {
+    try {
+      return await invoke<OrphanedModelFile[]>('list_orphaned_models');
+    } catch (error) {
+      console.error('Failed to list orphaned models:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Delete orphaned model files; only call after the user confirmed the list
+   */
+  async pruneOrphanedModels(): Promise<PrunedModels> {
+    try {
+      return await invoke<PrunedModels>('prune_orphaned_models', { confirm: true });
+    } catch (error) {
+      console.error('Failed to prune orphaned models:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Re-hash every downloaded model and report files that no longer match
+   */
+  async verifyAllModels(): Promise<ModelVerificationReport> {
+    try {
+      return await invoke<ModelVerificationReport>('verify_all_models');
+    } catch (error) {
+      console.error('Failed to verify models:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Find model files copied into the models directory that aren't registered
+   */
+  async scanForModels(): Promise<DiscoveredModel[]> {
+    try {
+      return await invoke<DiscoveredModel[]>('scan_for_models');
+    } catch (error) {
+      console.error('Failed to scan for models:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Register a file found by scanForModels, keeping it where it is
+   */
+  async registerDiscoveredModel(
+    filePath: string,
+    modelId: string,
+    name: string,
+    parameters: string
+  ): Promise<string> {
+    try {
+      return await invoke<string>('register_discovered_model', {
+        filePath,
+        modelId,
+        name,
+        parameters,
+      });
+    } catch (error) {
+      console.error('Failed to register model:', error);
+      throw error;
+    }
+  }
+
   /**
    * Import a model from a local file
    */
@@ -223,6 +496,18 @@ class ModelService {
       throw error;
     }
   }
+
+  /**
+   * Import every model listed in a JSON manifest, without network access
+   */
+  async importModelManifest(manifestPath: string): Promise<ManifestImportResult[]> {
+    try {
+      return await invoke<ManifestImportResult[]>('import_model_manifest', { manifestPath });
+    } catch (error) {
+      console.error('Failed to import model manifest:', error);
+      throw error;
+    }
+  }
 }
 
 export const modelService = new ModelService();
@@ -1,39 +1,215 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+export type EntityCategory =
+  | 'identity'
+  | 'organization'
+  | 'location'
+  | 'contact'
+  | 'financial'
+  | 'legal'
+  | 'temporal'
+  | 'technical';
 
 export interface Entity {
-  entity_type: {
-    Person?: null;
-    Organization?: null;
-    Location?: null;
-    Date?: null;
-    Money?: null;
-    Law?: null;
-    Case?: null;
-    Email?: null;
-    Phone?: null;
-    Identification?: null;
-    TechnicalIdentifier?: null;
-  };
+  entity_type:
+    | 'Person'
+    | 'Organization'
+    | 'Location'
+    | 'Date'
+    | 'Money'
+    | 'Law'
+    | 'Case'
+    | 'Email'
+    | 'Phone'
+    | 'Identification'
+    | 'TechnicalIdentifier';
   text: string;
+  /** Byte offsets into the UTF-8 text (backend only) */
   start: number;
   end: number;
+  /** Offsets in UTF-16 code units; use these to slice/highlight in JS */
+  utf16_start: number;
+  utf16_end: number;
   confidence: number;
   replacement?: string;
+  /** Detection layer whose span was kept */
+  source?: 'pattern' | 'ner' | 'presidio' | null;
+  /** True when a checksum (e.g. Luhn) confirmed the match */
+  validated: boolean;
+  /** Recognized but deliberately left in place, e.g. a preserved legal reference */
+  preserved: boolean;
+  /** Confidence fell inside the review band; left in place for a person to decide */
+  needs_review: boolean;
+  category: EntityCategory;
+  display_name: string;
 }
 
 export interface AnonymizationSettings {
   entity_types: string[];
   confidence_threshold: number;
+  /** Per-type thresholds overriding confidence_threshold, e.g. { Person: 0.9 } */
+  entity_confidence_thresholds?: Partial<Record<string, number>>;
   preserve_legal_references: boolean;
   consistent_replacement: boolean;
+  /** Whether a batch shares replacements across documents or restarts per document */
+  consistency_scope?: 'per_document' | 'per_batch';
   language: string;
+  pseudonymize?: boolean;
+  use_name_heuristic?: boolean;
+  /** How persons are replaced; defaults to pseudonym/bracket per `pseudonymize` */
+  person_style?: 'bracket' | 'initials' | 'pseudonym' | null;
+  /** Entities shorter than this are dropped (emails and IDs excepted); 0 keeps all */
+  min_entity_length?: number;
+  /** Replacement per entity type, e.g. { Person: { type: 'redact' } } */
+  masking_strategies?: Partial<Record<string, MaskingStrategy>>;
+  /** Replacement for entity types not in `masking_strategies` */
+  default_masking_strategy?: MaskingStrategy;
+  /**
+   * Placeholder format per entity type, e.g. { Person: '«Party {letter}»' };
+   * each needs {index} or {letter}
+   */
+  replacement_templates?: Partial<Record<string, string>>;
+  /** How numeric dates like 01/02/2024 are read; defaults to the language's convention */
+  date_order?: 'day_first' | 'month_first' | null;
+  /** Re-scan the anonymized text: report PII left behind, or anonymize it in a second pass */
+  verification?: 'off' | 'report' | 'repass';
+  /** Base64/hex blobs: detect as technical identifiers, or remove before detection */
+  embedded_data?: 'redact' | 'strip';
+  /** [low, high]: below low ignored, from high replaced, in between flagged for review */
+  review_band?: [number, number] | null;
 }
 
+export type MaskingStrategy =
+  | { type: 'placeholder' }
+  | { type: 'replace'; new_value: string }
+  | { type: 'redact' }
+  | { type: 'mask'; masking_char: string; chars_to_mask: number; from_end: boolean }
+  | { type: 'keep' };
+
 export interface AnonymizationResult {
   original_text: string;
   anonymized_text: string;
   entities: Entity[];
   replacements: Array<[string, string]>;
+  statistics?: Record<string, number>;
+  /** PII still found in anonymized_text by the verification pass, offsets into it */
+  residual_pii?: Entity[];
+}
+
+export type DiffSegment =
+  | { kind: 'unchanged'; text: string }
+  | { kind: 'replaced'; original: string; replacement: string; entity_type: Entity['entity_type'] };
+
+/** How mergeMappings resolves conflicts: the first mapping wins, or nothing is dropped */
+export type MergePolicy = 'prefer_a' | 'renumber';
+
+export type MappingConflict =
+  | {
+      kind: 'same_original';
+      original: string;
+      replacement_a: string;
+      replacement_b: string;
+      /** Replacement in the merged mapping; null when the entry was left out */
+      resolved: string | null;
+    }
+  | {
+      kind: 'same_pseudonym';
+      replacement: string;
+      original_a: string;
+      original_b: string;
+      /** Replacement of original_b in the merged mapping; null when left out */
+      resolved: string | null;
+    };
+
+export interface MergedMapping {
+  replacements: [string, string][];
+  conflicts: MappingConflict[];
+}
+
+export interface QuickAnonymizeResult {
+  anonymized_text: string;
+  /** Entities replaced, per type */
+  counts: Record<string, number>;
+  total: number;
+  mode: 'pattern_only' | 'ner_only' | 'hybrid' | 'full' | 'presidio_only';
+}
+
+export interface ModeDetection {
+  mode: QuickAnonymizeResult['mode'];
+  entities: Entity[];
+  /** Entities no other compared mode found at the same span and type */
+  unique: Entity[];
+  elapsed_ms: number;
+}
+
+export interface ModeComparison {
+  results: ModeDetection[];
+  /** Modes whose layers aren't available, or whose detection failed */
+  skipped: { mode: QuickAnonymizeResult['mode']; reason: string }[];
+}
+
+export interface SanitizedText {
+  text: string;
+  session_token: string;
+  entities_replaced: number;
+}
+
+export interface DeanonymizedText {
+  text: string;
+  /** Placeholders without a value in the mapping, left in the text */
+  unknown_placeholders: string[];
+}
+
+export interface RiskFactor {
+  /** Entity type, DATE_OF_BIRTH or AMBIGUOUS_CAPITALIZED */
+  factor: string;
+  count: number;
+  weight: number;
+  points: number;
+}
+
+export interface RiskScore {
+  /** 0 (nothing identifying found) to 100 */
+  score: number;
+  factors: RiskFactor[];
+}
+
+export interface BatchProgress {
+  index: number;
+  total: number;
+  file_name: string;
+  entities_found: number;
+  entity_counts: [string, number][];
+  error: string | null;
+}
+
+export interface BatchSummary {
+  total: number;
+  processed: number;
+  failed: number;
+  cancelled: boolean;
+  output_path: string;
+  /** Error that stopped the batch under the 'abort_on_error' policy */
+  error?: string;
+}
+
+/** Encoding of exported files; UTF-8, LF and no BOM by default */
+export interface ExportOptions {
+  encoding?: 'utf8' | 'utf16le' | 'utf16be';
+  line_ending?: 'lf' | 'crlf';
+  bom?: boolean;
+}
+
+/** What a batch does when one document fails */
+export type FailurePolicy = 'abort_on_error' | 'best_effort';
+
+/** Rejection of a document over the size limit of single-document commands */
+export interface DocumentTooLarge {
+  kind: 'document_too_large';
+  limit_bytes: number;
+  actual_bytes: number;
+  message: string;
 }
 
 export interface EntityStatistics {
@@ -63,6 +239,34 @@ class PIIService {
     }
   }
 
+  /**
+   * Split an anonymization result into unchanged/replaced segments for a side-by-side diff
+   */
+  async getAnonymizationDiff(result: AnonymizationResult): Promise<DiffSegment[]> {
+    try {
+      return await invoke<DiffSegment[]>('get_anonymization_diff', { result });
+    } catch (error) {
+      console.error('Failed to build anonymization diff:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Merge the replacement mappings of two anonymizations of one matter
+   */
+  async mergeMappings(
+    a: [string, string][],
+    b: [string, string][],
+    policy: MergePolicy = 'prefer_a'
+  ): Promise<MergedMapping> {
+    try {
+      return await invoke<MergedMapping>('merge_replacement_mappings', { a, b, policy });
+    } catch (error) {
+      console.error('Failed to merge mappings:', error);
+      throw error;
+    }
+  }
+
   /**
    * Anonymize multiple texts while maintaining consistency
    */
@@ -84,6 +288,54 @@ class PIIService {
     }
   }
 
+  /**
+   * Anonymize files one at a time, writing results as JSON Lines to outputPath
+   */
+  async anonymizeBatchStream(
+    paths: string[],
+    outputPath: string,
+    settings?: AnonymizationSettings,
+    failurePolicy: FailurePolicy = 'best_effort',
+    exportOptions?: ExportOptions
+  ): Promise<BatchSummary> {
+    try {
+      return await invoke<BatchSummary>('anonymize_batch_stream', {
+        request: {
+          paths,
+          output_path: outputPath,
+          settings,
+          failure_policy: failurePolicy,
+          export: exportOptions,
+        },
+      });
+    } catch (error) {
+      console.error('Failed to anonymize batch:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Stop the running streaming batch before its next document
+   */
+  async cancelBatch(): Promise<void> {
+    try {
+      await invoke('cancel_pii_batch');
+    } catch (error) {
+      console.error('Failed to cancel batch:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Listen to per-document progress of a streaming batch
+   */
+  async onBatchProgress(callback: (progress: BatchProgress) => void) {
+    const unlisten = await listen<BatchProgress>('pii-batch-progress', (event) => {
+      callback(event.payload);
+    });
+    return unlisten;
+  }
+
   /**
    * Clear PII replacement mappings (start fresh)
    */
@@ -149,6 +401,137 @@ class PIIService {
     }
   }
 
+  /**
+   * Get the size limit, in bytes, of anonymizeText and detectEntities
+   */
+  async getMaxDocumentBytes(): Promise<number> {
+    try {
+      return await invoke<number>('get_max_document_bytes');
+    } catch (error) {
+      console.error('Failed to get maximum document size:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Set the size limit, in bytes, of anonymizeText and detectEntities
+   */
+  async setMaxDocumentBytes(maxBytes: number): Promise<void> {
+    try {
+      await invoke('set_max_document_bytes', { maxBytes });
+    } catch (error) {
+      console.error('Failed to set maximum document size:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Estimate how much a document reveals before sharing it
+   */
+  async riskScore(text: string): Promise<RiskScore> {
+    try {
+      return await invoke<RiskScore>('risk_score', { text });
+    } catch (error) {
+      console.error('Failed to score anonymization risk:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Re-detect entities in one region (UTF-16 offsets) of a document;
+   * returned entities use full-document offsets
+   */
+  async detectRegion(text: string, start: number, end: number): Promise<Entity[]> {
+    try {
+      return await invoke<Entity[]>('detect_region', { text, start, end });
+    } catch (error) {
+      console.error('Failed to detect entities in region:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Anonymize a pasted snippet with default settings and the best available detection mode
+   */
+  async quickAnonymize(text: string): Promise<QuickAnonymizeResult> {
+    try {
+      return await invoke<QuickAnonymizeResult>('quick_anonymize', { text });
+    } catch (error) {
+      console.error('Failed to quick-anonymize text:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Compare what pattern-only, hybrid and full detection find in a sample
+   */
+  async compareModes(text: string): Promise<ModeComparison> {
+    try {
+      return await invoke<ModeComparison>('compare_modes', { text });
+    } catch (error) {
+      console.error('Failed to compare detection modes:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Anonymize text before sending it to an external model
+   */
+  async sanitizeForExternal(
+    text: string,
+    settings?: AnonymizationSettings
+  ): Promise<SanitizedText> {
+    try {
+      return await invoke<SanitizedText>('sanitize_for_external', { text, settings });
+    } catch (error) {
+      console.error('Failed to sanitize text:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Restore original values in an external model's reply
+   */
+  async restoreExternalResponse(response: string, sessionToken: string): Promise<string> {
+    try {
+      return await invoke<string>('restore_external_response', { response, sessionToken });
+    } catch (error) {
+      console.error('Failed to restore response:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Restore original values in a reviewed copy of an anonymized document
+   *
+   * Pass the settings the document was anonymized with, so placeholders from
+   * custom replacement templates are recognized.
+   */
+  async deanonymize(
+    text: string,
+    mappingToken: string,
+    settings?: AnonymizationSettings
+  ): Promise<DeanonymizedText> {
+    try {
+      return await invoke<DeanonymizedText>('deanonymize', { text, mappingToken, settings });
+    } catch (error) {
+      console.error('Failed to de-anonymize document:', error);
+      throw error;
+    }
+  }
+
+  /**
+   * Forget the mapping of an external session
+   */
+  async discardExternalSession(sessionToken: string): Promise<void> {
+    try {
+      await invoke('discard_external_session', { sessionToken });
+    } catch (error) {
+      console.error('Failed to discard external session:', error);
+      throw error;
+    }
+  }
+
   /**
    * Format entity type for display
    */
@@ -169,6 +552,23 @@ class PIIService {
     return displayNames[entityType] || entityType;
   }
 
+  /**
+   * Get color for an entity category
+   */
+  getCategoryColor(category: EntityCategory): string {
+    const colors: Record<EntityCategory, string> = {
+      identity: '#e53e3e',
+      organization: '#dd6b20',
+      location: '#d69e2e',
+      contact: '#d53f8c',
+      financial: '#319795',
+      legal: '#3182ce',
+      temporal: '#38a169',
+      technical: '#718096',
+    };
+    return colors[category] || '#718096';
+  }
+
   /**
    * Get color for entity type
    */